@@ -0,0 +1,522 @@
+//! Audit log search and export.
+//!
+//! Lists the events in `audit_events` with filters and cursor pagination, and
+//! exports every matching event as NDJSON or CSV for archival. The audit log
+//! spans all users, so these endpoints are limited to the admins listed in
+//! `AUDIT_ADMIN_USERS`. `/api/audit/verify` walks the events' hash chain (see
+//! [`crate::audit_chain`]) and reports the first broken link.
+
+use std::collections::HashSet;
+
+use crate::{
+    audit::{ResourceType, events},
+    audit_chain::{self, ChainVerification},
+    auth::AuthenticatedUser,
+    errors::ApiError,
+    storage::postgres::audit::{self, AuditCursor, AuditEventFilter, StoredAuditEvent},
+};
+use actix_web::{
+    HttpResponse, Responder, ResponseError, get,
+    http::header,
+    web::{Bytes, Data, Query},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use tracing::{error, warn};
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+/// Events fetched per query while exporting
+const EXPORT_PAGE_SIZE: i64 = 1000;
+
+const CSV_HEADER: &str = "audit_event_id,timestamp,event_type,outcome,user_id,username_display,resource_type,resource_id,details,request_id\n";
+
+/// Usernames allowed to read the audit log (`AUDIT_ADMIN_USERS`, comma
+/// separated). Nobody is when it is unset.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AuditAdmins(HashSet<String>);
+
+impl AuditAdmins {
+    pub(crate) fn from_env() -> Self {
+        Self::parse(&std::env::var("AUDIT_ADMIN_USERS").unwrap_or_default())
+    }
+
+    fn parse(value: &str) -> Self {
+        AuditAdmins(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|u| !u.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    fn contains(&self, username: &str) -> bool {
+        self.0.contains(username)
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct AuditFilterQuery {
+    /// Hashed user id or username of whoever caused the event
+    pub(crate) actor: Option<String>,
+    /// e.g. `Collection`, `Dataset`
+    pub(crate) resource_type: Option<String>,
+    pub(crate) resource_id: Option<String>,
+    /// Event type, e.g. `ResourceDelete`
+    pub(crate) action: Option<String>,
+    /// `Success`, `Failure`, `Denied` or `Allowed`
+    pub(crate) outcome: Option<String>,
+    /// `X-Request-Id` of the request that caused the event
+    pub(crate) request_id: Option<String>,
+    /// Events at or after this time (RFC 3339)
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Events before this time (RFC 3339)
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub(crate) to: Option<DateTime<Utc>>,
+}
+
+impl AuditFilterQuery {
+    fn filter(&self) -> Result<AuditEventFilter, ApiError> {
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && from >= to
+        {
+            return Err(ApiError::BadRequest(
+                "'from' must be before 'to'".to_string(),
+            ));
+        }
+        Ok(AuditEventFilter {
+            actor: self.actor.clone(),
+            resource_type: self.resource_type.clone(),
+            resource_id: self.resource_id.clone(),
+            event_type: self.action.clone(),
+            outcome: self.outcome.clone(),
+            request_id: self.request_id.clone(),
+            from: self.from,
+            to: self.to,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct AuditPageQuery {
+    /// Return events after this cursor; use `next_cursor` from the previous page
+    pub(crate) cursor: Option<String>,
+    #[param(default = 100, minimum = 1, maximum = 1000)]
+    pub(crate) limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct AuditExportQuery {
+    /// `ndjson` (default) or `csv`
+    pub(crate) format: Option<ExportFormat>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct AuditEventPage {
+    /// Newest first
+    pub(crate) events: Vec<StoredAuditEvent>,
+    /// Cursor for the next page; absent when there are no more events
+    pub(crate) next_cursor: Option<String>,
+}
+
+/// `<timestamp micros>_<audit_event_id>`
+fn encode_cursor(cursor: AuditCursor) -> String {
+    format!(
+        "{}_{}",
+        cursor.timestamp.timestamp_micros(),
+        cursor.audit_event_id
+    )
+}
+
+fn decode_cursor(cursor: &str) -> Result<AuditCursor, ApiError> {
+    let invalid = || ApiError::BadRequest(format!("invalid cursor '{cursor}'"));
+    let (micros, id) = cursor.split_once('_').ok_or_else(invalid)?;
+    let timestamp = DateTime::from_timestamp_micros(micros.parse().map_err(|_| invalid())?)
+        .ok_or_else(invalid)?;
+    Ok(AuditCursor {
+        timestamp,
+        audit_event_id: id.parse().map_err(|_| invalid())?,
+    })
+}
+
+/// Cursor after a page of `limit` events; none for a short page
+fn next_cursor(events: &[StoredAuditEvent], limit: i64) -> Option<AuditCursor> {
+    if (events.len() as i64) < limit {
+        return None;
+    }
+    events.last().map(|e| AuditCursor {
+        timestamp: e.timestamp,
+        audit_event_id: e.audit_event_id,
+    })
+}
+
+fn csv_field(out: &mut String, value: &str) {
+    if value.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&value.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(value);
+    }
+}
+
+fn to_csv_row(event: &StoredAuditEvent) -> String {
+    let fields = [
+        event.audit_event_id.to_string(),
+        event.timestamp.to_rfc3339(),
+        event.event_type.clone(),
+        event.outcome.clone(),
+        event.user_id.clone(),
+        event.username_display.clone(),
+        event.resource_type.clone().unwrap_or_default(),
+        event.resource_id.clone().unwrap_or_default(),
+        event.details.clone().unwrap_or_default(),
+        event.request_id.clone().unwrap_or_default(),
+    ];
+    let mut row = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            row.push(',');
+        }
+        csv_field(&mut row, field);
+    }
+    row.push('\n');
+    row
+}
+
+fn to_ndjson_line(event: &StoredAuditEvent) -> String {
+    let mut line = serde_json::to_string(event).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// 403 for anyone not in `AUDIT_ADMIN_USERS`, recorded as a denied access
+fn require_admin(user: &AuthenticatedUser, admins: &AuditAdmins) -> Result<(), ApiError> {
+    if admins.contains(user) {
+        return Ok(());
+    }
+    events::unauthorized_access(
+        &user.as_owner(),
+        user,
+        ResourceType::AuditLog,
+        "audit_events",
+        "not an audit admin",
+    );
+    Err(ApiError::Forbidden(
+        "Audit log access requires an admin".to_string(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/audit/events",
+    tag = "Audit",
+    params(AuditFilterQuery, AuditPageQuery),
+    responses(
+        (status = 200, description = "Matching audit events, newest first", body = AuditEventPage),
+        (status = 400, description = "Invalid filter or cursor"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an audit admin"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+)]
+#[get("/api/audit/events")]
+#[tracing::instrument(name = "list_audit_events", skip(user, admins, pool, filter, page))]
+pub(crate) async fn list_audit_events(
+    user: AuthenticatedUser,
+    admins: Data<AuditAdmins>,
+    pool: Data<Pool<Postgres>>,
+    filter: Query<AuditFilterQuery>,
+    page: Query<AuditPageQuery>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&user, &admins) {
+        return e.error_response();
+    }
+    let filter = match filter.filter() {
+        Ok(f) => f,
+        Err(e) => return e.error_response(),
+    };
+    let cursor = match page.cursor.as_deref().map(decode_cursor).transpose() {
+        Ok(c) => c,
+        Err(e) => return e.error_response(),
+    };
+    let limit = page.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    match audit::list_audit_events(&pool, &filter, cursor, limit).await {
+        Ok(events) => {
+            let next_cursor = next_cursor(&events, limit).map(encode_cursor);
+            HttpResponse::Ok().json(AuditEventPage {
+                events,
+                next_cursor,
+            })
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to list audit events");
+            ApiError::Database(e).error_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/audit/events/export",
+    tag = "Audit",
+    params(AuditFilterQuery, AuditExportQuery),
+    responses(
+        (status = 200, description = "Every matching audit event, newest first, as NDJSON or CSV"),
+        (status = 400, description = "Invalid filter"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an audit admin"),
+    ),
+)]
+#[get("/api/audit/events/export")]
+#[tracing::instrument(name = "export_audit_events", skip(user, admins, pool, filter, export))]
+pub(crate) async fn export_audit_events(
+    user: AuthenticatedUser,
+    admins: Data<AuditAdmins>,
+    pool: Data<Pool<Postgres>>,
+    filter: Query<AuditFilterQuery>,
+    export: Query<AuditExportQuery>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&user, &admins) {
+        return e.error_response();
+    }
+    let filter = match filter.filter() {
+        Ok(f) => f,
+        Err(e) => return e.error_response(),
+    };
+    let format = export.format.unwrap_or_default();
+    events::resource_read(
+        &user.as_owner(),
+        &user,
+        ResourceType::AuditLog,
+        "audit_events/export",
+    );
+
+    let pool = pool.into_inner();
+    let stream = async_stream::stream! {
+        if format == ExportFormat::Csv {
+            yield Ok::<_, actix_web::Error>(Bytes::from_static(CSV_HEADER.as_bytes()));
+        }
+        let mut cursor = None;
+        loop {
+            let events = match audit::list_audit_events(&pool, &filter, cursor, EXPORT_PAGE_SIZE).await {
+                Ok(events) => events,
+                Err(e) => {
+                    // Headers are already sent, so the export ends truncated
+                    error!(error = %e, "Failed to export audit events");
+                    yield Err(actix_web::error::ErrorInternalServerError("audit export failed"));
+                    break;
+                }
+            };
+            let mut chunk = String::new();
+            for event in &events {
+                chunk.push_str(&match format {
+                    ExportFormat::Ndjson => to_ndjson_line(event),
+                    ExportFormat::Csv => to_csv_row(event),
+                });
+            }
+            if !chunk.is_empty() {
+                yield Ok(Bytes::from(chunk));
+            }
+            cursor = next_cursor(&events, EXPORT_PAGE_SIZE);
+            if cursor.is_none() {
+                break;
+            }
+        }
+    };
+
+    let (content_type, extension) = match format {
+        ExportFormat::Ndjson => ("application/x-ndjson", "ndjson"),
+        ExportFormat::Csv => ("text/csv", "csv"),
+    };
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, content_type))
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"audit-events-{}.{extension}\"",
+                Utc::now().format("%Y%m%dT%H%M%SZ")
+            ),
+        ))
+        .streaming(stream)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/audit/verify",
+    tag = "Audit",
+    responses(
+        (status = 200, description = "Result of walking the audit hash chain", body = ChainVerification),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an audit admin"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+)]
+#[get("/api/audit/verify")]
+#[tracing::instrument(name = "verify_audit_chain", skip(user, admins, pool))]
+pub(crate) async fn verify_audit_chain(
+    user: AuthenticatedUser,
+    admins: Data<AuditAdmins>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&user, &admins) {
+        return e.error_response();
+    }
+    match audit_chain::verify_audit_chain(&pool).await {
+        Ok(verification) => {
+            if let Some(link) = &verification.first_broken_link {
+                warn!(
+                    audit_event_id = link.audit_event_id,
+                    reason = %link.reason,
+                    "Audit hash chain is broken"
+                );
+            }
+            HttpResponse::Ok().json(verification)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to verify audit chain");
+            ApiError::Database(e).error_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i64, details: Option<&str>) -> StoredAuditEvent {
+        StoredAuditEvent {
+            audit_event_id: id,
+            timestamp: DateTime::from_timestamp(1_767_225_600, 123_456_000).unwrap(),
+            event_type: "ResourceDelete".to_string(),
+            outcome: "Success".to_string(),
+            user_id: "u1".to_string(),
+            username_display: "alice".to_string(),
+            resource_type: Some("Dataset".to_string()),
+            resource_id: Some("42".to_string()),
+            details: details.map(str::to_string),
+            request_id: Some("req-7".to_string()),
+            event_id: None,
+            prev_hash: None,
+            hash: None,
+        }
+    }
+
+    #[test]
+    fn test_admins_from_comma_separated_list() {
+        let admins = AuditAdmins::parse(" alice, bob ,,");
+        assert!(admins.contains("alice"));
+        assert!(admins.contains("bob"));
+        assert!(!admins.contains(""));
+        assert!(!AuditAdmins::parse("").contains("alice"));
+    }
+
+    #[test]
+    fn test_cursor_round_trips() {
+        let cursor = next_cursor(&[event(9, None)], 1).unwrap();
+        assert_eq!(decode_cursor(&encode_cursor(cursor)).unwrap(), cursor);
+
+        assert!(decode_cursor("9").is_err());
+        assert!(decode_cursor("abc_9").is_err());
+        assert!(decode_cursor("1_abc").is_err());
+    }
+
+    #[test]
+    fn test_next_cursor_only_after_a_full_page() {
+        let events = vec![event(3, None), event(2, None)];
+        assert_eq!(next_cursor(&events, 3), None);
+        assert_eq!(next_cursor(&events, 2).unwrap().audit_event_id, 2);
+        assert_eq!(next_cursor(&[], 2), None);
+    }
+
+    #[test]
+    fn test_time_range_must_be_ordered() {
+        let now = Utc::now();
+        let query = AuditFilterQuery {
+            actor: None,
+            resource_type: None,
+            resource_id: None,
+            action: Some("ResourceDelete".to_string()),
+            outcome: None,
+            request_id: None,
+            from: Some(now),
+            to: Some(now),
+        };
+        assert!(matches!(query.filter(), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_csv_rows_escape_fields() {
+        let row = to_csv_row(&event(1, Some("file: \"a,b.txt\"\nreason: x")));
+        assert_eq!(
+            row,
+            "1,2026-01-01T00:00:00.123456+00:00,ResourceDelete,Success,u1,alice,Dataset,42,\"file: \"\"a,b.txt\"\"\nreason: x\",req-7\n"
+        );
+        assert_eq!(
+            CSV_HEADER.matches(',').count(),
+            to_csv_row(&event(1, None)).matches(',').count()
+        );
+    }
+
+    #[test]
+    fn test_ndjson_lines_are_single_line_json() {
+        let line = to_ndjson_line(&event(1, Some("multi\nline")));
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+        let parsed: StoredAuditEvent = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed, event(1, Some("multi\nline")));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_list_filters_and_pages() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::PgPool::connect(&url).await.unwrap();
+        let request_id = format!("audit-test-{}", uuid::Uuid::new_v4());
+        for i in 0..3 {
+            let mut event = crate::audit::AuditEvent::new(
+                crate::audit::AuditEventType::ResourceDelete,
+                crate::audit::AuditOutcome::Success,
+                "u1",
+                "alice",
+            )
+            .with_resource(ResourceType::Dataset, i.to_string());
+            event.request_id = Some(request_id.clone());
+            event.store(&pool).await.unwrap();
+        }
+
+        let filter = AuditEventFilter {
+            request_id: Some(request_id),
+            resource_type: Some("Dataset".to_string()),
+            ..Default::default()
+        };
+        let first = audit::list_audit_events(&pool, &filter, None, 2)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+        let rest = audit::list_audit_events(&pool, &filter, next_cursor(&first, 2), 2)
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(
+            first
+                .iter()
+                .all(|e| e.audit_event_id != rest[0].audit_event_id)
+        );
+    }
+}
@@ -15,12 +15,14 @@ use crate::{
     audit::{ResourceType, events},
     auth::AuthenticatedUser,
     chat::{
+        keepalive::{self, KeepAliveEvent},
         llm,
         models::{
             ChatMessageResponse, ChatMessagesResponse, ChatResponse, ChatSession, ChatSessions,
             CreateChatMessageRequest, CreateChatSessionRequest, RAGConfig,
         },
         rag::{self},
+        usage,
     },
     errors::ApiError,
     storage::postgres::chat,
@@ -48,6 +50,7 @@ use semantic_explorer_core::{
 #[utoipa::path(
     responses(
         (status = 201, description = "Created", body = String),
+        (status = 400, description = "Invalid token budget"),
         (status = 500, description = "Internal Server Error"),
     ),
     request_body = CreateChatSessionRequest,
@@ -61,6 +64,9 @@ pub(crate) async fn create_chat_session(
     pool: Data<Pool<Postgres>>,
     request: Json<CreateChatSessionRequest>,
 ) -> impl Responder {
+    if let Err(e) = usage::validate_budget(request.token_budget) {
+        return e.error_response();
+    }
     match chat::create_chat_session(&pool.into_inner(), &user.as_owner(), &user, &request).await {
         Ok(session) => {
             events::resource_created_with_request(
@@ -261,6 +267,7 @@ pub(crate) async fn get_chat_messages(
     responses(
         (status = 200, description = "OK", body = ChatResponse),
         (status = 404, description = "Not Found"),
+        (status = 429, description = "Session token budget exhausted"),
         (status = 500, description = "Internal Server Error"),
     ),
     request_body = CreateChatMessageRequest,
@@ -301,6 +308,13 @@ pub(crate) async fn send_chat_message(
         }
     };
 
+    if let Err(e) = usage::check_budget(&session) {
+        let chat_duration = chat_start.elapsed().as_secs_f64();
+        semantic_explorer_core::observability::record_chat_request(chat_duration, false);
+        return e.error_response();
+    }
+    let max_tokens = usage::cap_max_tokens(request.max_tokens, usage::remaining_tokens(&session));
+
     // Store user message
     if let Err(e) = chat::add_chat_message(
         &pool,
@@ -361,12 +375,15 @@ pub(crate) async fn send_chat_message(
         &request.content,
         &context,
         request.temperature,
-        request.max_tokens,
+        max_tokens,
         request.system_prompt.as_deref(),
     )
     .await
     {
-        Ok(response) => response,
+        Ok((response, token_usage)) => {
+            usage::record_usage(&pool, &session_id, token_usage).await;
+            response
+        }
         Err(e) => {
             tracing::error!(error = %e, "failed to generate LLM response");
             format!("Error generating response: {e}")
@@ -435,6 +452,7 @@ pub(crate) async fn send_chat_message(
     responses(
         (status = 200, description = "Server-Sent Events stream", content_type = "text/event-stream"),
         (status = 404, description = "Session not found"),
+        (status = 429, description = "Session token budget exhausted"),
         (status = 500, description = "Internal Server Error"),
     ),
 )]
@@ -468,6 +486,11 @@ pub(crate) async fn stream_chat_message(
         }
     };
 
+    if let Err(e) = usage::check_budget(&session) {
+        return e.error_response();
+    }
+    let max_tokens = usage::cap_max_tokens(request.max_tokens, usage::remaining_tokens(&session));
+
     // Store user message
     if let Err(e) = chat::add_chat_message(
         &pool,
@@ -554,6 +577,7 @@ pub(crate) async fn stream_chat_message(
     let postgres_pool_clone = pool.clone();
     let encryption_clone = encryption.clone();
     let llm_inference_url = llm_inference_config.url.clone();
+    let keepalive_period = Duration::from_secs(llm_inference_config.chat_sse_keepalive_secs);
 
     // Create SSE stream
     let stream = async_stream::stream! {
@@ -588,12 +612,12 @@ pub(crate) async fn stream_chat_message(
             &request.content,
             &context,
             request.temperature,
-            request.max_tokens,
+            max_tokens,
             request.system_prompt.as_deref(),
         )
         .await
         {
-            Ok(stream) => stream,
+            Ok(response_stream) => response_stream,
             Err(e) => {
                 // Update message status to error
                 let _ = chat::update_message_status(&postgres_pool_clone, message_id, "error", &owner).await;
@@ -605,9 +629,9 @@ pub(crate) async fn stream_chat_message(
             }
         };
 
-        // Set up heartbeat and timeout
-        let mut heartbeat = interval(Duration::from_secs(30));
-        heartbeat.tick().await; // Skip first immediate tick
+        // Keep-alive pings during gaps between tokens, plus an overall timeout
+        let prompt_tokens = llm_stream.prompt_tokens;
+        let llm_stream = keepalive::with_keepalive(llm_stream.stream, keepalive_period);
 
         let mut timeout_timer = interval(Duration::from_secs(300));
         timeout_timer.tick().await; // Skip first immediate tick
@@ -622,7 +646,10 @@ pub(crate) async fn stream_chat_message(
             tokio::select! {
                 chunk_result = llm_stream.next() => {
                     match chunk_result {
-                        Some(Ok(chunk)) => {
+                        Some(KeepAliveEvent::Ping) => {
+                            yield Ok(actix_web::web::Bytes::from(keepalive::PING));
+                        }
+                        Some(KeepAliveEvent::Item(Ok(chunk))) => {
                             accumulated_content.push_str(&chunk);
                             char_count += chunk.len();
 
@@ -649,7 +676,14 @@ pub(crate) async fn stream_chat_message(
                                 )));
                             }
                         }
-                        Some(Err(e)) => {
+                        Some(KeepAliveEvent::Item(Err(e))) => {
+                            // Tokens generated before the failure still count
+                            usage::record_usage(
+                                &postgres_pool_clone,
+                                &session_id,
+                                usage::TokenUsage::streamed(prompt_tokens, &accumulated_content),
+                            )
+                            .await;
                             let _ = chat::update_message_status(&postgres_pool_clone, message_id, "error", &owner).await;
                             yield Ok(actix_web::web::Bytes::from(format!(
                                 "event: error\ndata: {{\"message_id\":{},\"error\":\"{}\"}}\n\n",
@@ -658,6 +692,13 @@ pub(crate) async fn stream_chat_message(
                             return;
                         }
                         None => {
+                            usage::record_usage(
+                                &postgres_pool_clone,
+                                &session_id,
+                                usage::TokenUsage::streamed(prompt_tokens, &accumulated_content),
+                            )
+                            .await;
+
                             // Stream complete - transform and save
                             let transformed_content = rag::replace_chunk_references(
                                 &accumulated_content,
@@ -694,11 +735,14 @@ pub(crate) async fn stream_chat_message(
                         }
                     }
                 }
-                _ = heartbeat.tick() => {
-                    yield Ok(actix_web::web::Bytes::from(": heartbeat\n\n"));
-                }
                 _ = timeout_timer.tick() => {
                     // Timeout reached
+                    usage::record_usage(
+                        &postgres_pool_clone,
+                        &session_id,
+                        usage::TokenUsage::streamed(prompt_tokens, &accumulated_content),
+                    )
+                    .await;
                     let _ = chat::update_message_status(&postgres_pool_clone, message_id, "error", &owner).await;
                     yield Ok(actix_web::web::Bytes::from(format!(
                         "event: timeout\ndata: {{\"message_id\":{}}}\n\n",
@@ -734,6 +778,7 @@ pub(crate) struct RegenerateMessageQuery {
     responses(
         (status = 200, description = "OK", body = ChatResponse),
         (status = 404, description = "Message not found"),
+        (status = 429, description = "Session token budget exhausted"),
         (status = 500, description = "Internal Server Error"),
     ),
 )]
@@ -777,6 +822,10 @@ pub(crate) async fn regenerate_chat_message(
         }
     };
 
+    if let Err(e) = usage::check_budget(&session) {
+        return e.error_response();
+    }
+
     // Get existing retrieved documents (reuse them)
     let retrieved_documents = match chat::get_retrieved_documents(&pool, message_id).await {
         Ok(docs) => docs,
@@ -831,12 +880,15 @@ pub(crate) async fn regenerate_chat_message(
         user_query,
         &context,
         None,
-        None,
+        usage::cap_max_tokens(None, usage::remaining_tokens(&session)),
         None, // Use default system prompt for regeneration
     )
     .await
     {
-        Ok(response) => response,
+        Ok((response, token_usage)) => {
+            usage::record_usage(&pool, &message.session_id, token_usage).await;
+            response
+        }
         Err(e) => {
             tracing::error!(error = %e, "failed to generate LLM response");
             return ApiError::Internal(format!("failed to generate response: {}", e))
@@ -7,6 +7,8 @@ use crate::transforms::collection::models::{
     FailedFileWithTransform, ProcessedFile, UpdateCollectionTransform,
 };
 use crate::transforms::collection::scanner::trigger_collection_transform_scan;
+use crate::transforms::concurrency::{self, TransformKind};
+use semantic_explorer_core::config::WorkerConfig;
 use semantic_explorer_core::models::PaginatedResponse;
 use semantic_explorer_core::validation;
 
@@ -329,15 +331,17 @@ pub async fn delete_collection_transform(
     responses(
         (status = 200, description = "Collection transform triggered"),
         (status = 404, description = "Collection transform not found"),
+        (status = 429, description = "Too many transforms already running for this user"),
         (status = 401, description = "Unauthorized"),
     ),
 )]
 #[post("/api/collection-transforms/{id}/trigger")]
-#[tracing::instrument(name = "trigger_collection_transform", skip(user, pool, nats_client), fields(collection_transform_id = %path.as_ref()))]
+#[tracing::instrument(name = "trigger_collection_transform", skip(user, pool, nats_client, worker_config), fields(collection_transform_id = %path.as_ref()))]
 pub async fn trigger_collection_transform(
     user: AuthenticatedUser,
     pool: Data<Pool<Postgres>>,
     nats_client: Data<NatsClient>,
+    worker_config: Data<WorkerConfig>,
     path: Path<i32>,
 ) -> impl Responder {
     let collection_transform_id = path.into_inner();
@@ -350,6 +354,17 @@ pub async fn trigger_collection_transform(
     .await
     {
         Ok(_) => {
+            if let Err(response) = concurrency::reserve_slot(
+                &pool,
+                &worker_config,
+                &user.as_owner(),
+                TransformKind::Collection,
+                collection_transform_id,
+            )
+            .await
+            {
+                return response;
+            }
             if let Err(e) = trigger_collection_transform_scan(
                 &nats_client,
                 collection_transform_id,
@@ -1,39 +1,48 @@
-use actix_multipart::form::MultipartForm;
+use actix_multipart::{
+    Field, Multipart, MultipartError,
+    form::{MultipartForm, text::Text},
+};
 use actix_web::{
     HttpRequest, HttpResponse, Responder, ResponseError, delete, get, patch, post,
-    web::{self, Data, Json, Path},
+    web::{self, Bytes, BytesMut, Data, Json, Path},
 };
 use aws_sdk_s3::{Client, primitives::ByteStream};
+use futures_util::StreamExt;
 use sqlx::{Pool, Postgres};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
 use crate::{
     audit::{ResourceType, events},
     auth::AuthenticatedUser,
+    collections::access,
     collections::models::{
         Collection, CollectionListQuery, CollectionSearchQuery, CollectionUpload,
-        CollectionUploadResponse, CreateCollection, FailedUploadFile, FileListQuery,
-        PaginatedCollections, UpdateCollection,
+        CollectionUploadResponse, CreateCollection, ExtractionPreview, ExtractionPreviewUpload,
+        FailedUploadFile, FileListQuery, PaginatedCollections, PresignedUploadRequest,
+        StoredExtractionPreview, UpdateCollection,
     },
+    collections::reconcile::{self, FileReconciliationReport, ReconcileFilesQuery, ReconcileMode},
     errors::ApiError,
     storage::{
         self,
         postgres::{collection_transforms, collections},
         s3::{
-            delete_file,
-            models::{DocumentUpload, PaginatedFiles},
-            upload_document,
+            UploadTooLarge, delete_file,
+            models::{PaginatedFiles, PresignedUrl, StreamedUploadConfig},
+            upload_document_stream,
         },
         valkey::{self, ValkeyClients},
     },
-    transforms::collection::scanner::dispatch_upload_jobs,
-    validation::validate_upload_file,
+    transforms::collection::scanner::{dispatch_upload_jobs, trigger_collection_transform_scan},
+    validation::{MAGIC_BYTES_READ_SIZE, detect_mime_type},
 };
 use semantic_explorer_core::{
     config::{S3Config, ValkeyConfig},
     encryption::EncryptionService,
-    validation,
+    extraction::{EXTRACTION_CAPABILITIES, ExtractionCapabilities},
+    models::{ExtractionPreviewRequest, ExtractionPreviewResponse, ExtractionPreviewSource},
+    subjects, validation,
 };
 
 #[utoipa::path(
@@ -110,12 +119,34 @@ pub(crate) async fn get_collections(
     }
 }
 
+/// Load a collection the user may read: their own, or any public one.
+/// Private collections of other users are refused with 403.
+async fn get_readable_collection(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    collection_id: i32,
+) -> Result<Collection, ApiError> {
+    let collection = collections::get_collection_by_id(pool, collection_id)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("no rows") {
+                ApiError::NotFound(format!("Collection {} not found", collection_id))
+            } else {
+                tracing::error!(error = %e, collection_id = %collection_id, "failed to fetch collection");
+                ApiError::Internal(format!("Failed to fetch collection: {}", e))
+            }
+        })?;
+    access::check_read_access(&collection, &user.as_owner())?;
+    Ok(collection)
+}
+
 #[utoipa::path(
     params(
         ("collection_id" = i32, Path, description = "Collection ID"),
     ),
     responses(
         (status = 200, description = "OK", body = Collection),
+        (status = 403, description = "Collection is private"),
         (status = 404, description = "Collection not found"),
         (status = 500, description = "Internal Server Error"),
     ),
@@ -131,17 +162,9 @@ pub(crate) async fn get_collection(
     let collection_id = path.into_inner();
     let pool = &pool.into_inner();
 
-    match collections::get_collection(pool, &user.as_owner(), collection_id).await {
+    match get_readable_collection(pool, &user, collection_id).await {
         Ok(collection) => HttpResponse::Ok().json(collection),
-        Err(e) => {
-            if e.to_string().contains("no rows") {
-                ApiError::NotFound(format!("Collection {} not found", collection_id))
-                    .error_response()
-            } else {
-                tracing::error!(error = %e, collection_id = %collection_id, "failed to fetch collection");
-                ApiError::Internal(format!("Failed to fetch collection: {}", e)).error_response()
-            }
-        }
+        Err(e) => e.error_response(),
     }
 }
 
@@ -254,13 +277,17 @@ pub(crate) async fn create_collections(
     };
 
     let folder_key = collection.s3_folder_key();
-    if let Err(e) = s3_client
+    let marker_request = s3_client
         .put_object()
         .bucket(&s3_config.bucket_name)
         .key(&folder_key)
-        .body(ByteStream::from_static(b""))
-        .send()
-        .await
+        .body(ByteStream::from_static(b""));
+    if let Err(e) = semantic_explorer_core::storage::put_object_with_sse(
+        marker_request,
+        s3_config.server_side_encryption.as_ref(),
+    )
+    .send()
+    .await
     {
         error!(
             "Failed to create collection folder marker in S3 ({}): {}",
@@ -415,7 +442,7 @@ pub(crate) async fn update_collections(
     tag = "Collections",
 )]
 #[post("/api/collections/{collection_id}/files")]
-#[tracing::instrument(name = "upload_to_collection", skip(user, s3_client, s3_config, pool, payload, nats_client, encryption), fields(collection_id = %collection_id.as_ref(), file_count = payload.files.len()))]
+#[tracing::instrument(name = "upload_to_collection", skip(user, s3_client, s3_config, pool, payload, nats_client, encryption), fields(collection_id = %collection_id.as_ref()))]
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn upload_to_collection(
     user: AuthenticatedUser,
@@ -425,7 +452,7 @@ pub(crate) async fn upload_to_collection(
     nats_client: Data<async_nats::Client>,
     encryption: Data<EncryptionService>,
     collection_id: Path<i32>,
-    MultipartForm(payload): MultipartForm<CollectionUpload>,
+    mut payload: Multipart,
 ) -> impl Responder {
     let s3_client = s3_client.into_inner();
     let s3_config = s3_config.into_inner();
@@ -447,145 +474,104 @@ pub(crate) async fn upload_to_collection(
         }
     };
 
-    let mut completed = Vec::with_capacity(payload.files.len());
+    let upload_config = StreamedUploadConfig::from_s3_config(&s3_config);
+    let mut completed = Vec::new();
     let mut failed: Vec<FailedUploadFile> = Vec::new();
+    let mut idx = 0;
+
+    // Each file is streamed to storage as it arrives; nothing is spooled to disk
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(e) => {
+                warn!(error = %e, "Failed to read multipart upload");
+                if completed.is_empty() && failed.is_empty() {
+                    return ApiError::BadRequest(format!("Invalid multipart upload: {}", e))
+                        .error_response();
+                }
+                break;
+            }
+        };
+        if field.name() != Some("files") {
+            continue;
+        }
 
-    for (idx, temp_file) in payload.files.iter().enumerate() {
         let item_start = Instant::now();
-        let file_name = temp_file
-            .file_name
-            .as_ref()
+        let file_name = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
             .map(|s| s.to_string())
             .unwrap_or_else(|| format!("file_{}", idx));
+        idx += 1;
 
-        let file_path = temp_file.file.path().to_owned();
-        let file_size = temp_file.size;
-
-        let validation_result = validate_upload_file(&file_path, &file_name).await;
-
-        if !validation_result.is_valid {
-            tracing::warn!(
-                file_name = %file_name,
-                validation_errors = ?validation_result.validation_errors,
-                "File validation failed, rejecting upload"
-            );
-            let item_duration = item_start.elapsed().as_secs_f64();
-            semantic_explorer_core::observability::record_document_upload(
-                "collection",
-                item_duration,
-                false,
-            );
-            let validation_error = validation_result.validation_errors.join("; ");
-            failed.push(FailedUploadFile {
-                name: file_name.clone(),
-                error: format!("Validation failed: {}", validation_error),
-            });
-
-            crate::audit::events::file_validation_failed(
-                &user.as_owner(),
-                &user,
-                collection_id,
-                &file_name,
-                &validation_result.validation_errors.join("; "),
-            );
-            continue;
-        }
+        let upload_result = match read_upload_header(&mut field).await {
+            Ok(header) => {
+                let mime_type = detect_mime_type(&header);
+                let body = futures_util::stream::iter([Ok(header)]).chain(field);
+                upload_document_stream(
+                    &s3_client,
+                    &s3_config.bucket_name,
+                    collection.collection_id,
+                    &file_name,
+                    &mime_type,
+                    body,
+                    &upload_config,
+                )
+                .await
+            }
+            Err(e) => Err(anyhow::anyhow!("Failed to read upload body: {}", e)),
+        };
 
-        // Upload to S3 with retries for transient failures
-        let retry_policy = semantic_explorer_core::retry::s3_retry_policy();
-        let max_attempts = retry_policy.max_attempts + 1;
-        let mut upload_result = Ok(());
+        let item_duration = item_start.elapsed().as_secs_f64();
+        semantic_explorer_core::observability::record_document_upload(
+            "collection",
+            item_duration,
+            upload_result.is_ok(),
+        );
 
-        for attempt in 0..max_attempts {
-            if attempt > 0 {
-                let delay = retry_policy.delay_for_attempt(attempt);
-                info!(
+        match upload_result {
+            Ok(size) => {
+                tracing::info!(
                     file_name = %file_name,
-                    attempt = attempt + 1,
-                    max_attempts = max_attempts,
-                    delay_ms = delay.as_millis() as u64,
-                    "Retrying S3 upload after transient failure"
+                    size = size,
+                    "File uploaded successfully"
                 );
-                tokio::time::sleep(delay).await;
+                completed.push(file_name);
             }
+            Err(e) => match e.downcast_ref::<UploadTooLarge>() {
+                Some(too_large) => {
+                    let validation_error = too_large.to_string();
+                    tracing::warn!(
+                        file_name = %file_name,
+                        validation_error = %validation_error,
+                        "File validation failed, rejecting upload"
+                    );
+                    failed.push(FailedUploadFile {
+                        name: file_name.clone(),
+                        error: format!("Validation failed: {}", validation_error),
+                    });
 
-            // Re-create the stream from file path for each attempt
-            let content_stream = match ByteStream::from_path(&file_path).await {
-                Ok(stream) => stream,
-                Err(e) => {
-                    upload_result = Err(anyhow::anyhow!("Failed to read file: {}", e));
-                    break; // Not retryable
-                }
-            };
-
-            let document = DocumentUpload {
-                collection_id: collection.collection_id.to_string(),
-                name: file_name.clone(),
-                content: content_stream,
-                mime_type: validation_result
-                    .mime_type
-                    .clone()
-                    .unwrap_or_else(|| "application/octet-stream".to_string()),
-                size: file_size as u64,
-            };
-
-            match upload_document(&s3_client, &s3_config.bucket_name, document).await {
-                Ok(()) => {
-                    upload_result = Ok(());
-                    if attempt > 0 {
-                        info!(
-                            file_name = %file_name,
-                            attempt = attempt + 1,
-                            "S3 upload succeeded after retry"
-                        );
-                    }
-                    break;
+                    crate::audit::events::file_validation_failed(
+                        &user.as_owner(),
+                        &user,
+                        collection_id,
+                        &file_name,
+                        &validation_error,
+                    );
                 }
-                Err(e) => {
-                    warn!(
+                None => {
+                    failed.push(FailedUploadFile {
+                        name: file_name.clone(),
+                        error: format!("Upload to storage failed: {:#}", e),
+                    });
+                    error!(
                         file_name = %file_name,
-                        attempt = attempt + 1,
-                        max_attempts = max_attempts,
                         error = %e,
-                        "S3 upload attempt failed"
+                        "Failed to upload file to S3"
                     );
-                    upload_result = Err(e);
                 }
-            }
+            },
         }
-
-        if let Err(e) = upload_result {
-            let item_duration = item_start.elapsed().as_secs_f64();
-            semantic_explorer_core::observability::record_document_upload(
-                "collection",
-                item_duration,
-                false,
-            );
-            failed.push(FailedUploadFile {
-                name: file_name.clone(),
-                error: format!("Upload to storage failed: {}", e),
-            });
-            error!(
-                file_name = %file_name,
-                error = %e,
-                "Failed to upload file to S3 after all retry attempts"
-            );
-            continue;
-        }
-
-        let item_duration = item_start.elapsed().as_secs_f64();
-        semantic_explorer_core::observability::record_document_upload(
-            "collection",
-            item_duration,
-            true,
-        );
-
-        tracing::info!(
-            file_name = %file_name,
-            "File uploaded successfully"
-        );
-
-        completed.push(file_name);
     }
 
     if !completed.is_empty()
@@ -611,14 +597,30 @@ pub(crate) async fn upload_to_collection(
     HttpResponse::Ok().json(CollectionUploadResponse { completed, failed })
 }
 
+/// Read the first `MAGIC_BYTES_READ_SIZE` bytes of an uploaded file (fewer
+/// if it's shorter) to detect its type before the rest is streamed
+async fn read_upload_header(field: &mut Field) -> Result<Bytes, MultipartError> {
+    let mut header = BytesMut::new();
+    while header.len() < MAGIC_BYTES_READ_SIZE {
+        match field.next().await {
+            Some(chunk) => header.extend_from_slice(&chunk?),
+            None => break,
+        }
+    }
+    Ok(header.freeze())
+}
+
 #[utoipa::path(
     params(
         ("collection_id", description = "Collection ID"),
         ("page_size" = Option<usize>, Query, description = "Number of items per page (default: 10)"),
         ("continuation_token" = Option<String>, Query, description = "Continuation token for cursor-based pagination"),
+        ("prefix" = Option<String>, Query, description = "Folder inside the collection to list, e.g. logs/2024/"),
+        ("delimiter" = Option<String>, Query, description = "Return one level of folders and files split at this delimiter (usually /)"),
     ),
     responses(
         (status = 200, description = "OK", body = PaginatedFiles),
+        (status = 403, description = "Collection is private"),
         (status = 404, description = "Collection not found"),
         (status = 500, description = "Internal Server Error"),
     ),
@@ -638,18 +640,21 @@ pub(crate) async fn list_collection_files(
     let s3_config = s3_config.into_inner();
     let collection_id = collection_id.into_inner();
 
-    let collection = match collections::get_collection(
-        &pool.into_inner(),
-        &user.as_owner(),
-        collection_id,
-    )
-    .await
+    if let Some(prefix) = &query.prefix
+        && (prefix.starts_with('/') || prefix.split('/').any(|part| part == ".."))
+    {
+        return ApiError::BadRequest("prefix must be a relative path inside the collection".into())
+            .error_response();
+    }
+    if let Some(delimiter) = &query.delimiter
+        && (delimiter.is_empty() || delimiter.len() > 16)
     {
+        return ApiError::BadRequest("delimiter must be 1-16 characters".into()).error_response();
+    }
+
+    let collection = match get_readable_collection(&pool, &user, collection_id).await {
         Ok(collection) => collection,
-        Err(_) => {
-            return ApiError::NotFound(format!("Collection '{}' not found", collection_id))
-                .error_response();
-        }
+        Err(e) => return e.error_response(),
     };
 
     match storage::s3::list_files(
@@ -658,13 +663,18 @@ pub(crate) async fn list_collection_files(
         &collection.collection_id.to_string(),
         query.page_size,
         query.continuation_token.as_deref(),
+        query.prefix.as_deref(),
+        query.delimiter.as_deref(),
     )
     .await
     {
-        Ok(s3_files) => {
-            let mut total_count = None;
-            if query.continuation_token.is_none() {
-                total_count = storage::s3::count_collection_files(
+        Ok(mut paginated_files) => {
+            // The count covers the whole collection, so only report it for unfiltered listings
+            if query.continuation_token.is_none()
+                && query.prefix.is_none()
+                && query.delimiter.is_none()
+            {
+                paginated_files.total_count = storage::s3::count_collection_files(
                     &s3_client,
                     &s3_config.bucket_name,
                     collection.collection_id,
@@ -673,15 +683,6 @@ pub(crate) async fn list_collection_files(
                 .ok();
             }
 
-            let paginated_files = PaginatedFiles {
-                files: s3_files.files,
-                page: 0,
-                page_size: query.page_size,
-                has_more: s3_files.continuation_token.is_some(),
-                continuation_token: s3_files.continuation_token,
-                total_count,
-            };
-
             HttpResponse::Ok().json(paginated_files)
         }
         Err(e) => ApiError::Internal(format!("error listing files: {:?}", e)).error_response(),
@@ -695,6 +696,7 @@ pub(crate) async fn list_collection_files(
     ),
     responses(
         (status = 200, description = "OK", content_type = "application/octet-stream"),
+        (status = 403, description = "Collection is private"),
         (status = 404, description = "Collection or file not found"),
         (status = 500, description = "Internal Server Error"),
     ),
@@ -713,21 +715,12 @@ pub(crate) async fn download_collection_file(
     let s3_config = s3_config.into_inner();
     let (collection_id, file_key) = path.into_inner();
 
-    let collection = match collections::get_collection(
-        &pool.into_inner(),
-        &user.as_owner(),
-        collection_id,
-    )
-    .await
-    {
+    let collection = match get_readable_collection(&pool, &user, collection_id).await {
         Ok(collection) => collection,
-        Err(_) => {
-            return ApiError::NotFound(format!("Collection '{}' not found", collection_id))
-                .error_response();
-        }
+        Err(e) => return e.error_response(),
     };
 
-    match storage::s3::get_file_with_size_check(
+    match storage::s3::get_file_stream(
         &s3_client,
         &s3_config.bucket_name,
         &collection.collection_id.to_string(),
@@ -736,7 +729,7 @@ pub(crate) async fn download_collection_file(
     )
     .await
     {
-        Ok(file_data) => {
+        Ok(file) => {
             // Audit log the file download
             crate::audit::events::file_downloaded(
                 &user.as_owner(),
@@ -749,13 +742,15 @@ pub(crate) async fn download_collection_file(
                 .first_or_octet_stream()
                 .to_string();
 
-            HttpResponse::Ok()
-                .content_type(mime_type)
-                .insert_header((
-                    "Content-Disposition",
-                    format!("attachment; filename=\"{}\"", file_key),
-                ))
-                .body(file_data)
+            let mut response = HttpResponse::Ok();
+            response.content_type(mime_type).insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", file_key),
+            ));
+            if let Some(content_length) = file.content_length {
+                response.no_chunking(content_length);
+            }
+            response.streaming(file.body)
         }
         Err(e) => {
             let error_msg = e.to_string();
@@ -768,6 +763,168 @@ pub(crate) async fn download_collection_file(
     }
 }
 
+/// Check a presigned upload request before anything is signed, returning the
+/// file name to sign the upload for
+fn validate_presigned_upload(
+    collection_id: i32,
+    body: &PresignedUploadRequest,
+    max_upload_size_bytes: i64,
+) -> Result<&str, ApiError> {
+    validation::validate_file_name(&body.file_name)?;
+    let file_name = body.file_name.trim();
+    validation::validate_s3_key(&format!("collections/{}/{}", collection_id, file_name))?;
+    if body.size <= 0 || body.size > max_upload_size_bytes {
+        return Err(ApiError::BadRequest(format!(
+            "size must be between 1 and {} bytes, got {}",
+            max_upload_size_bytes, body.size
+        )));
+    }
+    Ok(file_name)
+}
+
+#[utoipa::path(
+    params(
+        ("collection_id", description = "Collection ID"),
+    ),
+    request_body = PresignedUploadRequest,
+    responses(
+        (status = 200, description = "URL to PUT the file to, with the headers to send", body = PresignedUrl),
+        (status = 400, description = "Invalid file name, size over the upload limit, or collection does not exist"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    tag = "Collections",
+)]
+#[post("/api/collections/{collection_id}/presigned-uploads")]
+#[tracing::instrument(name = "create_presigned_upload", skip(user, s3_client, s3_config, pool, body), fields(collection_id = %collection_id.as_ref(), file_name = %body.file_name))]
+pub(crate) async fn create_presigned_upload(
+    user: AuthenticatedUser,
+    s3_client: Data<Client>,
+    s3_config: Data<S3Config>,
+    pool: Data<Pool<Postgres>>,
+    collection_id: Path<i32>,
+    body: Json<PresignedUploadRequest>,
+) -> impl Responder {
+    let collection_id = collection_id.into_inner();
+    let body = body.into_inner();
+
+    let file_name =
+        match validate_presigned_upload(collection_id, &body, s3_config.max_upload_size_bytes) {
+            Ok(file_name) => file_name,
+            Err(e) => return e.error_response(),
+        };
+
+    let collection = match collections::get_collection(&pool, &user.as_owner(), collection_id).await
+    {
+        Ok(collection) => collection,
+        Err(_) => {
+            return ApiError::BadRequest(format!("collection '{}' does not exist", collection_id))
+                .error_response();
+        }
+    };
+
+    let content_type = body.content_type.clone().unwrap_or_else(|| {
+        mime_guess::from_path(file_name)
+            .first_or_octet_stream()
+            .to_string()
+    });
+    let presigned = match storage::s3::generate_presigned_put(
+        &s3_client,
+        &s3_config.bucket_name,
+        &collection.collection_id.to_string(),
+        file_name,
+        &content_type,
+        body.size,
+        s3_config.presigned_url_expiry,
+    )
+    .await
+    {
+        Ok(presigned) => presigned,
+        Err(e) => {
+            return ApiError::Internal(format!("error presigning upload: {}", e)).error_response();
+        }
+    };
+
+    if let Err(e) = collections::record_pending_upload(
+        &pool,
+        collection_id,
+        file_name,
+        &user.as_owner(),
+        presigned.expires_at,
+    )
+    .await
+    {
+        return ApiError::Internal(format!("error recording pending upload: {}", e))
+            .error_response();
+    }
+
+    HttpResponse::Ok().json(presigned)
+}
+
+#[utoipa::path(
+    params(
+        ("collection_id", description = "Collection ID"),
+        ("file_key", description = "File key/name"),
+    ),
+    responses(
+        (status = 200, description = "URL to GET the file from", body = PresignedUrl),
+        (status = 403, description = "Collection is private"),
+        (status = 404, description = "Collection or file not found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    tag = "Collections",
+)]
+#[get("/api/collections/{collection_id}/files/{file_key}/presigned-url")]
+#[tracing::instrument(name = "create_presigned_download", skip(user, s3_client, s3_config, pool, path), fields(collection_id = %path.0, file_key = %path.1))]
+pub(crate) async fn create_presigned_download(
+    user: AuthenticatedUser,
+    s3_client: Data<Client>,
+    s3_config: Data<S3Config>,
+    pool: Data<Pool<Postgres>>,
+    path: Path<(i32, String)>,
+) -> impl Responder {
+    let (collection_id, file_key) = path.into_inner();
+
+    let collection = match get_readable_collection(&pool, &user, collection_id).await {
+        Ok(collection) => collection,
+        Err(e) => return e.error_response(),
+    };
+
+    let key = format!("{}{}", collection.s3_folder_key(), file_key);
+    if let Err(e) = validation::validate_s3_key(&key) {
+        return ApiError::from(e).error_response();
+    }
+    match storage::s3::file_exists(&s3_client, &s3_config.bucket_name, &key).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return ApiError::NotFound(format!("file '{}' not found", file_key)).error_response();
+        }
+        Err(e) => {
+            return ApiError::Internal(format!("error checking file: {}", e)).error_response();
+        }
+    }
+
+    match storage::s3::generate_presigned_get(
+        &s3_client,
+        &s3_config.bucket_name,
+        &collection.collection_id.to_string(),
+        &file_key,
+        s3_config.presigned_url_expiry,
+    )
+    .await
+    {
+        Ok(presigned) => {
+            crate::audit::events::file_downloaded(
+                &user.as_owner(),
+                &user,
+                collection_id,
+                &file_key,
+            );
+            HttpResponse::Ok().json(presigned)
+        }
+        Err(e) => ApiError::Internal(format!("error presigning download: {}", e)).error_response(),
+    }
+}
+
 #[utoipa::path(
     params(
         ("collection_id", description = "Collection ID"),
@@ -835,6 +992,144 @@ pub(crate) async fn delete_collection_file(
     }
 }
 
+#[utoipa::path(
+    params(
+        ("collection_id", description = "Collection ID"),
+        ("mode" = Option<ReconcileMode>, Query, description = "report (default) only lists drift; heal deletes orphaned records and rescans unprocessed files"),
+    ),
+    responses(
+        (status = 200, description = "Reconciliation report", body = FileReconciliationReport),
+        (status = 404, description = "Collection not found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    tag = "Collections",
+)]
+#[post("/api/collections/{collection_id}/reconcile-files")]
+#[tracing::instrument(name = "reconcile_collection_files", skip(user, s3_client, s3_config, pool, nats_client, query), fields(collection_id = %collection_id.as_ref()))]
+pub(crate) async fn reconcile_collection_files(
+    user: AuthenticatedUser,
+    s3_client: Data<Client>,
+    s3_config: Data<S3Config>,
+    pool: Data<Pool<Postgres>>,
+    nats_client: Data<async_nats::Client>,
+    collection_id: Path<i32>,
+    web::Query(query): web::Query<ReconcileFilesQuery>,
+) -> impl Responder {
+    let collection_id = collection_id.into_inner();
+    let pool = pool.into_inner();
+    let owner = user.as_owner();
+
+    if let Err(e) = collections::get_collection(&pool, &owner, collection_id).await {
+        warn!(collection_id, error = %e, "Collection not found for file reconciliation");
+        return ApiError::NotFound(format!("Collection '{}' not found", collection_id))
+            .error_response();
+    }
+
+    let mut object_keys = Vec::new();
+    let mut continuation_token: Option<String> = None;
+    loop {
+        let page = match storage::s3::list_files(
+            &s3_client,
+            &s3_config.bucket_name,
+            &collection_id.to_string(),
+            1000,
+            continuation_token.as_deref(),
+            None,
+            None,
+        )
+        .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                return ApiError::Internal(format!("error listing files: {:?}", e))
+                    .error_response();
+            }
+        };
+        object_keys.extend(page.files.into_iter().map(|file| file.key));
+        continuation_token = page.continuation_token;
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+
+    let transforms = match collection_transforms::get_collection_transforms_for_collection(
+        &pool,
+        &owner,
+        collection_id,
+    )
+    .await
+    {
+        Ok(transforms) => transforms,
+        Err(e) => {
+            return ApiError::Internal(format!("error loading collection transforms: {:?}", e))
+                .error_response();
+        }
+    };
+    let mut records = Vec::new();
+    for transform in &transforms {
+        match collection_transforms::get_processed_files(&pool, transform.collection_transform_id)
+            .await
+        {
+            Ok(files) => records.extend(files),
+            Err(e) => {
+                return ApiError::Internal(format!("error loading processed files: {:?}", e))
+                    .error_response();
+            }
+        }
+    }
+
+    let mut report = reconcile::find_drift(
+        collection_id,
+        query.mode,
+        &object_keys,
+        &transforms,
+        &records,
+    );
+
+    if query.mode == ReconcileMode::Heal {
+        for file_key in report.orphaned_file_keys() {
+            match collection_transforms::delete_processed_file_records(
+                &pool,
+                file_key,
+                collection_id,
+            )
+            .await
+            {
+                Ok(deleted) => report.records_deleted += deleted,
+                Err(e) => error!(
+                    collection_id,
+                    file_key, error = %e, "Failed to delete orphaned processed file records"
+                ),
+            }
+        }
+        for collection_transform_id in report.transforms_to_rescan() {
+            match trigger_collection_transform_scan(&nats_client, collection_transform_id, &owner)
+                .await
+            {
+                Ok(()) => report.scans_triggered.push(collection_transform_id),
+                Err(e) => error!(
+                    collection_id,
+                    collection_transform_id, error = %e, "Failed to trigger rescan during file reconciliation"
+                ),
+            }
+        }
+    }
+
+    info!(
+        collection_id,
+        mode = ?report.mode,
+        objects = report.object_count,
+        records = report.record_count,
+        unrecorded_objects = report.unrecorded_objects.len(),
+        orphaned_records = report.orphaned_records.len(),
+        records_deleted = report.records_deleted,
+        scans_triggered = report.scans_triggered.len(),
+        "Reconciled collection files"
+    );
+
+    HttpResponse::Ok().json(report)
+}
+
 #[utoipa::path(
     responses(
         (status = 200, description = "List of allowed MIME types", body = Vec<String>),
@@ -848,3 +1143,211 @@ pub(crate) async fn get_allowed_file_types() -> impl Responder {
 
     HttpResponse::Ok().json(get_allowed_mime_types())
 }
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Supported formats and the extraction options that apply to each", body = ExtractionCapabilities),
+    ),
+    tag = "Collections",
+)]
+#[get("/api/collections-extraction-capabilities")]
+#[tracing::instrument(name = "get_extraction_capabilities")]
+pub(crate) async fn get_extraction_capabilities() -> impl Responder {
+    HttpResponse::Ok().json(&EXTRACTION_CAPABILITIES)
+}
+
+/// How long to wait for a worker to extract a preview
+const EXTRACTION_PREVIEW_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[utoipa::path(
+    request_body(content = ExtractionPreviewUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Extracted text and metadata; nothing is stored", body = ExtractionPreview),
+        (status = 400, description = "Invalid extraction config or the file could not be extracted"),
+        (status = 503, description = "No extraction worker available"),
+    ),
+    tag = "Collections",
+)]
+#[post("/api/collections-extraction-preview")]
+#[tracing::instrument(
+    name = "preview_uploaded_file_extraction",
+    skip(_user, nats_client, payload)
+)]
+pub(crate) async fn preview_uploaded_file_extraction(
+    _user: AuthenticatedUser,
+    nats_client: Data<async_nats::Client>,
+    MultipartForm(payload): MultipartForm<ExtractionPreviewUpload>,
+) -> impl Responder {
+    let extraction_config = match payload.extraction_config {
+        Some(Text(config)) if !config.trim().is_empty() => match serde_json::from_str(&config) {
+            Ok(config) => config,
+            Err(e) => {
+                return ApiError::BadRequest(format!("Invalid extraction config: {}", e))
+                    .error_response();
+            }
+        },
+        _ => serde_json::json!({}),
+    };
+    let file_name = payload
+        .file
+        .file_name
+        .clone()
+        .unwrap_or_else(|| "file".to_string());
+    let content = match tokio::fs::read(payload.file.file.path()).await {
+        Ok(content) => content,
+        Err(e) => {
+            return ApiError::Internal(format!("error reading uploaded file: {}", e))
+                .error_response();
+        }
+    };
+
+    let request = ExtractionPreviewRequest {
+        file_name,
+        source: ExtractionPreviewSource::Inline { content },
+        extraction_config,
+    };
+    match request_extraction_preview(&nats_client, &request).await {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => e.error_response(),
+    }
+}
+
+#[utoipa::path(
+    params(
+        ("collection_id", description = "Collection ID"),
+    ),
+    request_body = StoredExtractionPreview,
+    responses(
+        (status = 200, description = "Extracted text and metadata; nothing is stored", body = ExtractionPreview),
+        (status = 400, description = "Invalid extraction config or the file could not be extracted"),
+        (status = 403, description = "Collection is private"),
+        (status = 404, description = "Collection not found"),
+        (status = 503, description = "No extraction worker available"),
+    ),
+    tag = "Collections",
+)]
+#[post("/api/collections/{collection_id}/extraction-preview")]
+#[tracing::instrument(name = "preview_collection_file_extraction", skip(user, s3_config, pool, nats_client, body), fields(collection_id = %collection_id.as_ref()))]
+pub(crate) async fn preview_collection_file_extraction(
+    user: AuthenticatedUser,
+    s3_config: Data<S3Config>,
+    pool: Data<Pool<Postgres>>,
+    nats_client: Data<async_nats::Client>,
+    collection_id: Path<i32>,
+    body: Json<StoredExtractionPreview>,
+) -> impl Responder {
+    let collection_id = collection_id.into_inner();
+    let body = body.into_inner();
+
+    let collection = match get_readable_collection(&pool, &user, collection_id).await {
+        Ok(collection) => collection,
+        Err(e) => return e.error_response(),
+    };
+
+    let key = format!("{}{}", collection.s3_folder_key(), body.file_key);
+    if let Err(e) = validation::validate_s3_key(&key) {
+        return ApiError::from(e).error_response();
+    }
+    let extraction_config = if body.extraction_config.is_null() {
+        serde_json::json!({})
+    } else {
+        body.extraction_config
+    };
+
+    let request = ExtractionPreviewRequest {
+        file_name: body.file_key,
+        source: ExtractionPreviewSource::Stored {
+            bucket: s3_config.bucket_name.clone(),
+            key,
+        },
+        extraction_config,
+    };
+    match request_extraction_preview(&nats_client, &request).await {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => e.error_response(),
+    }
+}
+
+/// Ask a collection worker to run extraction on a file and wait for the result
+async fn request_extraction_preview(
+    nats_client: &async_nats::Client,
+    request: &ExtractionPreviewRequest,
+) -> Result<ExtractionPreview, ApiError> {
+    let payload = serde_json::to_vec(request)
+        .map_err(|e| ApiError::Internal(format!("error encoding preview request: {}", e)))?;
+    let nats_request = async_nats::Request::new()
+        .payload(payload.into())
+        .timeout(Some(EXTRACTION_PREVIEW_TIMEOUT));
+
+    let message = nats_client
+        .send_request(subjects::requests::EXTRACTION_PREVIEW, nats_request)
+        .await
+        .map_err(|e| match e.kind() {
+            async_nats::RequestErrorKind::NoResponders => {
+                ApiError::ServiceUnavailable("no extraction worker is available".to_string())
+            }
+            async_nats::RequestErrorKind::TimedOut => ApiError::ServiceUnavailable(
+                "extraction preview timed out; try a smaller file".to_string(),
+            ),
+            _ => ApiError::Internal(format!("error requesting extraction preview: {}", e)),
+        })?;
+
+    match serde_json::from_slice(&message.payload) {
+        Ok(ExtractionPreviewResponse::Extracted { text, metadata }) => {
+            Ok(ExtractionPreview { text, metadata })
+        }
+        Ok(ExtractionPreviewResponse::Failed { error }) => Err(ApiError::BadRequest(error)),
+        Err(e) => Err(ApiError::Internal(format!(
+            "invalid extraction preview reply: {}",
+            e
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    fn upload(file_name: &str, size: i64) -> PresignedUploadRequest {
+        PresignedUploadRequest {
+            file_name: file_name.to_string(),
+            size,
+            content_type: None,
+        }
+    }
+
+    #[test]
+    fn test_presigned_upload_accepts_a_plain_file_name() {
+        let body = upload(" report.pdf ", 1024);
+        assert_eq!(
+            validate_presigned_upload(7, &body, 1024).unwrap(),
+            "report.pdf"
+        );
+    }
+
+    #[test]
+    fn test_presigned_upload_rejects_paths_outside_the_collection() {
+        for name in [
+            "../other-collection/file.txt",
+            "..",
+            "/etc/passwd",
+            "\\\\server\\share.txt",
+            "nested/file.txt",
+            "file\0.txt",
+            "",
+        ] {
+            let err = validate_presigned_upload(7, &upload(name, 10), 1024).unwrap_err();
+            assert_eq!(err.status_code(), StatusCode::BAD_REQUEST, "{name:?}");
+        }
+    }
+
+    #[test]
+    fn test_presigned_upload_size_is_bounded_by_the_upload_limit() {
+        assert!(validate_presigned_upload(7, &upload("a.txt", 1024), 1024).is_ok());
+        for size in [0, -1, 1025] {
+            let err = validate_presigned_upload(7, &upload("a.txt", size), 1024).unwrap_err();
+            assert!(err.to_string().contains("size must be between 1 and 1024"));
+        }
+    }
+}
@@ -1,15 +1,31 @@
+use crate::api::embedders::measured_dimensions;
 use crate::audit::{ResourceType, events};
 use crate::auth::AuthenticatedUser;
-use crate::errors::{bad_request, not_found};
+use crate::embedded_datasets::models::EmbeddedDataset;
+use crate::embedded_datasets::naming;
+use crate::errors::{bad_request, conflict, not_found};
+use crate::search::vector_size_of;
 use crate::storage::postgres::dataset_transform_stats::reconcile_from_batches;
 use crate::storage::postgres::{
-    INTERNAL_BATCH_SIZE, dataset_transform_batches, dataset_transforms, datasets,
-    embedded_datasets, fetch_all_batched,
+    INTERNAL_BATCH_SIZE, bulk_reembeds, dataset_transform_batches, dataset_transforms, datasets,
+    embedded_datasets, embedders, fetch_all_batched,
 };
+use crate::storage::qdrant::write;
+use crate::transforms::concurrency::{self, TransformKind};
+use crate::transforms::dataset::bulk_reembed;
+use crate::transforms::dataset::document_vectors;
+use crate::transforms::dataset::fallback;
+use crate::transforms::dataset::field_weights;
+use crate::transforms::dataset::item_types;
 use crate::transforms::dataset::models::{
-    CreateDatasetTransform, DatasetTransform, DatasetTransformStats, UpdateDatasetTransform,
+    BulkReembedProgress, BulkReembedRequest, CreateDatasetTransform, DatasetTransform,
+    DatasetTransformStats, UpdateDatasetTransform,
 };
-use semantic_explorer_core::config::S3Config;
+use crate::transforms::dataset::pinning;
+use semantic_explorer_core::config::{EmbeddingInferenceConfig, S3Config, WorkerConfig};
+use semantic_explorer_core::document_vectors::document_collection_name;
+use semantic_explorer_core::embedder::{DimensionMismatch, check_dimensions};
+use semantic_explorer_core::encryption::EncryptionService;
 use semantic_explorer_core::models::PaginatedResponse;
 use semantic_explorer_core::validation;
 
@@ -19,7 +35,8 @@ use async_nats::Client as NatsClient;
 use qdrant_client::Qdrant;
 use serde::Deserialize;
 use sqlx::{Pool, Postgres};
-use tracing::{debug, error, info};
+use std::collections::HashMap;
+use tracing::{debug, error, info, warn};
 
 #[derive(Deserialize, Debug)]
 pub struct SortParams {
@@ -136,16 +153,19 @@ pub async fn get_dataset_transform(
     responses(
         (status = 201, description = "Dataset transform created (with N embedded datasets)", body = DatasetTransform),
         (status = 400, description = "Invalid request"),
+        (status = 409, description = "Conflict - a collection name is already in use"),
         (status = 401, description = "Unauthorized"),
     ),
 )]
 #[post("/api/dataset-transforms")]
-#[tracing::instrument(name = "create_dataset_transform", skip(user, pool, nats_client, body, req), fields(title = %body.title, embedder_count = %body.embedder_ids.len()))]
+#[tracing::instrument(name = "create_dataset_transform", skip(user, pool, nats_client, encryption, inference_config, body, req), fields(title = %body.title, embedder_count = %body.embedder_ids.len()))]
 pub async fn create_dataset_transform(
     user: AuthenticatedUser,
     req: HttpRequest,
     pool: Data<Pool<Postgres>>,
     nats_client: Data<NatsClient>,
+    encryption: Data<EncryptionService>,
+    inference_config: Data<EmbeddingInferenceConfig>,
     body: Json<CreateDatasetTransform>,
 ) -> impl Responder {
     if let Err(e) = validation::validate_title(&body.title) {
@@ -155,7 +175,55 @@ pub async fn create_dataset_transform(
         return bad_request("At least one embedder must be specified");
     }
 
-    let job_config = serde_json::json!({});
+    let mut job_config = serde_json::json!({});
+    if let Some(ref template) = body.collection_name_template {
+        if let Err(e) = naming::validate_collection_name_template(template) {
+            return bad_request(e);
+        }
+        job_config[naming::COLLECTION_NAME_TEMPLATE_KEY] = serde_json::json!(template);
+    }
+    if let Some(ref field) = body.item_type_field {
+        if field.trim().is_empty() {
+            return bad_request("item_type_field cannot be empty");
+        }
+        job_config[item_types::ITEM_TYPE_FIELD_KEY] = serde_json::json!(field);
+    }
+    if let Some(ref overrides) = body.item_type_overrides {
+        if let Err(e) = item_types::validate_item_type_overrides(overrides) {
+            return bad_request(e);
+        }
+        job_config[item_types::ITEM_TYPE_OVERRIDES_KEY] = serde_json::json!(overrides);
+    }
+    if let Some(ref weights) = body.field_weights {
+        if let Err(e) = field_weights::validate_field_weights(weights) {
+            return bad_request(e);
+        }
+        job_config[field_weights::FIELD_WEIGHTS_KEY] = serde_json::json!(weights);
+    }
+    if let Some(ref fallbacks) = body.fallback_embedders {
+        if let Err(e) = fallback::validate_fallback_embedders(fallbacks) {
+            return bad_request(e);
+        }
+        job_config[fallback::FALLBACK_EMBEDDERS_KEY] = serde_json::json!(fallbacks);
+    }
+    if let Some(mode) = body.document_vectors {
+        job_config[document_vectors::DOCUMENT_VECTORS_KEY] = serde_json::json!(mode);
+    }
+    if let Some(consistency) = body.qdrant_write {
+        job_config[write::WRITE_CONSISTENCY_KEY] = serde_json::json!(consistency);
+    }
+
+    // Measured before the first batch is published, so workers get the
+    // dimensions with their jobs. The collections are new and get created
+    // with them.
+    embedder_dimensions(
+        &pool,
+        &user,
+        &encryption,
+        &inference_config.url,
+        &body.embedder_ids,
+    )
+    .await;
 
     let owner = user.to_owner_info();
     match dataset_transforms::create_dataset_transform(
@@ -165,6 +233,7 @@ pub async fn create_dataset_transform(
         &body.embedder_ids,
         &owner,
         &job_config,
+        true,
     )
     .await
     {
@@ -200,6 +269,9 @@ pub async fn create_dataset_transform(
                 "message": format!("Created dataset transform with {} embedded datasets. Batches are being generated in the background.", embedded_datasets.len())
             }))
         }
+        Err(e) if embedded_datasets::is_duplicate_collection_name(&e) => {
+            conflict("An embedded dataset already uses this collection name")
+        }
         Err(e) => {
             error!("Failed to create dataset transform: {}", e);
             bad_request(format!("Failed to create dataset transform: {}", e))
@@ -218,6 +290,7 @@ pub async fn create_dataset_transform(
     responses(
         (status = 200, description = "Dataset transform updated", body = DatasetTransform),
         (status = 404, description = "Dataset transform not found"),
+        (status = 409, description = "Conflict - a collection name is already in use"),
         (status = 401, description = "Unauthorized"),
     ),
 )]
@@ -240,19 +313,54 @@ pub async fn update_dataset_transform(
     {
         return bad_request("At least one embedder must be specified");
     }
+    if let Some(template) = body
+        .job_config
+        .as_ref()
+        .and_then(|c| c.get(naming::COLLECTION_NAME_TEMPLATE_KEY))
+    {
+        // Only affects embedded datasets created after the update (e.g. newly added embedders)
+        match template.as_str() {
+            Some(t) => {
+                if let Err(e) = naming::validate_collection_name_template(t) {
+                    return bad_request(e);
+                }
+            }
+            None => return bad_request("collection_name_template must be a string"),
+        }
+    }
+    if let Some(job_config) = body.job_config.as_ref()
+        && let Err(e) = item_types::validate_job_config(job_config)
+            .and_then(|()| field_weights::validate_job_config(job_config))
+            .and_then(|()| fallback::validate_job_config(job_config))
+            .and_then(|()| pinning::validate_job_config(job_config))
+            .and_then(|()| document_vectors::validate_job_config(job_config))
+            .and_then(|()| write::validate_job_config(job_config))
+    {
+        return bad_request(e);
+    }
 
     let id = path.into_inner();
 
-    // Check if transform is being re-enabled
-    let was_disabled = if body.is_enabled == Some(true) {
+    let existing = if body.is_enabled == Some(true) || body.job_config.is_some() {
         dataset_transforms::get_dataset_transform(&pool, &user.as_owner(), id)
             .await
-            .map(|t| !t.is_enabled)
-            .unwrap_or(false)
+            .ok()
     } else {
-        false
+        None
     };
 
+    // Check if transform is being re-enabled
+    let was_disabled =
+        body.is_enabled == Some(true) && existing.as_ref().is_some_and(|t| !t.is_enabled);
+
+    // Replacing the job config must not unpin the models of the current run
+    let job_config = body.job_config.clone().map(|mut job_config| {
+        if let Some(existing) = &existing {
+            pinning::carry_over_pins(&mut job_config, &existing.job_config);
+        }
+        job_config
+    });
+
     match dataset_transforms::update_dataset_transform(
         &pool,
         &user.as_owner(),
@@ -260,7 +368,7 @@ pub async fn update_dataset_transform(
         body.title.as_deref(),
         body.is_enabled,
         body.embedder_ids.as_deref(),
-        body.job_config.as_ref(),
+        job_config.as_ref(),
     )
     .await
     {
@@ -293,6 +401,9 @@ pub async fn update_dataset_transform(
                 "message": format!("Updated dataset transform, now has {} embedded datasets", embedded_datasets.len())
             }))
         }
+        Err(e) if embedded_datasets::is_duplicate_collection_name(&e) => {
+            conflict("An embedded dataset already uses this collection name")
+        }
         Err(e) => {
             error!("Failed to update dataset transform: {}", e);
             not_found(format!("Failed to update dataset transform: {}", e))
@@ -382,6 +493,12 @@ pub async fn delete_dataset_transform(
                             e
                         );
                     }
+                    // Only exists if the transform stored document vectors
+                    let _ = qdrant
+                        .delete_collection(document_collection_name(
+                            &embedded_dataset.collection_name,
+                        ))
+                        .await;
 
                     let prefix = format!(
                         "embedded-datasets/embedded-dataset-{}/",
@@ -424,6 +541,70 @@ pub async fn delete_dataset_transform(
     }
 }
 
+/// Output dimensions of `embedder_ids`, measured and stored for the
+/// embedders that were never measured. Embedders that can't be reached now
+/// are left out; dataset workers measure them before embedding.
+async fn embedder_dimensions(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    encryption: &EncryptionService,
+    inference_url: &str,
+    embedder_ids: &[i32],
+) -> HashMap<i32, u64> {
+    let embedders_list =
+        match embedders::get_embedders_batch(pool, user, embedder_ids, encryption).await {
+            Ok(embedders_list) => embedders_list,
+            Err(e) => {
+                warn!(error = %e, "Failed to load embedders to measure their dimensions");
+                return HashMap::new();
+            }
+        };
+
+    let mut dimensions = HashMap::new();
+    for embedder in &embedders_list {
+        match measured_dimensions(pool, embedder, inference_url).await {
+            Ok(dims) => {
+                dimensions.insert(embedder.embedder_id, dims);
+            }
+            Err(e) => warn!(
+                embedder_id = embedder.embedder_id,
+                error = %e,
+                "Failed to measure embedder dimensions"
+            ),
+        }
+    }
+    dimensions
+}
+
+/// Existing collections of `embedded_datasets` whose vectors are not the size
+/// their embedder produces. Missing collections are created by the dataset
+/// worker with the embedder's dimensions.
+async fn dimension_mismatches(
+    qdrant_client: &Qdrant,
+    embedded_datasets: &[EmbeddedDataset],
+    dimensions: &HashMap<i32, u64>,
+) -> Vec<DimensionMismatch> {
+    let mut mismatches = Vec::new();
+    for embedded_dataset in embedded_datasets {
+        let Some(&embedder_dimensions) = dimensions.get(&embedded_dataset.embedder_id) else {
+            continue;
+        };
+        let Ok(info) = qdrant_client
+            .collection_info(&embedded_dataset.collection_name)
+            .await
+        else {
+            continue;
+        };
+        if let Some(size) = vector_size_of(&info)
+            && let Err(mismatch) =
+                check_dimensions(&embedded_dataset.collection_name, size, embedder_dimensions)
+        {
+            mismatches.push(mismatch);
+        }
+    }
+    mismatches
+}
+
 #[utoipa::path(
     post,
     path = "/api/dataset-transforms/{id}/trigger",
@@ -433,16 +614,22 @@ pub async fn delete_dataset_transform(
     ),
     responses(
         (status = 200, description = "Dataset transform triggered for all embedders"),
+        (status = 400, description = "An embedder's dimensions don't match its existing collection"),
         (status = 404, description = "Dataset transform not found"),
+        (status = 429, description = "Too many transforms already running for this user"),
         (status = 401, description = "Unauthorized"),
     ),
 )]
 #[post("/api/dataset-transforms/{id}/trigger")]
-#[tracing::instrument(name = "trigger_dataset_transform", skip(user, pool, nats_client), fields(dataset_transform_id = %path.as_ref()))]
+#[tracing::instrument(name = "trigger_dataset_transform", skip(user, pool, nats_client, encryption, worker_config, inference_config, qdrant_client), fields(dataset_transform_id = %path.as_ref()))]
 pub async fn trigger_dataset_transform(
     user: AuthenticatedUser,
     pool: Data<Pool<Postgres>>,
     nats_client: Data<NatsClient>,
+    encryption: Data<EncryptionService>,
+    worker_config: Data<WorkerConfig>,
+    inference_config: Data<EmbeddingInferenceConfig>,
+    qdrant_client: Data<Qdrant>,
     path: Path<i32>,
 ) -> impl Responder {
     let dataset_transform_id = path.into_inner();
@@ -462,6 +649,66 @@ pub async fn trigger_dataset_transform(
         }
     };
 
+    // Refuse a run whose embedders no longer fit the collections written by
+    // earlier runs, e.g. after an embedder's model was changed
+    let dimensions = embedder_dimensions(
+        &pool,
+        &user,
+        &encryption,
+        &inference_config.url,
+        &transform.embedder_ids,
+    )
+    .await;
+    match fetch_all_batched(INTERNAL_BATCH_SIZE, |limit, offset| {
+        embedded_datasets::get_embedded_datasets_for_transform(
+            &pool,
+            dataset_transform_id,
+            limit,
+            offset,
+        )
+    })
+    .await
+    {
+        Ok(embedded_datasets_list) => {
+            let mismatches =
+                dimension_mismatches(&qdrant_client, &embedded_datasets_list, &dimensions).await;
+            if !mismatches.is_empty() {
+                return bad_request(
+                    mismatches
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                );
+            }
+        }
+        Err(e) => warn!(
+            error = %e,
+            "Failed to load embedded datasets to check their collections, leaving it to the worker"
+        ),
+    }
+
+    if let Err(response) = concurrency::reserve_slot(
+        &pool,
+        &worker_config,
+        &user.as_owner(),
+        TransformKind::Dataset,
+        dataset_transform_id,
+    )
+    .await
+    {
+        return response;
+    }
+
+    // A new run embeds with the embedders' current models until it is
+    // triggered again
+    if let Err(e) = pinning::pin_transform_embedders(&pool, &encryption, &transform, true).await {
+        error!("Failed to pin embedders for dataset transform: {}", e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to pin embedders for dataset transform: {}", e)
+        }));
+    }
+
     // Actually trigger the scan
     if let Err(e) = crate::transforms::dataset::scanner::trigger_dataset_transform_scan(
         &nats_client,
@@ -487,6 +734,159 @@ pub async fn trigger_dataset_transform(
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/dataset-transforms/bulk-reembed",
+    tag = "Dataset Transforms",
+    request_body = BulkReembedRequest,
+    responses(
+        (status = 202, description = "Re-embed transforms created and queued", body = BulkReembedProgress),
+        (status = 400, description = "Invalid request"),
+        (status = 404, description = "Embedder or dataset not found"),
+        (status = 409, description = "Conflict - a collection name is already in use"),
+        (status = 401, description = "Unauthorized"),
+    ),
+)]
+#[post("/api/dataset-transforms/bulk-reembed")]
+#[tracing::instrument(name = "bulk_reembed_datasets", skip(user, req, pool, nats_client, encryption, worker_config, body), fields(embedder_id = %body.embedder_id, dataset_count = %body.dataset_ids.len()))]
+pub async fn bulk_reembed_datasets(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    pool: Data<Pool<Postgres>>,
+    nats_client: Data<NatsClient>,
+    encryption: Data<EncryptionService>,
+    worker_config: Data<WorkerConfig>,
+    body: Json<BulkReembedRequest>,
+) -> impl Responder {
+    let dataset_ids = match bulk_reembed::validate_dataset_ids(&body.dataset_ids) {
+        Ok(ids) => ids,
+        Err(e) => return bad_request(e),
+    };
+
+    let embedder = match embedders::get_embedder(&pool, &user, body.embedder_id, &encryption).await
+    {
+        Ok(embedder) => embedder,
+        Err(e) => {
+            error!("Embedder not found: {}", e);
+            return not_found(format!("Embedder {} not found", body.embedder_id));
+        }
+    };
+
+    // Check every dataset before creating anything
+    let owner = user.to_owner_info();
+    let mut source_datasets = Vec::with_capacity(dataset_ids.len());
+    for dataset_id in dataset_ids {
+        match datasets::get_dataset(&pool, &owner.owner_id, dataset_id).await {
+            Ok(dataset) => source_datasets.push(dataset),
+            Err(e) => {
+                error!("Dataset not found: {}", e);
+                return not_found(format!("Dataset {} not found", dataset_id));
+            }
+        }
+    }
+
+    // Transforms are created disabled and started as slots free up
+    let reembeds: Vec<(i32, String)> = source_datasets
+        .iter()
+        .map(|dataset| {
+            (
+                dataset.dataset_id,
+                format!("{} ({})", dataset.title, embedder.name),
+            )
+        })
+        .collect();
+    let (run, dataset_transform_ids) =
+        match bulk_reembeds::create_bulk_reembed(&pool, &owner, embedder.embedder_id, &reembeds)
+            .await
+        {
+            Ok(created) => created,
+            Err(e) if embedded_datasets::is_duplicate_collection_name(&e) => {
+                return conflict("An embedded dataset already uses this collection name");
+            }
+            Err(e) => {
+                error!("Failed to create bulk re-embed: {:#}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Failed to create bulk re-embed: {:#}", e)
+                }));
+            }
+        };
+    for dataset_transform_id in dataset_transform_ids {
+        events::resource_created_with_request(
+            &req,
+            &user.as_owner(),
+            &user,
+            ResourceType::Transform,
+            &dataset_transform_id.to_string(),
+        );
+    }
+
+    if let Err(e) =
+        bulk_reembed::start_queued(&pool, &nats_client, &encryption, &worker_config).await
+    {
+        // Log but don't fail - reconciliation starts them later
+        error!("Failed to start queued re-embeds: {}", e);
+    }
+
+    match bulk_reembed::progress(&pool, &run).await {
+        Ok(progress) => {
+            info!(
+                "Bulk re-embed {} queued {} datasets ({} started)",
+                run.bulk_reembed_id,
+                progress.total,
+                progress.total - progress.queued
+            );
+            HttpResponse::Accepted().json(progress)
+        }
+        Err(e) => {
+            error!("Failed to fetch bulk re-embed progress: {}", e);
+            HttpResponse::Accepted().json(serde_json::json!({
+                "bulk_reembed_id": run.bulk_reembed_id,
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/dataset-transforms/bulk-reembed/{id}",
+    tag = "Dataset Transforms",
+    params(
+        ("id" = i32, Path, description = "Bulk re-embed ID")
+    ),
+    responses(
+        (status = 200, description = "Aggregate and per-dataset progress", body = BulkReembedProgress),
+        (status = 404, description = "Bulk re-embed not found"),
+        (status = 401, description = "Unauthorized"),
+    ),
+)]
+#[get("/api/dataset-transforms/bulk-reembed/{id}")]
+#[tracing::instrument(name = "get_bulk_reembed", skip(user, pool), fields(bulk_reembed_id = %path.as_ref()))]
+pub async fn get_bulk_reembed(
+    user: AuthenticatedUser,
+    pool: Data<Pool<Postgres>>,
+    path: Path<i32>,
+) -> impl Responder {
+    let bulk_reembed_id = path.into_inner();
+    let run = match bulk_reembeds::get_bulk_reembed(&pool, &user.as_owner(), bulk_reembed_id).await
+    {
+        Ok(run) => run,
+        Err(e) => {
+            error!("Bulk re-embed not found: {}", e);
+            return not_found(format!("Bulk re-embed not found: {}", e));
+        }
+    };
+
+    match bulk_reembed::progress(&pool, &run).await {
+        Ok(progress) => HttpResponse::Ok().json(progress),
+        Err(e) => {
+            error!("Failed to fetch bulk re-embed progress: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to fetch bulk re-embed progress: {}", e)
+            }))
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/api/dataset-transforms/{id}/retry-failed",
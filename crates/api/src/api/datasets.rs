@@ -1,20 +1,26 @@
 use actix_web::{
-    HttpRequest, HttpResponse, Responder, ResponseError, delete, get, patch, post,
+    FromRequest, HttpRequest, HttpResponse, Responder, ResponseError, delete, get,
+    http::header,
+    patch, post,
     web::{self, Data, Json, Path, Query},
 };
 
 use crate::{
     audit::{ResourceType, events},
     auth::AuthenticatedUser,
+    datasets::dedup::{DedupOptions, DedupParams, NewItem},
     datasets::models::{
         CreateDataset, CreateDatasetItems, CreateDatasetItemsResponse, Dataset, DatasetItemChunks,
         DatasetListParams, DatasetWithStats, PaginatedDatasetItemSummaries, PaginatedDatasetItems,
         PaginatedDatasetList, PaginationParams,
     },
+    datasets::ndjson_import::{self, BatchOutcome, NdjsonImportLimits, NdjsonImportResponse},
     errors::ApiError,
     storage::{
         postgres::{
-            INTERNAL_BATCH_SIZE, dataset_transforms, datasets, embedded_datasets, fetch_all_batched,
+            INTERNAL_BATCH_SIZE, dataset_transforms,
+            datasets::{self, DedupedItems},
+            embedded_datasets, fetch_all_batched,
         },
         valkey::{self, ValkeyClients},
     },
@@ -27,6 +33,7 @@ use qdrant_client::{
     },
 };
 use semantic_explorer_core::config::{S3Config, ValkeyConfig};
+use semantic_explorer_core::document_vectors::document_collection_name;
 use semantic_explorer_core::validation;
 use sqlx::{Pool, Postgres};
 use tracing::{error, info};
@@ -428,6 +435,12 @@ pub(crate) async fn delete_dataset(
                             e
                         );
                     }
+                    // Only exists if the transform stored document vectors
+                    let _ = qdrant
+                        .delete_collection(document_collection_name(
+                            &embedded_dataset.collection_name,
+                        ))
+                        .await;
 
                     let prefix = format!(
                         "embedded-datasets/embedded-dataset-{}/",
@@ -470,12 +483,17 @@ pub(crate) async fn delete_dataset(
 }
 
 #[utoipa::path(
-    request_body(content = CreateDatasetItems),
+    request_body(content(
+        (CreateDatasetItems = "application/json"),
+        (String = "application/x-ndjson", description = "One CreateDatasetItem per line, imported as it streams in"),
+    )),
     params(
         ("dataset_id", description = "Dataset ID"),
+        DedupParams,
      ),
     responses(
         (status = 201, description = "Created", body = CreateDatasetItemsResponse),
+        (status = 200, description = "Summary of an NDJSON import", body = NdjsonImportResponse),
         (status = 401, description = "Unauthorized"),
         (status = 400, description = "Bad Request (dataset does not exist)"),
         (status = 500, description = "Internal Server Error"),
@@ -483,16 +501,33 @@ pub(crate) async fn delete_dataset(
     tag = "Datasets",
 )]
 #[post("/api/datasets/{dataset_id}/items")]
-#[tracing::instrument(name = "upload_to_dataset", skip(user, pool, nats_client, payload), fields(dataset_id = %dataset_id.as_ref(), item_count = payload.items.len()))]
+#[tracing::instrument(name = "upload_to_dataset", skip(user, pool, nats_client, params, req, body), fields(dataset_id = %dataset_id.as_ref(), item_count = tracing::field::Empty))]
 pub(crate) async fn upload_to_dataset(
     user: AuthenticatedUser,
     pool: Data<Pool<Postgres>>,
     nats_client: Data<async_nats::Client>,
     dataset_id: Path<i32>,
-    Json(payload): Json<CreateDatasetItems>,
-) -> impl Responder {
+    Query(params): Query<DedupParams>,
+    req: HttpRequest,
+    body: web::Payload,
+) -> HttpResponse {
     let pool = pool.into_inner();
     let dataset_id = dataset_id.into_inner();
+    let dedup = match params.options() {
+        Ok(dedup) => dedup,
+        Err(e) => return ApiError::BadRequest(e).error_response(),
+    };
+
+    if is_ndjson(&req) {
+        return import_ndjson_to_dataset(&user, &pool, &nats_client, dataset_id, dedup, body).await;
+    }
+
+    let payload = match Json::<CreateDatasetItems>::from_request(&req, &mut body.into_inner()).await
+    {
+        Ok(Json(payload)) => payload,
+        Err(e) => return e.error_response(),
+    };
+    tracing::Span::current().record("item_count", payload.items.len());
 
     let dataset = match datasets::get_dataset(&pool, &user.as_owner(), dataset_id).await {
         Ok(dataset) => dataset,
@@ -510,62 +545,182 @@ pub(crate) async fn upload_to_dataset(
         .map(|item| (item.title, item.chunks, item.metadata))
         .collect();
 
-    let (completed, failed) =
-        match datasets::create_dataset_items_batch(&pool, dataset.dataset_id, batch_items).await {
-            Ok((items, failed_titles)) => {
-                let item_duration = item_start.elapsed().as_secs_f64();
-                semantic_explorer_core::observability::record_document_upload(
-                    "dataset",
-                    item_duration,
-                    true,
-                );
-                (
-                    items.into_iter().map(|item| item.title).collect::<Vec<_>>(),
-                    failed_titles,
-                )
-            }
-            Err(e) => {
-                let item_duration = item_start.elapsed().as_secs_f64();
-                semantic_explorer_core::observability::record_document_upload(
-                    "dataset",
-                    item_duration,
-                    false,
-                );
-                error!("error batch uploading items to dataset '{dataset_id}': {e:?}");
-                return ApiError::Internal(format!("failed to upload items: {}", e))
-                    .error_response();
-            }
-        };
+    let stored = match store_items(&pool, dataset.dataset_id, batch_items, dedup.as_ref()).await {
+        Ok(stored) => {
+            let item_duration = item_start.elapsed().as_secs_f64();
+            semantic_explorer_core::observability::record_document_upload(
+                "dataset",
+                item_duration,
+                true,
+            );
+            stored
+        }
+        Err(e) => {
+            let item_duration = item_start.elapsed().as_secs_f64();
+            semantic_explorer_core::observability::record_document_upload(
+                "dataset",
+                item_duration,
+                false,
+            );
+            error!("error batch uploading items to dataset '{dataset_id}': {e:?}");
+            return ApiError::Internal(format!("failed to upload items: {}", e)).error_response();
+        }
+    };
 
-    // Trigger dataset transforms for items that were just created
+    let completed: Vec<String> = stored
+        .created
+        .into_iter()
+        .chain(stored.updated)
+        .map(|item| item.title)
+        .collect();
+
+    // Trigger dataset transforms for items that were just created or updated
     if !completed.is_empty() {
-        let owner = user.as_owner();
-        if let Ok(transforms) =
-            dataset_transforms::get_dataset_transforms_for_dataset(&pool, &owner, dataset_id).await
-        {
-            for transform in transforms {
-                if !transform.is_enabled {
-                    continue;
-                }
-                if let Err(e) = crate::transforms::trigger::publish_targeted_trigger(
-                    nats_client.as_ref(),
-                    "dataset",
-                    transform.dataset_transform_id,
-                    &owner,
-                )
-                .await
-                {
-                    tracing::warn!(
-                        "Failed to trigger dataset transform {} after item upload: {}",
-                        transform.dataset_transform_id,
-                        e
-                    );
-                }
+        trigger_dataset_transforms(&pool, &nats_client, &user, dataset_id).await;
+    }
+
+    HttpResponse::Ok().json(CreateDatasetItemsResponse {
+        completed,
+        failed: stored.failed,
+        skipped: stored.skipped,
+    })
+}
+
+/// Store items, deduplicating them against existing items if asked to
+async fn store_items(
+    pool: &Pool<Postgres>,
+    dataset_id: i32,
+    items: Vec<NewItem>,
+    dedup: Option<&DedupOptions>,
+) -> anyhow::Result<DedupedItems> {
+    match dedup {
+        Some(dedup) => {
+            datasets::create_dataset_items_deduplicated(pool, dataset_id, items, dedup).await
+        }
+        None => {
+            let (created, failed) =
+                datasets::create_dataset_items_batch(pool, dataset_id, items).await?;
+            Ok(DedupedItems {
+                created,
+                failed,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+fn is_ndjson(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| {
+            let mime = mime.trim();
+            mime.eq_ignore_ascii_case("application/x-ndjson")
+                || mime.eq_ignore_ascii_case("application/jsonl")
+        })
+}
+
+/// Stream an NDJSON body into the dataset, storing items batch by batch as
+/// they are parsed
+async fn import_ndjson_to_dataset(
+    user: &AuthenticatedUser,
+    pool: &Pool<Postgres>,
+    nats_client: &async_nats::Client,
+    dataset_id: i32,
+    dedup: Option<DedupOptions>,
+    body: web::Payload,
+) -> HttpResponse {
+    if datasets::get_dataset(pool, &user.as_owner(), dataset_id)
+        .await
+        .is_err()
+    {
+        return ApiError::BadRequest(format!("dataset '{}' does not exist", dataset_id))
+            .error_response();
+    }
+
+    let import_start = std::time::Instant::now();
+    let result = ndjson_import::import_ndjson(body, NdjsonImportLimits::default(), |items| {
+        let batch_items: Vec<(String, Vec<_>, serde_json::Value)> = items
+            .into_iter()
+            .map(|item| (item.title, item.chunks, item.metadata))
+            .collect();
+        let dedup = dedup.as_ref();
+        async move {
+            let stored = store_items(pool, dataset_id, batch_items, dedup).await?;
+            Ok::<_, anyhow::Error>(BatchOutcome {
+                created: stored.created.len(),
+                updated: stored.updated.len(),
+                skipped: stored.skipped.len(),
+                failed: stored.failed,
+            })
+        }
+    })
+    .await;
+    semantic_explorer_core::observability::record_document_upload(
+        "dataset",
+        import_start.elapsed().as_secs_f64(),
+        result.is_ok(),
+    );
+
+    match result {
+        Ok(summary) => {
+            tracing::Span::current().record("item_count", summary.items_created);
+            info!(
+                dataset_id,
+                items_created = summary.items_created,
+                items_updated = summary.items_updated,
+                items_skipped = summary.items_skipped,
+                failed = summary.failed_count,
+                invalid_lines = summary.invalid_line_count,
+                bytes_read = summary.bytes_read,
+                "NDJSON import completed"
+            );
+            if summary.items_created + summary.items_updated > 0 {
+                trigger_dataset_transforms(pool, nats_client, user, dataset_id).await;
             }
+            HttpResponse::Ok().json(summary)
+        }
+        Err(e) => {
+            // Batches stored before the error are kept; run transforms on them
+            trigger_dataset_transforms(pool, nats_client, user, dataset_id).await;
+            error!("error importing NDJSON into dataset '{dataset_id}': {e:?}");
+            ApiError::Internal(format!("NDJSON import failed: {}", e)).error_response()
         }
     }
+}
 
-    HttpResponse::Ok().json(CreateDatasetItemsResponse { completed, failed })
+/// Trigger the dataset's enabled transforms to pick up newly created items
+async fn trigger_dataset_transforms(
+    pool: &Pool<Postgres>,
+    nats_client: &async_nats::Client,
+    user: &AuthenticatedUser,
+    dataset_id: i32,
+) {
+    let owner = user.as_owner();
+    if let Ok(transforms) =
+        dataset_transforms::get_dataset_transforms_for_dataset(pool, &owner, dataset_id).await
+    {
+        for transform in transforms {
+            if !transform.is_enabled {
+                continue;
+            }
+            if let Err(e) = crate::transforms::trigger::publish_targeted_trigger(
+                nats_client,
+                "dataset",
+                transform.dataset_transform_id,
+                &owner,
+            )
+            .await
+            {
+                tracing::warn!(
+                    "Failed to trigger dataset transform {} after item upload: {}",
+                    transform.dataset_transform_id,
+                    e
+                );
+            }
+        }
+    }
 }
 
 #[utoipa::path(
@@ -0,0 +1,274 @@
+//! Dead-letter queue inspection and replay.
+//!
+//! Transform jobs that exhaust their delivery attempts are parked on the
+//! `DLQ_TRANSFORMS` stream. These endpoints list a user's dead-lettered jobs
+//! with the error that sank them, and put a job back on its source stream
+//! once the cause has been fixed. Users only see jobs they own.
+
+use crate::{
+    audit::{ResourceType, events},
+    auth::AuthenticatedUser,
+    errors::ApiError,
+};
+use actix_web::{
+    HttpResponse, Responder, ResponseError, get, post,
+    web::{Data, Path, Query},
+};
+use async_nats::Client as NatsClient;
+use semantic_explorer_core::nats::{self as core_nats, DlqMessage};
+use semantic_explorer_core::subjects::dlq;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+/// Most DLQ sequences examined per list request, so a user with few entries
+/// in a large DLQ can't make one request walk the whole stream
+const MAX_SCAN_PER_REQUEST: u64 = 1000;
+
+const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub(crate) struct DlqListQuery {
+    /// Return entries after this DLQ sequence; use `next_after` from the
+    /// previous page
+    #[schema(default = 0, minimum = 0)]
+    #[serde(default)]
+    pub(crate) after: u64,
+    #[schema(default = 20, minimum = 1, maximum = 100)]
+    #[serde(default = "default_limit")]
+    pub(crate) limit: usize,
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct DlqEntry {
+    /// DLQ stream sequence; pass it to the replay endpoint
+    pub(crate) sequence: u64,
+    pub(crate) subject: String,
+    /// Subject a replay publishes to
+    pub(crate) source_subject: Option<String>,
+    /// Error from the last delivery attempt
+    pub(crate) last_error: Option<String>,
+    pub(crate) delivery_count: Option<u64>,
+    pub(crate) failed_at: String,
+    /// The original job
+    #[schema(value_type = Object)]
+    pub(crate) payload: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct DlqEntries {
+    pub(crate) entries: Vec<DlqEntry>,
+    /// Cursor for the next page; absent when there are no more entries
+    pub(crate) next_after: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct DlqReplayResponse {
+    pub(crate) sequence: u64,
+    /// Subject the job was re-published to
+    pub(crate) subject: String,
+}
+
+/// DLQ subject for a transform type in the URL
+fn dlq_subject(transform_type: &str) -> Result<&'static str, ApiError> {
+    match transform_type {
+        "collection" => Ok(dlq::COLLECTION_TRANSFORM),
+        "dataset" => Ok(dlq::DATASET_TRANSFORM),
+        "visualization" => Ok(dlq::VISUALIZATION_TRANSFORM),
+        other => Err(ApiError::BadRequest(format!(
+            "unknown transform type '{other}', expected collection, dataset or visualization"
+        ))),
+    }
+}
+
+/// The original job as JSON, if it is owned by `owner`
+fn owned_payload(message: &DlqMessage, owner: &str) -> Option<serde_json::Value> {
+    let payload: serde_json::Value = serde_json::from_slice(&message.payload).ok()?;
+    (payload.get("owner_id").and_then(|v| v.as_str()) == Some(owner)).then_some(payload)
+}
+
+fn to_entry(message: DlqMessage, payload: serde_json::Value) -> DlqEntry {
+    DlqEntry {
+        sequence: message.sequence,
+        subject: message.subject,
+        source_subject: message.source_subject,
+        last_error: message.error,
+        delivery_count: message.delivery_count,
+        failed_at: message.failed_at,
+        payload,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/dlq/{transform_type}",
+    tag = "Dead Letter Queue",
+    params(
+        ("transform_type" = String, Path, description = "collection, dataset or visualization"),
+        DlqListQuery
+    ),
+    responses(
+        (status = 200, description = "Dead-lettered jobs owned by the caller", body = DlqEntries),
+        (status = 400, description = "Unknown transform type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+)]
+#[get("/api/dlq/{transform_type}")]
+#[tracing::instrument(name = "list_dlq_messages", skip(user, nats_client, query))]
+pub(crate) async fn list_dlq_messages(
+    user: AuthenticatedUser,
+    nats_client: Data<NatsClient>,
+    transform_type: Path<String>,
+    query: Query<DlqListQuery>,
+) -> impl Responder {
+    let subject = match dlq_subject(&transform_type) {
+        Ok(s) => s,
+        Err(e) => return e.error_response(),
+    };
+    let owner = user.as_owner();
+    let limit = query.limit.clamp(1, MAX_LIMIT);
+
+    let page = match core_nats::read_dlq_messages(
+        &nats_client,
+        subject,
+        query.after,
+        limit,
+        MAX_SCAN_PER_REQUEST,
+        |message| owned_payload(message, &owner).is_some(),
+    )
+    .await
+    {
+        Ok(page) => page,
+        Err(e) => {
+            error!(error = %e, "Failed to read DLQ");
+            return ApiError::Internal(format!("Failed to read DLQ: {e}")).error_response();
+        }
+    };
+
+    let entries = page
+        .messages
+        .into_iter()
+        .filter_map(|message| {
+            let payload = owned_payload(&message, &owner)?;
+            Some(to_entry(message, payload))
+        })
+        .collect();
+
+    HttpResponse::Ok().json(DlqEntries {
+        entries,
+        next_after: page.next_after,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/dlq/{transform_type}/{sequence}/replay",
+    tag = "Dead Letter Queue",
+    params(
+        ("transform_type" = String, Path, description = "collection, dataset or visualization"),
+        ("sequence" = u64, Path, description = "DLQ entry sequence")
+    ),
+    responses(
+        (status = 200, description = "Job re-published and removed from the DLQ", body = DlqReplayResponse),
+        (status = 400, description = "Unknown transform type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "DLQ entry not found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+)]
+#[post("/api/dlq/{transform_type}/{sequence}/replay")]
+#[tracing::instrument(name = "replay_dlq_message", skip(user, nats_client))]
+pub(crate) async fn replay_dlq_message(
+    user: AuthenticatedUser,
+    nats_client: Data<NatsClient>,
+    path: Path<(String, u64)>,
+) -> impl Responder {
+    let (transform_type, sequence) = path.into_inner();
+    let subject = match dlq_subject(&transform_type) {
+        Ok(s) => s,
+        Err(e) => return e.error_response(),
+    };
+
+    let message = match core_nats::get_dlq_message(&nats_client, sequence).await {
+        Ok(Some(message)) => message,
+        Ok(None) => {
+            return ApiError::NotFound(format!("DLQ entry {sequence} not found")).error_response();
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to read DLQ entry");
+            return ApiError::Internal(format!("Failed to read DLQ entry: {e}")).error_response();
+        }
+    };
+    // Someone else's entry is reported the same as a missing one
+    if message.subject != subject || owned_payload(&message, &user.as_owner()).is_none() {
+        return ApiError::NotFound(format!("DLQ entry {sequence} not found")).error_response();
+    }
+
+    match core_nats::replay_dlq_message(&nats_client, &message).await {
+        Ok(subject) => {
+            events::resource_updated(
+                &user.as_owner(),
+                &user,
+                ResourceType::Transform,
+                &format!("dlq/{transform_type}/{sequence}"),
+            );
+            HttpResponse::Ok().json(DlqReplayResponse { sequence, subject })
+        }
+        Err(e) => {
+            error!(error = %format!("{e:#}"), "Failed to replay DLQ entry");
+            ApiError::Internal(format!("Failed to replay DLQ entry: {e:#}")).error_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(payload: &str) -> DlqMessage {
+        DlqMessage {
+            sequence: 7,
+            subject: dlq::DATASET_TRANSFORM.to_string(),
+            source_subject: Some("workers.dataset-transform".to_string()),
+            error: Some("embedder unavailable".to_string()),
+            delivery_count: Some(5),
+            failed_at: "2026-01-01 0:00:00.0 +00:00:00".to_string(),
+            payload: payload.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_dlq_subject_for_transform_type() {
+        assert_eq!(dlq_subject("dataset").unwrap(), "dlq.dataset-transforms");
+        assert_eq!(
+            dlq_subject("collection").unwrap(),
+            "dlq.collection-transforms"
+        );
+        assert!(matches!(
+            dlq_subject("scanner"),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_entries_are_only_visible_to_their_owner() {
+        let own = message(r#"{"owner_id":"abc","dataset_transform_id":3}"#);
+        assert!(owned_payload(&own, "abc").is_some());
+        assert!(owned_payload(&own, "xyz").is_none());
+
+        // Payloads without an owner are never exposed
+        assert!(owned_payload(&message(r#"{"dataset_transform_id":3}"#), "abc").is_none());
+        assert!(owned_payload(&message("not json"), "abc").is_none());
+
+        let entry = to_entry(own.clone(), owned_payload(&own, "abc").unwrap());
+        assert_eq!(entry.sequence, 7);
+        assert_eq!(entry.last_error.as_deref(), Some("embedder unavailable"));
+        assert_eq!(entry.delivery_count, Some(5));
+        assert_eq!(entry.payload["dataset_transform_id"], 3);
+    }
+}
@@ -7,15 +7,17 @@ use crate::embedded_datasets::{
 };
 use crate::errors::{bad_request, not_found};
 use crate::storage::postgres::embedded_datasets;
+use crate::storage::qdrant::write;
 use actix_web::web::{Data, Json, Path, Query};
 use actix_web::{HttpRequest, HttpResponse, Responder, delete, get, patch, post};
 use qdrant_client::Qdrant;
 use qdrant_client::qdrant::point_id::PointIdOptions;
 use qdrant_client::qdrant::{
-    CreateCollectionBuilder, Distance, GetPointsBuilder, PointStruct, UpsertPointsBuilder,
-    VectorParams,
+    CreateCollectionBuilder, Distance, GetPointsBuilder, PointStruct, VectorParams,
 };
 use qdrant_client::qdrant::{PointId, ScrollPointsBuilder};
+use semantic_explorer_core::document_vectors::document_collection_name;
+use semantic_explorer_core::models::QdrantWriteConsistency;
 use semantic_explorer_core::validation;
 use serde::{Deserialize, Serialize};
 
@@ -233,6 +235,10 @@ pub async fn delete_embedded_dataset(
             embedded_dataset.collection_name, e
         );
     }
+    // Only exists if the transform stored document vectors
+    let _ = qdrant_client
+        .delete_collection(document_collection_name(&embedded_dataset.collection_name))
+        .await;
 
     // Delete the database entry
     match embedded_datasets::delete_embedded_dataset(&pool, &user.as_owner(), embedded_dataset_id)
@@ -925,9 +931,11 @@ pub async fn push_vectors_to_embedded_dataset(
 
     // Upsert points to Qdrant
     if let Err(e) = qdrant_client
-        .upsert_points(
-            UpsertPointsBuilder::new(&embedded_dataset.collection_name, points).wait(true),
-        )
+        .upsert_points(write::upsert_request(
+            &embedded_dataset.collection_name,
+            points,
+            QdrantWriteConsistency::default(),
+        ))
         .await
     {
         error!("Failed to upsert points to Qdrant: {}", e);
@@ -1,12 +1,14 @@
 use crate::{
+    api::precondition,
     audit::{ResourceType, events},
     auth::AuthenticatedUser,
     embedders::models::{
         CreateEmbedder, Embedder, EmbedderListQuery, PaginatedEmbedderList, UpdateEmbedder,
     },
     errors::ApiError,
+    search::vector_size_of,
     storage::{
-        postgres::embedders,
+        postgres::{embedded_datasets, embedders},
         valkey::{self, ValkeyClients},
     },
 };
@@ -14,6 +16,7 @@ use actix_web::{
     HttpRequest, HttpResponse, Responder, ResponseError, delete, get, patch, post,
     web::{self, Data, Json, Path},
 };
+use qdrant_client::Qdrant;
 use semantic_explorer_core::validation;
 use semantic_explorer_core::{
     config::{EmbeddingInferenceConfig, ValkeyConfig},
@@ -21,13 +24,7 @@ use semantic_explorer_core::{
     http_client::HTTP_CLIENT,
 };
 use sqlx::{Pool, Postgres};
-
-#[derive(serde::Serialize, utoipa::ToSchema)]
-pub(crate) struct TestEmbedderResponse {
-    success: bool,
-    message: String,
-    dimensions: Option<usize>,
-}
+use std::time::Instant;
 
 #[utoipa::path(
     params(
@@ -208,6 +205,10 @@ pub(crate) async fn create_embedder(
         return ApiError::Validation(e).error_response();
     }
 
+    if let Err(e) = crate::embedders::validate_config(&payload.config) {
+        return ApiError::BadRequest(e).error_response();
+    }
+
     // Validate base_url is not empty for non-internal providers
     if payload.provider != "internal" && payload.base_url.trim().is_empty() {
         return ApiError::BadRequest("base_url cannot be empty for this provider".to_string())
@@ -237,21 +238,25 @@ pub(crate) async fn create_embedder(
     request_body = UpdateEmbedder,
     responses(
         (status = 200, description = "OK", body = Embedder),
+        (status = 400, description = "Bad Request"),
         (status = 404, description = "Not Found"),
+        (status = 409, description = "Conflict - embedder changed since the If-Match version"),
         (status = 500, description = "Internal Server Error"),
     ),
     params(
-        ("embedder_id" = i32, Path, description = "Embedder ID")
+        ("embedder_id" = i32, Path, description = "Embedder ID"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the embedder is still at this version (its ETag or updated_at)")
     ),
     tag = "Embedders",
 )]
 #[patch("/api/embedders/{embedder_id}")]
 #[tracing::instrument(
     name = "update_embedder",
-    skip(user, pool, update_embedder, encryption, valkey)
+    skip(user, req, pool, update_embedder, encryption, valkey)
 )]
 pub(crate) async fn update_embedder(
     user: AuthenticatedUser,
+    req: HttpRequest,
     pool: Data<Pool<Postgres>>,
     encryption: Data<EncryptionService>,
     embedder_id: Path<i32>,
@@ -264,16 +269,36 @@ pub(crate) async fn update_embedder(
         return ApiError::Validation(e).error_response();
     }
 
+    if let Some(ref config) = update_embedder.config
+        && let Err(e) = crate::embedders::validate_config(config)
+    {
+        return ApiError::BadRequest(e).error_response();
+    }
+
+    let expected_version = match precondition::expected_version(&req) {
+        Ok(version) => version,
+        Err(e) => return e.error_response(),
+    };
+
+    let pool = pool.into_inner();
     match embedders::update_embedder(
-        &pool.into_inner(),
+        &pool,
         &user,
         *embedder_id,
         &update_embedder,
+        expected_version,
         &encryption,
     )
     .await
     {
-        Ok(embedder) => {
+        Ok(None) => {
+            let current = embedders::get_embedder_version(&pool, &user, *embedder_id)
+                .await
+                .ok()
+                .flatten();
+            precondition::missed_update("Embedder", expected_version, current).error_response()
+        }
+        Ok(Some(embedder)) => {
             events::resource_updated(
                 &user.as_owner(),
                 &user,
@@ -304,7 +329,9 @@ pub(crate) async fn update_embedder(
             }
 
             valkey::invalidate_resource_cache(valkey.as_ref(), "embedders", &user.as_owner());
-            HttpResponse::Ok().json(embedder)
+            HttpResponse::Ok()
+                .insert_header(precondition::etag(&embedder.updated_at))
+                .json(embedder)
         }
         Err(e) => {
             tracing::error!(error = %e, embedder_id = %embedder_id, "failed to update embedder");
@@ -374,6 +401,296 @@ pub(crate) async fn delete_embedder(
     }
 }
 
+/// Texts sent by the embedder test: short and long, with punctuation and
+/// non-ASCII text, so tokenizer or length problems show up before a real run
+const PROBE_TEXTS: &[&str] = &[
+    "This is a test embedding request to verify the embedder is working correctly.",
+    "hello",
+    "Semantische Suche über Dokumente: Vektoren, Ähnlichkeit & Ranking!",
+    "Embedders map text to fixed-length vectors so that passages with similar meaning end up close together. \
+     This longer probe checks that inputs spanning several sentences are accepted and produce vectors of the \
+     same dimension as short ones.",
+];
+
+/// A Qdrant collection the embedder writes to whose vector size differs from
+/// the dimension the embedder returned
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub(crate) struct CollectionDimensionMismatch {
+    collection_name: String,
+    /// Embedded dataset writing to the collection, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    embedded_dataset_id: Option<i32>,
+    collection_dimensions: u64,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub(crate) struct TestEmbedderResponse {
+    success: bool,
+    message: String,
+    /// Dimension of the returned vectors
+    dimensions: Option<usize>,
+    /// Dimensions configured on the embedder
+    configured_dimensions: i32,
+    /// Whether every probe text, alone or in the batch, got a vector of the
+    /// same dimension
+    consistent_dimensions: bool,
+    /// Number of probe texts
+    probe_count: usize,
+    /// Latency of embedding each probe text on its own, in milliseconds
+    single_latencies_ms: Vec<u64>,
+    /// Mean of `single_latencies_ms`
+    mean_single_latency_ms: u64,
+    /// Whether embedding all probe texts in one call worked
+    batch_supported: bool,
+    /// Latency of the batch call, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    batch_latency_ms: Option<u64>,
+    /// Why the batch call failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    batch_error: Option<String>,
+    /// Collections this embedder writes to that expect another dimension
+    dimension_mismatches: Vec<CollectionDimensionMismatch>,
+    /// Problems found, e.g. inconsistent or mismatched dimensions
+    warnings: Vec<String>,
+}
+
+fn default_model(provider: &str) -> &'static str {
+    match provider {
+        "openai" => "text-embedding-ada-002",
+        "cohere" => "embed-english-v3.0",
+        _ => "BAAI/bge-small-en-v1.5",
+    }
+}
+
+/// Describe a failed request, with a hint for the usual cause
+fn probe_request_error(provider: &str, e: &reqwest::Error) -> String {
+    if e.is_timeout() {
+        if provider == "internal" {
+            "request timeout (embedding-inference-api may be loading models)".to_string()
+        } else {
+            "request timeout (check network/firewall)".to_string()
+        }
+    } else if e.is_connect() {
+        if provider == "internal" {
+            "failed to connect (check embedding-inference-api URL)".to_string()
+        } else {
+            "failed to connect (check base URL)".to_string()
+        }
+    } else {
+        format!("{}", e)
+    }
+}
+
+/// Vectors in a provider's embedding response, in input order
+fn parse_probe_embeddings(
+    provider: &str,
+    json: &serde_json::Value,
+) -> Result<Vec<Vec<f32>>, String> {
+    let embeddings = match provider {
+        "openai" => json["data"].as_array().map(|data| {
+            data.iter()
+                .map(|item| item["embedding"].clone())
+                .collect::<Vec<_>>()
+        }),
+        // Cohere nests vectors by type when `embedding_types` is set
+        "cohere" => json["embeddings"]["float"]
+            .as_array()
+            .or_else(|| json["embeddings"].as_array())
+            .cloned(),
+        _ => json["embeddings"].as_array().cloned(),
+    }
+    .ok_or_else(|| "unexpected response format (check model)".to_string())?;
+
+    embeddings
+        .into_iter()
+        .map(|embedding| {
+            serde_json::from_value(embedding)
+                .map_err(|e| format!("unexpected embedding format: {}", e))
+        })
+        .collect()
+}
+
+/// Embed `texts` in one call to the embedder
+async fn embed_probe(
+    embedder: &Embedder,
+    inference_url: &str,
+    texts: &[&str],
+) -> Result<Vec<Vec<f32>>, String> {
+    let provider = embedder.provider.as_str();
+    let model = embedder
+        .config
+        .get("model")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| default_model(provider));
+
+    let (url, body, needs_auth) = match provider {
+        "openai" => (
+            format!("{}/embeddings", embedder.base_url.trim_end_matches('/')),
+            serde_json::json!({
+                "input": texts,
+                "model": model,
+            }),
+            true,
+        ),
+        "cohere" => {
+            let base = embedder.base_url.trim_end_matches('/');
+            let url = if base.ends_with("/embed") {
+                base.to_string()
+            } else {
+                format!("{}/embed", base)
+            };
+            (
+                url,
+                serde_json::json!({
+                    "texts": texts,
+                    "model": model,
+                    "input_type": "search_document",
+                }),
+                true,
+            )
+        }
+        "internal" => (
+            format!("{}/api/embed/batch", inference_url.trim_end_matches('/')),
+            serde_json::json!({
+                "texts": texts,
+                "model": model,
+                "input_type": "document",
+            }),
+            false,
+        ),
+        _ => return Err(format!("unsupported provider: {}", provider)),
+    };
+
+    let mut req = HTTP_CLIENT.post(&url).json(&body);
+    if needs_auth && let Some(api_key) = &embedder.api_key {
+        req = req.bearer_auth(api_key);
+    }
+
+    let response = req
+        .send()
+        .await
+        .map_err(|e| probe_request_error(provider, &e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let hint = if provider == "internal" {
+            "check embedding-inference-api URL"
+        } else {
+            "check API key and base URL"
+        };
+        return Err(match response.text().await {
+            Ok(text) if !text.is_empty() => format!("HTTP {}: {}", status, text),
+            _ => format!("HTTP {}: {}", status, hint),
+        });
+    }
+
+    let json = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("failed to parse response: {}", e))?;
+    let embeddings = parse_probe_embeddings(provider, &json)?;
+    if embeddings.len() != texts.len() {
+        return Err(format!(
+            "received {} embeddings for {} texts",
+            embeddings.len(),
+            texts.len()
+        ));
+    }
+    Ok(embeddings)
+}
+
+/// Output dimensions of `embedder`: the measured ones stored on it, or else
+/// measured now with a probe call and stored for next time
+pub(crate) async fn measured_dimensions(
+    pool: &Pool<Postgres>,
+    embedder: &Embedder,
+    inference_url: &str,
+) -> Result<u64, String> {
+    if let Some(dimensions) = embedder.verified_dimensions() {
+        return Ok(dimensions);
+    }
+    let embeddings = embed_probe(embedder, inference_url, &PROBE_TEXTS[..1]).await?;
+    let dimensions = consistent_dimension(&embeddings)
+        .ok_or_else(|| "embedder returned an empty vector".to_string())?;
+    store_measured_dimensions(pool, embedder, dimensions).await;
+    Ok(dimensions as u64)
+}
+
+/// Store measured dimensions on the embedder. Failing to is only logged; the
+/// dimensions are measured again next time.
+async fn store_measured_dimensions(pool: &Pool<Postgres>, embedder: &Embedder, dimensions: usize) {
+    let Ok(dimensions) = i32::try_from(dimensions) else {
+        return;
+    };
+    if let Err(e) = embedders::set_verified_dimensions(pool, embedder.embedder_id, dimensions).await
+    {
+        tracing::warn!(
+            embedder_id = embedder.embedder_id,
+            error = %e,
+            "failed to store measured embedder dimensions"
+        );
+    }
+}
+
+/// Dimension shared by all vectors, or `None` when they differ or are empty
+fn consistent_dimension<'a>(vectors: impl IntoIterator<Item = &'a Vec<f32>>) -> Option<usize> {
+    let mut dimensions = vectors.into_iter().map(Vec::len);
+    let first = dimensions.next()?;
+    (first > 0 && dimensions.all(|d| d == first)).then_some(first)
+}
+
+/// Collections of the embedder and of the user's embedded datasets using it
+/// whose vector size differs from `dimensions`. Collections that don't exist
+/// yet are skipped.
+async fn find_dimension_mismatches(
+    pool: &Pool<Postgres>,
+    qdrant_client: &Qdrant,
+    user: &AuthenticatedUser,
+    embedder: &Embedder,
+    dimensions: usize,
+) -> Vec<CollectionDimensionMismatch> {
+    let mut targets: Vec<(String, Option<i32>)> = embedder
+        .collection_name
+        .iter()
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| (name.clone(), None))
+        .collect();
+    match embedded_datasets::get_embedded_datasets_for_embedder(pool, user, embedder.embedder_id)
+        .await
+    {
+        Ok(datasets) => targets.extend(
+            datasets
+                .into_iter()
+                .map(|ed| (ed.collection_name, Some(ed.embedded_dataset_id))),
+        ),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to list embedded datasets for embedder test");
+        }
+    }
+
+    let mut mismatches = Vec::new();
+    for (collection_name, embedded_dataset_id) in targets {
+        let Ok(info) = qdrant_client.collection_info(&collection_name).await else {
+            continue;
+        };
+        if let Some(collection_dimensions) = vector_size_of(&info)
+            && collection_dimensions != dimensions as u64
+        {
+            mismatches.push(CollectionDimensionMismatch {
+                collection_name,
+                embedded_dataset_id,
+                collection_dimensions,
+            });
+        }
+    }
+    mismatches
+}
+
+fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+/// Embeds a fixed set of probe texts one at a time and then in one batch,
+/// and reports the vector dimension, latencies and any dimension problems.
 #[utoipa::path(
     post,
     path = "/api/embedders/{embedder_id}/test",
@@ -382,232 +699,134 @@ pub(crate) async fn delete_embedder(
         ("embedder_id" = i32, Path, description = "Embedder ID")
     ),
     responses(
-        (status = 200, description = "Test successful", body = TestEmbedderResponse),
+        (status = 200, description = "Embedder responded; see the diagnostics for problems", body = TestEmbedderResponse),
         (status = 404, description = "Embedder not found"),
         (status = 500, description = "Test failed"),
     ),
 )]
 #[post("/api/embedders/{embedder_id}/test")]
-#[tracing::instrument(name = "test_embedder", skip(user, pool, encryption, inference_config), fields(embedder_id = %path.as_ref()))]
+#[tracing::instrument(name = "test_embedder", skip(user, pool, encryption, inference_config, qdrant_client), fields(embedder_id = %path.as_ref()))]
 pub(crate) async fn test_embedder(
     user: AuthenticatedUser,
     pool: Data<Pool<Postgres>>,
     encryption: Data<EncryptionService>,
     inference_config: Data<EmbeddingInferenceConfig>,
+    qdrant_client: Data<Qdrant>,
     path: Path<i32>,
 ) -> impl Responder {
     let embedder_id = path.into_inner();
 
     // Fetch the embedder (api_key is decrypted by storage layer)
-    let embedder =
-        match embedders::get_embedder(&pool.into_inner(), &user, embedder_id, &encryption).await {
-            Ok(e) => e,
-            Err(e) => {
-                return ApiError::NotFound(format!("embedder not found: {}", e)).error_response();
-            }
-        };
-
-    let test_text = "This is a test embedding request to verify the embedder is working correctly.";
-
-    let result = match embedder.provider.as_str() {
-        "openai" => {
-            let model = embedder
-                .config
-                .get("model")
-                .and_then(|v| v.as_str())
-                .unwrap_or("text-embedding-ada-002");
-
-            let body = serde_json::json!({
-                "input": test_text,
-                "model": model,
-            });
-
-            let mut req = HTTP_CLIENT
-                .post(format!(
-                    "{}/embeddings",
-                    embedder.base_url.trim_end_matches('/')
-                ))
-                .json(&body);
+    let embedder = match embedders::get_embedder(&pool, &user, embedder_id, &encryption).await {
+        Ok(e) => e,
+        Err(e) => {
+            return ApiError::NotFound(format!("embedder not found: {}", e)).error_response();
+        }
+    };
 
-            if let Some(api_key) = &embedder.api_key {
-                req = req.bearer_auth(api_key);
+    let mut vectors = Vec::with_capacity(PROBE_TEXTS.len() * 2);
+    let mut single_latencies_ms = Vec::with_capacity(PROBE_TEXTS.len());
+    for (index, text) in PROBE_TEXTS.iter().enumerate() {
+        let start = Instant::now();
+        match embed_probe(&embedder, &inference_config.url, &[*text]).await {
+            Ok(embeddings) => {
+                single_latencies_ms.push(elapsed_ms(start));
+                vectors.extend(embeddings);
             }
-
-            match req.send().await {
-                Ok(response) => {
-                    if !response.status().is_success() {
-                        Err(format!(
-                            "HTTP {}: check API key and base URL",
-                            response.status()
-                        ))
-                    } else {
-                        match response.json::<serde_json::Value>().await {
-                            Ok(json) => {
-                                if let Some(dims) = json["data"][0]["embedding"].as_array() {
-                                    Ok(dims.len())
-                                } else {
-                                    Err("unexpected response format".to_string())
-                                }
-                            }
-                            Err(e) => Err(format!("failed to parse response: {}", e)),
-                        }
-                    }
-                }
-                Err(e) => {
-                    let error_msg = if e.is_timeout() {
-                        "request timeout (check network/firewall)".to_string()
-                    } else if e.is_connect() {
-                        "failed to connect (check base URL)".to_string()
-                    } else {
-                        format!("{}", e)
-                    };
-                    Err(error_msg)
-                }
+            Err(error) => {
+                tracing::warn!(
+                    embedder_id = embedder_id,
+                    probe = index,
+                    error = %error,
+                    "embedder test failed"
+                );
+                return ApiError::Internal(format!("embedder test failed: {}", error))
+                    .error_response();
             }
         }
-        "cohere" => {
-            let model = embedder
-                .config
-                .get("model")
-                .and_then(|v| v.as_str())
-                .unwrap_or("embed-english-v3.0");
-
-            let body = serde_json::json!({
-                "texts": [test_text],
-                "model": model,
-                "input_type": "search_document",
-            });
-
-            let base = embedder.base_url.trim_end_matches('/');
-            let url = if base.ends_with("/embed") {
-                base.to_string()
-            } else {
-                format!("{}/embed", base)
-            };
-
-            let mut req = HTTP_CLIENT.post(&url).json(&body);
+    }
 
-            if let Some(api_key) = &embedder.api_key {
-                req = req.bearer_auth(api_key);
+    let start = Instant::now();
+    let (batch_latency_ms, batch_error) =
+        match embed_probe(&embedder, &inference_config.url, PROBE_TEXTS).await {
+            Ok(embeddings) => {
+                let latency = elapsed_ms(start);
+                vectors.extend(embeddings);
+                (Some(latency), None)
             }
+            Err(error) => (None, Some(error)),
+        };
 
-            match req.send().await {
-                Ok(response) => {
-                    if !response.status().is_success() {
-                        let status = response.status();
-                        match response.text().await {
-                            Ok(text) => Err(format!("HTTP {}: {}", status, text)),
-                            Err(_) => Err(format!("HTTP {}: check API key and base URL", status)),
-                        }
-                    } else {
-                        match response.json::<serde_json::Value>().await {
-                            Ok(json) => {
-                                if let Some(dims) = json["embeddings"][0].as_array() {
-                                    Ok(dims.len())
-                                } else {
-                                    Err("unexpected response format (check model)".to_string())
-                                }
-                            }
-                            Err(e) => Err(format!("failed to parse response: {}", e)),
-                        }
-                    }
-                }
-                Err(e) => {
-                    let error_msg = if e.is_timeout() {
-                        "request timeout (check network/firewall)".to_string()
-                    } else if e.is_connect() {
-                        "failed to connect (check base URL)".to_string()
-                    } else {
-                        format!("{}", e)
-                    };
-                    Err(error_msg)
-                }
-            }
-        }
-        "internal" => {
-            // Test internal embedding-inference-api service using configured URL
-            let model = embedder
-                .config
-                .get("model")
-                .and_then(|v| v.as_str())
-                .unwrap_or("BAAI/bge-small-en-v1.5");
-
-            let body = serde_json::json!({
-                "text": test_text,
-                "model": model,
-            });
+    let mut warnings = Vec::new();
+    let dimensions = consistent_dimension(&vectors);
+    if let Some(dims) = dimensions
+        && embedder.verified_dimensions() != Some(dims as u64)
+    {
+        store_measured_dimensions(&pool, &embedder, dims).await;
+    }
+    if dimensions.is_none() {
+        let mut seen: Vec<usize> = vectors.iter().map(Vec::len).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        warnings.push(format!(
+            "embedder returned vectors of differing dimensions: {:?}",
+            seen
+        ));
+    }
+    if let Some(error) = &batch_error {
+        warnings.push(format!("batch embedding failed: {}", error));
+    }
 
-            let url = format!("{}/api/embed", inference_config.url.trim_end_matches('/'));
-            let req = HTTP_CLIENT.post(&url).json(&body);
-
-            match req.send().await {
-                Ok(response) => {
-                    if !response.status().is_success() {
-                        let status = response.status();
-                        match response.text().await {
-                            Ok(text) => Err(format!("HTTP {}: {}", status, text)),
-                            Err(_) => Err(format!(
-                                "HTTP {}: check embedding-inference-api URL",
-                                status
-                            )),
-                        }
-                    } else {
-                        match response.json::<serde_json::Value>().await {
-                            Ok(json) => {
-                                if let Some(dims) = json["dimensions"].as_u64() {
-                                    Ok(dims as usize)
-                                } else if let Some(embeddings) = json["embeddings"].as_array() {
-                                    if let Some(first) = embeddings.first() {
-                                        if let Some(arr) = first.as_array() {
-                                            Ok(arr.len())
-                                        } else {
-                                            Err("unexpected embedding format".to_string())
-                                        }
-                                    } else {
-                                        Err("empty embeddings array".to_string())
-                                    }
-                                } else {
-                                    Err("unexpected response format".to_string())
-                                }
-                            }
-                            Err(e) => Err(format!("failed to parse response: {}", e)),
-                        }
-                    }
-                }
-                Err(e) => {
-                    let error_msg = if e.is_timeout() {
-                        "request timeout (embedding-inference-api may be loading models)"
-                            .to_string()
-                    } else if e.is_connect() {
-                        "failed to connect (check embedding-inference-api URL)".to_string()
-                    } else {
-                        format!("{}", e)
-                    };
-                    Err(error_msg)
-                }
-            }
+    // Report the first vector's dimension even when later ones differ
+    let reported_dimensions = dimensions.or_else(|| vectors.first().map(Vec::len));
+    let mut dimension_mismatches = Vec::new();
+    if let Some(dims) = reported_dimensions {
+        if dims as i64 != i64::from(embedder.dimensions) {
+            warnings.push(format!(
+                "embedder is configured for {} dimensions but returned {}",
+                embedder.dimensions, dims
+            ));
         }
-        _ => Err(format!("unsupported provider: {}", embedder.provider)),
-    };
-
-    match result {
-        Ok(dimensions) => HttpResponse::Ok().json(TestEmbedderResponse {
-            success: true,
-            message: format!(
-                "embedder test successful - received {} dimensional embeddings",
-                dimensions
-            ),
-            dimensions: Some(dimensions),
-        }),
-        Err(error) => {
-            tracing::warn!(
-                embedder_id = embedder_id,
-                error = %error,
-                "embedder test failed"
-            );
-            ApiError::Internal(format!("embedder test failed: {}", error)).error_response()
+        dimension_mismatches =
+            find_dimension_mismatches(&pool, &qdrant_client, &user, &embedder, dims).await;
+        for mismatch in &dimension_mismatches {
+            warnings.push(format!(
+                "collection '{}' expects {} dimensions but the embedder returned {}",
+                mismatch.collection_name, mismatch.collection_dimensions, dims
+            ));
         }
     }
+
+    let mean_single_latency_ms =
+        single_latencies_ms.iter().sum::<u64>() / single_latencies_ms.len().max(1) as u64;
+    let message = match reported_dimensions {
+        Some(dims) if warnings.is_empty() => format!(
+            "embedder test successful - received {} dimensional embeddings",
+            dims
+        ),
+        Some(dims) => format!(
+            "embedder returned {} dimensional embeddings with {} warning(s)",
+            dims,
+            warnings.len()
+        ),
+        None => "embedder returned no embeddings".to_string(),
+    };
+
+    HttpResponse::Ok().json(TestEmbedderResponse {
+        success: warnings.is_empty(),
+        message,
+        dimensions: reported_dimensions,
+        configured_dimensions: embedder.dimensions,
+        consistent_dimensions: dimensions.is_some(),
+        probe_count: PROBE_TEXTS.len(),
+        single_latencies_ms,
+        mean_single_latency_ms,
+        batch_supported: batch_error.is_none(),
+        batch_latency_ms,
+        batch_error,
+        dimension_mismatches,
+        warnings,
+    })
 }
 
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
@@ -624,3 +843,55 @@ pub(crate) struct ModelInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dimensions: Option<usize>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_embeddings_per_provider() {
+        let openai = serde_json::json!({
+            "data": [{"embedding": [0.1, 0.2]}, {"embedding": [0.3, 0.4]}]
+        });
+        assert_eq!(
+            parse_probe_embeddings("openai", &openai).unwrap(),
+            vec![vec![0.1, 0.2], vec![0.3, 0.4]]
+        );
+
+        let cohere_typed = serde_json::json!({"embeddings": {"float": [[0.5, 0.6]]}});
+        let cohere_plain = serde_json::json!({"embeddings": [[0.5, 0.6]]});
+        assert_eq!(
+            parse_probe_embeddings("cohere", &cohere_typed).unwrap(),
+            parse_probe_embeddings("cohere", &cohere_plain).unwrap()
+        );
+
+        let internal = serde_json::json!({"embeddings": [[1.0, 0.0, 0.0]], "dimensions": 3});
+        assert_eq!(
+            parse_probe_embeddings("internal", &internal).unwrap(),
+            vec![vec![1.0, 0.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn test_parse_probe_embeddings_rejects_unexpected_responses() {
+        assert!(parse_probe_embeddings("openai", &serde_json::json!({"error": "nope"})).is_err());
+        assert!(
+            parse_probe_embeddings("internal", &serde_json::json!({"embeddings": ["x"]})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_consistent_dimension() {
+        assert_eq!(
+            consistent_dimension(&vec![vec![0.0; 3], vec![1.0; 3]]),
+            Some(3)
+        );
+        assert_eq!(
+            consistent_dimension(&vec![vec![0.0; 3], vec![1.0; 4]]),
+            None
+        );
+        // An empty vector is never a usable dimension
+        assert_eq!(consistent_dimension(&vec![Vec::new()]), None);
+        assert_eq!(consistent_dimension(&Vec::<Vec<f32>>::new()), None);
+    }
+}
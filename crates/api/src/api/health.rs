@@ -61,6 +61,10 @@ impl HealthResponse {
     }
 }
 
+/// Liveness probe for load balancers and kubelets. It takes no app data and
+/// checks no backends, so it stays cheap and keeps answering while Postgres or
+/// Qdrant are down; `/health/ready` is the probe that reports those. It is
+/// unauthenticated and excluded from metrics.
 #[get("/health/live")]
 pub async fn liveness() -> impl Responder {
     HttpResponse::Ok().body("ok")
@@ -185,3 +189,28 @@ async fn check_nats(client: &async_nats::Client) -> ComponentHealth {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, test};
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn test_liveness_needs_no_app_data_and_answers_immediately() {
+        // No pools or clients registered: any extractor would fail the request
+        let app = test::init_service(App::new().service(liveness)).await;
+
+        let started = Instant::now();
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/health/live").to_request(),
+        )
+        .await;
+        let elapsed = started.elapsed();
+
+        assert!(resp.status().is_success());
+        assert_eq!(test::read_body(resp).await, "ok");
+        assert!(elapsed < Duration::from_millis(50), "took {elapsed:?}");
+    }
+}
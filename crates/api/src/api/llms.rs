@@ -8,6 +8,7 @@ use semantic_explorer_core::validation;
 use sqlx::{Pool, Postgres};
 
 use crate::{
+    api::precondition,
     audit::{ResourceType, events},
     auth::AuthenticatedUser,
     errors::ApiError,
@@ -222,18 +223,25 @@ pub(crate) async fn create_llm(
     request_body = UpdateLargeLanguageModel,
     responses(
         (status = 200, description = "OK", body = LargeLanguageModel),
+        (status = 400, description = "Bad Request"),
         (status = 404, description = "Not Found"),
+        (status = 409, description = "Conflict - LLM changed since the If-Match version"),
         (status = 500, description = "Internal Server Error"),
     ),
     params(
-        ("llm_id" = i32, Path, description = "LLM ID")
+        ("llm_id" = i32, Path, description = "LLM ID"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the LLM is still at this version (its ETag or updated_at)")
     ),
     tag = "LLMs",
 )]
 #[patch("/api/llms/{llm_id}")]
-#[tracing::instrument(name = "update_llm", skip(user, pool, update_llm, encryption, valkey))]
+#[tracing::instrument(
+    name = "update_llm",
+    skip(user, req, pool, update_llm, encryption, valkey)
+)]
 pub(crate) async fn update_llm(
     user: AuthenticatedUser,
+    req: HttpRequest,
     pool: Data<Pool<Postgres>>,
     encryption: Data<EncryptionService>,
     llm_id: Path<i32>,
@@ -246,8 +254,30 @@ pub(crate) async fn update_llm(
         return ApiError::Validation(e).error_response();
     }
 
-    match llms::update_llm(&pool.into_inner(), &user, *llm_id, &update_llm, &encryption).await {
-        Ok(llm) => {
+    let expected_version = match precondition::expected_version(&req) {
+        Ok(version) => version,
+        Err(e) => return e.error_response(),
+    };
+
+    let pool = pool.into_inner();
+    match llms::update_llm(
+        &pool,
+        &user,
+        *llm_id,
+        &update_llm,
+        expected_version,
+        &encryption,
+    )
+    .await
+    {
+        Ok(None) => {
+            let current = llms::get_llm_version(&pool, &user, *llm_id)
+                .await
+                .ok()
+                .flatten();
+            precondition::missed_update("LLM", expected_version, current).error_response()
+        }
+        Ok(Some(llm)) => {
             events::resource_updated(
                 &user.as_owner(),
                 &user,
@@ -278,7 +308,9 @@ pub(crate) async fn update_llm(
             }
 
             valkey::invalidate_resource_cache(valkey.as_ref(), "llms", &user.as_owner());
-            HttpResponse::Ok().json(llm)
+            HttpResponse::Ok()
+                .insert_header(precondition::etag(&llm.updated_at))
+                .json(llm)
         }
         Err(e) => {
             tracing::error!(error = %e, llm_id = %llm_id, "failed to update LLM");
@@ -1,8 +1,10 @@
+pub(crate) mod audit;
 pub(crate) mod chat;
 pub(crate) mod collection_transforms;
 pub(crate) mod collections;
 pub(crate) mod dataset_transforms;
 pub(crate) mod datasets;
+pub(crate) mod dlq;
 pub(crate) mod embedded_datasets;
 pub(crate) mod embedders;
 pub(crate) mod embedding_inference;
@@ -10,6 +12,8 @@ pub(crate) mod health;
 pub(crate) mod llm_inference;
 pub(crate) mod llms;
 pub(crate) mod marketplace;
+pub(crate) mod precondition;
+pub(crate) mod provider_configs;
 pub(crate) mod search;
 pub(crate) mod status;
 pub(crate) mod visualization_transforms;
@@ -0,0 +1,143 @@
+//! Optimistic concurrency for PATCH endpoints.
+//!
+//! A resource's version is its `updated_at`. Clients send the version they
+//! last read in `If-Match` (as the quoted RFC 3339 timestamp returned in the
+//! `ETag` header, or the bare `updated_at` value); the update only applies
+//! while the row still has that version, so a concurrent writer's change is
+//! rejected with 409 instead of being silently overwritten. Requests without
+//! `If-Match` (or with `If-Match: *`) update unconditionally.
+
+use actix_web::HttpRequest;
+use actix_web::http::header::{ETAG, HeaderName, IF_MATCH};
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::errors::ApiError;
+
+/// Version the client expects to update, from `If-Match`
+pub(crate) fn expected_version(req: &HttpRequest) -> Result<Option<DateTime<Utc>>, ApiError> {
+    let Some(value) = req.headers().get(IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| ApiError::BadRequest("If-Match header is not valid text".to_string()))?;
+    parse_version(value)
+}
+
+fn parse_version(value: &str) -> Result<Option<DateTime<Utc>>, ApiError> {
+    let value = value.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    let tag = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+    DateTime::parse_from_rfc3339(tag)
+        .map(|version| Some(version.with_timezone(&Utc)))
+        .map_err(|_| {
+            ApiError::BadRequest(format!(
+                "If-Match must be the resource's ETag or updated_at timestamp, got '{value}'"
+            ))
+        })
+}
+
+/// `ETag` header for a resource at the given version
+pub(crate) fn etag(updated_at: &DateTime<Utc>) -> (HeaderName, String) {
+    (
+        ETAG,
+        format!(
+            "\"{}\"",
+            updated_at.to_rfc3339_opts(SecondsFormat::Micros, true)
+        ),
+    )
+}
+
+/// Error for a conditional update that matched no row: 409 when the resource
+/// still exists at another version, 404 when it doesn't exist (for this owner)
+pub(crate) fn missed_update(
+    resource: &str,
+    expected: Option<DateTime<Utc>>,
+    current: Option<DateTime<Utc>>,
+) -> ApiError {
+    match (expected, current) {
+        (Some(expected), Some(current)) if expected != current => ApiError::Conflict(format!(
+            "{resource} was modified at {} since version {}; reload it and retry",
+            current.to_rfc3339_opts(SecondsFormat::Micros, true),
+            expected.to_rfc3339_opts(SecondsFormat::Micros, true),
+        )),
+        _ => ApiError::NotFound(format!("{resource} not found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    fn at(micros: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_micros(1_760_000_000_000_000 + micros).unwrap()
+    }
+
+    #[test]
+    fn test_etag_round_trips_through_if_match() {
+        let (_, tag) = etag(&at(1));
+        let req = TestRequest::default()
+            .insert_header((IF_MATCH, tag))
+            .to_http_request();
+        assert_eq!(expected_version(&req).unwrap(), Some(at(1)));
+
+        // The bare updated_at from a JSON body works too
+        let req = TestRequest::default()
+            .insert_header((
+                IF_MATCH,
+                serde_json::to_value(at(2)).unwrap().as_str().unwrap(),
+            ))
+            .to_http_request();
+        assert_eq!(expected_version(&req).unwrap(), Some(at(2)));
+
+        // Without If-Match the update is unconditional
+        assert_eq!(
+            expected_version(&TestRequest::default().to_http_request()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("*").unwrap(), None);
+        assert_eq!(
+            parse_version("W/\"2025-10-09T08:53:20.000001Z\"").unwrap(),
+            Some(at(1))
+        );
+        assert_eq!(
+            parse_version("2025-10-09T10:53:20.000001+02:00").unwrap(),
+            Some(at(1))
+        );
+        assert!(matches!(
+            parse_version("\"v2\""),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_stale_if_match_is_rejected_with_conflict() {
+        let (_, tag) = etag(&at(1));
+        let req = TestRequest::default()
+            .insert_header((IF_MATCH, tag))
+            .to_http_request();
+        let expected = expected_version(&req).unwrap();
+
+        let err = missed_update("LLM", expected, Some(at(2)));
+        assert_eq!(err.status_code(), StatusCode::CONFLICT);
+        assert!(err.to_string().contains("reload it and retry"), "{err}");
+
+        assert_eq!(
+            missed_update("LLM", None, None).status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            missed_update("LLM", Some(at(1)), None).status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, ResponseError, get, post,
+    web::{self, Data, Json},
+};
+use semantic_explorer_core::encryption::EncryptionService;
+use sqlx::types::chrono::Utc;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    audit::{ResourceType, events},
+    auth::AuthenticatedUser,
+    errors::ApiError,
+    provider_configs::{
+        self,
+        models::{
+            CONFIG_BUNDLE_VERSION, ConfigBundle, ExportConfigsQuery, ImportConfigsQuery,
+            ImportConfigsResponse, ImportOutcome,
+        },
+    },
+    storage::{
+        postgres::provider_configs as storage,
+        valkey::{self, ValkeyClients},
+    },
+};
+
+#[utoipa::path(
+    params(
+        ExportConfigsQuery
+    ),
+    responses(
+        (status = 200, description = "OK", body = ConfigBundle),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    tag = "Provider Configs",
+)]
+#[get("/api/provider-configs/export")]
+#[tracing::instrument(name = "export_provider_configs", skip(user, pool, encryption))]
+pub(crate) async fn export_provider_configs(
+    user: AuthenticatedUser,
+    pool: Data<Pool<Postgres>>,
+    encryption: Data<EncryptionService>,
+    query: web::Query<ExportConfigsQuery>,
+) -> impl Responder {
+    let pool = pool.into_inner();
+
+    let (embedders, llms) = match tokio::try_join!(
+        storage::export_embedders(&pool, &user),
+        storage::export_llms(&pool, &user)
+    ) {
+        Ok(configs) => configs,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to fetch provider configs for export");
+            return ApiError::Internal(format!("error exporting configs: {:?}", e))
+                .error_response();
+        }
+    };
+
+    let mut bundle = ConfigBundle {
+        version: CONFIG_BUNDLE_VERSION,
+        exported_at: Utc::now(),
+        secrets: query.secrets,
+        embedders,
+        llms,
+    };
+
+    if let Err(e) = provider_configs::prepare_export(&mut bundle, &encryption) {
+        tracing::error!(error = %e, "failed to prepare API keys for export");
+        return ApiError::Internal(format!("error exporting configs: {:?}", e)).error_response();
+    }
+
+    events::resource_read(&user.as_owner(), &user, ResourceType::Embedder, "export");
+    events::resource_read(&user.as_owner(), &user, ResourceType::LlmProvider, "export");
+
+    HttpResponse::Ok().json(bundle)
+}
+
+#[utoipa::path(
+    params(
+        ImportConfigsQuery
+    ),
+    request_body = ConfigBundle,
+    responses(
+        (status = 200, description = "OK", body = ImportConfigsResponse),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    tag = "Provider Configs",
+)]
+#[post("/api/provider-configs/import")]
+#[tracing::instrument(
+    name = "import_provider_configs",
+    skip(user, req, pool, encryption, bundle, valkey)
+)]
+pub(crate) async fn import_provider_configs(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    pool: Data<Pool<Postgres>>,
+    encryption: Data<EncryptionService>,
+    query: web::Query<ImportConfigsQuery>,
+    bundle: Json<ConfigBundle>,
+    valkey: Option<Data<ValkeyClients>>,
+) -> impl Responder {
+    let mut bundle = bundle.into_inner();
+
+    if let Err(errors) = provider_configs::validate_bundle(&bundle)
+        .and_then(|_| provider_configs::seal_import_secrets(&mut bundle, &encryption))
+    {
+        return ApiError::BadRequest(errors.join("; ")).error_response();
+    }
+
+    // Same placeholder the create endpoints store when no key is given
+    let default_api_key = match encryption.encrypt("dummy") {
+        Ok(key) => key,
+        Err(e) => {
+            return ApiError::Internal(format!("error importing configs: {:?}", e))
+                .error_response();
+        }
+    };
+
+    let response = match storage::import_configs(
+        &pool.into_inner(),
+        &user,
+        &bundle.embedders,
+        &bundle.llms,
+        query.on_conflict,
+        &default_api_key,
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to import provider configs");
+            return ApiError::Internal(format!("error importing configs: {:?}", e))
+                .error_response();
+        }
+    };
+
+    let results = response
+        .embedders
+        .iter()
+        .map(|r| (ResourceType::Embedder, r))
+        .chain(response.llms.iter().map(|r| (ResourceType::LlmProvider, r)));
+    for (resource_type, result) in results {
+        let id = result.id.to_string();
+        match result.outcome {
+            ImportOutcome::Created => events::resource_created_with_request(
+                &req,
+                &user.as_owner(),
+                &user,
+                resource_type,
+                &id,
+            ),
+            ImportOutcome::Overwritten => {
+                events::resource_updated(&user.as_owner(), &user, resource_type, &id)
+            }
+            ImportOutcome::Skipped => {}
+        }
+    }
+
+    if response.created + response.overwritten > 0 {
+        valkey::invalidate_resource_cache(valkey.as_ref(), "embedders", &user.as_owner());
+        valkey::invalidate_resource_cache(valkey.as_ref(), "llms", &user.as_owner());
+    }
+
+    HttpResponse::Ok().json(response)
+}
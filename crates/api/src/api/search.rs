@@ -1,10 +1,13 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use actix_web::{
     HttpRequest, HttpResponse, Responder, ResponseError, post,
     web::{Data, Json},
 };
-use futures_util::future;
+use futures_util::{StreamExt, TryStreamExt, future, stream};
 
 use qdrant_client::Qdrant;
 use sqlx::{Pool, Postgres};
@@ -14,14 +17,19 @@ use crate::{
     auth::AuthenticatedUser,
     errors::ApiError,
     search::{
-        aggregate_matches_to_documents,
-        models::{EmbeddedDatasetSearchResults, SearchMode, SearchRequest, SearchResponse},
-        search_collection,
+        aggregate_matches_to_documents, collection_search_info, embedding_version_warnings,
+        explain::{self, ExplainContext},
+        models::{
+            BatchQueryResult, BatchSearchRequest, BatchSearchResponse, DocumentResult,
+            EmbeddedDatasetSearchResults, RetrievalMode, SearchMode, SearchRequest, SearchResponse,
+        },
+        rerank, resolve_metric_override, search_collection, search_collection_batch,
     },
-    storage::postgres::{embedded_datasets, embedders},
+    storage::postgres::{datasets, embedded_datasets, embedders},
 };
 use semantic_explorer_core::circuit_breaker::CircuitBreakers;
 use semantic_explorer_core::config::{EmbeddingInferenceConfig, WorkerConfig};
+use semantic_explorer_core::embedding_stamp::EmbeddingStamp;
 use semantic_explorer_core::encryption::EncryptionService;
 
 #[utoipa::path(
@@ -75,6 +83,15 @@ pub(crate) async fn search(
         return ApiError::BadRequest("Query cannot be empty".to_string()).error_response();
     }
 
+    if search_request.distance_metric.is_some()
+        && search_request.retrieval_mode == RetrievalMode::Sparse
+    {
+        return ApiError::BadRequest(
+            "distance_metric only applies to dense or hybrid retrieval".to_string(),
+        )
+        .error_response();
+    }
+
     let embedded_dataset_ids: Vec<String> = search_request
         .embedded_dataset_ids
         .iter()
@@ -122,11 +139,30 @@ pub(crate) async fn search(
             }
         };
 
+    // Reranking over-fetches candidates so the reranker has more to choose from
+    let rerank_model = search_request.rerank.then(|| {
+        search_request
+            .rerank_model
+            .clone()
+            .filter(|model| !model.trim().is_empty())
+            .unwrap_or_else(|| worker_config.search_rerank_model.clone())
+    });
+    let candidate_limit = if rerank_model.is_some() {
+        search_request.limit * worker_config.search_rerank_overfetch
+    } else {
+        search_request.limit
+    };
+    // Slowest collection query, since collections are searched in parallel
+    let qdrant_duration_micros = Arc::new(AtomicU64::new(0));
+
     // Process searches in parallel using futures::future::join_all
     let search_tasks: Vec<_> = search_request
         .embedded_dataset_ids
         .iter()
         .map(|embedded_dataset_id| {
+            let rerank_model = rerank_model.clone();
+            let pool = pool.clone();
+            let qdrant_duration_micros = qdrant_duration_micros.clone();
             let qdrant_client = qdrant_client.clone();
             let search_request = search_request.clone();
             let worker_config = worker_config.clone();
@@ -153,6 +189,7 @@ pub(crate) async fn search(
                             collection_name: String::new(),
                             matches: Vec::new(),
                             documents: None,
+                            warnings: Vec::new(),
                             error: Some("Embedded dataset not found or not accessible".to_string()),
                         };
                     }
@@ -170,6 +207,7 @@ pub(crate) async fn search(
                         collection_name: ed_details.collection_name,
                         matches: Vec::new(),
                         documents: None,
+                        warnings: Vec::new(),
                         error: Some("This embedded dataset does not support search (no embedder configured). Standalone datasets can only be used in visualizations.".to_string()),
                     };
                 }
@@ -188,6 +226,7 @@ pub(crate) async fn search(
                             collection_name: ed_details.collection_name,
                             matches: Vec::new(),
                             documents: None,
+                            warnings: Vec::new(),
                             error: Some("Embedder not found or not accessible".to_string()),
                         };
                     }
@@ -205,55 +244,103 @@ pub(crate) async fn search(
                     "Generating query embedding for search"
                 );
 
-                // Generate embedding for the query
-                let query_vector = match crate::embedding::generate_embedding(
-                    &embedder.provider,
-                    &embedder.base_url,
-                    embedder.api_key.as_deref(),
-                    &embedder.config,
-                    &search_request.query,
-                    Some(&inference_config.url),
-                )
-                .await
+                // Keyword and hybrid retrieval need the collection's BM25 vector;
+                // collections created before it was added fall back to dense
+                let collection_info =
+                    collection_search_info(&qdrant_client, &ed_details.collection_name).await;
+                let mut warnings = Vec::new();
+                let mut retrieval_mode = search_request.retrieval_mode;
+                if retrieval_mode != RetrievalMode::Dense && !collection_info.has_sparse {
+                    warnings.push(format!(
+                        "Collection '{}' has no keyword index, so {} retrieval fell back to dense. Re-create the embedded dataset to enable it.",
+                        ed_details.collection_name,
+                        retrieval_mode.as_str()
+                    ));
+                    retrieval_mode = RetrievalMode::Dense;
+                }
+
+                if let Err(e) =
+                    resolve_metric_override(collection_info.metric, search_request.distance_metric)
                 {
-                    Ok(v) => {
-                        tracing::info!(
-                            embedded_dataset_id = embedded_dataset_id,
-                            embedder_name = %embedder.name,
-                            vector_dimensions = v.len(),
-                            "Generated query embedding"
-                        );
-                        v
-                    },
-                    Err(e) => {
-                        tracing::error!(
-                            "Failed to generate embedding for embedded dataset {}: {}",
-                            embedded_dataset_id,
-                            e
-                        );
-                        return EmbeddedDatasetSearchResults {
-                            embedded_dataset_id,
-                            embedded_dataset_title: ed_details.title,
-                            source_dataset_id: ed_details.source_dataset_id,
-                            source_dataset_title: ed_details.source_dataset_title,
-                            embedder_id: ed_details.embedder_id,
-                            embedder_name: ed_details.embedder_name,
-                            collection_name: ed_details.collection_name.clone(),
-                            matches: Vec::new(),
-                            documents: None,
-                            error: Some(format!("Failed to generate embedding: {}", e)),
-                        };
+                    return EmbeddedDatasetSearchResults {
+                        embedded_dataset_id,
+                        embedded_dataset_title: ed_details.title,
+                        source_dataset_id: ed_details.source_dataset_id,
+                        source_dataset_title: ed_details.source_dataset_title,
+                        embedder_id: ed_details.embedder_id,
+                        embedder_name: ed_details.embedder_name,
+                        collection_name: ed_details.collection_name,
+                        matches: Vec::new(),
+                        documents: None,
+                        warnings,
+                        error: Some(e),
+                    };
+                }
+
+                // Generate embedding for the query; keyword-only search doesn't need one
+                let query_vector = if retrieval_mode == RetrievalMode::Sparse {
+                    Vec::new()
+                } else {
+                    match crate::embedding::generate_embedding(
+                        &embedder.provider,
+                        &embedder.base_url,
+                        embedder.api_key.as_deref(),
+                        &embedder.config,
+                        &search_request.query,
+                        Some(&inference_config.url),
+                    )
+                    .await
+                    {
+                        Ok(v) => {
+                            tracing::info!(
+                                embedded_dataset_id = embedded_dataset_id,
+                                embedder_name = %embedder.name,
+                                vector_dimensions = v.len(),
+                                "Generated query embedding"
+                            );
+                            v
+                        },
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to generate embedding for embedded dataset {}: {}",
+                                embedded_dataset_id,
+                                e
+                            );
+                            return EmbeddedDatasetSearchResults {
+                                embedded_dataset_id,
+                                embedded_dataset_title: ed_details.title,
+                                source_dataset_id: ed_details.source_dataset_id,
+                                source_dataset_title: ed_details.source_dataset_title,
+                                embedder_id: ed_details.embedder_id,
+                                embedder_name: ed_details.embedder_name,
+                                collection_name: ed_details.collection_name.clone(),
+                                matches: Vec::new(),
+                                documents: None,
+                                warnings: Vec::new(),
+                                error: Some(format!("Failed to generate embedding: {}", e)),
+                            };
+                        }
                     }
                 };
 
-                // Perform the search
+                // Perform the search, scoring by the metric the collection was
+                // created with unless the request overrides it
                 let search_batch_size = worker_config.search_batch_size;
+                let metric = collection_info.metric;
+                let scoring_metric = search_request.distance_metric.unwrap_or(metric);
+                let candidate_request = SearchRequest {
+                    limit: candidate_limit,
+                    ..search_request.clone()
+                };
+                let qdrant_started = Instant::now();
                 let matches = match search_collection(
                     &qdrant_client,
                     &ed_details.collection_name,
                     &query_vector,
-                    &search_request,
+                    &candidate_request,
                     search_batch_size,
+                    metric,
+                    retrieval_mode,
                 )
                 .await
                 {
@@ -278,6 +365,7 @@ pub(crate) async fn search(
                                 collection_name: ed_details.collection_name,
                                 matches: Vec::new(),
                                 documents: None,
+                                warnings: Vec::new(),
                                 error: Some(
                                     "This embedded dataset has not been processed yet. Please wait for the embedding process to complete.".to_string()
                                 ),
@@ -293,21 +381,113 @@ pub(crate) async fn search(
                                 collection_name: ed_details.collection_name,
                                 matches: Vec::new(),
                                 documents: None,
+                                warnings: Vec::new(),
                                 error: Some(format!("Search failed: {}", e)),
                             };
                         }
                     }
                 };
 
-                let documents = if matches!(search_request.search_mode, SearchMode::Documents) {
-                    let mut docs = aggregate_matches_to_documents(&matches);
+                qdrant_duration_micros.fetch_max(
+                    qdrant_started.elapsed().as_micros() as u64,
+                    Ordering::Relaxed,
+                );
+
+                // Flag results indexed with a different embedder version than the query
+                if search_request.check_embedding_version {
+                    let query_stamp = EmbeddingStamp::new(
+                        Some(embedder.embedder_id),
+                        &embedder.provider,
+                        model_name,
+                        &embedder.config,
+                        embedder.max_input_tokens,
+                    );
+                    let version_warnings = embedding_version_warnings(&query_stamp, &matches);
+                    for warning in &version_warnings {
+                        tracing::warn!(
+                            embedded_dataset_id = embedded_dataset_id,
+                            collection_name = %ed_details.collection_name,
+                            warning = %warning,
+                            "Embedding version mismatch between query and indexed vectors"
+                        );
+                    }
+                    warnings.extend(version_warnings);
+                }
+
+                let limit = search_request.limit as usize;
+                let mut matches = matches;
+                let mut documents = if matches!(search_request.search_mode, SearchMode::Documents) {
+                    // Keyword and fused scores always rank higher-is-better
+                    let higher_is_better =
+                        retrieval_mode != RetrievalMode::Dense || scoring_metric.higher_is_better();
+                    let mut docs = aggregate_matches_to_documents(&matches, higher_is_better);
+                    if let Some(model) = &rerank_model {
+                        // Rerank the top documents by their full text, dropping
+                        // any that can't be fetched in time
+                        docs.truncate(candidate_limit as usize);
+                        let source_dataset_id = ed_details.source_dataset_id;
+                        let (fetched, dropped) = rerank::fetch_candidate_texts(
+                            docs,
+                            worker_config.search_rerank_fetch_concurrency,
+                            worker_config.search_rerank_fetch_timeout,
+                            |document: &DocumentResult| {
+                                document_text(&pool, source_dataset_id, document.item_id)
+                            },
+                        )
+                        .await;
+                        if dropped > 0 {
+                            tracing::warn!(
+                                embedded_dataset_id = embedded_dataset_id,
+                                dropped = dropped,
+                                "Left rerank candidates out after failed or slow fetches"
+                            );
+                            warnings.push(format!(
+                                "{dropped} candidate document(s) could not be fetched in time and were left out of reranking"
+                            ));
+                        }
+                        let (candidates, texts): (Vec<_>, Vec<_>) = fetched.into_iter().unzip();
+                        docs = rerank_candidates(
+                            candidates,
+                            texts,
+                            rerank::reorder_documents,
+                            &search_request.query,
+                            model,
+                            &inference_config.url,
+                            limit,
+                            &mut warnings,
+                        )
+                        .await;
+                    }
                     // Limit documents to the requested amount
-                    docs.truncate(search_request.limit as usize);
+                    docs.truncate(limit);
                     Some(docs)
                 } else {
+                    if let Some(model) = &rerank_model {
+                        let texts = matches.iter().map(|m| m.text.clone()).collect();
+                        matches = rerank_candidates(
+                            matches,
+                            texts,
+                            rerank::reorder_matches,
+                            &search_request.query,
+                            model,
+                            &inference_config.url,
+                            limit,
+                            &mut warnings,
+                        )
+                        .await;
+                    }
                     None
                 };
 
+                if search_request.explain {
+                    let ctx = ExplainContext {
+                        retrieval_mode,
+                        metric: scoring_metric,
+                        request: &search_request,
+                    };
+                    explain::explain_results(&mut matches, documents.as_mut(), &ctx);
+                }
+
                 EmbeddedDatasetSearchResults {
                     embedded_dataset_id,
                     embedded_dataset_title: ed_details.title,
@@ -318,6 +498,7 @@ pub(crate) async fn search(
                     collection_name: ed_details.collection_name,
                     matches,
                     documents,
+                    warnings,
                     error: None,
                 }
             }
@@ -345,12 +526,14 @@ pub(crate) async fn search(
     let embedded_datasets_count = search_request.embedded_dataset_ids.len();
 
     // Record search metrics
+    let qdrant_duration = qdrant_duration_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
     semantic_explorer_core::observability::record_search_request(
         duration,
         0.0, // embedder_duration_secs - would need to track separately
-        0.0, // qdrant_duration_secs - would need to track separately
+        qdrant_duration,
         total_results,
         embedded_datasets_count,
+        search_request.retrieval_mode.as_str(),
         "success",
     );
 
@@ -358,5 +541,268 @@ pub(crate) async fn search(
         results,
         query: search_request.query.clone(),
         search_mode: search_request.search_mode,
+        retrieval_mode: search_request.retrieval_mode,
     })
 }
+
+/// Most queries accepted by one batch search
+const MAX_BATCH_QUERIES: usize = 100;
+
+/// Query embeddings generated at once for a batch search
+const BATCH_EMBEDDING_CONCURRENCY: usize = 8;
+
+#[utoipa::path(
+    request_body = BatchSearchRequest,
+    responses(
+        (status = 200, description = "OK", body = BatchSearchResponse),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Embedded dataset not found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    tag = "Search",
+)]
+#[post("/api/search/batch")]
+#[tracing::instrument(
+    name = "search_batch",
+    skip(
+        user,
+        qdrant_client,
+        pool,
+        search_request,
+        req,
+        encryption,
+        inference_config
+    ),
+    fields(query_count = search_request.queries.len())
+)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn search_batch(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    qdrant_client: Data<Qdrant>,
+    pool: Data<Pool<Postgres>>,
+    encryption: Data<EncryptionService>,
+    inference_config: Data<EmbeddingInferenceConfig>,
+    circuit_breakers: Data<CircuitBreakers>,
+    Json(search_request): Json<BatchSearchRequest>,
+) -> impl Responder {
+    let start_time = Instant::now();
+
+    if !circuit_breakers.qdrant.should_allow().await {
+        return ApiError::ServiceUnavailable("Search service temporarily unavailable".to_string())
+            .error_response();
+    }
+
+    if search_request.queries.is_empty() {
+        return ApiError::BadRequest("At least one query is required".to_string()).error_response();
+    }
+    if search_request.queries.len() > MAX_BATCH_QUERIES {
+        return ApiError::BadRequest(format!(
+            "A batch search accepts at most {MAX_BATCH_QUERIES} queries"
+        ))
+        .error_response();
+    }
+    if let Some(index) = search_request
+        .queries
+        .iter()
+        .position(|query| query.trim().is_empty())
+    {
+        return ApiError::BadRequest(format!("Query {index} is empty")).error_response();
+    }
+
+    let embedded_dataset_id = search_request.embedded_dataset_id;
+    events::search_request(
+        &req,
+        &user.as_owner(),
+        &user,
+        &[embedded_dataset_id.to_string()],
+    );
+
+    let ed_details = match embedded_datasets::get_embedded_datasets_with_details_batch(
+        &pool,
+        &user.as_owner(),
+        &[embedded_dataset_id],
+    )
+    .await
+    {
+        Ok(eds) => match eds.into_iter().next() {
+            Some(ed) => ed,
+            None => {
+                return ApiError::NotFound(format!(
+                    "Embedded dataset {embedded_dataset_id} not found"
+                ))
+                .error_response();
+            }
+        },
+        Err(e) => {
+            tracing::error!("Failed to fetch embedded dataset: {}", e);
+            return ApiError::Internal(format!("Failed to fetch embedded dataset: {}", e))
+                .error_response();
+        }
+    };
+    if ed_details.is_standalone {
+        return ApiError::BadRequest(
+            "This embedded dataset does not support search (no embedder configured)".to_string(),
+        )
+        .error_response();
+    }
+
+    let embedder =
+        match embedders::get_embedders_batch(&pool, &user, &[ed_details.embedder_id], &encryption)
+            .await
+        {
+            Ok(embs) => match embs.into_iter().next() {
+                Some(embedder) => embedder,
+                None => {
+                    return ApiError::BadRequest(
+                        "Embedder not found or not accessible".to_string(),
+                    )
+                    .error_response();
+                }
+            },
+            Err(e) => {
+                tracing::error!("Failed to fetch embedder: {}", e);
+                return ApiError::Internal(format!("Failed to fetch embedder: {}", e))
+                    .error_response();
+            }
+        };
+
+    let embedding_started = Instant::now();
+    let query_vectors: Vec<Vec<f32>> = match stream::iter(&search_request.queries)
+        .map(|query| {
+            crate::embedding::generate_embedding(
+                &embedder.provider,
+                &embedder.base_url,
+                embedder.api_key.as_deref(),
+                &embedder.config,
+                query,
+                Some(&inference_config.url),
+            )
+        })
+        .buffered(BATCH_EMBEDDING_CONCURRENCY)
+        .try_collect()
+        .await
+    {
+        Ok(vectors) => vectors,
+        Err(e) => {
+            tracing::error!(
+                embedded_dataset_id = embedded_dataset_id,
+                error = %e,
+                "Failed to generate query embeddings for batch search"
+            );
+            return ApiError::Internal(format!("Failed to generate embedding: {}", e))
+                .error_response();
+        }
+    };
+    let embedding_duration = embedding_started.elapsed().as_secs_f64();
+
+    let collection_info = collection_search_info(&qdrant_client, &ed_details.collection_name).await;
+    let qdrant_started = Instant::now();
+    let per_query = match search_collection_batch(
+        &qdrant_client,
+        &ed_details.collection_name,
+        query_vectors,
+        &search_request,
+        collection_info.metric,
+    )
+    .await
+    {
+        Ok(per_query) => {
+            circuit_breakers.qdrant.record_success().await;
+            per_query
+        }
+        Err(e) => {
+            circuit_breakers.qdrant.record_failure().await;
+            tracing::error!(
+                collection_name = %ed_details.collection_name,
+                error = %e,
+                "Batch search failed"
+            );
+            return ApiError::Internal(format!("Search failed: {}", e)).error_response();
+        }
+    };
+    let qdrant_duration = qdrant_started.elapsed().as_secs_f64();
+
+    let results: Vec<BatchQueryResult> = search_request
+        .queries
+        .into_iter()
+        .zip(per_query)
+        .map(|(query, matches)| BatchQueryResult { query, matches })
+        .collect();
+
+    semantic_explorer_core::observability::record_search_request(
+        start_time.elapsed().as_secs_f64(),
+        embedding_duration,
+        qdrant_duration,
+        results.iter().map(|r| r.matches.len()).sum(),
+        1,
+        RetrievalMode::Dense.as_str(),
+        "success",
+    );
+
+    HttpResponse::Ok().json(BatchSearchResponse {
+        embedded_dataset_id,
+        collection_name: ed_details.collection_name,
+        results,
+    })
+}
+
+/// Longest document text sent to the reranker; cross-encoders only read the
+/// start of their input anyway
+const RERANK_DOCUMENT_MAX_CHARS: usize = 8_000;
+
+/// Title and chunk text of a dataset item, for reranking whole documents
+async fn document_text(
+    pool: &Pool<Postgres>,
+    dataset_id: i32,
+    item_id: i32,
+) -> anyhow::Result<String> {
+    let item = datasets::get_dataset_item_chunks(pool, dataset_id, item_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Dataset item {item_id} not found"))?;
+    let mut text = item.title;
+    for chunk in item.chunks {
+        if text.len() >= RERANK_DOCUMENT_MAX_CHARS {
+            break;
+        }
+        text.push_str("\n\n");
+        text.push_str(&chunk.content);
+    }
+    Ok(text.chars().take(RERANK_DOCUMENT_MAX_CHARS).collect())
+}
+
+/// Rerank `candidates`, whose text to score is the same index of `texts`,
+/// down to `limit`. If the reranker fails the candidates keep their
+/// retrieval order and a warning explains why.
+#[allow(clippy::too_many_arguments)]
+async fn rerank_candidates<T>(
+    mut candidates: Vec<T>,
+    texts: Vec<String>,
+    reorder: fn(Vec<T>, &[(usize, f32)]) -> Vec<T>,
+    query: &str,
+    model: &str,
+    inference_url: &str,
+    limit: usize,
+    warnings: &mut Vec<String>,
+) -> Vec<T> {
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let started = Instant::now();
+    let result = rerank::rerank_scores(inference_url, model, query, &texts, limit).await;
+    semantic_explorer_core::observability::record_search_rerank(
+        started.elapsed().as_secs_f64(),
+        model,
+        if result.is_ok() { "success" } else { "error" },
+    );
+
+    match result {
+        Ok(order) => reorder(candidates, &order),
+        Err(e) => {
+            tracing::warn!(model = %model, error = %e, "Reranking failed, keeping retrieval order");
+            warnings.push(format!(
+                "Reranking with '{model}' failed, results are in retrieval order: {e}"
+            ));
+            candidates.truncate(limit);
+            candidates
+        }
+    }
+}
@@ -2,15 +2,19 @@ use crate::audit::{ResourceType, events};
 use crate::auth::AuthenticatedUser;
 use crate::errors::{bad_request, not_found};
 use crate::storage::postgres::{embedded_datasets, llms, visualization_transforms};
+use crate::transforms::concurrency::{self, TransformKind};
 use crate::transforms::visualization::models::{
-    CreateVisualizationTransform, UpdateVisualizationTransform, Visualization,
+    CreateVisualizationTransform, UpdateVisualizationTransform, Visualization, VisualizationPoints,
     VisualizationTransform, VisualizationTransformStats,
 };
 use crate::transforms::visualization::scanner::trigger_visualization_transform_scan;
 use aws_sdk_s3::Client;
-use semantic_explorer_core::config::S3Config;
+use qdrant_client::Qdrant;
+use semantic_explorer_core::config::{S3Config, WorkerConfig};
 use semantic_explorer_core::encryption::EncryptionService;
-use semantic_explorer_core::models::{PaginatedResponse, QdrantConnectionConfig};
+use semantic_explorer_core::models::{
+    ClusteringMethod, PaginatedResponse, QdrantConnectionConfig, VisualizationJobMode,
+};
 use semantic_explorer_core::validation;
 
 use actix_web::web::{Data, Json, Path, Query};
@@ -177,6 +181,9 @@ pub async fn create_visualization_transform(
     if let Err(e) = validation::validate_title(&body.title) {
         return bad_request(e);
     }
+    if body.clustering_method == ClusteringMethod::Kmeans && body.n_clusters < 2 {
+        return bad_request("n_clusters must be at least 2 for kmeans clustering");
+    }
 
     // Verify embedded dataset exists and belongs to user
     match embedded_datasets::get_embedded_dataset(&pool, &user.as_owner(), body.embedded_dataset_id)
@@ -219,9 +226,12 @@ pub async fn create_visualization_transform(
         "metric": body.metric,
         "min_cluster_size": body.min_cluster_size,
         "min_samples": body.min_samples,
+        "clustering_method": body.clustering_method,
+        "n_clusters": body.n_clusters,
         "topic_naming_llm_id": body.llm_id,
         "llm_batch_size": body.llm_batch_size,
         "samples_per_cluster": body.samples_per_cluster,
+        "topic_naming_method": body.topic_naming_method,
         // Datamapplot visualization parameters
         "min_fontsize": body.min_fontsize,
         "max_fontsize": body.max_fontsize,
@@ -259,6 +269,7 @@ pub async fn create_visualization_transform(
                 &user.as_owner(),
                 &encryption,
                 &qdrant_config,
+                VisualizationJobMode::Full,
             )
             .await
             {
@@ -373,30 +384,33 @@ pub async fn update_visualization_transform(
     ),
 )]
 #[delete("/api/visualization-transforms/{id}")]
-#[tracing::instrument(name = "delete_visualization_transform", skip(user, pool, req), fields(visualization_transform_id = %path.as_ref()))]
+#[tracing::instrument(name = "delete_visualization_transform", skip(user, pool, req, qdrant_client), fields(visualization_transform_id = %path.as_ref()))]
 pub async fn delete_visualization_transform(
     user: AuthenticatedUser,
     req: HttpRequest,
     pool: Data<Pool<Postgres>>,
+    qdrant_client: Data<Qdrant>,
     path: Path<i32>,
 ) -> impl Responder {
     let id = path.into_inner();
 
     // Verify ownership
-    match visualization_transforms::get_visualization_transform_by_id(&pool, id).await {
-        Ok(Some(transform)) => {
-            if transform.owner_id != user.as_owner() {
-                return not_found("Visualization transform not found".to_string());
+    let topics_collection_name =
+        match visualization_transforms::get_visualization_transform_by_id(&pool, id).await {
+            Ok(Some(transform)) => {
+                if transform.owner_id != user.as_owner() {
+                    return not_found("Visualization transform not found".to_string());
+                }
+                transform.topics_collection_name
             }
-        }
-        Ok(None) => return not_found("Visualization transform not found".to_string()),
-        Err(e) => {
-            error!("Failed to fetch visualization transform: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to fetch visualization transform: {}", e)
-            }));
-        }
-    }
+            Ok(None) => return not_found("Visualization transform not found".to_string()),
+            Err(e) => {
+                error!("Failed to fetch visualization transform: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Failed to fetch visualization transform: {}", e)
+                }));
+            }
+        };
 
     match visualization_transforms::delete_visualization_transform(&pool, id, &user.as_owner())
         .await
@@ -409,6 +423,16 @@ pub async fn delete_visualization_transform(
                 ResourceType::Visualization,
                 &id.to_string(),
             );
+            // Best-effort, like the dataset transform cleanup: an orphaned
+            // topics collection is harmless
+            if let Some(name) = topics_collection_name {
+                let qdrant = qdrant_client.into_inner();
+                tokio::spawn(async move {
+                    if let Err(e) = qdrant.delete_collection(&name).await {
+                        error!("Failed to delete topics collection {}: {}", name, e);
+                    }
+                });
+            }
             HttpResponse::NoContent().finish()
         }
         Err(e) => {
@@ -420,28 +444,41 @@ pub async fn delete_visualization_transform(
     }
 }
 
+#[derive(Deserialize, Debug)]
+pub struct TriggerVisualizationParams {
+    /// `full` (default) refits everything; `assign_only` assigns points
+    /// added since the last full run to its topics
+    #[serde(default)]
+    pub mode: VisualizationJobMode,
+}
+
 #[utoipa::path(
     post,
     path = "/api/visualization-transforms/{id}/trigger",
     tag = "Visualization Transforms",
     params(
-        ("id" = i32, Path, description = "Visualization Transform ID")
+        ("id" = i32, Path, description = "Visualization Transform ID"),
+        ("mode" = Option<VisualizationJobMode>, Query, description = "full (default) or assign_only"),
     ),
     responses(
         (status = 200, description = "Visualization transform triggered"),
+        (status = 400, description = "assign_only without a completed full run"),
         (status = 404, description = "Visualization transform not found"),
+        (status = 429, description = "Too many transforms already running for this user"),
         (status = 401, description = "Unauthorized"),
     ),
 )]
 #[post("/api/visualization-transforms/{id}/trigger")]
-#[tracing::instrument(name = "trigger_visualization_transform", skip(user, pool, nats_client, encryption, qdrant_config), fields(visualization_transform_id = %path.as_ref()))]
+#[tracing::instrument(name = "trigger_visualization_transform", skip(user, pool, nats_client, encryption, qdrant_config, worker_config), fields(visualization_transform_id = %path.as_ref()))]
 pub async fn trigger_visualization_transform(
     user: AuthenticatedUser,
     pool: Data<Pool<Postgres>>,
     nats_client: Data<NatsClient>,
     encryption: Data<EncryptionService>,
     qdrant_config: Data<QdrantConnectionConfig>,
+    worker_config: Data<WorkerConfig>,
     path: Path<i32>,
+    Query(params): Query<TriggerVisualizationParams>,
 ) -> impl Responder {
     let id = path.into_inner();
 
@@ -461,6 +498,35 @@ pub async fn trigger_visualization_transform(
         }
     }
 
+    if params.mode == VisualizationJobMode::AssignOnly {
+        match visualization_transforms::get_latest_fitted_visualization(&pool, id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return bad_request(
+                    "assign_only needs a completed full run of this visualization transform",
+                );
+            }
+            Err(e) => {
+                error!("Failed to fetch latest visualization: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Failed to fetch latest visualization: {}", e)
+                }));
+            }
+        }
+    }
+
+    if let Err(response) = concurrency::reserve_slot(
+        &pool,
+        &worker_config,
+        &user.as_owner(),
+        TransformKind::Visualization,
+        id,
+    )
+    .await
+    {
+        return response;
+    }
+
     match trigger_visualization_transform_scan(
         &pool,
         &nats_client,
@@ -468,6 +534,7 @@ pub async fn trigger_visualization_transform(
         &user.as_owner(),
         &encryption,
         &qdrant_config,
+        params.mode,
     )
     .await
     {
@@ -625,6 +692,13 @@ pub async fn get_visualizations(
     }
 }
 
+#[derive(Deserialize, Debug)]
+pub struct GetVisualizationParams {
+    /// Include the 2D coordinates and cluster of each point
+    #[serde(default)]
+    pub include_points: bool,
+}
+
 #[utoipa::path(
     get,
     path = "/api/visualization-transforms/{id}/visualizations/{visualization_id}",
@@ -632,19 +706,24 @@ pub async fn get_visualizations(
     params(
         ("id" = i32, Path, description = "Visualization Transform ID"),
         ("visualization_id" = i32, Path, description = "Visualization ID"),
+        ("include_points" = Option<bool>, Query, description = "Include each point's 2D coordinates and cluster"),
     ),
     responses(
         (status = 200, description = "Visualization details", body = Visualization),
+        (status = 400, description = "Points are too large to load"),
         (status = 404, description = "Visualization not found"),
         (status = 401, description = "Unauthorized"),
     ),
 )]
 #[get("/api/visualization-transforms/{id}/visualizations/{visualization_id}")]
-#[tracing::instrument(name = "get_visualization", skip(user, pool), fields(visualization_transform_id = %path.0, visualization_id = %path.1))]
+#[tracing::instrument(name = "get_visualization", skip(user, pool, s3_client, s3_config), fields(visualization_transform_id = %path.0, visualization_id = %path.1))]
 pub async fn get_visualization(
     user: AuthenticatedUser,
     pool: Data<Pool<Postgres>>,
+    s3_client: Data<Client>,
+    s3_config: Data<S3Config>,
     path: Path<(i32, i32)>,
+    Query(params): Query<GetVisualizationParams>,
 ) -> impl Responder {
     let (transform_id, visualization_id) = path.into_inner();
 
@@ -657,13 +736,49 @@ pub async fn get_visualization(
     )
     .await
     {
-        Ok(visualization) => {
+        Ok(mut visualization) => {
             events::resource_read(
                 &user.as_owner(),
                 &user,
                 ResourceType::Visualization,
                 &visualization_id.to_string(),
             );
+            // Visualizations made before points were stored have none
+            if params.include_points
+                && let Some(points_s3_key) = &visualization.points_s3_key
+            {
+                match semantic_explorer_core::storage::get_file_with_size_check(
+                    &s3_client,
+                    &s3_config.bucket_name,
+                    points_s3_key,
+                    s3_config.max_download_size_bytes,
+                )
+                .await
+                {
+                    Ok(data) => match serde_json::from_slice::<VisualizationPoints>(&data) {
+                        Ok(points) => visualization.points = Some(points),
+                        Err(e) => {
+                            error!(
+                                "Invalid points for visualization {}: {}",
+                                visualization_id, e
+                            );
+                            return HttpResponse::InternalServerError().json(serde_json::json!({
+                                "error": format!("Invalid visualization points: {}", e)
+                            }));
+                        }
+                    },
+                    Err(e) => {
+                        error!("Failed to download visualization points: {}", e);
+                        let error_msg = e.to_string();
+                        if error_msg.contains("exceeds maximum download limit") {
+                            return bad_request(error_msg);
+                        }
+                        return HttpResponse::InternalServerError().json(serde_json::json!({
+                            "error": format!("Failed to download visualization points: {}", e)
+                        }));
+                    }
+                }
+            }
             HttpResponse::Ok().json(visualization)
         }
         Err(e) => {
@@ -845,6 +960,7 @@ pub async fn download_visualization_html(
         &s3_client,
         &s3_config.bucket_name,
         &html_s3_key,
+        s3_config.max_download_size_bytes,
     )
     .await
     {
@@ -3,12 +3,18 @@
 //! This module provides infrastructure for audit logging using NATS JetStream
 //! for reliable, persistent event delivery. Events are published to the AUDIT_EVENTS
 //! stream and consumed by a background worker for database persistence.
+//! Publishing is retried with backoff; events that still can't be published
+//! are written to the database directly. Each event carries a unique id that
+//! both paths deduplicate on, so a retry never records an event twice.
 
 use crate::storage::postgres::audit as audit_storage;
+use semantic_explorer_core::{RetryPolicy, RetryableError, retry_with_policy};
 use serde::Serialize;
 use sqlx::{Pool, Postgres};
-use std::time::SystemTime;
-use tracing::{info, warn};
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, warn};
 
 /// NATS subject for audit events
 pub const AUDIT_EVENTS_SUBJECT: &str = "audit.events";
@@ -58,6 +64,7 @@ pub enum ResourceType {
     Visualization,
     LlmProvider,
     Session,
+    AuditLog,
 }
 
 /// Outcome of the audited action
@@ -74,6 +81,9 @@ pub enum AuditOutcome {
 /// Audit log entry for security events
 #[derive(Debug, Clone, Serialize, serde::Deserialize)]
 pub struct AuditEvent {
+    /// Unique id, used to drop duplicate deliveries
+    #[serde(default)]
+    pub event_id: String,
     /// ISO 8601 timestamp
     pub timestamp: String,
     /// Type of audit event
@@ -93,6 +103,9 @@ pub struct AuditEvent {
     /// Additional details/reason
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// `X-Request-Id` of the request that caused the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl AuditEvent {
@@ -114,6 +127,7 @@ impl AuditEvent {
             .unwrap_or_default();
 
         Self {
+            event_id: uuid::Uuid::new_v4().to_string(),
             timestamp,
             event_type,
             outcome,
@@ -122,6 +136,7 @@ impl AuditEvent {
             resource_type: None,
             resource_id: None,
             details: None,
+            request_id: None,
         }
     }
 
@@ -142,6 +157,17 @@ impl AuditEvent {
         self
     }
 
+    /// Add the request id of `req`, if the caller sent one
+    pub fn with_request(mut self, req: &actix_web::HttpRequest) -> Self {
+        self.request_id = req
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+        self
+    }
+
     /// Log this audit event
     /// Uses structured logging for easy parsing and aggregation
     pub fn log(&self) {
@@ -157,6 +183,7 @@ impl AuditEvent {
                     resource_type = ?self.resource_type,
                     resource_id = ?self.resource_id,
                     details = ?self.details,
+                    request_id = ?self.request_id,
                     "AUDIT"
                 );
             }
@@ -169,6 +196,7 @@ impl AuditEvent {
                     resource_type = ?self.resource_type,
                     resource_id = ?self.resource_id,
                     details = ?self.details,
+                    request_id = ?self.request_id,
                     "AUDIT"
                 );
             }
@@ -181,6 +209,88 @@ impl AuditEvent {
     }
 }
 
+/// Retry policy for publishing audit events, overridable with
+/// `AUDIT_PUBLISH_RETRY_*`
+fn publish_retry_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(2),
+        backoff_multiplier: 2.0,
+        jitter_factor: 0.1,
+    }
+    .with_env_overrides("AUDIT_PUBLISH_RETRY")
+}
+
+/// A failed publish; every failure is worth retrying since the event is
+/// otherwise only kept by the database fallback
+#[derive(Debug)]
+pub(crate) struct PublishError(pub(crate) String);
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl RetryableError for PublishError {
+    fn is_retryable(&self) -> bool {
+        true
+    }
+}
+
+/// Where an audit event ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Delivery {
+    /// Acknowledged by the audit stream
+    Published,
+    /// Written directly to `audit_events` after publishing kept failing
+    Stored,
+    /// Neither worked; the event only survives in the logs
+    Lost,
+}
+
+/// Publish `event` with retries and fall back to storing it. Both paths
+/// carry the event id, so an event that reaches the database both ways is
+/// only recorded once.
+pub(crate) async fn deliver<P, PF, S, SF>(
+    event: &AuditEvent,
+    policy: &RetryPolicy,
+    publish: P,
+    store: S,
+) -> Delivery
+where
+    P: FnMut() -> PF,
+    PF: Future<Output = Result<(), PublishError>>,
+    S: FnOnce() -> SF,
+    SF: Future<Output = anyhow::Result<()>>,
+{
+    let publish_error = match retry_with_policy(policy, "audit_publish", publish).await {
+        Ok(()) => return Delivery::Published,
+        Err(e) => e,
+    };
+    warn!(
+        target: "audit",
+        error = %publish_error,
+        event_id = %event.event_id,
+        "Failed to publish audit event, storing it directly"
+    );
+
+    match store().await {
+        Ok(()) => Delivery::Stored,
+        Err(e) => {
+            error!(
+                target: "audit",
+                error = %e,
+                publish_error = %publish_error,
+                event = %serde_json::to_string(event).unwrap_or_default(),
+                "Audit event could not be published or stored"
+            );
+            Delivery::Lost
+        }
+    }
+}
+
 /// Convenience functions for common audit events
 pub mod events {
     use super::*;
@@ -211,41 +321,58 @@ pub mod events {
         AUDIT_NATS_CLIENT.get()
     }
 
-    /// Publish audit event to NATS stream for async processing
-    /// Returns true if successfully published, false if NATS unavailable
-    fn publish_audit_event(event: &AuditEvent) -> bool {
-        if let Some(nats) = get_nats_client() {
-            match serde_json::to_vec(event) {
-                Ok(payload) => {
-                    let nats = nats.clone();
-                    tokio::spawn(async move {
-                        if let Err(e) = nats.publish(AUDIT_EVENTS_SUBJECT, payload.into()).await {
-                            warn!(
-                                target: "audit",
-                                error = %e,
-                                "Failed to publish audit event to NATS"
-                            );
-                        }
-                    });
-                    true
-                }
-                Err(e) => {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to serialize audit event"
-                    );
-                    false
-                }
-            }
-        } else {
-            false
+    /// Log `event` and deliver it in the background: published to the
+    /// audit stream with retries, or written straight to the database when
+    /// NATS stays unavailable
+    fn dispatch(event: AuditEvent) {
+        event.log();
+
+        let nats = get_nats_client();
+        let pool = get_db_pool();
+        if nats.is_none() && pool.is_none() {
+            return;
         }
+        tokio::spawn(async move {
+            let event = &event;
+            deliver(
+                event,
+                &publish_retry_policy(),
+                move || publish(nats, event),
+                move || async move {
+                    match pool {
+                        Some(pool) => event.store(pool).await.map_err(anyhow::Error::from),
+                        None => Err(anyhow::anyhow!("audit database pool is not initialized")),
+                    }
+                },
+            )
+            .await;
+        });
+    }
+
+    /// Publish `event` to the audit stream and wait for JetStream to
+    /// acknowledge it. The event id is the `Nats-Msg-Id`, so a retried
+    /// publish that had already landed is dropped by the stream.
+    async fn publish(
+        nats: Option<&async_nats::Client>,
+        event: &AuditEvent,
+    ) -> Result<(), PublishError> {
+        let nats = nats.ok_or_else(|| PublishError("NATS is not initialized".to_string()))?;
+        let payload = serde_json::to_vec(event).map_err(|e| PublishError(e.to_string()))?;
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert("Nats-Msg-Id", event.event_id.as_str());
+
+        async_nats::jetstream::new(nats.clone())
+            .publish_with_headers(AUDIT_EVENTS_SUBJECT, headers, payload.into())
+            .await
+            .map_err(|e| PublishError(e.to_string()))?
+            .await
+            .map_err(|e| PublishError(e.to_string()))?;
+        Ok(())
     }
 
     /// Log a successful resource creation with request context
     pub fn resource_created_with_request(
-        _req: &HttpRequest,
+        req: &HttpRequest,
         user_id: &str,
         user_display: &str,
         resource_type: ResourceType,
@@ -257,24 +384,9 @@ pub mod events {
             user_id,
             user_display,
         )
-        .with_resource(resource_type, resource_id);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        .with_resource(resource_type, resource_id)
+        .with_request(req);
+        dispatch(event);
     }
 
     /// Log a successful resource read
@@ -291,23 +403,7 @@ pub mod events {
             user_display,
         )
         .with_resource(resource_type, resource_id);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a successful resource update
@@ -324,28 +420,12 @@ pub mod events {
             user_display,
         )
         .with_resource(resource_type, resource_id);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a successful resource deletion with request context
     pub fn resource_deleted_with_request(
-        _req: &HttpRequest,
+        req: &HttpRequest,
         user_id: &str,
         user_display: &str,
         resource_type: ResourceType,
@@ -357,24 +437,9 @@ pub mod events {
             user_id,
             user_display,
         )
-        .with_resource(resource_type, resource_id);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        .with_resource(resource_type, resource_id)
+        .with_request(req);
+        dispatch(event);
     }
 
     /// Log an authentication failure
@@ -386,23 +451,7 @@ pub mod events {
             user_display,
         )
         .with_details(reason);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log an unauthorized access attempt
@@ -421,23 +470,7 @@ pub mod events {
         )
         .with_resource(resource_type, resource_id)
         .with_details(reason);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a validation failure
@@ -449,28 +482,12 @@ pub mod events {
             user_display,
         )
         .with_details(format!("{}: {}", field, reason));
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a chat message
     pub fn chat_message_sent(
-        _req: &HttpRequest,
+        req: &HttpRequest,
         user_id: &str,
         user_display: &str,
         session_id: &str,
@@ -481,29 +498,14 @@ pub mod events {
             user_id,
             user_display,
         )
-        .with_resource(ResourceType::Session, session_id);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        .with_resource(ResourceType::Session, session_id)
+        .with_request(req);
+        dispatch(event);
     }
 
     /// Log a search request
     pub fn search_request(
-        _req: &HttpRequest,
+        req: &HttpRequest,
         user_id: &str,
         user_display: &str,
         collection_ids: &[String],
@@ -514,24 +516,9 @@ pub mod events {
             user_id,
             user_display,
         )
-        .with_details(format!("collections: {}", collection_ids.join(", ")));
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        .with_details(format!("collections: {}", collection_ids.join(", ")))
+        .with_request(req);
+        dispatch(event);
     }
 
     /// Log a file download
@@ -544,23 +531,7 @@ pub mod events {
         )
         .with_resource(ResourceType::Collection, collection_id.to_string())
         .with_details(filename);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a configuration change (e.g., embedder/LLM API key update)
@@ -579,23 +550,7 @@ pub mod events {
         )
         .with_resource(resource_type, resource_id)
         .with_details(format!("field: {}", field));
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a marketplace operation (grab collection, grab dataset, etc.)
@@ -612,23 +567,7 @@ pub mod events {
             user_display,
         )
         .with_resource(resource_type, resource_id);
-        event.log();
-
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
-        }
+        dispatch(event);
     }
 
     /// Log a file validation failure during upload
@@ -647,22 +586,126 @@ pub mod events {
         )
         .with_resource(ResourceType::Collection, collection_id.to_string())
         .with_details(format!("file: {}; reason: {}", filename, reason));
-        event.log();
+        dispatch(event);
+    }
+}
 
-        // Try to publish to NATS, fall back to direct database write if unavailable
-        if !publish_audit_event(&event)
-            && let Some(pool) = get_db_pool()
-        {
-            let event_clone = event.clone();
-            tokio::spawn(async move {
-                if let Err(e) = event_clone.store(pool).await {
-                    warn!(
-                        target: "audit",
-                        error = %e,
-                        "Failed to store audit event in database"
-                    );
-                }
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn no_delay_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            backoff_multiplier: 1.0,
+            jitter_factor: 0.0,
         }
     }
+
+    fn event() -> AuditEvent {
+        AuditEvent::new(
+            AuditEventType::ResourceDelete,
+            AuditOutcome::Success,
+            "u1",
+            "alice",
+        )
+        .with_resource(ResourceType::Dataset, "42")
+    }
+
+    #[tokio::test]
+    async fn test_transient_publish_failure_is_retried() {
+        let event = event();
+        let (publishes, stores) = (&AtomicU32::new(0), &AtomicU32::new(0));
+
+        let delivery = deliver(
+            &event,
+            &no_delay_policy(),
+            move || async move {
+                if publishes.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(PublishError("no responders".to_string()))
+                } else {
+                    Ok(())
+                }
+            },
+            move || async move {
+                stores.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert_eq!(delivery, Delivery::Published);
+        assert_eq!(publishes.load(Ordering::SeqCst), 3);
+        assert_eq!(stores.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_publish_failure_falls_back_to_storage() {
+        let event = event();
+        let (publishes, stores) = (&AtomicU32::new(0), &AtomicU32::new(0));
+
+        let delivery = deliver(
+            &event,
+            &no_delay_policy(),
+            move || async move {
+                publishes.fetch_add(1, Ordering::SeqCst);
+                Err(PublishError("connection closed".to_string()))
+            },
+            move || async move {
+                stores.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert_eq!(delivery, Delivery::Stored);
+        // The first attempt plus three retries
+        assert_eq!(publishes.load(Ordering::SeqCst), 4);
+        assert_eq!(stores.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_event_is_lost_only_when_storage_fails_too() {
+        let delivery = deliver(
+            &event(),
+            &no_delay_policy(),
+            move || async move { Err(PublishError("connection closed".to_string())) },
+            move || async move { Err(anyhow::anyhow!("database unavailable")) },
+        )
+        .await;
+        assert_eq!(delivery, Delivery::Lost);
+    }
+
+    #[test]
+    fn test_events_get_unique_ids_that_survive_serialization() {
+        let a = event();
+        let b = event();
+        assert!(!a.event_id.is_empty());
+        assert_ne!(a.event_id, b.event_id);
+
+        let decoded: AuditEvent = serde_json::from_slice(&serde_json::to_vec(&a).unwrap()).unwrap();
+        assert_eq!(decoded.event_id, a.event_id);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_storing_an_event_twice_records_it_once() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::PgPool::connect(&url).await.unwrap();
+        let event = event();
+
+        event.store(&pool).await.unwrap();
+        event.store(&pool).await.unwrap();
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM audit_events WHERE event_id = $1")
+                .bind(&event.event_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(count, 1);
+    }
 }
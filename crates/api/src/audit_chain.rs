@@ -0,0 +1,296 @@
+//! Tamper-evident hash chain over `audit_events`.
+//!
+//! Every stored event records the hash of the event before it (`prev_hash`)
+//! and its own `hash = sha256(prev_hash || canonical_json)`, where the
+//! canonical JSON covers every stored column except the generated id. The
+//! first event chains from [`GENESIS_HASH`]. Writers hold an advisory lock
+//! while reading the latest hash and inserting, so concurrent writers can't
+//! fork the chain.
+//!
+//! Editing a row changes its hash, and deleting one leaves the next row
+//! pointing at a hash that isn't there; [`verify_links`] reports the first
+//! such break. Archival removes the oldest events, so verification starts
+//! from the hash of the last archived event kept in `audit_archives`.
+
+use chrono::SecondsFormat;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use utoipa::ToSchema;
+
+use crate::storage::postgres::audit::{self, StoredAuditEvent};
+
+/// `prev_hash` of the first event in the chain
+pub(crate) const GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Events read per query while verifying
+const VERIFY_BATCH_SIZE: i64 = 1000;
+
+/// The fields an event's hash covers, in a fixed order
+#[derive(Serialize)]
+struct CanonicalEvent<'a> {
+    event_id: Option<&'a str>,
+    timestamp: String,
+    event_type: &'a str,
+    outcome: &'a str,
+    user_id: &'a str,
+    username_display: &'a str,
+    resource_type: Option<&'a str>,
+    resource_id: Option<&'a str>,
+    details: Option<&'a str>,
+    request_id: Option<&'a str>,
+}
+
+fn canonical_json(event: &StoredAuditEvent) -> String {
+    let canonical = CanonicalEvent {
+        event_id: event.event_id.as_deref(),
+        // Postgres keeps microseconds, so that is all the hash may cover
+        timestamp: event.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+        event_type: &event.event_type,
+        outcome: &event.outcome,
+        user_id: &event.user_id,
+        username_display: &event.username_display,
+        resource_type: event.resource_type.as_deref(),
+        resource_id: event.resource_id.as_deref(),
+        details: event.details.as_deref(),
+        request_id: event.request_id.as_deref(),
+    };
+    serde_json::to_string(&canonical).unwrap_or_default()
+}
+
+/// Hash of `event` chained onto `prev_hash`
+pub(crate) fn event_hash(prev_hash: &str, event: &StoredAuditEvent) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(canonical_json(event).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// First event that doesn't fit the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub(crate) struct BrokenLink {
+    pub(crate) audit_event_id: i64,
+    pub(crate) reason: String,
+}
+
+/// Check that `events` (in id order) chain from `prev_hash`, advancing it to
+/// the last event's hash. Events written before chaining began have no hash
+/// and are skipped until the first chained event.
+pub(crate) fn verify_links(
+    prev_hash: &mut Option<String>,
+    events: &[StoredAuditEvent],
+) -> Result<(), BrokenLink> {
+    for event in events {
+        let broken = |reason: &str| BrokenLink {
+            audit_event_id: event.audit_event_id,
+            reason: reason.to_string(),
+        };
+        let (Some(event_prev), Some(hash)) = (&event.prev_hash, &event.hash) else {
+            if prev_hash.is_some() {
+                return Err(broken("event has no hash"));
+            }
+            continue;
+        };
+        let expected_prev = prev_hash.as_deref().unwrap_or(GENESIS_HASH);
+        if event_prev != expected_prev {
+            return Err(broken(
+                "prev_hash does not match the previous event; an event was deleted or reordered",
+            ));
+        }
+        if event_hash(event_prev, event) != *hash {
+            return Err(broken("hash does not match the event; it was modified"));
+        }
+        *prev_hash = Some(hash.clone());
+    }
+    Ok(())
+}
+
+/// Outcome of walking the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub(crate) struct ChainVerification {
+    /// Events checked up to the first broken link
+    pub(crate) events_checked: u64,
+    /// Absent when the whole chain is intact
+    pub(crate) first_broken_link: Option<BrokenLink>,
+}
+
+/// Walk every event in `audit_events` from the last archived event (or the
+/// genesis hash) and report the first broken link
+pub(crate) async fn verify_audit_chain(
+    pool: &Pool<Postgres>,
+) -> Result<ChainVerification, sqlx::Error> {
+    let mut prev_hash = audit::get_last_archived_event_hash(pool).await?;
+    let mut after_id = 0;
+    let mut events_checked = 0;
+
+    loop {
+        let events = audit::get_audit_events_after(pool, after_id, VERIFY_BATCH_SIZE).await?;
+        let Some(last) = events.last() else {
+            break;
+        };
+        after_id = last.audit_event_id;
+
+        if let Err(link) = verify_links(&mut prev_hash, &events) {
+            events_checked += events
+                .iter()
+                .take_while(|e| e.audit_event_id < link.audit_event_id)
+                .count() as u64;
+            return Ok(ChainVerification {
+                events_checked,
+                first_broken_link: Some(link),
+            });
+        }
+        events_checked += events.len() as u64;
+        if (events.len() as i64) < VERIFY_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(ChainVerification {
+        events_checked,
+        first_broken_link: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn event(id: i64) -> StoredAuditEvent {
+        StoredAuditEvent {
+            audit_event_id: id,
+            timestamp: DateTime::<Utc>::from_timestamp_micros(1_767_225_600_000_000 + id).unwrap(),
+            event_type: "ResourceUpdate".to_string(),
+            outcome: "Success".to_string(),
+            user_id: "u1".to_string(),
+            username_display: "alice".to_string(),
+            resource_type: Some("Dataset".to_string()),
+            resource_id: Some(id.to_string()),
+            details: None,
+            request_id: None,
+            event_id: Some(format!("event-{id}")),
+            prev_hash: None,
+            hash: None,
+        }
+    }
+
+    /// Events `ids` chained from genesis, as the writer stores them
+    fn chain(ids: std::ops::RangeInclusive<i64>) -> Vec<StoredAuditEvent> {
+        let mut prev = GENESIS_HASH.to_string();
+        ids.map(|id| {
+            let mut e = event(id);
+            e.hash = Some(event_hash(&prev, &e));
+            e.prev_hash = Some(std::mem::replace(&mut prev, e.hash.clone().unwrap()));
+            e
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let events = chain(1..=5);
+        let mut prev = None;
+        assert_eq!(verify_links(&mut prev, &events), Ok(()));
+        assert_eq!(prev, events[4].hash);
+
+        // Verification can continue batch by batch
+        let mut prev = None;
+        verify_links(&mut prev, &events[..2]).unwrap();
+        assert_eq!(verify_links(&mut prev, &events[2..]), Ok(()));
+    }
+
+    #[test]
+    fn test_mutated_middle_event_is_detected() {
+        let mut events = chain(1..=5);
+        events[2].details = Some("rewritten".to_string());
+
+        let broken = verify_links(&mut None, &events).unwrap_err();
+        assert_eq!(broken.audit_event_id, 3);
+        assert!(broken.reason.contains("modified"));
+    }
+
+    #[test]
+    fn test_rehashed_mutation_breaks_the_next_link() {
+        let mut events = chain(1..=5);
+        events[2].outcome = "Denied".to_string();
+        events[2].hash = Some(event_hash(
+            events[2].prev_hash.as_ref().unwrap(),
+            &events[2],
+        ));
+
+        let broken = verify_links(&mut None, &events).unwrap_err();
+        assert_eq!(broken.audit_event_id, 4);
+    }
+
+    #[test]
+    fn test_deleted_event_is_detected() {
+        let mut events = chain(1..=5);
+        events.remove(2);
+
+        let broken = verify_links(&mut None, &events).unwrap_err();
+        assert_eq!(broken.audit_event_id, 4);
+        assert!(broken.reason.contains("deleted"));
+    }
+
+    #[test]
+    fn test_deleting_the_first_event_is_detected() {
+        let events = chain(1..=3);
+        let broken = verify_links(&mut None, &events[1..]).unwrap_err();
+        assert_eq!(broken.audit_event_id, 2);
+    }
+
+    #[test]
+    fn test_unchained_events_before_the_chain_are_skipped() {
+        // Events written before the upgrade, then the chain starting at genesis
+        let mut events = vec![event(1), event(2)];
+        events.extend(chain(3..=4));
+        assert_eq!(verify_links(&mut None, &events), Ok(()));
+
+        // A chained event losing its hash is a break
+        events[3].hash = None;
+        assert_eq!(
+            verify_links(&mut None, &events).unwrap_err().audit_event_id,
+            4
+        );
+    }
+
+    #[test]
+    fn test_hash_covers_stored_fields_but_not_the_id() {
+        let mut e = event(1);
+        let hash = event_hash(GENESIS_HASH, &e);
+        e.audit_event_id = 99;
+        assert_eq!(event_hash(GENESIS_HASH, &e), hash);
+        e.request_id = Some("req-1".to_string());
+        assert_ne!(event_hash(GENESIS_HASH, &e), hash);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_stored_events_form_a_verifiable_chain() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::PgPool::connect(&url).await.unwrap();
+        let writes = (0..5).map(|i| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                crate::audit::AuditEvent::new(
+                    crate::audit::AuditEventType::ResourceRead,
+                    crate::audit::AuditOutcome::Success,
+                    "u1",
+                    "alice",
+                )
+                .with_details(format!("concurrent write {i}"))
+                .store(&pool)
+                .await
+            })
+        });
+        for write in futures_util::future::join_all(writes).await {
+            write.unwrap().unwrap();
+        }
+
+        let verification = verify_audit_chain(&pool).await.unwrap();
+        assert_eq!(verification.first_broken_link, None);
+        assert!(verification.events_checked >= 5);
+    }
+}
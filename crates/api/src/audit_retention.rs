@@ -0,0 +1,536 @@
+//! Audit event retention.
+//!
+//! Events older than `AUDIT_RETENTION_DAYS` are moved out of Postgres into
+//! S3 as gzipped NDJSON, one object per batch, and deleted from
+//! `audit_events` in the same transaction that records the archive.
+//!
+//! Archives are hash-chained: each archive's digest is
+//! `sha256(prev_digest || object)`, starting from [`GENESIS_DIGEST`], and the
+//! chain is kept in `audit_archives`. Deleting, reordering or editing an
+//! archived object breaks every digest after it. Before extending the chain a
+//! pass re-reads the newest archive and checks its digest, so archival stops
+//! (and logs) rather than building on a tampered tail.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::{DateTime, Utc};
+use semantic_explorer_core::compression::BodyCompression;
+use semantic_explorer_core::storage::{put_object_with_sse, server_side_encryption};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres, Transaction};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::storage::postgres::audit::{self, AuditArchive, StoredAuditEvent};
+
+/// `prev_digest` of the first archive
+pub(crate) const GENESIS_DIGEST: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Retention settings, loaded from env at startup.
+#[derive(Debug, Clone)]
+pub(crate) struct AuditRetentionConfig {
+    /// Age after which events are archived (`AUDIT_RETENTION_DAYS`); unset or
+    /// 0 keeps events in Postgres forever
+    pub(crate) retention: Option<Duration>,
+    /// Time between archival passes (`AUDIT_RETENTION_INTERVAL_SECS`)
+    pub(crate) interval: Duration,
+    /// Events per archive object (`AUDIT_ARCHIVE_BATCH_SIZE`)
+    pub(crate) batch_size: i64,
+    /// S3 key prefix for archive objects (`AUDIT_ARCHIVE_PREFIX`)
+    pub(crate) prefix: String,
+}
+
+impl AuditRetentionConfig {
+    pub(crate) fn from_env() -> Self {
+        Self {
+            retention: std::env::var("AUDIT_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|days| *days > 0)
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+            interval: Duration::from_secs(
+                std::env::var("AUDIT_RETENTION_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(3600),
+            ),
+            batch_size: std::env::var("AUDIT_ARCHIVE_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(10_000),
+            prefix: std::env::var("AUDIT_ARCHIVE_PREFIX")
+                .unwrap_or_else(|_| "audit-archive/".to_string()),
+        }
+    }
+}
+
+/// Where expired events are read from and archives are written to
+pub(crate) trait ArchiveStore {
+    /// Lock archival and return up to `limit` events older than `cutoff`
+    /// (oldest first), stopping at the first event that hasn't expired, with
+    /// the newest archive. None when another replica is archiving.
+    async fn begin(
+        &mut self,
+        cutoff: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Option<(Vec<StoredAuditEvent>, Option<AuditArchive>)>>;
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>>;
+
+    async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<()>;
+
+    /// Record `archive`, delete the archived events and release the lock
+    async fn commit(&mut self, archive: &AuditArchive, event_ids: &[i64]) -> Result<()>;
+}
+
+pub(crate) struct PostgresS3Store {
+    pool: Pool<Postgres>,
+    s3: S3Client,
+    bucket: String,
+    tx: Option<Transaction<'static, Postgres>>,
+}
+
+impl ArchiveStore for PostgresS3Store {
+    async fn begin(
+        &mut self,
+        cutoff: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Option<(Vec<StoredAuditEvent>, Option<AuditArchive>)>> {
+        let mut tx = self.pool.begin().await?;
+        if !audit::try_lock_audit_archival(&mut tx).await? {
+            return Ok(None);
+        }
+        let events = audit::get_expired_audit_events(&mut tx, cutoff, limit).await?;
+        let last = audit::get_last_audit_archive(&mut tx).await?;
+        self.tx = Some(tx);
+        Ok(Some((events, last)))
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self
+            .s3
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to read audit archive '{key}'"))?;
+        Ok(response.body.collect().await?.into_bytes().to_vec())
+    }
+
+    async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        let request = self
+            .s3
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type("application/gzip")
+            .body(ByteStream::from(body));
+        put_object_with_sse(request, server_side_encryption())
+            .send()
+            .await
+            .with_context(|| format!("Failed to write audit archive '{key}'"))?;
+        Ok(())
+    }
+
+    async fn commit(&mut self, archive: &AuditArchive, event_ids: &[i64]) -> Result<()> {
+        let mut tx = self
+            .tx
+            .take()
+            .context("audit archive committed without a batch")?;
+        audit::commit_audit_archive(&mut tx, archive, event_ids).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Gzipped NDJSON of `events`
+pub(crate) fn encode_archive(events: &[StoredAuditEvent]) -> Result<Vec<u8>> {
+    let mut ndjson = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut ndjson, event)?;
+        ndjson.push(b'\n');
+    }
+    BodyCompression::Gzip.encode(&ndjson)
+}
+
+pub(crate) fn decode_archive(body: &[u8]) -> Result<Vec<StoredAuditEvent>> {
+    let ndjson = BodyCompression::Gzip.decode(body)?;
+    ndjson
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).context("Invalid audit archive line"))
+        .collect()
+}
+
+/// Digest linking an archive object to its predecessor
+fn chain_digest(prev_digest: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_digest.as_bytes());
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Archive record and object for `events` (non-empty, oldest first),
+/// chained onto `prev`
+pub(crate) fn build_archive(
+    events: &[StoredAuditEvent],
+    prev: Option<&AuditArchive>,
+    prefix: &str,
+) -> Result<(AuditArchive, Vec<u8>)> {
+    let (Some(first), Some(last)) = (events.first(), events.last()) else {
+        bail!("cannot archive an empty batch");
+    };
+    let body = encode_archive(events)?;
+    let prev_digest = prev.map_or(GENESIS_DIGEST, |p| p.digest.as_str());
+    let archive = AuditArchive {
+        object_key: format!(
+            "{prefix}audit-events-{:020}-{:020}.ndjson.gz",
+            first.audit_event_id, last.audit_event_id
+        ),
+        first_event_id: first.audit_event_id,
+        last_event_id: last.audit_event_id,
+        event_count: i32::try_from(events.len()).context("audit archive batch too large")?,
+        digest: chain_digest(prev_digest, &body),
+        prev_digest: prev_digest.to_string(),
+        last_event_hash: last.hash.clone(),
+    };
+    Ok((archive, body))
+}
+
+/// Check that `archives` (oldest first, with their objects) chain from
+/// `prev_digest`, that each object matches its digest and record, and that
+/// event ids only move forward. Reports the first broken link.
+pub(crate) fn verify_archive_chain(
+    prev_digest: &str,
+    archives: &[(AuditArchive, Vec<u8>)],
+) -> Result<()> {
+    let mut prev_digest = prev_digest.to_string();
+    let mut prev_last_id = None;
+
+    for (archive, body) in archives {
+        let key = &archive.object_key;
+        if archive.prev_digest != prev_digest {
+            bail!("audit archive '{key}' does not follow the previous archive");
+        }
+        if chain_digest(&prev_digest, body) != archive.digest {
+            bail!("audit archive '{key}' does not match its digest");
+        }
+        let events = decode_archive(body)?;
+        let ids: Vec<i64> = events.iter().map(|e| e.audit_event_id).collect();
+        if ids.len() != archive.event_count as usize
+            || ids.first() != Some(&archive.first_event_id)
+            || ids.last() != Some(&archive.last_event_id)
+            || ids.windows(2).any(|w| w[0] >= w[1])
+            || prev_last_id.is_some_and(|last| archive.first_event_id <= last)
+        {
+            bail!("audit archive '{key}' does not match its recorded event range");
+        }
+        prev_last_id = Some(archive.last_event_id);
+        prev_digest.clone_from(&archive.digest);
+    }
+    Ok(())
+}
+
+/// Move every event older than `cutoff` into archives, `batch_size` at a
+/// time. Returns the number of events archived.
+pub(crate) async fn archive_expired_events<S: ArchiveStore>(
+    store: &mut S,
+    cutoff: DateTime<Utc>,
+    batch_size: i64,
+    prefix: &str,
+) -> Result<usize> {
+    let mut archived = 0;
+    let mut verified_digest: Option<String> = None;
+
+    loop {
+        let Some((events, last)) = store.begin(cutoff, batch_size).await? else {
+            info!("Audit archival is running on another replica");
+            break;
+        };
+        if events.is_empty() {
+            break;
+        }
+
+        // Only archives this pass didn't write itself need re-reading
+        if let Some(tail) = &last
+            && verified_digest.as_deref() != Some(tail.digest.as_str())
+        {
+            let body = store.get_object(&tail.object_key).await?;
+            verify_archive_chain(&tail.prev_digest, &[(tail.clone(), body)])
+                .context("Refusing to extend the audit archive chain")?;
+        }
+
+        let (archive, body) = build_archive(&events, last.as_ref(), prefix)?;
+        store.put_object(&archive.object_key, body).await?;
+        let event_ids: Vec<i64> = events.iter().map(|e| e.audit_event_id).collect();
+        store.commit(&archive, &event_ids).await?;
+
+        archived += events.len();
+        verified_digest = Some(archive.digest);
+        if (events.len() as i64) < batch_size {
+            break;
+        }
+    }
+    Ok(archived)
+}
+
+/// Start the periodic archival pass; None when retention is disabled
+pub(crate) fn start_audit_retention(
+    pool: Pool<Postgres>,
+    s3: S3Client,
+    bucket: String,
+    config: AuditRetentionConfig,
+) -> Option<JoinHandle<()>> {
+    let retention = chrono::Duration::from_std(config.retention?).ok()?;
+    info!(
+        retention_days = retention.num_days(),
+        "Audit event retention enabled"
+    );
+
+    let mut store = PostgresS3Store {
+        pool,
+        s3,
+        bucket,
+        tx: None,
+    };
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            let cutoff = Utc::now() - retention;
+            match archive_expired_events(&mut store, cutoff, config.batch_size, &config.prefix)
+                .await
+            {
+                Ok(0) => {}
+                Ok(archived) => info!(archived, "Archived expired audit events to S3"),
+                Err(e) => error!(error = %format!("{e:#}"), "Audit event archival failed"),
+            }
+            store.tx = None;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit_chain::{GENESIS_HASH, event_hash, verify_links};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        events: Vec<StoredAuditEvent>,
+        archives: Vec<AuditArchive>,
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl ArchiveStore for MemoryStore {
+        async fn begin(
+            &mut self,
+            cutoff: DateTime<Utc>,
+            limit: i64,
+        ) -> Result<Option<(Vec<StoredAuditEvent>, Option<AuditArchive>)>> {
+            let events = self
+                .events
+                .iter()
+                .take_while(|e| e.timestamp < cutoff)
+                .take(limit as usize)
+                .cloned()
+                .collect();
+            Ok(Some((events, self.archives.last().cloned())))
+        }
+
+        async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+            self.objects
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .context("no such object")
+        }
+
+        async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<()> {
+            self.objects.lock().unwrap().insert(key.to_string(), body);
+            Ok(())
+        }
+
+        async fn commit(&mut self, archive: &AuditArchive, event_ids: &[i64]) -> Result<()> {
+            self.archives.push(archive.clone());
+            self.events
+                .retain(|e| !event_ids.contains(&e.audit_event_id));
+            Ok(())
+        }
+    }
+
+    impl MemoryStore {
+        fn chain(&self) -> Vec<(AuditArchive, Vec<u8>)> {
+            let objects = self.objects.lock().unwrap();
+            self.archives
+                .iter()
+                .map(|a| (a.clone(), objects[&a.object_key].clone()))
+                .collect()
+        }
+    }
+
+    fn event(id: i64, age_days: i64) -> StoredAuditEvent {
+        StoredAuditEvent {
+            audit_event_id: id,
+            timestamp: Utc::now() - chrono::Duration::days(age_days),
+            event_type: "ResourceRead".to_string(),
+            outcome: "Success".to_string(),
+            user_id: "u1".to_string(),
+            username_display: "alice".to_string(),
+            resource_type: Some("Collection".to_string()),
+            resource_id: Some(id.to_string()),
+            details: None,
+            request_id: None,
+            event_id: None,
+            prev_hash: None,
+            hash: None,
+        }
+    }
+
+    fn cutoff() -> DateTime<Utc> {
+        Utc::now() - chrono::Duration::days(90)
+    }
+
+    #[tokio::test]
+    async fn test_expired_events_are_archived_and_removed() {
+        let events = vec![
+            event(1, 400),
+            event(2, 300),
+            event(3, 200),
+            event(4, 120),
+            event(5, 91),
+            event(6, 30),
+            event(7, 1),
+        ];
+        let mut store = MemoryStore {
+            events: events.clone(),
+            ..Default::default()
+        };
+
+        let archived = archive_expired_events(&mut store, cutoff(), 2, "audit/")
+            .await
+            .unwrap();
+
+        assert_eq!(archived, 5);
+        let remaining: Vec<i64> = store.events.iter().map(|e| e.audit_event_id).collect();
+        assert_eq!(remaining, vec![6, 7]);
+        assert_eq!(store.archives.len(), 3);
+        assert_eq!(
+            store.archives[0].object_key,
+            "audit/audit-events-00000000000000000001-00000000000000000002.ndjson.gz"
+        );
+
+        let chain = store.chain();
+        let restored: Vec<StoredAuditEvent> = chain
+            .iter()
+            .flat_map(|(_, body)| decode_archive(body).unwrap())
+            .collect();
+        assert_eq!(restored, events[..5]);
+        verify_archive_chain(GENESIS_DIGEST, &chain).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chain_verifies_across_passes() {
+        let mut store = MemoryStore {
+            events: vec![event(1, 200), event(2, 100), event(3, 10)],
+            ..Default::default()
+        };
+        archive_expired_events(&mut store, cutoff(), 10, "audit/")
+            .await
+            .unwrap();
+
+        // Time passes: event 3 ages out and new events arrive
+        store.events.push(event(4, 1));
+        let later = Utc::now();
+        let archived = archive_expired_events(&mut store, later, 10, "audit/")
+            .await
+            .unwrap();
+        assert_eq!(archived, 2);
+        assert!(store.events.is_empty());
+
+        let chain = store.chain();
+        assert_eq!(chain[1].0.prev_digest, chain[0].0.digest);
+        verify_archive_chain(GENESIS_DIGEST, &chain).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_events_out_of_timestamp_order_keep_the_chain_intact() {
+        // Clock skew between replicas: event 3 was written with an older
+        // timestamp than event 2
+        let mut prev = GENESIS_HASH.to_string();
+        let events: Vec<StoredAuditEvent> = [(1, 200), (2, 10), (3, 150), (4, 1)]
+            .into_iter()
+            .map(|(id, age_days)| {
+                let mut e = event(id, age_days);
+                e.hash = Some(event_hash(&prev, &e));
+                e.prev_hash = Some(std::mem::replace(&mut prev, e.hash.clone().unwrap()));
+                e
+            })
+            .collect();
+        let mut store = MemoryStore {
+            events,
+            ..Default::default()
+        };
+
+        let archived = archive_expired_events(&mut store, cutoff(), 10, "audit/")
+            .await
+            .unwrap();
+
+        // Event 3 waits for event 2 rather than leaving a gap in the chain
+        assert_eq!(archived, 1);
+        let remaining: Vec<i64> = store.events.iter().map(|e| e.audit_event_id).collect();
+        assert_eq!(remaining, vec![2, 3, 4]);
+        let mut prev_hash = store.archives.last().unwrap().last_event_hash.clone();
+        verify_links(&mut prev_hash, &store.events).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tampered_or_missing_archives_break_the_chain() {
+        let mut store = MemoryStore {
+            events: (1..=6).map(|id| event(id, 365)).collect(),
+            ..Default::default()
+        };
+        archive_expired_events(&mut store, cutoff(), 2, "audit/")
+            .await
+            .unwrap();
+        let chain = store.chain();
+
+        // An archive rewritten without event 3
+        let mut edited = chain.clone();
+        edited[1].1 = encode_archive(&[event(4, 365)]).unwrap();
+        let err = verify_archive_chain(GENESIS_DIGEST, &edited).unwrap_err();
+        assert!(
+            err.to_string().contains("does not match its digest"),
+            "{err}"
+        );
+
+        // A whole archive removed
+        let missing = vec![chain[0].clone(), chain[2].clone()];
+        let err = verify_archive_chain(GENESIS_DIGEST, &missing).unwrap_err();
+        assert!(err.to_string().contains("does not follow"), "{err}");
+
+        // A later pass refuses to build on a tampered tail
+        let tail_key = store.archives.last().unwrap().object_key.clone();
+        store
+            .objects
+            .lock()
+            .unwrap()
+            .insert(tail_key, encode_archive(&[event(5, 365)]).unwrap());
+        store.events.push(event(7, 365));
+        assert!(
+            archive_expired_events(&mut store, cutoff(), 2, "audit/")
+                .await
+                .is_err()
+        );
+        assert_eq!(store.events.len(), 1);
+    }
+}
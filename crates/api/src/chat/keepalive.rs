@@ -0,0 +1,104 @@
+//! SSE keep-alive pings for streamed chat responses.
+//!
+//! Models can pause for a long time before (or between) tokens, and proxies
+//! close connections that stay idle for too long. [`with_keepalive`] merges
+//! the token stream with a ping interval: a ping is emitted whenever no token
+//! arrived for a full period, and every token restarts the period, so pings
+//! only fill gaps. Pings are SSE comment lines, which `EventSource` clients
+//! ignore.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use tokio::time::{Instant, Interval, MissedTickBehavior, interval_at};
+
+/// SSE comment sent while waiting for tokens
+pub(crate) const PING: &str = ": ping\n\n";
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum KeepAliveEvent<T> {
+    Item(T),
+    Ping,
+}
+
+pub(crate) struct KeepAlive<S> {
+    inner: S,
+    ping: Interval,
+}
+
+/// `stream`'s items, with a [`KeepAliveEvent::Ping`] after each `period`
+/// without one. Ends when `stream` ends.
+pub(crate) fn with_keepalive<S: Stream + Unpin>(stream: S, period: Duration) -> KeepAlive<S> {
+    let mut ping = interval_at(Instant::now() + period, period);
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    KeepAlive {
+        inner: stream,
+        ping,
+    }
+}
+
+impl<S: Stream + Unpin> Stream for KeepAlive<S> {
+    type Item = KeepAliveEvent<S::Item>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.inner.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => {
+                // Data is flowing; the next ping is a full period away
+                self.ping.reset();
+                Poll::Ready(Some(KeepAliveEvent::Item(item)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => self.ping.poll_tick(cx).map(|_| Some(KeepAliveEvent::Ping)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pings_fill_gaps_between_tokens() {
+        let tokens = Box::pin(async_stream::stream! {
+            tokio::time::sleep(Duration::from_millis(130)).await;
+            yield "first";
+            yield "second";
+            tokio::time::sleep(Duration::from_millis(130)).await;
+            yield "third";
+        });
+
+        let events: Vec<_> = with_keepalive(tokens, Duration::from_millis(50))
+            .collect()
+            .await;
+
+        // Pings while waiting for the first token, none between tokens that
+        // arrive back to back, and pings again during the next pause
+        let first = events
+            .iter()
+            .position(|e| *e == KeepAliveEvent::Item("first"))
+            .unwrap();
+        assert!(first >= 1);
+        assert!(events[..first].iter().all(|e| *e == KeepAliveEvent::Ping));
+        assert_eq!(events[first + 1], KeepAliveEvent::Item("second"));
+        assert_eq!(events[first + 2], KeepAliveEvent::Ping);
+        assert_eq!(events.last(), Some(&KeepAliveEvent::Item("third")));
+    }
+
+    #[tokio::test]
+    async fn test_fast_stream_gets_no_pings() {
+        let tokens = futures_util::stream::iter(["a", "b", "c"]);
+        let events: Vec<_> = with_keepalive(tokens, Duration::from_millis(50))
+            .collect()
+            .await;
+        assert_eq!(
+            events,
+            vec![
+                KeepAliveEvent::Item("a"),
+                KeepAliveEvent::Item("b"),
+                KeepAliveEvent::Item("c"),
+            ]
+        );
+    }
+}
@@ -4,10 +4,22 @@ use semantic_explorer_core::http_client::HTTP_CLIENT;
 use sqlx::{Pool, Postgres};
 use std::pin::Pin;
 
+use crate::chat::usage::{TokenUsage, estimate_tokens};
 use crate::{chat::models::ChatMessage, storage::postgres::chat as chat_storage};
 
+/// Type alias for streamed LLM response chunks
+type LLMStream = Pin<Box<dyn Stream<Item = Result<String, String>> + Send>>;
+
 /// Type alias for streaming LLM response results
-type LLMStreamResult = Result<Pin<Box<dyn Stream<Item = Result<String, String>> + Send>>, String>;
+type LLMStreamResult = Result<LLMStream, String>;
+
+/// A streamed response together with the estimated size of the prompt that
+/// produced it. Streaming providers don't report usage, so completion tokens
+/// are estimated from the chunks as they arrive.
+pub(crate) struct LLMResponseStream {
+    pub(crate) stream: LLMStream,
+    pub(crate) prompt_tokens: i64,
+}
 
 /// Default system prompt for RAG chat
 /// Uses {{chunks}} placeholder which gets replaced with retrieved document chunks
@@ -56,7 +68,7 @@ pub(crate) async fn generate_response(
     temperature: Option<f32>,
     max_tokens: Option<i32>,
     system_prompt: Option<&str>,
-) -> Result<String, String> {
+) -> Result<(String, TokenUsage), String> {
     let llm_start = std::time::Instant::now();
 
     // Check for injection attempts in user query
@@ -101,7 +113,7 @@ pub(crate) async fn generate_response(
     let max_tokens = max_tokens.unwrap_or(2000).max(1);
 
     // Call the appropriate LLM API
-    let response_text = match provider.to_lowercase().as_str() {
+    let (response_text, usage) = match provider.to_lowercase().as_str() {
         "internal" => {
             // Get conversation history for internal LLM
             let history = chat_storage::get_chat_messages(pool, session_id)
@@ -160,8 +172,30 @@ pub(crate) async fn generate_response(
     let llm_duration = llm_start.elapsed().as_secs_f64();
     semantic_explorer_core::observability::record_llm_response(&model, llm_duration, true);
 
-    tracing::debug!(llm_name = %name, "generated LLM response");
-    Ok(response_text)
+    // Fill in whatever the provider didn't report
+    let prompt_estimate =
+        || estimate_tokens(&effective_system_prompt) + estimate_tokens(&user_prompt);
+    let usage = TokenUsage {
+        prompt_tokens: usage.prompt_tokens.unwrap_or_else(prompt_estimate),
+        completion_tokens: usage
+            .completion_tokens
+            .unwrap_or_else(|| estimate_tokens(&response_text)),
+    };
+
+    tracing::debug!(
+        llm_name = %name,
+        prompt_tokens = usage.prompt_tokens,
+        completion_tokens = usage.completion_tokens,
+        "generated LLM response"
+    );
+    Ok((response_text, usage))
+}
+
+/// Token counts as reported by a provider, if it reports them
+#[derive(Debug, Default)]
+struct ReportedUsage {
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
 }
 
 /// Call OpenAI API for chat completion
@@ -173,7 +207,7 @@ async fn call_openai_api(
     user_prompt: &str,
     temperature: f32,
     max_tokens: i32,
-) -> Result<String, String> {
+) -> Result<(String, ReportedUsage), String> {
     let api_key = api_key.ok_or_else(|| "API key not configured for OpenAI".to_string())?;
 
     let client = &*HTTP_CLIENT;
@@ -215,7 +249,17 @@ async fn call_openai_api(
         .ok_or_else(|| "unexpected OpenAI response format".to_string())?
         .to_string();
 
-    Ok(response_text)
+    let usage = response_json.get("usage");
+    let usage = ReportedUsage {
+        prompt_tokens: usage
+            .and_then(|u| u.get("prompt_tokens"))
+            .and_then(|t| t.as_i64()),
+        completion_tokens: usage
+            .and_then(|u| u.get("completion_tokens"))
+            .and_then(|t| t.as_i64()),
+    };
+
+    Ok((response_text, usage))
 }
 
 /// Call internal LLM inference API for chat completion
@@ -227,7 +271,7 @@ async fn call_internal_llm_api(
     history: &[ChatMessage],
     temperature: f32,
     max_tokens: i32,
-) -> Result<String, String> {
+) -> Result<(String, ReportedUsage), String> {
     // Build messages with conversation history
     let mut messages = vec![crate::llms::client::ChatMessage {
         role: "system".to_string(),
@@ -252,6 +296,10 @@ async fn call_internal_llm_api(
         content: user_prompt.to_string(),
     });
 
+    // The inference API only reports generated tokens; the prompt, history
+    // included, is estimated
+    let prompt_tokens = messages.iter().map(|m| estimate_tokens(&m.content)).sum();
+
     // Use the llm_client to call the internal inference API
     let response = crate::llms::client::chat_completion(
         llm_inference_url,
@@ -265,7 +313,11 @@ async fn call_internal_llm_api(
     .await
     .map_err(|e| format!("Internal LLM API error: {}", e))?;
 
-    Ok(response.message.content)
+    let usage = ReportedUsage {
+        prompt_tokens: Some(prompt_tokens),
+        completion_tokens: Some(response.tokens_generated as i64),
+    };
+    Ok((response.message.content, usage))
 }
 
 /// Call internal LLM inference API for streaming chat completion
@@ -336,7 +388,7 @@ async fn call_cohere_api(
     prompt: &str,
     temperature: f32,
     max_tokens: i32,
-) -> Result<String, String> {
+) -> Result<(String, ReportedUsage), String> {
     let api_key = api_key.ok_or_else(|| "API key not configured for Cohere".to_string())?;
 
     let client = &*HTTP_CLIENT;
@@ -385,7 +437,20 @@ async fn call_cohere_api(
         })?
         .to_string();
 
-    Ok(response_text)
+    // Cohere format: usage.billed_units.{input_tokens,output_tokens}
+    let billed = response_json
+        .get("usage")
+        .and_then(|u| u.get("billed_units"));
+    let usage = ReportedUsage {
+        prompt_tokens: billed
+            .and_then(|b| b.get("input_tokens"))
+            .and_then(|t| t.as_i64()),
+        completion_tokens: billed
+            .and_then(|b| b.get("output_tokens"))
+            .and_then(|t| t.as_i64()),
+    };
+
+    Ok((response_text, usage))
 }
 
 /// Generate a streaming LLM response with RAG context
@@ -405,7 +470,7 @@ pub(crate) async fn generate_response_stream(
     temperature: Option<f32>,
     max_tokens: Option<i32>,
     system_prompt: Option<&str>,
-) -> LLMStreamResult {
+) -> Result<LLMResponseStream, String> {
     // Fetch LLM details from database
     let (name, provider, base_url, model, api_key) =
         chat_storage::get_llm_details(pool, encryption, llm_id)
@@ -423,6 +488,7 @@ pub(crate) async fn generate_response_stream(
 
     let temperature = temperature.unwrap_or(0.7).clamp(0.0, 2.0);
     let max_tokens = max_tokens.unwrap_or(2000).max(1);
+    let prompt_tokens = estimate_tokens(&effective_system_prompt) + estimate_tokens(&user_prompt);
 
     tracing::debug!(llm_name = %name, provider = %provider, "starting streaming LLM response");
 
@@ -432,6 +498,12 @@ pub(crate) async fn generate_response_stream(
         let history = chat_storage::get_chat_messages(pool, session_id)
             .await
             .unwrap_or_default();
+        let prompt_tokens = prompt_tokens
+            + history
+                .iter()
+                .filter(|msg| msg.status == "complete")
+                .map(|msg| estimate_tokens(&msg.content))
+                .sum::<i64>();
         // Use internal LLM inference API streaming
         let stream = call_internal_llm_api_stream(
             llm_inference_url,
@@ -443,7 +515,10 @@ pub(crate) async fn generate_response_stream(
             max_tokens,
         )
         .await?;
-        return Ok(stream);
+        return Ok(LLMResponseStream {
+            stream,
+            prompt_tokens,
+        });
     }
 
     // Build config for the LLM request (for other providers)
@@ -510,7 +585,10 @@ pub(crate) async fn generate_response_stream(
         }
     };
 
-    Ok(Box::pin(stream))
+    Ok(LLMResponseStream {
+        stream: Box::pin(stream),
+        prompt_tokens,
+    })
 }
 
 /// Make a streaming request to an LLM provider
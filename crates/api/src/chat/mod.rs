@@ -1,4 +1,6 @@
+pub(crate) mod keepalive;
 pub(crate) mod llm;
 pub(crate) mod models;
 pub(crate) mod prompt_injection;
 pub(crate) mod rag;
+pub(crate) mod usage;
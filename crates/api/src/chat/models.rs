@@ -11,6 +11,12 @@ pub(crate) struct ChatSession {
     pub embedded_dataset_id: i32,
     pub llm_id: i32,
     pub title: String,
+    /// Prompt tokens sent to the LLM over the session's lifetime
+    pub prompt_tokens: i64,
+    /// Completion tokens generated over the session's lifetime
+    pub completion_tokens: i64,
+    /// Maximum prompt + completion tokens; unlimited when absent
+    pub token_budget: Option<i64>,
     #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTime<Utc>,
     #[schema(value_type = String, format = DateTime)]
@@ -95,6 +101,10 @@ pub(crate) struct CreateChatSessionRequest {
     pub llm_id: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// Maximum prompt + completion tokens the session may use; unlimited
+    /// when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,5 +1,6 @@
 use crate::chat::models::{RAGConfig, RetrievedDocument};
 use crate::embedding::generate_embedding;
+use crate::search::distance_metric_of;
 use crate::storage::postgres::{embedded_datasets, embedders};
 use once_cell::sync::Lazy;
 use qdrant_client::qdrant::SearchPointsBuilder;
@@ -62,7 +63,7 @@ pub async fn retrieve_documents(
     let _ = dimensions;
     debug!(collection = %collection_name, embedder_id = embedder_id, "retrieving documents from Qdrant");
 
-    let _collection_info = qdrant_client
+    let collection_info = qdrant_client
         .collection_info(&collection_name)
         .await
         .map_err(|e| {
@@ -87,13 +88,17 @@ pub async fn retrieve_documents(
         format!("Embedding service unavailable: {e}. Please try again later.")
     })?;
 
-    let search_builder = SearchPointsBuilder::new(
+    // min_similarity_score is a similarity; it doesn't apply to distances
+    let metric = distance_metric_of(&collection_info);
+    let mut search_builder = SearchPointsBuilder::new(
         &collection_name,
         query_embedding,
         config.max_context_documents as u64,
     )
-    .score_threshold(config.min_similarity_score)
     .with_payload(true);
+    if metric.higher_is_better() {
+        search_builder = search_builder.score_threshold(config.min_similarity_score);
+    }
 
     let search_response = qdrant_client
         .search_points(search_builder)
@@ -0,0 +1,173 @@
+//! Token accounting and budgets for chat sessions.
+//!
+//! Each LLM call adds its prompt and completion tokens to the session row.
+//! Providers that report usage (the internal inference API's
+//! `tokens_generated`, OpenAI's and Cohere's `usage`) are taken at their
+//! word; anything they don't report is estimated from text length. A session
+//! created with a `token_budget` stops accepting messages once its total
+//! reaches the budget, and each response's `max_tokens` is capped to what is
+//! left so a single answer can't run far past it.
+
+use sqlx::{Pool, Postgres};
+
+use crate::chat::models::ChatSession;
+use crate::errors::ApiError;
+use crate::storage::postgres::chat;
+
+/// Rough characters per token for English text
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct TokenUsage {
+    pub(crate) prompt_tokens: i64,
+    pub(crate) completion_tokens: i64,
+}
+
+impl TokenUsage {
+    /// Usage of a streamed response, whose completion is estimated from the
+    /// text received so far
+    pub(crate) fn streamed(prompt_tokens: i64, completion: &str) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens: estimate_tokens(completion),
+        }
+    }
+}
+
+/// Estimated token count of `text`, for providers that don't report usage
+pub(crate) fn estimate_tokens(text: &str) -> i64 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as i64
+}
+
+/// Tokens left in the session's budget; None when the session is unlimited
+pub(crate) fn remaining_tokens(session: &ChatSession) -> Option<i64> {
+    session
+        .token_budget
+        .map(|budget| (budget - session.prompt_tokens - session.completion_tokens).max(0))
+}
+
+/// Reject a new message once the session has used up its budget
+pub(crate) fn check_budget(session: &ChatSession) -> Result<(), ApiError> {
+    match (session.token_budget, remaining_tokens(session)) {
+        (Some(budget), Some(0)) => Err(ApiError::TokenBudgetExceeded {
+            used: session.prompt_tokens + session.completion_tokens,
+            budget,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// `max_tokens` for the next response, capped to the remaining budget
+pub(crate) fn cap_max_tokens(requested: Option<i32>, remaining: Option<i64>) -> Option<i32> {
+    match remaining {
+        Some(remaining) => {
+            let remaining = i32::try_from(remaining).unwrap_or(i32::MAX).max(1);
+            Some(requested.map_or(remaining, |requested| requested.min(remaining)))
+        }
+        None => requested,
+    }
+}
+
+/// Add `usage` to the session's totals. A failed update is logged rather
+/// than failing a response the user already has.
+pub(crate) async fn record_usage(pool: &Pool<Postgres>, session_id: &str, usage: TokenUsage) {
+    if let Err(e) = chat::add_chat_session_usage(
+        pool,
+        session_id,
+        usage.prompt_tokens,
+        usage.completion_tokens,
+    )
+    .await
+    {
+        tracing::error!(error = %e, session_id, "failed to record chat token usage");
+    }
+}
+
+/// Validate a budget requested at session creation
+pub(crate) fn validate_budget(budget: Option<i64>) -> Result<(), ApiError> {
+    match budget {
+        Some(budget) if budget <= 0 => Err(ApiError::BadRequest(
+            "token_budget must be a positive number of tokens".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use actix_web::http::StatusCode;
+    use chrono::Utc;
+
+    fn session(
+        prompt_tokens: i64,
+        completion_tokens: i64,
+        token_budget: Option<i64>,
+    ) -> ChatSession {
+        ChatSession {
+            session_id: "s-1".to_string(),
+            owner_id: "alice".to_string(),
+            owner_display_name: "Alice".to_string(),
+            embedded_dataset_id: 1,
+            llm_id: 1,
+            title: "chat".to_string(),
+            prompt_tokens,
+            completion_tokens,
+            token_budget,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_budget_is_enforced_once_used_up() {
+        // Unlimited sessions never hit a budget
+        assert!(check_budget(&session(1_000_000, 1_000_000, None)).is_ok());
+        assert_eq!(remaining_tokens(&session(10, 10, None)), None);
+
+        let within = session(600, 300, Some(1000));
+        assert!(check_budget(&within).is_ok());
+        assert_eq!(remaining_tokens(&within), Some(100));
+
+        let exhausted = session(700, 350, Some(1000));
+        assert_eq!(remaining_tokens(&exhausted), Some(0));
+        let err = check_budget(&exhausted).unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(matches!(
+            err,
+            ApiError::TokenBudgetExceeded {
+                used: 1050,
+                budget: 1000
+            }
+        ));
+    }
+
+    #[test]
+    fn test_max_tokens_is_capped_to_remaining_budget() {
+        assert_eq!(cap_max_tokens(Some(2000), None), Some(2000));
+        assert_eq!(cap_max_tokens(None, None), None);
+        assert_eq!(cap_max_tokens(Some(2000), Some(150)), Some(150));
+        assert_eq!(cap_max_tokens(Some(50), Some(150)), Some(50));
+        assert_eq!(cap_max_tokens(None, Some(150)), Some(150));
+    }
+
+    #[test]
+    fn test_estimate_and_validation() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(
+            TokenUsage::streamed(40, "abcdefgh"),
+            TokenUsage {
+                prompt_tokens: 40,
+                completion_tokens: 2
+            }
+        );
+        assert!(validate_budget(None).is_ok());
+        assert!(validate_budget(Some(1)).is_ok());
+        assert!(matches!(
+            validate_budget(Some(0)),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
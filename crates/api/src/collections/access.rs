@@ -0,0 +1,57 @@
+//! Visibility enforcement for collections and their files.
+//!
+//! Owners can always read their collections. Other users can read (view,
+//! list files, download files) public collections and are refused with 403
+//! on private ones. Changes stay owner-only and keep using owner-scoped
+//! lookups.
+
+use crate::collections::models::Collection;
+use crate::errors::ApiError;
+
+/// Check that `owner_id` may read `collection`.
+pub(crate) fn check_read_access(collection: &Collection, owner_id: &str) -> Result<(), ApiError> {
+    if collection.owner_id == owner_id || collection.is_public {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(format!(
+            "Collection '{}' is private",
+            collection.collection_id
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use actix_web::http::StatusCode;
+
+    fn collection(owner_id: &str, is_public: bool) -> Collection {
+        Collection {
+            collection_id: 7,
+            title: "docs".to_string(),
+            details: None,
+            owner_id: owner_id.to_string(),
+            owner_display_name: "Owner".to_string(),
+            tags: Vec::new(),
+            is_public,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_owner_can_read_public_and_private() {
+        assert!(check_read_access(&collection("alice", true), "alice").is_ok());
+        assert!(check_read_access(&collection("alice", false), "alice").is_ok());
+    }
+
+    #[test]
+    fn test_non_owner_reads_public_but_not_private() {
+        assert!(check_read_access(&collection("alice", true), "bob").is_ok());
+
+        let err = check_read_access(&collection("alice", false), "bob").unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(err.error_response().status(), StatusCode::FORBIDDEN);
+    }
+}
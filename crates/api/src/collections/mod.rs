@@ -1 +1,3 @@
+pub(crate) mod access;
 pub(crate) mod models;
+pub(crate) mod reconcile;
@@ -1,4 +1,4 @@
-use actix_multipart::form::{MultipartForm, tempfile::TempFile};
+use actix_multipart::form::{MultipartForm, tempfile::TempFile, text::Text};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
@@ -55,11 +55,55 @@ impl Collection {
     }
 }
 
-#[derive(MultipartForm, ToSchema)]
+/// Multipart body of a collection upload, for the API docs. The handler
+/// streams each `files` field to storage instead of parsing this form.
+#[derive(ToSchema)]
 pub(crate) struct CollectionUpload {
-    #[multipart(rename = "files", limit = "1024MB")]
+    #[allow(dead_code)]
     #[schema(value_type = Vec<String>, format = Binary)]
-    pub(crate) files: Vec<TempFile>,
+    pub(crate) files: Vec<Vec<u8>>,
+}
+
+/// File a client wants to upload directly to the object store
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct PresignedUploadRequest {
+    /// Name of the file inside the collection (no path separators)
+    pub(crate) file_name: String,
+    /// Size of the file in bytes; signed into the URL as its Content-Length
+    /// and held to `S3_MAX_UPLOAD_SIZE_BYTES`
+    pub(crate) size: i64,
+    /// Content type to store; guessed from the file name if omitted
+    pub(crate) content_type: Option<String>,
+}
+
+/// Single file to extract without storing it. Kept small because the file
+/// travels to the worker inside one NATS message.
+#[derive(MultipartForm, ToSchema)]
+pub(crate) struct ExtractionPreviewUpload {
+    #[multipart(limit = "512KB")]
+    #[schema(value_type = String, format = Binary)]
+    pub(crate) file: TempFile,
+    /// Extraction config as JSON (same shape as a collection transform's);
+    /// defaults apply if omitted
+    #[schema(value_type = Option<String>)]
+    pub(crate) extraction_config: Option<Text<String>>,
+}
+
+/// Extract a file already stored in the collection without running a transform
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct StoredExtractionPreview {
+    pub(crate) file_key: String,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub(crate) extraction_config: serde_json::Value,
+}
+
+/// Text and metadata a collection transform would extract from a file
+#[derive(Serialize, ToSchema)]
+pub(crate) struct ExtractionPreview {
+    pub(crate) text: String,
+    #[schema(value_type = Option<Object>)]
+    pub(crate) metadata: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -67,6 +111,10 @@ pub(crate) struct FileListQuery {
     #[serde(default = "default_page_size")]
     pub(crate) page_size: i32,
     pub(crate) continuation_token: Option<String>,
+    /// Folder inside the collection to list, e.g. `logs/2024/`
+    pub(crate) prefix: Option<String>,
+    /// Roll keys up into folders at this delimiter (usually `/`)
+    pub(crate) delimiter: Option<String>,
 }
 
 fn default_page_size() -> i32 {
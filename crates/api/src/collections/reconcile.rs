@@ -0,0 +1,261 @@
+//! Reconciliation of a collection's S3 objects with its processed-file records.
+//!
+//! The `transform_processed_files` rows of a collection's transforms can
+//! drift from the objects actually stored in S3: an upload that failed after
+//! the object was written leaves a file no transform has picked up, and a
+//! manual delete in S3 leaves records for a file that no longer exists.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::transforms::collection::models::{CollectionTransform, ProcessedFile};
+
+/// Whether reconciliation only reports drift or also repairs it
+#[derive(Deserialize, Serialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReconcileMode {
+    #[default]
+    Report,
+    /// Delete orphaned records and trigger scans for unprocessed objects
+    Heal,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct ReconcileFilesQuery {
+    #[serde(default)]
+    pub(crate) mode: ReconcileMode,
+}
+
+/// An S3 object that enabled transforms of the collection have no record of
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnrecordedObject {
+    pub(crate) file_key: String,
+    pub(crate) collection_transform_ids: Vec<i32>,
+}
+
+/// A processed-file record whose object is no longer in S3
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OrphanedRecord {
+    pub(crate) collection_transform_id: i32,
+    pub(crate) file_key: String,
+}
+
+#[derive(Serialize, ToSchema, Debug, Default)]
+pub(crate) struct FileReconciliationReport {
+    pub(crate) collection_id: i32,
+    pub(crate) mode: ReconcileMode,
+    pub(crate) object_count: usize,
+    pub(crate) record_count: usize,
+    pub(crate) unrecorded_objects: Vec<UnrecordedObject>,
+    pub(crate) orphaned_records: Vec<OrphanedRecord>,
+    /// Records deleted while healing
+    pub(crate) records_deleted: u64,
+    /// Transforms a scan was triggered for while healing
+    pub(crate) scans_triggered: Vec<i32>,
+}
+
+impl FileReconciliationReport {
+    /// Transforms that are missing records for at least one object
+    pub(crate) fn transforms_to_rescan(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self
+            .unrecorded_objects
+            .iter()
+            .flat_map(|object| object.collection_transform_ids.iter().copied())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Distinct file keys of orphaned records
+    pub(crate) fn orphaned_file_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .orphaned_records
+            .iter()
+            .map(|record| record.file_key.as_str())
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+}
+
+/// Compare the object keys in a collection's S3 folder with the processed
+/// file records of its transforms. Folder markers are ignored. Objects only
+/// count as unrecorded for enabled transforms, since disabled ones are not
+/// expected to keep up; orphaned records are reported for every transform.
+pub(crate) fn find_drift(
+    collection_id: i32,
+    mode: ReconcileMode,
+    object_keys: &[String],
+    transforms: &[CollectionTransform],
+    records: &[ProcessedFile],
+) -> FileReconciliationReport {
+    let objects: HashSet<&str> = object_keys
+        .iter()
+        .map(String::as_str)
+        .filter(|key| !key.ends_with('/'))
+        .collect();
+    let recorded: HashSet<(i32, &str)> = records
+        .iter()
+        .map(|record| (record.transform_id, record.file_key.as_str()))
+        .collect();
+
+    let mut unrecorded: BTreeMap<&str, Vec<i32>> = BTreeMap::new();
+    for transform in transforms.iter().filter(|t| t.is_enabled) {
+        for key in &objects {
+            if !recorded.contains(&(transform.collection_transform_id, key)) {
+                unrecorded
+                    .entry(key)
+                    .or_default()
+                    .push(transform.collection_transform_id);
+            }
+        }
+    }
+
+    let mut orphaned_records: Vec<OrphanedRecord> = records
+        .iter()
+        .filter(|record| !objects.contains(record.file_key.as_str()))
+        .map(|record| OrphanedRecord {
+            collection_transform_id: record.transform_id,
+            file_key: record.file_key.clone(),
+        })
+        .collect();
+    orphaned_records.sort_by(|a, b| {
+        (&a.file_key, a.collection_transform_id).cmp(&(&b.file_key, b.collection_transform_id))
+    });
+
+    FileReconciliationReport {
+        collection_id,
+        mode,
+        object_count: objects.len(),
+        record_count: records.len(),
+        unrecorded_objects: unrecorded
+            .into_iter()
+            .map(|(key, mut ids)| {
+                ids.sort_unstable();
+                UnrecordedObject {
+                    file_key: key.to_string(),
+                    collection_transform_ids: ids,
+                }
+            })
+            .collect(),
+        orphaned_records,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::chrono::Utc;
+
+    /// In-memory stand-in for the S3 folder and `transform_processed_files`
+    struct MockStore {
+        objects: Vec<String>,
+        transforms: Vec<CollectionTransform>,
+        records: Vec<ProcessedFile>,
+    }
+
+    impl MockStore {
+        fn new(objects: &[&str]) -> Self {
+            Self {
+                objects: objects.iter().map(|k| k.to_string()).collect(),
+                transforms: Vec::new(),
+                records: Vec::new(),
+            }
+        }
+
+        fn transform(mut self, id: i32, is_enabled: bool) -> Self {
+            self.transforms.push(CollectionTransform {
+                collection_transform_id: id,
+                title: format!("transform {id}"),
+                collection_id: 1,
+                dataset_id: 1,
+                owner_id: "owner".to_string(),
+                owner_display_name: "Owner".to_string(),
+                is_enabled,
+                chunk_size: 200,
+                job_config: serde_json::json!({}),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            });
+            self
+        }
+
+        fn record(mut self, transform_id: i32, file_key: &str) -> Self {
+            self.records.push(ProcessedFile {
+                id: self.records.len() as i32 + 1,
+                transform_type: "collection".to_string(),
+                transform_id,
+                file_key: file_key.to_string(),
+                processed_at: Utc::now(),
+                item_count: 1,
+                process_status: "completed".to_string(),
+                process_error: None,
+                processing_duration_ms: None,
+            });
+            self
+        }
+
+        fn reconcile(&self) -> FileReconciliationReport {
+            find_drift(
+                1,
+                ReconcileMode::Report,
+                &self.objects,
+                &self.transforms,
+                &self.records,
+            )
+        }
+    }
+
+    #[test]
+    fn test_detects_drift_in_both_directions() {
+        let store = MockStore::new(&["a.pdf", "docs/", "docs/new.txt"])
+            .transform(10, true)
+            .record(10, "a.pdf")
+            .record(10, "deleted.pdf");
+
+        let report = store.reconcile();
+        assert_eq!(report.object_count, 2);
+        assert_eq!(
+            report.unrecorded_objects,
+            vec![UnrecordedObject {
+                file_key: "docs/new.txt".to_string(),
+                collection_transform_ids: vec![10],
+            }]
+        );
+        assert_eq!(
+            report.orphaned_records,
+            vec![OrphanedRecord {
+                collection_transform_id: 10,
+                file_key: "deleted.pdf".to_string(),
+            }]
+        );
+        assert_eq!(report.transforms_to_rescan(), vec![10]);
+        assert_eq!(report.orphaned_file_keys(), vec!["deleted.pdf"]);
+    }
+
+    #[test]
+    fn test_disabled_transforms_only_report_orphans() {
+        let store = MockStore::new(&["a.pdf"])
+            .transform(10, true)
+            .transform(11, false)
+            .record(10, "a.pdf")
+            .record(11, "gone.pdf");
+
+        let report = store.reconcile();
+        assert!(report.unrecorded_objects.is_empty());
+        assert_eq!(report.orphaned_file_keys(), vec!["gone.pdf"]);
+
+        // In sync: nothing to report
+        let report = MockStore::new(&["a.pdf"])
+            .transform(10, true)
+            .record(10, "a.pdf")
+            .reconcile();
+        assert!(report.unrecorded_objects.is_empty());
+        assert!(report.orphaned_records.is_empty());
+    }
+}
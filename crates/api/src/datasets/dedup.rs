@@ -0,0 +1,312 @@
+//! Deduplication of imported dataset items.
+//!
+//! Items are always unique by title within a dataset, and a plain upload
+//! overwrites items with the same title. Re-importing overlapping data under
+//! different titles (e.g. file names that changed) creates duplicates, so an
+//! upload can instead match incoming items to existing ones by a key:
+//!
+//! - `title`
+//! - `metadata.<field>`: the value of a metadata field, e.g. a source id
+//! - `content_hash`: the content of the item's chunks
+//!
+//! Matched items are skipped, or updated in place when their chunks or
+//! metadata changed. Unchanged items are never rewritten, so they are not
+//! re-embedded. Within one upload, the first item with a key wins.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use utoipa::{IntoParams, ToSchema};
+
+use super::models::ChunkWithMetadata;
+
+/// An item to import: title, chunks and metadata
+pub(crate) type NewItem = (String, Vec<ChunkWithMetadata>, serde_json::Value);
+
+/// What identifies an item as a duplicate of an existing one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DedupKey {
+    Title,
+    MetadataField(String),
+    ContentHash,
+}
+
+impl DedupKey {
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "title" => Ok(DedupKey::Title),
+            "content_hash" => Ok(DedupKey::ContentHash),
+            other => match other.strip_prefix("metadata.") {
+                Some(field) if !field.is_empty() => Ok(DedupKey::MetadataField(field.to_string())),
+                _ => Err(format!(
+                    "invalid dedup_key '{other}': expected 'title', 'content_hash' or 'metadata.<field>'"
+                )),
+            },
+        }
+    }
+
+    /// Key of `item`, or `None` when it has none (a missing metadata field),
+    /// in which case it is imported without deduplication
+    pub(crate) fn key_of(&self, item: &NewItem) -> Option<String> {
+        let (title, chunks, metadata) = item;
+        match self {
+            DedupKey::Title => Some(title.clone()),
+            DedupKey::MetadataField(field) => metadata_key(metadata.get(field)?),
+            DedupKey::ContentHash => Some(content_hash(chunks)),
+        }
+    }
+}
+
+/// A metadata value as Postgres' `metadata->>'field'` renders it
+fn metadata_key(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Hash of the chunk contents, for spotting duplicates within an upload.
+/// Existing items are matched by their `content_hash` column instead.
+fn content_hash(chunks: &[ChunkWithMetadata]) -> String {
+    let mut hasher = Sha256::new();
+    for chunk in chunks {
+        hasher.update(chunk.content.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// What happens to items matching an existing one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DuplicateAction {
+    /// Leave the existing item as it is
+    Skip,
+    /// Replace the existing item's chunks and metadata if they changed
+    #[default]
+    Update,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct DedupParams {
+    /// `title`, `content_hash` or `metadata.<field>`; without it items are
+    /// upserted by title
+    pub(crate) dedup_key: Option<String>,
+    /// `skip` or `update` (default)
+    pub(crate) on_duplicate: Option<DuplicateAction>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DedupOptions {
+    pub(crate) key: DedupKey,
+    pub(crate) on_duplicate: DuplicateAction,
+}
+
+impl DedupParams {
+    /// Dedup options of the request, if dedup was asked for
+    pub(crate) fn options(&self) -> Result<Option<DedupOptions>, String> {
+        let Some(key) = self.dedup_key.as_deref() else {
+            if self.on_duplicate.is_some() {
+                return Err("on_duplicate requires dedup_key".to_string());
+            }
+            return Ok(None);
+        };
+        Ok(Some(DedupOptions {
+            key: DedupKey::parse(key)?,
+            on_duplicate: self.on_duplicate.unwrap_or_default(),
+        }))
+    }
+}
+
+/// Chunks and metadata of the existing item an incoming item matched
+#[derive(Debug, Clone)]
+pub(crate) struct ExistingItem {
+    pub(crate) item_id: i32,
+    pub(crate) chunks: serde_json::Value,
+    pub(crate) metadata: serde_json::Value,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct DedupPlan {
+    pub(crate) insert: Vec<NewItem>,
+    /// Existing item ids with their new content
+    pub(crate) update: Vec<(i32, NewItem)>,
+    /// Titles of items that were duplicates and left as they were
+    pub(crate) skipped: Vec<String>,
+}
+
+/// Drop items whose key an earlier item of the same upload already has,
+/// returning the kept items and the titles of the dropped ones
+pub(crate) fn dedup_within(items: Vec<NewItem>, key: &DedupKey) -> (Vec<NewItem>, Vec<String>) {
+    let mut seen = HashSet::new();
+    let mut kept = Vec::with_capacity(items.len());
+    let mut dropped = Vec::new();
+    for item in items {
+        match key.key_of(&item) {
+            Some(k) if !seen.insert(k) => dropped.push(item.0),
+            _ => kept.push(item),
+        }
+    }
+    (kept, dropped)
+}
+
+/// Decide per item whether to insert, update or skip it. `existing` maps an
+/// item's index to the existing item it duplicates.
+pub(crate) fn plan(
+    items: Vec<NewItem>,
+    existing: &HashMap<usize, ExistingItem>,
+    on_duplicate: DuplicateAction,
+) -> DedupPlan {
+    let mut plan = DedupPlan::default();
+    for (index, item) in items.into_iter().enumerate() {
+        let Some(existing) = existing.get(&index) else {
+            plan.insert.push(item);
+            continue;
+        };
+        let changed = serde_json::to_value(&item.1).ok().as_ref() != Some(&existing.chunks)
+            || item.2 != existing.metadata;
+        if on_duplicate == DuplicateAction::Update && changed {
+            plan.update.push((existing.item_id, item));
+        } else {
+            plan.skipped.push(item.0);
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(title: &str, contents: &[&str], metadata: serde_json::Value) -> NewItem {
+        let chunks = contents
+            .iter()
+            .map(|c| ChunkWithMetadata {
+                content: c.to_string(),
+                metadata: json!({}),
+            })
+            .collect();
+        (title.to_string(), chunks, metadata)
+    }
+
+    fn existing(item_id: i32, item: &NewItem) -> ExistingItem {
+        ExistingItem {
+            item_id,
+            chunks: serde_json::to_value(&item.1).unwrap(),
+            metadata: item.2.clone(),
+        }
+    }
+
+    #[test]
+    fn test_parse_dedup_key() {
+        assert_eq!(DedupKey::parse("title").unwrap(), DedupKey::Title);
+        assert_eq!(
+            DedupKey::parse("content_hash").unwrap(),
+            DedupKey::ContentHash
+        );
+        assert_eq!(
+            DedupKey::parse("metadata.source_id").unwrap(),
+            DedupKey::MetadataField("source_id".to_string())
+        );
+        assert!(DedupKey::parse("metadata.").is_err());
+        assert!(DedupKey::parse("url").is_err());
+    }
+
+    #[test]
+    fn test_on_duplicate_requires_dedup_key() {
+        let params = DedupParams {
+            dedup_key: None,
+            on_duplicate: Some(DuplicateAction::Skip),
+        };
+        assert!(params.options().is_err());
+    }
+
+    #[test]
+    fn test_reimport_updates_changed_items_and_skips_the_rest() {
+        let first_import = vec![
+            item("a.txt", &["alpha"], json!({"source_id": "1"})),
+            item("b.txt", &["beta"], json!({"source_id": "2"})),
+        ];
+        let stored: HashMap<String, ExistingItem> = first_import
+            .iter()
+            .enumerate()
+            .map(|(i, it)| {
+                (
+                    it.2["source_id"].as_str().unwrap().to_string(),
+                    existing(i as i32 + 1, it),
+                )
+            })
+            .collect();
+
+        // Same sources under new titles, one with changed content, plus a new one
+        let reimport = vec![
+            item("a-renamed.txt", &["alpha"], json!({"source_id": "1"})),
+            item("b-renamed.txt", &["beta v2"], json!({"source_id": "2"})),
+            item("c.txt", &["gamma"], json!({"source_id": "3"})),
+        ];
+        let key = DedupKey::parse("metadata.source_id").unwrap();
+        let matches: HashMap<usize, ExistingItem> = reimport
+            .iter()
+            .enumerate()
+            .filter_map(|(i, it)| Some((i, stored.get(&key.key_of(it)?)?.clone())))
+            .collect();
+
+        let plan = plan(reimport, &matches, DuplicateAction::Update);
+
+        let inserted: Vec<&str> = plan.insert.iter().map(|i| i.0.as_str()).collect();
+        assert_eq!(inserted, vec!["c.txt"]);
+        assert_eq!(plan.update.len(), 1);
+        assert_eq!(plan.update[0].0, 2);
+        assert_eq!(plan.update[0].1.1[0].content, "beta v2");
+        assert_eq!(plan.skipped, vec!["a-renamed.txt"]);
+    }
+
+    #[test]
+    fn test_skip_leaves_changed_duplicates_alone() {
+        let old = item("a.txt", &["alpha"], json!({}));
+        let new = item("a.txt", &["alpha v2"], json!({}));
+        let matches = HashMap::from([(0, existing(7, &old))]);
+
+        let plan = plan(vec![new], &matches, DuplicateAction::Skip);
+
+        assert!(plan.insert.is_empty() && plan.update.is_empty());
+        assert_eq!(plan.skipped, vec!["a.txt"]);
+    }
+
+    #[test]
+    fn test_duplicates_within_an_upload_keep_the_first() {
+        let items = vec![
+            item("a.txt", &["same", "text"], json!({})),
+            item("b.txt", &["other"], json!({})),
+            item("a-copy.txt", &["same", "text"], json!({})),
+            // Chunk boundaries are part of the content
+            item("a-joined.txt", &["sametext"], json!({})),
+        ];
+
+        let (kept, dropped) = dedup_within(items, &DedupKey::ContentHash);
+
+        let kept: Vec<&str> = kept.iter().map(|i| i.0.as_str()).collect();
+        assert_eq!(kept, vec!["a.txt", "b.txt", "a-joined.txt"]);
+        assert_eq!(dropped, vec!["a-copy.txt"]);
+    }
+
+    #[test]
+    fn test_items_without_the_metadata_field_are_not_deduplicated() {
+        let key = DedupKey::parse("metadata.source_id").unwrap();
+        let items = vec![
+            item("a.txt", &["a"], json!({})),
+            item("b.txt", &["b"], json!({"source_id": null})),
+            item("c.txt", &["c"], json!({"source_id": 42})),
+        ];
+        assert_eq!(key.key_of(&items[0]), None);
+        assert_eq!(key.key_of(&items[1]), None);
+        assert_eq!(key.key_of(&items[2]).as_deref(), Some("42"));
+
+        let (kept, dropped) = dedup_within(items, &key);
+        assert_eq!(kept.len(), 3);
+        assert!(dropped.is_empty());
+    }
+}
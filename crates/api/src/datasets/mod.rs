@@ -1 +1,3 @@
+pub(crate) mod dedup;
 pub(crate) mod models;
+pub(crate) mod ndjson_import;
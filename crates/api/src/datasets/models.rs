@@ -115,6 +115,9 @@ pub(crate) struct CreateDatasetItem {
 pub(crate) struct CreateDatasetItemsResponse {
     pub(crate) completed: Vec<String>,
     pub(crate) failed: Vec<String>,
+    /// Duplicates left unchanged (uploads with `dedup_key` only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) skipped: Vec<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -0,0 +1,409 @@
+//! Streaming NDJSON dataset imports.
+//!
+//! Each line of the request body is one [`CreateDatasetItem`]. Lines are
+//! parsed as the body arrives and handed to the caller in batches; the next
+//! part of the body is only read once the previous batch has been stored, so
+//! a slow database pushes back on the client instead of the import buffering
+//! the file. Memory is bounded by one batch plus one partial line.
+
+use std::future::Future;
+
+use actix_web::web::Bytes;
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use tracing::info;
+use utoipa::ToSchema;
+
+use super::models::CreateDatasetItem;
+
+/// Items handed to the caller per batch
+pub(crate) const NDJSON_IMPORT_BATCH_SIZE: usize = 500;
+
+/// Longest accepted line; longer lines are reported as invalid and skipped
+pub(crate) const MAX_NDJSON_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Failed titles and invalid lines listed in the response, so that a
+/// thoroughly broken file doesn't produce an unbounded response
+const MAX_REPORTED_FAILURES: usize = 100;
+
+/// Outcome of storing one batch: the number of items created, updated and
+/// skipped as duplicates, and the titles that could not be stored
+#[derive(Default)]
+pub(crate) struct BatchOutcome {
+    pub(crate) created: usize,
+    pub(crate) updated: usize,
+    pub(crate) skipped: usize,
+    pub(crate) failed: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct InvalidNdjsonLine {
+    /// 1-based line number
+    pub(crate) line: u64,
+    pub(crate) error: String,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub(crate) struct NdjsonImportResponse {
+    pub(crate) items_created: u64,
+    /// Existing items updated by an upload with `dedup_key`
+    pub(crate) items_updated: u64,
+    /// Duplicates left unchanged by an upload with `dedup_key`
+    pub(crate) items_skipped: u64,
+    pub(crate) lines_read: u64,
+    pub(crate) bytes_read: u64,
+    pub(crate) failed_count: u64,
+    /// The first failed titles
+    pub(crate) failed: Vec<String>,
+    pub(crate) invalid_line_count: u64,
+    /// The first invalid lines
+    pub(crate) invalid_lines: Vec<InvalidNdjsonLine>,
+    /// Largest partial line held while reading the body
+    #[serde(skip)]
+    pub(crate) peak_buffered_bytes: usize,
+}
+
+impl NdjsonImportResponse {
+    fn record_failed(&mut self, titles: Vec<String>) {
+        self.failed_count += titles.len() as u64;
+        let room = MAX_REPORTED_FAILURES.saturating_sub(self.failed.len());
+        self.failed.extend(titles.into_iter().take(room));
+    }
+
+    fn record_invalid(&mut self, line: u64, error: String) {
+        self.invalid_line_count += 1;
+        if self.invalid_lines.len() < MAX_REPORTED_FAILURES {
+            self.invalid_lines.push(InvalidNdjsonLine { line, error });
+        }
+    }
+}
+
+/// Limits of an import
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NdjsonImportLimits {
+    pub(crate) batch_size: usize,
+    pub(crate) max_line_bytes: usize,
+}
+
+impl Default for NdjsonImportLimits {
+    fn default() -> Self {
+        Self {
+            batch_size: NDJSON_IMPORT_BATCH_SIZE,
+            max_line_bytes: MAX_NDJSON_LINE_BYTES,
+        }
+    }
+}
+
+struct Importer<F> {
+    limits: NdjsonImportLimits,
+    store_batch: F,
+    batch: Vec<CreateDatasetItem>,
+    partial_line: Vec<u8>,
+    /// Set while skipping the rest of an over-long line
+    skipping_line: bool,
+    summary: NdjsonImportResponse,
+}
+
+impl<F, Fut> Importer<F>
+where
+    F: FnMut(Vec<CreateDatasetItem>) -> Fut,
+    Fut: Future<Output = anyhow::Result<BatchOutcome>>,
+{
+    async fn push_chunk(&mut self, mut chunk: &[u8]) -> anyhow::Result<()> {
+        self.summary.bytes_read += chunk.len() as u64;
+        while let Some(newline) = chunk.iter().position(|b| *b == b'\n') {
+            let (line, rest) = chunk.split_at(newline);
+            chunk = &rest[1..];
+            if self.skipping_line {
+                self.skipping_line = false;
+            } else if self.partial_line.len() + line.len() > self.limits.max_line_bytes {
+                self.partial_line = Vec::new();
+                self.reject_oversized_line();
+            } else if self.partial_line.is_empty() {
+                self.parse_line(line).await?;
+            } else {
+                self.partial_line.extend_from_slice(line);
+                let line = std::mem::take(&mut self.partial_line);
+                self.parse_line(&line).await?;
+            }
+        }
+
+        if self.skipping_line {
+            return Ok(());
+        }
+        if self.partial_line.len() + chunk.len() > self.limits.max_line_bytes {
+            // Drop what we have and ignore the rest of the line
+            self.partial_line = Vec::new();
+            self.skipping_line = true;
+            self.reject_oversized_line();
+        } else {
+            self.partial_line.extend_from_slice(chunk);
+            self.summary.peak_buffered_bytes = self
+                .summary
+                .peak_buffered_bytes
+                .max(self.partial_line.len());
+        }
+        Ok(())
+    }
+
+    fn reject_oversized_line(&mut self) {
+        self.summary.lines_read += 1;
+        let line = self.summary.lines_read;
+        self.summary.record_invalid(
+            line,
+            format!("line exceeds {} bytes", self.limits.max_line_bytes),
+        );
+    }
+
+    async fn parse_line(&mut self, line: &[u8]) -> anyhow::Result<()> {
+        self.summary.lines_read += 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+        match serde_json::from_slice::<CreateDatasetItem>(line) {
+            Ok(item) => {
+                self.batch.push(item);
+                if self.batch.len() >= self.limits.batch_size {
+                    self.flush().await?;
+                }
+            }
+            Err(e) => {
+                let line = self.summary.lines_read;
+                self.summary.record_invalid(line, e.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::take(&mut self.batch);
+        let outcome = (self.store_batch)(batch).await?;
+        self.summary.items_created += outcome.created as u64;
+        self.summary.items_updated += outcome.updated as u64;
+        self.summary.items_skipped += outcome.skipped as u64;
+        self.summary.record_failed(outcome.failed);
+        info!(
+            items_created = self.summary.items_created,
+            lines_read = self.summary.lines_read,
+            bytes_read = self.summary.bytes_read,
+            "NDJSON import progress"
+        );
+        Ok(())
+    }
+
+    async fn finish(mut self) -> anyhow::Result<NdjsonImportResponse> {
+        // The last line may lack a trailing newline
+        if !self.skipping_line && !self.partial_line.is_empty() {
+            let line = std::mem::take(&mut self.partial_line);
+            self.parse_line(&line).await?;
+        }
+        self.flush().await?;
+        Ok(self.summary)
+    }
+}
+
+/// Read NDJSON items from `body`, storing them in batches with `store_batch`.
+///
+/// Invalid lines are reported in the response and skipped. Reading stops at
+/// the first body or storage error; batches stored before it are kept.
+pub(crate) async fn import_ndjson<S, E, F, Fut>(
+    mut body: S,
+    limits: NdjsonImportLimits,
+    store_batch: F,
+) -> anyhow::Result<NdjsonImportResponse>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+    F: FnMut(Vec<CreateDatasetItem>) -> Fut,
+    Fut: Future<Output = anyhow::Result<BatchOutcome>>,
+{
+    let mut importer = Importer {
+        limits,
+        store_batch,
+        batch: Vec::with_capacity(limits.batch_size),
+        partial_line: Vec::new(),
+        skipping_line: false,
+        summary: NdjsonImportResponse::default(),
+    };
+
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| anyhow::anyhow!("failed to read request body: {e}"))?;
+        importer.push_chunk(&chunk).await?;
+    }
+    importer.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::stream;
+    use std::cell::Cell;
+    use std::convert::Infallible;
+
+    fn ndjson_line(i: usize) -> String {
+        format!(
+            "{{\"title\":\"doc-{i}\",\"chunks\":[{{\"content\":\"chunk of document {i}\",\"metadata\":{{}}}}],\"metadata\":{{\"n\":{i}}}}}\n"
+        )
+    }
+
+    /// Body of `lines` items delivered in `chunk_size` pieces, generated
+    /// lazily so the test itself never holds the whole file
+    fn ndjson_body(
+        lines: usize,
+        chunk_size: usize,
+    ) -> impl Stream<Item = Result<Bytes, Infallible>> + Unpin {
+        let mut next_line = 0;
+        let mut pending: Vec<u8> = Vec::new();
+        Box::pin(stream::poll_fn(move |_| {
+            while pending.len() < chunk_size && next_line < lines {
+                pending.extend_from_slice(ndjson_line(next_line).as_bytes());
+                next_line += 1;
+            }
+            if pending.is_empty() {
+                return std::task::Poll::Ready(None);
+            }
+            let take = chunk_size.min(pending.len());
+            let chunk: Vec<u8> = pending.drain(..take).collect();
+            std::task::Poll::Ready(Some(Ok(Bytes::from(chunk))))
+        }))
+    }
+
+    fn bytes_body(parts: &[&str]) -> impl Stream<Item = Result<Bytes, Infallible>> + Unpin {
+        stream::iter(
+            parts
+                .iter()
+                .map(|p| Ok(Bytes::copy_from_slice(p.as_bytes())))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    async fn store_all(items: Vec<CreateDatasetItem>) -> anyhow::Result<BatchOutcome> {
+        Ok(BatchOutcome {
+            created: items.len(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_large_import_creates_every_item_with_bounded_memory() {
+        const LINES: usize = 100_000;
+        let limits = NdjsonImportLimits {
+            batch_size: 250,
+            max_line_bytes: 4096,
+        };
+        let largest_batch = Cell::new(0);
+        let batches = Cell::new(0);
+
+        let summary = import_ndjson(ndjson_body(LINES, 7919), limits, |items| {
+            largest_batch.set(largest_batch.get().max(items.len()));
+            batches.set(batches.get() + 1);
+            store_all(items)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(summary.items_created, LINES as u64);
+        assert_eq!(summary.lines_read, LINES as u64);
+        assert_eq!(summary.invalid_line_count, 0);
+        assert_eq!(batches.get(), LINES / 250);
+        assert_eq!(largest_batch.get(), 250);
+        // Only a partial line is ever buffered, not the body
+        assert!(summary.peak_buffered_bytes <= ndjson_line(LINES).len());
+        assert!(summary.bytes_read > 10 * 1024 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_lines_split_across_chunks_and_without_trailing_newline() {
+        let line = ndjson_line(1);
+        let (head, tail) = line.trim_end().split_at(10);
+        let last = ndjson_line(2);
+        let summary = import_ndjson(
+            bytes_body(&[head, tail, "\r\n", last.trim_end()]),
+            NdjsonImportLimits::default(),
+            store_all,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.items_created, 2);
+        assert_eq!(summary.invalid_line_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_oversized_lines_are_reported_and_skipped() {
+        let long_title = "x".repeat(300);
+        let long_line = format!(
+            "{{\"title\":\"{long_title}\",\"chunks\":[{{\"content\":\"c\",\"metadata\":{{}}}}],\"metadata\":{{}}}}\n"
+        );
+        let body = [
+            ndjson_line(0),
+            "not json\n".to_string(),
+            "\n".to_string(),
+            long_line,
+            "{\"title\":\"\",\"chunks\":[],\"metadata\":{}}\n".to_string(),
+            ndjson_line(1),
+        ];
+        let parts: Vec<&str> = body.iter().map(String::as_str).collect();
+        let limits = NdjsonImportLimits {
+            batch_size: 10,
+            max_line_bytes: 200,
+        };
+
+        let summary = import_ndjson(bytes_body(&parts), limits, store_all)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.items_created, 2);
+        assert_eq!(summary.lines_read, 6);
+        let invalid: Vec<u64> = summary.invalid_lines.iter().map(|l| l.line).collect();
+        assert_eq!(invalid, vec![2, 4, 5]);
+        assert!(summary.invalid_lines[1].error.contains("exceeds 200 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_storage_failures_are_counted_and_capped() {
+        let summary = import_ndjson(
+            ndjson_body(1000, 4096),
+            NdjsonImportLimits {
+                batch_size: 100,
+                max_line_bytes: 4096,
+            },
+            |items| async move {
+                Ok(BatchOutcome {
+                    failed: items.into_iter().map(|i| i.title).collect(),
+                    ..Default::default()
+                })
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.items_created, 0);
+        assert_eq!(summary.failed_count, 1000);
+        assert_eq!(summary.failed.len(), MAX_REPORTED_FAILURES);
+    }
+
+    #[tokio::test]
+    async fn test_storage_error_stops_the_import() {
+        let calls = Cell::new(0);
+        let result = import_ndjson(
+            ndjson_body(1000, 4096),
+            NdjsonImportLimits {
+                batch_size: 100,
+                max_line_bytes: 4096,
+            },
+            |_| {
+                calls.set(calls.get() + 1);
+                async { Err::<BatchOutcome, _>(anyhow::anyhow!("database unavailable")) }
+            },
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -0,0 +1,277 @@
+//! Qdrant collection naming for embedded datasets.
+//!
+//! A dataset transform may set `collection_name_template` in its `job_config`.
+//! Every embedded dataset the transform creates renders its collection name
+//! from that template; the rendered name is stored on the embedded dataset,
+//! so the dataset worker, search, and visualizations all use the same name.
+//!
+//! Names rendered from a custom template start with `ed-{embedded_dataset_id}-`,
+//! which the template can't change. Literal text in a template can mimic an
+//! id (`embedded-dataset-1{embedded_dataset_id}` renders like the default
+//! name of another embedded dataset), so the prefix is what keeps one owner
+//! from naming their collection after someone else's.
+
+use semantic_explorer_core::validation::validate_collection_name;
+
+/// Key in a dataset transform's `job_config` that holds the naming template.
+pub const COLLECTION_NAME_TEMPLATE_KEY: &str = "collection_name_template";
+
+/// Template reproducing the historical `embedded-dataset-{id}-{owner}` names.
+pub const DEFAULT_COLLECTION_NAME_TEMPLATE: &str = "embedded-dataset-{embedded_dataset_id}-{owner}";
+
+/// Placeholders accepted in a collection name template.
+pub const COLLECTION_NAME_PLACEHOLDERS: &[&str] = &[
+    "embedded_dataset_id",
+    "dataset_transform_id",
+    "source_dataset_id",
+    "embedder_id",
+    "owner",
+];
+
+/// Values substituted into a collection name template.
+#[derive(Debug, Clone, Copy)]
+pub struct CollectionNameParams<'a> {
+    pub embedded_dataset_id: i32,
+    pub dataset_transform_id: i32,
+    pub source_dataset_id: i32,
+    pub embedder_id: i32,
+    pub owner: &'a str,
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn parse_template(template: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find(&['{', '}'][..]) {
+        if rest[start..].starts_with('}') {
+            return Err("collection name template has an unmatched '}'".to_string());
+        }
+        if start > 0 {
+            segments.push(Segment::Literal(&rest[..start]));
+        }
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| "collection name template has an unclosed '{'".to_string())?;
+        let name = &after[..end];
+        if !COLLECTION_NAME_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "unknown placeholder '{{{}}}' in collection name template; expected one of: {}",
+                name,
+                COLLECTION_NAME_PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{p}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        segments.push(Segment::Placeholder(name));
+        rest = &after[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+
+    Ok(segments)
+}
+
+/// Validate a collection name template.
+///
+/// The template must only use known placeholders, must be unique per embedded
+/// dataset (it needs `{embedded_dataset_id}`, or both `{dataset_transform_id}`
+/// and `{embedder_id}`), and must render to a safe Qdrant collection name.
+pub fn validate_collection_name_template(template: &str) -> Result<(), String> {
+    let segments = parse_template(template)?;
+    let uses = |placeholder: &str| {
+        segments
+            .iter()
+            .any(|s| matches!(s, Segment::Placeholder(p) if *p == placeholder))
+    };
+
+    if !uses("embedded_dataset_id") && !(uses("dataset_transform_id") && uses("embedder_id")) {
+        return Err(
+            "collection name template must include {embedded_dataset_id}, or both {dataset_transform_id} and {embedder_id}, so each embedded dataset gets its own collection"
+                .to_string(),
+        );
+    }
+
+    let sample = CollectionNameParams {
+        embedded_dataset_id: 1,
+        dataset_transform_id: 1,
+        source_dataset_id: 1,
+        embedder_id: 1,
+        owner: "owner",
+    };
+    // The template's own part must be a valid name, not just the prefixed one
+    validate_collection_name(&render_segments(&segments, &sample)).map_err(|e| e.to_string())?;
+    render_collection_name(template, &sample).map(|_| ())
+}
+
+fn render_segments(segments: &[Segment<'_>], params: &CollectionNameParams<'_>) -> String {
+    let mut name = String::new();
+    for segment in segments {
+        match *segment {
+            Segment::Literal(text) => name.push_str(text),
+            Segment::Placeholder("embedded_dataset_id") => {
+                name.push_str(&params.embedded_dataset_id.to_string())
+            }
+            Segment::Placeholder("dataset_transform_id") => {
+                name.push_str(&params.dataset_transform_id.to_string())
+            }
+            Segment::Placeholder("source_dataset_id") => {
+                name.push_str(&params.source_dataset_id.to_string())
+            }
+            Segment::Placeholder("embedder_id") => name.push_str(&params.embedder_id.to_string()),
+            Segment::Placeholder(_) => name.push_str(params.owner),
+        }
+    }
+    name
+}
+
+/// Render a collection name template and validate the result.
+///
+/// Custom templates are rendered after `ed-{embedded_dataset_id}-`; the
+/// default template already starts with its own fixed per-dataset prefix.
+pub fn render_collection_name(
+    template: &str,
+    params: &CollectionNameParams<'_>,
+) -> Result<String, String> {
+    let mut name = String::new();
+    if template != DEFAULT_COLLECTION_NAME_TEMPLATE {
+        name.push_str(&format!("ed-{}-", params.embedded_dataset_id));
+    }
+    name.push_str(&render_segments(&parse_template(template)?, params));
+
+    validate_collection_name(&name).map_err(|e| e.to_string())?;
+    Ok(name)
+}
+
+/// Read the naming template from a dataset transform's `job_config`,
+/// falling back to [`DEFAULT_COLLECTION_NAME_TEMPLATE`].
+pub fn template_from_job_config(job_config: &serde_json::Value) -> &str {
+    job_config
+        .get(COLLECTION_NAME_TEMPLATE_KEY)
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_COLLECTION_NAME_TEMPLATE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(owner: &str) -> CollectionNameParams<'_> {
+        CollectionNameParams {
+            embedded_dataset_id: 42,
+            dataset_transform_id: 7,
+            source_dataset_id: 3,
+            embedder_id: 9,
+            owner,
+        }
+    }
+
+    #[test]
+    fn test_default_template_matches_legacy_names() {
+        let name =
+            render_collection_name(DEFAULT_COLLECTION_NAME_TEMPLATE, &params("abc123")).unwrap();
+        assert_eq!(name, "embedded-dataset-42-abc123");
+    }
+
+    #[test]
+    fn test_render_all_placeholders() {
+        let name = render_collection_name(
+            "ds{source_dataset_id}_t{dataset_transform_id}_e{embedder_id}_{embedded_dataset_id}.{owner}",
+            &params("team-a"),
+        )
+        .unwrap();
+        assert_eq!(name, "ed-42-ds3_t7_e9_42.team-a");
+    }
+
+    #[test]
+    fn test_custom_template_cannot_take_another_owners_name() {
+        // The victim's embedded dataset 123 has the default name
+        let victim = render_collection_name(
+            DEFAULT_COLLECTION_NAME_TEMPLATE,
+            &CollectionNameParams {
+                embedded_dataset_id: 123,
+                ..params("victim")
+            },
+        )
+        .unwrap();
+        assert_eq!(victim, "embedded-dataset-123-victim");
+
+        // The attacker's embedded dataset 23 spells out the rest of that name
+        let attacker = render_collection_name(
+            "embedded-dataset-1{embedded_dataset_id}-victim",
+            &CollectionNameParams {
+                embedded_dataset_id: 23,
+                ..params("attacker")
+            },
+        )
+        .unwrap();
+        assert_eq!(attacker, "ed-23-embedded-dataset-123-victim");
+        assert_ne!(attacker, victim);
+
+        // Two custom templates can't meet either: the prefix fixes the id
+        let other = render_collection_name(
+            "x-{embedded_dataset_id}",
+            &CollectionNameParams {
+                embedded_dataset_id: 1,
+                ..params("victim")
+            },
+        )
+        .unwrap();
+        let mimic = render_collection_name(
+            "1-x-{embedded_dataset_id}",
+            &CollectionNameParams {
+                embedded_dataset_id: 1,
+                ..params("attacker")
+            },
+        )
+        .unwrap();
+        assert_eq!(other, "ed-1-x-1");
+        assert_eq!(mimic, "ed-1-1-x-1");
+    }
+
+    #[test]
+    fn test_template_validation() {
+        assert!(validate_collection_name_template(DEFAULT_COLLECTION_NAME_TEMPLATE).is_ok());
+        assert!(
+            validate_collection_name_template("prod-{dataset_transform_id}-{embedder_id}").is_ok()
+        );
+
+        // Unknown placeholder and malformed braces
+        assert!(validate_collection_name_template("prod-{title}-{embedded_dataset_id}").is_err());
+        assert!(validate_collection_name_template("prod-{embedded_dataset_id").is_err());
+        assert!(validate_collection_name_template("prod}-{embedded_dataset_id}").is_err());
+
+        // Not unique per embedded dataset
+        assert!(validate_collection_name_template("prod-{dataset_transform_id}").is_err());
+    }
+
+    #[test]
+    fn test_invalid_rendered_names_rejected() {
+        assert!(validate_collection_name_template("prod/{embedded_dataset_id}").is_err());
+        assert!(validate_collection_name_template("my vectors {embedded_dataset_id}").is_err());
+        assert!(validate_collection_name_template(".{embedded_dataset_id}").is_err());
+        assert!(render_collection_name(DEFAULT_COLLECTION_NAME_TEMPLATE, &params("a/b")).is_err());
+    }
+
+    #[test]
+    fn test_template_from_job_config() {
+        let custom = serde_json::json!({ "collection_name_template": "vec-{embedded_dataset_id}" });
+        assert_eq!(
+            template_from_job_config(&custom),
+            "vec-{embedded_dataset_id}"
+        );
+        assert_eq!(
+            template_from_job_config(&serde_json::json!({})),
+            DEFAULT_COLLECTION_NAME_TEMPLATE
+        );
+    }
+}
@@ -1 +1,40 @@
 pub(crate) mod models;
+
+use semantic_explorer_core::models::DistanceMetric;
+use serde_json::Value;
+
+/// Validate the `config` fields the platform itself interprets, as opposed to
+/// those passed through to the provider.
+pub(crate) fn validate_config(config: &Value) -> Result<(), String> {
+    match config.get(DistanceMetric::CONFIG_KEY) {
+        None | Some(Value::Null) => Ok(()),
+        Some(Value::String(metric)) => DistanceMetric::parse(metric).map(|_| ()),
+        Some(_) => Err("distance_metric must be a string".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_distance_metric() {
+        assert!(validate_config(&json!({ "model": "m" })).is_ok());
+        assert!(validate_config(&json!({ "distance_metric": "Dot" })).is_ok());
+        assert!(validate_config(&json!({ "distance_metric": "euclidean" })).is_ok());
+
+        let err = validate_config(&json!({ "distance_metric": "manhattan" })).unwrap_err();
+        assert!(err.contains("cosine, dot or euclidean"));
+        assert!(validate_config(&json!({ "distance_metric": 1 })).is_err());
+
+        assert_eq!(
+            DistanceMetric::from_config(&json!({ "distance_metric": "dot_product" })),
+            DistanceMetric::Dot
+        );
+        assert_eq!(
+            DistanceMetric::from_config(&json!({})),
+            DistanceMetric::Cosine
+        );
+    }
+}
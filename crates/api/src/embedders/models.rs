@@ -78,6 +78,9 @@ pub(crate) struct Embedder {
     pub(crate) config: serde_json::Value,
     pub(crate) batch_size: i32,
     pub(crate) dimensions: i32,
+    /// Whether `dimensions` were measured from the embedder's output
+    #[serde(default)]
+    pub(crate) dimensions_verified: bool,
     #[serde(default = "default_max_input_tokens")]
     pub(crate) max_input_tokens: i32,
     pub(crate) truncate_strategy: String,
@@ -103,3 +106,14 @@ pub(crate) struct UpdateEmbedder {
     pub(crate) collection_name: Option<String>,
     pub(crate) is_public: Option<bool>,
 }
+
+impl Embedder {
+    /// Output dimensions, when they were measured rather than entered
+    pub(crate) fn verified_dimensions(&self) -> Option<u64> {
+        if self.dimensions_verified {
+            u64::try_from(self.dimensions).ok()
+        } else {
+            None
+        }
+    }
+}
@@ -62,9 +62,11 @@ pub async fn generate_embedding(
                 query_len = query.len(),
                 "Generating embedding via internal inference API"
             );
+            // The service applies the model's query prefix, if it has one
             let body = serde_json::json!({
                 "text": query,
                 "model": model,
+                "input_type": "query",
             });
             (endpoint, body, false)
         }
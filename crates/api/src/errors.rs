@@ -49,12 +49,18 @@ pub enum ApiError {
     /// Unauthorized access (401)
     #[error("{0}")]
     Unauthorized(String),
+    /// Authenticated but not allowed to access the resource (403)
+    #[error("{0}")]
+    Forbidden(String),
     /// Conflict - resource has dependencies (409)
     #[error("{0}")]
     Conflict(String),
     /// Service temporarily unavailable (503) — circuit breaker open
     #[error("{0}")]
     ServiceUnavailable(String),
+    /// Chat session has used its whole token budget (429)
+    #[error("Token budget exhausted: {used} of {budget} tokens used")]
+    TokenBudgetExceeded { used: i64, budget: i64 },
     /// Internal server error (500)
     #[error("{0}")]
     Internal(String),
@@ -72,8 +78,10 @@ impl ResponseError for ApiError {
             ApiError::NotFound(_) => StatusCode::NOT_FOUND,
             ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
             ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
             ApiError::Conflict(_) => StatusCode::CONFLICT,
             ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::TokenBudgetExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
             ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::Validation(_) => StatusCode::BAD_REQUEST,
@@ -87,8 +95,10 @@ impl ResponseError for ApiError {
             ApiError::NotFound(msg) => ("NotFound", msg.clone()),
             ApiError::BadRequest(msg) => ("BadRequest", msg.clone()),
             ApiError::Unauthorized(msg) => ("Unauthorized", msg.clone()),
+            ApiError::Forbidden(msg) => ("Forbidden", msg.clone()),
             ApiError::Conflict(msg) => ("Conflict", msg.clone()),
             ApiError::ServiceUnavailable(msg) => ("ServiceUnavailable", msg.clone()),
+            ApiError::TokenBudgetExceeded { .. } => ("TokenBudgetExceeded", self.to_string()),
             ApiError::Internal(msg) => ("InternalServerError", msg.clone()),
             ApiError::Database(e) => {
                 // Log the actual error for debugging but don't expose to client
@@ -103,11 +113,18 @@ impl ResponseError for ApiError {
 
         let response = ErrorResponse::new(error_type, message);
 
-        HttpResponse::build(status).json(json!({
+        let mut body = json!({
             "error": response.error,
             "message": response.message,
             "status": status.as_u16()
-        }))
+        });
+        // Clients need the numbers to tell the user how far over they are
+        if let ApiError::TokenBudgetExceeded { used, budget } = self {
+            body["tokens_used"] = json!(used);
+            body["token_budget"] = json!(budget);
+        }
+
+        HttpResponse::build(status).json(body)
     }
 }
 
@@ -156,6 +173,11 @@ pub(crate) fn not_found(message: impl std::fmt::Display) -> HttpResponse {
     error_response_with_status(actix_web::http::StatusCode::NOT_FOUND, "NotFound", message)
 }
 
+/// Create a Conflict (409) JSON response with standardized format
+pub(crate) fn conflict(message: impl std::fmt::Display) -> HttpResponse {
+    error_response_with_status(actix_web::http::StatusCode::CONFLICT, "Conflict", message)
+}
+
 /// Create an Unauthorized (401) JSON response with standardized format
 pub(crate) fn unauthorized(message: impl std::fmt::Display) -> HttpResponse {
     error_response_with_status(
@@ -164,3 +186,12 @@ pub(crate) fn unauthorized(message: impl std::fmt::Display) -> HttpResponse {
         message,
     )
 }
+
+/// Create a Too Many Requests (429) JSON response with standardized format
+pub(crate) fn too_many_requests(message: impl std::fmt::Display) -> HttpResponse {
+    error_response_with_status(
+        actix_web::http::StatusCode::TOO_MANY_REQUESTS,
+        "TooManyRequests",
+        message,
+    )
+}
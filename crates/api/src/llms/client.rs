@@ -47,7 +47,6 @@ pub struct ChatResponse {
     #[allow(dead_code)]
     pub model: String,
     /// Number of tokens generated
-    #[allow(dead_code)]
     pub tokens_generated: usize,
     /// Reason generation stopped (length, stop, eos, error)
     #[allow(dead_code)]
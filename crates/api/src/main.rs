@@ -1,5 +1,7 @@
 mod api;
 mod audit;
+mod audit_chain;
+mod audit_retention;
 mod audit_worker;
 mod auth;
 mod chat;
@@ -11,6 +13,8 @@ mod embedding;
 mod errors;
 mod llms;
 mod observability;
+mod provider_configs;
+mod request_timeout;
 mod search;
 mod storage;
 mod transforms;
@@ -47,8 +51,15 @@ async fn main() -> Result<()> {
     dotenv().ok();
 
     // Load centralized configuration - fail fast if required config is missing
+    // or invalid, with every problem reported at once
     let config = AppConfig::from_env()?;
 
+    // Dry run: validate the configuration and exit without starting anything
+    if std::env::args().any(|arg| arg == "--check-config") {
+        println!("Configuration is valid");
+        return Ok(());
+    }
+
     // Force-initialize the global HTTP client so TLS errors surface immediately
     let _ = &*semantic_explorer_core::http_client::HTTP_CLIENT;
 
@@ -58,7 +69,9 @@ async fn main() -> Result<()> {
             "Warning: Encryption service not initialized: {}. API keys will NOT be encrypted.",
             e
         );
-        eprintln!("To enable encryption, set ENCRYPTION_MASTER_KEY environment variable");
+        eprintln!(
+            "To enable encryption, set ENCRYPTION_MASTER_KEY, or ENCRYPTION_PROVIDER=kms with ENCRYPTION_KMS_KEY_ID"
+        );
         eprintln!("Generate a key with: echo $(openssl rand -hex 32)");
         e
     })?;
@@ -130,10 +143,19 @@ async fn main() -> Result<()> {
         })
     };
 
+    // Archive audit events past AUDIT_RETENTION_DAYS to S3 (disabled when unset)
+    let _audit_retention_handle = audit_retention::start_audit_retention(
+        pool.clone(),
+        s3_client.clone(),
+        config.s3.bucket_name.clone(),
+        audit_retention::AuditRetentionConfig::from_env(),
+    );
+
     transforms::listeners::start_result_listeners(
         pool.clone(),
         s3_client.clone(),
         config.s3.bucket_name.clone(),
+        config.s3.max_download_size_bytes,
         nats_client.clone(),
     )
     .await?;
@@ -165,10 +187,13 @@ async fn main() -> Result<()> {
         s3_bucket_name: config.s3.bucket_name.clone(),
         encryption: encryption_service.clone(),
         qdrant_config: qdrant_connection_config.clone(),
+        qdrant: qdrant_client.clone(),
         scanner_config,
+        worker_config: worker_config.clone(),
     };
 
     let circuit_breakers = semantic_explorer_core::circuit_breaker::CircuitBreakers::from_env();
+    let audit_admins = api::audit::AuditAdmins::from_env();
 
     // Start trigger listener (all instances listen, NATS coordinates)
     let _scanner_listener = transforms::trigger::start_trigger_listener(scanner_ctx);
@@ -255,6 +280,9 @@ async fn main() -> Result<()> {
             ));
 
         App::new()
+            .wrap(request_timeout::RequestTimeout::new(
+                config.server.request_timeout.clone(),
+            ))
             .wrap(openid_client.get_middleware())
             .wrap(Compress::default())
             .wrap(security_headers)
@@ -285,6 +313,7 @@ async fn main() -> Result<()> {
             .app_data(web::Data::new(llm_inference_config.clone()))
             .app_data(web::Data::new(worker_config.clone()))
             .app_data(web::Data::new(circuit_breakers.clone()))
+            .app_data(web::Data::new(audit_admins.clone()))
             .into_utoipa_app()
             .openapi(ApiDoc::openapi())
             .service(api::collections::get_collection)
@@ -297,7 +326,13 @@ async fn main() -> Result<()> {
             .service(api::collections::search_collections)
             .service(api::collections::list_collection_files)
             .service(api::collections::download_collection_file)
+            .service(api::collections::create_presigned_download)
+            .service(api::collections::create_presigned_upload)
+            .service(api::collections::reconcile_collection_files)
             .service(api::collections::get_allowed_file_types)
+            .service(api::collections::get_extraction_capabilities)
+            .service(api::collections::preview_uploaded_file_extraction)
+            .service(api::collections::preview_collection_file_extraction)
             .service(api::datasets::get_dataset)
             .service(api::datasets::get_datasets)
             .service(api::datasets::create_dataset)
@@ -332,6 +367,8 @@ async fn main() -> Result<()> {
             .service(api::llms::update_llm)
             .service(api::llms::delete_llm)
             .service(api::llm_inference::list_inference_llms)
+            .service(api::provider_configs::export_provider_configs)
+            .service(api::provider_configs::import_provider_configs)
             .service(api::marketplace::get_public_collections)
             .service(api::marketplace::get_recent_public_collections)
             .service(api::marketplace::get_public_datasets)
@@ -345,6 +382,7 @@ async fn main() -> Result<()> {
             .service(api::marketplace::grab_embedder)
             .service(api::marketplace::grab_llm)
             .service(api::search::search)
+            .service(api::search::search_batch)
             .service(api::collection_transforms::get_collection_transforms)
             .service(api::collection_transforms::stream_collection_transform_status)
             .service(api::collection_transforms::get_collection_transform)
@@ -360,6 +398,8 @@ async fn main() -> Result<()> {
             .service(api::collection_transforms::get_collection_transforms_for_dataset)
             .service(api::dataset_transforms::get_dataset_transforms)
             .service(api::dataset_transforms::stream_dataset_transform_status)
+            .service(api::dataset_transforms::bulk_reembed_datasets)
+            .service(api::dataset_transforms::get_bulk_reembed)
             .service(api::dataset_transforms::get_dataset_transform)
             .service(api::dataset_transforms::create_dataset_transform)
             .service(api::dataset_transforms::update_dataset_transform)
@@ -394,6 +434,11 @@ async fn main() -> Result<()> {
             .service(api::chat::send_chat_message)
             .service(api::chat::stream_chat_message)
             .service(api::chat::regenerate_chat_message)
+            .service(api::dlq::list_dlq_messages)
+            .service(api::dlq::replay_dlq_message)
+            .service(api::audit::list_audit_events)
+            .service(api::audit::export_audit_events)
+            .service(api::audit::verify_audit_chain)
             .openapi_service(|api| {
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api/openapi.json", api)
             })
@@ -0,0 +1,336 @@
+//! Bulk export and import of embedder and LLM configurations.
+//!
+//! Configs are matched by name within the importing user's own configs.
+//! API keys are either redacted on export or re-encrypted with the master key;
+//! an encrypted bundle can only be imported where the same key is configured.
+
+pub(crate) mod models;
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use semantic_explorer_core::encryption::EncryptionService;
+use semantic_explorer_core::validation;
+
+use models::{CONFIG_BUNDLE_VERSION, ConfigBundle, ConflictMode, SecretHandling};
+
+/// What an import does with a single config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportAction {
+    Create,
+    Overwrite(i32),
+    Skip(i32),
+}
+
+/// Decide what to do with an imported config given the ids of existing
+/// configs keyed by name.
+pub(crate) fn plan_import(
+    name: &str,
+    existing: &HashMap<String, i32>,
+    mode: ConflictMode,
+) -> ImportAction {
+    match (existing.get(name), mode) {
+        (None, _) => ImportAction::Create,
+        (Some(&id), ConflictMode::Skip) => ImportAction::Skip(id),
+        (Some(&id), ConflictMode::Overwrite) => ImportAction::Overwrite(id),
+    }
+}
+
+/// Validate a bundle before anything is written. Returns every problem found.
+pub(crate) fn validate_bundle(bundle: &ConfigBundle) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    if bundle.version == 0 || bundle.version > CONFIG_BUNDLE_VERSION {
+        errors.push(format!(
+            "unsupported bundle version {}; expected at most {}",
+            bundle.version, CONFIG_BUNDLE_VERSION
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for (i, e) in bundle.embedders.iter().enumerate() {
+        let at = format!("embedders[{i}]");
+        if let Err(err) = validation::validate_title(&e.name) {
+            errors.push(format!("{at}: {err}"));
+        }
+        if !seen.insert(e.name.as_str()) {
+            errors.push(format!("{at}: duplicate embedder name '{}'", e.name));
+        }
+        if e.provider != "internal" && e.base_url.trim().is_empty() {
+            errors.push(format!("{at}: base_url cannot be empty for this provider"));
+        }
+        if e.batch_size <= 0 || e.dimensions <= 0 || e.max_input_tokens <= 0 {
+            errors.push(format!(
+                "{at}: batch_size, dimensions and max_input_tokens must be positive"
+            ));
+        }
+        if e.api_key.is_some() && e.api_key_encrypted.is_some() {
+            errors.push(format!(
+                "{at}: set either api_key or api_key_encrypted, not both"
+            ));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (i, l) in bundle.llms.iter().enumerate() {
+        let at = format!("llms[{i}]");
+        if let Err(err) = validation::validate_title(&l.name) {
+            errors.push(format!("{at}: {err}"));
+        }
+        if !seen.insert(l.name.as_str()) {
+            errors.push(format!("{at}: duplicate LLM name '{}'", l.name));
+        }
+        if l.model.trim().is_empty() {
+            errors.push(format!("{at}: model cannot be empty"));
+        }
+        if l.api_key.is_some() && l.api_key_encrypted.is_some() {
+            errors.push(format!(
+                "{at}: set either api_key or api_key_encrypted, not both"
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Apply the requested secret handling to stored (encrypted) API keys.
+pub(crate) fn prepare_export(
+    bundle: &mut ConfigBundle,
+    encryption: &EncryptionService,
+) -> Result<()> {
+    let secrets = bundle.secrets;
+    let keys = bundle
+        .embedders
+        .iter_mut()
+        .map(|e| &mut e.api_key_encrypted)
+        .chain(bundle.llms.iter_mut().map(|l| &mut l.api_key_encrypted));
+
+    for key in keys {
+        *key = match (secrets, key.take()) {
+            (SecretHandling::Encrypt, Some(stored)) if !stored.is_empty() => {
+                if encryption.is_encrypted(&stored) {
+                    Some(encryption.reencrypt(&stored)?)
+                } else {
+                    Some(encryption.encrypt(&stored)?)
+                }
+            }
+            _ => None,
+        };
+    }
+    Ok(())
+}
+
+/// Turn the API keys of an imported bundle into ciphertext ready to store.
+///
+/// Plaintext `api_key` values are encrypted; `api_key_encrypted` values must
+/// decrypt with this deployment's master key.
+pub(crate) fn seal_import_secrets(
+    bundle: &mut ConfigBundle,
+    encryption: &EncryptionService,
+) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    let entries = bundle
+        .embedders
+        .iter_mut()
+        .enumerate()
+        .map(|(i, e)| {
+            (
+                format!("embedders[{i}]"),
+                &mut e.api_key,
+                &mut e.api_key_encrypted,
+            )
+        })
+        .chain(bundle.llms.iter_mut().enumerate().map(|(i, l)| {
+            (
+                format!("llms[{i}]"),
+                &mut l.api_key,
+                &mut l.api_key_encrypted,
+            )
+        }));
+
+    for (at, plaintext, sealed) in entries {
+        if let Some(key) = plaintext.take().filter(|k| !k.is_empty()) {
+            match encryption.encrypt(&key) {
+                Ok(ciphertext) => *sealed = Some(ciphertext),
+                Err(e) => errors.push(format!("{at}: failed to encrypt api_key: {e}")),
+            }
+        } else if let Some(ciphertext) = sealed.as_deref()
+            && encryption.decrypt(ciphertext).is_err()
+        {
+            errors.push(format!(
+                "{at}: api_key_encrypted cannot be decrypted with this deployment's master key; export with redacted secrets and supply api_key instead"
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::{EmbedderConfigEntry, LlmConfigEntry};
+    use sqlx::types::chrono::Utc;
+
+    fn service() -> EncryptionService {
+        EncryptionService::from_hex_key(&EncryptionService::generate_master_key()).unwrap()
+    }
+
+    fn bundle(secrets: SecretHandling) -> ConfigBundle {
+        ConfigBundle {
+            version: CONFIG_BUNDLE_VERSION,
+            exported_at: Utc::now(),
+            secrets,
+            embedders: vec![
+                EmbedderConfigEntry {
+                    name: "openai-small".to_string(),
+                    provider: "openai".to_string(),
+                    base_url: "https://api.openai.com/v1".to_string(),
+                    api_key: Some("sk-embed".to_string()),
+                    api_key_encrypted: None,
+                    config: serde_json::json!({ "model": "text-embedding-3-small" }),
+                    batch_size: 64,
+                    dimensions: 1536,
+                    max_input_tokens: 8191,
+                    truncate_strategy: "NONE".to_string(),
+                    is_public: false,
+                },
+                EmbedderConfigEntry {
+                    name: "local-bge".to_string(),
+                    provider: "internal".to_string(),
+                    base_url: String::new(),
+                    api_key: None,
+                    api_key_encrypted: None,
+                    config: serde_json::json!({ "model": "BAAI/bge-small-en-v1.5" }),
+                    batch_size: 100,
+                    dimensions: 384,
+                    max_input_tokens: 512,
+                    truncate_strategy: "END".to_string(),
+                    is_public: true,
+                },
+            ],
+            llms: vec![LlmConfigEntry {
+                name: "gpt".to_string(),
+                provider: "openai".to_string(),
+                model: "gpt-4o-mini".to_string(),
+                base_url: "https://api.openai.com/v1".to_string(),
+                api_key: Some("sk-llm".to_string()),
+                api_key_encrypted: None,
+                config: serde_json::json!({ "model": "gpt-4o-mini" }),
+                is_public: false,
+            }],
+        }
+    }
+
+    /// Simulate storing an imported bundle and reading it back for export.
+    fn store_and_export(
+        mut bundle: ConfigBundle,
+        secrets: SecretHandling,
+        encryption: &EncryptionService,
+    ) -> ConfigBundle {
+        seal_import_secrets(&mut bundle, encryption).unwrap();
+        bundle.secrets = secrets;
+        prepare_export(&mut bundle, encryption).unwrap();
+        let json = serde_json::to_string(&bundle).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_with_encrypted_secrets() {
+        let encryption = service();
+        let original = bundle(SecretHandling::Encrypt);
+        let mut exported = store_and_export(original.clone(), SecretHandling::Encrypt, &encryption);
+
+        assert!(validate_bundle(&exported).is_ok());
+        assert!(exported.embedders.iter().all(|e| e.api_key.is_none()));
+        seal_import_secrets(&mut exported, &encryption).unwrap();
+
+        assert_eq!(exported.embedders.len(), original.embedders.len());
+        for (got, want) in exported.embedders.iter().zip(&original.embedders) {
+            assert_eq!(got.name, want.name);
+            assert_eq!(got.provider, want.provider);
+            assert_eq!(got.config, want.config);
+            assert_eq!(got.dimensions, want.dimensions);
+            assert_eq!(got.truncate_strategy, want.truncate_strategy);
+            assert_eq!(got.is_public, want.is_public);
+            assert_eq!(
+                got.api_key_encrypted
+                    .as_deref()
+                    .map(|k| encryption.decrypt(k).unwrap()),
+                want.api_key
+            );
+        }
+        assert_eq!(exported.llms[0].model, "gpt-4o-mini");
+        assert_eq!(
+            encryption
+                .decrypt(exported.llms[0].api_key_encrypted.as_deref().unwrap())
+                .unwrap(),
+            "sk-llm"
+        );
+    }
+
+    #[test]
+    fn test_redacted_export_has_no_secrets() {
+        let encryption = service();
+        let exported = store_and_export(
+            bundle(SecretHandling::Redact),
+            SecretHandling::Redact,
+            &encryption,
+        );
+        let json = serde_json::to_string(&exported).unwrap();
+        assert!(!json.contains("api_key"));
+        assert!(validate_bundle(&exported).is_ok());
+    }
+
+    #[test]
+    fn test_encrypted_bundle_rejected_with_other_key() {
+        let mut imported = store_and_export(
+            bundle(SecretHandling::Encrypt),
+            SecretHandling::Encrypt,
+            &service(),
+        );
+        let errors = seal_import_secrets(&mut imported, &service()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_conflict_modes() {
+        let existing = HashMap::from([("openai-small".to_string(), 7)]);
+
+        assert_eq!(
+            plan_import("openai-small", &existing, ConflictMode::Skip),
+            ImportAction::Skip(7)
+        );
+        assert_eq!(
+            plan_import("openai-small", &existing, ConflictMode::Overwrite),
+            ImportAction::Overwrite(7)
+        );
+        for mode in [ConflictMode::Skip, ConflictMode::Overwrite] {
+            assert_eq!(
+                plan_import("local-bge", &existing, mode),
+                ImportAction::Create
+            );
+        }
+    }
+
+    #[test]
+    fn test_validation_rejects_bad_bundles() {
+        let mut b = bundle(SecretHandling::Redact);
+        b.version = CONFIG_BUNDLE_VERSION + 1;
+        b.embedders[1].name = b.embedders[0].name.clone();
+        b.embedders[0].provider = "cohere".to_string();
+        b.embedders[0].base_url = " ".to_string();
+        b.llms[0].model = String::new();
+
+        let errors = validate_bundle(&b).unwrap_err();
+        assert_eq!(errors.len(), 4);
+    }
+}
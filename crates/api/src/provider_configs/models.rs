@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// Current version of the exported configuration bundle format.
+pub(crate) const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+/// How API keys are written into an exported bundle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SecretHandling {
+    /// API keys are left out of the export.
+    #[default]
+    Redact,
+    /// API keys are re-encrypted with this deployment's master key, so the
+    /// bundle can only be imported by deployments sharing that key.
+    Encrypt,
+}
+
+/// What to do when an imported config has the same name as an existing one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ConflictMode {
+    /// Keep the existing config and ignore the imported one.
+    #[default]
+    Skip,
+    /// Replace the existing config with the imported one.
+    Overwrite,
+}
+
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub(crate) struct ExportConfigsQuery {
+    #[serde(default)]
+    pub(crate) secrets: SecretHandling,
+}
+
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub(crate) struct ImportConfigsQuery {
+    #[serde(default)]
+    pub(crate) on_conflict: ConflictMode,
+}
+
+/// Portable embedder configuration. Ownership, ids and the per-deployment
+/// Qdrant collection name are not exported.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
+pub(crate) struct EmbedderConfigEntry {
+    pub(crate) name: String,
+    pub(crate) provider: String,
+    pub(crate) base_url: String,
+    /// Plaintext API key; only accepted on import.
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) api_key_encrypted: Option<String>,
+    #[schema(value_type = Object)]
+    pub(crate) config: serde_json::Value,
+    pub(crate) batch_size: i32,
+    pub(crate) dimensions: i32,
+    pub(crate) max_input_tokens: i32,
+    pub(crate) truncate_strategy: String,
+    #[serde(default)]
+    pub(crate) is_public: bool,
+}
+
+/// Portable LLM configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
+pub(crate) struct LlmConfigEntry {
+    pub(crate) name: String,
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) base_url: String,
+    /// Plaintext API key; only accepted on import.
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) api_key_encrypted: Option<String>,
+    #[schema(value_type = Object)]
+    pub(crate) config: serde_json::Value,
+    #[serde(default)]
+    pub(crate) is_public: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ConfigBundle {
+    pub(crate) version: u32,
+    #[schema(value_type = String, format = DateTime)]
+    pub(crate) exported_at: DateTime<Utc>,
+    pub(crate) secrets: SecretHandling,
+    #[serde(default)]
+    pub(crate) embedders: Vec<EmbedderConfigEntry>,
+    #[serde(default)]
+    pub(crate) llms: Vec<LlmConfigEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ImportOutcome {
+    Created,
+    Overwritten,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ImportItemResult {
+    pub(crate) name: String,
+    pub(crate) id: i32,
+    pub(crate) outcome: ImportOutcome,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ImportConfigsResponse {
+    pub(crate) embedders: Vec<ImportItemResult>,
+    pub(crate) llms: Vec<ImportItemResult>,
+    pub(crate) created: usize,
+    pub(crate) overwritten: usize,
+    pub(crate) skipped: usize,
+}
+
+impl ImportConfigsResponse {
+    pub(crate) fn tally(&mut self) {
+        let outcomes = self
+            .embedders
+            .iter()
+            .chain(self.llms.iter())
+            .map(|r| r.outcome);
+        let (mut created, mut overwritten, mut skipped) = (0, 0, 0);
+        for outcome in outcomes {
+            match outcome {
+                ImportOutcome::Created => created += 1,
+                ImportOutcome::Overwritten => overwritten += 1,
+                ImportOutcome::Skipped => skipped += 1,
+            }
+        }
+        self.created = created;
+        self.overwritten = overwritten;
+        self.skipped = skipped;
+    }
+}
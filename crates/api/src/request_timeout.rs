@@ -0,0 +1,213 @@
+//! Server-side request deadline.
+//!
+//! Handlers that wait on slow backends (chat completions, transforms, search
+//! with rerank) would otherwise hold a connection for as long as the backend
+//! takes. This middleware races the inner service against the deadline for
+//! the request path and, when the deadline wins, drops the handler future
+//! (cancelling any in-flight backend calls) and answers 504.
+//!
+//! The deadline covers producing the response, including reading the request
+//! body, but not streaming the response body, so SSE responses keep streaming
+//! once their headers have been sent. No route has a deadline by default.
+
+use std::future::{Ready, ready};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use semantic_explorer_core::config::RequestTimeoutConfig;
+use serde_json::json;
+use tracing::warn;
+
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Middleware factory; wrap it innermost so the deadline only covers handlers
+#[derive(Clone)]
+pub(crate) struct RequestTimeout {
+    config: Arc<RequestTimeoutConfig>,
+}
+
+impl RequestTimeout {
+    pub(crate) fn new(config: RequestTimeoutConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTimeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequestTimeoutMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTimeoutMiddleware {
+            service: Rc::new(service),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+pub(crate) struct RequestTimeoutMiddleware<S> {
+    service: Rc<S>,
+    config: Arc<RequestTimeoutConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+        let Some(deadline) = self.config.for_path(req.path()) else {
+            return Box::pin(async move { Ok(srv.call(req).await?.map_into_left_body()) });
+        };
+
+        let http_req = req.request().clone();
+        Box::pin(async move {
+            match tokio::time::timeout(deadline, srv.call(req)).await {
+                Ok(res) => Ok(res?.map_into_left_body()),
+                Err(_) => {
+                    let request_id = request_id(&http_req);
+                    warn!(
+                        method = %http_req.method(),
+                        path = %http_req.path(),
+                        request_id = %request_id,
+                        deadline_secs = deadline.as_secs_f64(),
+                        "Request exceeded its deadline, cancelling handler"
+                    );
+                    let response = timeout_response(&request_id, deadline.as_secs_f64());
+                    Ok(ServiceResponse::new(http_req, response).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+/// The caller's `X-Request-Id`, or a new one so the 504 can still be quoted
+fn request_id(req: &actix_web::HttpRequest) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+fn timeout_response(request_id: &str, deadline_secs: f64) -> HttpResponse {
+    let status = StatusCode::GATEWAY_TIMEOUT;
+    let mut response = HttpResponse::build(status).json(json!({
+        "error": "GatewayTimeout",
+        "message": format!("Request did not complete within {deadline_secs}s"),
+        "status": status.as_u16(),
+        "request_id": request_id,
+    }));
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, test, web};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    /// Sets its flag when dropped, i.e. when the handler future is cancelled
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_slow_handler_is_cut_off_and_fast_one_is_not() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let slow_cancelled = cancelled.clone();
+        let config = RequestTimeoutConfig {
+            default: Some(Duration::from_millis(100)),
+            routes: vec![("/unbounded".to_string(), None)],
+        };
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestTimeout::new(config))
+                .route(
+                    "/slow",
+                    web::get().to(move || {
+                        let guard = DropFlag(slow_cancelled.clone());
+                        async move {
+                            let _guard = guard;
+                            tokio::time::sleep(Duration::from_secs(5)).await;
+                            HttpResponse::Ok().finish()
+                        }
+                    }),
+                )
+                .route("/fast", web::get().to(|| async { "done" }))
+                .route(
+                    "/unbounded",
+                    web::get().to(|| async {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        "done"
+                    }),
+                ),
+        )
+        .await;
+
+        let started = std::time::Instant::now();
+        let req = test::TestRequest::get()
+            .uri("/slow")
+            .insert_header(("X-Request-Id", "req-42"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "req-42");
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["error"], "GatewayTimeout");
+        assert_eq!(body["status"], 504);
+        assert_eq!(body["request_id"], "req-42");
+        // The handler future was dropped, not left running
+        assert!(cancelled.load(Ordering::SeqCst));
+
+        let res =
+            test::call_service(&app, test::TestRequest::get().uri("/fast").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "done");
+
+        let res = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/unbounded").to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_request_id_is_generated_when_missing() {
+        let req = test::TestRequest::default().to_http_request();
+        let id = request_id(&req);
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+    }
+}
@@ -0,0 +1,200 @@
+//! Per-hit explanations for `explain: true` searches.
+//!
+//! A hit's `score` may come from the vector search, the keyword search, the
+//! hybrid fusion or the reranker, which makes it hard to tell why a result
+//! ranks where it does. Explain mode attaches the score from each stage the
+//! hit went through, the stage that produced its final score and the filters
+//! it had to pass.
+
+use semantic_explorer_core::models::DistanceMetric;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::search::models::{DocumentResult, RetrievalMode, SearchMatch, SearchRequest};
+
+/// How a hit was found and scored
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub(crate) struct SearchExplanation {
+    /// Stages that retrieved the hit (`dense`, `sparse`)
+    pub retrieved_by: Vec<String>,
+    /// Stage that produced the hit's final score (`dense`, `sparse`,
+    /// `hybrid` or `rerank`)
+    pub scored_by: String,
+    /// Raw vector similarity (or distance, for Euclidean) of the hit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_score: Option<f32>,
+    /// Metric `vector_score` was computed with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub vector_metric: Option<DistanceMetric>,
+    /// BM25 keyword score of the hit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sparse_score: Option<f32>,
+    /// Reciprocal Rank Fusion score of a hybrid hit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fused_score: Option<f32>,
+    /// Cross-encoder relevance score, when the hit was reranked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
+    /// Payload filters the hit matched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub filters: Option<serde_json::Value>,
+    /// Minimum score (maximum distance, for Euclidean) the hit passed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_threshold: Option<f32>,
+}
+
+/// What the search applied to every hit of one embedded dataset
+pub(crate) struct ExplainContext<'a> {
+    pub retrieval_mode: RetrievalMode,
+    /// Metric dense scores are reported in, after any per-search override
+    pub metric: DistanceMetric,
+    pub request: &'a SearchRequest,
+}
+
+/// Explanation of a hit after retrieval and, if `retrieval_score` is set,
+/// reranking
+pub(crate) fn explain_match(search_match: &SearchMatch, ctx: &ExplainContext) -> SearchExplanation {
+    let reranked = search_match.retrieval_score.is_some();
+    let retrieval_score = search_match.retrieval_score.unwrap_or(search_match.score);
+
+    let (retrieved_by, vector_score, sparse_score, fused_score) = match ctx.retrieval_mode {
+        RetrievalMode::Dense => (vec!["dense"], Some(retrieval_score), None, None),
+        RetrievalMode::Sparse => (vec!["sparse"], None, Some(retrieval_score), None),
+        RetrievalMode::Hybrid => {
+            let breakdown = search_match.score_breakdown.clone().unwrap_or_default();
+            let mut retrieved_by = Vec::new();
+            if breakdown.dense_rank.is_some() {
+                retrieved_by.push("dense");
+            }
+            if breakdown.sparse_rank.is_some() {
+                retrieved_by.push("sparse");
+            }
+            (
+                retrieved_by,
+                breakdown.dense_score,
+                breakdown.sparse_score,
+                Some(retrieval_score),
+            )
+        }
+    };
+
+    let threshold = ctx.request.score_threshold;
+    SearchExplanation {
+        retrieved_by: retrieved_by.into_iter().map(String::from).collect(),
+        scored_by: if reranked {
+            "rerank".to_string()
+        } else {
+            ctx.retrieval_mode.as_str().to_string()
+        },
+        vector_metric: vector_score.map(|_| ctx.metric),
+        vector_score,
+        sparse_score,
+        fused_score,
+        rerank_score: reranked.then_some(search_match.score),
+        filters: ctx.request.filters.clone(),
+        score_threshold: (threshold != 0.0).then_some(threshold),
+    }
+}
+
+/// Attach explanations to the final matches and documents
+pub(crate) fn explain_results(
+    matches: &mut [SearchMatch],
+    documents: Option<&mut Vec<DocumentResult>>,
+    ctx: &ExplainContext,
+) {
+    for search_match in matches {
+        search_match.explanation = Some(explain_match(search_match, ctx));
+    }
+    for document in documents.into_iter().flatten() {
+        document.best_chunk.explanation = Some(explain_match(&document.best_chunk, ctx));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::models::ScoreBreakdown;
+    use crate::search::rerank::reorder_matches;
+    use serde_json::json;
+
+    fn request(filters: Option<serde_json::Value>) -> SearchRequest {
+        serde_json::from_value(json!({
+            "query": "rust",
+            "embedded_dataset_ids": [1],
+            "filters": filters,
+            "rerank": true,
+            "explain": true
+        }))
+        .unwrap()
+    }
+
+    fn hit(id: &str, score: f32, breakdown: Option<ScoreBreakdown>) -> SearchMatch {
+        SearchMatch {
+            id: id.to_string(),
+            score,
+            text: format!("text {id}"),
+            metadata: json!({}),
+            score_breakdown: breakdown,
+            retrieval_score: None,
+            explanation: None,
+        }
+    }
+
+    #[test]
+    fn test_reranked_dense_hit_reports_each_stage() {
+        let filters = json!({ "must": [{ "key": "lang", "match": { "value": "en" } }] });
+        let request = request(Some(filters.clone()));
+        let ctx = ExplainContext {
+            retrieval_mode: RetrievalMode::Dense,
+            metric: DistanceMetric::Cosine,
+            request: &request,
+        };
+
+        let candidates = vec![hit("a", 0.91, None), hit("b", 0.72, None)];
+        let mut reranked = reorder_matches(candidates, &[(1, 6.5), (0, 2.0)]);
+        explain_results(&mut reranked, None, &ctx);
+
+        let explanation = reranked[0].explanation.as_ref().unwrap();
+        assert_eq!(reranked[0].id, "b");
+        assert_eq!(explanation.retrieved_by, vec!["dense"]);
+        assert_eq!(explanation.scored_by, "rerank");
+        assert_eq!(explanation.vector_score, Some(0.72));
+        assert_eq!(explanation.vector_metric, Some(DistanceMetric::Cosine));
+        assert_eq!(explanation.rerank_score, Some(6.5));
+        assert_eq!(explanation.filters, Some(filters));
+        assert_eq!(explanation.score_threshold, None);
+    }
+
+    #[test]
+    fn test_reranked_hybrid_hit_keeps_fusion_inputs() {
+        let request = request(None);
+        let ctx = ExplainContext {
+            retrieval_mode: RetrievalMode::Hybrid,
+            metric: DistanceMetric::Cosine,
+            request: &request,
+        };
+        let breakdown = ScoreBreakdown {
+            dense_score: None,
+            dense_rank: None,
+            sparse_score: Some(12.4),
+            sparse_rank: Some(1),
+        };
+
+        let mut reranked = reorder_matches(vec![hit("a", 0.016, Some(breakdown))], &[(0, 3.0)]);
+        let explanation = explain_match(&reranked.remove(0), &ctx);
+        // Found only by keyword search, so there is no vector score
+        assert_eq!(explanation.retrieved_by, vec!["sparse"]);
+        assert_eq!(explanation.vector_score, None);
+        assert_eq!(explanation.vector_metric, None);
+        assert_eq!(explanation.sparse_score, Some(12.4));
+        assert_eq!(explanation.fused_score, Some(0.016));
+        assert_eq!(explanation.rerank_score, Some(3.0));
+
+        // Without reranking the fused score is the final one
+        let explanation = explain_match(&hit("a", 0.016, None), &ctx);
+        assert_eq!(explanation.scored_by, "hybrid");
+        assert_eq!(explanation.rerank_score, None);
+    }
+}
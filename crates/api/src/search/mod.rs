@@ -1,12 +1,17 @@
+pub(crate) mod explain;
 pub mod models;
+pub(crate) mod rerank;
 
 use anyhow::Result;
 use qdrant_client::{
     Qdrant,
     qdrant::{
-        Condition, FieldCondition, Filter, Match as QdrantMatch, SearchParamsBuilder,
-        SearchPointsBuilder, Value as QdrantValue, condition::ConditionOneOf,
-        point_id::PointIdOptions, value::Kind,
+        Condition, Distance, FieldCondition, Filter, GetCollectionInfoResponse,
+        Match as QdrantMatch, Query, QueryPointsBuilder, ScoredPoint, SearchParamsBuilder,
+        SearchPointsBuilder, Value as QdrantValue, VectorInput, VectorParams,
+        condition::ConditionOneOf, point_id::PointIdOptions, value::Kind,
+        vector_output::Vector as VectorOutputKind, vectors_config::Config as VectorsConfigKind,
+        vectors_output::VectorsOptions,
     },
 };
 
@@ -15,15 +20,240 @@ use std::{
     collections::{HashMap, HashSet},
 };
 
-use crate::search::models::{DocumentResult, SearchMatch, SearchMode, SearchRequest};
+use crate::search::models::{
+    BatchSearchRequest, DocumentResult, RetrievalMode, ScoreBreakdown, SearchMatch, SearchMode,
+    SearchRequest,
+};
+use crate::storage::qdrant::batch::query_batch;
 use qdrant_client::qdrant::r#match::MatchValue;
+use semantic_explorer_core::embedding_stamp::EmbeddingStamp;
+use semantic_explorer_core::models::DistanceMetric;
+use semantic_explorer_core::sparse::{self, SPARSE_VECTOR_NAME};
+
+/// Reciprocal Rank Fusion constant; damps the weight of top ranks
+const RRF_K: f32 = 60.0;
+
+/// How a collection's vectors can be searched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct CollectionSearchInfo {
+    /// Metric the dense vectors were created with
+    pub(crate) metric: DistanceMetric,
+    /// Whether points carry the BM25 sparse vector
+    pub(crate) has_sparse: bool,
+}
+
+/// Collections that predate configurable metrics or sparse vectors, or whose
+/// info can't be read, are treated as cosine and dense-only.
+pub(crate) async fn collection_search_info(
+    qdrant: &Qdrant,
+    collection_name: &str,
+) -> CollectionSearchInfo {
+    qdrant
+        .collection_info(collection_name)
+        .await
+        .map(|info| CollectionSearchInfo {
+            metric: distance_metric_of(&info),
+            has_sparse: has_sparse_vector(&info),
+        })
+        .unwrap_or_default()
+}
+
+fn has_sparse_vector(info: &GetCollectionInfoResponse) -> bool {
+    info.result
+        .as_ref()
+        .and_then(|info| info.config.as_ref())
+        .and_then(|config| config.params.as_ref())
+        .and_then(|params| params.sparse_vectors_config.as_ref())
+        .is_some_and(|sparse| sparse.map.contains_key(SPARSE_VECTOR_NAME))
+}
+
+fn dense_vector_params(info: &GetCollectionInfoResponse) -> Option<&VectorParams> {
+    let vectors = info
+        .result
+        .as_ref()
+        .and_then(|info| info.config.as_ref())
+        .and_then(|config| config.params.as_ref())
+        .and_then(|params| params.vectors_config.as_ref())
+        .and_then(|vectors| vectors.config.as_ref());
+    match vectors {
+        Some(VectorsConfigKind::Params(params)) => Some(params),
+        _ => None,
+    }
+}
+
+pub(crate) fn distance_metric_of(info: &GetCollectionInfoResponse) -> DistanceMetric {
+    dense_vector_params(info)
+        .map(|params| distance_metric_from_qdrant(params.distance))
+        .unwrap_or_default()
+}
+
+/// Dimensions of the collection's dense vectors, if it has a single unnamed one
+pub(crate) fn vector_size_of(info: &GetCollectionInfoResponse) -> Option<u64> {
+    dense_vector_params(info).map(|params| params.size)
+}
+
+fn distance_metric_from_qdrant(distance: i32) -> DistanceMetric {
+    match Distance::try_from(distance) {
+        Ok(Distance::Dot) => DistanceMetric::Dot,
+        // Both score by distance, lower is better
+        Ok(Distance::Euclid) | Ok(Distance::Manhattan) => DistanceMetric::Euclidean,
+        _ => DistanceMetric::Cosine,
+    }
+}
+
+/// Score threshold to send to Qdrant. For Euclidean collections the score is
+/// a distance and the threshold an upper bound, so the default of 0 would
+/// drop everything and is treated as "no threshold".
+fn effective_score_threshold(metric: DistanceMetric, score_threshold: f32) -> Option<f32> {
+    if metric.higher_is_better() || score_threshold > 0.0 {
+        Some(score_threshold)
+    } else {
+        None
+    }
+}
+
+/// Metric to rescore dense candidates with when a search asks for
+/// `requested` on a collection created with `collection`, or `None` when the
+/// collection's own scores already fit. Cosine collections store normalized
+/// vectors, so their candidates can't be rescored with another metric.
+pub(crate) fn resolve_metric_override(
+    collection: DistanceMetric,
+    requested: Option<DistanceMetric>,
+) -> Result<Option<DistanceMetric>, String> {
+    match requested {
+        None => Ok(None),
+        Some(requested) if requested == collection => Ok(None),
+        Some(requested) if collection == DistanceMetric::Cosine => Err(format!(
+            "distance_metric '{}' is not available for this collection: it was created for cosine similarity and stores normalized vectors",
+            requested.as_str()
+        )),
+        Some(requested) => Ok(Some(requested)),
+    }
+}
+
+/// Score of `vector` against `query` under `metric`, on the same scale
+/// Qdrant uses (Euclidean scores are distances)
+fn similarity(metric: DistanceMetric, query: &[f32], vector: &[f32]) -> f32 {
+    let dot: f32 = query.iter().zip(vector).map(|(a, b)| a * b).sum();
+    match metric {
+        DistanceMetric::Dot => dot,
+        DistanceMetric::Cosine => {
+            let norms = query.iter().map(|a| a * a).sum::<f32>().sqrt()
+                * vector.iter().map(|b| b * b).sum::<f32>().sqrt();
+            if norms > 0.0 { dot / norms } else { 0.0 }
+        }
+        DistanceMetric::Euclidean => query
+            .iter()
+            .zip(vector)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt(),
+    }
+}
+
+/// Rescore candidates with their stored vectors under `metric`, drop those
+/// past the score threshold and order the rest best first
+fn rescore_candidates(
+    query: &[f32],
+    candidates: Vec<(SearchMatch, Vec<f32>)>,
+    metric: DistanceMetric,
+    score_threshold: f32,
+) -> Vec<SearchMatch> {
+    let threshold = effective_score_threshold(metric, score_threshold);
+    let mut rescored: Vec<SearchMatch> = candidates
+        .into_iter()
+        .map(|(candidate, vector)| SearchMatch {
+            score: similarity(metric, query, &vector),
+            ..candidate
+        })
+        .filter(|candidate| match threshold {
+            Some(threshold) if metric.higher_is_better() => candidate.score >= threshold,
+            Some(threshold) => candidate.score <= threshold,
+            None => true,
+        })
+        .collect();
+    if metric.higher_is_better() {
+        rescored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    } else {
+        rescored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal));
+    }
+    rescored
+}
+
+/// The dense vector of a point fetched with its vectors
+fn dense_vector(point: &ScoredPoint) -> Option<Vec<f32>> {
+    let vector = match point.vectors.as_ref()?.vectors_options.as_ref()? {
+        VectorsOptions::Vector(vector) => vector.clone(),
+        // Collections with the BM25 vector name the dense one ""
+        VectorsOptions::Vectors(named) => named.vectors.get("")?.clone(),
+    };
+    match vector.into_vector() {
+        VectorOutputKind::Dense(dense) => Some(dense.data),
+        _ => None,
+    }
+}
 
+/// Search a collection in the given retrieval mode. Sparse and hybrid modes
+/// need a collection with the BM25 sparse vector; `query_vector` is unused in
+/// sparse mode.
 pub(crate) async fn search_collection(
     qdrant: &Qdrant,
     collection_name: &str,
     query_vector: &[f32],
     request: &SearchRequest,
     search_batch_size: u64,
+    metric: DistanceMetric,
+    mode: RetrievalMode,
+) -> Result<Vec<SearchMatch>> {
+    // Keyword candidates: the requested chunks, or one batch to group into documents
+    let sparse_limit = match request.search_mode {
+        SearchMode::Chunks => request.limit,
+        SearchMode::Documents => search_batch_size,
+    };
+
+    match mode {
+        RetrievalMode::Dense => {
+            dense_search(
+                qdrant,
+                collection_name,
+                query_vector,
+                request,
+                search_batch_size,
+                metric,
+            )
+            .await
+        }
+        RetrievalMode::Sparse => {
+            sparse_search(qdrant, collection_name, request, sparse_limit).await
+        }
+        RetrievalMode::Hybrid => {
+            let (dense, sparse) = futures_util::try_join!(
+                dense_search(
+                    qdrant,
+                    collection_name,
+                    query_vector,
+                    request,
+                    search_batch_size,
+                    metric,
+                ),
+                sparse_search(qdrant, collection_name, request, sparse_limit),
+            )?;
+            let mut fused = reciprocal_rank_fusion(dense, sparse);
+            if matches!(request.search_mode, SearchMode::Chunks) {
+                fused.truncate(request.limit as usize);
+            }
+            Ok(fused)
+        }
+    }
+}
+
+async fn dense_search(
+    qdrant: &Qdrant,
+    collection_name: &str,
+    query_vector: &[f32],
+    request: &SearchRequest,
+    search_batch_size: u64,
+    metric: DistanceMetric,
 ) -> Result<Vec<SearchMatch>> {
     // In document mode, fetch chunks in batches until we have enough unique documents
     if matches!(request.search_mode, SearchMode::Documents) {
@@ -41,6 +271,7 @@ pub(crate) async fn search_collection(
                 request,
                 search_batch_size,
                 offset,
+                metric,
             )
             .await?;
 
@@ -85,6 +316,7 @@ pub(crate) async fn search_collection(
             request,
             request.limit,
             0,
+            metric,
         )
         .await
     }
@@ -97,14 +329,180 @@ async fn search_batch(
     request: &SearchRequest,
     limit: u64,
     offset: u64,
+    metric: DistanceMetric,
 ) -> Result<Vec<SearchMatch>> {
+    let rescore_metric =
+        resolve_metric_override(metric, request.distance_metric).map_err(anyhow::Error::msg)?;
+
     let mut search_builder =
         SearchPointsBuilder::new(collection_name, query_vector.to_vec(), limit)
-            .score_threshold(request.score_threshold)
             .with_payload(true)
             .offset(offset);
+    // Rescored candidates are thresholded on their new scores instead
+    if rescore_metric.is_some() {
+        search_builder = search_builder.with_vectors(true);
+    } else if let Some(threshold) = effective_score_threshold(metric, request.score_threshold) {
+        search_builder = search_builder.score_threshold(threshold);
+    }
+
+    if let Some(filter) = build_filter(request.filters.as_ref()) {
+        search_builder = search_builder.filter(filter);
+    }
+
+    if let Some(params) = &request.search_params
+        && let Some(hnsw_ef) = params.hnsw_ef
+    {
+        search_builder =
+            search_builder.params(SearchParamsBuilder::default().hnsw_ef(hnsw_ef).build());
+    }
+
+    let search_result = qdrant.search_points(search_builder).await?;
+
+    let Some(rescore_metric) = rescore_metric else {
+        return Ok(search_result
+            .result
+            .into_iter()
+            .map(scored_point_to_match)
+            .collect());
+    };
+    let candidates = search_result
+        .result
+        .into_iter()
+        .filter_map(|point| {
+            let vector = dense_vector(&point)?;
+            Some((scored_point_to_match(point), vector))
+        })
+        .collect();
+    Ok(rescore_candidates(
+        query_vector,
+        candidates,
+        rescore_metric,
+        request.score_threshold,
+    ))
+}
+
+/// Dense chunk search for many query vectors in one Qdrant request. The
+/// result holds each query's matches at the query's index.
+pub(crate) async fn search_collection_batch(
+    qdrant: &Qdrant,
+    collection_name: &str,
+    query_vectors: Vec<Vec<f32>>,
+    request: &BatchSearchRequest,
+    metric: DistanceMetric,
+) -> Result<Vec<Vec<SearchMatch>>> {
+    let threshold = effective_score_threshold(metric, request.score_threshold);
+    let filter = build_filter(request.filters.as_ref());
+    let hnsw_ef = request.search_params.as_ref().and_then(|p| p.hnsw_ef);
+
+    let queries = query_vectors
+        .into_iter()
+        .map(|vector| {
+            let mut query_builder = QueryPointsBuilder::new(collection_name)
+                .query(Query::new_nearest(vector))
+                .limit(request.limit)
+                .with_payload(true);
+            if let Some(threshold) = threshold {
+                query_builder = query_builder.score_threshold(threshold);
+            }
+            if let Some(filter) = &filter {
+                query_builder = query_builder.filter(filter.clone());
+            }
+            if let Some(hnsw_ef) = hnsw_ef {
+                query_builder =
+                    query_builder.params(SearchParamsBuilder::default().hnsw_ef(hnsw_ef).build());
+            }
+            query_builder.build()
+        })
+        .collect();
+
+    Ok(query_batch(qdrant, collection_name, queries)
+        .await?
+        .into_iter()
+        .map(|points| points.into_iter().map(scored_point_to_match).collect())
+        .collect())
+}
+
+/// BM25 keyword search over the collection's sparse vectors
+async fn sparse_search(
+    qdrant: &Qdrant,
+    collection_name: &str,
+    request: &SearchRequest,
+    limit: u64,
+) -> Result<Vec<SearchMatch>> {
+    let terms = sparse::query_vector(&request.query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query_builder = QueryPointsBuilder::new(collection_name)
+        .query(Query::new_nearest(VectorInput::new_sparse(
+            terms.indices,
+            terms.values,
+        )))
+        .using(SPARSE_VECTOR_NAME)
+        .limit(limit)
+        .with_payload(true);
+    if let Some(filter) = build_filter(request.filters.as_ref()) {
+        query_builder = query_builder.filter(filter);
+    }
+
+    let response = qdrant.query(query_builder).await?;
+    Ok(response
+        .result
+        .into_iter()
+        .map(scored_point_to_match)
+        .collect())
+}
+
+/// Fuse dense and sparse rankings with Reciprocal Rank Fusion. Matches are
+/// ordered by fused score and keep their per-source scores and ranks.
+pub(crate) fn reciprocal_rank_fusion(
+    dense: Vec<SearchMatch>,
+    sparse: Vec<SearchMatch>,
+) -> Vec<SearchMatch> {
+    let mut fused: Vec<SearchMatch> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (is_dense, ranking) in [(true, dense), (false, sparse)] {
+        for (index, ranked) in ranking.into_iter().enumerate() {
+            let rank = index + 1;
+            let source_score = ranked.score;
+            let position = match positions.get(&ranked.id) {
+                Some(position) => *position,
+                None => {
+                    positions.insert(ranked.id.clone(), fused.len());
+                    fused.push(SearchMatch {
+                        score: 0.0,
+                        score_breakdown: Some(ScoreBreakdown::default()),
+                        ..ranked
+                    });
+                    fused.len() - 1
+                }
+            };
 
-    if let Some(filters) = &request.filters
+            let fused_match = &mut fused[position];
+            let breakdown = fused_match.score_breakdown.get_or_insert_default();
+            let (score_slot, rank_slot) = if is_dense {
+                (&mut breakdown.dense_score, &mut breakdown.dense_rank)
+            } else {
+                (&mut breakdown.sparse_score, &mut breakdown.sparse_rank)
+            };
+            // A point listed twice by one source only counts at its best rank
+            if rank_slot.is_none() {
+                *score_slot = Some(source_score);
+                *rank_slot = Some(rank);
+                fused_match.score += 1.0 / (RRF_K + rank as f32);
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    fused
+}
+
+/// Payload filter from the request's `filters` (exact match on metadata fields)
+fn build_filter(filters: Option<&serde_json::Value>) -> Option<Filter> {
+    if let Some(filters) = filters
         && let Some(obj) = filters.as_object()
     {
         let mut conditions = Vec::new();
@@ -137,64 +535,53 @@ async fn search_batch(
         }
 
         if !conditions.is_empty() {
-            search_builder = search_builder.filter(Filter {
+            return Some(Filter {
                 must: conditions,
                 ..Default::default()
             });
         }
     }
+    None
+}
 
-    if let Some(params) = &request.search_params
-        && let Some(hnsw_ef) = params.hnsw_ef
-    {
-        search_builder =
-            search_builder.params(SearchParamsBuilder::default().hnsw_ef(hnsw_ef).build());
-    }
-
-    let search_result = qdrant.search_points(search_builder).await?;
-
-    let matches = search_result
-        .result
-        .into_iter()
-        .map(|point| {
-            let text = point
-                .payload
-                .get("text")
-                .and_then(|v| {
-                    if let Some(Kind::StringValue(s)) = &v.kind {
-                        Some(s.clone())
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or_default();
-
-            let mut metadata_map = serde_json::Map::new();
-            for (key, value) in &point.payload {
-                if key != "text"
-                    && let Some(json_val) = qdrant_value_to_json(value)
-                {
-                    metadata_map.insert(key.clone(), json_val);
-                }
-            }
-
-            SearchMatch {
-                id: point
-                    .id
-                    .map(|id| match id.point_id_options {
-                        Some(PointIdOptions::Uuid(u)) => u,
-                        Some(PointIdOptions::Num(n)) => n.to_string(),
-                        None => format!("{:?}", id),
-                    })
-                    .unwrap_or_default(),
-                score: point.score,
-                text,
-                metadata: serde_json::Value::Object(metadata_map),
+fn scored_point_to_match(point: ScoredPoint) -> SearchMatch {
+    let text = point
+        .payload
+        .get("text")
+        .and_then(|v| {
+            if let Some(Kind::StringValue(s)) = &v.kind {
+                Some(s.clone())
+            } else {
+                None
             }
         })
-        .collect();
+        .unwrap_or_default();
+
+    let mut metadata_map = serde_json::Map::new();
+    for (key, value) in &point.payload {
+        if key != "text"
+            && let Some(json_val) = qdrant_value_to_json(value)
+        {
+            metadata_map.insert(key.clone(), json_val);
+        }
+    }
 
-    Ok(matches)
+    SearchMatch {
+        id: point
+            .id
+            .map(|id| match id.point_id_options {
+                Some(PointIdOptions::Uuid(u)) => u,
+                Some(PointIdOptions::Num(n)) => n.to_string(),
+                None => format!("{:?}", id),
+            })
+            .unwrap_or_default(),
+        score: point.score,
+        text,
+        metadata: serde_json::Value::Object(metadata_map),
+        score_breakdown: None,
+        retrieval_score: None,
+        explanation: None,
+    }
 }
 
 pub(crate) fn qdrant_value_to_json(value: &QdrantValue) -> Option<serde_json::Value> {
@@ -229,8 +616,21 @@ pub(crate) fn qdrant_value_to_json(value: &QdrantValue) -> Option<serde_json::Va
 }
 
 /// Aggregate matches into unique documents based on item_id
-/// Returns a list of documents sorted by best score (descending)
-pub(crate) fn aggregate_matches_to_documents(matches: &[SearchMatch]) -> Vec<DocumentResult> {
+/// Returns a list of documents sorted best first; when scores are distances
+/// (`higher_is_better` is false) the best score is the smallest one
+pub(crate) fn aggregate_matches_to_documents(
+    matches: &[SearchMatch],
+    higher_is_better: bool,
+) -> Vec<DocumentResult> {
+    // Orders scores so that the better one compares greater
+    let better = |a: f32, b: f32| {
+        let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        if higher_is_better {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    };
     let mut document_map: HashMap<i32, (f32, Vec<&SearchMatch>)> = HashMap::new();
 
     for match_result in matches {
@@ -249,11 +649,13 @@ pub(crate) fn aggregate_matches_to_documents(matches: &[SearchMatch]) -> Vec<Doc
             continue;
         }
 
-        let entry = document_map.entry(item_id).or_insert((0.0, Vec::new()));
+        let entry = document_map
+            .entry(item_id)
+            .or_insert((match_result.score, Vec::new()));
         entry.1.push(match_result);
 
         // Track best score
-        if match_result.score > entry.0 {
+        if better(match_result.score, entry.0) == Ordering::Greater {
             entry.0 = match_result.score;
         }
     }
@@ -261,10 +663,10 @@ pub(crate) fn aggregate_matches_to_documents(matches: &[SearchMatch]) -> Vec<Doc
     let mut documents: Vec<DocumentResult> = document_map
         .into_iter()
         .map(|(item_id, (best_score, chunks))| {
-            // Find the best chunk (highest score)
+            // Find the best chunk
             let best_chunk = chunks
                 .iter()
-                .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal))
+                .max_by(|a, b| better(a.score, b.score))
                 .unwrap();
 
             let item_title = best_chunk
@@ -284,12 +686,226 @@ pub(crate) fn aggregate_matches_to_documents(matches: &[SearchMatch]) -> Vec<Doc
         })
         .collect();
 
-    // Sort documents by score (descending)
-    documents.sort_by(|a, b| {
-        b.best_score
-            .partial_cmp(&a.best_score)
-            .unwrap_or(Ordering::Equal)
-    });
+    // Sort documents best first
+    documents.sort_by(|a, b| better(b.best_score, a.best_score));
 
     documents
 }
+
+/// Distinct warnings for matches whose embedding stamp doesn't match the
+/// embedder used for the query. Points indexed before stamping are skipped.
+pub(crate) fn embedding_version_warnings(
+    query_stamp: &EmbeddingStamp,
+    matches: &[SearchMatch],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for search_match in matches {
+        if let Some(indexed) = EmbeddingStamp::from_payload(&search_match.metadata)
+            && let Some(warning) = query_stamp.mismatch(&indexed)
+            && !warnings.contains(&warning)
+        {
+            warnings.push(warning);
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn search_match(metadata: serde_json::Value) -> SearchMatch {
+        SearchMatch {
+            id: "1".to_string(),
+            score: 0.9,
+            text: "text".to_string(),
+            metadata,
+            score_breakdown: None,
+            retrieval_score: None,
+            explanation: None,
+        }
+    }
+
+    fn scored(item_id: i64, score: f32) -> SearchMatch {
+        SearchMatch {
+            id: format!("{item_id}-{score}"),
+            score,
+            ..search_match(json!({ "item_id": item_id }))
+        }
+    }
+
+    #[test]
+    fn test_documents_rank_by_collection_metric() {
+        let matches = vec![scored(1, 0.5), scored(1, 2.0), scored(2, 1.0)];
+
+        let docs = aggregate_matches_to_documents(&matches, DistanceMetric::Dot.higher_is_better());
+        assert_eq!(
+            docs.iter()
+                .map(|d| (d.item_id, d.best_score))
+                .collect::<Vec<_>>(),
+            vec![(1, 2.0), (2, 1.0)]
+        );
+
+        // Euclidean scores are distances: smaller is closer
+        let docs =
+            aggregate_matches_to_documents(&matches, DistanceMetric::Euclidean.higher_is_better());
+        assert_eq!(
+            docs.iter()
+                .map(|d| (d.item_id, d.best_score))
+                .collect::<Vec<_>>(),
+            vec![(1, 0.5), (2, 1.0)]
+        );
+        assert_eq!(docs[0].best_chunk.score, 0.5);
+        assert_eq!(docs[0].chunk_count, 2);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_rewards_agreement() {
+        let dense = vec![scored(1, 0.9), scored(2, 0.8), scored(3, 0.7)];
+        // Same point as the dense rank 3, with its BM25 score
+        let sparse = vec![
+            SearchMatch {
+                score: 12.0,
+                ..scored(3, 0.7)
+            },
+            scored(4, 9.5),
+        ];
+
+        let fused = reciprocal_rank_fusion(dense, sparse);
+        let ids: Vec<&str> = fused.iter().map(|m| m.id.as_str()).collect();
+        // Found by both retrievers, so it beats the dense-only top hit
+        // Ties keep dense order first
+        assert_eq!(ids, vec!["3-0.7", "1-0.9", "2-0.8", "4-9.5"]);
+        assert!((fused[0].score - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-6);
+
+        assert_eq!(
+            fused[0].score_breakdown,
+            Some(ScoreBreakdown {
+                dense_score: Some(0.7),
+                dense_rank: Some(3),
+                sparse_score: Some(12.0),
+                sparse_rank: Some(1),
+            })
+        );
+        let keyword_only = fused[3].score_breakdown.as_ref().unwrap();
+        assert_eq!(keyword_only.dense_rank, None);
+        assert_eq!(keyword_only.sparse_rank, Some(2));
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_counts_duplicates_once() {
+        let fused = reciprocal_rank_fusion(vec![scored(1, 0.9), scored(1, 0.9)], Vec::new());
+        assert_eq!(fused.len(), 1);
+        assert!((fused[0].score - 1.0 / 61.0).abs() < 1e-6);
+        assert!(reciprocal_rank_fusion(Vec::new(), Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_score_threshold_and_qdrant_distance_mapping() {
+        assert_eq!(
+            effective_score_threshold(DistanceMetric::Cosine, 0.0),
+            Some(0.0)
+        );
+        assert_eq!(
+            effective_score_threshold(DistanceMetric::Euclidean, 0.0),
+            None
+        );
+        assert_eq!(
+            effective_score_threshold(DistanceMetric::Euclidean, 1.5),
+            Some(1.5)
+        );
+
+        assert_eq!(
+            distance_metric_from_qdrant(Distance::Dot as i32),
+            DistanceMetric::Dot
+        );
+        assert_eq!(
+            distance_metric_from_qdrant(Distance::Euclid as i32),
+            DistanceMetric::Euclidean
+        );
+        assert_eq!(
+            distance_metric_from_qdrant(Distance::Cosine as i32),
+            DistanceMetric::Cosine
+        );
+        assert_eq!(distance_metric_from_qdrant(99), DistanceMetric::Cosine);
+    }
+
+    #[test]
+    fn test_metric_override_is_validated_against_collection() {
+        assert_eq!(
+            resolve_metric_override(DistanceMetric::Dot, Some(DistanceMetric::Cosine)),
+            Ok(Some(DistanceMetric::Cosine))
+        );
+        assert_eq!(
+            resolve_metric_override(DistanceMetric::Euclidean, Some(DistanceMetric::Dot)),
+            Ok(Some(DistanceMetric::Dot))
+        );
+        // Same metric as the collection, or none: Qdrant's scores are used
+        assert_eq!(
+            resolve_metric_override(DistanceMetric::Cosine, Some(DistanceMetric::Cosine)),
+            Ok(None)
+        );
+        assert_eq!(resolve_metric_override(DistanceMetric::Dot, None), Ok(None));
+        // Normalized vectors can't give back dot or Euclidean scores
+        assert!(
+            resolve_metric_override(DistanceMetric::Cosine, Some(DistanceMetric::Dot)).is_err()
+        );
+        assert!(
+            resolve_metric_override(DistanceMetric::Cosine, Some(DistanceMetric::Euclidean))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rescoring_applies_override_metric() {
+        let query = [1.0, 0.0];
+        // Long vector at an angle vs. short vector pointing the same way
+        let candidates = || {
+            vec![
+                (scored(1, 0.0), vec![3.0, 3.0]),
+                (scored(2, 0.0), vec![0.5, 0.0]),
+            ]
+        };
+
+        let dot = rescore_candidates(&query, candidates(), DistanceMetric::Dot, 0.0);
+        assert_eq!(dot[0].id, "1-0");
+        assert_eq!(dot[0].score, 3.0);
+
+        let cosine = rescore_candidates(&query, candidates(), DistanceMetric::Cosine, 0.0);
+        assert_eq!(cosine[0].id, "2-0");
+        assert!((cosine[0].score - 1.0).abs() < 1e-6);
+
+        // Distances rank smallest first, and a threshold of 0 means no limit
+        let euclidean = rescore_candidates(&query, candidates(), DistanceMetric::Euclidean, 0.0);
+        assert_eq!(euclidean[0].id, "2-0");
+        assert!((euclidean[0].score - 0.5).abs() < 1e-6);
+        assert_eq!(euclidean.len(), 2);
+
+        // Thresholds apply to the new scores
+        let dot = rescore_candidates(&query, candidates(), DistanceMetric::Dot, 1.0);
+        assert_eq!(dot.len(), 1);
+        let euclidean = rescore_candidates(&query, candidates(), DistanceMetric::Euclidean, 1.0);
+        assert_eq!(euclidean.len(), 1);
+    }
+
+    #[test]
+    fn test_version_mismatch_surfaces_warning() {
+        let indexed = EmbeddingStamp::new(Some(3), "openai", "model-a", &json!({}), 512);
+        let mut metadata = serde_json::Map::new();
+        indexed.stamp_payload(&mut metadata);
+        let matches = vec![
+            search_match(serde_json::Value::Object(metadata.clone())),
+            search_match(serde_json::Value::Object(metadata)),
+            // Indexed before stamping; can't be checked
+            search_match(json!({ "item_id": 1 })),
+        ];
+
+        assert!(embedding_version_warnings(&indexed, &matches).is_empty());
+
+        let query = EmbeddingStamp::new(Some(3), "openai", "model-b", &json!({}), 512);
+        let warnings = embedding_version_warnings(&query, &matches);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("model-a"));
+    }
+}
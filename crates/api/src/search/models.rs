@@ -1,12 +1,17 @@
+use semantic_explorer_core::models::DistanceMetric;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::search::explain::SearchExplanation;
+
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub(crate) struct SearchRequest {
     pub query: String,
     pub embedded_dataset_ids: Vec<i32>,
     #[serde(default = "default_limit")]
     pub limit: u64,
+    /// Minimum score; for Euclidean collections the maximum distance, where
+    /// `0` means no limit
     #[serde(default)]
     pub score_threshold: f32,
     #[serde(default)]
@@ -15,6 +20,49 @@ pub(crate) struct SearchRequest {
     pub search_params: Option<SearchParams>,
     #[serde(default)]
     pub search_mode: SearchMode,
+    /// Dense (vector), sparse (BM25 keyword) or hybrid retrieval
+    #[serde(default)]
+    pub retrieval_mode: RetrievalMode,
+    /// Warn when results were embedded with a different embedder, model
+    /// version or preprocessing config than the query
+    #[serde(default)]
+    pub check_embedding_version: bool,
+    /// Reorder the top candidates with a cross-encoder reranker
+    #[serde(default)]
+    pub rerank: bool,
+    /// Reranker model; defaults to the server's configured reranker
+    #[serde(default)]
+    pub rerank_model: Option<String>,
+    /// Score dense candidates with this metric (`cosine`, `dot` or
+    /// `euclidean`) instead of the collection's. Candidates are fetched by
+    /// the collection's metric and rescored from their stored vectors, which
+    /// collections created for cosine similarity don't keep.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub distance_metric: Option<DistanceMetric>,
+    /// Attach to each hit the score from every search stage it went through
+    /// and the filters it matched
+    #[serde(default)]
+    pub explain: bool,
+}
+
+/// Many queries against one embedded dataset, searched with a single Qdrant
+/// request. Results are dense chunk matches.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub(crate) struct BatchSearchRequest {
+    pub queries: Vec<String>,
+    pub embedded_dataset_id: i32,
+    /// Matches per query
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    /// Minimum score; for Euclidean collections the maximum distance, where
+    /// `0` means no limit
+    #[serde(default)]
+    pub score_threshold: f32,
+    #[serde(default)]
+    pub filters: Option<serde_json::Value>,
+    #[serde(default)]
+    pub search_params: Option<SearchParams>,
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema, Clone, Copy, Default)]
@@ -25,6 +73,26 @@ pub(crate) enum SearchMode {
     Chunks,
 }
 
+#[derive(Debug, Deserialize, Serialize, ToSchema, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RetrievalMode {
+    #[default]
+    Dense,
+    Sparse,
+    /// Dense and sparse results fused with Reciprocal Rank Fusion
+    Hybrid,
+}
+
+impl RetrievalMode {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dense => "dense",
+            Self::Sparse => "sparse",
+            Self::Hybrid => "hybrid",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub(crate) struct SearchParams {
     #[serde(default)]
@@ -41,6 +109,21 @@ pub(crate) struct SearchResponse {
     pub results: Vec<EmbeddedDatasetSearchResults>,
     pub query: String,
     pub search_mode: SearchMode,
+    pub retrieval_mode: RetrievalMode,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct BatchSearchResponse {
+    pub embedded_dataset_id: i32,
+    pub collection_name: String,
+    /// One entry per query, in request order
+    pub results: Vec<BatchQueryResult>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct BatchQueryResult {
+    pub query: String,
+    pub matches: Vec<SearchMatch>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -64,14 +147,35 @@ pub(crate) struct EmbeddedDatasetSearchResults {
     pub matches: Vec<SearchMatch>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub documents: Option<Vec<DocumentResult>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
     pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub(crate) struct SearchMatch {
     pub id: String,
+    /// Qdrant score, the fused RRF score in hybrid mode, or the reranker's
+    /// relevance score when reranked
     pub score: f32,
     pub text: String,
     #[schema(value_type = Object)]
     pub metadata: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_breakdown: Option<ScoreBreakdown>,
+    /// Score the match was retrieved with, before reranking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_score: Option<f32>,
+    /// How the match was found and scored, for `explain` searches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<SearchExplanation>,
+}
+
+/// Per-source scores and 1-based ranks behind a hybrid match
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub(crate) struct ScoreBreakdown {
+    pub dense_score: Option<f32>,
+    pub dense_rank: Option<usize>,
+    pub sparse_score: Option<f32>,
+    pub sparse_rank: Option<usize>,
 }
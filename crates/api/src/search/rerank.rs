@@ -0,0 +1,279 @@
+//! Second-stage reranking of search candidates.
+//!
+//! Vector search over-fetches candidates, then the inference API's
+//! cross-encoder (`/api/rerank`) scores each candidate text against the
+//! query. Results are reordered by that relevance score; the retrieval score
+//! they were fetched with is kept in `retrieval_score`.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use futures_util::{StreamExt, stream};
+use semantic_explorer_core::http_client::HTTP_CLIENT;
+use serde::{Deserialize, Serialize};
+
+use crate::search::models::{DocumentResult, SearchMatch};
+
+#[derive(Serialize)]
+struct RerankDocument<'a> {
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+struct RerankRequest<'a> {
+    query: &'a str,
+    documents: Vec<RerankDocument<'a>>,
+    model: &'a str,
+    top_k: usize,
+    return_scores: bool,
+}
+
+#[derive(Deserialize)]
+struct RerankResult {
+    index: usize,
+    score: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct RerankResponse {
+    results: Vec<RerankResult>,
+}
+
+/// Relevance order of `texts` as `(index, score)` pairs, best first, at most
+/// `top_n` long
+pub(crate) async fn rerank_scores(
+    inference_url: &str,
+    model: &str,
+    query: &str,
+    texts: &[&str],
+    top_n: usize,
+) -> Result<Vec<(usize, f32)>> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let endpoint = format!("{}/api/rerank", inference_url.trim_end_matches('/'));
+    let request = RerankRequest {
+        query,
+        documents: texts.iter().map(|text| RerankDocument { text }).collect(),
+        model,
+        top_k: top_n,
+        return_scores: true,
+    };
+
+    let resp = HTTP_CLIENT.post(&endpoint).json(&request).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        bail!("Rerank API error {status}: {text}");
+    }
+    let response: RerankResponse = resp.json().await?;
+
+    response
+        .results
+        .into_iter()
+        .take(top_n)
+        .map(|result| {
+            if result.index >= texts.len() {
+                bail!("Reranker returned unknown document index {}", result.index);
+            }
+            let score = result
+                .score
+                .ok_or_else(|| anyhow!("Reranker returned no score"))?;
+            Ok((result.index, score))
+        })
+        .collect()
+}
+
+/// Text to rerank each candidate by, from `fetch`, with at most
+/// `concurrency` fetches in flight. A candidate whose fetch fails or takes
+/// longer than `timeout` is dropped instead of failing the search; the rest
+/// keep their order. Returns the kept candidates and how many were dropped.
+pub(crate) async fn fetch_candidate_texts<T, F, Fut>(
+    candidates: Vec<T>,
+    concurrency: usize,
+    timeout: Duration,
+    fetch: F,
+) -> (Vec<(T, String)>, usize)
+where
+    F: Fn(&T) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let texts: Vec<_> = stream::iter(
+        candidates
+            .iter()
+            .map(|candidate| tokio::time::timeout(timeout, fetch(candidate))),
+    )
+    .buffered(concurrency.max(1))
+    .collect()
+    .await;
+
+    let total = candidates.len();
+    let kept: Vec<_> = candidates
+        .into_iter()
+        .zip(texts)
+        .filter_map(|(candidate, text)| match text {
+            Ok(Ok(text)) => Some((candidate, text)),
+            Ok(Err(e)) => {
+                tracing::debug!(error = %e, "Dropping rerank candidate, fetch failed");
+                None
+            }
+            Err(_) => {
+                tracing::debug!(?timeout, "Dropping rerank candidate, fetch timed out");
+                None
+            }
+        })
+        .collect();
+    let dropped = total - kept.len();
+    (kept, dropped)
+}
+
+/// Chunks in reranked order, scored by relevance
+pub(crate) fn reorder_matches(
+    matches: Vec<SearchMatch>,
+    order: &[(usize, f32)],
+) -> Vec<SearchMatch> {
+    reorder(matches, order, |search_match, score| {
+        search_match.retrieval_score = Some(search_match.score);
+        search_match.score = score;
+    })
+}
+
+/// Documents in the reranked order of their best chunks, scored by relevance
+pub(crate) fn reorder_documents(
+    documents: Vec<DocumentResult>,
+    order: &[(usize, f32)],
+) -> Vec<DocumentResult> {
+    reorder(documents, order, |document, score| {
+        document.best_chunk.retrieval_score = Some(document.best_chunk.score);
+        document.best_chunk.score = score;
+        document.best_score = score;
+    })
+}
+
+fn reorder<T>(items: Vec<T>, order: &[(usize, f32)], rescore: impl Fn(&mut T, f32)) -> Vec<T> {
+    let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order
+        .iter()
+        .filter_map(|&(index, score)| {
+            let mut item = slots.get_mut(index)?.take()?;
+            rescore(&mut item, score);
+            Some(item)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn candidate(id: &str, score: f32) -> SearchMatch {
+        SearchMatch {
+            id: id.to_string(),
+            score,
+            text: format!("text {id}"),
+            metadata: json!({}),
+            score_breakdown: None,
+            retrieval_score: None,
+            explanation: None,
+        }
+    }
+
+    #[test]
+    fn test_reorder_matches_by_relevance() {
+        let candidates = vec![
+            candidate("a", 0.9),
+            candidate("b", 0.8),
+            candidate("c", 0.7),
+        ];
+
+        // The reranker prefers the vector search's last candidate
+        let reranked = reorder_matches(candidates, &[(2, 4.5), (0, 1.0)]);
+        assert_eq!(
+            reranked
+                .iter()
+                .map(|m| (m.id.as_str(), m.score, m.retrieval_score))
+                .collect::<Vec<_>>(),
+            vec![("c", 4.5, Some(0.7)), ("a", 1.0, Some(0.9))]
+        );
+
+        // Repeated or out-of-range indices are ignored
+        let reranked = reorder_matches(vec![candidate("a", 0.9)], &[(0, 2.0), (0, 1.0), (5, 0.5)]);
+        assert_eq!(reranked.len(), 1);
+        assert_eq!(reranked[0].score, 2.0);
+    }
+
+    #[test]
+    fn test_reorder_documents_rescores_best_chunk() {
+        let document = |item_id: i32, score: f32| DocumentResult {
+            item_id,
+            item_title: format!("doc {item_id}"),
+            best_score: score,
+            chunk_count: 1,
+            best_chunk: candidate(&item_id.to_string(), score),
+        };
+
+        let reranked = reorder_documents(vec![document(1, 0.9), document(2, 0.5)], &[(1, 3.0)]);
+        assert_eq!(reranked.len(), 1);
+        assert_eq!(reranked[0].item_id, 2);
+        assert_eq!(reranked[0].best_score, 3.0);
+        assert_eq!(reranked[0].best_chunk.retrieval_score, Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_candidate_texts_runs_concurrently() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let fetch = |id: &i32| {
+            let id = *id;
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(format!("document {id}"))
+            }
+        };
+
+        let (kept, dropped) =
+            fetch_candidate_texts((1..=6).collect(), 3, Duration::from_secs(5), fetch).await;
+        assert_eq!(dropped, 0);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            kept.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(kept[3].1, "document 4");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_candidate_texts_drops_slow_and_failed_candidates() {
+        let fetch = |id: &i32| {
+            let id = *id;
+            async move {
+                match id {
+                    2 => {
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                        Ok("too late".to_string())
+                    }
+                    3 => Err(anyhow!("item was deleted")),
+                    _ => Ok(format!("document {id}")),
+                }
+            }
+        };
+
+        let (kept, dropped) =
+            fetch_candidate_texts(vec![1, 2, 3, 4], 4, Duration::from_millis(50), fetch).await;
+        assert_eq!(dropped, 2);
+        assert_eq!(
+            kept,
+            vec![(1, "document 1".to_string()), (4, "document 4".to_string())]
+        );
+    }
+}
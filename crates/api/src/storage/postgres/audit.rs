@@ -1,6 +1,10 @@
-use sqlx::{Pool, Postgres};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Postgres, Transaction};
+use utoipa::ToSchema;
 
 use crate::audit::AuditEvent;
+use crate::audit_chain;
 
 const INSERT_AUDIT_EVENT_QUERY: &str = r#"
     INSERT INTO audit_events (
@@ -11,38 +15,322 @@ const INSERT_AUDIT_EVENT_QUERY: &str = r#"
         username_display,
         resource_type,
         resource_id,
-        details
-    )
-    VALUES (
-        $1::timestamp with time zone, $2, $3, $4, $5, $6, $7, $8
+        details,
+        request_id,
+        event_id,
+        prev_hash,
+        hash
     )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
     ON CONFLICT DO NOTHING
 "#;
 
-/// Stores an audit event in the database using a connection pool (non-transactional).
-/// For transactional operations, use `store_audit_event` with a transaction instead.
+/// Arbitrary key for the advisory lock serializing writes to the hash chain
+const AUDIT_CHAIN_LOCK_KEY: i64 = 0x6175_6469_7463_6861;
+
+/// Hash of the newest chained event, or of the last archived one when every
+/// chained event has been archived
+const GET_CHAIN_HEAD_QUERY: &str = r#"
+    SELECT COALESCE(
+        (SELECT hash FROM audit_events WHERE hash IS NOT NULL
+         ORDER BY audit_event_id DESC LIMIT 1),
+        (SELECT last_event_hash FROM audit_archives ORDER BY archive_id DESC LIMIT 1)
+    )
+"#;
+
+/// Stores an audit event, chained onto the newest stored event (see
+/// [`crate::audit_chain`]). Writes are serialized by an advisory lock so the
+/// chain stays linear.
 pub async fn store_audit_event_simple(
     pool: &Pool<Postgres>,
     event: &AuditEvent,
 ) -> Result<(), sqlx::Error> {
-    let result = sqlx::query(INSERT_AUDIT_EVENT_QUERY)
-        .bind(&event.timestamp)
-        .bind(format!("{:?}", event.event_type))
-        .bind(format!("{:?}", event.outcome))
-        .bind(&event.user)
-        .bind(&event.user_display)
-        .bind(
-            event
-                .resource_type
-                .as_ref()
-                .map(|rt| format!("{:?}", rt))
-                .as_deref(),
-        )
-        .bind(event.resource_id.as_deref())
-        .bind(event.details.as_deref())
-        .execute(pool)
-        .await;
-
-    result?;
+    let mut stored = StoredAuditEvent::from(event);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(AUDIT_CHAIN_LOCK_KEY)
+        .execute(&mut *tx)
+        .await?;
+    let prev_hash: Option<String> = sqlx::query_scalar(GET_CHAIN_HEAD_QUERY)
+        .fetch_one(&mut *tx)
+        .await?;
+    let prev_hash = prev_hash.unwrap_or_else(|| audit_chain::GENESIS_HASH.to_string());
+    stored.hash = Some(audit_chain::event_hash(&prev_hash, &stored));
+    stored.prev_hash = Some(prev_hash);
+
+    sqlx::query(INSERT_AUDIT_EVENT_QUERY)
+        .bind(stored.timestamp)
+        .bind(&stored.event_type)
+        .bind(&stored.outcome)
+        .bind(&stored.user_id)
+        .bind(&stored.username_display)
+        .bind(stored.resource_type.as_deref())
+        .bind(stored.resource_id.as_deref())
+        .bind(stored.details.as_deref())
+        .bind(stored.request_id.as_deref())
+        .bind(stored.event_id.as_deref())
+        .bind(stored.prev_hash.as_deref())
+        .bind(stored.hash.as_deref())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// An audit row as listed, exported and written to an archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
+pub(crate) struct StoredAuditEvent {
+    pub(crate) audit_event_id: i64,
+    #[schema(value_type = String, format = DateTime)]
+    pub(crate) timestamp: DateTime<Utc>,
+    pub(crate) event_type: String,
+    pub(crate) outcome: String,
+    pub(crate) user_id: String,
+    pub(crate) username_display: String,
+    pub(crate) resource_type: Option<String>,
+    pub(crate) resource_id: Option<String>,
+    pub(crate) details: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) event_id: Option<String>,
+    /// Hash of the previous event in the chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) prev_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) hash: Option<String>,
+}
+
+impl From<&AuditEvent> for StoredAuditEvent {
+    /// The row `event` is stored as, before it is chained
+    fn from(event: &AuditEvent) -> Self {
+        let timestamp = DateTime::parse_from_rfc3339(&event.timestamp)
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        StoredAuditEvent {
+            audit_event_id: 0,
+            // Truncated to what Postgres keeps, so the hash matches the row
+            timestamp: DateTime::from_timestamp_micros(timestamp.timestamp_micros())
+                .unwrap_or(timestamp),
+            event_type: format!("{:?}", event.event_type),
+            outcome: format!("{:?}", event.outcome),
+            user_id: event.user.clone(),
+            username_display: event.user_display.clone(),
+            resource_type: event.resource_type.as_ref().map(|rt| format!("{:?}", rt)),
+            resource_id: event.resource_id.clone(),
+            details: event.details.clone(),
+            request_id: event.request_id.clone(),
+            event_id: Some(event.event_id.clone()).filter(|id| !id.is_empty()),
+            prev_hash: None,
+            hash: None,
+        }
+    }
+}
+
+/// A batch of audit events moved to S3
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub(crate) struct AuditArchive {
+    pub(crate) object_key: String,
+    pub(crate) first_event_id: i64,
+    pub(crate) last_event_id: i64,
+    pub(crate) event_count: i32,
+    pub(crate) prev_digest: String,
+    pub(crate) digest: String,
+    /// Chain hash of the archive's last event, where audit events resume
+    pub(crate) last_event_hash: Option<String>,
+}
+
+/// Arbitrary key for the advisory lock serializing archival across replicas
+const AUDIT_ARCHIVE_LOCK_KEY: i64 = 0x6175_6469_7461_7263;
+
+const GET_EXPIRED_AUDIT_EVENTS_QUERY: &str = r#"
+    SELECT audit_event_id, timestamp, event_type, outcome, user_id,
+           username_display, resource_type, resource_id, details, request_id,
+           event_id, prev_hash, hash
+    FROM audit_events
+    WHERE timestamp < $1
+      AND audit_event_id < COALESCE(
+          (SELECT min(audit_event_id) FROM audit_events WHERE timestamp >= $1),
+          9223372036854775807
+      )
+    ORDER BY audit_event_id
+    LIMIT $2
+    FOR UPDATE
+"#;
+
+const GET_LAST_AUDIT_ARCHIVE_QUERY: &str = r#"
+    SELECT object_key, first_event_id, last_event_id, event_count, prev_digest, digest,
+           last_event_hash
+    FROM audit_archives
+    ORDER BY archive_id DESC
+    LIMIT 1
+"#;
+
+const INSERT_AUDIT_ARCHIVE_QUERY: &str = r#"
+    INSERT INTO audit_archives (
+        object_key, first_event_id, last_event_id, event_count, prev_digest, digest,
+        last_event_hash
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
+"#;
+
+const DELETE_AUDIT_EVENTS_QUERY: &str = "DELETE FROM audit_events WHERE audit_event_id = ANY($1)";
+
+/// Take the archival lock for the rest of the transaction; false when
+/// another replica holds it
+pub(crate) async fn try_lock_audit_archival(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+        .bind(AUDIT_ARCHIVE_LOCK_KEY)
+        .fetch_one(&mut **tx)
+        .await
+}
+
+/// The oldest events written before `cutoff`, locked until the transaction
+/// ends. Stops at the first event that hasn't expired, so what is archived is
+/// always a prefix of the chain and the events left behind still link from
+/// the last archived hash.
+pub(crate) async fn get_expired_audit_events(
+    tx: &mut Transaction<'_, Postgres>,
+    cutoff: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<StoredAuditEvent>, sqlx::Error> {
+    sqlx::query_as::<_, StoredAuditEvent>(GET_EXPIRED_AUDIT_EVENTS_QUERY)
+        .bind(cutoff)
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await
+}
+
+/// The most recent archive, if any
+pub(crate) async fn get_last_audit_archive(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Option<AuditArchive>, sqlx::Error> {
+    sqlx::query_as::<_, AuditArchive>(GET_LAST_AUDIT_ARCHIVE_QUERY)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+/// Record `archive` and delete the events it holds
+pub(crate) async fn commit_audit_archive(
+    tx: &mut Transaction<'_, Postgres>,
+    archive: &AuditArchive,
+    event_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(INSERT_AUDIT_ARCHIVE_QUERY)
+        .bind(&archive.object_key)
+        .bind(archive.first_event_id)
+        .bind(archive.last_event_id)
+        .bind(archive.event_count)
+        .bind(&archive.prev_digest)
+        .bind(&archive.digest)
+        .bind(archive.last_event_hash.as_deref())
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query(DELETE_AUDIT_EVENTS_QUERY)
+        .bind(event_ids)
+        .execute(&mut **tx)
+        .await?;
     Ok(())
 }
+
+/// Filters for listing audit events; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AuditEventFilter {
+    /// Hashed user id or display name
+    pub(crate) actor: Option<String>,
+    pub(crate) resource_type: Option<String>,
+    pub(crate) resource_id: Option<String>,
+    pub(crate) event_type: Option<String>,
+    pub(crate) outcome: Option<String>,
+    pub(crate) request_id: Option<String>,
+    /// Inclusive lower bound
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound
+    pub(crate) to: Option<DateTime<Utc>>,
+}
+
+/// Position after the last event of a page; events are ordered newest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AuditCursor {
+    pub(crate) timestamp: DateTime<Utc>,
+    pub(crate) audit_event_id: i64,
+}
+
+const LIST_AUDIT_EVENTS_QUERY: &str = r#"
+    SELECT audit_event_id, timestamp, event_type, outcome, user_id,
+           username_display, resource_type, resource_id, details, request_id,
+           event_id, prev_hash, hash
+    FROM audit_events
+    WHERE ($1::text IS NULL OR user_id = $1 OR username_display = $1)
+      AND ($2::text IS NULL OR resource_type = $2)
+      AND ($3::text IS NULL OR resource_id = $3)
+      AND ($4::text IS NULL OR event_type = $4)
+      AND ($5::text IS NULL OR outcome = $5)
+      AND ($6::text IS NULL OR request_id = $6)
+      AND ($7::timestamptz IS NULL OR timestamp >= $7)
+      AND ($8::timestamptz IS NULL OR timestamp < $8)
+      AND ($9::timestamptz IS NULL OR (timestamp, audit_event_id) < ($9, $10))
+    ORDER BY timestamp DESC, audit_event_id DESC
+    LIMIT $11
+"#;
+
+/// Up to `limit` events matching `filter` after `cursor`, newest first
+pub(crate) async fn list_audit_events(
+    pool: &Pool<Postgres>,
+    filter: &AuditEventFilter,
+    cursor: Option<AuditCursor>,
+    limit: i64,
+) -> Result<Vec<StoredAuditEvent>, sqlx::Error> {
+    sqlx::query_as::<_, StoredAuditEvent>(LIST_AUDIT_EVENTS_QUERY)
+        .bind(filter.actor.as_deref())
+        .bind(filter.resource_type.as_deref())
+        .bind(filter.resource_id.as_deref())
+        .bind(filter.event_type.as_deref())
+        .bind(filter.outcome.as_deref())
+        .bind(filter.request_id.as_deref())
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(cursor.map(|c| c.timestamp))
+        .bind(cursor.map(|c| c.audit_event_id))
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+const GET_AUDIT_EVENTS_AFTER_QUERY: &str = r#"
+    SELECT audit_event_id, timestamp, event_type, outcome, user_id,
+           username_display, resource_type, resource_id, details, request_id,
+           event_id, prev_hash, hash
+    FROM audit_events
+    WHERE audit_event_id > $1
+    ORDER BY audit_event_id
+    LIMIT $2
+"#;
+
+const GET_LAST_ARCHIVED_EVENT_HASH_QUERY: &str =
+    "SELECT last_event_hash FROM audit_archives ORDER BY archive_id DESC LIMIT 1";
+
+/// Up to `limit` events with ids above `after_id`, in chain order
+pub(crate) async fn get_audit_events_after(
+    pool: &Pool<Postgres>,
+    after_id: i64,
+    limit: i64,
+) -> Result<Vec<StoredAuditEvent>, sqlx::Error> {
+    sqlx::query_as::<_, StoredAuditEvent>(GET_AUDIT_EVENTS_AFTER_QUERY)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+/// Chain hash of the newest archived event, if any was chained
+pub(crate) async fn get_last_archived_event_hash(
+    pool: &Pool<Postgres>,
+) -> Result<Option<String>, sqlx::Error> {
+    let hash: Option<Option<String>> = sqlx::query_scalar(GET_LAST_ARCHIVED_EVENT_HASH_QUERY)
+        .fetch_optional(pool)
+        .await?;
+    Ok(hash.flatten())
+}
@@ -0,0 +1,215 @@
+//! Bulk re-embed runs.
+//!
+//! A run re-embeds several datasets with one embedder. Each dataset gets a
+//! dataset transform, created disabled so the scanner leaves it alone until
+//! the run starts it; `started_at` records when that happened.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use semantic_explorer_core::owner_info::OwnerInfo;
+use sqlx::{FromRow, Pool, Postgres};
+
+use super::dataset_transforms::create_dataset_transform_in_transaction;
+
+const CREATE_BULK_REEMBED_QUERY: &str = r#"
+    INSERT INTO bulk_reembeds (owner_id, embedder_id)
+    VALUES ($1, $2)
+    RETURNING bulk_reembed_id, owner_id, embedder_id
+"#;
+
+const ADD_BULK_REEMBED_TRANSFORM_QUERY: &str = r#"
+    INSERT INTO bulk_reembed_transforms (bulk_reembed_id, dataset_id, dataset_transform_id, position)
+    VALUES ($1, $2, $3, $4)
+"#;
+
+const GET_BULK_REEMBED_QUERY: &str = r#"
+    SELECT bulk_reembed_id, owner_id, embedder_id
+    FROM bulk_reembeds
+    WHERE bulk_reembed_id = $1 AND owner_id = $2
+"#;
+
+const GET_BULK_REEMBED_TRANSFORMS_QUERY: &str = r#"
+    SELECT t.dataset_id, t.dataset_transform_id, r.owner_id, t.started_at
+    FROM bulk_reembed_transforms t
+    JOIN bulk_reembeds r ON r.bulk_reembed_id = t.bulk_reembed_id
+    WHERE t.bulk_reembed_id = $1
+    ORDER BY t.position
+"#;
+
+const GET_QUEUED_REEMBEDS_QUERY: &str = r#"
+    SELECT t.dataset_id, t.dataset_transform_id, r.owner_id, t.started_at
+    FROM bulk_reembed_transforms t
+    JOIN bulk_reembeds r ON r.bulk_reembed_id = t.bulk_reembed_id
+    WHERE t.started_at IS NULL
+    ORDER BY t.bulk_reembed_id, t.position
+"#;
+
+const MARK_REEMBED_STARTED_QUERY: &str = r#"
+    UPDATE bulk_reembed_transforms
+    SET started_at = NOW()
+    WHERE dataset_transform_id = $1 AND started_at IS NULL
+"#;
+
+const ENABLE_DATASET_TRANSFORM_QUERY: &str = r#"
+    UPDATE dataset_transforms
+    SET is_enabled = TRUE, updated_at = NOW()
+    WHERE dataset_transform_id = $1
+"#;
+
+#[derive(Debug, Clone, FromRow)]
+pub(crate) struct BulkReembed {
+    pub(crate) bulk_reembed_id: i32,
+    pub(crate) owner_id: String,
+    pub(crate) embedder_id: i32,
+}
+
+/// A dataset in a bulk re-embed run and its transform
+#[derive(Debug, Clone, FromRow)]
+pub(crate) struct BulkReembedTransform {
+    pub(crate) dataset_id: i32,
+    pub(crate) dataset_transform_id: i32,
+    pub(crate) owner_id: String,
+    pub(crate) started_at: Option<DateTime<Utc>>,
+}
+
+/// Create a run with a disabled transform for each of `datasets` (dataset id
+/// and transform title, in start order). Everything is created in one
+/// transaction, so a failure part way leaves no run or transforms behind.
+/// Returns the run and its transform ids.
+#[tracing::instrument(name = "database.create_bulk_reembed", skip(pool, owner, datasets), fields(database.system = "postgresql", database.operation = "INSERT", owner_id = %owner.owner_id, embedder_id = %embedder_id, dataset_count = %datasets.len()))]
+pub(crate) async fn create_bulk_reembed(
+    pool: &Pool<Postgres>,
+    owner: &OwnerInfo,
+    embedder_id: i32,
+    datasets: &[(i32, String)],
+) -> Result<(BulkReembed, Vec<i32>)> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    let run = sqlx::query_as::<_, BulkReembed>(CREATE_BULK_REEMBED_QUERY)
+        .bind(&owner.owner_id)
+        .bind(embedder_id)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to create bulk re-embed")?;
+
+    let mut dataset_transform_ids = Vec::with_capacity(datasets.len());
+    for (position, (dataset_id, title)) in datasets.iter().enumerate() {
+        let (transform, _) = create_dataset_transform_in_transaction(
+            &mut tx,
+            title,
+            *dataset_id,
+            &[embedder_id],
+            owner,
+            &serde_json::json!({}),
+            false,
+        )
+        .await
+        .with_context(|| format!("Failed to create re-embed transform for dataset {dataset_id}"))?;
+        sqlx::query(ADD_BULK_REEMBED_TRANSFORM_QUERY)
+            .bind(run.bulk_reembed_id)
+            .bind(dataset_id)
+            .bind(transform.dataset_transform_id)
+            .bind(position as i32)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to add dataset {dataset_id} to bulk re-embed"))?;
+        dataset_transform_ids.push(transform.dataset_transform_id);
+    }
+
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((run, dataset_transform_ids))
+}
+
+#[tracing::instrument(name = "database.get_bulk_reembed", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", bulk_reembed_id = %bulk_reembed_id))]
+pub(crate) async fn get_bulk_reembed(
+    pool: &Pool<Postgres>,
+    owner_id: &str,
+    bulk_reembed_id: i32,
+) -> Result<BulkReembed> {
+    sqlx::query_as::<_, BulkReembed>(GET_BULK_REEMBED_QUERY)
+        .bind(bulk_reembed_id)
+        .bind(owner_id)
+        .fetch_one(pool)
+        .await
+        .context("Bulk re-embed not found")
+}
+
+/// The datasets of a run, in start order
+#[tracing::instrument(name = "database.get_bulk_reembed_transforms", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", bulk_reembed_id = %bulk_reembed_id))]
+pub(crate) async fn get_bulk_reembed_transforms(
+    pool: &Pool<Postgres>,
+    bulk_reembed_id: i32,
+) -> Result<Vec<BulkReembedTransform>> {
+    sqlx::query_as::<_, BulkReembedTransform>(GET_BULK_REEMBED_TRANSFORMS_QUERY)
+        .bind(bulk_reembed_id)
+        .fetch_all(pool)
+        .await
+        .context("Failed to fetch bulk re-embed transforms")
+}
+
+/// Transforms of every run that have not been started, oldest run first
+#[tracing::instrument(name = "database.get_queued_reembeds", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT"))]
+pub(crate) async fn get_queued_reembeds(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<BulkReembedTransform>> {
+    sqlx::query_as::<_, BulkReembedTransform>(GET_QUEUED_REEMBEDS_QUERY)
+        .fetch_all(pool)
+        .await
+        .context("Failed to fetch queued re-embeds")
+}
+
+/// Record that a queued transform was started and enable it, so later scans
+/// pick it up
+#[tracing::instrument(name = "database.mark_reembed_started", skip(pool), fields(database.system = "postgresql", database.operation = "UPDATE", dataset_transform_id = %dataset_transform_id))]
+pub(crate) async fn mark_reembed_started(
+    pool: &Pool<Postgres>,
+    dataset_transform_id: i32,
+) -> Result<()> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    sqlx::query(MARK_REEMBED_STARTED_QUERY)
+        .bind(dataset_transform_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(ENABLE_DATASET_TRANSFORM_QUERY)
+        .bind(dataset_transform_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_failed_create_leaves_nothing_behind() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::migrate!("src/storage/postgres/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let owner = OwnerInfo::new(
+            format!("bulk-reembed-test-{}", uuid::Uuid::new_v4()),
+            "Bulk re-embed test".to_string(),
+        );
+
+        // The run is inserted before the missing dataset fails its transform
+        let result =
+            create_bulk_reembed(&pool, &owner, 1, &[(i32::MAX, "missing".to_string())]).await;
+        assert!(result.is_err());
+
+        let runs: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM bulk_reembeds WHERE owner_id = $1")
+                .bind(&owner.owner_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(runs, 0);
+    }
+}
@@ -18,6 +18,9 @@ struct ChatSessionWithCount {
     pub embedded_dataset_id: i32,
     pub llm_id: i32,
     pub title: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub token_budget: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub total_count: i64,
@@ -35,6 +38,9 @@ impl ChatSessionWithCount {
                 embedded_dataset_id: r.embedded_dataset_id,
                 llm_id: r.llm_id,
                 title: r.title,
+                prompt_tokens: r.prompt_tokens,
+                completion_tokens: r.completion_tokens,
+                token_budget: r.token_budget,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             })
@@ -44,19 +50,19 @@ impl ChatSessionWithCount {
 }
 
 const CREATE_SESSION_QUERY: &str = r#"
-    INSERT INTO chat_sessions (session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, created_at, updated_at)
-    VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW())
-    RETURNING session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, created_at, updated_at
+    INSERT INTO chat_sessions (session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, token_budget, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
+    RETURNING session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, prompt_tokens, completion_tokens, token_budget, created_at, updated_at
 "#;
 
 const GET_SESSION_QUERY: &str = r#"
-    SELECT session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, created_at, updated_at
+    SELECT session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, prompt_tokens, completion_tokens, token_budget, created_at, updated_at
     FROM chat_sessions
     WHERE session_id = $1 AND owner_id = $2
 "#;
 
 const GET_SESSIONS_QUERY: &str = r#"
-    SELECT session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, created_at, updated_at,
+    SELECT session_id, owner_id, owner_display_name, embedded_dataset_id, llm_id, title, prompt_tokens, completion_tokens, token_budget, created_at, updated_at,
         COUNT(*) OVER() AS total_count
     FROM chat_sessions
     WHERE owner_id = $1
@@ -64,6 +70,12 @@ const GET_SESSIONS_QUERY: &str = r#"
     LIMIT $2 OFFSET $3
 "#;
 
+const ADD_SESSION_USAGE_QUERY: &str = r#"
+    UPDATE chat_sessions
+    SET prompt_tokens = prompt_tokens + $2, completion_tokens = completion_tokens + $3
+    WHERE session_id = $1
+"#;
+
 const DELETE_SESSION_QUERY: &str = r#"
     DELETE FROM chat_sessions WHERE session_id = $1 AND owner_id = $2
 "#;
@@ -153,6 +165,7 @@ pub(crate) async fn create_chat_session(
         .bind(request.embedded_dataset_id)
         .bind(request.llm_id)
         .bind(&title)
+        .bind(request.token_budget)
         .fetch_one(pool)
         .await;
 
@@ -197,6 +210,23 @@ pub(crate) async fn get_chat_sessions(
     })
 }
 
+/// Add one LLM call's token usage to the session's running totals
+#[tracing::instrument(name = "database.add_chat_session_usage", skip(pool), fields(database.system = "postgresql", database.operation = "UPDATE"))]
+pub(crate) async fn add_chat_session_usage(
+    pool: &Pool<Postgres>,
+    session_id: &str,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+) -> Result<()> {
+    sqlx::query(ADD_SESSION_USAGE_QUERY)
+        .bind(session_id)
+        .bind(prompt_tokens)
+        .bind(completion_tokens)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 #[tracing::instrument(name = "database.delete_chat_session", skip(pool), fields(database.system = "postgresql", database.operation = "DELETE", owner_id = %owner_id))]
 pub(crate) async fn delete_chat_session(
     pool: &Pool<Postgres>,
@@ -48,6 +48,12 @@ const GET_COLLECTION_QUERY: &str = r#"
     WHERE c.collection_id = $1 AND c.owner_id = $2
 "#;
 
+const GET_COLLECTION_BY_ID_QUERY: &str = r#"
+    SELECT c.collection_id, c.title, c.details, c.owner_id, c.owner_display_name, c.tags, c.is_public, c.created_at, c.updated_at
+    FROM collections c
+    WHERE c.collection_id = $1
+"#;
+
 const GET_COLLECTIONS_PAGINATED_QUERY: &str = r#"
     SELECT collection_id, title, details, owner_id, owner_display_name, tags, is_public, created_at, updated_at,
         COUNT(*) OVER() AS total_count
@@ -113,6 +119,33 @@ const TOUCH_COLLECTION_UPDATED_AT_QUERY: &str = r#"
     WHERE collection_id = $1
 "#;
 
+const RECORD_PENDING_UPLOAD_QUERY: &str = r#"
+    INSERT INTO collection_pending_uploads (collection_id, file_key, owner_id, expires_at)
+    VALUES ($1, $2, $3, $4)
+    ON CONFLICT (collection_id, file_key)
+    DO UPDATE SET owner_id = EXCLUDED.owner_id, expires_at = EXCLUDED.expires_at
+"#;
+
+const GET_PENDING_UPLOADS_QUERY: &str = r#"
+    SELECT collection_id, file_key, owner_id, expires_at
+    FROM collection_pending_uploads
+    ORDER BY created_at
+    LIMIT $1
+"#;
+
+const DELETE_PENDING_UPLOAD_QUERY: &str = r#"
+    DELETE FROM collection_pending_uploads WHERE collection_id = $1 AND file_key = $2
+"#;
+
+/// A file a client was given a presigned upload URL for
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub(crate) struct PendingUpload {
+    pub(crate) collection_id: i32,
+    pub(crate) file_key: String,
+    pub(crate) owner_id: String,
+    pub(crate) expires_at: DateTime<Utc>,
+}
+
 #[tracing::instrument(name = "database.get_collection", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", owner_id = %owner_id, collection_id = %collection_id))]
 pub(crate) async fn get_collection(
     pool: &Pool<Postgres>,
@@ -128,6 +161,21 @@ pub(crate) async fn get_collection(
     Ok(result?)
 }
 
+/// Fetch a collection regardless of owner; callers must check visibility
+/// with [`crate::collections::access::check_read_access`].
+#[tracing::instrument(name = "database.get_collection_by_id", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", collection_id = %collection_id))]
+pub(crate) async fn get_collection_by_id(
+    pool: &Pool<Postgres>,
+    collection_id: i32,
+) -> Result<Collection> {
+    let result = sqlx::query_as::<_, Collection>(GET_COLLECTION_BY_ID_QUERY)
+        .bind(collection_id)
+        .fetch_one(pool)
+        .await;
+
+    Ok(result?)
+}
+
 #[tracing::instrument(name = "database.get_collections_paginated", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", owner_id = %owner_id, limit = %limit, offset = %offset))]
 pub(crate) async fn get_collections_paginated(
     pool: &Pool<Postgres>,
@@ -257,14 +305,15 @@ pub(crate) async fn grab_public_collection(
         s3_bucket_name,
         collection_id,
         new_collection.collection_id,
+        &crate::storage::s3::models::CopyOptions::default(),
     )
     .await
     {
-        Ok(copied_count) => {
+        Ok(summary) => {
             tracing::info!(
                 source_collection_id = %collection_id,
                 destination_collection_id = %new_collection.collection_id,
-                copied_count = copied_count,
+                copied_count = summary.copied,
                 "Successfully copied S3 files for grabbed collection"
             );
 
@@ -327,3 +376,50 @@ pub(crate) async fn touch_collection_updated_at(
     result?;
     Ok(())
 }
+
+/// Remember a key handed out for a presigned upload so the scanner can
+/// dispatch jobs for it once the object exists
+#[tracing::instrument(name = "database.record_pending_upload", skip(pool), fields(database.system = "postgresql", database.operation = "INSERT", collection_id = %collection_id, file_key = %file_key))]
+pub(crate) async fn record_pending_upload(
+    pool: &Pool<Postgres>,
+    collection_id: i32,
+    file_key: &str,
+    owner_id: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(RECORD_PENDING_UPLOAD_QUERY)
+        .bind(collection_id)
+        .bind(file_key)
+        .bind(owner_id)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Oldest pending presigned uploads, across all collections
+#[tracing::instrument(name = "database.get_pending_uploads", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT"))]
+pub(crate) async fn get_pending_uploads(
+    pool: &Pool<Postgres>,
+    limit: i64,
+) -> Result<Vec<PendingUpload>> {
+    let uploads = sqlx::query_as::<_, PendingUpload>(GET_PENDING_UPLOADS_QUERY)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(uploads)
+}
+
+#[tracing::instrument(name = "database.delete_pending_upload", skip(pool), fields(database.system = "postgresql", database.operation = "DELETE", collection_id = %collection_id, file_key = %file_key))]
+pub(crate) async fn delete_pending_upload(
+    pool: &Pool<Postgres>,
+    collection_id: i32,
+    file_key: &str,
+) -> Result<()> {
+    sqlx::query(DELETE_PENDING_UPLOAD_QUERY)
+        .bind(collection_id)
+        .bind(file_key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
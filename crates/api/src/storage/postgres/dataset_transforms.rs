@@ -1,4 +1,5 @@
 use crate::embedded_datasets::EmbeddedDataset;
+use crate::embedded_datasets::naming::template_from_job_config;
 use crate::storage::postgres::INTERNAL_BATCH_SIZE;
 use crate::storage::postgres::embedded_datasets::{
     delete_embedded_dataset_in_transaction, get_embedded_datasets_for_transform_in_transaction,
@@ -101,8 +102,8 @@ const GET_ACTIVE_DATASET_TRANSFORMS_QUERY: &str = r#"
 "#;
 
 const CREATE_DATASET_TRANSFORM_QUERY: &str = r#"
-    INSERT INTO dataset_transforms (title, source_dataset_id, embedder_ids, owner_id, owner_display_name, job_config)
-    VALUES ($1, $2, $3, $4, $5, $6)
+    INSERT INTO dataset_transforms (title, source_dataset_id, embedder_ids, owner_id, owner_display_name, job_config, is_enabled)
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
     RETURNING dataset_transform_id, title, source_dataset_id, embedder_ids, owner_id, owner_display_name, is_enabled,
               job_config, created_at, updated_at
 "#;
@@ -119,6 +120,13 @@ const UPDATE_DATASET_TRANSFORM_QUERY: &str = r#"
               job_config, created_at, updated_at
 "#;
 
+const SET_PINNED_EMBEDDERS_QUERY: &str = r#"
+    UPDATE dataset_transforms
+    SET job_config = job_config || jsonb_build_object('pinned_embedders', $2::jsonb)
+    WHERE dataset_transform_id = $1
+    RETURNING job_config
+"#;
+
 const DELETE_DATASET_TRANSFORM_QUERY: &str = r#"
     DELETE FROM dataset_transforms
     WHERE dataset_transform_id = $1 AND owner_id = $2
@@ -385,13 +393,37 @@ pub async fn create_dataset_transform(
     embedder_ids: &[i32],
     owner: &OwnerInfo,
     job_config: &serde_json::Value,
+    is_enabled: bool,
 ) -> Result<(DatasetTransform, Vec<EmbeddedDataset>)> {
     let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    let created = create_dataset_transform_in_transaction(
+        &mut tx,
+        title,
+        source_dataset_id,
+        embedder_ids,
+        owner,
+        job_config,
+        is_enabled,
+    )
+    .await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(created)
+}
 
+/// Creates a Dataset Transform and its Embedded Datasets within a transaction
+pub(crate) async fn create_dataset_transform_in_transaction(
+    tx: &mut Transaction<'_, Postgres>,
+    title: &str,
+    source_dataset_id: i32,
+    embedder_ids: &[i32],
+    owner: &OwnerInfo,
+    job_config: &serde_json::Value,
+    is_enabled: bool,
+) -> Result<(DatasetTransform, Vec<EmbeddedDataset>)> {
     // Step 1: Get source dataset total_chunks to calculate total_chunks_to_process
     let total_chunks: i64 = sqlx::query_scalar(GET_SOURCE_DATASET_TOTAL_CHUNKS_QUERY)
         .bind(source_dataset_id)
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await
         .context("Failed to fetch source dataset total_chunks")?;
 
@@ -403,14 +435,15 @@ pub async fn create_dataset_transform(
         .bind(&owner.owner_id)
         .bind(&owner.owner_display_name)
         .bind(job_config)
-        .fetch_one(&mut *tx)
+        .bind(is_enabled)
+        .fetch_one(&mut **tx)
         .await
         .context("Failed to create dataset transform")?;
 
     // Step 3: Initialize stats with total_chunks_to_process = source total_chunks * embedder_count
     let total_chunks_to_process = total_chunks * embedder_ids.len() as i64;
     super::dataset_transform_stats::initialize_stats(
-        &mut tx,
+        tx,
         transform.dataset_transform_id,
         total_chunks_to_process,
     )
@@ -421,12 +454,13 @@ pub async fn create_dataset_transform(
     let mut embedded_datasets = Vec::new();
     for embedder_id in embedder_ids {
         let embedded_dataset = create_embedded_dataset_internal(
-            &mut tx,
+            tx,
             transform.dataset_transform_id,
             source_dataset_id,
             *embedder_id,
             owner,
             &transform.title,
+            &transform.job_config,
         )
         .await
         .context(format!(
@@ -436,8 +470,6 @@ pub async fn create_dataset_transform(
         embedded_datasets.push(embedded_dataset);
     }
 
-    tx.commit().await.context("Failed to commit transaction")?;
-
     Ok((transform, embedded_datasets))
 }
 
@@ -478,6 +510,21 @@ pub async fn update_dataset_transform(
     Ok((transform, embedded_datasets))
 }
 
+/// Store the embedder pins of a transform run, leaving the rest of its
+/// `job_config` untouched. Returns the updated `job_config`.
+pub async fn set_pinned_embedders(
+    pool: &Pool<Postgres>,
+    dataset_transform_id: i32,
+    pins: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let job_config = sqlx::query_scalar::<_, serde_json::Value>(SET_PINNED_EMBEDDERS_QUERY)
+        .bind(dataset_transform_id)
+        .bind(pins)
+        .fetch_one(pool)
+        .await?;
+    Ok(job_config)
+}
+
 pub async fn delete_dataset_transform(
     pool: &Pool<Postgres>,
     owner: &str,
@@ -523,12 +570,12 @@ async fn create_embedded_dataset_internal(
     embedder_id: i32,
     owner: &OwnerInfo,
     dataset_transform_title: &str,
+    job_config: &serde_json::Value,
 ) -> Result<EmbeddedDataset> {
     // Import the create function from embedded_datasets module
     use crate::storage::postgres::embedded_datasets::create_embedded_dataset_in_transaction;
 
     let title = format!("{dataset_transform_title}-{embedder_id}");
-    let collection_name = EmbeddedDataset::generate_collection_name(0, &owner.owner_id); // Will be updated with actual ID
 
     create_embedded_dataset_in_transaction(
         tx,
@@ -537,7 +584,7 @@ async fn create_embedded_dataset_internal(
         source_dataset_id,
         embedder_id,
         owner,
-        &collection_name,
+        template_from_job_config(job_config),
         None, // dimensions will be derived from embedder for transform-based datasets
     )
     .await
@@ -584,6 +631,7 @@ async fn sync_embedded_datasets(
             embedder_id,
             &owner,
             &transform.title,
+            &transform.job_config,
         )
         .await?;
     }
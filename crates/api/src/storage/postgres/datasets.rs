@@ -1,9 +1,11 @@
 use anyhow::Result;
 use sqlx::{
-    Pool, Postgres,
+    Pool, Postgres, Row,
     types::chrono::{DateTime, Utc},
 };
+use std::collections::HashMap;
 
+use crate::datasets::dedup::{self, DedupKey, DedupOptions, ExistingItem, NewItem};
 use crate::datasets::models::{ChunkWithMetadata, Dataset, DatasetItem};
 
 const GET_DATASET_QUERY: &str = r#"
@@ -102,6 +104,18 @@ const GET_DATASET_ITEMS_FOR_SCANNER_QUERY: &str = r#"
     LIMIT $3
 "#;
 
+/// Already-scanned items (item_id <= $2) updated after $3, paginated by
+/// (updated_at, item_id) along idx_dataset_items_watermark
+const GET_DATASET_ITEMS_UPDATED_SINCE_QUERY: &str = r#"
+    SELECT item_id, dataset_id, title, chunks, metadata, created_at, COALESCE(updated_at, created_at) as updated_at
+    FROM dataset_items
+    WHERE dataset_id = $1
+      AND item_id <= $2
+      AND (COALESCE(updated_at, created_at), item_id) > ($3, $4)
+    ORDER BY COALESCE(updated_at, created_at) ASC, item_id ASC
+    LIMIT $5
+"#;
+
 /// Get the dataset's version (updated_at timestamp) for efficient stats refresh (#4)
 const GET_DATASET_VERSION_QUERY: &str = r#"
     SELECT COALESCE(updated_at, created_at)
@@ -169,6 +183,38 @@ const CREATE_DATASET_ITEMS_BATCH: &str = r#"
     RETURNING item_id, dataset_id, title, chunks, metadata, created_at, updated_at
 "#;
 
+/// Existing item per incoming title; `$2` are the keys, matched by position
+const FIND_DUPLICATES_BY_TITLE_QUERY: &str = r#"
+    SELECT DISTINCT ON (i.ord) i.ord, d.item_id, d.chunks, d.metadata
+    FROM unnest($2::text[]) WITH ORDINALITY AS i(key, ord)
+    JOIN dataset_items d ON d.dataset_id = $1 AND d.title = i.key
+    ORDER BY i.ord, d.item_id
+"#;
+
+const FIND_DUPLICATES_BY_METADATA_QUERY: &str = r#"
+    SELECT DISTINCT ON (i.ord) i.ord, d.item_id, d.chunks, d.metadata
+    FROM unnest($2::text[]) WITH ORDINALITY AS i(key, ord)
+    JOIN dataset_items d ON d.dataset_id = $1 AND d.metadata->>$3 = i.key
+    ORDER BY i.ord, d.item_id
+"#;
+
+/// Hashes the incoming chunks the same way as the `content_hash` column
+const FIND_DUPLICATES_BY_CONTENT_QUERY: &str = r#"
+    SELECT DISTINCT ON (i.ord) i.ord, d.item_id, d.chunks, d.metadata
+    FROM unnest($2::jsonb[]) WITH ORDINALITY AS i(chunks, ord)
+    JOIN dataset_items d ON d.dataset_id = $1
+        AND d.content_hash = md5(jsonb_path_query_array(i.chunks, '$[*].content')::text)
+    ORDER BY i.ord, d.item_id
+"#;
+
+const UPDATE_DATASET_ITEMS_BATCH: &str = r#"
+    UPDATE dataset_items d
+    SET chunks = u.chunks, metadata = u.metadata, updated_at = NOW()
+    FROM unnest($2::int[], $3::jsonb[], $4::jsonb[]) AS u(item_id, chunks, metadata)
+    WHERE d.dataset_id = $1 AND d.item_id = u.item_id
+    RETURNING d.item_id, d.dataset_id, d.title, d.chunks, d.metadata, d.created_at, d.updated_at
+"#;
+
 #[tracing::instrument(name = "database.get_dataset", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", owner_id = %owner_id, dataset_id = %dataset_id))]
 pub(crate) async fn get_dataset(
     pool: &Pool<Postgres>,
@@ -387,6 +433,116 @@ pub(crate) async fn create_dataset_items_batch(
     Ok((successful, failed))
 }
 
+/// Outcome of an upload with deduplication
+#[derive(Default)]
+pub(crate) struct DedupedItems {
+    pub(crate) created: Vec<DatasetItem>,
+    pub(crate) updated: Vec<DatasetItem>,
+    /// Titles of duplicates left as they were
+    pub(crate) skipped: Vec<String>,
+    pub(crate) failed: Vec<String>,
+}
+
+/// Insert items, matching them to existing items by `dedup.key` first:
+/// duplicates are skipped, or updated if they changed and
+/// `dedup.on_duplicate` is `update`
+#[tracing::instrument(name = "database.create_dataset_items_deduplicated", skip(pool, items, dedup), fields(database.system = "postgresql", dataset_id = %dataset_id, item_count = items.len()))]
+pub(crate) async fn create_dataset_items_deduplicated(
+    pool: &Pool<Postgres>,
+    dataset_id: i32,
+    items: Vec<NewItem>,
+    dedup: &DedupOptions,
+) -> Result<DedupedItems> {
+    let (items, mut skipped) = dedup::dedup_within(items, &dedup.key);
+    let existing = find_duplicate_items(pool, dataset_id, &items, &dedup.key).await?;
+    let plan = dedup::plan(items, &existing, dedup.on_duplicate);
+    skipped.extend(plan.skipped);
+
+    let updated = update_dataset_items_batch(pool, dataset_id, plan.update).await?;
+    let (created, failed) = create_dataset_items_batch(pool, dataset_id, plan.insert).await?;
+
+    Ok(DedupedItems {
+        created,
+        updated,
+        skipped,
+        failed,
+    })
+}
+
+/// Existing item each of `items` duplicates, by the item's index
+async fn find_duplicate_items(
+    pool: &Pool<Postgres>,
+    dataset_id: i32,
+    items: &[NewItem],
+    key: &DedupKey,
+) -> Result<HashMap<usize, ExistingItem>> {
+    // Items without a key can't match; keep their positions aligned with NULL
+    let keys: Vec<Option<String>> = items.iter().map(|item| key.key_of(item)).collect();
+    let query = match key {
+        DedupKey::Title => sqlx::query(FIND_DUPLICATES_BY_TITLE_QUERY)
+            .bind(dataset_id)
+            .bind(&keys),
+        DedupKey::MetadataField(field) => sqlx::query(FIND_DUPLICATES_BY_METADATA_QUERY)
+            .bind(dataset_id)
+            .bind(&keys)
+            .bind(field),
+        DedupKey::ContentHash => {
+            let chunks = items
+                .iter()
+                .map(|item| serde_json::to_value(&item.1))
+                .collect::<Result<Vec<_>, _>>()?;
+            sqlx::query(FIND_DUPLICATES_BY_CONTENT_QUERY)
+                .bind(dataset_id)
+                .bind(chunks)
+        }
+    };
+
+    let rows = query.fetch_all(pool).await?;
+    let mut existing = HashMap::with_capacity(rows.len());
+    for row in rows {
+        // ORDINALITY is 1-based
+        let ord: i64 = row.try_get("ord")?;
+        existing.insert(
+            (ord - 1) as usize,
+            ExistingItem {
+                item_id: row.try_get("item_id")?,
+                chunks: row.try_get("chunks")?,
+                metadata: row.try_get("metadata")?,
+            },
+        );
+    }
+    Ok(existing)
+}
+
+/// Replace the chunks and metadata of existing items
+async fn update_dataset_items_batch(
+    pool: &Pool<Postgres>,
+    dataset_id: i32,
+    updates: Vec<(i32, NewItem)>,
+) -> Result<Vec<DatasetItem>> {
+    if updates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut item_ids = Vec::with_capacity(updates.len());
+    let mut chunks_array = Vec::with_capacity(updates.len());
+    let mut metadata_array = Vec::with_capacity(updates.len());
+    for (item_id, (_, chunks, metadata)) in updates {
+        item_ids.push(item_id);
+        chunks_array.push(serde_json::to_value(chunks)?);
+        metadata_array.push(metadata);
+    }
+
+    let items = sqlx::query_as::<_, DatasetItem>(UPDATE_DATASET_ITEMS_BATCH)
+        .bind(dataset_id)
+        .bind(&item_ids)
+        .bind(&chunks_array)
+        .bind(&metadata_array)
+        .fetch_all(pool)
+        .await?;
+    Ok(items)
+}
+
 #[tracing::instrument(name = "database.get_dataset_items", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", dataset_id = %dataset_id, page = %page, page_size = %page_size))]
 pub(crate) async fn get_dataset_items(
     pool: &Pool<Postgres>,
@@ -522,6 +678,28 @@ pub(crate) async fn get_dataset_items_for_scanner(
     Ok(items)
 }
 
+/// Fetch items at or below the scanner watermark `max_item_id` that were
+/// updated after `since`, resuming after the `(updated_at, item_id)` cursor
+/// `after` of the previous page.
+#[tracing::instrument(name = "database.get_dataset_items_updated_since", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", dataset_id = %dataset_id))]
+pub(crate) async fn get_dataset_items_updated_since(
+    pool: &Pool<Postgres>,
+    dataset_id: i32,
+    max_item_id: i32,
+    after: (DateTime<Utc>, i32),
+    limit: i64,
+) -> Result<Vec<DatasetItem>> {
+    let items = sqlx::query_as::<_, DatasetItem>(GET_DATASET_ITEMS_UPDATED_SINCE_QUERY)
+        .bind(dataset_id)
+        .bind(max_item_id)
+        .bind(after.0)
+        .bind(after.1)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(items)
+}
+
 /// Get the dataset's version (updated_at timestamp) for efficient stats refresh (#4)
 /// Returns None if the dataset doesn't exist
 pub(crate) async fn get_dataset_version(
@@ -663,3 +841,104 @@ pub(crate) async fn grab_public_dataset(
     tx.commit().await?;
     Ok(new_dataset)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::dedup::DuplicateAction;
+    use serde_json::json;
+
+    fn item(title: &str, content: &str, source_id: &str) -> NewItem {
+        (
+            title.to_string(),
+            vec![ChunkWithMetadata {
+                content: content.to_string(),
+                metadata: json!({}),
+            }],
+            json!({ "source_id": source_id }),
+        )
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_reimport_with_dedup_updates_instead_of_duplicating() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::migrate!("src/storage/postgres/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let dataset = create_dataset(
+            &pool,
+            "dedup test",
+            None,
+            "dedup-test",
+            "Dedup Test",
+            &[],
+            false,
+        )
+        .await
+        .unwrap();
+        let dataset_id = dataset.dataset_id;
+
+        for (key, renamed) in [("metadata.source_id", "-v2"), ("content_hash", "-v3")] {
+            let dedup = DedupOptions {
+                key: DedupKey::parse(key).unwrap(),
+                on_duplicate: DuplicateAction::Update,
+            };
+            let before = count_dataset_items(&pool, dataset_id).await.unwrap();
+            let first = create_dataset_items_deduplicated(
+                &pool,
+                dataset_id,
+                vec![item(&format!("a{renamed}"), "alpha", "1")],
+                &dedup,
+            )
+            .await
+            .unwrap();
+            assert!(first.failed.is_empty());
+
+            // The same item under another title is not added again
+            let again = create_dataset_items_deduplicated(
+                &pool,
+                dataset_id,
+                vec![item(&format!("a{renamed}-renamed"), "alpha", "1")],
+                &dedup,
+            )
+            .await
+            .unwrap();
+            assert!(again.created.is_empty() && again.updated.is_empty());
+            assert_eq!(again.skipped.len(), 1);
+            assert_eq!(
+                count_dataset_items(&pool, dataset_id).await.unwrap(),
+                before + first.created.len() as i64
+            );
+        }
+
+        // A changed item with the same source id is updated in place
+        let dedup = DedupOptions {
+            key: DedupKey::parse("metadata.source_id").unwrap(),
+            on_duplicate: DuplicateAction::Update,
+        };
+        let count = count_dataset_items(&pool, dataset_id).await.unwrap();
+        let changed = create_dataset_items_deduplicated(
+            &pool,
+            dataset_id,
+            vec![item("a-changed", "alpha, revised", "1")],
+            &dedup,
+        )
+        .await
+        .unwrap();
+        assert!(changed.created.is_empty());
+        assert_eq!(changed.updated.len(), 1);
+        assert_eq!(changed.updated[0].chunks[0].content, "alpha, revised");
+        assert_eq!(count_dataset_items(&pool, dataset_id).await.unwrap(), count);
+
+        delete_dataset(&pool, dataset_id, "dedup-test")
+            .await
+            .unwrap();
+    }
+}
@@ -3,11 +3,20 @@ use semantic_explorer_core::owner_info::OwnerInfo;
 use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::{FromRow, Pool, Postgres, Transaction};
 
+use crate::embedded_datasets::naming::{CollectionNameParams, render_collection_name};
 use crate::embedded_datasets::{
     EmbeddedDataset, EmbeddedDatasetProcessedBatch, EmbeddedDatasetStats,
     EmbeddedDatasetWithDetails,
 };
 
+/// Content hash of a dataset item as of when it was last embedded
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub(crate) struct ItemHash {
+    pub(crate) item_id: i32,
+    pub(crate) content_hash: String,
+    pub(crate) chunk_count: i32,
+}
+
 #[derive(FromRow)]
 pub struct EmbeddedDatasetInfo {
     pub collection_name: String,
@@ -65,6 +74,14 @@ const COUNT_EMBEDDED_DATASETS_BY_EMBEDDER_QUERY: &str = r#"
     WHERE embedder_id = $1 AND owner_id = $2
 "#;
 
+const GET_EMBEDDED_DATASETS_FOR_EMBEDDER_QUERY: &str = r#"
+    SELECT embedded_dataset_id, title, dataset_transform_id, source_dataset_id, embedder_id,
+           owner_id, owner_display_name, collection_name, dimensions, created_at, updated_at, last_processed_at, last_processed_item_id, source_dataset_version
+    FROM embedded_datasets
+    WHERE embedder_id = $1 AND owner_id = $2
+    ORDER BY created_at DESC
+"#;
+
 const GET_EMBEDDED_DATASET_QUERY: &str = r#"
     SELECT embedded_dataset_id, title, dataset_transform_id, source_dataset_id, embedder_id,
            owner_id, owner_display_name, collection_name, dimensions, created_at, updated_at, last_processed_at, last_processed_item_id, source_dataset_version
@@ -258,6 +275,45 @@ const UPDATE_EMBEDDED_DATASET_LAST_PROCESSED_AT_QUERY: &str = r#"
     WHERE embedded_dataset_id = $1
 "#;
 
+const GET_ITEM_HASHES_QUERY: &str = r#"
+    SELECT item_id, content_hash, chunk_count
+    FROM embedded_dataset_item_hashes
+    WHERE embedded_dataset_id = $1 AND item_id = ANY($2)
+"#;
+
+const UPSERT_ITEM_HASHES_QUERY: &str = r#"
+    INSERT INTO embedded_dataset_item_hashes (embedded_dataset_id, item_id, content_hash, chunk_count)
+    SELECT $1, unnest($2::int[]), unnest($3::text[]), unnest($4::int[])
+    ON CONFLICT (embedded_dataset_id, item_id)
+    DO UPDATE SET
+        content_hash = EXCLUDED.content_hash,
+        chunk_count = EXCLUDED.chunk_count,
+        updated_at = NOW()
+"#;
+
+/// Items an embedded dataset has hashes for that no longer exist
+const GET_DELETED_ITEM_IDS_QUERY: &str = r#"
+    SELECT h.item_id
+    FROM embedded_dataset_item_hashes h
+    LEFT JOIN dataset_items di ON di.item_id = h.item_id
+    WHERE h.embedded_dataset_id = $1 AND di.item_id IS NULL
+    ORDER BY h.item_id
+    LIMIT $2
+"#;
+
+const DELETE_ITEM_HASHES_QUERY: &str = r#"
+    DELETE FROM embedded_dataset_item_hashes
+    WHERE embedded_dataset_id = $1 AND item_id = ANY($2)
+"#;
+
+const GET_ITEMS_CHECKED_VERSION_QUERY: &str = r#"
+    SELECT items_checked_version FROM embedded_datasets WHERE embedded_dataset_id = $1
+"#;
+
+const UPDATE_ITEMS_CHECKED_VERSION_QUERY: &str = r#"
+    UPDATE embedded_datasets SET items_checked_version = $2 WHERE embedded_dataset_id = $1
+"#;
+
 /// Atomically acquire a scan lock on an embedded dataset.
 /// Returns true (1 row updated) if the lock was acquired, false if another scanner holds it.
 /// The lock expires after the specified timeout so stale locks don't block forever.
@@ -333,6 +389,23 @@ pub async fn count_by_embedder(
     Ok(result?.0)
 }
 
+/// Embedded datasets of the user that were embedded with a given embedder.
+#[tracing::instrument(name = "database.get_embedded_datasets_for_embedder", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT"))]
+pub async fn get_embedded_datasets_for_embedder(
+    pool: &Pool<Postgres>,
+    user: &crate::auth::AuthenticatedUser,
+    embedder_id: i32,
+) -> Result<Vec<EmbeddedDataset>> {
+    let embedded_datasets =
+        sqlx::query_as::<_, EmbeddedDataset>(GET_EMBEDDED_DATASETS_FOR_EMBEDDER_QUERY)
+            .bind(embedder_id)
+            .bind(user.as_owner())
+            .fetch_all(pool)
+            .await?;
+
+    Ok(embedded_datasets)
+}
+
 pub async fn get_embedded_dataset(
     pool: &Pool<Postgres>,
     owner: &str,
@@ -655,6 +728,114 @@ pub async fn update_source_dataset_version(
     Ok(())
 }
 
+/// Stored hashes of `item_ids` for an embedded dataset; items without one
+/// were never embedded, or were embedded before hashes were tracked
+pub(crate) async fn get_item_hashes(
+    pool: &Pool<Postgres>,
+    embedded_dataset_id: i32,
+    item_ids: &[i32],
+) -> Result<Vec<ItemHash>> {
+    let hashes = sqlx::query_as::<_, ItemHash>(GET_ITEM_HASHES_QUERY)
+        .bind(embedded_dataset_id)
+        .bind(item_ids)
+        .fetch_all(pool)
+        .await?;
+    Ok(hashes)
+}
+
+/// Record the hashes of items dispatched for embedding
+pub(crate) async fn upsert_item_hashes(
+    pool: &Pool<Postgres>,
+    embedded_dataset_id: i32,
+    hashes: &[ItemHash],
+) -> Result<()> {
+    if hashes.is_empty() {
+        return Ok(());
+    }
+    let item_ids: Vec<i32> = hashes.iter().map(|h| h.item_id).collect();
+    let content_hashes: Vec<&str> = hashes.iter().map(|h| h.content_hash.as_str()).collect();
+    let chunk_counts: Vec<i32> = hashes.iter().map(|h| h.chunk_count).collect();
+    sqlx::query(UPSERT_ITEM_HASHES_QUERY)
+        .bind(embedded_dataset_id)
+        .bind(&item_ids)
+        .bind(&content_hashes)
+        .bind(&chunk_counts)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Up to `limit` items that were embedded but have since been deleted
+pub(crate) async fn get_deleted_item_ids(
+    pool: &Pool<Postgres>,
+    embedded_dataset_id: i32,
+    limit: i64,
+) -> Result<Vec<i32>> {
+    let ids = sqlx::query_scalar::<_, i32>(GET_DELETED_ITEM_IDS_QUERY)
+        .bind(embedded_dataset_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(ids)
+}
+
+pub(crate) async fn delete_item_hashes(
+    pool: &Pool<Postgres>,
+    embedded_dataset_id: i32,
+    item_ids: &[i32],
+) -> Result<()> {
+    sqlx::query(DELETE_ITEM_HASHES_QUERY)
+        .bind(embedded_dataset_id)
+        .bind(item_ids)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Source dataset version the item hashes were last compared against
+pub(crate) async fn get_items_checked_version(
+    pool: &Pool<Postgres>,
+    embedded_dataset_id: i32,
+) -> Result<Option<DateTime<Utc>>> {
+    let version = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(GET_ITEMS_CHECKED_VERSION_QUERY)
+        .bind(embedded_dataset_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(version.flatten())
+}
+
+pub(crate) async fn update_items_checked_version(
+    pool: &Pool<Postgres>,
+    embedded_dataset_id: i32,
+    version: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(UPDATE_ITEMS_CHECKED_VERSION_QUERY)
+        .bind(embedded_dataset_id)
+        .bind(version)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Stand-in collection name for a row whose id isn't known yet. Collection
+/// names are unique, so concurrent creates can't share one.
+fn pending_collection_name() -> String {
+    format!("pending-{}", uuid::Uuid::new_v4())
+}
+
+/// Whether `error` is Postgres rejecting a duplicate collection name
+pub(crate) fn is_duplicate_collection_name(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<sqlx::Error>()
+            .and_then(|e| e.as_database_error())
+            .is_some_and(|e| {
+                e.is_unique_violation()
+                    && e.constraint() == Some("embedded_datasets_collection_name_key")
+            })
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn create_embedded_dataset_in_transaction(
     tx: &mut Transaction<'_, Postgres>,
@@ -663,9 +844,10 @@ pub async fn create_embedded_dataset_in_transaction(
     source_dataset_id: i32,
     embedder_id: i32,
     owner: &OwnerInfo,
-    collection_name: &str,
+    collection_name_template: &str,
     dimensions: Option<i32>,
 ) -> Result<EmbeddedDataset> {
+    // Create with placeholder collection name, rendered below once we have the ID
     let mut embedded_dataset = sqlx::query_as::<_, EmbeddedDataset>(CREATE_EMBEDDED_DATASET_QUERY)
         .bind(title)
         .bind(dataset_transform_id)
@@ -673,15 +855,22 @@ pub async fn create_embedded_dataset_in_transaction(
         .bind(embedder_id)
         .bind(&owner.owner_id)
         .bind(&owner.owner_display_name)
-        .bind(collection_name)
+        .bind(pending_collection_name())
         .bind(dimensions)
         .fetch_one(&mut **tx)
         .await?;
 
-    let actual_collection_name = EmbeddedDataset::generate_collection_name(
-        embedded_dataset.embedded_dataset_id,
-        &owner.owner_id,
-    );
+    let actual_collection_name = render_collection_name(
+        collection_name_template,
+        &CollectionNameParams {
+            embedded_dataset_id: embedded_dataset.embedded_dataset_id,
+            dataset_transform_id,
+            source_dataset_id,
+            embedder_id,
+            owner: &owner.owner_id,
+        },
+    )
+    .map_err(|e| anyhow::anyhow!("Invalid collection name: {}", e))?;
     embedded_dataset =
         sqlx::query_as::<_, EmbeddedDataset>(UPDATE_EMBEDDED_DATASET_COLLECTION_NAME_QUERY)
             .bind(embedded_dataset.embedded_dataset_id)
@@ -747,7 +936,7 @@ pub async fn create_standalone_embedded_dataset(
             .bind(title)
             .bind(&owner.owner_id)
             .bind(&owner.owner_display_name)
-            .bind("placeholder") // Will be updated below
+            .bind(pending_collection_name()) // Will be updated below
             .bind(dimensions)
             .fetch_one(&mut *tx)
             .await?;
@@ -767,3 +956,46 @@ pub async fn create_standalone_embedded_dataset(
     tx.commit().await?;
     Ok(embedded_dataset)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_collection_names_are_unique_across_owners() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::migrate!("src/storage/postgres/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let run = uuid::Uuid::new_v4();
+        let victim = OwnerInfo::new(format!("victim-{run}"), "Victim");
+        let attacker = OwnerInfo::new(format!("attacker-{run}"), "Attacker");
+
+        let victims = create_standalone_embedded_dataset(&pool, &victim, "victim", 4)
+            .await
+            .unwrap();
+        let attackers = create_standalone_embedded_dataset(&pool, &attacker, "attacker", 4)
+            .await
+            .unwrap();
+        assert_ne!(victims.collection_name, attackers.collection_name);
+
+        // However the second name came about, Postgres refuses the duplicate
+        let error: anyhow::Error =
+            sqlx::query_as::<_, EmbeddedDataset>(UPDATE_EMBEDDED_DATASET_COLLECTION_NAME_QUERY)
+                .bind(attackers.embedded_dataset_id)
+                .bind(&victims.collection_name)
+                .fetch_one(&pool)
+                .await
+                .unwrap_err()
+                .into();
+        assert!(is_duplicate_collection_name(&error));
+        assert!(!is_duplicate_collection_name(&anyhow::anyhow!("other")));
+    }
+}
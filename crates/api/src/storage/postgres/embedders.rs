@@ -20,6 +20,7 @@ struct EmbedderWithCount {
     pub config: serde_json::Value,
     pub batch_size: i32,
     pub dimensions: i32,
+    pub dimensions_verified: bool,
     pub max_input_tokens: i32,
     pub truncate_strategy: String,
     pub collection_name: Option<String>,
@@ -45,6 +46,7 @@ impl EmbedderWithCount {
                 config: r.config,
                 batch_size: r.batch_size,
                 dimensions: r.dimensions,
+                dimensions_verified: r.dimensions_verified,
                 max_input_tokens: r.max_input_tokens,
                 truncate_strategy: r.truncate_strategy,
                 collection_name: r.collection_name,
@@ -109,13 +111,13 @@ pub struct EmbedderConfig {
 }
 
 const GET_EMBEDDER_QUERY: &str = r#"
-    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
     FROM embedders
     WHERE embedder_id = $1 AND owner_id = $2
 "#;
 
 const GET_EMBEDDERS_QUERY: &str = r#"
-    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at,
+    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at,
         COUNT(*) OVER() AS total_count
     FROM embedders
     WHERE owner_id = $1
@@ -124,7 +126,7 @@ const GET_EMBEDDERS_QUERY: &str = r#"
 "#;
 
 const GET_EMBEDDERS_WITH_SEARCH_QUERY: &str = r#"
-    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at,
+    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at,
         COUNT(*) OVER() AS total_count
     FROM embedders
     WHERE owner_id = $1 AND name ILIKE $2
@@ -135,7 +137,7 @@ const GET_EMBEDDERS_WITH_SEARCH_QUERY: &str = r#"
 const CREATE_EMBEDDER_QUERY: &str = r#"
     INSERT INTO embedders (name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at)
     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, NOW(), NOW())
-    RETURNING embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+    RETURNING embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
 "#;
 
 const DELETE_EMBEDDER_QUERY: &str = r#"
@@ -143,7 +145,7 @@ const DELETE_EMBEDDER_QUERY: &str = r#"
 "#;
 
 const GET_PUBLIC_EMBEDDERS_QUERY: &str = r#"
-    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
     FROM embedders
     WHERE is_public = TRUE
     ORDER BY created_at DESC
@@ -151,7 +153,7 @@ const GET_PUBLIC_EMBEDDERS_QUERY: &str = r#"
 "#;
 
 const GET_RECENT_PUBLIC_EMBEDDERS_QUERY: &str = r#"
-    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+    SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
     FROM embedders
     WHERE is_public = TRUE
     ORDER BY updated_at DESC
@@ -159,11 +161,11 @@ const GET_RECENT_PUBLIC_EMBEDDERS_QUERY: &str = r#"
 "#;
 
 const GRAB_PUBLIC_EMBEDDER_QUERY: &str = r#"
-    INSERT INTO embedders (name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at)
-    SELECT name || '-grabbed', $1, $2, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, NULL, FALSE, NOW(), NOW()
+    INSERT INTO embedders (name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at)
+    SELECT name || '-grabbed', $1, $2, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, NULL, FALSE, NOW(), NOW()
     FROM embedders
     WHERE embedder_id = $3 AND is_public = TRUE
-    RETURNING embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+    RETURNING embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
 "#;
 
 const UPDATE_EMBEDDER_QUERY: &str = r#"
@@ -174,13 +176,26 @@ const UPDATE_EMBEDDER_QUERY: &str = r#"
         config = COALESCE($5, config),
         batch_size = COALESCE($6, batch_size),
         dimensions = COALESCE($7, dimensions),
+        dimensions_verified = dimensions_verified AND $3 IS NULL AND $5 IS NULL AND $7 IS NULL,
         max_input_tokens = COALESCE($8, max_input_tokens),
         truncate_strategy = COALESCE($9, truncate_strategy),
         collection_name = COALESCE($10, collection_name),
         is_public = COALESCE($11, is_public),
         updated_at = NOW()
     WHERE embedder_id = $1 AND owner_id = $12
-    RETURNING embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+      AND ($13::timestamptz IS NULL OR updated_at = $13)
+    RETURNING embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name, is_public, created_at, updated_at
+"#;
+
+const GET_EMBEDDER_VERSION_QUERY: &str = r#"
+    SELECT updated_at FROM embedders
+    WHERE embedder_id = $1 AND owner_id = $2
+"#;
+
+const SET_VERIFIED_DIMENSIONS_QUERY: &str = r#"
+    UPDATE embedders
+    SET dimensions = $2, dimensions_verified = TRUE
+    WHERE embedder_id = $1
 "#;
 
 const GET_EMBEDDER_BY_ID_QUERY: &str = r#"
@@ -191,7 +206,7 @@ const GET_EMBEDDER_BY_ID_QUERY: &str = r#"
 
 const GET_EMBEDDERS_BATCH: &str = r#"
         SELECT embedder_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size,
-               dimensions, max_input_tokens, truncate_strategy, collection_name,
+               dimensions, dimensions_verified, max_input_tokens, truncate_strategy, collection_name,
                is_public, created_at, updated_at
         FROM embedders
         WHERE embedder_id = ANY($1) AND owner_id = $2
@@ -367,8 +382,9 @@ pub(crate) async fn update_embedder(
     user: &AuthenticatedUser,
     embedder_id: i32,
     update_embedder: &UpdateEmbedder,
+    expected_updated_at: Option<DateTime<Utc>>,
     encryption: &EncryptionService,
-) -> Result<Embedder> {
+) -> Result<Option<Embedder>> {
     // Encrypt API key if provided
     let encrypted_api_key = encrypt_api_key(encryption, &update_embedder.api_key)?;
 
@@ -385,12 +401,47 @@ pub(crate) async fn update_embedder(
         .bind(&update_embedder.collection_name)
         .bind(update_embedder.is_public)
         .bind(user.as_owner())
-        .fetch_one(pool)
+        .bind(expected_updated_at)
+        .fetch_optional(pool)
         .await;
 
-    let embedder = result?;
+    // No row: the embedder doesn't exist or is no longer at the expected version
+    let Some(embedder) = result? else {
+        return Ok(None);
+    };
     // Decrypt api_key in response
-    decrypt_embedder_api_key(encryption, embedder)
+    decrypt_embedder_api_key(encryption, embedder).map(Some)
+}
+
+/// Store the output dimensions measured for an embedder. The embedder's
+/// version is left alone, as nothing the user set has changed.
+#[tracing::instrument(name = "database.set_embedder_verified_dimensions", skip(pool), fields(database.system = "postgresql", database.operation = "UPDATE", embedder_id = %embedder_id))]
+pub(crate) async fn set_verified_dimensions(
+    pool: &Pool<Postgres>,
+    embedder_id: i32,
+    dimensions: i32,
+) -> Result<()> {
+    sqlx::query(SET_VERIFIED_DIMENSIONS_QUERY)
+        .bind(embedder_id)
+        .bind(dimensions)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Current version (`updated_at`) of an embedder, if the user owns it
+#[tracing::instrument(name = "database.get_embedder_version", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", embedder_id = %embedder_id))]
+pub(crate) async fn get_embedder_version(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    embedder_id: i32,
+) -> Result<Option<DateTime<Utc>>> {
+    let version = sqlx::query_scalar::<_, DateTime<Utc>>(GET_EMBEDDER_VERSION_QUERY)
+        .bind(embedder_id)
+        .bind(user.as_owner())
+        .fetch_optional(pool)
+        .await?;
+    Ok(version)
 }
 
 #[tracing::instrument(name = "database.get_public_embedders", skip(pool, encryption), fields(database.system = "postgresql", database.operation = "SELECT"))]
@@ -463,3 +514,114 @@ pub async fn get_embedder_config(
     }
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_update_applies_only_at_the_expected_version() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::migrate!("src/storage/postgres/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let encryption = EncryptionService::from_hex_key(&"11".repeat(32)).unwrap();
+        let user = AuthenticatedUser("precondition-test".to_string());
+        let created = create_embedder(
+            &pool,
+            &user,
+            &CreateEmbedder {
+                name: "original".to_string(),
+                provider: "openai".to_string(),
+                base_url: "http://localhost".to_string(),
+                api_key: None,
+                config: serde_json::json!({}),
+                batch_size: 100,
+                dimensions: 1536,
+                max_input_tokens: 8191,
+                truncate_strategy: "NONE".to_string(),
+                collection_name: None,
+                is_public: false,
+            },
+            &encryption,
+        )
+        .await
+        .unwrap();
+        let rename = |name: &str| UpdateEmbedder {
+            name: Some(name.to_string()),
+            base_url: None,
+            api_key: None,
+            config: None,
+            batch_size: None,
+            dimensions: None,
+            max_input_tokens: None,
+            truncate_strategy: None,
+            collection_name: None,
+            is_public: None,
+        };
+        let id = created.embedder_id;
+
+        // Two clients read the same version; the first write wins
+        let first = update_embedder(
+            &pool,
+            &user,
+            id,
+            &rename("first"),
+            Some(created.updated_at),
+            &encryption,
+        )
+        .await
+        .unwrap()
+        .expect("update at the current version applies");
+        assert_eq!(first.name, "first");
+        assert!(first.updated_at > created.updated_at);
+
+        let stale = update_embedder(
+            &pool,
+            &user,
+            id,
+            &rename("second"),
+            Some(created.updated_at),
+            &encryption,
+        )
+        .await
+        .unwrap();
+        assert!(stale.is_none());
+        assert_eq!(
+            get_embedder_version(&pool, &user, id).await.unwrap(),
+            Some(first.updated_at)
+        );
+
+        // Without an expected version the update is unconditional
+        let unconditional = update_embedder(&pool, &user, id, &rename("third"), None, &encryption)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(unconditional.name, "third");
+
+        // Another owner can't update it at any version
+        let other = AuthenticatedUser("someone-else".to_string());
+        assert!(
+            update_embedder(
+                &pool,
+                &other,
+                id,
+                &rename("stolen"),
+                Some(unconditional.updated_at),
+                &encryption,
+            )
+            .await
+            .unwrap()
+            .is_none()
+        );
+
+        delete_embedder(&pool, &user, id).await.unwrap();
+    }
+}
@@ -113,9 +113,15 @@ const UPDATE_LLM_QUERY: &str = r#"
         is_public = COALESCE($6, is_public),
         updated_at = NOW()
     WHERE llm_id = $1 AND owner_id = $7
+      AND ($8::timestamptz IS NULL OR updated_at = $8)
     RETURNING llm_id, name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, is_public, created_at, updated_at
 "#;
 
+const GET_LLM_VERSION_QUERY: &str = r#"
+    SELECT updated_at FROM llms
+    WHERE llm_id = $1 AND owner_id = $2
+"#;
+
 const GRAB_PUBLIC_LLM_QUERY: &str = r#"
     INSERT INTO llms (name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, model, config, is_public, created_at, updated_at)
     SELECT name || '-grabbed', $1, $2, provider, base_url, api_key_encrypted, model, config, FALSE, NOW(), NOW()
@@ -297,8 +303,9 @@ pub(crate) async fn update_llm(
     user: &AuthenticatedUser,
     llm_id: i32,
     update_llm: &UpdateLargeLanguageModel,
+    expected_updated_at: Option<DateTime<Utc>>,
     encryption: &EncryptionService,
-) -> Result<LargeLanguageModel> {
+) -> Result<Option<LargeLanguageModel>> {
     // Encrypt API key if provided
     let encrypted_api_key = encrypt_api_key(encryption, &update_llm.api_key)?;
 
@@ -310,12 +317,31 @@ pub(crate) async fn update_llm(
         .bind(&update_llm.config)
         .bind(update_llm.is_public)
         .bind(user.as_owner())
-        .fetch_one(pool)
+        .bind(expected_updated_at)
+        .fetch_optional(pool)
         .await;
 
-    let llm = result?;
+    // No row: the LLM doesn't exist or is no longer at the expected version
+    let Some(llm) = result? else {
+        return Ok(None);
+    };
     // Decrypt api_key in response
-    decrypt_llm_api_key(encryption, llm)
+    decrypt_llm_api_key(encryption, llm).map(Some)
+}
+
+/// Current version (`updated_at`) of an LLM, if the user owns it
+#[tracing::instrument(name = "database.get_llm_version", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", llm_id = %llm_id))]
+pub(crate) async fn get_llm_version(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    llm_id: i32,
+) -> Result<Option<DateTime<Utc>>> {
+    let version = sqlx::query_scalar::<_, DateTime<Utc>>(GET_LLM_VERSION_QUERY)
+        .bind(llm_id)
+        .bind(user.as_owner())
+        .fetch_optional(pool)
+        .await?;
+    Ok(version)
 }
 
 #[tracing::instrument(name = "database.grab_public_llm", skip(pool, encryption), fields(database.system = "postgresql", database.operation = "INSERT", owner_id = %user.as_owner(), llm_id = %llm_id))]
@@ -371,3 +397,103 @@ fn decrypt_llms_api_keys(
         .map(|llm| decrypt_llm_api_key(encryption, llm))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_update_applies_only_at_the_expected_version() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::migrate!("src/storage/postgres/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let encryption = EncryptionService::from_hex_key(&"11".repeat(32)).unwrap();
+        let user = AuthenticatedUser("precondition-test".to_string());
+        let created = create_llm(
+            &pool,
+            &user,
+            &CreateLLM {
+                name: "original".to_string(),
+                provider: "openai".to_string(),
+                model: "gpt-4o-mini".to_string(),
+                base_url: "http://localhost".to_string(),
+                api_key: None,
+                config: serde_json::json!({}),
+                is_public: false,
+            },
+            &encryption,
+        )
+        .await
+        .unwrap();
+        let rename = |name: &str| UpdateLargeLanguageModel {
+            name: Some(name.to_string()),
+            base_url: None,
+            api_key: None,
+            config: None,
+            is_public: None,
+        };
+        let id = created.llm_id;
+
+        // Two clients read the same version; the first write wins
+        let first = update_llm(
+            &pool,
+            &user,
+            id,
+            &rename("first"),
+            Some(created.updated_at),
+            &encryption,
+        )
+        .await
+        .unwrap()
+        .expect("update at the current version applies");
+        assert_eq!(first.name, "first");
+
+        let stale = update_llm(
+            &pool,
+            &user,
+            id,
+            &rename("second"),
+            Some(created.updated_at),
+            &encryption,
+        )
+        .await
+        .unwrap();
+        assert!(stale.is_none());
+        assert_eq!(
+            get_llm_version(&pool, &user, id).await.unwrap(),
+            Some(first.updated_at)
+        );
+
+        // Retrying at the version just read applies
+        let retried = update_llm(
+            &pool,
+            &user,
+            id,
+            &rename("second"),
+            Some(first.updated_at),
+            &encryption,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(retried.name, "second");
+
+        // Without an expected version the update is unconditional
+        assert!(
+            update_llm(&pool, &user, id, &rename("third"), None, &encryption)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        delete_llm(&pool, &user, id).await.unwrap();
+    }
+}
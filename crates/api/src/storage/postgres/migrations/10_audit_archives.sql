@@ -0,0 +1,15 @@
+-- Audit events older than AUDIT_RETENTION_DAYS are moved to S3 as gzipped
+-- NDJSON, one object per batch. Each archive's digest covers the previous
+-- archive's digest and its own content, so the archives form a hash chain:
+-- a deleted, reordered or edited archive breaks every digest after it.
+CREATE TABLE audit_archives (
+    archive_id       BIGSERIAL   PRIMARY KEY,
+    object_key       TEXT        NOT NULL UNIQUE,
+    first_event_id   BIGINT      NOT NULL,
+    last_event_id    BIGINT      NOT NULL,
+    event_count      INTEGER     NOT NULL CHECK (event_count > 0),
+    prev_digest      TEXT        NOT NULL,
+    digest           TEXT        NOT NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (first_event_id <= last_event_id)
+);
@@ -0,0 +1,54 @@
+-- Incremental dataset transforms: remember the content hash of every dataset
+-- item an embedded dataset was built from, so a re-scan re-embeds only the
+-- items that changed and removes the points of items that were deleted.
+CREATE TABLE IF NOT EXISTS embedded_dataset_item_hashes (
+    embedded_dataset_id INTEGER     NOT NULL REFERENCES embedded_datasets(embedded_dataset_id) ON DELETE CASCADE,
+    -- No FK: rows outlive deleted items until their points are removed
+    item_id             INTEGER     NOT NULL,
+    content_hash        TEXT        NOT NULL,
+    chunk_count         INTEGER     NOT NULL,
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (embedded_dataset_id, item_id)
+);
+
+-- Source dataset version the item hashes were last compared against
+ALTER TABLE embedded_datasets ADD COLUMN IF NOT EXISTS items_checked_version TIMESTAMPTZ;
+
+-- Metadata is embedded with each chunk, so metadata-only edits must bump the
+-- dataset version as well for the scanner to notice them.
+CREATE OR REPLACE FUNCTION update_dataset_stats() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE datasets SET
+            item_count = item_count + 1,
+            total_chunks = total_chunks + jsonb_array_length(NEW.chunks),
+            updated_at = NOW()
+        WHERE dataset_id = NEW.dataset_id;
+        RETURN NEW;
+    ELSIF TG_OP = 'DELETE' THEN
+        UPDATE datasets SET
+            item_count = GREATEST(item_count - 1, 0),
+            total_chunks = GREATEST(total_chunks - jsonb_array_length(OLD.chunks), 0),
+            updated_at = NOW()
+        WHERE dataset_id = OLD.dataset_id;
+        RETURN OLD;
+    ELSIF TG_OP = 'UPDATE' THEN
+        IF OLD.chunks IS DISTINCT FROM NEW.chunks THEN
+            UPDATE datasets SET
+                total_chunks = total_chunks - jsonb_array_length(OLD.chunks) + jsonb_array_length(NEW.chunks),
+                updated_at = NOW()
+            WHERE dataset_id = NEW.dataset_id;
+        ELSIF OLD.metadata IS DISTINCT FROM NEW.metadata THEN
+            UPDATE datasets SET updated_at = NOW() WHERE dataset_id = NEW.dataset_id;
+        END IF;
+        RETURN NEW;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_update_dataset_stats ON dataset_items;
+CREATE TRIGGER trg_update_dataset_stats
+AFTER INSERT OR DELETE OR UPDATE OF chunks, metadata ON dataset_items
+FOR EACH ROW
+EXECUTE FUNCTION update_dataset_stats();
@@ -0,0 +1,14 @@
+-- Files a client was given a presigned upload URL for. The upload goes
+-- straight to the object store, so the scanner checks these keys and
+-- dispatches transform jobs once the object shows up.
+CREATE TABLE IF NOT EXISTS collection_pending_uploads (
+    collection_id INTEGER     NOT NULL REFERENCES collections(collection_id) ON DELETE CASCADE,
+    file_key      TEXT        NOT NULL,
+    owner_id      TEXT        NOT NULL,
+    expires_at    TIMESTAMPTZ NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (collection_id, file_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_collection_pending_uploads_expires_at
+    ON collection_pending_uploads (expires_at);
@@ -0,0 +1,14 @@
+-- Transforms a user triggered whose scan has not recorded any work yet.
+-- Scans run in the background, so without these a user could trigger many
+-- transforms before any of them shows up as running. A claim stops counting
+-- once the transform records work after it, or after the claim TTL.
+CREATE TABLE IF NOT EXISTS transform_trigger_claims (
+    transform_type TEXT        NOT NULL CHECK (transform_type IN ('collection', 'dataset', 'visualization')),
+    transform_id   INTEGER     NOT NULL,
+    owner_id       TEXT        NOT NULL,
+    claimed_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (transform_type, transform_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transform_trigger_claims_owner_id
+    ON transform_trigger_claims (owner_id);
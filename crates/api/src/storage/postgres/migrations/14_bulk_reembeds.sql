@@ -0,0 +1,21 @@
+-- Bulk re-embeds: one dataset transform per dataset, created disabled and
+-- started in order as the owner's concurrency slots free up.
+CREATE TABLE IF NOT EXISTS bulk_reembeds (
+    bulk_reembed_id SERIAL      PRIMARY KEY,
+    owner_id        TEXT        NOT NULL,
+    embedder_id     INTEGER     NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS bulk_reembed_transforms (
+    bulk_reembed_id      INTEGER     NOT NULL REFERENCES bulk_reembeds (bulk_reembed_id) ON DELETE CASCADE,
+    dataset_id           INTEGER     NOT NULL,
+    dataset_transform_id INTEGER     NOT NULL REFERENCES dataset_transforms (dataset_transform_id) ON DELETE CASCADE,
+    position             INTEGER     NOT NULL,
+    started_at           TIMESTAMPTZ,
+    PRIMARY KEY (bulk_reembed_id, dataset_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bulk_reembed_transforms_queued
+    ON bulk_reembed_transforms (bulk_reembed_id, position)
+    WHERE started_at IS NULL;
@@ -0,0 +1,3 @@
+-- The 2D layout and cluster of every point, stored as JSON next to the
+-- rendered HTML so clients can draw their own scatter.
+ALTER TABLE visualizations ADD COLUMN IF NOT EXISTS points_s3_key TEXT;
@@ -0,0 +1,5 @@
+-- Whether an embedder's `dimensions` were measured from its output rather
+-- than entered by hand (they default to 1536). Transforms are checked
+-- against the target collection with measured dimensions only; editing the
+-- embedder's endpoint, config or dimensions clears the flag.
+ALTER TABLE embedders ADD COLUMN IF NOT EXISTS dimensions_verified BOOLEAN NOT NULL DEFAULT FALSE;
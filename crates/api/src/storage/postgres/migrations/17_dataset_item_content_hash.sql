@@ -0,0 +1,8 @@
+-- Hash of an item's chunk contents, so uploads can skip or update items
+-- whose content is already in the dataset under another title
+-- (`dedup_key=content_hash`). Chunk metadata is left out of the hash.
+ALTER TABLE dataset_items ADD COLUMN IF NOT EXISTS content_hash TEXT
+    GENERATED ALWAYS AS (md5(jsonb_path_query_array(chunks, '$[*].content')::text)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_dataset_items_dataset_content_hash
+    ON dataset_items(dataset_id, content_hash);
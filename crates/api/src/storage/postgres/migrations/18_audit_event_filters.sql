@@ -0,0 +1,10 @@
+-- Id of the HTTP request that produced an event (the `X-Request-Id` header),
+-- so every event of one request can be pulled up together
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS request_id TEXT;
+
+-- Audit log queries filtered by resource type, newest first
+CREATE INDEX IF NOT EXISTS idx_audit_events_resource_type_timestamp
+    ON audit_events(resource_type, timestamp DESC);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_request_id
+    ON audit_events(request_id) WHERE request_id IS NOT NULL;
@@ -0,0 +1,8 @@
+-- Id assigned when an event is created. An event can reach the table both
+-- through the audit stream and through the direct-write fallback, or be
+-- redelivered by the stream; the unique index makes the insert's
+-- `ON CONFLICT DO NOTHING` keep only the first copy.
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS event_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audit_events_event_id
+    ON audit_events(event_id) WHERE event_id IS NOT NULL;
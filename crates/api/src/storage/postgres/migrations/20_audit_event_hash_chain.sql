@@ -0,0 +1,9 @@
+-- Hash chain over audit events: hash = sha256(prev_hash || canonical event
+-- JSON), so an edited or deleted row breaks the chain at that point. Rows
+-- written before this migration have no hash and precede the chain.
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS prev_hash TEXT;
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS hash TEXT;
+
+-- Archival deletes the oldest events; the chain resumes from the hash of the
+-- last event each archive holds
+ALTER TABLE audit_archives ADD COLUMN IF NOT EXISTS last_event_hash TEXT;
@@ -0,0 +1,8 @@
+-- Each embedded dataset owns its Qdrant collection. Two rows with the same
+-- collection_name would write into and search each other's vectors, so the
+-- name is unique. Replaces the plain lookup index from 2_indexes.sql.
+
+DROP INDEX IF EXISTS idx_embedded_datasets_collection;
+
+ALTER TABLE embedded_datasets
+    ADD CONSTRAINT embedded_datasets_collection_name_key UNIQUE (collection_name);
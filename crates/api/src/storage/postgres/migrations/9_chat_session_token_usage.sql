@@ -0,0 +1,7 @@
+-- Per-session token accounting. prompt_tokens/completion_tokens accumulate
+-- across every LLM call made for the session; token_budget (NULL = unlimited)
+-- caps their sum, after which new messages are rejected with 429.
+ALTER TABLE chat_sessions
+    ADD COLUMN IF NOT EXISTS prompt_tokens BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS completion_tokens BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS token_budget BIGINT CHECK (token_budget IS NULL OR token_budget > 0);
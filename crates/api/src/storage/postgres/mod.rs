@@ -1,4 +1,5 @@
 pub(crate) mod audit;
+pub(crate) mod bulk_reembeds;
 pub(crate) mod chat;
 pub(crate) mod collection_transforms;
 pub(crate) mod collections;
@@ -10,14 +11,17 @@ pub(crate) mod datasets;
 pub(crate) mod embedded_datasets;
 pub(crate) mod embedders;
 pub(crate) mod llms;
+pub(crate) mod provider_configs;
+pub(crate) mod transform_slots;
 pub(crate) mod visualization_transforms;
 
 use actix_web::rt::{spawn, time::interval};
 use anyhow::Result;
+use futures_util::future;
 use semantic_explorer_core::config::DatabaseConfig;
 use semantic_explorer_core::observability::update_database_pool_stats;
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Default batch size for internal batched fetches.
 pub(crate) const INTERNAL_BATCH_SIZE: i64 = 1000;
@@ -73,6 +77,16 @@ pub(crate) async fn initialize_pool(config: &DatabaseConfig) -> Result<Pool<Post
         .run(&pool)
         .await?;
 
+    if config.warmup {
+        let start = Instant::now();
+        let warmed = warm_up_pool(&pool, config.min_connections).await?;
+        tracing::info!(
+            connections = warmed,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "Warmed up database pool"
+        );
+    }
+
     let pool_clone = pool.clone();
     let max_connections = config.max_connections as u64;
     spawn(async move {
@@ -87,3 +101,40 @@ pub(crate) async fn initialize_pool(config: &DatabaseConfig) -> Result<Pool<Post
 
     Ok(pool)
 }
+
+/// Establish `connections` pool connections at once and run a probe query on
+/// each. The connections are returned to the pool idle, so the first requests
+/// find them open. Returns the number of connections probed.
+pub(crate) async fn warm_up_pool(pool: &Pool<Postgres>, connections: u32) -> Result<u32> {
+    let connections = connections.min(pool.options().get_max_connections());
+    // Hold every connection until all are acquired, so each acquire opens a
+    // new one rather than reusing the previous
+    let mut held = future::try_join_all((0..connections).map(|_| pool.acquire())).await?;
+    for conn in &mut held {
+        sqlx::query("SELECT 1").execute(&mut **conn).await?;
+    }
+    Ok(held.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn test_warmup_establishes_min_connections() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .min_connections(3)
+            .connect_lazy(&url)
+            .unwrap();
+        assert_eq!(pool.size(), 0);
+
+        let warmed = warm_up_pool(&pool, 3).await.unwrap();
+
+        assert_eq!(warmed, 3);
+        assert!(pool.size() >= 3);
+        assert!(pool.num_idle() >= 3);
+    }
+}
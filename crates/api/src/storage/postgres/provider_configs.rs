@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use sqlx::{Pool, Postgres, Transaction};
+
+use crate::auth::AuthenticatedUser;
+use crate::provider_configs::models::{
+    ConflictMode, EmbedderConfigEntry, ImportConfigsResponse, ImportItemResult, ImportOutcome,
+    LlmConfigEntry,
+};
+use crate::provider_configs::{ImportAction, plan_import};
+
+const EXPORT_EMBEDDERS_QUERY: &str = r#"
+    SELECT name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, is_public
+    FROM embedders
+    WHERE owner_id = $1
+    ORDER BY name, embedder_id
+"#;
+
+const EXPORT_LLMS_QUERY: &str = r#"
+    SELECT name, provider, model, base_url, api_key_encrypted, config, is_public
+    FROM llms
+    WHERE owner_id = $1
+    ORDER BY name, llm_id
+"#;
+
+// Names are not unique, so conflicts resolve to the most recently updated config.
+const GET_EMBEDDER_IDS_BY_NAME_QUERY: &str = r#"
+    SELECT DISTINCT ON (name) name, embedder_id
+    FROM embedders
+    WHERE owner_id = $1 AND name = ANY($2)
+    ORDER BY name, updated_at DESC
+"#;
+
+const GET_LLM_IDS_BY_NAME_QUERY: &str = r#"
+    SELECT DISTINCT ON (name) name, llm_id
+    FROM llms
+    WHERE owner_id = $1 AND name = ANY($2)
+    ORDER BY name, updated_at DESC
+"#;
+
+const IMPORT_EMBEDDER_QUERY: &str = r#"
+    INSERT INTO embedders (name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, config, batch_size, dimensions, max_input_tokens, truncate_strategy, is_public, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW(), NOW())
+    RETURNING embedder_id
+"#;
+
+const OVERWRITE_EMBEDDER_QUERY: &str = r#"
+    UPDATE embedders
+    SET provider = $3,
+        base_url = $4,
+        api_key_encrypted = COALESCE($5, api_key_encrypted),
+        config = $6,
+        batch_size = $7,
+        dimensions = $8,
+        dimensions_verified = FALSE,
+        max_input_tokens = $9,
+        truncate_strategy = $10,
+        is_public = $11,
+        updated_at = NOW()
+    WHERE embedder_id = $1 AND owner_id = $2
+"#;
+
+const IMPORT_LLM_QUERY: &str = r#"
+    INSERT INTO llms (name, owner_id, owner_display_name, provider, base_url, api_key_encrypted, model, config, is_public, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW(), NOW())
+    RETURNING llm_id
+"#;
+
+const OVERWRITE_LLM_QUERY: &str = r#"
+    UPDATE llms
+    SET provider = $3,
+        base_url = $4,
+        api_key_encrypted = COALESCE($5, api_key_encrypted),
+        model = $6,
+        config = $7,
+        is_public = $8,
+        updated_at = NOW()
+    WHERE llm_id = $1 AND owner_id = $2
+"#;
+
+/// Fetch all of the user's embedders for export, with API keys still encrypted.
+#[tracing::instrument(name = "database.export_embedders", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", username = %user.as_str()))]
+pub(crate) async fn export_embedders(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+) -> Result<Vec<EmbedderConfigEntry>> {
+    let embedders = sqlx::query_as::<_, EmbedderConfigEntry>(EXPORT_EMBEDDERS_QUERY)
+        .bind(user.as_owner())
+        .fetch_all(pool)
+        .await?;
+    Ok(embedders)
+}
+
+/// Fetch all of the user's LLMs for export, with API keys still encrypted.
+#[tracing::instrument(name = "database.export_llms", skip(pool), fields(database.system = "postgresql", database.operation = "SELECT", username = %user.as_str()))]
+pub(crate) async fn export_llms(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+) -> Result<Vec<LlmConfigEntry>> {
+    let llms = sqlx::query_as::<_, LlmConfigEntry>(EXPORT_LLMS_QUERY)
+        .bind(user.as_owner())
+        .fetch_all(pool)
+        .await?;
+    Ok(llms)
+}
+
+async fn ids_by_name(
+    tx: &mut Transaction<'_, Postgres>,
+    query: &str,
+    owner_id: &str,
+    names: Vec<String>,
+) -> Result<HashMap<String, i32>> {
+    let rows: Vec<(String, i32)> = sqlx::query_as(query)
+        .bind(owner_id)
+        .bind(names)
+        .fetch_all(&mut **tx)
+        .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Import embedder and LLM configs in a single transaction.
+///
+/// API keys must already be sealed into `api_key_encrypted`. Newly created
+/// configs without a key get `default_api_key_encrypted`, matching the
+/// create endpoints; overwritten configs keep their existing key.
+#[tracing::instrument(name = "database.import_provider_configs", skip_all, fields(database.system = "postgresql", database.operation = "INSERT", owner_id = %user.as_owner()))]
+pub(crate) async fn import_configs(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    embedders: &[EmbedderConfigEntry],
+    llms: &[LlmConfigEntry],
+    mode: ConflictMode,
+    default_api_key_encrypted: &str,
+) -> Result<ImportConfigsResponse> {
+    let owner_id = user.as_owner();
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    let mut response = ImportConfigsResponse::default();
+
+    let existing = ids_by_name(
+        &mut tx,
+        GET_EMBEDDER_IDS_BY_NAME_QUERY,
+        &owner_id,
+        embedders.iter().map(|e| e.name.clone()).collect(),
+    )
+    .await
+    .context("Failed to look up existing embedders")?;
+
+    for e in embedders {
+        let (id, outcome) = match plan_import(&e.name, &existing, mode) {
+            ImportAction::Skip(id) => (id, ImportOutcome::Skipped),
+            ImportAction::Overwrite(id) => {
+                sqlx::query(OVERWRITE_EMBEDDER_QUERY)
+                    .bind(id)
+                    .bind(&owner_id)
+                    .bind(&e.provider)
+                    .bind(&e.base_url)
+                    .bind(&e.api_key_encrypted)
+                    .bind(&e.config)
+                    .bind(e.batch_size)
+                    .bind(e.dimensions)
+                    .bind(e.max_input_tokens)
+                    .bind(&e.truncate_strategy)
+                    .bind(e.is_public)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to overwrite embedder '{}'", e.name))?;
+                (id, ImportOutcome::Overwritten)
+            }
+            ImportAction::Create => {
+                let id: i32 = sqlx::query_scalar(IMPORT_EMBEDDER_QUERY)
+                    .bind(&e.name)
+                    .bind(&owner_id)
+                    .bind(&**user)
+                    .bind(&e.provider)
+                    .bind(&e.base_url)
+                    .bind(
+                        e.api_key_encrypted
+                            .as_deref()
+                            .unwrap_or(default_api_key_encrypted),
+                    )
+                    .bind(&e.config)
+                    .bind(e.batch_size)
+                    .bind(e.dimensions)
+                    .bind(e.max_input_tokens)
+                    .bind(&e.truncate_strategy)
+                    .bind(e.is_public)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to create embedder '{}'", e.name))?;
+                (id, ImportOutcome::Created)
+            }
+        };
+        response.embedders.push(ImportItemResult {
+            name: e.name.clone(),
+            id,
+            outcome,
+        });
+    }
+
+    let existing = ids_by_name(
+        &mut tx,
+        GET_LLM_IDS_BY_NAME_QUERY,
+        &owner_id,
+        llms.iter().map(|l| l.name.clone()).collect(),
+    )
+    .await
+    .context("Failed to look up existing LLMs")?;
+
+    for l in llms {
+        let (id, outcome) = match plan_import(&l.name, &existing, mode) {
+            ImportAction::Skip(id) => (id, ImportOutcome::Skipped),
+            ImportAction::Overwrite(id) => {
+                sqlx::query(OVERWRITE_LLM_QUERY)
+                    .bind(id)
+                    .bind(&owner_id)
+                    .bind(&l.provider)
+                    .bind(&l.base_url)
+                    .bind(&l.api_key_encrypted)
+                    .bind(&l.model)
+                    .bind(&l.config)
+                    .bind(l.is_public)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to overwrite LLM '{}'", l.name))?;
+                (id, ImportOutcome::Overwritten)
+            }
+            ImportAction::Create => {
+                let id: i32 = sqlx::query_scalar(IMPORT_LLM_QUERY)
+                    .bind(&l.name)
+                    .bind(&owner_id)
+                    .bind(&**user)
+                    .bind(&l.provider)
+                    .bind(&l.base_url)
+                    .bind(
+                        l.api_key_encrypted
+                            .as_deref()
+                            .unwrap_or(default_api_key_encrypted),
+                    )
+                    .bind(&l.model)
+                    .bind(&l.config)
+                    .bind(l.is_public)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to create LLM '{}'", l.name))?;
+                (id, ImportOutcome::Created)
+            }
+        };
+        response.llms.push(ImportItemResult {
+            name: l.name.clone(),
+            id,
+            outcome,
+        });
+    }
+
+    tx.commit().await.context("Failed to commit import")?;
+    response.tally();
+    Ok(response)
+}
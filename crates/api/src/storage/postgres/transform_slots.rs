@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use sqlx::{FromRow, Pool, Postgres};
+use std::time::Duration;
+
+use crate::transforms::concurrency::{SlotClaim, TransformKind, admit};
+
+/// Transforms of an owner that hold a concurrency slot: those with work in
+/// flight, plus recent trigger claims whose scan has not recorded any work
+/// since the claim.
+const GET_ACTIVE_TRANSFORMS_QUERY: &str = r#"
+    SELECT 'visualization' AS transform_type, vt.visualization_transform_id AS transform_id
+    FROM visualization_transforms vt
+    JOIN visualizations v ON v.visualization_transform_id = vt.visualization_transform_id
+    WHERE vt.owner_id = $1 AND v.status IN ('pending', 'processing')
+    UNION
+    SELECT 'dataset', dt.dataset_transform_id
+    FROM dataset_transforms dt
+    WHERE dt.owner_id = $1
+      AND (
+        EXISTS (
+            SELECT 1 FROM dataset_transform_batches b
+            WHERE b.dataset_transform_id = dt.dataset_transform_id
+              AND b.status IN ('pending', 'processing')
+        )
+        OR EXISTS (
+            SELECT 1 FROM pending_batches pb
+            WHERE pb.dataset_transform_id = dt.dataset_transform_id AND pb.status = 'pending'
+        )
+        OR EXISTS (
+            SELECT 1 FROM embedded_datasets ed
+            JOIN transform_processed_files tpf
+              ON tpf.transform_type = 'dataset' AND tpf.transform_id = ed.embedded_dataset_id
+            WHERE ed.dataset_transform_id = dt.dataset_transform_id
+              AND tpf.process_status = 'processing'
+        )
+      )
+    UNION
+    SELECT 'collection', ct.collection_transform_id
+    FROM collection_transforms ct
+    WHERE ct.owner_id = $1
+      AND (
+        EXISTS (
+            SELECT 1 FROM transform_processed_files tpf
+            WHERE tpf.transform_type = 'collection'
+              AND tpf.transform_id = ct.collection_transform_id
+              AND tpf.process_status = 'processing'
+        )
+        OR EXISTS (
+            SELECT 1 FROM pending_batches pb
+            WHERE pb.collection_transform_id = ct.collection_transform_id AND pb.status = 'pending'
+        )
+      )
+    UNION
+    SELECT c.transform_type, c.transform_id
+    FROM transform_trigger_claims c
+    WHERE c.owner_id = $1
+      AND c.claimed_at > NOW() - make_interval(secs => $2)
+      AND NOT CASE c.transform_type
+        WHEN 'visualization' THEN EXISTS (
+            SELECT 1 FROM visualizations v
+            WHERE v.visualization_transform_id = c.transform_id AND v.created_at >= c.claimed_at
+        )
+        WHEN 'dataset' THEN EXISTS (
+            SELECT 1 FROM dataset_transform_batches b
+            WHERE b.dataset_transform_id = c.transform_id AND b.updated_at >= c.claimed_at
+        )
+        ELSE EXISTS (
+            SELECT 1 FROM transform_processed_files tpf
+            WHERE tpf.transform_type = 'collection'
+              AND tpf.transform_id = c.transform_id
+              AND tpf.processed_at >= c.claimed_at
+        )
+      END
+"#;
+
+const UPSERT_TRIGGER_CLAIM_QUERY: &str = r#"
+    INSERT INTO transform_trigger_claims (transform_type, transform_id, owner_id)
+    VALUES ($1, $2, $3)
+    ON CONFLICT (transform_type, transform_id)
+    DO UPDATE SET owner_id = EXCLUDED.owner_id, claimed_at = NOW()
+"#;
+
+#[derive(Debug, FromRow)]
+struct ActiveTransform {
+    transform_type: String,
+    transform_id: i32,
+}
+
+/// Claim one of `owner_id`'s concurrency slots for a transform about to be
+/// triggered. Claims for the same owner are serialized with an advisory
+/// lock, so concurrent triggers can't both take the last slot.
+#[tracing::instrument(name = "database.claim_transform_slot", skip(pool), fields(database.system = "postgresql", database.operation = "INSERT", owner_id = %owner_id, transform_type = %kind.as_str(), transform_id = %transform_id))]
+pub(crate) async fn claim_transform_slot(
+    pool: &Pool<Postgres>,
+    owner_id: &str,
+    kind: TransformKind,
+    transform_id: i32,
+    limit: usize,
+    claim_ttl: Duration,
+) -> Result<SlotClaim> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(owner_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let running: Vec<(TransformKind, i32)> =
+        sqlx::query_as::<_, ActiveTransform>(GET_ACTIVE_TRANSFORMS_QUERY)
+            .bind(owner_id)
+            .bind(claim_ttl.as_secs_f64())
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .filter_map(|row| Some((TransformKind::parse(&row.transform_type)?, row.transform_id)))
+            .collect();
+
+    let claim = admit(&running, kind, transform_id, limit);
+    if claim.is_admitted() {
+        sqlx::query(UPSERT_TRIGGER_CLAIM_QUERY)
+            .bind(kind.as_str())
+            .bind(transform_id)
+            .bind(owner_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(claim)
+}
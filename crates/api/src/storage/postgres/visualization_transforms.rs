@@ -65,6 +65,7 @@ pub struct VisualizationUpdate {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub html_s3_key: Option<String>,
+    pub points_s3_key: Option<String>,
     pub point_count: Option<i32>,
     pub cluster_count: Option<i32>,
     pub error_message: Option<String>,
@@ -95,6 +96,12 @@ impl VisualizationUpdate {
         self
     }
 
+    /// Set the S3 key of the points JSON
+    pub fn points_s3_key(mut self, points_s3_key: impl Into<String>) -> Self {
+        self.points_s3_key = Some(points_s3_key.into());
+        self
+    }
+
     /// Set the point count
     pub fn point_count(mut self, point_count: i32) -> Self {
         self.point_count = Some(point_count);
@@ -143,19 +150,19 @@ const CREATE_VISUALIZATION_QUERY: &str = r#"
     INSERT INTO visualizations (visualization_transform_id, status, created_at)
     VALUES ($1, $2, NOW())
     RETURNING visualization_id, visualization_transform_id, status, started_at, completed_at,
-              html_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+              html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
 "#;
 
 const GET_VISUALIZATION_QUERY: &str = r#"
     SELECT visualization_id, visualization_transform_id, status, started_at, completed_at,
-           html_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+           html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
     FROM visualizations
     WHERE visualization_id = $1
 "#;
 
 const GET_VISUALIZATION_WITH_OWNER_QUERY: &str = r#"
     SELECT v.visualization_id, v.visualization_transform_id, v.status, v.started_at, v.completed_at,
-           v.html_s3_key, v.point_count, v.cluster_count, v.error_message, v.stats_json, v.created_at
+           v.html_s3_key, v.points_s3_key, v.point_count, v.cluster_count, v.error_message, v.stats_json, v.created_at
     FROM visualizations v
     INNER JOIN visualization_transforms vt ON v.visualization_transform_id = vt.visualization_transform_id
     WHERE v.visualization_id = $1 AND v.visualization_transform_id = $2 AND vt.owner_id = $3
@@ -163,16 +170,26 @@ const GET_VISUALIZATION_WITH_OWNER_QUERY: &str = r#"
 
 const GET_LATEST_VISUALIZATION_QUERY: &str = r#"
     SELECT visualization_id, visualization_transform_id, status, started_at, completed_at,
-           html_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+           html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
     FROM visualizations
     WHERE visualization_transform_id = $1
     ORDER BY created_at DESC
     LIMIT 1
 "#;
 
+/// Latest full run, the one whose points and topics `assign_only` runs use
+const GET_LATEST_FITTED_VISUALIZATION_QUERY: &str = r#"
+    SELECT visualization_id, visualization_transform_id, status, started_at, completed_at,
+           html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+    FROM visualizations
+    WHERE visualization_transform_id = $1 AND status = 'completed' AND points_s3_key IS NOT NULL
+    ORDER BY created_at DESC
+    LIMIT 1
+"#;
+
 const LIST_VISUALIZATIONS_QUERY: &str = r#"
     SELECT visualization_id, visualization_transform_id, status, started_at, completed_at,
-           html_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+           html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
     FROM visualizations
     WHERE visualization_transform_id = $1
     ORDER BY created_at DESC
@@ -188,10 +205,11 @@ const UPDATE_VISUALIZATION_QUERY: &str = r#"
         point_count = COALESCE($6, point_count),
         cluster_count = COALESCE($7, cluster_count),
         error_message = COALESCE($8, error_message),
-        stats_json = COALESCE($9, stats_json)
+        stats_json = COALESCE($9, stats_json),
+        points_s3_key = COALESCE($10, points_s3_key)
     WHERE visualization_id = $1
     RETURNING visualization_id, visualization_transform_id, status, started_at, completed_at,
-              html_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+              html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
 "#;
 
 /// Atomically update visualization to processing status only if not already completed/failed.
@@ -203,12 +221,12 @@ const UPDATE_VISUALIZATION_TO_PROCESSING_QUERY: &str = r#"
     WHERE visualization_id = $1
       AND status NOT IN ('completed', 'failed')
     RETURNING visualization_id, visualization_transform_id, status, started_at, completed_at,
-              html_s3_key, point_count, cluster_count, error_message, stats_json, created_at
+              html_s3_key, points_s3_key, point_count, cluster_count, error_message, stats_json, created_at
 "#;
 
 const GET_RECENT_VISUALIZATIONS_QUERY: &str = r#"
     SELECT v.visualization_id, v.visualization_transform_id, v.status, v.started_at, v.completed_at,
-           v.html_s3_key, v.point_count, v.cluster_count, v.error_message, v.stats_json, v.created_at
+           v.html_s3_key, v.points_s3_key, v.point_count, v.cluster_count, v.error_message, v.stats_json, v.created_at
     FROM visualizations v
     INNER JOIN visualization_transforms vt ON v.visualization_transform_id = vt.visualization_transform_id
     WHERE vt.owner_id = $1
@@ -266,6 +284,17 @@ pub async fn get_latest_visualization(
     Ok(visualization)
 }
 
+pub async fn get_latest_fitted_visualization(
+    pool: &Pool<Postgres>,
+    visualization_transform_id: i32,
+) -> Result<Option<Visualization>> {
+    let visualization = sqlx::query_as::<_, Visualization>(GET_LATEST_FITTED_VISUALIZATION_QUERY)
+        .bind(visualization_transform_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(visualization)
+}
+
 pub async fn list_visualizations(
     pool: &Pool<Postgres>,
     visualization_transform_id: i32,
@@ -310,6 +339,7 @@ pub async fn update_visualization(
         .bind(update.cluster_count)
         .bind(update.error_message.as_deref())
         .bind(update.stats_json.as_ref())
+        .bind(update.points_s3_key.as_deref())
         .fetch_one(pool)
         .await?;
     Ok(visualization)
@@ -377,6 +407,12 @@ const UPDATE_VISUALIZATION_TRANSFORM_STATUS_QUERY: &str = r#"
     WHERE visualization_transform_id = $1
 "#;
 
+const SET_TOPICS_COLLECTION_NAME_QUERY: &str = r#"
+    UPDATE visualization_transforms
+    SET topics_collection_name = $2, updated_at = NOW()
+    WHERE visualization_transform_id = $1
+"#;
+
 const GET_VISUALIZATION_TRANSFORMS_BY_EMBEDDED_DATASET_QUERY: &str = r#"
     SELECT visualization_transform_id, title, embedded_dataset_id, owner_id, owner_display_name, is_enabled,
            reduced_collection_name, topics_collection_name, visualization_config,
@@ -719,6 +755,19 @@ pub async fn delete_visualization_transform(
     Ok(())
 }
 
+pub async fn set_topics_collection_name(
+    pool: &Pool<Postgres>,
+    id: i32,
+    topics_collection_name: &str,
+) -> Result<()> {
+    sqlx::query(SET_TOPICS_COLLECTION_NAME_QUERY)
+        .bind(id)
+        .bind(topics_collection_name)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 pub async fn get_visualization_transforms_by_embedded_dataset(
     pool: &Pool<Postgres>,
     embedded_dataset_id: i32,
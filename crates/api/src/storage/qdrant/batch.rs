@@ -0,0 +1,127 @@
+//! Batched queries: many queries against one collection in a single Qdrant
+//! request, with each query's results handed back at the query's index.
+
+use anyhow::{Result, bail};
+use qdrant_client::{
+    Qdrant, QdrantError,
+    qdrant::{QueryBatchPointsBuilder, QueryBatchResponse, QueryPoints, ScoredPoint},
+};
+
+/// Run `queries` against `collection_name` in one round trip
+pub(crate) async fn query_batch(
+    qdrant: &Qdrant,
+    collection_name: &str,
+    queries: Vec<QueryPoints>,
+) -> Result<Vec<Vec<ScoredPoint>>> {
+    run_batch(queries, |queries| {
+        qdrant.query_batch(QueryBatchPointsBuilder::new(collection_name, queries))
+    })
+    .await
+}
+
+async fn run_batch<F, Fut>(queries: Vec<QueryPoints>, send: F) -> Result<Vec<Vec<ScoredPoint>>>
+where
+    F: FnOnce(Vec<QueryPoints>) -> Fut,
+    Fut: Future<Output = Result<QueryBatchResponse, QdrantError>>,
+{
+    if queries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let expected = queries.len();
+    let response = send(queries).await?;
+    if response.result.len() != expected {
+        bail!(
+            "Qdrant returned {} results for a batch of {} queries",
+            response.result.len(),
+            expected
+        );
+    }
+    Ok(response
+        .result
+        .into_iter()
+        .map(|batch| batch.result)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qdrant_client::qdrant::{BatchResult, PointId, Query, QueryPointsBuilder};
+    use std::cell::Cell;
+
+    fn query(vector: Vec<f32>) -> QueryPoints {
+        QueryPointsBuilder::new("collection")
+            .query(Query::new_nearest(vector))
+            .limit(2)
+            .build()
+    }
+
+    fn point(id: u64, score: f32) -> ScoredPoint {
+        ScoredPoint {
+            id: Some(PointId::from(id)),
+            score,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_returns_results_in_query_order_in_one_round_trip() {
+        let round_trips = Cell::new(0);
+        let results = run_batch(
+            vec![
+                query(vec![1.0, 0.0]),
+                query(vec![0.0, 1.0]),
+                query(vec![0.5, 0.5]),
+            ],
+            |queries| {
+                round_trips.set(round_trips.get() + 1);
+                // Answer each query with points derived from its position
+                let result = (0..queries.len() as u64)
+                    .map(|i| BatchResult {
+                        result: vec![point(i * 10, 0.9), point(i * 10 + 1, 0.5)],
+                    })
+                    .collect();
+                async move {
+                    Ok(QueryBatchResponse {
+                        result,
+                        ..Default::default()
+                    })
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(round_trips.get(), 1);
+        assert_eq!(results.len(), 3);
+        for (i, points) in results.iter().enumerate() {
+            let ids: Vec<_> = points.iter().map(|p| p.id.clone().unwrap()).collect();
+            let i = i as u64;
+            assert_eq!(ids, vec![PointId::from(i * 10), PointId::from(i * 10 + 1)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejects_mismatched_result_count() {
+        let result = run_batch(vec![query(vec![1.0]), query(vec![2.0])], |_| async {
+            Ok(QueryBatchResponse {
+                result: vec![BatchResult::default()],
+                ..Default::default()
+            })
+        })
+        .await;
+        assert!(result.is_err());
+
+        // An empty batch never reaches Qdrant
+        let sent = Cell::new(false);
+        let empty = run_batch(Vec::new(), |_| {
+            sent.set(true);
+            async { Ok(QueryBatchResponse::default()) }
+        })
+        .await
+        .unwrap();
+        assert!(empty.is_empty());
+        assert!(!sent.get());
+    }
+}
@@ -2,7 +2,9 @@ use anyhow::Result;
 use qdrant_client::Qdrant;
 use semantic_explorer_core::config::QdrantConfig;
 
+pub(crate) mod batch;
 pub mod quantization;
+pub(crate) mod write;
 
 pub use quantization::{QuantizationType, log_quantization_config};
 
@@ -0,0 +1,100 @@
+//! Write consistency for Qdrant upserts.
+//!
+//! A dataset transform may set `qdrant_write` in its `job_config`, e.g.
+//! `{"wait": true, "ordering": "strong"}`, for flows that search right after
+//! writing. `wait` makes an upsert return only once the points are applied;
+//! `ordering` controls how the write is ordered across replicas. The scanner
+//! passes the setting to the dataset worker with every job.
+
+use qdrant_client::qdrant::{PointStruct, UpsertPointsBuilder, WriteOrdering, WriteOrderingType};
+use serde_json::Value;
+
+use semantic_explorer_core::models::{QdrantWriteConsistency, WriteOrderingLevel};
+
+/// Key in a dataset transform's `job_config` holding the write consistency
+pub const WRITE_CONSISTENCY_KEY: &str = "qdrant_write";
+
+/// Validate the write consistency in a `job_config` supplied on update.
+pub fn validate_job_config(job_config: &Value) -> Result<(), String> {
+    match job_config.get(WRITE_CONSISTENCY_KEY) {
+        None | Some(Value::Null) => Ok(()),
+        Some(consistency) => serde_json::from_value::<QdrantWriteConsistency>(consistency.clone())
+            .map(|_| ())
+            .map_err(|e| format!("invalid qdrant_write: {e}")),
+    }
+}
+
+/// The transform's write consistency, the default when unset
+pub fn write_consistency(job_config: &Value) -> QdrantWriteConsistency {
+    job_config
+        .get(WRITE_CONSISTENCY_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn ordering_type(level: WriteOrderingLevel) -> WriteOrderingType {
+    match level {
+        WriteOrderingLevel::Weak => WriteOrderingType::Weak,
+        WriteOrderingLevel::Medium => WriteOrderingType::Medium,
+        WriteOrderingLevel::Strong => WriteOrderingType::Strong,
+    }
+}
+
+/// Upsert request for `points` acknowledged per `consistency`
+pub(crate) fn upsert_request(
+    collection_name: &str,
+    points: Vec<PointStruct>,
+    consistency: QdrantWriteConsistency,
+) -> UpsertPointsBuilder {
+    UpsertPointsBuilder::new(collection_name, points)
+        .wait(consistency.wait)
+        .ordering(WriteOrdering {
+            r#type: ordering_type(consistency.ordering) as i32,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_upsert_request_follows_config() {
+        let request = upsert_request("c", Vec::new(), QdrantWriteConsistency::default()).build();
+        assert_eq!(request.wait, Some(true));
+        assert_eq!(
+            request.ordering.map(|o| o.r#type),
+            Some(WriteOrderingType::Weak as i32)
+        );
+
+        let consistency = write_consistency(
+            &json!({ WRITE_CONSISTENCY_KEY: { "wait": false, "ordering": "strong" } }),
+        );
+        let request = upsert_request("c", Vec::new(), consistency).build();
+        assert_eq!(request.wait, Some(false));
+        assert_eq!(
+            request.ordering.map(|o| o.r#type),
+            Some(WriteOrderingType::Strong as i32)
+        );
+    }
+
+    #[test]
+    fn test_write_consistency_config() {
+        assert_eq!(
+            write_consistency(&json!({})),
+            QdrantWriteConsistency::default()
+        );
+        // Unset fields keep their defaults
+        let consistency =
+            write_consistency(&json!({ WRITE_CONSISTENCY_KEY: { "ordering": "medium" } }));
+        assert!(consistency.wait);
+        assert_eq!(consistency.ordering, WriteOrderingLevel::Medium);
+
+        assert!(validate_job_config(&json!({ WRITE_CONSISTENCY_KEY: { "wait": true } })).is_ok());
+        assert!(
+            validate_job_config(&json!({ WRITE_CONSISTENCY_KEY: { "ordering": "eventual" } }))
+                .is_err()
+        );
+        assert!(validate_job_config(&json!({ WRITE_CONSISTENCY_KEY: "strong" })).is_err());
+    }
+}
@@ -3,16 +3,31 @@ pub(crate) mod models;
 use anyhow::{Context, Result, bail};
 use aws_sdk_s3::Client;
 use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use aws_sdk_s3::presigning::{PresignedRequest, PresigningConfig};
 use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
 use semantic_explorer_core::observability::record_storage_operation;
-
+use semantic_explorer_core::retry::{RetryableError, retry_with_policy, s3_retry_policy};
+use semantic_explorer_core::storage::{
+    copy_object_with_sse, create_multipart_upload_with_sse, object_tagging, object_tagging_header,
+    put_object_with_sse, server_side_encryption,
+};
+
+use actix_web::web::{Bytes, BytesMut};
+use futures_util::stream::{BoxStream, Stream, StreamExt};
 use std::error::Error;
 use std::fmt::Debug;
-use std::time::Instant;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
 use tracing::warn;
 
-use crate::storage::s3::models::{CollectionFile, DocumentUpload, S3FileList};
+use crate::storage::s3::models::{
+    CollectionFile, CopyFailure, CopyOptions, CopySummary, PaginatedFiles, PresignedUrl,
+    StreamedUploadConfig,
+};
 
 /// Initialize S3 client using shared configuration from core
 /// Supports both static credentials and IAM roles
@@ -20,41 +35,65 @@ pub(crate) async fn initialize_client() -> Result<aws_sdk_s3::Client> {
     semantic_explorer_core::storage::initialize_client().await
 }
 
-/// Multipart upload threshold: files larger than 8 MiB use multipart upload.
-/// MinIO rejects single-part uploads with chunks > 16 MiB.
-const MULTIPART_THRESHOLD: u64 = 8 * 1024 * 1024;
-/// Part size for multipart uploads (8 MiB).
-const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
-
-/// Upload document to collection using single-bucket architecture.
+/// Stream a document into a collection using single-bucket architecture.
 /// Uses: S3_BUCKET_NAME/collections/{collection_id}/{filename}
 ///
-/// Files <= 8 MiB are uploaded with a single PutObject.
-/// Files > 8 MiB use S3 multipart upload to stay within MinIO's 16 MiB chunk limit.
-#[tracing::instrument(name = "s3.upload_document", skip(client, bucket_name, document), fields(storage.system = "s3", collection_id = %document.collection_id, key = %document.name, size = document.size))]
-pub(crate) async fn upload_document(
+/// The body is read one `part_size` part at a time. A body that ends within
+/// the first part is uploaded with a single PutObject; anything larger goes
+/// through a multipart upload with up to `concurrency` parts in flight, so
+/// memory stays bounded whatever the file size. The multipart upload is
+/// aborted if the body fails (e.g. the client disconnects), a part can't be
+/// uploaded, or the body grows past `max_size`.
+///
+/// Returns the number of bytes uploaded.
+#[tracing::instrument(name = "s3.upload_document_stream", skip(client, bucket_name, body, config), fields(storage.system = "s3", collection_id = %collection_id, key = %file_name))]
+pub(crate) async fn upload_document_stream<S, E>(
     client: &Client,
     bucket_name: &str,
-    document: DocumentUpload,
-) -> Result<()> {
+    collection_id: i32,
+    file_name: &str,
+    mime_type: &str,
+    body: S,
+    config: &StreamedUploadConfig,
+) -> Result<u64>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
     let start = Instant::now();
-    let file_size = document.size;
-    let key = format!("collections/{}/{}", document.collection_id, document.name);
+    let key = format!("collections/{}/{}", collection_id, file_name);
+    let mut reader = PartReader::new(body, config);
 
     tracing::debug!(
         bucket = %bucket_name,
-        collection_id = %document.collection_id,
-        key = %document.name,
-        size = file_size,
-        mime_type = %document.mime_type,
-        "Uploading document to S3"
+        collection_id = collection_id,
+        key = %file_name,
+        mime_type = %mime_type,
+        part_size = config.part_size,
+        "Streaming document to S3"
     );
 
-    let result = if file_size <= MULTIPART_THRESHOLD {
-        upload_single_part(client, bucket_name, &key, document).await
-    } else {
-        upload_multipart(client, bucket_name, &key, document).await
+    let result = match reader.next_part().await {
+        Ok(first) => {
+            let first = first.unwrap_or_default();
+            if first.len() < config.part_size {
+                upload_single_part(client, bucket_name, &key, mime_type, first).await
+            } else {
+                upload_multipart(
+                    client,
+                    bucket_name,
+                    &key,
+                    mime_type,
+                    first,
+                    &mut reader,
+                    config,
+                )
+                .await
+            }
+        }
+        Err(e) => Err(e),
     };
+    let file_size = reader.received;
 
     let duration = start.elapsed().as_secs_f64();
     let success = result.is_ok();
@@ -88,176 +127,243 @@ pub(crate) async fn upload_document(
         }
     }
 
-    result
+    result.map(|_| file_size)
+}
+
+/// Returned by `upload_document_stream` when the body grows past the
+/// configured maximum upload size
+#[derive(Debug)]
+pub(crate) struct UploadTooLarge {
+    pub(crate) max_size: u64,
 }
 
-/// Single-part upload for small files (<= 8 MiB)
+impl std::fmt::Display for UploadTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "File exceeds maximum size of {} bytes ({})",
+            self.max_size,
+            format_file_size(self.max_size)
+        )
+    }
+}
+
+impl Error for UploadTooLarge {}
+
+/// Splits an upload body into parts of `part_size` bytes, counting the bytes
+/// received against `max_size`
+struct PartReader<S> {
+    body: S,
+    buffer: BytesMut,
+    part_size: usize,
+    max_size: u64,
+    received: u64,
+}
+
+impl<S, E> PartReader<S>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    fn new(body: S, config: &StreamedUploadConfig) -> Self {
+        Self {
+            body,
+            buffer: BytesMut::new(),
+            part_size: config.part_size.max(1),
+            max_size: config.max_size,
+            received: 0,
+        }
+    }
+
+    /// The next part: `part_size` bytes, fewer for the last one, or `None`
+    /// once the body is exhausted
+    async fn next_part(&mut self) -> Result<Option<Bytes>> {
+        while self.buffer.len() < self.part_size {
+            match self.body.next().await {
+                Some(Ok(chunk)) => {
+                    self.received += chunk.len() as u64;
+                    if self.received > self.max_size {
+                        return Err(UploadTooLarge {
+                            max_size: self.max_size,
+                        }
+                        .into());
+                    }
+                    self.buffer.extend_from_slice(&chunk);
+                }
+                Some(Err(e)) => bail!("Failed to read upload body: {}", e),
+                None => break,
+            }
+        }
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+        let len = self.buffer.len().min(self.part_size);
+        Ok(Some(self.buffer.split_to(len).freeze()))
+    }
+}
+
+/// Configured lifecycle tags for an object uploaded now
+fn upload_tagging(key: &str, content_type: &str) -> Option<String> {
+    object_tagging_header(
+        object_tagging(),
+        key,
+        content_type,
+        chrono::Utc::now().date_naive(),
+    )
+}
+
+/// Single PutObject for a body that fits in one part
 async fn upload_single_part(
     client: &Client,
     bucket_name: &str,
     key: &str,
-    document: DocumentUpload,
+    mime_type: &str,
+    content: Bytes,
 ) -> Result<()> {
-    client
-        .put_object()
-        .bucket(bucket_name)
-        .key(key)
-        .body(document.content)
-        .content_length(document.size as i64)
-        .content_type(&document.mime_type)
-        .send()
-        .await
-        .map_err(|e| {
-            anyhow::anyhow!(
-                "S3 upload failed for '{}' ({}): {}",
-                document.name,
-                format_file_size(document.size),
-                format_s3_error(&e)
-            )
-        })?;
+    let size = content.len() as u64;
+    retry_with_policy(&s3_retry_policy(), "s3_put_object", || {
+        let request = client
+            .put_object()
+            .bucket(bucket_name)
+            .key(key)
+            .body(ByteStream::from(content.clone()))
+            .content_length(size as i64)
+            .content_type(mime_type)
+            .set_tagging(upload_tagging(key, mime_type));
+        async move {
+            put_object_with_sse(request, server_side_encryption())
+                .send()
+                .await
+                .map_err(|e| S3RequestError::from_sdk_error(&e))
+        }
+    })
+    .await
+    .map_err(|e| {
+        anyhow::anyhow!(
+            "S3 upload failed for '{}' ({}): {}",
+            key,
+            format_file_size(size),
+            e
+        )
+    })?;
     Ok(())
 }
 
-/// Multipart upload for large files (> 8 MiB)
-/// Splits the file into 8 MiB parts and uploads them individually.
-async fn upload_multipart(
+/// Multipart upload of `first` and the rest of `reader`
+async fn upload_multipart<S, E>(
     client: &Client,
     bucket_name: &str,
     key: &str,
-    document: DocumentUpload,
-) -> Result<()> {
-    let file_name = document.name.clone();
-    let file_size = document.size;
-    let num_parts = (file_size as usize).div_ceil(MULTIPART_PART_SIZE);
-
-    tracing::debug!(
-        key = %key,
-        file_size = file_size,
-        num_parts = num_parts,
-        part_size = MULTIPART_PART_SIZE,
-        "Starting multipart upload"
-    );
-
-    // 1. Initiate multipart upload
-    let create_resp = client
+    mime_type: &str,
+    first: Bytes,
+    reader: &mut PartReader<S>,
+    config: &StreamedUploadConfig,
+) -> Result<()>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let request = client
         .create_multipart_upload()
         .bucket(bucket_name)
         .key(key)
-        .content_type(&document.mime_type)
+        .content_type(mime_type)
+        .set_tagging(upload_tagging(key, mime_type));
+    let create_resp = create_multipart_upload_with_sse(request, server_side_encryption())
         .send()
         .await
         .map_err(|e| {
             anyhow::anyhow!(
                 "Failed to initiate multipart upload for '{}': {}",
-                file_name,
+                key,
                 format_s3_error(&e)
             )
         })?;
-
     let upload_id = create_resp
         .upload_id()
-        .context("Missing upload_id from CreateMultipartUpload response")?;
-
-    // 2. Collect the stream into bytes so we can split into parts
-    let all_bytes = document
-        .content
-        .collect()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to read file content for '{}': {}", file_name, e))?
-        .into_bytes();
-
-    // 3. Upload each part
-    let mut completed_parts: Vec<CompletedPart> = Vec::with_capacity(num_parts);
-    let mut offset = 0usize;
-    let total = all_bytes.len();
-
-    for part_number in 1..=num_parts {
-        let end = std::cmp::min(offset + MULTIPART_PART_SIZE, total);
-        let part_data = all_bytes.slice(offset..end);
-        let part_len = part_data.len() as i64;
+        .context("Missing upload_id from CreateMultipartUpload response")?
+        .to_string();
+
+    // Aborts the upload unless it completes, including when the request
+    // handler is dropped mid-upload
+    let mut guard = AbortMultipartUpload {
+        client: client.clone(),
+        bucket_name: bucket_name.to_string(),
+        key: key.to_string(),
+        upload_id: Some(upload_id.clone()),
+    };
 
-        let upload_part_result = client
-            .upload_part()
-            .bucket(bucket_name)
-            .key(key)
-            .upload_id(upload_id)
-            .part_number(part_number as i32)
-            .content_length(part_len)
-            .body(ByteStream::from(part_data))
-            .send()
-            .await;
-
-        match upload_part_result {
-            Ok(resp) => {
-                let etag = resp.e_tag().unwrap_or_default().to_string();
+    let upload_part = {
+        let client = client.clone();
+        let bucket_name = bucket_name.to_string();
+        let key = key.to_string();
+        move |part_number: i32, data: Bytes| {
+            let client = client.clone();
+            let bucket_name = bucket_name.clone();
+            let key = key.clone();
+            let upload_id = upload_id.clone();
+            async move {
+                let response = retry_with_policy(&s3_retry_policy(), "s3_upload_part", || {
+                    let request = client
+                        .upload_part()
+                        .bucket(&bucket_name)
+                        .key(&key)
+                        .upload_id(&upload_id)
+                        .part_number(part_number)
+                        .content_length(data.len() as i64)
+                        .body(ByteStream::from(data.clone()));
+                    async move {
+                        request
+                            .send()
+                            .await
+                            .map_err(|e| S3RequestError::from_sdk_error(&e))
+                    }
+                })
+                .await?;
                 tracing::debug!(
                     key = %key,
                     part_number = part_number,
-                    part_size = part_len,
+                    part_size = data.len(),
                     "Uploaded part"
                 );
-                completed_parts.push(
-                    CompletedPart::builder()
-                        .e_tag(&etag)
-                        .part_number(part_number as i32)
-                        .build(),
-                );
-            }
-            Err(e) => {
-                // Abort the multipart upload on failure
-                let error_detail = format_s3_error(&e);
-                tracing::error!(
-                    key = %key,
-                    part_number = part_number,
-                    error = %error_detail,
-                    "Failed to upload part, aborting multipart upload"
-                );
-                let _ = client
-                    .abort_multipart_upload()
-                    .bucket(bucket_name)
-                    .key(key)
-                    .upload_id(upload_id)
-                    .send()
-                    .await;
-                return Err(anyhow::anyhow!(
-                    "S3 multipart upload failed for '{}' ({}) at part {}/{}: {}",
-                    file_name,
-                    format_file_size(file_size),
-                    part_number,
-                    num_parts,
-                    error_detail
-                ));
+                Ok(CompletedPart::builder()
+                    .e_tag(response.e_tag().unwrap_or_default())
+                    .part_number(part_number)
+                    .build())
             }
         }
+    };
 
-        offset = end;
-    }
-
-    // 4. Complete the multipart upload
-    let completed_upload = CompletedMultipartUpload::builder()
-        .set_parts(Some(completed_parts))
-        .build();
+    let completed_parts = upload_parts(reader, first, config.concurrency, upload_part)
+        .await
+        .with_context(|| format!("S3 multipart upload failed for '{}'", key))?;
+    let num_parts = completed_parts.len();
 
     client
         .complete_multipart_upload()
         .bucket(bucket_name)
         .key(key)
-        .upload_id(upload_id)
-        .multipart_upload(completed_upload)
+        .upload_id(guard.upload_id.as_deref().unwrap_or_default())
+        .multipart_upload(
+            CompletedMultipartUpload::builder()
+                .set_parts(Some(completed_parts))
+                .build(),
+        )
         .send()
         .await
         .map_err(|e| {
             anyhow::anyhow!(
                 "Failed to complete multipart upload for '{}' ({}): {}",
-                file_name,
-                format_file_size(file_size),
+                key,
+                format_file_size(reader.received),
                 format_s3_error(&e)
             )
         })?;
+    guard.upload_id = None;
 
     tracing::info!(
         key = %key,
-        file_size = file_size,
+        file_size = reader.received,
         num_parts = num_parts,
         "Multipart upload completed successfully"
     );
@@ -265,7 +371,173 @@ async fn upload_multipart(
     Ok(())
 }
 
+/// Upload the parts of `reader`, starting with `first`, with at most
+/// `concurrency` uploads in flight. Returns the completed parts in order.
+async fn upload_parts<S, E, F, Fut>(
+    reader: &mut PartReader<S>,
+    first: Bytes,
+    concurrency: usize,
+    upload_part: F,
+) -> Result<Vec<CompletedPart>>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+    F: Fn(i32, Bytes) -> Fut,
+    Fut: Future<Output = std::result::Result<CompletedPart, S3RequestError>> + Send + 'static,
+{
+    let mut in_flight = JoinSet::new();
+    let mut completed = Vec::new();
+    let mut next = Some(first);
+    let mut part_number = 0;
+
+    loop {
+        while in_flight.len() < concurrency.max(1)
+            && let Some(part) = next.take()
+        {
+            part_number += 1;
+            in_flight.spawn(upload_part(part_number, part));
+            next = reader.next_part().await?;
+        }
+        match in_flight.join_next().await {
+            Some(result) => {
+                let part = result
+                    .context("Part upload task failed")?
+                    .context("Failed to upload part")?;
+                completed.push(part);
+            }
+            None => break,
+        }
+    }
+
+    completed.sort_by_key(|part| part.part_number());
+    Ok(completed)
+}
+
+/// Aborts a multipart upload when dropped, unless `upload_id` was taken
+/// after the upload completed
+struct AbortMultipartUpload {
+    client: Client,
+    bucket_name: String,
+    key: String,
+    upload_id: Option<String>,
+}
+
+impl Drop for AbortMultipartUpload {
+    fn drop(&mut self) {
+        let Some(upload_id) = self.upload_id.take() else {
+            return;
+        };
+        let client = self.client.clone();
+        let bucket_name = std::mem::take(&mut self.bucket_name);
+        let key = std::mem::take(&mut self.key);
+        tokio::spawn(async move {
+            if let Err(e) = client
+                .abort_multipart_upload()
+                .bucket(&bucket_name)
+                .key(&key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+            {
+                warn!(
+                    key = %key,
+                    upload_id = %upload_id,
+                    error = %format_s3_error(&e),
+                    "Failed to abort multipart upload"
+                );
+            } else {
+                tracing::debug!(key = %key, "Aborted multipart upload");
+            }
+        });
+    }
+}
+
+/// Presigned PutObject URL for uploading `collections/{collection_id}/{file_name}`
+/// straight to the bucket. The content length, configured server-side
+/// encryption and lifecycle tags are signed into the request, so the client
+/// must send the returned headers with the upload and can't send a larger file.
+#[tracing::instrument(name = "s3.generate_presigned_put", skip(client, bucket_name), fields(storage.system = "s3", collection_id = %collection_id, key = %file_name))]
+pub(crate) async fn generate_presigned_put(
+    client: &Client,
+    bucket_name: &str,
+    collection_id: &str,
+    file_name: &str,
+    content_type: &str,
+    content_length: i64,
+    expires_in: Duration,
+) -> Result<PresignedUrl> {
+    let key = format!("collections/{}/{}", collection_id, file_name);
+    let request = client
+        .put_object()
+        .bucket(bucket_name)
+        .key(&key)
+        .content_type(content_type)
+        .content_length(content_length)
+        .set_tagging(upload_tagging(&key, content_type));
+    let presigned = put_object_with_sse(request, server_side_encryption())
+        .presigned(presigning_config(expires_in)?)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to presign upload for '{}': {}",
+                file_name,
+                format_s3_error(&e)
+            )
+        })?;
+    Ok(presigned_url(presigned, file_name, expires_in))
+}
+
+/// Presigned GetObject URL for downloading `collections/{collection_id}/{file_name}`
+/// straight from the bucket
+#[tracing::instrument(name = "s3.generate_presigned_get", skip(client, bucket_name), fields(storage.system = "s3", collection_id = %collection_id, key = %file_name))]
+pub(crate) async fn generate_presigned_get(
+    client: &Client,
+    bucket_name: &str,
+    collection_id: &str,
+    file_name: &str,
+    expires_in: Duration,
+) -> Result<PresignedUrl> {
+    let key = format!("collections/{}/{}", collection_id, file_name);
+    let presigned = client
+        .get_object()
+        .bucket(bucket_name)
+        .key(&key)
+        .response_content_disposition(format!("attachment; filename=\"{}\"", file_name))
+        .presigned(presigning_config(expires_in)?)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to presign download for '{}': {}",
+                file_name,
+                format_s3_error(&e)
+            )
+        })?;
+    Ok(presigned_url(presigned, file_name, expires_in))
+}
+
+fn presigning_config(expires_in: Duration) -> Result<PresigningConfig> {
+    PresigningConfig::expires_in(expires_in).context("Invalid presigned URL expiry")
+}
+
+fn presigned_url(request: PresignedRequest, file_name: &str, expires_in: Duration) -> PresignedUrl {
+    PresignedUrl {
+        url: request.uri().to_string(),
+        method: request.method().to_string(),
+        headers: request
+            .headers()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        key: file_name.to_string(),
+        expires_at: chrono::Utc::now()
+            + chrono::Duration::from_std(expires_in).unwrap_or_else(|_| chrono::Duration::zero()),
+    }
+}
+
 /// Uses: S3_BUCKET_NAME/collections/{collection_id}/
+///
+/// With a `delimiter`, lists a single level below `folder` (a path inside the
+/// collection such as `logs/2024/`): keys containing the delimiter past that
+/// point are rolled up into `folders`, like `aws s3 ls` without `--recursive`.
 #[tracing::instrument(name = "s3.list_files", skip(s3_client, bucket_name), fields(storage.system = "s3", collection_id = %collection_id, page_size = %page_size))]
 pub(crate) async fn list_files(
     s3_client: &Client,
@@ -273,19 +545,24 @@ pub(crate) async fn list_files(
     collection_id: &str,
     page_size: i32,
     continuation_token: Option<&str>,
-) -> Result<S3FileList> {
+    folder: Option<&str>,
+    delimiter: Option<&str>,
+) -> Result<PaginatedFiles> {
     let start = Instant::now();
-    let prefix = format!("collections/{}/", collection_id);
+    let collection_prefix = format!("collections/{}/", collection_id);
+    let prefix = format!("{}{}", collection_prefix, folder.unwrap_or_default());
 
     tracing::debug!(
         bucket = %bucket_name,
         collection_id = %collection_id,
         page_size = page_size,
         has_continuation_token = continuation_token.is_some(),
+        folder = ?folder,
+        delimiter = ?delimiter,
         "Listing files for collection"
     );
 
-    let mut files = Vec::new();
+    let mut entries = Vec::new();
     let page_size_usize = page_size as usize;
     let target_count = page_size_usize + 1; // Need page_size + 1 to know if there are more
 
@@ -293,7 +570,8 @@ pub(crate) async fn list_files(
     let mut request = s3_client
         .list_objects_v2()
         .bucket(bucket_name)
-        .prefix(&prefix);
+        .prefix(&prefix)
+        .set_delimiter(delimiter.map(str::to_string));
 
     // If we have a continuation token, use it as start_after
     if let Some(token) = continuation_token {
@@ -302,7 +580,7 @@ pub(crate) async fn list_files(
 
     let mut paginator = request.into_paginator().send();
 
-    // Iterate through pages until we have enough files or run out
+    // Iterate through pages until we have enough entries or run out
     while let Some(result) = paginator.next().await {
         let output = match result {
             Ok(output) => output,
@@ -317,49 +595,39 @@ pub(crate) async fn list_files(
             }
         };
 
-        for obj in output.contents() {
-            let key = obj.key().unwrap_or_default();
-
-            // Skip the directory marker itself
-            if key == prefix {
-                continue;
-            }
-
-            // Extract display key (without prefix)
-            let display_key = key.strip_prefix(&prefix).unwrap_or(key).to_string();
-
-            files.push(CollectionFile {
-                key: display_key.clone(),
-                size: obj.size().unwrap_or(0),
-                last_modified: obj.last_modified().map(|dt| dt.to_string()),
-                content_type: mime_guess::from_path(&display_key)
-                    .first_raw()
-                    .map(|s| s.to_string()),
-            });
-
-            // Stop once we have enough files to determine if there are more pages
-            if files.len() >= target_count {
+        for entry in listing_entries(
+            output.contents(),
+            output.common_prefixes(),
+            &collection_prefix,
+            &prefix,
+        ) {
+            entries.push(entry);
+            // Stop once we have enough entries to determine if there are more pages
+            if entries.len() >= target_count {
                 break;
             }
         }
 
-        // Stop paginating if we have enough files
-        if files.len() >= target_count {
+        // Stop paginating if we have enough entries
+        if entries.len() >= target_count {
             break;
         }
     }
 
-    // Determine if there are more pages
-    let has_more = files.len() > page_size_usize;
+    let (entries, next_cursor) =
+        semantic_explorer_core::storage::split_page(entries, page_size_usize, |e| {
+            e.cursor(&collection_prefix)
+        });
+    let has_more = next_cursor.is_some();
 
-    // Truncate to page_size and determine next cursor
-    let next_cursor = if has_more {
-        files.truncate(page_size_usize);
-        // Use the last file's full S3 key as the cursor for start_after pagination
-        files.last().map(|f| format!("{}{}", prefix, &f.key))
-    } else {
-        None
-    };
+    let mut files = Vec::new();
+    let mut folders = Vec::new();
+    for entry in entries {
+        match entry {
+            ListingEntry::File(file) => files.push(file),
+            ListingEntry::Folder(folder) => folders.push(folder),
+        }
+    }
 
     let duration = start.elapsed().as_secs_f64();
     record_storage_operation("list", duration, None, true);
@@ -369,26 +637,117 @@ pub(crate) async fn list_files(
         bucket = %bucket_name,
         collection_id = %collection_id,
         file_count = files.len(),
+        folder_count = folders.len(),
         has_more = has_more,
         duration_ms = duration * 1000.0,
         "Successfully listed files for collection"
     );
 
-    Ok(S3FileList {
+    Ok(PaginatedFiles {
         files,
+        folders,
+        page: 0,
+        page_size,
+        has_more,
         continuation_token: next_cursor,
+        total_count: None,
     })
 }
 
-/// Get file from collection using single-bucket architecture
+/// A file or rolled-up folder in a collection listing; both carry their
+/// path relative to the collection prefix
+#[derive(Debug)]
+enum ListingEntry {
+    File(CollectionFile),
+    Folder(String),
+}
+
+impl ListingEntry {
+    fn key(&self) -> &str {
+        match self {
+            ListingEntry::File(file) => &file.key,
+            ListingEntry::Folder(folder) => folder,
+        }
+    }
+
+    /// `start_after` value resuming the listing after this entry. For a
+    /// folder this sorts after every key inside it, so the folder isn't
+    /// returned again on the next page.
+    fn cursor(&self, collection_prefix: &str) -> String {
+        match self {
+            ListingEntry::File(file) => format!("{}{}", collection_prefix, file.key),
+            ListingEntry::Folder(folder) => {
+                format!("{}{}{}", collection_prefix, folder, char::MAX)
+            }
+        }
+    }
+}
+
+/// Merge one page of objects and common prefixes into key-ordered entries,
+/// skipping folder marker objects
+fn listing_entries(
+    objects: &[aws_sdk_s3::types::Object],
+    common_prefixes: &[aws_sdk_s3::types::CommonPrefix],
+    collection_prefix: &str,
+    list_prefix: &str,
+) -> Vec<ListingEntry> {
+    let files = objects.iter().filter_map(|obj| {
+        let key = obj.key().unwrap_or_default();
+        // Skip the directory marker itself
+        if key == list_prefix || key == collection_prefix {
+            return None;
+        }
+
+        // Extract display key (without prefix)
+        let display_key = key
+            .strip_prefix(collection_prefix)
+            .unwrap_or(key)
+            .to_string();
+        Some(ListingEntry::File(CollectionFile {
+            content_type: mime_guess::from_path(&display_key)
+                .first_raw()
+                .map(|s| s.to_string()),
+            key: display_key,
+            size: obj.size().unwrap_or(0),
+            last_modified: obj.last_modified().map(|dt| dt.to_string()),
+        }))
+    });
+    let folders = common_prefixes.iter().filter_map(|cp| {
+        let prefix = cp.prefix()?;
+        Some(ListingEntry::Folder(
+            prefix
+                .strip_prefix(collection_prefix)
+                .unwrap_or(prefix)
+                .to_string(),
+        ))
+    });
+
+    let mut entries: Vec<ListingEntry> = files.chain(folders).collect();
+    entries.sort_by(|a, b| a.key().cmp(b.key()));
+    entries
+}
+
+/// Body of a collection file, streamed from S3 as it is read
+pub(crate) struct FileStream {
+    /// Object size reported by S3, when known
+    pub(crate) content_length: Option<u64>,
+    pub(crate) body: MeteredStream<BoxStream<'static, Result<Bytes>>>,
+}
+
+/// Stream a file from a collection without buffering it in memory.
 /// Uses: S3_BUCKET_NAME/collections/{collection_id}/{filename}
-#[tracing::instrument(name = "s3.get_file", skip(client, bucket_name), fields(storage.system = "s3", collection_id = %collection_id, key = %key))]
-pub(crate) async fn get_file(
+///
+/// Fails before reading the body if S3 reports a size above
+/// `max_download_size_bytes`. Download metrics are recorded when the stream
+/// finishes (or is dropped), with the bytes actually sent.
+#[tracing::instrument(name = "s3.get_file_stream", skip(client, bucket_name), fields(storage.system = "s3", collection_id = %collection_id, key = %key))]
+pub(crate) async fn get_file_stream(
     client: &Client,
     bucket_name: &str,
     collection_id: &str,
     key: &str,
-) -> Result<Vec<u8>> {
+    max_download_size_bytes: i64,
+) -> Result<FileStream> {
     let start = Instant::now();
     let full_key = format!("collections/{}/{}", collection_id, key);
 
@@ -396,42 +755,17 @@ pub(crate) async fn get_file(
         bucket = %bucket_name,
         collection_id = %collection_id,
         key = %key,
-        "Retrieving file from S3"
+        "Streaming file from S3"
     );
 
-    let result = client
+    let response = match client
         .get_object()
         .bucket(bucket_name)
         .key(&full_key)
         .send()
-        .await;
-
-    match result {
-        Ok(response) => {
-            let data = response.body.collect().await?;
-            let bytes = data.into_bytes().to_vec();
-            let file_size = bytes.len() as u64;
-
-            let duration = start.elapsed().as_secs_f64();
-            record_storage_operation("download", duration, Some(file_size), true);
-            semantic_explorer_core::observability::record_storage_download(
-                bucket_name,
-                duration,
-                Some(file_size),
-                true,
-            );
-
-            tracing::debug!(
-                bucket = %bucket_name,
-                collection_id = %collection_id,
-                key = %key,
-                size = file_size,
-                duration_ms = duration * 1000.0,
-                "Successfully retrieved file from S3"
-            );
-
-            Ok(bytes)
-        }
+        .await
+    {
+        Ok(response) => response,
         Err(e) => {
             let duration = start.elapsed().as_secs_f64();
             record_storage_operation("download", duration, None, false);
@@ -451,35 +785,11 @@ pub(crate) async fn get_file(
                 "Failed to retrieve file from S3. Check network connectivity, bucket existence, and file permissions."
             );
 
-            Err(e.into())
+            return Err(e.into());
         }
-    }
-}
-
-/// Get file with size validation to prevent OOM on large downloads
-/// Returns error if file exceeds the configured max_download_size_bytes
-#[tracing::instrument(name = "s3.get_file_with_size_check", skip(client, bucket_name), fields(storage.system = "s3", collection_id = %collection_id, key = %key))]
-pub(crate) async fn get_file_with_size_check(
-    client: &Client,
-    bucket_name: &str,
-    collection_id: &str,
-    key: &str,
-    max_download_size_bytes: i64,
-) -> Result<Vec<u8>> {
-    let start = Instant::now();
-    let full_key = format!("collections/{}/{}", collection_id, key);
-
-    // First, check file size using head_object
-    let head_result = client
-        .head_object()
-        .bucket(bucket_name)
-        .key(&full_key)
-        .send()
-        .await
-        .context("Failed to get file metadata")?;
-
-    let file_size = head_result.content_length().unwrap_or(0);
+    };
 
+    let file_size = response.content_length().unwrap_or(0);
     if file_size > max_download_size_bytes {
         let duration = start.elapsed().as_secs_f64();
         record_storage_operation("download", duration, None, false);
@@ -498,8 +808,103 @@ pub(crate) async fn get_file_with_size_check(
         );
     }
 
-    // Size is acceptable, proceed with download
-    get_file(client, bucket_name, collection_id, key).await
+    let content_length = response
+        .content_length()
+        .and_then(|len| u64::try_from(len).ok());
+    let body = futures_util::stream::unfold(response.body, |mut body| async move {
+        body.next()
+            .await
+            .map(|chunk| (chunk.map_err(anyhow::Error::from), body))
+    })
+    .boxed();
+
+    let bucket = bucket_name.to_string();
+    let collection_id = collection_id.to_string();
+    let key = key.to_string();
+    let body = MeteredStream::new(body, start, move |duration, bytes, success| {
+        record_storage_operation("download", duration, Some(bytes), success);
+        semantic_explorer_core::observability::record_storage_download(
+            &bucket,
+            duration,
+            Some(bytes),
+            success,
+        );
+        tracing::debug!(
+            bucket = %bucket,
+            collection_id = %collection_id,
+            key = %key,
+            size = bytes,
+            success,
+            duration_ms = duration * 1000.0,
+            "Finished streaming file from S3"
+        );
+    });
+
+    Ok(FileStream {
+        content_length,
+        body,
+    })
+}
+
+type StreamFinished = Box<dyn FnOnce(f64, u64, bool) + Send>;
+
+/// Byte stream wrapper that tallies bytes as they flow and reports the
+/// duration, byte count and outcome exactly once: when the stream ends,
+/// yields an error, or is dropped early (e.g. the client disconnected).
+pub(crate) struct MeteredStream<S> {
+    inner: S,
+    start: Instant,
+    bytes: u64,
+    on_finish: Option<StreamFinished>,
+}
+
+impl<S> MeteredStream<S> {
+    fn new(
+        inner: S,
+        start: Instant,
+        on_finish: impl FnOnce(f64, u64, bool) + Send + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            start,
+            bytes: 0,
+            on_finish: Some(Box::new(on_finish)),
+        }
+    }
+
+    fn finish(&mut self, success: bool) {
+        if let Some(on_finish) = self.on_finish.take() {
+            on_finish(self.start.elapsed().as_secs_f64(), self.bytes, success);
+        }
+    }
+}
+
+impl<S> Stream for MeteredStream<S>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.inner).poll_next(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(chunk))) => this.bytes += chunk.len() as u64,
+            Poll::Ready(Some(Err(_))) => this.finish(false),
+            Poll::Ready(None) => this.finish(true),
+            Poll::Pending => {}
+        }
+        poll
+    }
+}
+
+impl<S> Drop for MeteredStream<S> {
+    fn drop(&mut self) {
+        self.finish(false);
+    }
 }
 
 /// Delete file from collection using single-bucket architecture
@@ -655,14 +1060,18 @@ pub(crate) async fn count_collection_files(
 
 /// Copy files from one collection prefix to another within the same bucket
 /// Uses single-bucket architecture: S3_BUCKET_NAME/collections/{source_collection_id}/* -> S3_BUCKET_NAME/collections/{dest_collection_id}/*
+///
+/// Each object copy is retried on transient errors. With `continue_on_error`,
+/// objects that still fail are recorded in the summary instead of aborting the copy.
 pub(crate) async fn copy_collection_files(
     s3_client: &Client,
     bucket_name: &str,
     source_collection_id: i32,
     destination_collection_id: i32,
-) -> Result<usize> {
+    options: &CopyOptions,
+) -> Result<CopySummary> {
     let start = Instant::now();
-    let mut copied_count = 0;
+    let mut summary = CopySummary::default();
 
     let source_prefix = format!("collections/{}/", source_collection_id);
     let dest_prefix = format!("collections/{}/", destination_collection_id);
@@ -671,6 +1080,7 @@ pub(crate) async fn copy_collection_files(
         bucket = %bucket_name,
         source_prefix = %source_prefix,
         dest_prefix = %dest_prefix,
+        continue_on_error = options.continue_on_error,
         "Copying collection files within bucket"
     );
 
@@ -696,59 +1106,152 @@ pub(crate) async fn copy_collection_files(
             }
         };
 
-        for obj in output.contents() {
-            let source_key = obj.key().unwrap_or_default();
-
+        let keys = output.contents().iter().map(|obj| {
+            let source_key = obj.key().unwrap_or_default().to_string();
             // Extract the filename from the source key (after the prefix)
             let filename = source_key
                 .strip_prefix(&source_prefix)
-                .unwrap_or(source_key);
+                .unwrap_or(&source_key);
             let dest_key = format!("{}{}", dest_prefix, filename);
-            let copy_source = format!("{}/{}", bucket_name, source_key);
+            (source_key, dest_key)
+        });
+
+        copy_objects(
+            keys,
+            options,
+            &mut summary,
+            |source_key, dest_key| async move {
+                let request = s3_client
+                    .copy_object()
+                    .copy_source(format!("{}/{}", bucket_name, source_key))
+                    .bucket(bucket_name)
+                    .key(dest_key);
+                copy_object_with_sse(request, server_side_encryption())
+                    .send()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| S3RequestError::from_sdk_error(&e))
+            },
+        )
+        .await?;
+    }
 
-            // Copy the object within the same bucket
-            match s3_client
-                .copy_object()
-                .copy_source(&copy_source)
-                .bucket(bucket_name)
-                .key(&dest_key)
-                .send()
-                .await
-            {
-                Ok(_) => {
-                    copied_count += 1;
-                    tracing::debug!(
-                        source_key = %source_key,
-                        dest_key = %dest_key,
-                        "Successfully copied file"
-                    );
-                }
-                Err(e) => {
-                    tracing::error!(
-                        source_key = %source_key,
-                        dest_key = %dest_key,
-                        error = %e,
-                        "Failed to copy file"
-                    );
-                    return Err(e.into());
-                }
+    let duration = start.elapsed().as_secs_f64();
+    record_storage_operation("copy_collection", duration, None, summary.failed.is_empty());
+
+    if summary.failed.is_empty() {
+        tracing::info!(
+            bucket = %bucket_name,
+            source_collection_id = %source_collection_id,
+            dest_collection_id = %destination_collection_id,
+            copied_count = summary.copied,
+            duration_ms = duration * 1000.0,
+            "Successfully copied all files from source to destination collection prefix"
+        );
+    } else {
+        tracing::warn!(
+            bucket = %bucket_name,
+            source_collection_id = %source_collection_id,
+            dest_collection_id = %destination_collection_id,
+            copied_count = summary.copied,
+            failed_count = summary.failed_count(),
+            first_failed_key = %summary.failed[0].source_key,
+            first_error = %summary.failed[0].error,
+            duration_ms = duration * 1000.0,
+            "Copied collection files with failures"
+        );
+    }
+
+    Ok(summary)
+}
+
+/// Error from a single S3 request, classified for retry
+#[derive(Debug)]
+struct S3RequestError {
+    message: String,
+    transient: bool,
+}
+
+impl S3RequestError {
+    fn from_sdk_error<E: Debug + Error + 'static>(err: &SdkError<E>) -> Self {
+        let transient = match err {
+            SdkError::TimeoutError(_)
+            | SdkError::DispatchFailure(_)
+            | SdkError::ResponseError(_) => true,
+            SdkError::ServiceError(service_err) => {
+                let status = service_err.raw().status().as_u16();
+                status == 429 || status >= 500
             }
+            _ => false,
+        };
+        Self {
+            message: format_s3_error(err),
+            transient,
         }
     }
+}
 
-    let duration = start.elapsed().as_secs_f64();
-    record_storage_operation("copy_collection", duration, None, true);
+impl std::fmt::Display for S3RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
 
-    tracing::info!(
-        bucket = %bucket_name,
-        source_collection_id = %source_collection_id,
-        dest_collection_id = %destination_collection_id,
-        copied_count = copied_count,
-        duration_ms = duration * 1000.0,
-        "Successfully copied all files from source to destination collection prefix"
-    );
+impl Error for S3RequestError {}
 
-    Ok(copied_count)
+impl RetryableError for S3RequestError {
+    fn is_retryable(&self) -> bool {
+        self.transient
+    }
+}
+
+/// Copy each `(source_key, dest_key)` pair with `copy`, retrying transient
+/// failures. Failures are recorded in `summary`; unless `continue_on_error`
+/// is set, the first one is also returned as an error.
+async fn copy_objects<F, Fut>(
+    keys: impl IntoIterator<Item = (String, String)>,
+    options: &CopyOptions,
+    summary: &mut CopySummary,
+    mut copy: F,
+) -> Result<()>
+where
+    F: FnMut(String, String) -> Fut,
+    Fut: Future<Output = std::result::Result<(), S3RequestError>>,
+{
+    for (source_key, dest_key) in keys {
+        let result = retry_with_policy(&options.retry_policy, "s3_copy_object", || {
+            copy(source_key.clone(), dest_key.clone())
+        })
+        .await;
+
+        match result {
+            Ok(()) => {
+                summary.copied += 1;
+                tracing::debug!(
+                    source_key = %source_key,
+                    dest_key = %dest_key,
+                    "Successfully copied file"
+                );
+            }
+            Err(e) => {
+                tracing::error!(
+                    source_key = %source_key,
+                    dest_key = %dest_key,
+                    error = %e,
+                    "Failed to copy file"
+                );
+                summary.failed.push(CopyFailure {
+                    source_key: source_key.clone(),
+                    error: e.to_string(),
+                });
+                if !options.continue_on_error {
+                    bail!("Failed to copy {}: {}", source_key, e);
+                }
+            }
+        }
+    }
+
+    Ok(())
 }
 
 /// Empty all files in a collection using single-bucket architecture
@@ -936,3 +1439,374 @@ fn format_file_size(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semantic_explorer_core::retry::RetryPolicy;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn options(continue_on_error: bool) -> CopyOptions {
+        CopyOptions {
+            retry_policy: RetryPolicy {
+                max_attempts: 2,
+                initial_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+                backoff_multiplier: 1.0,
+                jitter_factor: 0.0,
+            },
+            continue_on_error,
+        }
+    }
+
+    fn keys(names: &[&str]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|n| (format!("collections/1/{n}"), format!("collections/2/{n}")))
+            .collect()
+    }
+
+    /// Mock S3 copy: `flaky.txt` fails once with a 503, `broken.txt` always
+    /// fails with a non-retryable error, everything else succeeds.
+    fn mock_copy(
+        attempts: &mut HashMap<String, u32>,
+        source_key: String,
+    ) -> std::future::Ready<std::result::Result<(), S3RequestError>> {
+        let attempt = attempts.entry(source_key.clone()).or_default();
+        *attempt += 1;
+        let result = if source_key.ends_with("flaky.txt") && *attempt == 1 {
+            Err(S3RequestError {
+                message: "HTTP 503 - SlowDown".to_string(),
+                transient: true,
+            })
+        } else if source_key.ends_with("broken.txt") {
+            Err(S3RequestError {
+                message: "HTTP 403 - AccessDenied".to_string(),
+                transient: false,
+            })
+        } else {
+            Ok(())
+        };
+        std::future::ready(result)
+    }
+
+    fn object(key: &str) -> aws_sdk_s3::types::Object {
+        aws_sdk_s3::types::Object::builder()
+            .key(key)
+            .size(1)
+            .build()
+    }
+
+    fn common_prefix(prefix: &str) -> aws_sdk_s3::types::CommonPrefix {
+        aws_sdk_s3::types::CommonPrefix::builder()
+            .prefix(prefix)
+            .build()
+    }
+
+    #[test]
+    fn test_listing_separates_folders_and_files() {
+        // What S3 returns for prefix `collections/7/` and delimiter `/` over
+        // readme.md, logs/app.log, logs/2024/jan.log and src/main.rs
+        let entries = listing_entries(
+            &[object("collections/7/"), object("collections/7/readme.md")],
+            &[
+                common_prefix("collections/7/src/"),
+                common_prefix("collections/7/logs/"),
+            ],
+            "collections/7/",
+            "collections/7/",
+        );
+        let keys: Vec<(&str, bool)> = entries
+            .iter()
+            .map(|e| (e.key(), matches!(e, ListingEntry::Folder(_))))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![("logs/", true), ("readme.md", false), ("src/", true)]
+        );
+
+        // One level down, inside `logs/`
+        let entries = listing_entries(
+            &[
+                object("collections/7/logs/"),
+                object("collections/7/logs/app.log"),
+            ],
+            &[common_prefix("collections/7/logs/2024/")],
+            "collections/7/",
+            "collections/7/logs/",
+        );
+        let keys: Vec<&str> = entries.iter().map(ListingEntry::key).collect();
+        assert_eq!(keys, vec!["logs/2024/", "logs/app.log"]);
+        let ListingEntry::File(file) = &entries[1] else {
+            panic!("expected a file entry");
+        };
+        assert_eq!(file.content_type, None);
+    }
+
+    #[test]
+    fn test_folder_cursor_skips_folder_contents() {
+        let folder = ListingEntry::Folder("logs/".to_string());
+        let cursor = folder.cursor("collections/7/");
+        assert!(cursor.as_str() > "collections/7/logs/2024/zzz.log");
+        assert!(cursor.as_str() < "collections/7/readme.md");
+    }
+
+    #[test]
+    fn test_collection_listing_pages_like_bucket_listing() {
+        let entries = listing_entries(
+            &[object("collections/7/a.txt"), object("collections/7/c.txt")],
+            &[common_prefix("collections/7/b/")],
+            "collections/7/",
+            "collections/7/",
+        );
+        let (page, next) =
+            semantic_explorer_core::storage::split_page(entries, 2, |e| e.cursor("collections/7/"));
+        let keys: Vec<&str> = page.iter().map(ListingEntry::key).collect();
+        assert_eq!(keys, vec!["a.txt", "b/"]);
+        assert_eq!(next, Some(format!("collections/7/b/{}", char::MAX)));
+    }
+
+    type Finished = std::sync::Arc<std::sync::Mutex<Vec<(u64, bool)>>>;
+
+    fn metered(
+        chunks: Vec<Result<Bytes>>,
+    ) -> (MeteredStream<BoxStream<'static, Result<Bytes>>>, Finished) {
+        let finished = Finished::default();
+        let recorded = finished.clone();
+        let stream = MeteredStream::new(
+            futures_util::stream::iter(chunks).boxed(),
+            Instant::now(),
+            move |_, bytes, success| recorded.lock().unwrap().push((bytes, success)),
+        );
+        (stream, finished)
+    }
+
+    #[tokio::test]
+    async fn test_metered_stream_reports_bytes_once_at_completion() {
+        let (mut stream, finished) = metered(vec![
+            Ok(Bytes::from_static(b"hello ")),
+            Ok(Bytes::from_static(b"world")),
+        ]);
+        assert!(stream.next().await.is_some());
+        assert!(finished.lock().unwrap().is_empty());
+        while stream.next().await.is_some() {}
+        drop(stream);
+        assert_eq!(*finished.lock().unwrap(), vec![(11, true)]);
+    }
+
+    #[tokio::test]
+    async fn test_metered_stream_reports_errors_and_early_drops_as_failures() {
+        let (stream, finished) = metered(vec![
+            Ok(Bytes::from_static(b"abc")),
+            Err(anyhow::anyhow!("connection reset")),
+        ]);
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(*finished.lock().unwrap(), vec![(3, false)]);
+
+        let (mut stream, finished) = metered(vec![
+            Ok(Bytes::from_static(b"abc")),
+            Ok(Bytes::from_static(b"def")),
+        ]);
+        stream.next().await;
+        drop(stream);
+        assert_eq!(*finished.lock().unwrap(), vec![(3, false)]);
+    }
+
+    #[tokio::test]
+    async fn test_copy_retries_transient_failures_and_records_permanent_ones() {
+        let mut attempts = HashMap::new();
+        let mut summary = CopySummary::default();
+
+        copy_objects(
+            keys(&["a.txt", "flaky.txt", "broken.txt", "b.txt"]),
+            &options(true),
+            &mut summary,
+            |source_key, _| mock_copy(&mut attempts, source_key),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.copied, 3);
+        assert_eq!(summary.failed_count(), 1);
+        assert_eq!(summary.failed[0].source_key, "collections/1/broken.txt");
+        assert!(summary.failed[0].error.contains("AccessDenied"));
+        assert_eq!(attempts["collections/1/flaky.txt"], 2);
+        // Non-retryable errors are not retried
+        assert_eq!(attempts["collections/1/broken.txt"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_copy_aborts_on_first_failure_by_default() {
+        let mut attempts = HashMap::new();
+        let mut summary = CopySummary::default();
+
+        let result = copy_objects(
+            keys(&["a.txt", "broken.txt", "b.txt"]),
+            &options(false),
+            &mut summary,
+            |source_key, _| mock_copy(&mut attempts, source_key),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.failed_count(), 1);
+        assert!(!attempts.contains_key("collections/1/b.txt"));
+    }
+
+    fn upload_config(part_size: usize, concurrency: usize, max_size: u64) -> StreamedUploadConfig {
+        StreamedUploadConfig {
+            part_size,
+            concurrency,
+            max_size,
+        }
+    }
+
+    fn body(
+        chunks: &[&'static [u8]],
+    ) -> impl Stream<Item = std::result::Result<Bytes, std::io::Error>> + Unpin {
+        futures_util::stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(Bytes::from_static(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Mock part upload that records what it received. Earlier parts take
+    /// longer, so they complete out of order.
+    fn mock_upload_part(
+        uploaded: Arc<Mutex<Vec<(i32, Bytes)>>>,
+        max_in_flight: Arc<AtomicUsize>,
+    ) -> impl Fn(
+        i32,
+        Bytes,
+    ) -> Pin<
+        Box<dyn Future<Output = std::result::Result<CompletedPart, S3RequestError>> + Send>,
+    > {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        move |part_number, data| {
+            let uploaded = uploaded.clone();
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            Box::pin(async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10 / part_number as u64)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                uploaded.lock().unwrap().push((part_number, data));
+                Ok(CompletedPart::builder().part_number(part_number).build())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_parts_splits_body_into_ordered_parts() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let config = upload_config(5, 2, 1024);
+        let mut reader = PartReader::new(body(&[b"abc", b"defg", b"hijkl", b"mn"]), &config);
+
+        let first = reader.next_part().await.unwrap().unwrap();
+        assert_eq!(&first[..], b"abcde");
+        let parts = upload_parts(
+            &mut reader,
+            first,
+            config.concurrency,
+            mock_upload_part(uploaded.clone(), max_in_flight.clone()),
+        )
+        .await
+        .unwrap();
+
+        let part_numbers: Vec<_> = parts.iter().filter_map(|p| p.part_number()).collect();
+        assert_eq!(part_numbers, vec![1, 2, 3]);
+        let mut uploaded = uploaded.lock().unwrap().clone();
+        uploaded.sort_by_key(|(n, _)| *n);
+        let data: Vec<_> = uploaded.iter().map(|(_, d)| &d[..]).collect();
+        assert_eq!(data, vec![&b"abcde"[..], &b"fghij"[..], &b"klmn"[..]]);
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 2);
+        assert_eq!(reader.received, 14);
+    }
+
+    #[tokio::test]
+    async fn test_upload_parts_bounds_concurrency() {
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let config = upload_config(1, 3, 1024);
+        let mut reader = PartReader::new(body(&[b"abcdefghij"]), &config);
+
+        let first = reader.next_part().await.unwrap().unwrap();
+        let parts = upload_parts(
+            &mut reader,
+            first,
+            config.concurrency,
+            mock_upload_part(Arc::new(Mutex::new(Vec::new())), max_in_flight.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(parts.len(), 10);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_small_body_fits_in_first_part() {
+        let config = upload_config(64, 2, 1024);
+        let mut reader = PartReader::new(body(&[b"hello ", b"world"]), &config);
+
+        let first = reader.next_part().await.unwrap().unwrap();
+        assert!(first.len() < config.part_size);
+        assert_eq!(&first[..], b"hello world");
+        assert!(reader.next_part().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upload_stops_past_max_size() {
+        let config = upload_config(4, 2, 10);
+        let mut reader = PartReader::new(body(&[b"abcd", b"efgh", b"ijkl"]), &config);
+
+        let first = reader.next_part().await.unwrap().unwrap();
+        let error = upload_parts(
+            &mut reader,
+            first,
+            config.concurrency,
+            mock_upload_part(
+                Arc::new(Mutex::new(Vec::new())),
+                Arc::new(AtomicUsize::new(0)),
+            ),
+        )
+        .await
+        .unwrap_err();
+
+        let too_large = error.downcast_ref::<UploadTooLarge>().unwrap();
+        assert_eq!(too_large.max_size, 10);
+    }
+
+    #[tokio::test]
+    async fn test_upload_fails_on_body_error() {
+        let config = upload_config(4, 2, 1024);
+        let body = futures_util::stream::iter(vec![
+            Ok(Bytes::from_static(b"abcd")),
+            Err(std::io::Error::other("connection reset")),
+        ]);
+        let mut reader = PartReader::new(body, &config);
+
+        let first = reader.next_part().await.unwrap().unwrap();
+        let error = upload_parts(
+            &mut reader,
+            first,
+            config.concurrency,
+            mock_upload_part(
+                Arc::new(Mutex::new(Vec::new())),
+                Arc::new(AtomicUsize::new(0)),
+            ),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(error.to_string().contains("connection reset"));
+    }
+}
@@ -1,14 +1,42 @@
-use aws_sdk_s3::primitives::ByteStream;
+use chrono::{DateTime, Utc};
+use semantic_explorer_core::config::S3Config;
+use semantic_explorer_core::retry::RetryPolicy;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use utoipa::ToSchema;
 
-#[derive(Debug)]
-pub(crate) struct DocumentUpload {
-    pub(crate) collection_id: String,
-    pub(crate) name: String,
-    pub(crate) content: ByteStream,
-    pub(crate) mime_type: String,
-    pub(crate) size: u64,
+/// How a collection upload is streamed to the bucket
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StreamedUploadConfig {
+    /// Size of each multipart upload part; smaller files are sent whole
+    pub(crate) part_size: usize,
+    /// Parts uploaded at once, which also bounds the parts held in memory
+    pub(crate) concurrency: usize,
+    /// Uploads growing past this many bytes are aborted
+    pub(crate) max_size: u64,
+}
+
+impl StreamedUploadConfig {
+    pub(crate) fn from_s3_config(config: &S3Config) -> Self {
+        Self {
+            part_size: config.multipart_part_size,
+            concurrency: config.multipart_concurrency,
+            max_size: config.max_upload_size_bytes.max(0) as u64,
+        }
+    }
+}
+
+/// Time-limited URL for a client to upload or download an object directly.
+/// `headers` are part of the signature and must be sent unchanged.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct PresignedUrl {
+    pub(crate) url: String,
+    pub(crate) method: String,
+    pub(crate) headers: BTreeMap<String, String>,
+    /// Key of the object inside the collection
+    pub(crate) key: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub(crate) expires_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, ToSchema)]
@@ -19,9 +47,15 @@ pub(crate) struct CollectionFile {
     pub(crate) content_type: Option<String>,
 }
 
+/// One page of collection files. `continuation_token` is present exactly
+/// when `has_more` is set; `total_count` is only reported on the first page
+/// of an unfiltered listing.
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub(crate) struct PaginatedFiles {
     pub(crate) files: Vec<CollectionFile>,
+    /// Sub-folders at this level, when browsing with a delimiter
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) folders: Vec<String>,
     pub(crate) page: i32,
     pub(crate) page_size: i32,
     pub(crate) has_more: bool,
@@ -29,8 +63,39 @@ pub(crate) struct PaginatedFiles {
     pub(crate) total_count: Option<i64>,
 }
 
-#[derive(Debug)]
-pub(crate) struct S3FileList {
-    pub(crate) files: Vec<CollectionFile>,
-    pub(crate) continuation_token: Option<String>,
+/// How `copy_collection_files` handles objects that fail to copy
+#[derive(Debug, Clone)]
+pub(crate) struct CopyOptions {
+    /// Backoff applied to each object's copy on transient S3 errors
+    pub(crate) retry_policy: RetryPolicy,
+    /// Record failed objects and keep going instead of aborting on the first one
+    pub(crate) continue_on_error: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            retry_policy: semantic_explorer_core::retry::s3_retry_policy(),
+            continue_on_error: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CopyFailure {
+    pub(crate) source_key: String,
+    pub(crate) error: String,
+}
+
+/// Outcome of copying a collection's objects
+#[derive(Debug, Default)]
+pub(crate) struct CopySummary {
+    pub(crate) copied: usize,
+    pub(crate) failed: Vec<CopyFailure>,
+}
+
+impl CopySummary {
+    pub(crate) fn failed_count(&self) -> usize {
+        self.failed.len()
+    }
 }
@@ -31,7 +31,7 @@ pub struct ValkeyClients {
 /// The `ConnectionManager` provides automatic reconnection on failures
 /// and is cheaply cloneable (backed by `Arc`).
 pub(crate) async fn initialize_client(config: &ValkeyConfig) -> Result<ConnectionManager> {
-    let url = build_url(&config.url, config.password.as_deref(), config.tls_enabled);
+    let url = config.connection_url();
     let client = Client::open(url.as_str())
         .with_context(|| format!("Failed to create Valkey client for URL: {}", config.url))?;
 
@@ -53,11 +53,7 @@ pub(crate) async fn initialize_client(config: &ValkeyConfig) -> Result<Connectio
 /// Initialize the Valkey read client (read-replica) connection manager.
 /// Falls back to the primary URL if `VALKEY_READ_URL` is not set separately.
 pub(crate) async fn initialize_read_client(config: &ValkeyConfig) -> Result<ConnectionManager> {
-    let url = build_url(
-        &config.read_url,
-        config.password.as_deref(),
-        config.tls_enabled,
-    );
+    let url = config.read_connection_url();
     let client = Client::open(url.as_str()).with_context(|| {
         format!(
             "Failed to create Valkey read client for URL: {}",
@@ -79,20 +75,6 @@ pub(crate) async fn initialize_read_client(config: &ValkeyConfig) -> Result<Conn
     Ok(manager)
 }
 
-/// Build a redis:// or rediss:// URL with optional password.
-fn build_url(base_url: &str, password: Option<&str>, tls: bool) -> String {
-    let scheme = if tls { "rediss" } else { "redis" };
-    // If the URL already has a scheme, strip it so we can reconstruct
-    let host_port = base_url
-        .trim_start_matches("redis://")
-        .trim_start_matches("rediss://");
-
-    match password {
-        Some(pw) => format!("{scheme}://default:{pw}@{host_port}"),
-        None => format!("{scheme}://{host_port}"),
-    }
-}
-
 // ─────────────────────────────────────────────────────────────────────────────
 // Cache helpers — all operations are fallible with graceful degradation
 // ─────────────────────────────────────────────────────────────────────────────
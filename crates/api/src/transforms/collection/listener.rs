@@ -14,7 +14,7 @@ use crate::storage::postgres::{collection_transforms, dataset_transforms, datase
 use semantic_explorer_core::models::CollectionTransformResult;
 use semantic_explorer_core::storage::{delete_file_by_key, get_file_with_size_check};
 
-use super::super::listeners::publish_transform_status;
+use super::super::listeners::{KeyedExecutor, publish_transform_status};
 
 /// Context for transform result handling
 #[derive(Clone)]
@@ -23,6 +23,10 @@ pub(crate) struct CollectionListenerContext {
     pub s3_client: S3Client,
     pub s3_bucket_name: String,
     pub nats_client: NatsClient,
+    /// Largest chunks file downloaded into memory
+    pub max_download_size_bytes: i64,
+    /// Results handled at once; results for one transform are applied in order
+    pub max_concurrency: usize,
 }
 
 /// Start the collection transform result listener
@@ -74,6 +78,7 @@ pub(crate) fn start(context: CollectionListenerContext) {
             consumer_name
         );
 
+        let executor = KeyedExecutor::new(context.max_concurrency);
         let mut messages = match consumer.messages().await {
             Ok(m) => m,
             Err(e) => {
@@ -94,10 +99,15 @@ pub(crate) fn start(context: CollectionListenerContext) {
             info!("Received file result message on subject: {}", msg.subject);
             match serde_json::from_slice::<CollectionTransformResult>(&msg.payload) {
                 Ok(result) => {
-                    handle_result(result, &context).await;
-                    if let Err(e) = msg.ack().await {
-                        error!("Failed to acknowledge message: {}", e);
-                    }
+                    let context = context.clone();
+                    executor
+                        .submit(result.collection_transform_id, async move {
+                            handle_result(result, &context).await;
+                            if let Err(e) = msg.ack().await {
+                                error!("Failed to acknowledge message: {}", e);
+                            }
+                        })
+                        .await;
                 }
                 Err(e) => {
                     error!("Failed to deserialize file result: {}", e);
@@ -276,18 +286,23 @@ async fn handle_result(result: CollectionTransformResult, ctx: &CollectionListen
         result.collection_transform_id, result.chunks_file_key
     );
 
-    let chunks_content =
-        match get_file_with_size_check(&ctx.s3_client, &ctx.s3_bucket_name, &full_chunks_key).await
-        {
-            Ok(c) => c,
-            Err(e) => {
-                error!(
-                    "Failed to download chunks for {} from {}: {}",
-                    result.source_file_key, full_chunks_key, e
-                );
-                return;
-            }
-        };
+    let chunks_content = match get_file_with_size_check(
+        &ctx.s3_client,
+        &ctx.s3_bucket_name,
+        &full_chunks_key,
+        ctx.max_download_size_bytes,
+    )
+    .await
+    {
+        Ok(c) => c,
+        Err(e) => {
+            error!(
+                "Failed to download chunks for {} from {}: {}",
+                result.source_file_key, full_chunks_key, e
+            );
+            return;
+        }
+    };
 
     let chunks: Vec<ChunkWithMetadata> = match serde_json::from_slice(&chunks_content) {
         Ok(c) => c,
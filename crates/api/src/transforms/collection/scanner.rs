@@ -210,6 +210,86 @@ pub(crate) async fn dispatch_upload_jobs(
     }
 }
 
+/// Pending presigned uploads checked per scan
+const PENDING_UPLOADS_PER_SCAN: i64 = 500;
+
+/// What a scan does with a pending presigned upload
+#[derive(Debug, PartialEq, Eq)]
+enum PendingUploadAction {
+    /// The object arrived: dispatch its jobs and forget the key
+    Dispatch,
+    /// Not uploaded yet, but the URL is still valid
+    Wait,
+    /// The URL expired unused: forget the key
+    Forget,
+}
+
+fn pending_upload_action(
+    uploaded: bool,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> PendingUploadAction {
+    match (uploaded, expires_at > now) {
+        (true, _) => PendingUploadAction::Dispatch,
+        (false, true) => PendingUploadAction::Wait,
+        (false, false) => PendingUploadAction::Forget,
+    }
+}
+
+/// Dispatch jobs for files uploaded through presigned URLs. Each pending key
+/// is checked in S3: uploaded files are dispatched like a regular upload,
+/// and keys whose URL expired without an upload are forgotten.
+#[tracing::instrument(name = "process_pending_uploads", skip_all)]
+pub(crate) async fn process_pending_uploads(
+    pool: &Pool<Postgres>,
+    nats: &NatsClient,
+    s3: &S3Client,
+    s3_bucket_name: &str,
+    encryption: &EncryptionService,
+) -> Result<()> {
+    let pending = collections::get_pending_uploads(pool, PENDING_UPLOADS_PER_SCAN).await?;
+    let mut dispatched = 0;
+
+    for upload in pending {
+        let key = format!("collections/{}/{}", upload.collection_id, upload.file_key);
+        let uploaded = match s3::file_exists(s3, s3_bucket_name, &key).await {
+            Ok(uploaded) => uploaded,
+            Err(e) => {
+                warn!(key = %key, error = %e, "Failed to check pending upload");
+                continue;
+            }
+        };
+        match pending_upload_action(uploaded, upload.expires_at, chrono::Utc::now()) {
+            PendingUploadAction::Dispatch => {
+                if let Err(e) =
+                    collections::touch_collection_updated_at(pool, upload.collection_id).await
+                {
+                    warn!(collection_id = upload.collection_id, error = %e, "Failed to touch updated_at after presigned upload");
+                }
+                dispatch_upload_jobs(
+                    pool,
+                    nats,
+                    s3_bucket_name,
+                    upload.collection_id,
+                    &upload.owner_id,
+                    std::slice::from_ref(&upload.file_key),
+                    encryption,
+                )
+                .await;
+                dispatched += 1;
+            }
+            PendingUploadAction::Wait => continue,
+            PendingUploadAction::Forget => {}
+        }
+        collections::delete_pending_upload(pool, upload.collection_id, &upload.file_key).await?;
+    }
+
+    if dispatched > 0 {
+        info!("Dispatched jobs for {} presigned uploads", dispatched);
+    }
+    Ok(())
+}
+
 #[tracing::instrument(name = "scan_active_collection_transforms", skip_all)]
 pub(crate) async fn scan_active_collection_transforms(
     pool: &Pool<Postgres>,
@@ -218,6 +298,10 @@ pub(crate) async fn scan_active_collection_transforms(
     s3_bucket_name: &str,
     encryption: &EncryptionService,
 ) -> Result<()> {
+    if let Err(e) = process_pending_uploads(pool, nats, s3, s3_bucket_name, encryption).await {
+        error!("Failed to process pending presigned uploads: {}", e);
+    }
+
     let transforms = get_active_collection_transforms_privileged(pool).await?;
     info!("Scanning {} active collection transforms", transforms.len());
 
@@ -359,6 +443,8 @@ pub(crate) async fn backfill_collection_transform(
             &collection.collection_id.to_string(),
             100,
             continuation_token.as_deref(),
+            None,
+            None,
         )
         .await?;
         if files.files.is_empty() {
@@ -434,3 +520,35 @@ pub async fn trigger_collection_transform_scan(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_pending_upload_pickup() {
+        let now = Utc::now();
+        let valid = now + Duration::minutes(5);
+        let expired = now - Duration::minutes(5);
+
+        // An arrived object is dispatched, even if its URL has since expired
+        assert_eq!(
+            pending_upload_action(true, valid, now),
+            PendingUploadAction::Dispatch
+        );
+        assert_eq!(
+            pending_upload_action(true, expired, now),
+            PendingUploadAction::Dispatch
+        );
+        // A missing object is waited for until the URL expires
+        assert_eq!(
+            pending_upload_action(false, valid, now),
+            PendingUploadAction::Wait
+        );
+        assert_eq!(
+            pending_upload_action(false, expired, now),
+            PendingUploadAction::Forget
+        );
+    }
+}
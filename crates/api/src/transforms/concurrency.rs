@@ -0,0 +1,189 @@
+//! Per-user cap on concurrently running transforms.
+//!
+//! Triggering a transform claims one of the owner's slots
+//! (`MAX_CONCURRENT_TRANSFORMS_PER_USER`). A transform holds its slot while
+//! it has batches or files in flight, or, right after it is triggered and
+//! before its scan has recorded any work, through a row in
+//! `transform_trigger_claims`. Triggers past the cap are rejected with 429.
+
+use actix_web::HttpResponse;
+use semantic_explorer_core::config::WorkerConfig;
+use sqlx::{Pool, Postgres};
+use tracing::{error, warn};
+
+use crate::errors::too_many_requests;
+use crate::storage::postgres::transform_slots;
+
+/// Kind of transform holding a slot, as stored in `transform_type` columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TransformKind {
+    Collection,
+    Dataset,
+    Visualization,
+}
+
+impl TransformKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            TransformKind::Collection => "collection",
+            TransformKind::Dataset => "dataset",
+            TransformKind::Visualization => "visualization",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "collection" => Some(TransformKind::Collection),
+            "dataset" => Some(TransformKind::Dataset),
+            "visualization" => Some(TransformKind::Visualization),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of asking for a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SlotClaim {
+    /// The transform got a free slot
+    Claimed,
+    /// The transform is already running and keeps its slot
+    AlreadyRunning,
+    /// Every slot is taken
+    Full { running: usize, limit: usize },
+}
+
+impl SlotClaim {
+    pub(crate) fn is_admitted(self) -> bool {
+        !matches!(self, SlotClaim::Full { .. })
+    }
+
+    /// Message for a rejected trigger
+    pub(crate) fn rejection_message(self) -> Option<String> {
+        match self {
+            SlotClaim::Full { running, limit } => Some(format!(
+                "You already have {running} transforms running (limit {limit}). \
+                 Wait for one to finish before triggering another."
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Decide whether `(kind, id)` may start given the owner's running
+/// transforms. A `limit` of 0 means no limit; re-triggering a transform that
+/// is already running never takes another slot.
+pub(crate) fn admit(
+    running: &[(TransformKind, i32)],
+    kind: TransformKind,
+    id: i32,
+    limit: usize,
+) -> SlotClaim {
+    if running.contains(&(kind, id)) {
+        return SlotClaim::AlreadyRunning;
+    }
+    if limit == 0 || running.len() < limit {
+        SlotClaim::Claimed
+    } else {
+        SlotClaim::Full {
+            running: running.len(),
+            limit,
+        }
+    }
+}
+
+/// Take a slot for a trigger handler, or the response to return instead
+pub(crate) async fn reserve_slot(
+    pool: &Pool<Postgres>,
+    worker_config: &WorkerConfig,
+    owner_id: &str,
+    kind: TransformKind,
+    transform_id: i32,
+) -> Result<(), HttpResponse> {
+    let claim = transform_slots::claim_transform_slot(
+        pool,
+        owner_id,
+        kind,
+        transform_id,
+        worker_config.max_concurrent_transforms_per_user,
+        worker_config.transform_trigger_claim_ttl,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to claim a transform slot: {}", e);
+        HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to check running transforms: {}", e)
+        }))
+    })?;
+
+    match claim.rejection_message() {
+        Some(message) => {
+            warn!(
+                owner_id = %owner_id,
+                transform_type = kind.as_str(),
+                transform_id,
+                "Transform trigger rejected: concurrency limit reached"
+            );
+            Err(too_many_requests(message))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_past_cap_is_rejected_until_a_slot_frees() {
+        let mut running = vec![(TransformKind::Dataset, 1), (TransformKind::Collection, 2)];
+
+        let claim = admit(&running, TransformKind::Visualization, 3, 2);
+        assert_eq!(
+            claim,
+            SlotClaim::Full {
+                running: 2,
+                limit: 2
+            }
+        );
+        assert!(!claim.is_admitted());
+        assert!(claim.rejection_message().unwrap().contains("limit 2"));
+
+        // The collection transform finishes
+        running.retain(|&(kind, _)| kind != TransformKind::Collection);
+        let claim = admit(&running, TransformKind::Visualization, 3, 2);
+        assert_eq!(claim, SlotClaim::Claimed);
+        assert_eq!(claim.rejection_message(), None);
+    }
+
+    #[test]
+    fn test_running_transform_can_be_retriggered_at_cap() {
+        let running = vec![(TransformKind::Dataset, 1)];
+        assert_eq!(
+            admit(&running, TransformKind::Dataset, 1, 1),
+            SlotClaim::AlreadyRunning
+        );
+        // Same id, different kind is a different transform
+        assert!(!admit(&running, TransformKind::Collection, 1, 1).is_admitted());
+    }
+
+    #[test]
+    fn test_zero_limit_is_unlimited() {
+        let running: Vec<_> = (0..100).map(|id| (TransformKind::Dataset, id)).collect();
+        assert_eq!(
+            admit(&running, TransformKind::Dataset, 100, 0),
+            SlotClaim::Claimed
+        );
+    }
+
+    #[test]
+    fn test_kind_round_trips() {
+        for kind in [
+            TransformKind::Collection,
+            TransformKind::Dataset,
+            TransformKind::Visualization,
+        ] {
+            assert_eq!(TransformKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(TransformKind::parse("chat"), None);
+    }
+}
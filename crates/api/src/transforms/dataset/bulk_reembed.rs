@@ -0,0 +1,387 @@
+//! Bulk re-embeds: re-embedding many datasets with one embedder, e.g. after
+//! upgrading the embedder's model.
+//!
+//! A run creates one dataset transform per dataset, disabled and queued in
+//! request order. Each queued transform is started (pinned, enabled and
+//! triggered) once its owner has a free concurrency slot, so a run never
+//! takes more than `MAX_CONCURRENT_TRANSFORMS_PER_USER` slots. The rest are
+//! started by later reconciliation passes as running transforms finish.
+
+use std::collections::HashSet;
+use std::future::Future;
+
+use anyhow::Result;
+use async_nats::Client as NatsClient;
+use semantic_explorer_core::config::WorkerConfig;
+use semantic_explorer_core::encryption::EncryptionService;
+use sqlx::{Pool, Postgres};
+use tracing::{error, info};
+
+use crate::storage::postgres::bulk_reembeds::{self, BulkReembed, BulkReembedTransform};
+use crate::storage::postgres::{dataset_transforms, transform_slots};
+use crate::transforms::concurrency::{SlotClaim, TransformKind};
+use crate::transforms::dataset::models::{
+    BulkReembedDatasetProgress, BulkReembedProgress, DatasetTransformStats,
+};
+use crate::transforms::dataset::pinning;
+
+/// Most datasets one run may re-embed
+pub const MAX_BULK_REEMBED_DATASETS: usize = 100;
+
+/// Validate the dataset ids of a bulk re-embed request, dropping repeats
+/// while keeping the request order
+pub fn validate_dataset_ids(dataset_ids: &[i32]) -> Result<Vec<i32>, String> {
+    if dataset_ids.is_empty() {
+        return Err("At least one dataset must be specified".to_string());
+    }
+    let mut seen = HashSet::new();
+    let unique: Vec<i32> = dataset_ids
+        .iter()
+        .copied()
+        .filter(|id| seen.insert(*id))
+        .collect();
+    if unique.len() > MAX_BULK_REEMBED_DATASETS {
+        return Err(format!(
+            "At most {MAX_BULK_REEMBED_DATASETS} datasets can be re-embedded at once, got {}",
+            unique.len()
+        ));
+    }
+    Ok(unique)
+}
+
+/// Start queued transforms in order while their owners have free slots.
+///
+/// Once an owner's slots are full their remaining transforms are skipped, so
+/// one owner's run doesn't hold up another's. Returns the started transforms.
+pub(crate) async fn start_in_order<C, CF, S, SF>(
+    queued: Vec<BulkReembedTransform>,
+    mut claim: C,
+    mut start: S,
+) -> Vec<i32>
+where
+    C: FnMut(BulkReembedTransform) -> CF,
+    CF: Future<Output = Result<SlotClaim>>,
+    S: FnMut(BulkReembedTransform) -> SF,
+    SF: Future<Output = Result<()>>,
+{
+    let mut full_owners = HashSet::new();
+    let mut started = Vec::new();
+    for queued in queued {
+        if full_owners.contains(&queued.owner_id) {
+            continue;
+        }
+        let dataset_transform_id = queued.dataset_transform_id;
+        match claim(queued.clone()).await {
+            Ok(SlotClaim::Full { .. }) => {
+                full_owners.insert(queued.owner_id);
+            }
+            Ok(_) => match start(queued).await {
+                Ok(()) => started.push(dataset_transform_id),
+                Err(e) => error!(
+                    dataset_transform_id,
+                    "Failed to start queued re-embed: {}", e
+                ),
+            },
+            Err(e) => {
+                error!(
+                    dataset_transform_id,
+                    "Failed to claim a slot for queued re-embed: {}", e
+                );
+                full_owners.insert(queued.owner_id);
+            }
+        }
+    }
+    started
+}
+
+/// Start whichever queued re-embeds fit in their owners' free slots
+pub(crate) async fn start_queued(
+    pool: &Pool<Postgres>,
+    nats: &NatsClient,
+    encryption: &EncryptionService,
+    worker_config: &WorkerConfig,
+) -> Result<Vec<i32>> {
+    let queued = bulk_reembeds::get_queued_reembeds(pool).await?;
+    if queued.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let started = start_in_order(
+        queued,
+        |queued| async move {
+            transform_slots::claim_transform_slot(
+                pool,
+                &queued.owner_id,
+                TransformKind::Dataset,
+                queued.dataset_transform_id,
+                worker_config.max_concurrent_transforms_per_user,
+                worker_config.transform_trigger_claim_ttl,
+            )
+            .await
+        },
+        |queued| async move { start(pool, nats, encryption, &queued).await },
+    )
+    .await;
+
+    if !started.is_empty() {
+        info!(count = started.len(), "Started queued re-embeds");
+    }
+    Ok(started)
+}
+
+async fn start(
+    pool: &Pool<Postgres>,
+    nats: &NatsClient,
+    encryption: &EncryptionService,
+    queued: &BulkReembedTransform,
+) -> Result<()> {
+    let transform = dataset_transforms::get_dataset_transform(
+        pool,
+        &queued.owner_id,
+        queued.dataset_transform_id,
+    )
+    .await?;
+    pinning::pin_transform_embedders(pool, encryption, &transform, true).await?;
+    bulk_reembeds::mark_reembed_started(pool, queued.dataset_transform_id).await?;
+
+    // Enabled now, so the next periodic scan picks it up if this is lost
+    if let Err(e) = crate::transforms::trigger::publish_targeted_trigger(
+        nats,
+        "dataset",
+        queued.dataset_transform_id,
+        &queued.owner_id,
+    )
+    .await
+    {
+        error!(
+            "Failed to publish targeted scan trigger for dataset transform {}: {}",
+            queued.dataset_transform_id, e
+        );
+    }
+    Ok(())
+}
+
+/// Progress of one dataset in a run. `stats` is `None` for queued datasets
+/// or when the transform's stats couldn't be read.
+pub(crate) fn dataset_progress(
+    queued: &BulkReembedTransform,
+    stats: Option<&DatasetTransformStats>,
+) -> BulkReembedDatasetProgress {
+    let status = match (queued.started_at, stats) {
+        (None, _) => "queued",
+        (Some(_), None) => "running",
+        (Some(_), Some(stats)) => match stats.status() {
+            // A started transform with nothing to embed is done
+            "completed" | "completed_with_errors" | "idle" => "completed",
+            "failed" => "failed",
+            _ => "running",
+        },
+    };
+    BulkReembedDatasetProgress {
+        dataset_id: queued.dataset_id,
+        dataset_transform_id: queued.dataset_transform_id,
+        status: status.to_string(),
+        chunks_embedded: stats.map_or(0, |s| s.total_chunks_embedded),
+        chunks_failed: stats.map_or(0, |s| s.total_chunks_failed),
+        chunks_to_process: stats.map_or(0, |s| s.total_chunks_to_process),
+    }
+}
+
+/// Aggregate the per-dataset progress of a run
+pub(crate) fn aggregate_progress(
+    bulk_reembed_id: i32,
+    embedder_id: i32,
+    datasets: Vec<BulkReembedDatasetProgress>,
+) -> BulkReembedProgress {
+    let count = |status: &str| datasets.iter().filter(|d| d.status == status).count();
+    BulkReembedProgress {
+        bulk_reembed_id,
+        embedder_id,
+        total: datasets.len(),
+        queued: count("queued"),
+        running: count("running"),
+        completed: count("completed"),
+        failed: count("failed"),
+        chunks_embedded: datasets.iter().map(|d| d.chunks_embedded).sum(),
+        chunks_failed: datasets.iter().map(|d| d.chunks_failed).sum(),
+        chunks_to_process: datasets.iter().map(|d| d.chunks_to_process).sum(),
+        datasets,
+    }
+}
+
+/// Current progress of a run
+pub(crate) async fn progress(
+    pool: &Pool<Postgres>,
+    run: &BulkReembed,
+) -> Result<BulkReembedProgress> {
+    let transforms = bulk_reembeds::get_bulk_reembed_transforms(pool, run.bulk_reembed_id).await?;
+    let mut datasets = Vec::with_capacity(transforms.len());
+    for queued in &transforms {
+        let stats = match queued.started_at {
+            Some(_) => dataset_transforms::get_dataset_transform_stats(
+                pool,
+                &run.owner_id,
+                queued.dataset_transform_id,
+            )
+            .await
+            .ok(),
+            None => None,
+        };
+        datasets.push(dataset_progress(queued, stats.as_ref()));
+    }
+    Ok(aggregate_progress(
+        run.bulk_reembed_id,
+        run.embedder_id,
+        datasets,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::cell::RefCell;
+
+    fn queued(owner: &str, dataset_id: i32, dataset_transform_id: i32) -> BulkReembedTransform {
+        BulkReembedTransform {
+            dataset_id,
+            dataset_transform_id,
+            owner_id: owner.to_string(),
+            started_at: None,
+        }
+    }
+
+    fn stats(embedded: i64, failed: i64, to_process: i64) -> DatasetTransformStats {
+        DatasetTransformStats {
+            dataset_transform_id: 0,
+            embedder_count: 1,
+            total_batches_processed: 1,
+            successful_batches: 1,
+            failed_batches: 0,
+            processing_batches: 0,
+            total_chunks_embedded: embedded,
+            total_chunks_processing: 0,
+            total_chunks_failed: failed,
+            total_chunks_to_process: to_process,
+            total_batches_dispatched: 0,
+            total_chunks_dispatched: 0,
+            current_run_id: None,
+            current_run_started_at: None,
+            last_run_at: None,
+            first_processing_at: None,
+        }
+    }
+
+    #[test]
+    fn test_dataset_ids_are_deduplicated_in_order() {
+        assert_eq!(
+            validate_dataset_ids(&[3, 1, 3, 2, 1]).unwrap(),
+            vec![3, 1, 2]
+        );
+        assert!(validate_dataset_ids(&[]).is_err());
+        let too_many: Vec<i32> = (0..=MAX_BULK_REEMBED_DATASETS as i32).collect();
+        assert!(validate_dataset_ids(&too_many).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_enqueues_each_dataset_within_the_concurrency_cap() {
+        // Two free slots for alice; bob has none
+        let slots = RefCell::new(2);
+        let queue = vec![
+            queued("alice", 1, 11),
+            queued("bob", 2, 12),
+            queued("alice", 3, 13),
+            queued("alice", 4, 14),
+            queued("alice", 5, 15),
+        ];
+
+        let started = start_in_order(
+            queue,
+            |queued| {
+                let claim = if queued.owner_id == "bob" || *slots.borrow() == 0 {
+                    SlotClaim::Full {
+                        running: 2,
+                        limit: 2,
+                    }
+                } else {
+                    *slots.borrow_mut() -= 1;
+                    SlotClaim::Claimed
+                };
+                async move { Ok(claim) }
+            },
+            |_| async { Ok(()) },
+        )
+        .await;
+        assert_eq!(started, vec![11, 13]);
+
+        // Both of alice's transforms finish; the next pass starts the rest
+        *slots.borrow_mut() = 2;
+        let started = start_in_order(
+            vec![queued("alice", 4, 14), queued("alice", 5, 15)],
+            |_| {
+                *slots.borrow_mut() -= 1;
+                async { Ok(SlotClaim::Claimed) }
+            },
+            |_| async { Ok(()) },
+        )
+        .await;
+        assert_eq!(started, vec![14, 15]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_does_not_block_the_queue() {
+        let started = start_in_order(
+            vec![queued("alice", 1, 11), queued("alice", 2, 12)],
+            |_| async { Ok(SlotClaim::Claimed) },
+            |queued| async move {
+                if queued.dataset_transform_id == 11 {
+                    anyhow::bail!("transform deleted");
+                }
+                Ok(())
+            },
+        )
+        .await;
+        assert_eq!(started, vec![12]);
+    }
+
+    #[test]
+    fn test_reports_aggregate_progress() {
+        let mut done = queued("alice", 1, 11);
+        done.started_at = Some(Utc::now());
+        let mut running = queued("alice", 2, 12);
+        running.started_at = Some(Utc::now());
+        let mut failed = queued("alice", 3, 13);
+        failed.started_at = Some(Utc::now());
+        let waiting = queued("alice", 4, 14);
+
+        let mut all_failed = stats(0, 40, 40);
+        all_failed.successful_batches = 0;
+        all_failed.failed_batches = 1;
+
+        let progress = aggregate_progress(
+            7,
+            3,
+            vec![
+                dataset_progress(&done, Some(&stats(100, 0, 100))),
+                dataset_progress(&running, Some(&stats(25, 0, 50))),
+                dataset_progress(&failed, Some(&all_failed)),
+                dataset_progress(&waiting, None),
+            ],
+        );
+
+        assert_eq!(progress.total, 4);
+        assert_eq!(
+            (
+                progress.queued,
+                progress.running,
+                progress.completed,
+                progress.failed
+            ),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(progress.chunks_embedded, 125);
+        assert_eq!(progress.chunks_failed, 40);
+        assert_eq!(progress.chunks_to_process, 190);
+        assert_eq!(progress.datasets[3].status, "queued");
+    }
+}
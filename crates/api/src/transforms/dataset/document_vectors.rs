@@ -0,0 +1,49 @@
+//! Document vectors for dataset transforms.
+//!
+//! A dataset transform may set `document_vectors` in its `job_config` (only
+//! `"mean"` for now) to also store one vector per dataset item, computed by
+//! the dataset worker from the item's chunk vectors, in a `<collection>-documents`
+//! collection next to each embedded dataset's chunks.
+
+use serde_json::Value;
+
+use semantic_explorer_core::document_vectors::DocumentVectorMode;
+
+/// Key in a dataset transform's `job_config` holding the document vector mode
+pub const DOCUMENT_VECTORS_KEY: &str = "document_vectors";
+
+/// Validate the document vector mode in a `job_config` supplied on update.
+pub fn validate_job_config(job_config: &Value) -> Result<(), String> {
+    match job_config.get(DOCUMENT_VECTORS_KEY) {
+        None | Some(Value::Null) => Ok(()),
+        Some(mode) => serde_json::from_value::<DocumentVectorMode>(mode.clone())
+            .map(|_| ())
+            .map_err(|e| format!("invalid document_vectors: {e}")),
+    }
+}
+
+/// The transform's document vector mode, `None` when document vectors are off
+pub fn document_vector_mode(job_config: &Value) -> Option<DocumentVectorMode> {
+    job_config
+        .get(DOCUMENT_VECTORS_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_document_vector_mode() {
+        assert_eq!(document_vector_mode(&json!({})), None);
+        assert_eq!(
+            document_vector_mode(&json!({ DOCUMENT_VECTORS_KEY: "mean" })),
+            Some(DocumentVectorMode::Mean)
+        );
+        assert!(validate_job_config(&json!({ DOCUMENT_VECTORS_KEY: null })).is_ok());
+        assert!(validate_job_config(&json!({ DOCUMENT_VECTORS_KEY: "mean" })).is_ok());
+        assert!(validate_job_config(&json!({ DOCUMENT_VECTORS_KEY: "median" })).is_err());
+        assert!(validate_job_config(&json!({ DOCUMENT_VECTORS_KEY: true })).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Fallback embedders for dataset transforms.
+//!
+//! A dataset transform may set `fallback_embedders` in its `job_config`,
+//! mapping an embedder id to the embedder to use while the first one is
+//! unreachable (its circuit breaker is open), e.g. `{"4": 9}`. The scanner
+//! resolves the fallback for each embedded dataset; the dataset worker uses
+//! it and tags the points it produces so they can be re-embedded later.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use sqlx::{Pool, Postgres};
+use tracing::warn;
+
+use semantic_explorer_core::encryption::EncryptionService;
+use semantic_explorer_core::models::FallbackEmbedder;
+
+use crate::auth::AuthenticatedUser;
+use crate::embedders::models::Embedder;
+use crate::storage::postgres::embedders;
+use crate::transforms::dataset::item_types;
+
+/// Key in a dataset transform's `job_config` holding the fallback embedders
+pub const FALLBACK_EMBEDDERS_KEY: &str = "fallback_embedders";
+
+/// Validate fallback embedders from a create/update request.
+pub fn validate_fallback_embedders(fallbacks: &HashMap<i32, i32>) -> Result<(), String> {
+    for (&embedder_id, &fallback_id) in fallbacks {
+        if embedder_id <= 0 || fallback_id <= 0 {
+            return Err("embedder ids in fallback_embedders must be positive".to_string());
+        }
+        if embedder_id == fallback_id {
+            return Err(format!("embedder {embedder_id} cannot be its own fallback"));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the fallback embedders in a `job_config` supplied on update.
+pub fn validate_job_config(job_config: &serde_json::Value) -> Result<(), String> {
+    match job_config.get(FALLBACK_EMBEDDERS_KEY) {
+        None | Some(serde_json::Value::Null) => Ok(()),
+        Some(fallbacks) => {
+            let fallbacks: HashMap<i32, i32> = serde_json::from_value(fallbacks.clone())
+                .map_err(|e| format!("invalid fallback_embedders: {e}"))?;
+            validate_fallback_embedders(&fallbacks)
+        }
+    }
+}
+
+/// Fallback embedder id configured for `embedder_id`, if any
+pub fn fallback_embedder_id(job_config: &serde_json::Value, embedder_id: i32) -> Option<i32> {
+    job_config
+        .get(FALLBACK_EMBEDDERS_KEY)
+        .and_then(|v| serde_json::from_value::<HashMap<i32, i32>>(v.clone()).ok())
+        .and_then(|fallbacks| fallbacks.get(&embedder_id).copied())
+}
+
+/// Resolve the fallback for `base` into job settings. A fallback whose
+/// dimensions don't match `base` is dropped, since its vectors can't share
+/// the embedded dataset's collection.
+pub(crate) async fn resolve_fallback_embedder(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    encryption: &EncryptionService,
+    job_config: &serde_json::Value,
+    base: &Embedder,
+) -> Result<Option<FallbackEmbedder>> {
+    let Some(fallback_id) = fallback_embedder_id(job_config, base.embedder_id) else {
+        return Ok(None);
+    };
+
+    let fallback = embedders::get_embedders_batch(pool, user, &[fallback_id], encryption)
+        .await?
+        .into_iter()
+        .next();
+    match fallback {
+        Some(embedder) if embedder.dimensions == base.dimensions => Ok(Some(FallbackEmbedder {
+            embedder_id: fallback_id,
+            embedder_config: item_types::embedder_config(&embedder)?,
+        })),
+        Some(embedder) => {
+            warn!(
+                embedder_id = base.embedder_id,
+                fallback_embedder_id = fallback_id,
+                dimensions = embedder.dimensions,
+                expected_dimensions = base.dimensions,
+                "Ignoring fallback embedder with mismatched dimensions"
+            );
+            Ok(None)
+        }
+        None => {
+            warn!(
+                embedder_id = base.embedder_id,
+                fallback_embedder_id = fallback_id,
+                "Ignoring fallback embedder: embedder not found or not accessible"
+            );
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_fallback_embedders() {
+        assert!(validate_fallback_embedders(&HashMap::from([(4, 9)])).is_ok());
+        assert!(validate_fallback_embedders(&HashMap::from([(4, 4)])).is_err());
+        assert!(validate_fallback_embedders(&HashMap::from([(4, 0)])).is_err());
+    }
+
+    #[test]
+    fn test_fallback_lookup_from_job_config() {
+        let config = json!({ "fallback_embedders": { "4": 9 } });
+        assert!(validate_job_config(&config).is_ok());
+        assert_eq!(fallback_embedder_id(&config, 4), Some(9));
+        assert_eq!(fallback_embedder_id(&config, 9), None);
+        assert_eq!(fallback_embedder_id(&json!({}), 4), None);
+
+        assert!(validate_job_config(&json!({ "fallback_embedders": [4, 9] })).is_err());
+        assert!(validate_job_config(&json!({ "fallback_embedders": { "four": 9 } })).is_err());
+    }
+}
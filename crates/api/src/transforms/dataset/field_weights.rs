@@ -0,0 +1,176 @@
+//! Weighted multi-field embedding for dataset transforms.
+//!
+//! A dataset transform may set `field_weights` in its `job_config` to embed
+//! several fields of a record separately and combine them into one vector,
+//! e.g. `{"title": 3, "text": 1, "tags": 1}`. `text` is the chunk content,
+//! `title` the item title, and any other name is read from the chunk
+//! metadata, then the item metadata (strings, numbers, or lists of them).
+//! The scanner attaches each chunk's non-empty fields with their weights to
+//! its batch item; the dataset worker embeds and combines them.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Key in a dataset transform's `job_config` holding the field weights
+pub const FIELD_WEIGHTS_KEY: &str = "field_weights";
+
+/// Field name for the chunk content
+pub const TEXT_FIELD: &str = "text";
+
+/// Field name for the item title
+pub const TITLE_FIELD: &str = "title";
+
+const MAX_FIELDS: usize = 16;
+
+/// One field of a chunk, as sent to the worker in the batch item's `fields`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WeightedField {
+    pub text: String,
+    pub weight: f32,
+}
+
+/// Validate field weights from a create/update request.
+pub fn validate_field_weights(weights: &HashMap<String, f32>) -> Result<(), String> {
+    if weights.is_empty() {
+        return Err("field_weights must name at least one field".to_string());
+    }
+    if weights.len() > MAX_FIELDS {
+        return Err(format!(
+            "field_weights can name at most {MAX_FIELDS} fields"
+        ));
+    }
+    for (field, weight) in weights {
+        if field.trim().is_empty() {
+            return Err("field names in field_weights cannot be empty".to_string());
+        }
+        if !weight.is_finite() || *weight <= 0.0 {
+            return Err(format!(
+                "weight for field '{field}' must be a positive number"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the field weights in a `job_config` supplied on update.
+pub fn validate_job_config(job_config: &Value) -> Result<(), String> {
+    match job_config.get(FIELD_WEIGHTS_KEY) {
+        None | Some(Value::Null) => Ok(()),
+        Some(weights) => {
+            let weights: HashMap<String, f32> = serde_json::from_value(weights.clone())
+                .map_err(|e| format!("invalid field_weights: {e}"))?;
+            validate_field_weights(&weights)
+        }
+    }
+}
+
+/// The transform's field weights sorted by field name, empty when unset
+pub fn field_weights(job_config: &Value) -> Vec<(String, f32)> {
+    let weights: HashMap<String, f32> = job_config
+        .get(FIELD_WEIGHTS_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let mut weights: Vec<(String, f32)> = weights.into_iter().collect();
+    weights.sort_by(|a, b| a.0.cmp(&b.0));
+    weights
+}
+
+/// The weighted fields present on one chunk. Missing and empty fields are
+/// left out, so the worker renormalizes over the fields a record has.
+pub fn weighted_fields(
+    weights: &[(String, f32)],
+    chunk_text: &str,
+    item_title: &str,
+    chunk_metadata: &Value,
+    item_metadata: &Value,
+) -> Vec<WeightedField> {
+    weights
+        .iter()
+        .filter_map(|(field, weight)| {
+            let text = match field.as_str() {
+                TEXT_FIELD => chunk_text.to_string(),
+                TITLE_FIELD => item_title.to_string(),
+                _ => [chunk_metadata, item_metadata]
+                    .into_iter()
+                    .find_map(|metadata| metadata_text(metadata.get(field)?))?,
+            };
+            (!text.trim().is_empty()).then_some(WeightedField {
+                text,
+                weight: *weight,
+            })
+        })
+        .collect()
+}
+
+fn metadata_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(values) => {
+            let parts: Vec<String> = values.iter().filter_map(metadata_text).collect();
+            (!parts.is_empty()).then(|| parts.join(", "))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_field_weights() {
+        assert!(validate_field_weights(&HashMap::from([("title".to_string(), 2.0)])).is_ok());
+        assert!(validate_field_weights(&HashMap::new()).is_err());
+        assert!(validate_field_weights(&HashMap::from([(" ".to_string(), 1.0)])).is_err());
+        assert!(validate_field_weights(&HashMap::from([("tags".to_string(), 0.0)])).is_err());
+        assert!(validate_field_weights(&HashMap::from([("tags".to_string(), f32::NAN)])).is_err());
+
+        assert!(validate_job_config(&json!({})).is_ok());
+        assert!(
+            validate_job_config(&json!({ "field_weights": { "title": 3, "text": 1 } })).is_ok()
+        );
+        assert!(validate_job_config(&json!({ "field_weights": ["title"] })).is_err());
+    }
+
+    #[test]
+    fn test_weighted_fields_resolve_from_chunk_and_item() {
+        let config =
+            json!({ "field_weights": { "title": 3, "text": 1, "tags": 1, "author": 0.5 } });
+        let weights = field_weights(&config);
+        assert_eq!(
+            weights.iter().map(|(f, _)| f.as_str()).collect::<Vec<_>>(),
+            vec!["author", "tags", "text", "title"]
+        );
+
+        let fields = weighted_fields(
+            &weights,
+            "Chunk body",
+            "Record Title",
+            &json!({ "tags": ["rust", "async"] }),
+            &json!({ "tags": "ignored", "author": "" }),
+        );
+        assert_eq!(
+            fields,
+            vec![
+                WeightedField {
+                    text: "rust, async".to_string(),
+                    weight: 1.0
+                },
+                WeightedField {
+                    text: "Chunk body".to_string(),
+                    weight: 1.0
+                },
+                WeightedField {
+                    text: "Record Title".to_string(),
+                    weight: 3.0
+                },
+            ]
+        );
+
+        assert!(field_weights(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,461 @@
+//! Incremental re-runs of dataset transforms.
+//!
+//! The scanner's item_id watermark only finds items added since the last
+//! scan. To pick up edits and deletions as well, every embedded dataset keeps
+//! a content hash per item it has dispatched. Whenever the source dataset's
+//! version moves, the items updated since the last check are hashed again:
+//! items whose hash differs are re-batched (the worker then skips chunks whose
+//! text is unchanged), points past the new end of a shrunk item are removed,
+//! and the points of items that no longer exist are deleted from Qdrant,
+//! along with their document vectors.
+//! Items embedded before hashes were tracked get their hash recorded without
+//! being re-embedded.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_nats::Client as NatsClient;
+use aws_sdk_s3::Client as S3Client;
+use chrono::{DateTime, Utc};
+use qdrant_client::{
+    Qdrant,
+    qdrant::{
+        Condition, DeletePointsBuilder, FieldCondition, Filter, Match as QdrantMatch, Range,
+        RepeatedIntegers, condition::ConditionOneOf, r#match::MatchValue,
+        points_selector::PointsSelectorOneOf,
+    },
+};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use tracing::{info, warn};
+
+use semantic_explorer_core::circuit_breaker::CircuitBreaker;
+use semantic_explorer_core::document_vectors::document_collection_name;
+
+use crate::datasets::models::DatasetItem;
+use crate::embedded_datasets::EmbeddedDataset;
+use crate::storage::postgres::embedded_datasets::{self, ItemHash};
+use crate::storage::postgres::{INTERNAL_BATCH_SIZE, datasets};
+use crate::transforms::dataset::models::DatasetTransform;
+
+use super::scanner::{
+    DatasetBatchConfig, DispatchedBatches, RateLimiter, build_batch_items, dispatch_batch_items,
+    fully_dispatched_items, trigger_dataset_transform_scan,
+};
+
+/// How far before the last check to look for updated items. Covers writes
+/// that committed after the check with an `updated_at` from before it.
+const UPDATED_SINCE_OVERLAP: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Hash of everything about an item that ends up in its points
+pub(crate) fn item_content_hash(item: &DatasetItem) -> String {
+    let content = serde_json::json!([item.title, item.chunks, item.metadata]);
+    let mut hasher = Sha256::new();
+    hasher.update(content.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub(crate) fn item_hashes(items: &[DatasetItem]) -> Vec<ItemHash> {
+    items
+        .iter()
+        .map(|item| ItemHash {
+            item_id: item.item_id,
+            content_hash: item_content_hash(item),
+            chunk_count: item.chunks.len() as i32,
+        })
+        .collect()
+}
+
+/// What to do about a page of updated items
+#[derive(Default)]
+pub(super) struct ChangePlan {
+    /// Items whose content differs from what was embedded
+    pub(super) changed: Vec<DatasetItem>,
+    /// `(item_id, chunk_count)` of changed items that now have fewer chunks
+    pub(super) shrunk: Vec<(i32, usize)>,
+    /// Hashes of items embedded before hashes were tracked
+    pub(super) baseline: Vec<ItemHash>,
+}
+
+/// Compare `items` against the hashes stored when they were last dispatched.
+/// Unchanged items are dropped.
+pub(super) fn plan_changes(items: Vec<DatasetItem>, stored: &[ItemHash]) -> ChangePlan {
+    let stored: HashMap<i32, &ItemHash> = stored.iter().map(|h| (h.item_id, h)).collect();
+    let mut plan = ChangePlan::default();
+    for item in items {
+        let hash = item_content_hash(&item);
+        match stored.get(&item.item_id) {
+            None => plan.baseline.push(ItemHash {
+                item_id: item.item_id,
+                content_hash: hash,
+                chunk_count: item.chunks.len() as i32,
+            }),
+            Some(previous) if previous.content_hash == hash => {}
+            Some(previous) => {
+                if item.chunks.len() < previous.chunk_count as usize {
+                    plan.shrunk.push((item.item_id, item.chunks.len()));
+                }
+                plan.changed.push(item);
+            }
+        }
+    }
+    plan
+}
+
+fn integer_condition(key: &str, match_value: MatchValue) -> Condition {
+    Condition {
+        condition_one_of: Some(ConditionOneOf::Field(FieldCondition {
+            key: key.to_string(),
+            r#match: Some(QdrantMatch {
+                match_value: Some(match_value),
+            }),
+            ..Default::default()
+        })),
+    }
+}
+
+/// Filter selecting every point of the given items
+pub(super) fn deleted_items_filter(item_ids: &[i32]) -> Filter {
+    Filter {
+        must: vec![integer_condition(
+            "item_id",
+            MatchValue::Integers(RepeatedIntegers {
+                integers: item_ids.iter().map(|id| *id as i64).collect(),
+            }),
+        )],
+        ..Default::default()
+    }
+}
+
+/// Filter selecting the points of an item from chunk `from_chunk` onwards
+pub(super) fn stale_chunks_filter(item_id: i32, from_chunk: usize) -> Filter {
+    Filter {
+        must: vec![
+            integer_condition("item_id", MatchValue::Integer(item_id as i64)),
+            Condition {
+                condition_one_of: Some(ConditionOneOf::Field(FieldCondition {
+                    key: "chunk_index".to_string(),
+                    range: Some(Range {
+                        gte: Some(from_chunk as f64),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })),
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+async fn delete_points(qdrant: &Qdrant, collection_name: &str, filter: Filter) -> Result<()> {
+    let request =
+        DeletePointsBuilder::new(collection_name).points(PointsSelectorOneOf::Filter(filter));
+    qdrant.delete_points(request).await?;
+    Ok(())
+}
+
+/// Remove the points of deleted items and re-dispatch items that changed
+/// since the source dataset version was last checked. Returns the number of
+/// jobs created. Only items at or below the watermark are considered; newer
+/// items are left to the regular scan.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn sync_changed_items(
+    pool: &Pool<Postgres>,
+    s3: &S3Client,
+    nats: &NatsClient,
+    qdrant: &Qdrant,
+    transform: &DatasetTransform,
+    embedded_dataset: &EmbeddedDataset,
+    config: &DatasetBatchConfig,
+    rate_limiter: &RateLimiter,
+    circuit_breaker: &Arc<CircuitBreaker>,
+    max_batches: usize,
+    dataset_version: DateTime<Utc>,
+) -> Result<usize> {
+    let embedded_dataset_id = embedded_dataset.embedded_dataset_id;
+    let watermark = embedded_dataset.last_processed_item_id.unwrap_or(0);
+    if watermark == 0 {
+        return Ok(0);
+    }
+    let checked_version =
+        embedded_datasets::get_items_checked_version(pool, embedded_dataset_id).await?;
+    if checked_version == Some(dataset_version) {
+        return Ok(0);
+    }
+
+    // Deleted items first, so their hash rows don't linger if a later step fails
+    let mut deleted_items = 0;
+    loop {
+        let item_ids =
+            embedded_datasets::get_deleted_item_ids(pool, embedded_dataset_id, INTERNAL_BATCH_SIZE)
+                .await?;
+        if item_ids.is_empty() {
+            break;
+        }
+        delete_points(
+            qdrant,
+            &embedded_dataset.collection_name,
+            deleted_items_filter(&item_ids),
+        )
+        .await?;
+        // Document points carry the same item_id payload as the chunks
+        let document_collection = document_collection_name(&embedded_dataset.collection_name);
+        if qdrant.collection_exists(&document_collection).await? {
+            delete_points(
+                qdrant,
+                &document_collection,
+                deleted_items_filter(&item_ids),
+            )
+            .await?;
+        }
+        embedded_datasets::delete_item_hashes(pool, embedded_dataset_id, &item_ids).await?;
+        deleted_items += item_ids.len();
+    }
+
+    let mut cursor = (
+        checked_version
+            .map(|v| v - UPDATED_SINCE_OVERLAP)
+            .unwrap_or(DateTime::<Utc>::UNIX_EPOCH),
+        0,
+    );
+    let mut changed_items = 0;
+    let mut jobs_created = 0;
+    let mut batches_left = max_batches;
+    loop {
+        let items = datasets::get_dataset_items_updated_since(
+            pool,
+            transform.source_dataset_id,
+            watermark,
+            cursor,
+            INTERNAL_BATCH_SIZE,
+        )
+        .await?;
+        let Some(last) = items.last() else {
+            break;
+        };
+        cursor = (
+            last.updated_at.or(last.created_at).unwrap_or(cursor.0),
+            last.item_id,
+        );
+
+        let item_ids: Vec<i32> = items.iter().map(|item| item.item_id).collect();
+        let stored =
+            embedded_datasets::get_item_hashes(pool, embedded_dataset_id, &item_ids).await?;
+        let plan = plan_changes(items, &stored);
+        embedded_datasets::upsert_item_hashes(pool, embedded_dataset_id, &plan.baseline).await?;
+        if plan.changed.is_empty() {
+            continue;
+        }
+
+        // New points only go up to the new chunk count, so removing the
+        // tail first can't race with the re-embedding
+        for (item_id, chunk_count) in &plan.shrunk {
+            delete_points(
+                qdrant,
+                &embedded_dataset.collection_name,
+                stale_chunks_filter(*item_id, *chunk_count),
+            )
+            .await?;
+        }
+
+        let (batch_items, item_chunk_boundaries) =
+            build_batch_items(transform, embedded_dataset_id, &plan.changed);
+        let DispatchedBatches {
+            jobs_created: jobs,
+            chunks_dispatched,
+            capped,
+        } = dispatch_batch_items(
+            pool,
+            s3,
+            nats,
+            transform,
+            embedded_dataset,
+            config,
+            rate_limiter,
+            circuit_breaker,
+            batches_left,
+            &batch_items,
+        )
+        .await?;
+        jobs_created += jobs;
+        batches_left = batches_left.saturating_sub(jobs);
+
+        let dispatched = fully_dispatched_items(&item_chunk_boundaries, chunks_dispatched);
+        embedded_datasets::upsert_item_hashes(
+            pool,
+            embedded_dataset_id,
+            &item_hashes(&plan.changed[..dispatched]),
+        )
+        .await?;
+        changed_items += dispatched;
+
+        if capped || batches_left == 0 {
+            // Leave the version unchecked so the next scan carries on; items
+            // already re-dispatched now match their hash and are skipped
+            warn!(
+                embedded_dataset_id,
+                changed_items, "Batch limit reached while re-embedding changed items"
+            );
+            if let Err(e) = trigger_dataset_transform_scan(
+                nats,
+                transform.dataset_transform_id,
+                &transform.owner_id,
+            )
+            .await
+            {
+                warn!(
+                    "Failed to schedule follow-up scan, will rely on reconciliation: {}",
+                    e
+                );
+            }
+            return Ok(jobs_created);
+        }
+    }
+
+    embedded_datasets::update_items_checked_version(pool, embedded_dataset_id, dataset_version)
+        .await?;
+    if changed_items > 0 || deleted_items > 0 {
+        info!(
+            embedded_dataset_id,
+            changed_items, deleted_items, jobs_created, "Synced changed and deleted dataset items"
+        );
+    }
+    Ok(jobs_created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::models::ChunkWithMetadata;
+    use serde_json::json;
+
+    fn item(item_id: i32, chunks: &[&str]) -> DatasetItem {
+        DatasetItem {
+            item_id,
+            dataset_id: 1,
+            title: format!("item {item_id}"),
+            chunks: chunks
+                .iter()
+                .map(|content| ChunkWithMetadata {
+                    content: content.to_string(),
+                    metadata: json!({}),
+                })
+                .collect(),
+            metadata: json!({"source": "test"}),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn transform() -> DatasetTransform {
+        DatasetTransform {
+            dataset_transform_id: 1,
+            title: "t".to_string(),
+            source_dataset_id: 1,
+            embedder_ids: vec![1],
+            owner_id: "owner".to_string(),
+            owner_display_name: "owner".to_string(),
+            is_enabled: true,
+            job_config: json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn field_key(condition: &Condition) -> &str {
+        match &condition.condition_one_of {
+            Some(ConditionOneOf::Field(field)) => &field.key,
+            _ => panic!("expected a field condition"),
+        }
+    }
+
+    #[test]
+    fn test_content_hash_tracks_chunks_title_and_metadata() {
+        let original = item(1, &["a", "b"]);
+        assert_eq!(
+            item_content_hash(&original),
+            item_content_hash(&item(1, &["a", "b"]))
+        );
+        assert_ne!(
+            item_content_hash(&original),
+            item_content_hash(&item(1, &["a", "c"]))
+        );
+
+        let mut retitled = item(1, &["a", "b"]);
+        retitled.title = "renamed".to_string();
+        assert_ne!(item_content_hash(&original), item_content_hash(&retitled));
+
+        let mut tagged = item(1, &["a", "b"]);
+        tagged.metadata = json!({"source": "other"});
+        assert_ne!(item_content_hash(&original), item_content_hash(&tagged));
+    }
+
+    #[test]
+    fn test_rerun_only_reembeds_modified_item() {
+        let stored = item_hashes(&[item(1, &["a"]), item(2, &["b", "c"]), item(3, &["d"])]);
+
+        let plan = plan_changes(
+            vec![item(1, &["a"]), item(2, &["b", "changed"]), item(3, &["d"])],
+            &stored,
+        );
+        assert_eq!(
+            plan.changed.iter().map(|i| i.item_id).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert!(plan.shrunk.is_empty());
+        assert!(plan.baseline.is_empty());
+
+        let (batch_items, boundaries) = build_batch_items(&transform(), 7, &plan.changed);
+        assert_eq!(batch_items.len(), 2);
+        assert!(
+            batch_items
+                .iter()
+                .all(|b| b["payload"]["item_id"] == json!(2))
+        );
+        assert_eq!(boundaries, vec![(2, 2)]);
+    }
+
+    #[test]
+    fn test_shrunk_item_drops_trailing_chunks() {
+        let stored = item_hashes(&[item(4, &["a", "b", "c"])]);
+        let plan = plan_changes(vec![item(4, &["a"])], &stored);
+        assert_eq!(plan.changed.len(), 1);
+        assert_eq!(plan.shrunk, vec![(4, 1)]);
+
+        let filter = stale_chunks_filter(4, 1);
+        assert_eq!(filter.must.len(), 2);
+        assert_eq!(field_key(&filter.must[0]), "item_id");
+        match &filter.must[1].condition_one_of {
+            Some(ConditionOneOf::Field(field)) => {
+                assert_eq!(field.key, "chunk_index");
+                assert_eq!(field.range.as_ref().and_then(|r| r.gte), Some(1.0));
+            }
+            _ => panic!("expected a chunk_index range"),
+        }
+    }
+
+    #[test]
+    fn test_items_without_stored_hash_are_baselined_not_reembedded() {
+        let plan = plan_changes(vec![item(5, &["a"])], &[]);
+        assert!(plan.changed.is_empty());
+        assert_eq!(plan.baseline, item_hashes(&[item(5, &["a"])]));
+    }
+
+    #[test]
+    fn test_deleted_item_filter_targets_only_that_item() {
+        let filter = deleted_items_filter(&[9]);
+        assert_eq!(filter.must.len(), 1);
+        assert_eq!(field_key(&filter.must[0]), "item_id");
+        match &filter.must[0].condition_one_of {
+            Some(ConditionOneOf::Field(FieldCondition {
+                r#match:
+                    Some(QdrantMatch {
+                        match_value: Some(MatchValue::Integers(ids)),
+                    }),
+                ..
+            })) => assert_eq!(ids.integers, vec![9]),
+            _ => panic!("expected an item_id match"),
+        }
+    }
+}
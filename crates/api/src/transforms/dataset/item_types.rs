@@ -0,0 +1,278 @@
+//! Per-item-type embedding overrides for dataset transforms.
+//!
+//! A dataset transform may set `item_type_overrides` in its `job_config` to
+//! embed items of different types (read from the `item_type_field` of the
+//! chunk or item metadata, `type` by default) with their own text prefix
+//! and/or embedder. The scanner tags each batch item with its type and
+//! resolves the override embedders; the dataset worker applies them.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use semantic_explorer_core::encryption::EncryptionService;
+use semantic_explorer_core::models::{EmbedderConfig, ItemTypeEmbedding};
+
+use crate::auth::AuthenticatedUser;
+use crate::embedders::models::Embedder;
+use crate::storage::postgres::embedders;
+
+/// Key in a dataset transform's `job_config` naming the metadata field that
+/// holds an item's type.
+pub const ITEM_TYPE_FIELD_KEY: &str = "item_type_field";
+
+/// Key in a dataset transform's `job_config` holding the per-type overrides.
+pub const ITEM_TYPE_OVERRIDES_KEY: &str = "item_type_overrides";
+
+pub const DEFAULT_ITEM_TYPE_FIELD: &str = "type";
+
+const MAX_PREFIX_LENGTH: usize = 1000;
+
+/// Embedding settings for one item type
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct ItemTypeOverride {
+    /// Text prepended to each item of this type before embedding, e.g. `"title: "`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Embedder to use instead of the embedded dataset's own; it must produce
+    /// vectors of the same dimensions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedder_id: Option<i32>,
+}
+
+/// Validate per-type overrides from a create/update request.
+pub fn validate_item_type_overrides(
+    overrides: &HashMap<String, ItemTypeOverride>,
+) -> Result<(), String> {
+    for (item_type, item_override) in overrides {
+        if item_type.trim().is_empty() {
+            return Err("item type names in item_type_overrides cannot be empty".to_string());
+        }
+        if item_override.prefix.is_none() && item_override.embedder_id.is_none() {
+            return Err(format!(
+                "override for item type '{item_type}' must set a prefix or an embedder_id"
+            ));
+        }
+        if let Some(prefix) = &item_override.prefix
+            && prefix.len() > MAX_PREFIX_LENGTH
+        {
+            return Err(format!(
+                "prefix for item type '{item_type}' exceeds {MAX_PREFIX_LENGTH} characters"
+            ));
+        }
+        if let Some(embedder_id) = item_override.embedder_id
+            && embedder_id <= 0
+        {
+            return Err(format!(
+                "embedder_id for item type '{item_type}' must be positive"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the item type settings in a `job_config` supplied on update.
+pub fn validate_job_config(job_config: &serde_json::Value) -> Result<(), String> {
+    if let Some(field) = job_config.get(ITEM_TYPE_FIELD_KEY) {
+        match field.as_str() {
+            Some(f) if !f.trim().is_empty() => {}
+            _ => return Err("item_type_field must be a non-empty string".to_string()),
+        }
+    }
+    if let Some(overrides) = job_config.get(ITEM_TYPE_OVERRIDES_KEY) {
+        let overrides: HashMap<String, ItemTypeOverride> =
+            serde_json::from_value(overrides.clone())
+                .map_err(|e| format!("invalid item_type_overrides: {e}"))?;
+        validate_item_type_overrides(&overrides)?;
+    }
+    Ok(())
+}
+
+/// Metadata field holding the item type, falling back to [`DEFAULT_ITEM_TYPE_FIELD`].
+pub fn item_type_field(job_config: &serde_json::Value) -> &str {
+    job_config
+        .get(ITEM_TYPE_FIELD_KEY)
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_ITEM_TYPE_FIELD)
+}
+
+pub fn overrides_from_job_config(
+    job_config: &serde_json::Value,
+) -> HashMap<String, ItemTypeOverride> {
+    job_config
+        .get(ITEM_TYPE_OVERRIDES_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Type of a chunk: the chunk's own metadata wins over its item's.
+pub fn item_type_of(
+    field: &str,
+    chunk_metadata: &serde_json::Value,
+    item_metadata: &serde_json::Value,
+) -> Option<String> {
+    [chunk_metadata, item_metadata]
+        .into_iter()
+        .find_map(|metadata| metadata.get(field).and_then(|v| v.as_str()))
+        .map(str::to_string)
+}
+
+/// Resolve a transform's overrides into job settings for one embedded
+/// dataset, loading override embedders. Embedder overrides whose dimensions
+/// don't match `base` are dropped (their prefix still applies), since their
+/// vectors can't share the embedded dataset's collection.
+pub(crate) async fn resolve_item_type_overrides(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    encryption: &EncryptionService,
+    job_config: &serde_json::Value,
+    base: &Embedder,
+) -> Result<HashMap<String, ItemTypeEmbedding>> {
+    let overrides = overrides_from_job_config(job_config);
+    if overrides.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut embedder_ids: Vec<i32> = overrides.values().filter_map(|o| o.embedder_id).collect();
+    embedder_ids.sort_unstable();
+    embedder_ids.dedup();
+    let override_embedders: HashMap<i32, Embedder> =
+        embedders::get_embedders_batch(pool, user, &embedder_ids, encryption)
+            .await?
+            .into_iter()
+            .map(|e| (e.embedder_id, e))
+            .collect();
+
+    let mut resolved = HashMap::with_capacity(overrides.len());
+    for (item_type, item_override) in overrides {
+        let mut settings = ItemTypeEmbedding {
+            prefix: item_override.prefix,
+            ..Default::default()
+        };
+
+        if let Some(embedder_id) = item_override.embedder_id {
+            match override_embedders.get(&embedder_id) {
+                Some(embedder) if embedder.dimensions == base.dimensions => {
+                    settings.embedder_id = Some(embedder_id);
+                    settings.embedder_config = Some(embedder_config(embedder)?);
+                }
+                Some(embedder) => warn!(
+                    item_type = %item_type,
+                    embedder_id,
+                    dimensions = embedder.dimensions,
+                    expected_dimensions = base.dimensions,
+                    "Ignoring item type embedder override with mismatched dimensions"
+                ),
+                None => warn!(
+                    item_type = %item_type,
+                    embedder_id,
+                    "Ignoring item type embedder override: embedder not found or not accessible"
+                ),
+            }
+        }
+
+        resolved.insert(item_type, settings);
+    }
+
+    Ok(resolved)
+}
+
+pub(crate) fn embedder_config(embedder: &Embedder) -> Result<EmbedderConfig> {
+    let model = embedder
+        .config
+        .get("model")
+        .and_then(|m| m.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Embedder config must specify a 'model' field"))?
+        .to_string();
+
+    Ok(EmbedderConfig::new(
+        embedder.provider.clone(),
+        embedder.base_url.clone(),
+        embedder.api_key.clone(),
+        model,
+        embedder.config.clone(),
+        embedder.batch_size,
+        embedder.max_input_tokens,
+    )
+    .with_dimensions(embedder.verified_dimensions()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_item_type_overrides() {
+        let valid = HashMap::from([
+            (
+                "title".to_string(),
+                ItemTypeOverride {
+                    prefix: Some("title: ".to_string()),
+                    embedder_id: None,
+                },
+            ),
+            (
+                "code".to_string(),
+                ItemTypeOverride {
+                    prefix: None,
+                    embedder_id: Some(4),
+                },
+            ),
+        ]);
+        assert!(validate_item_type_overrides(&valid).is_ok());
+
+        let empty = HashMap::from([("body".to_string(), ItemTypeOverride::default())]);
+        assert!(validate_item_type_overrides(&empty).is_err());
+
+        let bad_id = HashMap::from([(
+            "body".to_string(),
+            ItemTypeOverride {
+                prefix: None,
+                embedder_id: Some(0),
+            },
+        )]);
+        assert!(validate_item_type_overrides(&bad_id).is_err());
+    }
+
+    #[test]
+    fn test_validate_job_config() {
+        assert!(validate_job_config(&json!({})).is_ok());
+        assert!(
+            validate_job_config(&json!({
+                "item_type_field": "kind",
+                "item_type_overrides": { "title": { "prefix": "title: " } }
+            }))
+            .is_ok()
+        );
+        assert!(validate_job_config(&json!({ "item_type_field": 3 })).is_err());
+        assert!(validate_job_config(&json!({ "item_type_overrides": ["title"] })).is_err());
+    }
+
+    #[test]
+    fn test_item_type_lookup() {
+        let config = json!({ "item_type_field": "kind" });
+        let field = item_type_field(&config);
+        assert_eq!(field, "kind");
+        assert_eq!(item_type_field(&json!({})), DEFAULT_ITEM_TYPE_FIELD);
+
+        // Chunk metadata wins over item metadata
+        assert_eq!(
+            item_type_of(
+                field,
+                &json!({ "kind": "code" }),
+                &json!({ "kind": "body" })
+            ),
+            Some("code".to_string())
+        );
+        assert_eq!(
+            item_type_of(field, &json!(null), &json!({ "kind": "body" })),
+            Some("body".to_string())
+        );
+        assert_eq!(item_type_of(field, &json!({}), &json!({})), None);
+    }
+}
@@ -10,6 +10,8 @@
 //! 2. **Atomic Transactions**: All batch tracking and stats updates happen in a single transaction
 //! 3. **ACK-after-commit**: NATS messages are only acknowledged AFTER the transaction commits
 //! 4. **Distributed Tracing**: Trace context is propagated for end-to-end visibility
+//! 5. **Per-transform Ordering**: Results for one transform are applied in delivery
+//!    order; different transforms are handled concurrently
 
 use async_nats::{Client as NatsClient, jetstream};
 use aws_sdk_s3::Client as S3Client;
@@ -28,7 +30,7 @@ use semantic_explorer_core::models::DatasetTransformResult;
 use semantic_explorer_core::nats::extract_otel_context;
 use semantic_explorer_core::storage::delete_file_by_key;
 
-use super::super::listeners::publish_transform_status;
+use super::super::listeners::{KeyedExecutor, publish_transform_status};
 
 /// Context for transform result handling
 #[derive(Clone)]
@@ -37,6 +39,8 @@ pub(crate) struct DatasetListenerContext {
     pub s3_client: S3Client,
     pub s3_bucket_name: String,
     pub nats_client: NatsClient,
+    /// Results handled at once; results for one transform are applied in order
+    pub max_concurrency: usize,
 }
 
 /// Result of handling a batch result message
@@ -100,6 +104,7 @@ pub(crate) fn start_result_listener(context: DatasetListenerContext) {
             consumer_name
         );
 
+        let executor = KeyedExecutor::new(context.max_concurrency);
         let mut messages = match consumer.messages().await {
             Ok(m) => m,
             Err(e) => {
@@ -117,14 +122,6 @@ pub(crate) fn start_result_listener(context: DatasetListenerContext) {
                 }
             };
 
-            // Extract trace context for distributed tracing
-            let parent_context = extract_otel_context(msg.headers.as_ref());
-            let span = info_span!(
-                "handle_dataset_transform_result",
-                subject = %msg.subject,
-            );
-            let _ = span.set_parent(parent_context);
-
             let result: DatasetTransformResult = match serde_json::from_slice(&msg.payload) {
                 Ok(r) => r,
                 Err(e) => {
@@ -137,31 +134,46 @@ pub(crate) fn start_result_listener(context: DatasetListenerContext) {
                 }
             };
 
-            // Process within the trace span
-            let handle_result = async { handle_result_atomic(result.clone(), &context).await }
-                .instrument(span.clone())
-                .await;
+            // Extract trace context for distributed tracing
+            let parent_context = extract_otel_context(msg.headers.as_ref());
+            let span = info_span!(
+                "handle_dataset_transform_result",
+                subject = %msg.subject,
+            );
+            let _ = span.set_parent(parent_context);
 
-            // ACK/NAK based on result - CRITICAL: only after transaction commits
-            match handle_result {
-                HandleResult::Success | HandleResult::Skipped | HandleResult::ResourceDeleted => {
-                    if let Err(e) = msg.ack().await {
-                        error!("Failed to acknowledge message: {}", e);
+            let context = context.clone();
+            executor
+                .submit(result.dataset_transform_id, async move {
+                    // Process within the trace span
+                    let handle_result = handle_result_atomic(result, &context)
+                        .instrument(span)
+                        .await;
+
+                    // ACK/NAK based on result - CRITICAL: only after transaction commits
+                    match handle_result {
+                        HandleResult::Success
+                        | HandleResult::Skipped
+                        | HandleResult::ResourceDeleted => {
+                            if let Err(e) = msg.ack().await {
+                                error!("Failed to acknowledge message: {}", e);
+                            }
+                        }
+                        HandleResult::Failed(reason) => {
+                            warn!("Batch handling failed, will retry: {}", reason);
+                            // NAK with delay for retry
+                            if let Err(e) = msg
+                                .ack_with(async_nats::jetstream::AckKind::Nak(Some(
+                                    Duration::from_secs(30),
+                                )))
+                                .await
+                            {
+                                error!("Failed to NAK message: {}", e);
+                            }
+                        }
                     }
-                }
-                HandleResult::Failed(reason) => {
-                    warn!("Batch handling failed, will retry: {}", reason);
-                    // NAK with delay for retry
-                    if let Err(e) = msg
-                        .ack_with(async_nats::jetstream::AckKind::Nak(Some(
-                            Duration::from_secs(30),
-                        )))
-                        .await
-                    {
-                        error!("Failed to NAK message: {}", e);
-                    }
-                }
-            }
+                })
+                .await;
         }
     });
 }
@@ -1,4 +1,11 @@
+pub(crate) mod bulk_reembed;
+pub(crate) mod document_vectors;
+pub(crate) mod fallback;
+pub(crate) mod field_weights;
+pub(crate) mod incremental;
+pub(crate) mod item_types;
 pub(crate) mod listener;
 pub(crate) mod models;
+pub(crate) mod pinning;
 pub mod reconciliation;
 pub(crate) mod scanner;
@@ -1,8 +1,15 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use sqlx::types::chrono::{DateTime, Utc};
 use utoipa::ToSchema;
 
+use semantic_explorer_core::document_vectors::DocumentVectorMode;
+use semantic_explorer_core::models::QdrantWriteConsistency;
+
+use super::item_types::ItemTypeOverride;
+
 /// Dataset Transform: Processes a Dataset with 1-N embedders to create N Embedded Datasets
 /// One Dataset Transform can create multiple Embedded Datasets (one per embedder)
 #[derive(Serialize, ToSchema, FromRow, Debug, Clone)]
@@ -29,6 +36,39 @@ pub struct CreateDatasetTransform {
     pub title: String,
     pub source_dataset_id: i32,
     pub embedder_ids: Vec<i32>, // Must have at least 1 embedder
+    /// Qdrant collection naming template for the created embedded datasets,
+    /// e.g. `prod-{dataset_transform_id}-{embedder_id}`. Supported placeholders:
+    /// `{embedded_dataset_id}`, `{dataset_transform_id}`, `{source_dataset_id}`,
+    /// `{embedder_id}`, `{owner}`. Defaults to `embedded-dataset-{embedded_dataset_id}-{owner}`.
+    /// Names from a custom template are prefixed with `ed-{embedded_dataset_id}-`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection_name_template: Option<String>,
+    /// Metadata field (on the chunk, then the item) holding each item's type,
+    /// used to look up `item_type_overrides`. Defaults to `type`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_type_field: Option<String>,
+    /// Per-item-type text prefix and/or embedder, keyed by item type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_type_overrides: Option<HashMap<String, ItemTypeOverride>>,
+    /// Embed these fields separately and combine them by weight into one
+    /// vector, e.g. `{"title": 3, "text": 1}`. `text` is the chunk content,
+    /// `title` the item title, other names are metadata fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field_weights: Option<HashMap<String, f32>>,
+    /// Embedder to use while another is unreachable, keyed by the embedder
+    /// it stands in for, e.g. `{"4": 9}`. It must produce vectors of the same
+    /// dimensions; points it embeds are tagged for re-embedding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_embedders: Option<HashMap<i32, i32>>,
+    /// Also store one vector per dataset item, computed from its chunk
+    /// vectors, in a `<collection>-documents` collection next to the chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_vectors: Option<DocumentVectorMode>,
+    /// How the worker's Qdrant upserts are acknowledged, e.g.
+    /// `{"wait": true, "ordering": "strong"}`. Defaults to waiting for each
+    /// write with weak ordering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qdrant_write: Option<QdrantWriteConsistency>,
 }
 
 /// Request to update an existing Dataset Transform
@@ -41,6 +81,42 @@ pub struct UpdateDatasetTransform {
     pub job_config: Option<serde_json::Value>,
 }
 
+/// Request to re-embed several datasets with one embedder
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct BulkReembedRequest {
+    pub dataset_ids: Vec<i32>,
+    pub embedder_id: i32,
+}
+
+/// Progress of one dataset in a bulk re-embed
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct BulkReembedDatasetProgress {
+    pub dataset_id: i32,
+    pub dataset_transform_id: i32,
+    /// `queued`, `running`, `completed` or `failed`
+    pub status: String,
+    pub chunks_embedded: i64,
+    pub chunks_failed: i64,
+    pub chunks_to_process: i64,
+}
+
+/// Aggregate progress of a bulk re-embed
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct BulkReembedProgress {
+    pub bulk_reembed_id: i32,
+    pub embedder_id: i32,
+    pub total: usize,
+    /// Waiting for a free concurrency slot
+    pub queued: usize,
+    pub running: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub chunks_embedded: i64,
+    pub chunks_failed: i64,
+    pub chunks_to_process: i64,
+    pub datasets: Vec<BulkReembedDatasetProgress>,
+}
+
 /// Aggregate statistics for a Dataset Transform (across all embedded datasets)
 #[derive(Serialize, ToSchema, Debug, Clone, FromRow)]
 pub struct DatasetTransformStats {
@@ -0,0 +1,300 @@
+//! Embedder model pinning for dataset transforms.
+//!
+//! Editing an embedder's model or config while a transform is running would
+//! otherwise mix vectors from two models in one embedded dataset. When a run
+//! is triggered, the model, config and input limit of each of the
+//! transform's embedders are pinned under `pinned_embedders` in its
+//! `job_config`; the scanner builds every job from the pin, so the whole run
+//! (including scans resumed later) embeds with the same model. Connection
+//! settings (base URL, API key, batch size) still come from the embedder, and
+//! API keys are never copied into the pin.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use tracing::{info, warn};
+
+use semantic_explorer_core::embedding_stamp::EmbeddingStamp;
+use semantic_explorer_core::encryption::EncryptionService;
+use semantic_explorer_core::models::EmbedderConfig;
+
+use crate::auth::AuthenticatedUser;
+use crate::embedders::models::Embedder;
+use crate::storage::postgres::{dataset_transforms, embedders};
+use crate::transforms::dataset::item_types;
+use crate::transforms::dataset::models::DatasetTransform;
+
+/// Key in a dataset transform's `job_config` holding the pinned embedders
+pub const PINNED_EMBEDDERS_KEY: &str = "pinned_embedders";
+
+/// The model settings of an embedder as they were when a run was triggered
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PinnedEmbedder {
+    pub provider: String,
+    pub model: String,
+    /// Pinned model revision, when the embedder config sets one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    pub config: serde_json::Value,
+    pub max_input_tokens: i32,
+    /// Measured output dimensions of the pinned model, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u64>,
+    pub pinned_at: DateTime<Utc>,
+}
+
+impl PinnedEmbedder {
+    pub(crate) fn from_embedder(embedder: &Embedder) -> Result<Self> {
+        let embedder_config = item_types::embedder_config(embedder)?;
+        let stamp =
+            EmbeddingStamp::from_embedder_config(Some(embedder.embedder_id), &embedder_config);
+        Ok(Self {
+            provider: embedder_config.provider,
+            model: embedder_config.model,
+            model_version: stamp.model_version,
+            config: embedder_config.config,
+            max_input_tokens: embedder_config.max_input_tokens,
+            dimensions: embedder_config.dimensions,
+            pinned_at: Utc::now(),
+        })
+    }
+
+    /// Whether `embedder` would still embed with this model and config
+    fn matches(&self, embedder: &Embedder) -> bool {
+        self.provider == embedder.provider
+            && self.config == embedder.config
+            && self.max_input_tokens == embedder.max_input_tokens
+    }
+}
+
+/// Validate the pinned embedders in a `job_config` supplied on update.
+pub fn validate_job_config(job_config: &serde_json::Value) -> Result<(), String> {
+    match job_config.get(PINNED_EMBEDDERS_KEY) {
+        None | Some(serde_json::Value::Null) => Ok(()),
+        Some(pins) => serde_json::from_value::<HashMap<i32, PinnedEmbedder>>(pins.clone())
+            .map(|_| ())
+            .map_err(|e| format!("invalid pinned_embedders: {e}")),
+    }
+}
+
+/// All pins recorded in `job_config`
+pub fn pinned_embedders(job_config: &serde_json::Value) -> HashMap<i32, PinnedEmbedder> {
+    job_config
+        .get(PINNED_EMBEDDERS_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Keep the pins of `existing` when an update replaces the `job_config`
+/// without mentioning them, so editing a transform doesn't unpin its run.
+pub fn carry_over_pins(job_config: &mut serde_json::Value, existing: &serde_json::Value) {
+    if job_config.get(PINNED_EMBEDDERS_KEY).is_some() {
+        return;
+    }
+    if let (Some(config), Some(pins)) = (
+        job_config.as_object_mut(),
+        existing.get(PINNED_EMBEDDERS_KEY),
+    ) {
+        config.insert(PINNED_EMBEDDERS_KEY.to_string(), pins.clone());
+    }
+}
+
+/// Pins for `embedders`: the existing pin of each embedder is kept unless
+/// `repin` is set, and embedders without one are pinned as they are now.
+/// Returns `None` when nothing changed.
+pub(crate) fn pins_for(
+    existing: &HashMap<i32, PinnedEmbedder>,
+    embedders: &[Embedder],
+    repin: bool,
+) -> Result<Option<HashMap<i32, PinnedEmbedder>>> {
+    let mut pins = existing.clone();
+    let mut changed = false;
+    for embedder in embedders {
+        if repin || !pins.contains_key(&embedder.embedder_id) {
+            pins.insert(
+                embedder.embedder_id,
+                PinnedEmbedder::from_embedder(embedder)?,
+            );
+            changed = true;
+        }
+    }
+    Ok(changed.then_some(pins))
+}
+
+/// Job settings for `embedder`, with the model, config and input limit taken
+/// from its pin when there is one.
+pub(crate) fn embedder_config(
+    embedder: &Embedder,
+    pin: Option<&PinnedEmbedder>,
+) -> Result<EmbedderConfig> {
+    let Some(pin) = pin else {
+        return item_types::embedder_config(embedder);
+    };
+    if !pin.matches(embedder) {
+        warn!(
+            embedder_id = embedder.embedder_id,
+            pinned_model = %pin.model,
+            pinned_at = %pin.pinned_at,
+            "Embedder changed since the transform run was triggered, using the pinned model"
+        );
+    }
+    Ok(EmbedderConfig::new(
+        pin.provider.clone(),
+        embedder.base_url.clone(),
+        embedder.api_key.clone(),
+        pin.model.clone(),
+        pin.config.clone(),
+        embedder.batch_size,
+        pin.max_input_tokens,
+    )
+    .with_dimensions(pin.dimensions))
+}
+
+/// Pin the transform's embedders and store the pins. With `repin` (a new run
+/// being triggered) every embedder is pinned as it is now; otherwise only
+/// embedders without a pin are. Returns the transform's updated `job_config`.
+pub(crate) async fn pin_transform_embedders(
+    pool: &Pool<Postgres>,
+    encryption: &EncryptionService,
+    transform: &DatasetTransform,
+    repin: bool,
+) -> Result<serde_json::Value> {
+    let existing = pinned_embedders(&transform.job_config);
+    if !repin
+        && transform
+            .embedder_ids
+            .iter()
+            .all(|id| existing.contains_key(id))
+    {
+        return Ok(transform.job_config.clone());
+    }
+
+    let user = AuthenticatedUser(transform.owner_display_name.clone());
+    let embedders_list =
+        embedders::get_embedders_batch(pool, &user, &transform.embedder_ids, encryption).await?;
+    let Some(pins) = pins_for(&existing, &embedders_list, repin)? else {
+        return Ok(transform.job_config.clone());
+    };
+
+    let job_config = dataset_transforms::set_pinned_embedders(
+        pool,
+        transform.dataset_transform_id,
+        &serde_json::to_value(&pins)?,
+    )
+    .await?;
+    info!(
+        dataset_transform_id = transform.dataset_transform_id,
+        pinned = pins.len(),
+        "Pinned embedder models for dataset transform"
+    );
+    Ok(job_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn embedder(embedder_id: i32, config: serde_json::Value) -> Embedder {
+        Embedder {
+            embedder_id,
+            name: format!("embedder-{embedder_id}"),
+            owner_id: "owner".to_string(),
+            owner_display_name: "owner".to_string(),
+            provider: "openai".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: Some("sk-test".to_string()),
+            config,
+            batch_size: 64,
+            dimensions: 1536,
+            dimensions_verified: false,
+            max_input_tokens: 8191,
+            truncate_strategy: "NONE".to_string(),
+            collection_name: None,
+            is_public: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_pin_survives_embedder_change() {
+        let original = embedder(
+            4,
+            json!({ "model": "text-embedding-3-small", "revision": "2024-01" }),
+        );
+        let pins = pins_for(&HashMap::new(), std::slice::from_ref(&original), false)
+            .unwrap()
+            .unwrap();
+        let job_config = json!({ PINNED_EMBEDDERS_KEY: pins });
+        assert!(validate_job_config(&job_config).is_ok());
+
+        // The embedder's default model is changed while the run is in flight
+        let mut changed = original.clone();
+        changed.config = json!({ "model": "text-embedding-3-large" });
+        changed.api_key = Some("sk-rotated".to_string());
+
+        // Resumed scans don't re-pin an embedder that already has a pin
+        assert!(
+            pins_for(&pinned_embedders(&job_config), &[changed.clone()], false)
+                .unwrap()
+                .is_none()
+        );
+
+        let pin = pinned_embedders(&job_config).remove(&4).unwrap();
+        assert_eq!(pin.model_version.as_deref(), Some("2024-01"));
+        let config = embedder_config(&changed, Some(&pin)).unwrap();
+        assert_eq!(config.model, "text-embedding-3-small");
+        assert_eq!(config.config["revision"], "2024-01");
+        // Connection settings still follow the embedder
+        assert_eq!(config.api_key.as_deref(), Some("sk-rotated"));
+
+        // Without a pin the current model is used
+        let config = embedder_config(&changed, None).unwrap();
+        assert_eq!(config.model, "text-embedding-3-large");
+    }
+
+    #[test]
+    fn test_repin_takes_current_model() {
+        let original = embedder(4, json!({ "model": "text-embedding-3-small" }));
+        let existing = pins_for(&HashMap::new(), &[original], false)
+            .unwrap()
+            .unwrap();
+
+        let mut changed = embedder(4, json!({ "model": "text-embedding-3-large" }));
+        changed.max_input_tokens = 4096;
+        let added = embedder(9, json!({ "model": "nomic-embed-text" }));
+
+        let pins = pins_for(&existing, &[changed.clone(), added.clone()], false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pins[&4].model, "text-embedding-3-small");
+        assert_eq!(pins[&9].model, "nomic-embed-text");
+
+        let pins = pins_for(&existing, &[changed, added], true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pins[&4].model, "text-embedding-3-large");
+        assert_eq!(pins[&4].max_input_tokens, 4096);
+        assert!(!serde_json::to_string(&pins).unwrap().contains("sk-test"));
+    }
+
+    #[test]
+    fn test_carry_over_pins() {
+        let existing =
+            json!({ PINNED_EMBEDDERS_KEY: { "4": { "model": "a" } }, "field_weights": {} });
+
+        let mut job_config = json!({ "item_type_field": "kind" });
+        carry_over_pins(&mut job_config, &existing);
+        assert_eq!(job_config[PINNED_EMBEDDERS_KEY]["4"]["model"], "a");
+
+        let mut job_config = json!({ PINNED_EMBEDDERS_KEY: {} });
+        carry_over_pins(&mut job_config, &existing);
+        assert_eq!(job_config[PINNED_EMBEDDERS_KEY], json!({}));
+
+        assert!(validate_job_config(&json!({ PINNED_EMBEDDERS_KEY: [4] })).is_err());
+    }
+}
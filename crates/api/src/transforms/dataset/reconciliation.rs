@@ -23,7 +23,9 @@ use crate::storage::postgres::{
     INTERNAL_BATCH_SIZE, dataset_transform_batches, dataset_transform_stats, fetch_all_batched,
 };
 use crate::storage::postgres::{embedded_datasets, embedders};
+use crate::storage::qdrant::write;
 use crate::storage::s3 as s3_storage;
+use crate::transforms::dataset::{document_vectors, fallback, item_types};
 use semantic_explorer_core::encryption::EncryptionService;
 use semantic_explorer_core::models::{
     CollectionTransformJob, DatasetTransformJob, QdrantConnectionConfig,
@@ -353,6 +355,45 @@ async fn recover_failed_batches(ctx: &ReconciliationContext) -> Result<usize> {
                             embedder.max_input_tokens,
                         );
 
+                        let item_type_overrides = match item_types::resolve_item_type_overrides(
+                            &ctx.pool,
+                            &user,
+                            &ctx.encryption,
+                            &transform.job_config,
+                            &embedder,
+                        )
+                        .await
+                        {
+                            Ok(overrides) => overrides,
+                            Err(e) => {
+                                warn!(
+                                    batch_key = %batch.batch_key,
+                                    error = %e,
+                                    "Failed to resolve item type overrides for batch recovery, skipping"
+                                );
+                                continue;
+                            }
+                        };
+                        let fallback_embedder = match fallback::resolve_fallback_embedder(
+                            &ctx.pool,
+                            &user,
+                            &ctx.encryption,
+                            &transform.job_config,
+                            &embedder,
+                        )
+                        .await
+                        {
+                            Ok(fallback_embedder) => fallback_embedder,
+                            Err(e) => {
+                                warn!(
+                                    batch_key = %batch.batch_key,
+                                    error = %e,
+                                    "Failed to resolve fallback embedder for batch recovery, skipping"
+                                );
+                                continue;
+                            }
+                        };
+
                         info!(
                             dataset_transform_id = transform.dataset_transform_id,
                             batch_key = %batch.batch_key,
@@ -371,10 +412,17 @@ async fn recover_failed_batches(ctx: &ReconciliationContext) -> Result<usize> {
                             dataset_transform_id: transform.dataset_transform_id,
                             embedded_dataset_id: ed.embedded_dataset_id,
                             owner_id: transform.owner_id.clone(),
+                            embedder_id: Some(embedder.embedder_id),
                             embedder_config,
                             qdrant_config: ctx.qdrant_config.clone(),
                             collection_name: ed.collection_name.clone(),
                             batch_size: Some(embedder.batch_size as usize),
+                            item_type_overrides,
+                            fallback_embedder,
+                            document_vectors: document_vectors::document_vector_mode(
+                                &transform.job_config,
+                            ),
+                            write_consistency: write::write_consistency(&transform.job_config),
                         };
 
                         let payload = serde_json::to_vec(&job)?;
@@ -1,16 +1,21 @@
 use anyhow::Result;
 use async_nats::Client as NatsClient;
 use aws_sdk_s3::Client as S3Client;
+use qdrant_client::Qdrant;
 use sqlx::{Pool, Postgres};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use semantic_explorer_core::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use semantic_explorer_core::document_vectors::{DocumentVectorMode, POINT_ID_NAMESPACE};
 use semantic_explorer_core::encryption::EncryptionService;
-use semantic_explorer_core::models::{DatasetTransformJob, EmbedderConfig, QdrantConnectionConfig};
+use semantic_explorer_core::models::{
+    DatasetTransformJob, EmbedderConfig, FallbackEmbedder, ItemTypeEmbedding,
+    QdrantConnectionConfig, QdrantWriteConsistency,
+};
 use semantic_explorer_core::observability::{
     record_scanner_backpressure_skip, record_scanner_batches_created,
     record_scanner_circuit_breaker_trip, record_scanner_items_discovered,
@@ -19,6 +24,7 @@ use semantic_explorer_core::observability::{
 use semantic_explorer_core::storage::{DocumentUpload, upload_document};
 
 use crate::auth::AuthenticatedUser;
+use crate::datasets::models::DatasetItem;
 use crate::embedded_datasets::EmbeddedDataset;
 use crate::storage::postgres::dataset_transform_pending_batches::{
     self as pending_batches, CreatePendingBatch,
@@ -30,8 +36,15 @@ use crate::storage::postgres::datasets;
 use crate::storage::postgres::embedded_datasets;
 use crate::storage::postgres::embedders;
 use crate::storage::postgres::{INTERNAL_BATCH_SIZE, fetch_all_batched};
+use crate::storage::qdrant::write;
 use crate::storage::s3;
+use crate::transforms::dataset::document_vectors;
+use crate::transforms::dataset::fallback;
+use crate::transforms::dataset::field_weights;
+use crate::transforms::dataset::incremental;
+use crate::transforms::dataset::item_types;
 use crate::transforms::dataset::models::DatasetTransform;
+use crate::transforms::dataset::pinning;
 
 /// Backpressure state for scanner throttling (#3)
 enum BackpressureState {
@@ -118,7 +131,7 @@ async fn check_backpressure(
 
 /// Simple rate limiter for batch publishing (#8)
 /// Uses a fixed delay between operations to prevent overwhelming downstream systems.
-struct RateLimiter {
+pub(super) struct RateLimiter {
     delay: Duration,
 }
 
@@ -186,15 +199,20 @@ fn validate_transform_config(transform: &DatasetTransform) -> Result<()> {
 
 /// Configuration for batch processing of dataset items
 #[derive(Debug, Clone)]
-struct DatasetBatchConfig {
+pub(super) struct DatasetBatchConfig {
     embedder_config: EmbedderConfig,
     qdrant_config: QdrantConnectionConfig,
     s3_bucket: String,
     embedded_dataset_prefix: String,
     embedding_batch_size: usize,
+    item_type_overrides: HashMap<String, ItemTypeEmbedding>,
+    fallback_embedder: Option<FallbackEmbedder>,
+    document_vectors: Option<DocumentVectorMode>,
+    write_consistency: QdrantWriteConsistency,
 }
 
 #[tracing::instrument(name = "scan_active_dataset_transforms", skip_all)]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn scan_active_dataset_transforms(
     pool: &Pool<Postgres>,
     nats: &NatsClient,
@@ -202,6 +220,7 @@ pub(crate) async fn scan_active_dataset_transforms(
     s3_bucket_name: &str,
     encryption: &EncryptionService,
     qdrant_config: &QdrantConnectionConfig,
+    qdrant: &Qdrant,
     scanner_config: &ScannerConfig,
 ) -> Result<()> {
     let transforms = get_active_dataset_transforms_privileged(pool).await?;
@@ -218,6 +237,7 @@ pub(crate) async fn scan_active_dataset_transforms(
                 &transform,
                 encryption,
                 qdrant_config,
+                qdrant,
                 scanner_config,
             ),
         )
@@ -245,7 +265,7 @@ pub(crate) async fn scan_active_dataset_transforms(
 /// Scan a specific dataset transform by ID (privileged, for NATS triggers)
 #[tracing::instrument(
     name = "scan_dataset_transform",
-    skip(pool, nats, s3, encryption, qdrant_config, scanner_config),
+    skip(pool, nats, s3, encryption, qdrant_config, qdrant, scanner_config),
     fields(dataset_transform_id = %dataset_transform_id)
 )]
 #[allow(clippy::too_many_arguments)]
@@ -257,6 +277,7 @@ pub(crate) async fn scan_dataset_transform(
     dataset_transform_id: i32,
     encryption: &EncryptionService,
     qdrant_config: &QdrantConnectionConfig,
+    qdrant: &Qdrant,
     scanner_config: &ScannerConfig,
 ) -> Result<()> {
     let transform = get_dataset_transform_privileged(pool, dataset_transform_id).await?;
@@ -287,6 +308,7 @@ pub(crate) async fn scan_dataset_transform(
         &transform,
         encryption,
         qdrant_config,
+        qdrant,
         scanner_config,
     )
     .await
@@ -294,7 +316,7 @@ pub(crate) async fn scan_dataset_transform(
 
 #[tracing::instrument(
     name = "process_dataset_transform_scan",
-    skip(pool, nats, s3, transform, encryption, qdrant_config, scanner_config),
+    skip(pool, nats, s3, transform, encryption, qdrant_config, qdrant, scanner_config),
     fields(dataset_transform_id = %transform.dataset_transform_id, embedder_count = %transform.embedder_ids.len())
 )]
 #[allow(clippy::too_many_arguments)]
@@ -306,6 +328,7 @@ async fn process_dataset_transform_scan(
     transform: &DatasetTransform,
     encryption: &EncryptionService,
     qdrant_config: &QdrantConnectionConfig,
+    qdrant: &Qdrant,
     scanner_config: &ScannerConfig,
 ) -> Result<()> {
     info!(
@@ -421,6 +444,10 @@ async fn process_dataset_transform_scan(
     let embedders_list =
         embedders::get_embedders_batch(pool, &user, &embedder_ids, encryption).await?;
 
+    let pins = pinning::pinned_embedders(
+        &pinning::pin_transform_embedders(pool, encryption, transform, false).await?,
+    );
+
     let embedders_map: std::collections::HashMap<i32, _> = embedders_list
         .into_iter()
         .map(|embedder| (embedder.embedder_id, embedder))
@@ -440,26 +467,32 @@ async fn process_dataset_transform_scan(
             }
         };
 
-        let model = embedder
-            .config
-            .get("model")
-            .and_then(|m| m.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Embedder config must specify a 'model' field"))?
-            .to_string();
-
-        let embedder_config = EmbedderConfig::new(
-            embedder.provider.clone(),
-            embedder.base_url.clone(),
-            embedder.api_key.clone(),
-            model,
-            embedder.config.clone(),
-            embedder.batch_size,
-            embedder.max_input_tokens,
-        );
+        // Embed with the model pinned when the run was triggered, even if the
+        // embedder has been edited since
+        let embedder_config = pinning::embedder_config(embedder, pins.get(&embedder.embedder_id))?;
 
         // Use the embedder's configured batch size
         let embedding_batch_size = embedder.batch_size as usize;
 
+        let item_type_overrides = item_types::resolve_item_type_overrides(
+            pool,
+            &user,
+            encryption,
+            &transform.job_config,
+            embedder,
+        )
+        .await?;
+        let fallback_embedder = fallback::resolve_fallback_embedder(
+            pool,
+            &user,
+            encryption,
+            &transform.job_config,
+            embedder,
+        )
+        .await?;
+        let document_vectors = document_vectors::document_vector_mode(&transform.job_config);
+        let write_consistency = write::write_consistency(&transform.job_config);
+
         // Use single-bucket architecture with embedded-datasets prefix
         let s3_bucket = s3_bucket_name.to_string();
         let embedded_dataset_prefix = format!(
@@ -496,6 +529,8 @@ async fn process_dataset_transform_scan(
                 &embedded_dataset_prefix,
                 100,
                 continuation_token.as_deref(),
+                None,
+                None,
             )
             .await
             {
@@ -553,10 +588,15 @@ async fn process_dataset_transform_scan(
                 dataset_transform_id: transform.dataset_transform_id,
                 embedded_dataset_id: embedded_dataset.embedded_dataset_id,
                 owner_id: transform.owner_id.clone(),
+                embedder_id: Some(embedder.embedder_id),
                 embedder_config: embedder_config.clone(),
                 qdrant_config: qdrant_config.clone(),
                 collection_name: embedded_dataset.collection_name.clone(),
                 batch_size: Some(embedding_batch_size),
+                item_type_overrides: item_type_overrides.clone(),
+                fallback_embedder: fallback_embedder.clone(),
+                document_vectors,
+                write_consistency,
             };
 
             let payload = serde_json::to_vec(&job)?;
@@ -642,7 +682,38 @@ async fn process_dataset_transform_scan(
             s3_bucket: s3_bucket.clone(),
             embedded_dataset_prefix: embedded_dataset_prefix.clone(),
             embedding_batch_size,
+            item_type_overrides,
+            fallback_embedder,
+            document_vectors,
+            write_consistency,
         };
+
+        // Re-embed items edited since the last run and drop deleted ones.
+        // A failure here shouldn't hold up new items; it's retried next scan.
+        if let Some(version) = dataset_version {
+            match incremental::sync_changed_items(
+                pool,
+                s3,
+                nats,
+                qdrant,
+                transform,
+                &embedded_dataset,
+                &batch_config,
+                &rate_limiter,
+                &circuit_breaker,
+                scanner_config.max_batches_per_scan,
+                version,
+            )
+            .await
+            {
+                Ok(jobs) => total_jobs += jobs,
+                Err(e) => warn!(
+                    embedded_dataset_id = embedded_dataset.embedded_dataset_id,
+                    "Failed to sync changed dataset items: {}", e
+                ),
+            }
+        }
+
         let items_created = create_batches_from_dataset_items(
             pool,
             s3,
@@ -746,23 +817,166 @@ async fn create_batches_from_dataset_items(
     );
 
     // Convert dataset items to batch items (one per chunk)
+    let (all_batch_items, item_chunk_boundaries) =
+        build_batch_items(transform, embedded_dataset.embedded_dataset_id, &items);
+
+    if all_batch_items.is_empty() {
+        info!(
+            "No chunks found in modified items for embedded dataset {}",
+            embedded_dataset.embedded_dataset_id
+        );
+        return Ok(0);
+    }
+
+    info!(
+        "Created {} batch items from {} modified dataset items",
+        all_batch_items.len(),
+        items.len()
+    );
+
+    // Split into batches and upload to S3, then dispatch jobs
+    let DispatchedBatches {
+        jobs_created,
+        chunks_dispatched: actual_chunks_dispatched,
+        capped: was_batches_capped,
+    } = dispatch_batch_items(
+        pool,
+        s3,
+        nats,
+        transform,
+        embedded_dataset,
+        config,
+        rate_limiter,
+        circuit_breaker,
+        limits.max_batches_per_scan,
+        &all_batch_items,
+    )
+    .await?;
+
+    // Remember what the dispatched items looked like so a later scan can
+    // tell when they change
+    let dispatched_items = fully_dispatched_items(&item_chunk_boundaries, actual_chunks_dispatched);
+    if let Err(e) = embedded_datasets::upsert_item_hashes(
+        pool,
+        embedded_dataset.embedded_dataset_id,
+        &incremental::item_hashes(&items[..dispatched_items]),
+    )
+    .await
+    {
+        warn!(
+            embedded_dataset_id = embedded_dataset.embedded_dataset_id,
+            "Failed to record item hashes: {}", e
+        );
+    }
+
+    // Advance the item_id watermark based on what was actually processed.
+    // Items are ordered ASC by item_id, so we advance incrementally.
+    //
+    // The watermark is a simple item_id value. Since PostgreSQL SERIAL IDs are
+    // monotonically increasing, scanning by `item_id > last_processed_item_id`
+    // correctly finds all newly inserted items regardless of their created_at
+    // timestamp. This eliminates the MVCC race condition that occurred with
+    // timestamp-based watermarks.
+    //
+    // - No truncation, no batch cap: advance to the last item's item_id
+    // - Items truncated (but all batches dispatched): advance to the last fetched item's item_id
+    // - Batches capped: advance only to the item_id of the last item whose chunks
+    //   were fully included in dispatched batches. Remaining items will be picked up next scan.
+    // - Re-processing is always safe due to deterministic chunk UUIDs (Uuid::new_v5)
+    if jobs_created > 0 {
+        let new_item_id = if was_batches_capped {
+            // Only advance to cover items fully within dispatched batches.
+            item_chunk_boundaries
+                .iter()
+                .rfind(|(cumul, _)| *cumul <= actual_chunks_dispatched)
+                .map(|(_, id)| *id)
+                .unwrap_or(last_processed_item_id)
+        } else {
+            // All batches dispatched (whether truncated or not):
+            // advance to item_id of last processed item (last in ASC order)
+            item_chunk_boundaries
+                .last()
+                .map(|(_, id)| *id)
+                .unwrap_or(last_processed_item_id)
+        };
+
+        let now = chrono::Utc::now();
+        info!(
+            embedded_dataset_id = embedded_dataset.embedded_dataset_id,
+            was_truncated = was_truncated,
+            was_batches_capped = was_batches_capped,
+            new_item_id,
+            prev_item_id = last_processed_item_id,
+            "Advancing item_id watermark"
+        );
+
+        embedded_datasets::update_embedded_dataset_last_processed_at_to(
+            pool,
+            embedded_dataset.embedded_dataset_id,
+            now,
+            Some(new_item_id),
+        )
+        .await?;
+    }
+
+    // Self-trigger: always schedule a follow-up scan when items were found.
+    // This is essential for handling concurrent inserts (e.g. collection worker
+    // still populating the dataset). Without this, the scanner would stop after
+    // processing the first batch of items and wait up to 5 minutes for
+    // reconciliation to pick up the rest.
+    //
+    // Natural termination: when a follow-up scan finds 0 items, jobs_created=0,
+    // no self-trigger fires, and the scan loop stops.
+    if jobs_created > 0 {
+        info!(
+            embedded_dataset_id = embedded_dataset.embedded_dataset_id,
+            was_batches_capped,
+            was_truncated,
+            "Scheduling follow-up scan for remaining/concurrent items"
+        );
+        if let Err(e) = trigger_dataset_transform_scan(
+            nats,
+            transform.dataset_transform_id,
+            &transform.owner_id,
+        )
+        .await
+        {
+            warn!(
+                "Failed to schedule follow-up scan, will rely on reconciliation: {}",
+                e
+            );
+        }
+    }
+
+    Ok(jobs_created)
+}
+
+/// Batch items (one per chunk) for `items`, with the cumulative chunk count
+/// after each item as `(cumulative_chunks, item_id)`
+pub(super) fn build_batch_items(
+    transform: &DatasetTransform,
+    embedded_dataset_id: i32,
+    items: &[DatasetItem],
+) -> (Vec<serde_json::Value>, Vec<(usize, i32)>) {
     let mut all_batch_items: Vec<serde_json::Value> = Vec::new();
     // Track cumulative chunk count per item for watermark calculation.
     // Stores (cumulative_chunks, item_id) — items are ordered ASC by item_id.
     let mut item_chunk_boundaries: Vec<(usize, i32)> = Vec::new();
     // Use a namespace UUID for generating deterministic chunk IDs
     // This ensures the same item+chunk always gets the same UUID, enabling idempotent upserts
-    let namespace = Uuid::parse_str("6ba7b810-9dad-11d1-80b4-00c04fd430c8").unwrap(); // URL namespace UUID
-    for item in &items {
+    let namespace = POINT_ID_NAMESPACE;
+    let item_type_field = item_types::item_type_field(&transform.job_config);
+    let field_weights = field_weights::field_weights(&transform.job_config);
+    for item in items {
         for (chunk_idx, chunk) in item.chunks.iter().enumerate() {
             // Generate a deterministic UUID based on embedded_dataset_id, item_id, and chunk_index
             // This allows re-processing to update existing vectors rather than create duplicates
             let chunk_id_string = format!(
                 "ed-{}-item-{}-chunk-{}",
-                embedded_dataset.embedded_dataset_id, item.item_id, chunk_idx
+                embedded_dataset_id, item.item_id, chunk_idx
             );
             let chunk_uuid = Uuid::new_v5(&namespace, chunk_id_string.as_bytes());
-            let batch_item = serde_json::json!({
+            let mut batch_item = serde_json::json!({
                 "id": chunk_uuid.to_string(),
                 "text": chunk.content,
                 "payload": {
@@ -773,39 +987,78 @@ async fn create_batches_from_dataset_items(
                     "item_metadata": item.metadata
                 }
             });
+            if let Some(item_type) =
+                item_types::item_type_of(item_type_field, &chunk.metadata, &item.metadata)
+            {
+                batch_item["item_type"] = serde_json::json!(item_type);
+            }
+            if !field_weights.is_empty() {
+                let fields = field_weights::weighted_fields(
+                    &field_weights,
+                    &chunk.content,
+                    &item.title,
+                    &chunk.metadata,
+                    &item.metadata,
+                );
+                if !fields.is_empty() {
+                    batch_item["fields"] = serde_json::json!(fields);
+                }
+            }
             all_batch_items.push(batch_item);
         }
         let cumulative_chunks = all_batch_items.len();
         item_chunk_boundaries.push((cumulative_chunks, item.item_id));
     }
+    (all_batch_items, item_chunk_boundaries)
+}
 
-    if all_batch_items.is_empty() {
-        info!(
-            "No chunks found in modified items for embedded dataset {}",
-            embedded_dataset.embedded_dataset_id
-        );
-        return Ok(0);
-    }
+/// Number of leading items (per `build_batch_items` boundaries) whose chunks
+/// were all dispatched
+pub(super) fn fully_dispatched_items(
+    item_chunk_boundaries: &[(usize, i32)],
+    chunks_dispatched: usize,
+) -> usize {
+    item_chunk_boundaries
+        .iter()
+        .take_while(|(cumulative, _)| *cumulative <= chunks_dispatched)
+        .count()
+}
 
-    info!(
-        "Created {} batch items from {} modified dataset items",
-        all_batch_items.len(),
-        items.len()
-    );
+/// Outcome of [`dispatch_batch_items`]
+pub(super) struct DispatchedBatches {
+    pub(super) jobs_created: usize,
+    pub(super) chunks_dispatched: usize,
+    /// Whether batches were left out to stay within `max_batches`
+    pub(super) capped: bool,
+}
 
-    // Split into batches and upload to S3, then dispatch jobs
+/// Upload `all_batch_items` to S3 in batches of the embedder's batch size and
+/// publish a worker job for each, creating at most `max_batches` batches
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn dispatch_batch_items(
+    pool: &Pool<Postgres>,
+    s3: &S3Client,
+    nats: &NatsClient,
+    transform: &DatasetTransform,
+    embedded_dataset: &EmbeddedDataset,
+    config: &DatasetBatchConfig,
+    rate_limiter: &RateLimiter,
+    circuit_breaker: &Arc<CircuitBreaker>,
+    max_batches: usize,
+    all_batch_items: &[serde_json::Value],
+) -> Result<DispatchedBatches> {
     let mut jobs_created = 0;
     let mut actual_chunks_dispatched: usize = 0;
     let chunks_per_batch = config.embedding_batch_size;
     let total_batches = all_batch_items.len().div_ceil(chunks_per_batch);
 
     // Apply max batch limit
-    let effective_total = total_batches.min(limits.max_batches_per_scan);
+    let effective_total = total_batches.min(max_batches);
     let was_batches_capped = total_batches > effective_total;
     if was_batches_capped {
         warn!(
             total_batches = total_batches,
-            max_batches = limits.max_batches_per_scan,
+            max_batches = max_batches,
             "Capping batch creation at max_batches_per_scan limit"
         );
     }
@@ -885,10 +1138,15 @@ async fn create_batches_from_dataset_items(
             dataset_transform_id: transform.dataset_transform_id,
             embedded_dataset_id: embedded_dataset.embedded_dataset_id,
             owner_id: transform.owner_id.clone(),
+            embedder_id: Some(embedded_dataset.embedder_id),
             embedder_config: config.embedder_config.clone(),
             qdrant_config: config.qdrant_config.clone(),
             collection_name: embedded_dataset.collection_name.clone(),
             batch_size: Some(config.embedding_batch_size),
+            item_type_overrides: config.item_type_overrides.clone(),
+            fallback_embedder: config.fallback_embedder.clone(),
+            document_vectors: config.document_vectors,
+            write_consistency: config.write_consistency,
         };
 
         let payload = serde_json::to_vec(&job)?;
@@ -959,86 +1217,11 @@ async fn create_batches_from_dataset_items(
         record_scanner_batches_created("dataset", jobs_created as u64);
     }
 
-    // Advance the item_id watermark based on what was actually processed.
-    // Items are ordered ASC by item_id, so we advance incrementally.
-    //
-    // The watermark is a simple item_id value. Since PostgreSQL SERIAL IDs are
-    // monotonically increasing, scanning by `item_id > last_processed_item_id`
-    // correctly finds all newly inserted items regardless of their created_at
-    // timestamp. This eliminates the MVCC race condition that occurred with
-    // timestamp-based watermarks.
-    //
-    // - No truncation, no batch cap: advance to the last item's item_id
-    // - Items truncated (but all batches dispatched): advance to the last fetched item's item_id
-    // - Batches capped: advance only to the item_id of the last item whose chunks
-    //   were fully included in dispatched batches. Remaining items will be picked up next scan.
-    // - Re-processing is always safe due to deterministic chunk UUIDs (Uuid::new_v5)
-    if jobs_created > 0 {
-        let new_item_id = if was_batches_capped {
-            // Only advance to cover items fully within dispatched batches.
-            item_chunk_boundaries
-                .iter()
-                .rfind(|(cumul, _)| *cumul <= actual_chunks_dispatched)
-                .map(|(_, id)| *id)
-                .unwrap_or(last_processed_item_id)
-        } else {
-            // All batches dispatched (whether truncated or not):
-            // advance to item_id of last processed item (last in ASC order)
-            item_chunk_boundaries
-                .last()
-                .map(|(_, id)| *id)
-                .unwrap_or(last_processed_item_id)
-        };
-
-        let now = chrono::Utc::now();
-        info!(
-            embedded_dataset_id = embedded_dataset.embedded_dataset_id,
-            was_truncated = was_truncated,
-            was_batches_capped = was_batches_capped,
-            new_item_id,
-            prev_item_id = last_processed_item_id,
-            "Advancing item_id watermark"
-        );
-
-        embedded_datasets::update_embedded_dataset_last_processed_at_to(
-            pool,
-            embedded_dataset.embedded_dataset_id,
-            now,
-            Some(new_item_id),
-        )
-        .await?;
-    }
-
-    // Self-trigger: always schedule a follow-up scan when items were found.
-    // This is essential for handling concurrent inserts (e.g. collection worker
-    // still populating the dataset). Without this, the scanner would stop after
-    // processing the first batch of items and wait up to 5 minutes for
-    // reconciliation to pick up the rest.
-    //
-    // Natural termination: when a follow-up scan finds 0 items, jobs_created=0,
-    // no self-trigger fires, and the scan loop stops.
-    if jobs_created > 0 {
-        info!(
-            embedded_dataset_id = embedded_dataset.embedded_dataset_id,
-            was_batches_capped,
-            was_truncated,
-            "Scheduling follow-up scan for remaining/concurrent items"
-        );
-        if let Err(e) = trigger_dataset_transform_scan(
-            nats,
-            transform.dataset_transform_id,
-            &transform.owner_id,
-        )
-        .await
-        {
-            warn!(
-                "Failed to schedule follow-up scan, will rely on reconciliation: {}",
-                e
-            );
-        }
-    }
-
-    Ok(jobs_created)
+    Ok(DispatchedBatches {
+        jobs_created,
+        chunks_dispatched: actual_chunks_dispatched,
+        capped: was_batches_capped,
+    })
 }
 
 /// Trigger a dataset transform scan via NATS (non-blocking)
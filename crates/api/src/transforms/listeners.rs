@@ -3,17 +3,110 @@
 //! This module coordinates the startup of all transform result listeners.
 //! Each transform type (collection, dataset, visualization) has its own
 //! listener implementation in its respective submodule.
+//!
+//! Listeners hand each result to a [`KeyedExecutor`] keyed by transform ID:
+//! results for different transforms are applied concurrently, while results
+//! for the same transform are applied one at a time in delivery order, so a
+//! transform's status never runs ahead of the batches recorded for it.
 
 use anyhow::Result;
 use async_nats::Client as NatsClient;
 use aws_sdk_s3::Client as S3Client;
 use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Semaphore, oneshot};
 use tracing::{error, info, warn};
 
 use crate::transforms::collection::listener as collection_listener;
 use crate::transforms::dataset::listener as dataset_listener;
 use crate::transforms::visualization::listener as visualization_listener;
 
+/// Result listener configuration, loaded from env at startup.
+#[derive(Debug, Clone)]
+pub(crate) struct ResultListenerConfig {
+    /// Results handled at once per listener (default: 8)
+    pub max_concurrency: usize,
+}
+
+impl ResultListenerConfig {
+    pub(crate) fn from_env() -> Self {
+        Self {
+            max_concurrency: std::env::var("RESULT_LISTENER_MAX_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8)
+                .max(1),
+        }
+    }
+}
+
+/// Runs tasks concurrently up to a limit, while tasks sharing a key run one
+/// at a time in the order they were submitted.
+#[derive(Clone)]
+pub(crate) struct KeyedExecutor<K> {
+    permits: Arc<Semaphore>,
+    /// Completion signal of the latest task per key, tagged with its sequence
+    /// number so a finishing task only removes its own entry
+    lanes: Arc<Mutex<HashMap<K, (u64, oneshot::Receiver<()>)>>>,
+    next_seq: Arc<AtomicU64>,
+}
+
+impl<K: Eq + Hash + Clone + Send + 'static> KeyedExecutor<K> {
+    pub(crate) fn new(max_concurrency: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrency.max(1))),
+            lanes: Arc::new(Mutex::new(HashMap::new())),
+            next_seq: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Wait for a free slot, then spawn `task` to run once every task
+    /// submitted earlier with the same key has finished.
+    ///
+    /// A waiting task holds its slot, but the task it waits on was admitted
+    /// first and holds one too, so the chain always makes progress.
+    pub(crate) async fn submit<F>(&self, key: K, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("listener semaphore is never closed");
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let (done_tx, done_rx) = oneshot::channel();
+        let previous = self
+            .lanes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.clone(), (seq, done_rx))
+            .map(|(_, rx)| rx);
+        let lanes = self.lanes.clone();
+
+        tokio::spawn(async move {
+            if let Some(previous) = previous {
+                // Errors only mean the previous task panicked; carry on
+                let _ = previous.await;
+            }
+            task.await;
+            drop(permit);
+            {
+                let mut lanes = lanes.lock().unwrap_or_else(|e| e.into_inner());
+                if lanes.get(&key).is_some_and(|(latest, _)| *latest == seq) {
+                    lanes.remove(&key);
+                }
+            }
+            let _ = done_tx.send(());
+        });
+    }
+}
+
 /// Status update payload for SSE streams
 #[derive(serde::Serialize)]
 pub(crate) struct TransformStatusUpdate {
@@ -78,14 +171,23 @@ pub(crate) async fn start_result_listeners(
     pool: Pool<Postgres>,
     s3_client: S3Client,
     s3_bucket_name: String,
+    max_download_size_bytes: i64,
     nats_client: NatsClient,
 ) -> Result<()> {
+    let config = ResultListenerConfig::from_env();
+    info!(
+        max_concurrency = config.max_concurrency,
+        "Starting transform result listeners"
+    );
+
     // Start collection transform result listener
     collection_listener::start(collection_listener::CollectionListenerContext {
         pool: pool.clone(),
         s3_client: s3_client.clone(),
         s3_bucket_name: s3_bucket_name.clone(),
         nats_client: nats_client.clone(),
+        max_download_size_bytes,
+        max_concurrency: config.max_concurrency,
     });
 
     // Start dataset transform result listener
@@ -94,13 +196,86 @@ pub(crate) async fn start_result_listeners(
         s3_client: s3_client.clone(),
         s3_bucket_name: s3_bucket_name.clone(),
         nats_client: nats_client.clone(),
+        max_concurrency: config.max_concurrency,
     });
 
     // Start visualization transform result listener
     visualization_listener::start(visualization_listener::VisualizationListenerContext {
         pool: pool.clone(),
         nats_client: nats_client.clone(),
+        max_concurrency: config.max_concurrency,
     });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::{Barrier, mpsc};
+
+    #[tokio::test]
+    async fn test_same_key_results_are_applied_in_order() {
+        let executor = KeyedExecutor::new(4);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // Earlier results take longer, so only ordering keeps them first
+        for (n, delay_ms) in [(1, 60), (2, 20), (3, 0)] {
+            let tx = tx.clone();
+            executor
+                .submit(7, async move {
+                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                    tx.send(n).unwrap();
+                })
+                .await;
+        }
+        let mut applied = Vec::new();
+        for _ in 0..3 {
+            applied.push(rx.recv().await.unwrap());
+        }
+        assert_eq!(applied, vec![1, 2, 3]);
+        assert!(executor.lanes.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_run_concurrently() {
+        let executor = KeyedExecutor::new(2);
+        // Each task waits for the other, so this only finishes if both run at once
+        let barrier = Arc::new(Barrier::new(2));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for key in [1, 2] {
+            let (barrier, tx) = (barrier.clone(), tx.clone());
+            executor
+                .submit(key, async move {
+                    barrier.wait().await;
+                    tx.send(key).unwrap();
+                })
+                .await;
+        }
+        let finished = tokio::time::timeout(Duration::from_secs(5), async {
+            (rx.recv().await.unwrap(), rx.recv().await.unwrap())
+        })
+        .await;
+        assert!(finished.is_ok(), "tasks for different keys did not overlap");
+    }
+
+    #[tokio::test]
+    async fn test_submit_waits_for_a_free_slot() {
+        let executor = KeyedExecutor::new(1);
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        executor
+            .submit(1, async move {
+                let _ = release_rx.await;
+            })
+            .await;
+
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), executor.submit(2, async {})).await;
+        assert!(blocked.is_err(), "second task admitted past the limit");
+
+        release_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), executor.submit(2, async {}))
+            .await
+            .expect("slot was not released");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod collection;
+pub mod concurrency;
 pub mod dataset;
 pub mod listeners;
 pub mod trigger;
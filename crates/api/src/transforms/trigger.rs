@@ -19,11 +19,13 @@ use async_nats::{
 };
 use aws_sdk_s3::Client as S3Client;
 use futures_util::StreamExt;
+use qdrant_client::Qdrant;
 use sqlx::{Pool, Postgres};
 use std::time::Duration;
 use tracing::{Instrument, error, info, warn};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+use semantic_explorer_core::config::WorkerConfig;
 use semantic_explorer_core::encryption::EncryptionService;
 use semantic_explorer_core::models::{QdrantConnectionConfig, ScanTrigger};
 use semantic_explorer_core::nats::{ensure_consumer, extract_otel_context, inject_trace_context};
@@ -33,6 +35,7 @@ use semantic_explorer_core::observability::{
 };
 
 use super::collection::scanner as collection_scanner;
+use super::dataset::bulk_reembed;
 use super::dataset::reconciliation::{ReconciliationContext, run_reconciliation};
 use super::dataset::scanner as dataset_scanner;
 use super::dataset::scanner::ScannerConfig;
@@ -46,7 +49,9 @@ pub struct ScannerContext {
     pub s3_bucket_name: String,
     pub encryption: EncryptionService,
     pub qdrant_config: QdrantConnectionConfig,
+    pub qdrant: Qdrant,
     pub scanner_config: ScannerConfig,
+    pub worker_config: WorkerConfig,
 }
 
 /// Start the reconciliation trigger publisher.
@@ -263,6 +268,7 @@ async fn process_trigger_inner(ctx: &ScannerContext, msg: &Message) -> Result<()
                 transform_id,
                 &ctx.encryption,
                 &ctx.qdrant_config,
+                &ctx.qdrant,
                 &ctx.scanner_config,
             )
             .await
@@ -280,6 +286,7 @@ async fn process_trigger_inner(ctx: &ScannerContext, msg: &Message) -> Result<()
                 &ctx.s3_bucket_name,
                 &ctx.encryption,
                 &ctx.qdrant_config,
+                &ctx.qdrant,
                 &ctx.scanner_config,
             )
             .await
@@ -298,6 +305,18 @@ async fn process_trigger_inner(ctx: &ScannerContext, msg: &Message) -> Result<()
             };
             run_reconciliation(&reconciliation_ctx).await?;
 
+            // Start queued bulk re-embeds whose owners have freed a slot
+            if let Err(e) = bulk_reembed::start_queued(
+                &ctx.pool,
+                &ctx.nats,
+                &ctx.encryption,
+                &ctx.worker_config,
+            )
+            .await
+            {
+                warn!("Failed to start queued re-embeds: {}", e);
+            }
+
             // Backfill scans: catch files/items missed by event-driven triggers
             collection_scanner::scan_active_collection_transforms(
                 &ctx.pool,
@@ -314,6 +333,7 @@ async fn process_trigger_inner(ctx: &ScannerContext, msg: &Message) -> Result<()
                 &ctx.s3_bucket_name,
                 &ctx.encryption,
                 &ctx.qdrant_config,
+                &ctx.qdrant,
                 &ctx.scanner_config,
             )
             .await
@@ -12,13 +12,15 @@ use crate::storage::postgres::visualization_transforms::{self, VisualizationUpda
 use crate::transforms::visualization::models::VisualizationTransform;
 use semantic_explorer_core::models::VisualizationTransformResult;
 
-use super::super::listeners::publish_transform_status;
+use super::super::listeners::{KeyedExecutor, publish_transform_status};
 
 /// Context for visualization listener
 #[derive(Clone)]
 pub(crate) struct VisualizationListenerContext {
     pub pool: Pool<Postgres>,
     pub nats_client: NatsClient,
+    /// Results handled at once; results for one transform are applied in order
+    pub max_concurrency: usize,
 }
 
 /// Start the visualization transform result listener
@@ -70,6 +72,7 @@ pub(crate) fn start(context: VisualizationListenerContext) {
             consumer_name
         );
 
+        let executor = KeyedExecutor::new(context.max_concurrency);
         let mut messages = match consumer.messages().await {
             Ok(m) => m,
             Err(e) => {
@@ -93,10 +96,15 @@ pub(crate) fn start(context: VisualizationListenerContext) {
             );
             match serde_json::from_slice::<VisualizationTransformResult>(&msg.payload) {
                 Ok(result) => {
-                    handle_result(result, &context).await;
-                    if let Err(e) = msg.ack().await {
-                        error!("Failed to acknowledge message: {}", e);
-                    }
+                    let context = context.clone();
+                    executor
+                        .submit(result.visualization_transform_id, async move {
+                            handle_result(result, &context).await;
+                            if let Err(e) = msg.ack().await {
+                                error!("Failed to acknowledge message: {}", e);
+                            }
+                        })
+                        .await;
                 }
                 Err(e) => {
                     error!("Failed to deserialize visualization result: {}", e);
@@ -323,6 +331,10 @@ async fn handle_success_status(
         update = update.html_s3_key(s3_key);
     }
 
+    if let Some(ref s3_key) = result.points_s3_key {
+        update = update.points_s3_key(s3_key);
+    }
+
     if let Some(point_count) = result.point_count {
         update = update.point_count(point_count as i32);
     }
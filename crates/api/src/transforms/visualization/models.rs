@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use sqlx::types::chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 use utoipa::ToSchema;
 
+use semantic_explorer_core::models::{ClusteringMethod, TopicNamingMethod};
+
 /// Visualization Transform: Creates interactive visualizations from Embedded Datasets
 /// Generates UMAP reductions + HDBSCAN clustering + datamapplot visualizations
 #[derive(Serialize, ToSchema, FromRow, Debug, Clone)]
@@ -40,6 +43,8 @@ pub struct Visualization {
     #[schema(value_type = Option<String>, format = DateTime)]
     pub completed_at: Option<DateTime<Utc>>,
     pub html_s3_key: Option<String>,
+    /// JSON with the 2D coordinates and cluster of each point
+    pub points_s3_key: Option<String>,
     pub point_count: Option<i32>,
     pub cluster_count: Option<i32>,
     pub error_message: Option<String>,
@@ -47,6 +52,23 @@ pub struct Visualization {
     pub stats_json: Option<serde_json::Value>,
     #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTime<Utc>,
+    /// Loaded from `points_s3_key` when requested with `include_points`
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub points: Option<VisualizationPoints>,
+}
+
+/// 2D UMAP layout of a visualization, for drawing an interactive scatter.
+/// `ids`, `x`, `y` and `cluster_ids` are parallel; noise is cluster -1.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct VisualizationPoints {
+    /// Qdrant point IDs
+    pub ids: Vec<String>,
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub cluster_ids: Vec<i32>,
+    /// Topic name by cluster ID
+    pub cluster_names: BTreeMap<String, String>,
 }
 
 /// Request to create a new Visualization Transform
@@ -68,11 +90,20 @@ pub struct CreateVisualizationTransform {
     pub min_cluster_size: i32,
     #[serde(default)]
     pub min_samples: Option<i32>,
+    /// `hdbscan` (default) or `kmeans`
+    #[serde(default)]
+    pub clustering_method: ClusteringMethod,
+    /// Number of topics for `kmeans`
+    #[serde(default = "default_n_clusters")]
+    pub n_clusters: i32,
     // LLM naming configuration
     #[serde(default = "default_llm_batch_size")]
     pub llm_batch_size: i32,
     #[serde(default = "default_samples_per_cluster")]
     pub samples_per_cluster: i32,
+    /// `llm` (default), `tfidf` or `ctfidf`
+    #[serde(default)]
+    pub topic_naming_method: TopicNamingMethod,
     // Datamapplot visualization parameters
     #[serde(default = "default_min_fontsize")]
     pub min_fontsize: f32,
@@ -129,6 +160,10 @@ fn default_min_cluster_size() -> i32 {
     10
 }
 
+fn default_n_clusters() -> i32 {
+    10
+}
+
 fn default_llm_batch_size() -> i32 {
     10
 }
@@ -6,11 +6,17 @@ use uuid::Uuid;
 
 use semantic_explorer_core::encryption::EncryptionService;
 use semantic_explorer_core::models::{
-    LLMConfig, QdrantConnectionConfig, VisualizationConfig, VisualizationTransformJob,
+    LLMConfig, QdrantConnectionConfig, VisualizationConfig, VisualizationJobMode,
+    VisualizationTransformJob,
 };
 
 use crate::storage::postgres::{embedded_datasets, llms, visualization_transforms};
 
+/// Qdrant collection holding the topic centroids of a visualization transform
+pub(crate) fn topics_collection_name(visualization_transform_id: i32) -> String {
+    format!("visualization-{visualization_transform_id}-topics")
+}
+
 /// Trigger a visualization transform scan manually
 pub async fn trigger_visualization_transform_scan(
     pool: &Pool<Postgres>,
@@ -19,10 +25,11 @@ pub async fn trigger_visualization_transform_scan(
     owner: &str,
     encryption: &EncryptionService,
     qdrant_config: &QdrantConnectionConfig,
+    mode: VisualizationJobMode,
 ) -> Result<()> {
     info!(
-        "Manually triggering visualization transform scan for ID: {}",
-        visualization_transform_id
+        "Manually triggering visualization transform scan for ID: {} (mode: {:?})",
+        visualization_transform_id, mode
     );
 
     // Get transform
@@ -50,6 +57,39 @@ pub async fn trigger_visualization_transform_scan(
     let embedded_dataset =
         embedded_datasets::get_embedded_dataset(pool, owner, transform.embedded_dataset_id).await?;
 
+    // Assigning new points needs the points and topics of a full run
+    let fitted_points_s3_key = match mode {
+        VisualizationJobMode::Full => None,
+        VisualizationJobMode::AssignOnly => {
+            let fitted = visualization_transforms::get_latest_fitted_visualization(
+                pool,
+                visualization_transform_id,
+            )
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Visualization transform {} has no completed full run to assign new points to",
+                    visualization_transform_id
+                )
+            })?;
+            fitted.points_s3_key
+        }
+    };
+
+    let topics_collection_name = match transform.topics_collection_name.clone() {
+        Some(name) => name,
+        None => {
+            let name = topics_collection_name(visualization_transform_id);
+            visualization_transforms::set_topics_collection_name(
+                pool,
+                visualization_transform_id,
+                &name,
+            )
+            .await?;
+            name
+        }
+    };
+
     // Create visualization record
     let visualization =
         visualization_transforms::create_visualization(pool, visualization_transform_id).await?;
@@ -101,6 +141,14 @@ pub async fn trigger_visualization_transform_scan(
                     .get("min_samples")
                     .and_then(|v| v.as_i64())
                     .map(|v| v as i32),
+                clustering_method: viz_config
+                    .get("clustering_method")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default(),
+                n_clusters: viz_config
+                    .get("n_clusters")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(10) as i32,
                 topic_naming_llm_id: viz_config
                     .get("topic_naming_llm_id")
                     .and_then(|v| v.as_i64())
@@ -118,6 +166,18 @@ pub async fn trigger_visualization_transform_scan(
                     .and_then(|v| v.as_str())
                     .unwrap_or("These are representative texts from a document cluster:\n\n{{samples}}\n\nProvide a short, concise topic name (2-4 words) that captures the main theme. Respond with ONLY the topic name, nothing else.")
                     .to_string(),
+                topic_naming_method: viz_config
+                    .get("topic_naming_method")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default(),
+                assignment_distance_threshold: viz_config
+                    .get("assignment_distance_threshold")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.35) as f32,
+                refit_outlier_ratio: viz_config
+                    .get("refit_outlier_ratio")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.2) as f32,
                 // Use defaults for all datamapplot parameters
                 inline_data: true,
                 noise_label: "Unlabelled".to_string(),
@@ -206,6 +266,9 @@ pub async fn trigger_visualization_transform_scan(
         visualization_config,
         qdrant_config: qdrant_config.clone(),
         llm_config,
+        mode,
+        topics_collection_name: Some(topics_collection_name),
+        fitted_points_s3_key,
     };
 
     // Publish to NATS with retry
@@ -1,20 +1,17 @@
 //! File upload validation using magic bytes and compression detection.
 //!
-//! This module provides comprehensive validation for uploaded files including:
+//! This module provides validation for uploaded files including:
 //! - Magic byte verification using the `infer` crate
 //! - MIME type validation
-//! - File size limits
-use std::path::Path;
-
+//!
+//! Uploads are streamed, so the size limit (`S3_MAX_UPLOAD_SIZE_BYTES`) is
+//! enforced while the file is uploaded rather than here.
 use infer::Infer;
 
-/// Maximum allowed file size: 100MB
-const MAX_FILE_SIZE_BYTES: u64 = 100 * 1024 * 1024;
-
 /// Number of bytes read from file header for MIME type detection.
 /// The `infer` crate only inspects the first few hundred bytes,
 /// so 8 KiB is more than sufficient.
-const MAGIC_BYTES_READ_SIZE: usize = 8192;
+pub(crate) const MAGIC_BYTES_READ_SIZE: usize = 8192;
 
 /// Whitelist of allowed MIME types for document uploads
 /// This list matches the extraction capabilities in worker-collections
@@ -77,94 +74,8 @@ const ALLOWED_MIME_TYPES: &[&str] = &[
     "application/x-gzip",
 ];
 
-#[derive(Debug, Clone)]
-pub(crate) struct FileValidationResult {
-    pub(crate) is_valid: bool,
-    pub(crate) validation_errors: Vec<String>,
-    pub(crate) mime_type: Option<String>,
-}
-
-/// Validate an uploaded file without loading the entire file into memory.
-///
-/// Reads file metadata for size checking and only the first few KiB for
-/// MIME-type detection via magic bytes, keeping peak memory usage constant
-/// regardless of file size.
-///
-/// # Arguments
-/// * `file_path` - Path to the temporary upload file on disk
-/// * `filename` - The original filename for logging
-pub(crate) async fn validate_upload_file(file_path: &Path, filename: &str) -> FileValidationResult {
-    let mut errors = Vec::new();
-
-    let metadata = match tokio::fs::metadata(file_path).await {
-        Ok(m) => m,
-        Err(e) => {
-            tracing::error!(filename = %filename, error = %e, "Failed to read file metadata");
-            return FileValidationResult {
-                is_valid: false,
-                validation_errors: vec![format!("Failed to read file metadata: {e}")],
-                mime_type: None,
-            };
-        }
-    };
-
-    let file_size = metadata.len();
-    if file_size > MAX_FILE_SIZE_BYTES {
-        errors.push(format!(
-            "File exceeds maximum size of {} bytes ({}MB)",
-            MAX_FILE_SIZE_BYTES,
-            MAX_FILE_SIZE_BYTES / (1024 * 1024)
-        ));
-    }
-
-    let header = match read_file_header(file_path).await {
-        Ok(buf) => buf,
-        Err(e) => {
-            tracing::error!(filename = %filename, error = %e, "Failed to read file header");
-            return FileValidationResult {
-                is_valid: false,
-                validation_errors: vec![format!("Failed to read file: {e}")],
-                mime_type: None,
-            };
-        }
-    };
-
-    let detected_mime = detect_mime_type(&header);
-
-    tracing::debug!(
-        filename = %filename,
-        detected_mime = %detected_mime,
-        file_size = file_size,
-        "File validation started"
-    );
-
-    let is_valid = errors.is_empty();
-
-    if !is_valid {
-        tracing::warn!(
-            filename = %filename,
-            validation_errors = ?errors,
-            "File validation failed"
-        );
-    }
-
-    FileValidationResult {
-        is_valid,
-        validation_errors: errors,
-        mime_type: Some(detected_mime),
-    }
-}
-
-async fn read_file_header(path: &Path) -> std::io::Result<Vec<u8>> {
-    use tokio::io::AsyncReadExt;
-    let mut file = tokio::fs::File::open(path).await?;
-    let mut buf = vec![0u8; MAGIC_BYTES_READ_SIZE];
-    let bytes_read = file.read(&mut buf).await?;
-    buf.truncate(bytes_read);
-    Ok(buf)
-}
-
-fn detect_mime_type(header: &[u8]) -> String {
+/// MIME type of an upload from the magic bytes at the start of the file
+pub(crate) fn detect_mime_type(header: &[u8]) -> String {
     let infer = Infer::new();
     infer
         .get(header)
@@ -183,49 +94,6 @@ pub fn get_allowed_mime_types() -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
-    use tempfile::NamedTempFile;
-
-    fn write_temp_file(content: &[u8]) -> NamedTempFile {
-        let mut f = NamedTempFile::new().expect("failed to create temp file");
-        f.write_all(content)
-            .expect("failed to write temp file content");
-        f.flush().expect("failed to flush temp file");
-        f
-    }
-
-    #[tokio::test]
-    async fn test_validate_plain_text() {
-        let f = write_temp_file(b"Hello, world!");
-        let result = validate_upload_file(f.path(), "test.txt").await;
-        assert!(result.is_valid);
-    }
-
-    #[tokio::test]
-    async fn test_validate_file_too_large() {
-        let mut f = NamedTempFile::new().unwrap();
-        let chunk = vec![0u8; 1024 * 1024];
-        for _ in 0..101 {
-            f.write_all(&chunk).unwrap();
-        }
-        f.flush().unwrap();
-        let result = validate_upload_file(f.path(), "large.txt").await;
-        assert!(!result.is_valid);
-        assert!(
-            result
-                .validation_errors
-                .iter()
-                .any(|e| e.contains("exceeds maximum size"))
-        );
-    }
-
-    #[tokio::test]
-    async fn test_validate_mime_type_detection() {
-        let f = write_temp_file(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
-        let result = validate_upload_file(f.path(), "test.pdf").await;
-        assert!(result.is_valid);
-        assert_eq!(result.mime_type.as_deref(), Some("application/pdf"));
-    }
 
     #[test]
     fn test_detect_mime_type_plain_text() {
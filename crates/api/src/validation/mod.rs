@@ -1,3 +1,3 @@
 pub(crate) mod file_upload;
 
-pub(crate) use file_upload::{get_allowed_mime_types, validate_upload_file};
+pub(crate) use file_upload::{MAGIC_BYTES_READ_SIZE, detect_mime_type, get_allowed_mime_types};
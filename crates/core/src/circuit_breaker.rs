@@ -109,6 +109,7 @@ struct CircuitBreakerState {
     failure_count: u32,
     success_count: u32,
     last_failure_time: Option<Instant>,
+    last_success_time: Option<Instant>,
     opened_at: Option<Instant>,
 }
 
@@ -133,6 +134,7 @@ impl CircuitBreaker {
                 failure_count: 0,
                 success_count: 0,
                 last_failure_time: None,
+                last_success_time: None,
                 opened_at: None,
             }),
             total_requests: AtomicU64::new(0),
@@ -179,7 +181,7 @@ impl CircuitBreaker {
                         );
                         state.state = CircuitState::HalfOpen;
                         state.success_count = 0;
-                        self.state_transitions.fetch_add(1, Ordering::Relaxed);
+                        self.record_transition(CircuitState::HalfOpen);
                         true
                     } else {
                         self.total_rejections.fetch_add(1, Ordering::Relaxed);
@@ -204,6 +206,7 @@ impl CircuitBreaker {
     /// Record a successful operation
     pub async fn record_success(&self) {
         let mut state = self.state.write().await;
+        state.last_success_time = Some(Instant::now());
 
         match state.state {
             CircuitState::Closed => {
@@ -222,7 +225,7 @@ impl CircuitBreaker {
                     state.failure_count = 0;
                     state.success_count = 0;
                     state.opened_at = None;
-                    self.state_transitions.fetch_add(1, Ordering::Relaxed);
+                    self.record_transition(CircuitState::Closed);
                 }
             }
             CircuitState::Open => {
@@ -251,7 +254,7 @@ impl CircuitBreaker {
                     );
                     state.state = CircuitState::Open;
                     state.opened_at = Some(Instant::now());
-                    self.state_transitions.fetch_add(1, Ordering::Relaxed);
+                    self.record_transition(CircuitState::Open);
                 }
             }
             CircuitState::HalfOpen => {
@@ -262,7 +265,7 @@ impl CircuitBreaker {
                 state.state = CircuitState::Open;
                 state.success_count = 0;
                 state.opened_at = Some(Instant::now());
-                self.state_transitions.fetch_add(1, Ordering::Relaxed);
+                self.record_transition(CircuitState::Open);
             }
             CircuitState::Open => {
                 // Already open, update opened_at to extend timeout
@@ -271,6 +274,28 @@ impl CircuitBreaker {
         }
     }
 
+    /// Time left before an open circuit lets a trial request through, or
+    /// None when the circuit isn't open
+    pub async fn open_remaining(&self) -> Option<Duration> {
+        let state = self.state.read().await;
+        match (state.state, state.opened_at) {
+            (CircuitState::Open, Some(opened_at)) => {
+                Some(self.config.timeout.saturating_sub(opened_at.elapsed()))
+            }
+            _ => None,
+        }
+    }
+
+    /// When an operation last succeeded, if ever
+    pub async fn last_success(&self) -> Option<Instant> {
+        self.state.read().await.last_success_time
+    }
+
+    fn record_transition(&self, to: CircuitState) {
+        self.state_transitions.fetch_add(1, Ordering::Relaxed);
+        crate::observability::record_circuit_breaker_transition(&self.config.name, to);
+    }
+
     /// Get metrics for this circuit breaker
     pub fn metrics(&self) -> CircuitBreakerMetrics {
         CircuitBreakerMetrics {
@@ -0,0 +1,133 @@
+//! HTTP body compression for embedding requests to the inference API.
+//!
+//! Batch embed requests and responses are large JSON arrays. When
+//! `EMBEDDING_REQUEST_COMPRESSION` is set, the embedder compresses request
+//! bodies with `Content-Encoding` and asks for compressed responses with
+//! `Accept-Encoding`; the inference API's actix-web stack decodes the former
+//! and negotiates the latter.
+
+use std::io::{Read, Write};
+
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+/// zstd level used for request bodies; low levels keep CPU cost well below
+/// the network time saved on large float arrays
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl BodyCompression {
+    /// Read `EMBEDDING_REQUEST_COMPRESSION` (`none`, `gzip` or `zstd`)
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("EMBEDDING_REQUEST_COMPRESSION") {
+            Ok(value) => Self::parse(&value).context("Invalid EMBEDDING_REQUEST_COMPRESSION"),
+            Err(_) => Ok(Self::None),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "none" | "off" | "identity" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            other => bail!("unsupported compression '{other}', expected none, gzip or zstd"),
+        }
+    }
+
+    /// Encoding for a `Content-Encoding` response header value; `None` for
+    /// encodings this module can't decode
+    pub fn from_content_encoding(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "identity" => Some(Self::None),
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Value for `Content-Encoding` / `Accept-Encoding`, or `None` when disabled
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Zstd => Some("zstd"),
+        }
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Self::Zstd => Ok(zstd::encode_all(data, ZSTD_LEVEL)?),
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(data)
+                    .read_to_end(&mut decoded)
+                    .context("Failed to decode gzip body")?;
+                Ok(decoded)
+            }
+            Self::Zstd => zstd::decode_all(data).context("Failed to decode zstd body"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_and_shrinks_embedding_payloads() {
+        let body = serde_json::to_vec(&serde_json::json!({
+            "embeddings": vec![vec![0.125f32; 384]; 32],
+        }))
+        .unwrap();
+
+        for compression in [BodyCompression::Gzip, BodyCompression::Zstd] {
+            let encoded = compression.encode(&body).unwrap();
+            assert!(encoded.len() < body.len() / 4, "{compression:?}");
+            assert_eq!(compression.decode(&encoded).unwrap(), body);
+        }
+        assert_eq!(BodyCompression::None.encode(&body).unwrap(), body);
+        assert!(BodyCompression::Gzip.decode(b"not gzip").is_err());
+    }
+
+    #[test]
+    fn test_parse_and_header_values() {
+        assert_eq!(
+            BodyCompression::parse("ZSTD").unwrap(),
+            BodyCompression::Zstd
+        );
+        assert_eq!(
+            BodyCompression::parse("none").unwrap(),
+            BodyCompression::None
+        );
+        assert!(BodyCompression::parse("brotli").is_err());
+
+        assert_eq!(BodyCompression::Gzip.content_encoding(), Some("gzip"));
+        assert_eq!(BodyCompression::None.content_encoding(), None);
+        assert_eq!(
+            BodyCompression::from_content_encoding("x-gzip"),
+            Some(BodyCompression::Gzip)
+        );
+        assert_eq!(BodyCompression::from_content_encoding("br"), None);
+    }
+}
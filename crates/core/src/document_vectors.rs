@@ -0,0 +1,54 @@
+//! Document-level vectors for embedded datasets.
+//!
+//! Besides one point per chunk, a dataset transform can store one vector per
+//! dataset item (document), so whole documents can be searched and compared.
+//! Document points live in a sibling collection next to the chunk collection
+//! and carry the ids of the chunk points they were built from.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Namespace of the deterministic point ids the scanner and worker derive
+pub const POINT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6ba7b810_9dad_11d1_80b4_00c04fd430c8);
+
+/// Suffix of the collection holding an embedded dataset's document vectors
+pub const DOCUMENT_COLLECTION_SUFFIX: &str = "-documents";
+
+/// How a document's vector is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentVectorMode {
+    /// Normalized mean of the document's chunk vectors
+    Mean,
+}
+
+/// Collection holding the document vectors of the chunk collection `collection_name`
+pub fn document_collection_name(collection_name: &str) -> String {
+    format!("{collection_name}{DOCUMENT_COLLECTION_SUFFIX}")
+}
+
+/// Point id of an item's document vector. Like chunk ids it is derived from
+/// the embedded dataset and item, so recomputing it overwrites the old point.
+pub fn document_point_id(embedded_dataset_id: i32, item_id: i64) -> String {
+    let name = format!("ed-{embedded_dataset_id}-item-{item_id}-document");
+    Uuid::new_v5(&POINT_ID_NAMESPACE, name.as_bytes()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_point_id_is_stable_and_distinct_from_chunks() {
+        let id = document_point_id(3, 42);
+        assert_eq!(id, document_point_id(3, 42));
+        assert_ne!(id, document_point_id(4, 42));
+        let chunk = Uuid::new_v5(&POINT_ID_NAMESPACE, b"ed-3-item-42-chunk-0").to_string();
+        assert_ne!(id, chunk);
+        assert_eq!(
+            document_collection_name("embedded-dataset-3-alice"),
+            "embedded-dataset-3-alice-documents"
+        );
+    }
+}
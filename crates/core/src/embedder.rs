@@ -1,5 +1,7 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
@@ -7,9 +9,11 @@ use std::sync::OnceLock;
 use tokio::sync::Semaphore;
 use tokio::time::sleep;
 
-use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+use crate::compression::BodyCompression;
 use crate::http_client::HTTP_CLIENT;
 use crate::models::EmbedderConfig;
+use crate::retry::{RetryPolicy, inference_retry_policy};
 
 const DEFAULT_OPENAI_BATCH_SIZE: usize = 2048;
 const DEFAULT_COHERE_BATCH_SIZE: usize = 96;
@@ -33,6 +37,207 @@ fn inference_circuit_breaker() -> &'static Arc<CircuitBreaker> {
     })
 }
 
+/// Circuit breakers for external providers, one per endpoint, so an outage at
+/// one provider doesn't block embedders (or their fallbacks) using another.
+/// They share the `INFERENCE_CB_*` settings.
+static PROVIDER_CIRCUIT_BREAKERS: OnceLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> =
+    OnceLock::new();
+
+/// The circuit breaker guarding requests for `config`'s endpoint
+fn circuit_breaker_for(config: &EmbedderConfig) -> Arc<CircuitBreaker> {
+    if config.provider == "internal" {
+        return inference_circuit_breaker().clone();
+    }
+    let endpoint = format!(
+        "{}:{}",
+        config.provider,
+        config.base_url.trim_end_matches('/')
+    );
+    let mut breakers = PROVIDER_CIRCUIT_BREAKERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    breakers
+        .entry(endpoint)
+        .or_insert_with_key(|endpoint| {
+            CircuitBreaker::new(CircuitBreakerConfig::from_env_with_prefix(
+                endpoint,
+                "INFERENCE_CB",
+            ))
+        })
+        .clone()
+}
+
+/// How embedder requests are retried (set by `init_embedder`)
+static EMBEDDER_RETRY: OnceLock<EmbedderRetryConfig> = OnceLock::new();
+
+fn embedder_retry_config() -> &'static EmbedderRetryConfig {
+    EMBEDDER_RETRY.get_or_init(EmbedderRetryConfig::from_env)
+}
+
+/// Wait after a 503 that didn't say how long to back off
+const DEFAULT_503_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Retry settings for embedder requests
+#[derive(Debug, Clone)]
+pub struct EmbedderRetryConfig {
+    /// Attempts and backoff for failed requests (`EMBEDDER_RETRY_*`)
+    pub policy: RetryPolicy,
+    /// Consecutive 503s after which a batch is abandoned
+    /// (`EMBEDDER_RETRY_MAX_503_RETRIES`)
+    pub max_503_retries: u32,
+}
+
+impl EmbedderRetryConfig {
+    pub fn from_env() -> Self {
+        Self {
+            policy: inference_retry_policy(),
+            max_503_retries: std::env::var("EMBEDDER_RETRY_MAX_503_RETRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2),
+        }
+    }
+}
+
+/// Returned without calling the embedder while its circuit breaker is open.
+/// Nothing was sent, so the work can be retried unchanged once `retry_after`
+/// has passed; workers NAK the job for at least that long instead of
+/// failing it.
+#[derive(Debug, Clone)]
+pub struct EmbedderUnavailable {
+    pub retry_after: Duration,
+}
+
+impl std::fmt::Display for EmbedderUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Inference circuit breaker is open — embedding service unavailable, retry in {}s",
+            self.retry_after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for EmbedderUnavailable {}
+
+/// The [`EmbedderUnavailable`] behind `error`, if that is why it failed
+pub fn unavailable_cause(error: &anyhow::Error) -> Option<&EmbedderUnavailable> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<EmbedderUnavailable>())
+}
+
+/// An embedder whose vectors don't fit the Qdrant collection it writes to.
+/// Qdrant only rejects such vectors on upsert, after all the embedding work,
+/// so callers check before embedding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DimensionMismatch {
+    pub collection: String,
+    pub collection_dimensions: u64,
+    pub embedder_dimensions: u64,
+}
+
+impl std::fmt::Display for DimensionMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Embedder produces {}-dimensional vectors but Qdrant collection '{}' holds {}-dimensional vectors; \
+             use an embedder with matching dimensions or delete the collection so it is recreated",
+            self.embedder_dimensions, self.collection, self.collection_dimensions
+        )
+    }
+}
+
+impl std::error::Error for DimensionMismatch {}
+
+/// `Err` when `embedder_dimensions` differ from the vector size of `collection`
+pub fn check_dimensions(
+    collection: &str,
+    collection_dimensions: u64,
+    embedder_dimensions: u64,
+) -> std::result::Result<(), DimensionMismatch> {
+    if collection_dimensions == embedder_dimensions {
+        Ok(())
+    } else {
+        Err(DimensionMismatch {
+            collection: collection.to_string(),
+            collection_dimensions,
+            embedder_dimensions,
+        })
+    }
+}
+
+/// The [`DimensionMismatch`] behind `error`, if that is why it failed
+pub fn dimension_mismatch_cause(error: &anyhow::Error) -> Option<&DimensionMismatch> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<DimensionMismatch>())
+}
+
+/// Reachability of the embedding inference API, as reported by the worker
+/// `/status` endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbedderHealth {
+    /// State of the `INFERENCE_CB_*` circuit breaker
+    pub circuit_state: CircuitState,
+    /// Seconds since the last successful request, None if there was none yet
+    pub last_success_secs_ago: Option<u64>,
+    /// Seconds until an open circuit lets a trial request through
+    pub retry_after_secs: Option<u64>,
+}
+
+impl EmbedderHealth {
+    /// False while the circuit is open, when embedding jobs fail without
+    /// reaching the inference API
+    pub fn reachable(&self) -> bool {
+        self.circuit_state != CircuitState::Open
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "reachable": self.reachable(),
+            "circuit_state": self.circuit_state.to_string(),
+            "last_success_secs_ago": self.last_success_secs_ago,
+            "retry_after_secs": self.retry_after_secs,
+        })
+    }
+}
+
+/// Health of the embedding inference API, or None in processes that never
+/// called [`init_embedder`]
+pub async fn embedder_health() -> Option<EmbedderHealth> {
+    EMBEDDING_INFERENCE_API_URL.get()?;
+    Some(circuit_health(inference_circuit_breaker()).await)
+}
+
+async fn circuit_health(circuit: &CircuitBreaker) -> EmbedderHealth {
+    EmbedderHealth {
+        circuit_state: circuit.state().await,
+        last_success_secs_ago: circuit
+            .last_success()
+            .await
+            .map(|at| at.elapsed().as_secs()),
+        retry_after_secs: circuit.open_remaining().await.map(|d| d.as_secs()),
+    }
+}
+
+/// Bodies smaller than this are sent uncompressed
+const COMPRESSION_MIN_BYTES: usize = 1024;
+
+/// Compression for requests to the internal inference API
+/// (`EMBEDDING_REQUEST_COMPRESSION`, read once on first use)
+static REQUEST_COMPRESSION: OnceLock<BodyCompression> = OnceLock::new();
+
+fn request_compression() -> BodyCompression {
+    *REQUEST_COMPRESSION.get_or_init(|| {
+        BodyCompression::from_env().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Ignoring embedding request compression setting");
+            BodyCompression::None
+        })
+    })
+}
+
 /// Cached embedding inference API URL (set once at startup)
 static EMBEDDING_INFERENCE_API_URL: OnceLock<String> = OnceLock::new();
 
@@ -51,8 +256,19 @@ static SERVER_QUEUE_CAPACITY: AtomicU64 = AtomicU64::new(0);
 /// Must be called from main before any embedding requests.
 /// - `api_url`: URL of the local embedding inference API
 /// - `max_concurrent_requests`: maximum concurrent embedding API requests
+///
+/// Requests are retried per [`EmbedderRetryConfig::from_env`] behind the
+/// `INFERENCE_CB_*` circuit breaker, both read here. External providers get
+/// a breaker per endpoint with the same settings.
 pub fn init_embedder(api_url: &str, max_concurrent_requests: usize) {
     EMBEDDING_INFERENCE_API_URL.get_or_init(|| api_url.to_string());
+    let retry = embedder_retry_config();
+    inference_circuit_breaker();
+    tracing::info!(
+        max_retries = retry.policy.max_attempts,
+        max_503_retries = retry.max_503_retries,
+        "Initialized embedder retry policy"
+    );
     EMBEDDING_SEMAPHORE.get_or_init(|| {
         tracing::info!(
             max_concurrent = max_concurrent_requests,
@@ -184,6 +400,21 @@ fn get_embedding_inference_api_url() -> &'static str {
         .unwrap_or("http://localhost:8090")
 }
 
+/// Text embedded to measure an embedder's output dimensions
+const DIMENSION_PROBE_TEXT: &str = "dimension probe";
+
+/// Output dimensions of `config`'s model, measured by embedding one short text
+pub async fn probe_dimensions(config: &EmbedderConfig) -> Result<u64> {
+    let embeddings = generate_batch_embeddings(config, vec![DIMENSION_PROBE_TEXT], Some(1)).await?;
+    match embeddings.first().map(Vec::len) {
+        Some(dimensions) if dimensions > 0 => Ok(dimensions as u64),
+        _ => Err(anyhow::anyhow!(
+            "Embedder {} returned no vector for the dimension probe",
+            config.model
+        )),
+    }
+}
+
 pub async fn generate_batch_embeddings(
     config: &EmbedderConfig,
     texts: Vec<&str>,
@@ -305,9 +536,12 @@ async fn process_single_batch(config: &EmbedderConfig, texts: Vec<&str>) -> Resu
         }
         "internal" => {
             let model = &config.model;
+            // Workers embed documents; the service applies the model's
+            // document prefix, if it has one
             let body = serde_json::json!({
                 "texts": texts,
                 "model": model,
+                "input_type": "document",
             });
             let inference_url = get_embedding_inference_api_url();
             let url = format!("{}/api/embed/batch", inference_url.trim_end_matches('/'));
@@ -323,7 +557,28 @@ async fn process_single_batch(config: &EmbedderConfig, texts: Vec<&str>) -> Resu
         _ => return Err(anyhow::anyhow!("Unsupported provider: {}", config.provider)),
     };
 
-    let mut req = client.post(&url).json(&body);
+    // Only the internal inference API is known to accept compressed bodies
+    let compression = if config.provider == "internal" {
+        request_compression()
+    } else {
+        BodyCompression::None
+    };
+    let mut req = match compression.content_encoding() {
+        Some(encoding) => {
+            let json = serde_json::to_vec(&body)?;
+            let req = client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(reqwest::header::ACCEPT_ENCODING, encoding);
+            if json.len() >= COMPRESSION_MIN_BYTES {
+                req.header(reqwest::header::CONTENT_ENCODING, encoding)
+                    .body(compression.encode(&json)?)
+            } else {
+                req.body(json)
+            }
+        }
+        None => client.post(&url).json(&body),
+    };
 
     if needs_bearer_auth {
         if let Some(key) = &config.api_key {
@@ -336,175 +591,221 @@ async fn process_single_batch(config: &EmbedderConfig, texts: Vec<&str>) -> Resu
         }
     }
 
-    // Max retries for embedding operations.
-    // For 503 (VRAM pressure) we fail fast after 2 attempts — the VRAM
-    // situation won't resolve in seconds, and NATS redelivery (with NAK
-    // delay) is a better retry granularity for resource exhaustion.
-    let max_retries: u32 = 5;
-    let max_503_retries: u32 = 2;
+    let retry = embedder_retry_config();
+    let circuit = circuit_breaker_for(config);
+    let result = send_with_retries(retry, &circuit, model_name, || {
+        let request = req.try_clone();
+        let url = &url;
+        async move {
+            let Some(request) = request else {
+                return EmbedAttempt::Transport("Failed to clone request for retry".to_string());
+            };
+            let resp = match request.send().await {
+                Ok(resp) => resp,
+                Err(e) => {
+                    return EmbedAttempt::Transport(format!(
+                        "Failed to send request to {}: {}",
+                        url, e
+                    ));
+                }
+            };
+            if resp.status().is_success() {
+                // Read server-side backpressure headers before consuming body
+                update_server_backpressure(&resp);
+                return match read_json_body(resp).await {
+                    Ok(body) => EmbedAttempt::Success(body),
+                    Err(e) => {
+                        EmbedAttempt::Transport(format!("Failed to read embedder response: {}", e))
+                    }
+                };
+            }
+            let status = resp.status().as_u16();
+            let retry_after = resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let body = resp.text().await.unwrap_or_default();
+            EmbedAttempt::Status {
+                status,
+                retry_after,
+                body,
+            }
+        }
+    })
+    .await
+    .and_then(|body| parse_embeddings_response(config, body));
+
+    // Aggregate metrics: record once per batch with total duration
+    let batch_duration = batch_start.elapsed().as_secs_f64();
+    crate::observability::record_embedding_batch(
+        model_name,
+        batch_duration,
+        chunk_count,
+        result.is_ok(),
+    );
+
+    result
+}
+
+/// Outcome of one attempt at an embedder request. Kept apart from reqwest so
+/// the retry and circuit-breaker handling in `send_with_retries` can be
+/// driven by a mock transport in tests.
+enum EmbedAttempt {
+    /// 2xx response with its decoded JSON body
+    Success(serde_json::Value),
+    /// Any other response
+    Status {
+        status: u16,
+        retry_after: Option<Duration>,
+        body: String,
+    },
+    /// No usable response (connection, timeout or body errors)
+    Transport(String),
+}
+
+/// Send an embedder request with `send`, retrying transient failures per
+/// `retry` and feeding every outcome to `circuit`. While the circuit is open
+/// nothing is sent and the call fails with [`EmbedderUnavailable`].
+///
+/// 4xx responses are not retried. 503s (VRAM pressure) wait for the
+/// server's `Retry-After` instead of the backoff and give up after
+/// `max_503_retries` in a row — that situation won't resolve in seconds, and
+/// NATS redelivery (with NAK delay) is a better retry granularity for
+/// resource exhaustion.
+async fn send_with_retries<F, Fut>(
+    retry: &EmbedderRetryConfig,
+    circuit: &CircuitBreaker,
+    model: &str,
+    mut send: F,
+) -> Result<serde_json::Value>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = EmbedAttempt>,
+{
+    let attempts = retry.policy.max_attempts + 1; // +1 for the initial attempt
     let mut consecutive_503s: u32 = 0;
     let mut last_error = None;
-    let mut used_server_retry_delay = false; // Track if we already waited per server's Retry-After
-
-    let circuit = inference_circuit_breaker();
+    let mut delay = Duration::ZERO;
 
-    for attempt in 0..=max_retries {
-        // Check circuit breaker before each attempt
-        if !circuit.should_allow().await {
-            let batch_duration = batch_start.elapsed().as_secs_f64();
-            crate::observability::record_embedding_batch(
-                model_name,
-                batch_duration,
-                chunk_count,
-                false,
-            );
-            return Err(anyhow::anyhow!(
-                "Inference circuit breaker is open — embedding service unavailable"
-            ));
-        }
-        // Apply exponential backoff only if we didn't already use server's Retry-After delay
-        if attempt > 0 && !used_server_retry_delay {
-            let delay = Duration::from_secs(1 << (attempt - 1).min(4)); // Cap at 16 seconds
-            tracing::warn!(
-                attempt = attempt,
-                delay_secs = delay.as_secs(),
-                "Retrying embedder request after transient error"
-            );
+    for attempt in 1..=attempts {
+        if !delay.is_zero() {
             sleep(delay).await;
         }
-        used_server_retry_delay = false; // Reset for this attempt
-
-        let request = req
-            .try_clone()
-            .ok_or_else(|| anyhow::anyhow!("Failed to clone request for retry"))?;
-
-        match request.send().await {
-            Ok(resp) => {
-                if resp.status().is_success() {
-                    // Read server-side backpressure headers before consuming body
-                    update_server_backpressure(&resp);
-
-                    let response_body: serde_json::Value = resp.json().await?;
-                    let result = parse_embeddings_response(config, response_body);
-
-                    // Clear downstream pressure on success
-                    DOWNSTREAM_PRESSURE.store(false, Ordering::Relaxed);
-                    DOWNSTREAM_PRESSURE_SET_AT.store(0, Ordering::Relaxed);
-
-                    circuit.record_success().await;
-
-                    // Aggregate metrics: record once per batch with total duration
-                    let batch_duration = batch_start.elapsed().as_secs_f64();
-                    crate::observability::record_embedding_batch(
-                        model_name,
-                        batch_duration,
-                        chunk_count,
-                        result.is_ok(),
-                    );
-
-                    return result;
-                }
-
-                let status = resp.status();
 
-                // Don't retry 4xx client errors - these are non-transient failures
-                if status.is_client_error() {
-                    let text = resp.text().await.unwrap_or_default();
-                    tracing::error!(
-                        status = %status,
-                        error = %text,
-                        "Embedder API client error (non-retriable)"
-                    );
-                    return Err(anyhow::anyhow!("Embedder API error {}: {}", status, text));
-                }
+        // Check the circuit after any backoff, which may have tripped it meanwhile
+        if !circuit.should_allow().await {
+            let retry_after = circuit.open_remaining().await.unwrap_or_default();
+            return Err(EmbedderUnavailable { retry_after }.into());
+        }
 
-                // Handle 503 Service Unavailable with Retry-After header
-                if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
-                    // Signal downstream pressure to upstream workers
-                    DOWNSTREAM_PRESSURE.store(true, Ordering::Relaxed);
-                    let now = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs();
-                    DOWNSTREAM_PRESSURE_SET_AT.store(now, Ordering::Relaxed);
-
-                    let retry_after = resp
-                        .headers()
-                        .get("Retry-After")
-                        .and_then(|v| v.to_str().ok())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .unwrap_or(5);
-
-                    let text = resp.text().await.unwrap_or_default();
-
-                    consecutive_503s += 1;
-                    circuit.record_failure().await;
+        let reason = match send().await {
+            EmbedAttempt::Success(body) => {
+                // Clear downstream pressure on success
+                DOWNSTREAM_PRESSURE.store(false, Ordering::Relaxed);
+                DOWNSTREAM_PRESSURE_SET_AT.store(0, Ordering::Relaxed);
+                circuit.record_success().await;
+                return Ok(body);
+            }
+            // Don't retry 4xx client errors - these are non-transient failures
+            EmbedAttempt::Status { status, body, .. } if (400..500).contains(&status) => {
+                tracing::error!(
+                    status = status,
+                    error = %body,
+                    "Embedder API client error (non-retriable)"
+                );
+                return Err(anyhow::anyhow!("Embedder API error {}: {}", status, body));
+            }
+            EmbedAttempt::Status {
+                status: 503,
+                retry_after,
+                body,
+            } => {
+                // Signal downstream pressure to upstream workers
+                DOWNSTREAM_PRESSURE.store(true, Ordering::Relaxed);
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                DOWNSTREAM_PRESSURE_SET_AT.store(now, Ordering::Relaxed);
+
+                consecutive_503s += 1;
+                circuit.record_failure().await;
+                tracing::warn!(
+                    attempt = attempt,
+                    consecutive_503s = consecutive_503s,
+                    max_503_retries = retry.max_503_retries,
+                    "Embedding service at capacity (503), backing off"
+                );
+
+                // Fail fast on persistent VRAM pressure — retrying won't
+                // help, let the job NAK so NATS can redeliver later.
+                if consecutive_503s >= retry.max_503_retries {
                     tracing::warn!(
-                        attempt = attempt,
-                        retry_after_secs = retry_after,
                         consecutive_503s = consecutive_503s,
-                        max_503_retries = max_503_retries,
-                        "Embedding service at capacity (503), backing off"
+                        "Aborting batch: persistent 503 from embedding service"
                     );
-
-                    // Fail fast on persistent VRAM pressure — retrying won't
-                    // help, let the job NAK so NATS can redeliver later.
-                    if consecutive_503s >= max_503_retries {
-                        tracing::warn!(
-                            consecutive_503s = consecutive_503s,
-                            "Aborting batch: persistent 503 from embedding service"
-                        );
-                        // Record failure metrics
-                        let batch_duration = batch_start.elapsed().as_secs_f64();
-                        crate::observability::record_embedding_batch(
-                            model_name,
-                            batch_duration,
-                            chunk_count,
-                            false,
-                        );
-                        return Err(anyhow::anyhow!(
-                            "Embedding service persistently at capacity after {} consecutive 503 responses: {}",
-                            consecutive_503s,
-                            text
-                        ));
-                    }
-
-                    // Use the server-suggested retry delay (skip exponential backoff on next iteration)
-                    if attempt < max_retries {
-                        sleep(Duration::from_secs(retry_after)).await;
-                        used_server_retry_delay = true; // Skip exponential backoff on next attempt
-                    }
-
-                    last_error = Some(anyhow::anyhow!(
-                        "Embedding service at capacity (503): {}",
-                        text
+                    return Err(anyhow::anyhow!(
+                        "Embedding service persistently at capacity after {} consecutive 503 responses: {}",
+                        consecutive_503s,
+                        body
                     ));
-                    continue;
-                }
-
-                let text = resp.text().await.unwrap_or_default();
-
-                if status.is_client_error() {
-                    return Err(anyhow::anyhow!("Embedder API error {}: {}", status, text));
                 }
 
+                // Use the server-suggested retry delay instead of the backoff
+                delay = retry_after.unwrap_or(DEFAULT_503_RETRY_AFTER);
+                last_error = Some(anyhow::anyhow!(
+                    "Embedding service at capacity (503): {}",
+                    body
+                ));
+                "unavailable"
+            }
+            EmbedAttempt::Status { status, body, .. } => {
                 circuit.record_failure().await;
-                last_error = Some(anyhow::anyhow!("Embedder API error {}: {}", status, text));
+                delay = retry.policy.delay_for_attempt(attempt);
+                last_error = Some(anyhow::anyhow!("Embedder API error {}: {}", status, body));
+                "server_error"
             }
-            Err(e) => {
+            EmbedAttempt::Transport(e) => {
                 circuit.record_failure().await;
-                last_error = Some(anyhow::anyhow!("Failed to send request to {}: {}", url, e));
+                delay = retry.policy.delay_for_attempt(attempt);
+                last_error = Some(anyhow::anyhow!(e));
+                "transport"
             }
+        };
+
+        if attempt < attempts {
+            tracing::warn!(
+                attempt = attempt,
+                delay_ms = delay.as_millis() as u64,
+                reason = reason,
+                "Retrying embedder request after transient error"
+            );
+            crate::observability::record_embedder_retry(model, reason);
         }
     }
 
-    // Record failure metrics for this batch (aggregated)
-    let batch_duration = batch_start.elapsed().as_secs_f64();
-    crate::observability::record_embedding_batch(model_name, batch_duration, chunk_count, false);
-
     Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Unknown embedder error")))
 }
 
+/// Read a JSON response body, decoding it if the server compressed it
+async fn read_json_body(resp: reqwest::Response) -> Result<serde_json::Value> {
+    let encoding = resp
+        .headers()
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let bytes = resp.bytes().await?;
+    let body = match &encoding {
+        Some(encoding) => BodyCompression::from_content_encoding(encoding)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported response encoding '{}'", encoding))?
+            .decode(&bytes)?,
+        None => bytes.to_vec(),
+    };
+    Ok(serde_json::from_slice(&body)?)
+}
+
 fn parse_embeddings_response(
     config: &EmbedderConfig,
     response_body: serde_json::Value,
@@ -545,3 +846,212 @@ fn parse_embeddings_response(
         _ => Err(anyhow::anyhow!("Unsupported provider parsing")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_dimension_mismatch_detected() {
+        assert_eq!(check_dimensions("docs", 384, 384), Ok(()));
+
+        let mismatch = check_dimensions("docs", 384, 1536).unwrap_err();
+        assert_eq!(
+            mismatch,
+            DimensionMismatch {
+                collection: "docs".to_string(),
+                collection_dimensions: 384,
+                embedder_dimensions: 1536,
+            }
+        );
+        let message = mismatch.to_string();
+        assert!(message.contains("1536-dimensional vectors"));
+        assert!(message.contains("'docs' holds 384-dimensional"));
+
+        let error = anyhow::Error::new(mismatch.clone()).context("Job failed");
+        assert_eq!(dimension_mismatch_cause(&error), Some(&mismatch));
+        assert!(dimension_mismatch_cause(&anyhow::anyhow!("other")).is_none());
+    }
+
+    fn retry_config(max_attempts: u32, max_503_retries: u32) -> EmbedderRetryConfig {
+        EmbedderRetryConfig {
+            policy: RetryPolicy {
+                max_attempts,
+                initial_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+                backoff_multiplier: 1.0,
+                jitter_factor: 0.0,
+            },
+            max_503_retries,
+        }
+    }
+
+    fn circuit(failure_threshold: u32) -> Arc<CircuitBreaker> {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            name: "test-embedder".to_string(),
+            failure_threshold,
+            success_threshold: 1,
+            timeout: Duration::from_secs(60),
+            failure_window: Duration::from_secs(60),
+        })
+    }
+
+    /// Replays scripted attempts in place of HTTP, counting requests made
+    struct MockTransport {
+        attempts: Mutex<VecDeque<EmbedAttempt>>,
+        sent: AtomicU64,
+    }
+
+    impl MockTransport {
+        fn new(attempts: Vec<EmbedAttempt>) -> Self {
+            Self {
+                attempts: Mutex::new(attempts.into()),
+                sent: AtomicU64::new(0),
+            }
+        }
+
+        async fn send(&self) -> EmbedAttempt {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            self.attempts
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("more requests than scripted")
+        }
+
+        fn sent(&self) -> u64 {
+            self.sent.load(Ordering::SeqCst)
+        }
+    }
+
+    fn server_error() -> EmbedAttempt {
+        EmbedAttempt::Status {
+            status: 500,
+            retry_after: None,
+            body: "boom".to_string(),
+        }
+    }
+
+    fn success() -> EmbedAttempt {
+        EmbedAttempt::Success(serde_json::json!({"embeddings": [[0.1, 0.2]]}))
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried_until_success() {
+        let transport = MockTransport::new(vec![
+            server_error(),
+            EmbedAttempt::Transport("connection reset".to_string()),
+            success(),
+        ]);
+        let body = send_with_retries(&retry_config(3, 2), &circuit(10), "m", || transport.send())
+            .await
+            .unwrap();
+        assert_eq!(body["embeddings"][0][1], 0.2);
+        assert_eq!(transport.sent(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_and_persistent_503s_are_not_retried() {
+        let transport = MockTransport::new(vec![EmbedAttempt::Status {
+            status: 400,
+            retry_after: None,
+            body: "bad input".to_string(),
+        }]);
+        let err = send_with_retries(&retry_config(5, 2), &circuit(10), "m", || transport.send())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("400"));
+        assert_eq!(transport.sent(), 1);
+
+        let unavailable = || EmbedAttempt::Status {
+            status: 503,
+            retry_after: Some(Duration::ZERO),
+            body: "at capacity".to_string(),
+        };
+        let transport = MockTransport::new(vec![unavailable(), unavailable()]);
+        let err = send_with_retries(&retry_config(5, 2), &circuit(10), "m", || transport.send())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("persistently at capacity"));
+        assert_eq!(transport.sent(), 2);
+        assert!(unavailable_cause(&err).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_short_circuits_with_distinct_error() {
+        let circuit = circuit(2);
+        let transport = MockTransport::new(vec![server_error(), server_error()]);
+        // The second failure opens the circuit, so the third attempt is never sent
+        let err = send_with_retries(&retry_config(5, 2), &circuit, "m", || transport.send())
+            .await
+            .unwrap_err();
+        assert_eq!(transport.sent(), 2);
+        let unavailable = unavailable_cause(&err).expect("circuit-open error");
+        assert!(unavailable.retry_after <= Duration::from_secs(60));
+        assert!(unavailable.retry_after > Duration::from_secs(50));
+
+        // Later calls fail without touching the transport, even behind context
+        let transport = MockTransport::new(vec![]);
+        let err = send_with_retries(&retry_config(5, 2), &circuit, "m", || transport.send())
+            .await
+            .unwrap_err()
+            .context("sub-batch 0 failed");
+        assert_eq!(transport.sent(), 0);
+        assert!(unavailable_cause(&err).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_health_reflects_circuit_state() {
+        let circuit = circuit(2);
+        let health = circuit_health(&circuit).await;
+        assert!(health.reachable());
+        assert_eq!(health.last_success_secs_ago, None);
+
+        let transport = MockTransport::new(vec![success()]);
+        send_with_retries(&retry_config(0, 2), &circuit, "m", || transport.send())
+            .await
+            .unwrap();
+        assert_eq!(
+            circuit_health(&circuit).await.last_success_secs_ago,
+            Some(0)
+        );
+
+        // An unreachable embedder opens the circuit
+        let transport = MockTransport::new(vec![server_error(), server_error()]);
+        let _ = send_with_retries(&retry_config(5, 2), &circuit, "m", || transport.send()).await;
+        let health = circuit_health(&circuit).await;
+        assert!(!health.reachable());
+        assert_eq!(health.circuit_state, CircuitState::Open);
+        assert!(health.retry_after_secs.is_some());
+        assert_eq!(health.last_success_secs_ago, Some(0));
+
+        let json = health.to_json();
+        assert_eq!(json["reachable"], false);
+        assert_eq!(json["circuit_state"], "open");
+    }
+
+    #[test]
+    fn test_external_endpoints_have_their_own_circuit_breakers() {
+        let config = |provider: &str, base_url: &str| {
+            EmbedderConfig::new(
+                provider.to_string(),
+                base_url.to_string(),
+                None,
+                "m".to_string(),
+                serde_json::json!({}),
+                8,
+                512,
+            )
+        };
+
+        let openai = circuit_breaker_for(&config("openai", "https://api.openai.com/v1"));
+        let same = circuit_breaker_for(&config("openai", "https://api.openai.com/v1/"));
+        let cohere = circuit_breaker_for(&config("cohere", "https://api.cohere.ai/v1"));
+        assert!(Arc::ptr_eq(&openai, &same));
+        assert!(!Arc::ptr_eq(&openai, &cohere));
+
+        let internal = circuit_breaker_for(&config("internal", "http://localhost:8090"));
+        assert!(Arc::ptr_eq(&internal, inference_circuit_breaker()));
+    }
+}
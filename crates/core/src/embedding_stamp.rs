@@ -0,0 +1,281 @@
+//! Embedder version stamps stored in each Qdrant point's payload.
+//!
+//! Vectors produced by different models, model revisions or preprocessing
+//! settings are not comparable. The dataset worker stamps every point it
+//! upserts, and search compares the stamp of the embedder used for the query
+//! against the stamps on the returned points.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::models::EmbedderConfig;
+
+/// Payload key holding the [`EmbeddingStamp`] of a point
+pub const EMBEDDING_STAMP_PAYLOAD_KEY: &str = "embedding_stamp";
+
+/// Payload key holding the stamp of the embedder a point should have been
+/// embedded with, on points a fallback embedder produced while it was
+/// unavailable. Filtering on it finds the points to re-embed.
+pub const FALLBACK_FOR_PAYLOAD_KEY: &str = "embedding_fallback_for";
+
+/// Embedder config keys that affect throughput or reliability but not the
+/// vectors produced, so they are left out of the preprocessing fingerprint.
+const NON_PREPROCESSING_CONFIG_KEYS: &[&str] = &[
+    "batch_size",
+    "max_batch_size",
+    "timeout",
+    "timeout_secs",
+    "max_retries",
+    "max_concurrent_requests",
+    // Only changes how vectors are compared, not the vectors themselves
+    "distance_metric",
+];
+
+/// Config keys that pin a specific revision of the model weights
+const MODEL_VERSION_CONFIG_KEYS: &[&str] = &["model_version", "revision"];
+
+/// Identifies the embedder, model version and preprocessing that produced a
+/// vector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingStamp {
+    /// Embedder id (absent for points written by jobs that predate it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedder_id: Option<i32>,
+    pub model: String,
+    /// Pinned model revision, when the embedder config sets one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    /// Hash of everything in the embedder config that changes the vectors
+    pub preprocessing_fingerprint: String,
+}
+
+impl EmbeddingStamp {
+    pub fn new(
+        embedder_id: Option<i32>,
+        provider: &str,
+        model: &str,
+        config: &Value,
+        max_input_tokens: i32,
+    ) -> Self {
+        let model_version = MODEL_VERSION_CONFIG_KEYS
+            .iter()
+            .find_map(|key| config.get(key).and_then(|v| v.as_str()))
+            .map(str::to_string);
+
+        Self {
+            embedder_id,
+            model: model.to_string(),
+            model_version,
+            preprocessing_fingerprint: preprocessing_fingerprint(
+                provider,
+                model,
+                config,
+                max_input_tokens,
+            ),
+        }
+    }
+
+    pub fn from_embedder_config(embedder_id: Option<i32>, config: &EmbedderConfig) -> Self {
+        Self::new(
+            embedder_id,
+            &config.provider,
+            &config.model,
+            &config.config,
+            config.max_input_tokens,
+        )
+    }
+
+    /// Add this stamp to a point payload
+    pub fn stamp_payload(&self, payload: &mut Map<String, Value>) {
+        if let Ok(value) = serde_json::to_value(self) {
+            payload.insert(EMBEDDING_STAMP_PAYLOAD_KEY.to_string(), value);
+        }
+    }
+
+    /// Read the stamp from a point payload (or search match metadata)
+    pub fn from_payload(payload: &Value) -> Option<Self> {
+        payload
+            .get(EMBEDDING_STAMP_PAYLOAD_KEY)
+            .and_then(|stamp| serde_json::from_value(stamp.clone()).ok())
+    }
+
+    /// Describe how `indexed` differs from this (query-side) stamp, or `None`
+    /// if vectors produced under both are comparable.
+    pub fn mismatch(&self, indexed: &EmbeddingStamp) -> Option<String> {
+        let mut differences = Vec::new();
+
+        if let (Some(query_id), Some(indexed_id)) = (self.embedder_id, indexed.embedder_id)
+            && query_id != indexed_id
+        {
+            differences.push(format!("embedder {indexed_id} (query uses {query_id})"));
+        }
+        if self.model != indexed.model {
+            differences.push(format!(
+                "model '{}' (query uses '{}')",
+                indexed.model, self.model
+            ));
+        }
+        if self.model_version != indexed.model_version {
+            differences.push(format!(
+                "model version {} (query uses {})",
+                indexed.model_version.as_deref().unwrap_or("unpinned"),
+                self.model_version.as_deref().unwrap_or("unpinned")
+            ));
+        }
+        if self.preprocessing_fingerprint != indexed.preprocessing_fingerprint
+            && differences.is_empty()
+        {
+            differences.push(format!(
+                "preprocessing config {} (query uses {})",
+                indexed.preprocessing_fingerprint, self.preprocessing_fingerprint
+            ));
+        }
+
+        if differences.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "Indexed vectors were embedded with {}; scores may not be comparable until the dataset is re-embedded",
+                differences.join(", ")
+            ))
+        }
+    }
+}
+
+/// Stable hash of the provider, model, input limit and vector-affecting config.
+pub fn preprocessing_fingerprint(
+    provider: &str,
+    model: &str,
+    config: &Value,
+    max_input_tokens: i32,
+) -> String {
+    let mut config = config.clone();
+    if let Some(obj) = config.as_object_mut() {
+        for key in NON_PREPROCESSING_CONFIG_KEYS {
+            obj.remove(*key);
+        }
+    }
+
+    let mut canonical = String::new();
+    write_canonical(
+        &serde_json::json!({
+            "provider": provider,
+            "model": model,
+            "max_input_tokens": max_input_tokens,
+            "config": config,
+        }),
+        &mut canonical,
+    );
+
+    let digest = Sha256::digest(canonical.as_bytes());
+    hex::encode(&digest[..8])
+}
+
+/// JSON with object keys sorted, so key order in stored configs doesn't
+/// change the fingerprint
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn embedder_config(config: Value) -> EmbedderConfig {
+        EmbedderConfig::new(
+            "openai".to_string(),
+            "https://api.openai.com/v1".to_string(),
+            None,
+            "text-embedding-3-small".to_string(),
+            config,
+            100,
+            8191,
+        )
+    }
+
+    #[test]
+    fn test_stamp_round_trips_through_payload() {
+        let stamp = EmbeddingStamp::from_embedder_config(
+            Some(7),
+            &embedder_config(json!({ "model": "text-embedding-3-small", "revision": "2024-01" })),
+        );
+        assert_eq!(stamp.model_version.as_deref(), Some("2024-01"));
+
+        let mut payload = Map::new();
+        payload.insert("text".to_string(), json!("hello"));
+        stamp.stamp_payload(&mut payload);
+
+        let payload = Value::Object(payload);
+        assert_eq!(payload[EMBEDDING_STAMP_PAYLOAD_KEY]["embedder_id"], 7);
+        assert_eq!(EmbeddingStamp::from_payload(&payload), Some(stamp));
+        assert_eq!(EmbeddingStamp::from_payload(&json!({ "text": "x" })), None);
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable_and_ignores_operational_keys() {
+        let a = preprocessing_fingerprint(
+            "cohere",
+            "embed-english-v3.0",
+            &json!({ "truncate": "END", "input_type": "search_document" }),
+            512,
+        );
+        let reordered = preprocessing_fingerprint(
+            "cohere",
+            "embed-english-v3.0",
+            &json!({ "input_type": "search_document", "truncate": "END", "batch_size": 96 }),
+            512,
+        );
+        assert_eq!(a, reordered);
+
+        let changed = preprocessing_fingerprint(
+            "cohere",
+            "embed-english-v3.0",
+            &json!({ "truncate": "START", "input_type": "search_document" }),
+            512,
+        );
+        assert_ne!(a, changed);
+    }
+
+    #[test]
+    fn test_mismatch_reports_version_changes() {
+        let indexed = EmbeddingStamp::new(Some(1), "openai", "model-a", &json!({}), 512);
+        assert!(indexed.mismatch(&indexed.clone()).is_none());
+
+        let other_model = EmbeddingStamp::new(Some(1), "openai", "model-b", &json!({}), 512);
+        let warning = other_model.mismatch(&indexed).unwrap();
+        assert!(warning.contains("model 'model-a'"));
+
+        let other_preprocessing =
+            EmbeddingStamp::new(Some(1), "openai", "model-a", &json!({}), 256);
+        let warning = other_preprocessing.mismatch(&indexed).unwrap();
+        assert!(warning.contains("preprocessing config"));
+    }
+}
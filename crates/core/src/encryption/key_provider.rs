@@ -0,0 +1,167 @@
+use anyhow::{Result, anyhow};
+use sha2::{Digest, Sha256};
+use std::env;
+
+/// Prefix of ciphertexts encrypted with a static key, followed by the key id
+pub(crate) const STATIC_ENCRYPTION_PREFIX: &str = "enc:v2:";
+
+/// Number of hex characters of a key's SHA-256 digest used as its id
+pub(crate) const KEY_ID_LEN: usize = 8;
+
+/// A 256-bit AES key and the reference stored alongside everything it
+/// encrypts, from which the provider can recover the key again
+#[derive(Clone)]
+pub struct DataKey {
+    pub key: [u8; 32],
+    /// Must not contain ':'
+    pub key_ref: String,
+}
+
+/// Source of the data keys [`EncryptionService`](super::EncryptionService)
+/// encrypts secrets with
+///
+/// Calls may block (KMS round trips); the service asks for one data key when
+/// it is created and caches every key it resolves.
+pub trait KeyProvider: Send + Sync {
+    /// Prefix of ciphertexts written with this provider's keys
+    fn prefix(&self) -> &'static str;
+
+    /// Data key for new ciphertexts
+    fn data_key(&self) -> Result<DataKey>;
+
+    /// Data key of a ciphertext, from the reference stored with it
+    fn resolve(&self, key_ref: &str) -> Result<[u8; 32]>;
+
+    /// Whether ciphertexts with this reference use the provider's current
+    /// key, or should be re-encrypted
+    fn is_current(&self, _key_ref: &str) -> bool {
+        true
+    }
+
+    /// Keys to try on ciphertexts written before keys were referenced
+    fn legacy_keys(&self) -> Vec<[u8; 32]> {
+        Vec::new()
+    }
+}
+
+#[derive(Clone)]
+struct StaticKey {
+    id: String,
+    key: [u8; 32],
+}
+
+impl StaticKey {
+    fn new(key: [u8; 32]) -> Self {
+        let digest = hex::encode(Sha256::digest(key));
+        StaticKey {
+            id: digest[..KEY_ID_LEN].to_string(),
+            key,
+        }
+    }
+
+    fn from_hex(var: &str, key_str: &str) -> Result<Self> {
+        let key_bytes = hex::decode(key_str.trim())
+            .map_err(|_| anyhow!("{var} must be valid hex string of 64 characters (32 bytes)"))?;
+
+        if key_bytes.len() != 32 {
+            return Err(anyhow!(
+                "{var} must be exactly 32 bytes (64 hex characters), got {}",
+                key_bytes.len()
+            ));
+        }
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&key_bytes);
+        Ok(StaticKey::new(key))
+    }
+}
+
+/// Keys configured directly: `ENCRYPTION_MASTER_KEY` encrypts, and keys it
+/// replaced (`ENCRYPTION_PREVIOUS_KEYS`) still decrypt. Ciphertexts reference
+/// their key by a short id derived from it.
+pub struct StaticKeyProvider {
+    primary: StaticKey,
+    previous: Vec<StaticKey>,
+}
+
+impl StaticKeyProvider {
+    pub fn from_env() -> Result<Self> {
+        let master_key_str = env::var("ENCRYPTION_MASTER_KEY")
+            .map_err(|_| anyhow!("ENCRYPTION_MASTER_KEY environment variable not set"))?;
+        let previous_keys = env::var("ENCRYPTION_PREVIOUS_KEYS").unwrap_or_default();
+        let previous_keys: Vec<&str> = previous_keys
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .collect();
+
+        Self::from_hex_keys(&master_key_str, &previous_keys)
+    }
+
+    /// Provider for a primary key and the keys it replaced, all hex-encoded
+    /// 256-bit keys
+    pub fn from_hex_keys(master_key_str: &str, previous_key_strs: &[&str]) -> Result<Self> {
+        let primary = StaticKey::from_hex("ENCRYPTION_MASTER_KEY", master_key_str)?;
+        let previous = previous_key_strs
+            .iter()
+            .map(|key_str| StaticKey::from_hex("ENCRYPTION_PREVIOUS_KEYS entries", key_str))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_keys(primary, previous))
+    }
+
+    pub fn new(primary: [u8; 32], previous: Vec<[u8; 32]>) -> Self {
+        Self::from_keys(
+            StaticKey::new(primary),
+            previous.into_iter().map(StaticKey::new).collect(),
+        )
+    }
+
+    fn from_keys(primary: StaticKey, keys: Vec<StaticKey>) -> Self {
+        let mut previous: Vec<StaticKey> = Vec::with_capacity(keys.len());
+        for key in keys {
+            // The primary key may still be listed while a rotation rolls out
+            if key.id == primary.id || previous.iter().any(|k| k.id == key.id) {
+                continue;
+            }
+            previous.push(key);
+        }
+        StaticKeyProvider { primary, previous }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &StaticKey> {
+        std::iter::once(&self.primary).chain(self.previous.iter())
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn prefix(&self) -> &'static str {
+        STATIC_ENCRYPTION_PREFIX
+    }
+
+    fn data_key(&self) -> Result<DataKey> {
+        Ok(DataKey {
+            key: self.primary.key,
+            key_ref: self.primary.id.clone(),
+        })
+    }
+
+    fn resolve(&self, key_ref: &str) -> Result<[u8; 32]> {
+        self.keys()
+            .find(|k| k.id == key_ref)
+            .map(|k| k.key)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Secret was encrypted with unknown key '{key_ref}'; \
+                     add that key to ENCRYPTION_PREVIOUS_KEYS to decrypt it"
+                )
+            })
+    }
+
+    fn is_current(&self, key_ref: &str) -> bool {
+        key_ref == self.primary.id
+    }
+
+    fn legacy_keys(&self) -> Vec<[u8; 32]> {
+        self.keys().map(|k| k.key).collect()
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result, anyhow};
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::DataKeySpec;
+use base64::{Engine, engine::general_purpose::STANDARD as base64_engine};
+use std::env;
+use std::sync::mpsc;
+use std::thread;
+
+use super::key_provider::{DataKey, KeyProvider};
+
+/// Prefix of ciphertexts encrypted with a KMS data key, followed by the
+/// base64 wrapped data key
+pub(crate) const KMS_ENCRYPTION_PREFIX: &str = "enc:kms:v1:";
+
+/// Encryption context bound to every data key, so KMS only unwraps keys that
+/// were generated for this purpose
+const ENCRYPTION_CONTEXT: (&str, &str) = ("purpose", "semantic-explorer-secrets");
+
+enum KmsRequest {
+    GenerateDataKey(mpsc::Sender<Result<DataKey>>),
+    Decrypt(Vec<u8>, mpsc::Sender<Result<[u8; 32]>>),
+}
+
+/// Envelope encryption with AWS KMS: data keys are generated by KMS under
+/// `ENCRYPTION_KMS_KEY_ID` and stored wrapped in each ciphertext, so
+/// decryption only needs the ciphertext and access to the KMS key.
+///
+/// [`KeyProvider`] is synchronous, so KMS is called from a dedicated thread
+/// with its own runtime; callers block for one round trip.
+pub struct KmsKeyProvider {
+    requests: mpsc::Sender<KmsRequest>,
+}
+
+impl KmsKeyProvider {
+    /// Provider for `ENCRYPTION_KMS_KEY_ID` (key id, ARN or alias), in
+    /// `ENCRYPTION_KMS_REGION` or `AWS_REGION`. `ENCRYPTION_KMS_ENDPOINT_URL`
+    /// overrides the endpoint (e.g. LocalStack); `AWS_ENDPOINT_URL` is not
+    /// used since it points at the S3 store.
+    pub fn from_env() -> Result<Self> {
+        let key_id = env::var("ENCRYPTION_KMS_KEY_ID")
+            .ok()
+            .filter(|k| !k.trim().is_empty())
+            .ok_or_else(|| {
+                anyhow!("ENCRYPTION_KMS_KEY_ID is required when ENCRYPTION_PROVIDER=kms")
+            })?;
+        let region = env::var("ENCRYPTION_KMS_REGION")
+            .or_else(|_| env::var("AWS_REGION"))
+            .ok();
+        let endpoint_url = env::var("ENCRYPTION_KMS_ENDPOINT_URL").ok();
+        Self::new(key_id, region, endpoint_url)
+    }
+
+    pub fn new(
+        key_id: String,
+        region: Option<String>,
+        endpoint_url: Option<String>,
+    ) -> Result<Self> {
+        let (requests, receiver) = mpsc::channel::<KmsRequest>();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<()>>();
+
+        thread::Builder::new()
+            .name("kms-key-provider".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(anyhow!("failed to start KMS runtime: {e}")));
+                        return;
+                    }
+                };
+                let client = runtime.block_on(kms_client(region, endpoint_url));
+                let _ = ready_tx.send(Ok(()));
+
+                // Ends once the provider, and with it the sender, is dropped
+                while let Ok(request) = receiver.recv() {
+                    match request {
+                        KmsRequest::GenerateDataKey(reply) => {
+                            let _ =
+                                reply.send(runtime.block_on(generate_data_key(&client, &key_id)));
+                        }
+                        KmsRequest::Decrypt(wrapped, reply) => {
+                            let _ = reply.send(
+                                runtime.block_on(decrypt_data_key(&client, &key_id, wrapped)),
+                            );
+                        }
+                    }
+                }
+            })
+            .context("failed to spawn KMS key provider thread")?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| anyhow!("KMS key provider thread exited during startup"))??;
+        Ok(KmsKeyProvider { requests })
+    }
+
+    fn call<T>(&self, request: impl FnOnce(mpsc::Sender<Result<T>>) -> KmsRequest) -> Result<T> {
+        let (reply, response) = mpsc::channel();
+        self.requests
+            .send(request(reply))
+            .map_err(|_| anyhow!("KMS key provider thread has stopped"))?;
+        response
+            .recv()
+            .map_err(|_| anyhow!("KMS key provider thread has stopped"))?
+    }
+}
+
+impl KeyProvider for KmsKeyProvider {
+    fn prefix(&self) -> &'static str {
+        KMS_ENCRYPTION_PREFIX
+    }
+
+    fn data_key(&self) -> Result<DataKey> {
+        self.call(KmsRequest::GenerateDataKey)
+    }
+
+    fn resolve(&self, key_ref: &str) -> Result<[u8; 32]> {
+        let wrapped = base64_engine
+            .decode(key_ref)
+            .map_err(|e| anyhow!("Invalid wrapped data key: {e}"))?;
+        self.call(|reply| KmsRequest::Decrypt(wrapped, reply))
+    }
+}
+
+async fn kms_client(region: Option<String>, endpoint_url: Option<String>) -> aws_sdk_kms::Client {
+    let mut config_loader = aws_config::defaults(BehaviorVersion::latest());
+    if let Some(region) = region {
+        config_loader = config_loader.region(Region::new(region));
+    }
+    let shared_config = config_loader.load().await;
+
+    // Replaces any AWS_ENDPOINT_URL picked up from the environment
+    let kms_config = aws_sdk_kms::config::Builder::from(&shared_config)
+        .set_endpoint_url(endpoint_url)
+        .build();
+    aws_sdk_kms::Client::from_conf(kms_config)
+}
+
+async fn generate_data_key(client: &aws_sdk_kms::Client, key_id: &str) -> Result<DataKey> {
+    let output = client
+        .generate_data_key()
+        .key_id(key_id)
+        .key_spec(DataKeySpec::Aes256)
+        .encryption_context(ENCRYPTION_CONTEXT.0, ENCRYPTION_CONTEXT.1)
+        .send()
+        .await
+        .map_err(|e| anyhow!("KMS GenerateDataKey failed: {}", e.into_service_error()))?;
+
+    let key = output
+        .plaintext()
+        .ok_or_else(|| anyhow!("KMS GenerateDataKey returned no plaintext key"))?;
+    let wrapped = output
+        .ciphertext_blob()
+        .ok_or_else(|| anyhow!("KMS GenerateDataKey returned no wrapped key"))?;
+
+    Ok(DataKey {
+        key: to_key(key.as_ref())?,
+        key_ref: base64_engine.encode(wrapped.as_ref()),
+    })
+}
+
+async fn decrypt_data_key(
+    client: &aws_sdk_kms::Client,
+    key_id: &str,
+    wrapped: Vec<u8>,
+) -> Result<[u8; 32]> {
+    let output = client
+        .decrypt()
+        .key_id(key_id)
+        .ciphertext_blob(Blob::new(wrapped))
+        .encryption_context(ENCRYPTION_CONTEXT.0, ENCRYPTION_CONTEXT.1)
+        .send()
+        .await
+        .map_err(|e| anyhow!("KMS Decrypt failed: {}", e.into_service_error()))?;
+
+    let key = output
+        .plaintext()
+        .ok_or_else(|| anyhow!("KMS Decrypt returned no plaintext key"))?;
+    to_key(key.as_ref())
+}
+
+fn to_key(bytes: &[u8]) -> Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("KMS data key must be 32 bytes, got {}", bytes.len()))
+}
@@ -0,0 +1,487 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::STANDARD as base64_engine};
+use rand::RngExt;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+mod key_provider;
+mod kms;
+
+pub use key_provider::{DataKey, KeyProvider, StaticKeyProvider};
+pub use kms::KmsKeyProvider;
+
+use key_provider::STATIC_ENCRYPTION_PREFIX;
+
+/// Prefix of ciphertexts written before key rotation, which carry no key id
+const LEGACY_ENCRYPTION_PREFIX: &str = "enc:v1:";
+
+/// Common start of every ciphertext prefix
+const ENCRYPTED_MARKER: &str = "enc:";
+
+/// Encryption service for API keys and secrets
+/// Uses AES-256-GCM for authenticated encryption
+///
+/// The AES keys come from [`KeyProvider`]s, selected with
+/// `ENCRYPTION_PROVIDER`:
+/// - `static` (default): `ENCRYPTION_MASTER_KEY` encrypts, keys listed in
+///   `ENCRYPTION_PREVIOUS_KEYS` only decrypt secrets written before a
+///   rotation
+/// - `kms`: envelope encryption with a data key generated and wrapped by AWS
+///   KMS; the wrapped key is stored in each ciphertext. A static key that is
+///   still configured decrypts secrets written before the switch.
+///
+/// [`reencrypt`] moves a secret to the current key.
+///
+/// [`reencrypt`]: EncryptionService::reencrypt
+#[derive(Clone)]
+pub struct EncryptionService {
+    /// The first provider encrypts; all of them decrypt
+    providers: Vec<Arc<dyn KeyProvider>>,
+    current: DataKey,
+    /// Data keys resolved so far, by ciphertext prefix and key reference
+    resolved: Arc<Mutex<HashMap<String, [u8; 32]>>>,
+}
+
+impl EncryptionService {
+    /// Initialize the encryption service from environment variables
+    pub fn from_env() -> Result<Self> {
+        let provider = env::var("ENCRYPTION_PROVIDER").unwrap_or_else(|_| "static".to_string());
+        match provider.trim().to_lowercase().as_str() {
+            "" | "static" => Self::new(vec![Arc::new(StaticKeyProvider::from_env()?)]),
+            "kms" => {
+                let mut providers: Vec<Arc<dyn KeyProvider>> =
+                    vec![Arc::new(KmsKeyProvider::from_env()?)];
+                if env::var("ENCRYPTION_MASTER_KEY").is_ok_and(|k| !k.trim().is_empty()) {
+                    providers.push(Arc::new(StaticKeyProvider::from_env()?));
+                }
+                Self::new(providers)
+            }
+            other => bail!("ENCRYPTION_PROVIDER must be 'static' or 'kms', got '{other}'"),
+        }
+    }
+
+    /// Initialize the encryption service from a hex-encoded 256-bit key
+    pub fn from_hex_key(master_key_str: &str) -> Result<Self> {
+        Self::from_hex_keys(master_key_str, &[])
+    }
+
+    /// Initialize the encryption service from a primary key and the keys it
+    /// replaced, all hex-encoded 256-bit keys
+    pub fn from_hex_keys(master_key_str: &str, previous_key_strs: &[&str]) -> Result<Self> {
+        Self::new(vec![Arc::new(StaticKeyProvider::from_hex_keys(
+            master_key_str,
+            previous_key_strs,
+        )?)])
+    }
+
+    /// Encryption service whose first provider encrypts new secrets, and
+    /// whose providers all decrypt. Fetches the data key for new secrets.
+    pub fn new(providers: Vec<Arc<dyn KeyProvider>>) -> Result<Self> {
+        let Some(primary) = providers.first() else {
+            bail!("At least one key provider is required");
+        };
+        let current = primary.data_key()?;
+        if current.key_ref.contains(':') {
+            bail!("Data key reference must not contain ':'");
+        }
+        let resolved = HashMap::from([(
+            format!("{}{}", primary.prefix(), current.key_ref),
+            current.key,
+        )]);
+
+        Ok(EncryptionService {
+            providers,
+            current,
+            resolved: Arc::new(Mutex::new(resolved)),
+        })
+    }
+
+    /// Generate a new random master key (for key rotation or initial setup)
+    pub fn generate_master_key() -> String {
+        let mut rng = rand::rng();
+        let key: [u8; 32] = rng.random();
+        hex::encode(key)
+    }
+
+    /// Reference to the data key new ciphertexts are encrypted with: the key
+    /// id for static keys, the wrapped data key for KMS
+    pub fn primary_key_id(&self) -> &str {
+        &self.current.key_ref
+    }
+
+    /// Encrypt a secret (API key) using AES-256-GCM under the current data key
+    /// Returns prefixed base64-encoded ciphertext with nonce prepended
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.current.key));
+
+        let mut rng = rand::rng();
+        let mut nonce_bytes = [0u8; 12];
+        rng.fill(&mut nonce_bytes);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+
+        let ciphertext = cipher
+            .encrypt(nonce, plaintext.as_bytes())
+            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+
+        let mut encrypted_data = nonce_bytes.to_vec();
+        encrypted_data.extend_from_slice(&ciphertext);
+
+        Ok(format!(
+            "{}{}:{}",
+            self.providers[0].prefix(),
+            self.current.key_ref,
+            base64_engine.encode(&encrypted_data)
+        ))
+    }
+
+    /// Decrypt a secret (API key) encrypted with AES-256-GCM
+    ///
+    /// The data key is recovered from the reference stored in the ciphertext
+    /// by the provider that wrote it; legacy ciphertexts without one are
+    /// tried against every configured static key.
+    pub fn decrypt(&self, encrypted: &str) -> Result<String> {
+        for provider in &self.providers {
+            if let Some(rest) = encrypted.strip_prefix(provider.prefix()) {
+                let (key_ref, base64_data) = rest
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Invalid encrypted data: missing key reference"))?;
+                let key = self.resolve(provider.as_ref(), key_ref)?;
+                return decrypt_with(&key, base64_data);
+            }
+        }
+
+        let Some(base64_data) = encrypted.strip_prefix(LEGACY_ENCRYPTION_PREFIX) else {
+            if encrypted.starts_with(ENCRYPTED_MARKER) {
+                bail!("Secret was encrypted by a key provider that is not configured");
+            }
+            bail!(
+                "Invalid encrypted data: missing '{}' prefix",
+                self.providers[0].prefix()
+            );
+        };
+        let mut last_error = None;
+        for key in self.providers.iter().flat_map(|p| p.legacy_keys()) {
+            match decrypt_with(&key, base64_data) {
+                Ok(plaintext) => return Ok(plaintext),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No keys configured for legacy secrets")))
+    }
+
+    /// Re-encrypt a stored secret under the current data key, decrypting it
+    /// with whichever configured key it was written with
+    pub fn reencrypt(&self, encrypted: &str) -> Result<String> {
+        let plaintext = self.decrypt(encrypted)?;
+        self.encrypt(&plaintext)
+    }
+
+    /// Whether a stored secret was encrypted by another provider or with a
+    /// retired key, and should be passed through [`reencrypt`](Self::reencrypt)
+    pub fn needs_reencryption(&self, encrypted: &str) -> bool {
+        let primary = &self.providers[0];
+        match encrypted.strip_prefix(primary.prefix()) {
+            Some(rest) => rest
+                .split_once(':')
+                .is_none_or(|(key_ref, _)| !primary.is_current(key_ref)),
+            None => self.is_encrypted(encrypted),
+        }
+    }
+
+    /// Check if a string is encrypted using the deterministic prefix
+    pub fn is_encrypted(&self, data: &str) -> bool {
+        data.starts_with(LEGACY_ENCRYPTION_PREFIX)
+            || data.starts_with(STATIC_ENCRYPTION_PREFIX)
+            || data.starts_with(kms::KMS_ENCRYPTION_PREFIX)
+            || self.providers.iter().any(|p| data.starts_with(p.prefix()))
+    }
+
+    /// Data key of a ciphertext, asking the provider only the first time a
+    /// reference is seen
+    fn resolve(&self, provider: &dyn KeyProvider, key_ref: &str) -> Result<[u8; 32]> {
+        let cache_key = format!("{}{}", provider.prefix(), key_ref);
+        if let Some(key) = self.resolved_keys().get(&cache_key) {
+            return Ok(*key);
+        }
+        let key = provider.resolve(key_ref)?;
+        self.resolved_keys().insert(cache_key, key);
+        Ok(key)
+    }
+
+    fn resolved_keys(&self) -> std::sync::MutexGuard<'_, HashMap<String, [u8; 32]>> {
+        self.resolved.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn decrypt_with(key: &[u8; 32], base64_data: &str) -> Result<String> {
+    let encrypted_data = base64_engine
+        .decode(base64_data)
+        .map_err(|e| anyhow!("Failed to decode base64: {}", e))?;
+
+    if encrypted_data.len() < 12 {
+        return Err(anyhow!(
+            "Encrypted data too short (must contain at least 12-byte nonce)"
+        ));
+    }
+
+    let (nonce_bytes, ciphertext) = encrypted_data.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| anyhow!("Decryption failed: {}", e))?;
+
+    String::from_utf8(plaintext).map_err(|e| anyhow!("Decrypted data is not valid UTF-8: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::key_provider::KEY_ID_LEN;
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const OLD_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+    const NEW_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+
+    fn test_service(key: [u8; 32]) -> EncryptionService {
+        EncryptionService::new(vec![Arc::new(StaticKeyProvider::new(key, Vec::new()))]).unwrap()
+    }
+
+    /// Stands in for KMS: data keys are random and "wrapped" by XOR with a
+    /// fixed mask, and calls are counted
+    #[derive(Default)]
+    struct MockKmsProvider {
+        generated: AtomicUsize,
+        resolved: AtomicUsize,
+    }
+
+    const MOCK_MASK: u8 = 0x5a;
+
+    impl KeyProvider for MockKmsProvider {
+        fn prefix(&self) -> &'static str {
+            "enc:mock:"
+        }
+
+        fn data_key(&self) -> Result<DataKey> {
+            self.generated.fetch_add(1, Ordering::SeqCst);
+            let key: [u8; 32] = rand::rng().random();
+            let wrapped: Vec<u8> = key.iter().map(|b| b ^ MOCK_MASK).collect();
+            Ok(DataKey {
+                key,
+                key_ref: base64_engine.encode(wrapped),
+            })
+        }
+
+        fn resolve(&self, key_ref: &str) -> Result<[u8; 32]> {
+            self.resolved.fetch_add(1, Ordering::SeqCst);
+            let wrapped = base64_engine.decode(key_ref)?;
+            let key: Vec<u8> = wrapped.iter().map(|b| b ^ MOCK_MASK).collect();
+            key.try_into()
+                .map_err(|_| anyhow!("wrapped key has the wrong length"))
+        }
+    }
+
+    fn mock_service(mock: &Arc<MockKmsProvider>) -> EncryptionService {
+        EncryptionService::new(vec![mock.clone() as Arc<dyn KeyProvider>]).unwrap()
+    }
+
+    /// Encrypt the way services did before key ids were embedded
+    fn legacy_encrypt(key: &[u8; 32], plaintext: &str) -> String {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let nonce_bytes = [7u8; 12];
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_bytes())
+            .unwrap();
+        let mut data = nonce_bytes.to_vec();
+        data.extend_from_slice(&ciphertext);
+        format!("{LEGACY_ENCRYPTION_PREFIX}{}", base64_engine.encode(&data))
+    }
+
+    #[test]
+    fn test_generate_master_key() {
+        let key = EncryptionService::generate_master_key();
+        assert_eq!(key.len(), 64); // 32 bytes = 64 hex characters
+        assert!(hex::decode(&key).is_ok());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let service = test_service(*b"01234567890123456789012345678901");
+
+        let plaintext = "sk-1234567890abcdef";
+        let encrypted = service.encrypt(plaintext).expect("Encryption failed");
+
+        assert_ne!(plaintext, encrypted);
+        assert!(encrypted.starts_with(STATIC_ENCRYPTION_PREFIX));
+
+        let decrypted = service.decrypt(&encrypted).expect("Decryption failed");
+        assert_eq!(plaintext, decrypted);
+    }
+
+    #[test]
+    fn test_decrypt_without_prefix_fails() {
+        let service = test_service(*b"01234567890123456789012345678901");
+
+        let plaintext = "sk-test-key";
+        let encrypted = service.encrypt(plaintext).expect("Encryption failed");
+        let unprefixed = encrypted
+            .strip_prefix(STATIC_ENCRYPTION_PREFIX)
+            .expect("should have prefix");
+
+        let result = service.decrypt(unprefixed);
+        assert!(result.is_err(), "Decrypting without prefix should fail");
+    }
+
+    #[test]
+    fn test_is_encrypted() {
+        let service = test_service(*b"01234567890123456789012345678901");
+
+        let plaintext = "not_encrypted_key";
+        assert!(!service.is_encrypted(plaintext));
+
+        let base64_looking = "SGVsbG8gV29ybGQhIFRoaXMgaXMgYSBsb25nIGVub3VnaCBzdHJpbmc=";
+        assert!(!service.is_encrypted(base64_looking));
+
+        let encrypted = service.encrypt(plaintext).expect("Encryption failed");
+        assert!(service.is_encrypted(&encrypted));
+    }
+
+    #[test]
+    fn test_decrypt_wrong_key_fails() {
+        let service1 = test_service(*b"01234567890123456789012345678901");
+
+        let service2 = test_service(*b"10987654321fedcba9876543210fedcb");
+
+        let plaintext = "secret_api_key";
+        let encrypted = service1.encrypt(plaintext).expect("Encryption failed");
+
+        let result = service2.decrypt(&encrypted);
+        assert!(result.is_err(), "Decryption with wrong key should fail");
+    }
+
+    #[test]
+    fn test_decrypt_with_previous_key() {
+        let old = EncryptionService::from_hex_key(OLD_KEY).unwrap();
+        let encrypted = old.encrypt("sk-old").unwrap();
+
+        let rotated = EncryptionService::from_hex_keys(NEW_KEY, &[OLD_KEY]).unwrap();
+        assert_eq!(rotated.decrypt(&encrypted).unwrap(), "sk-old");
+        assert!(rotated.needs_reencryption(&encrypted));
+
+        // Without the old key the error names the missing key id
+        let new_only = EncryptionService::from_hex_key(NEW_KEY).unwrap();
+        let error = new_only.decrypt(&encrypted).unwrap_err().to_string();
+        assert!(error.contains(old.primary_key_id()), "{error}");
+    }
+
+    #[test]
+    fn test_encrypt_uses_primary_key() {
+        let rotated = EncryptionService::from_hex_keys(NEW_KEY, &[OLD_KEY]).unwrap();
+        let encrypted = rotated.encrypt("sk-new").unwrap();
+
+        let key_id = rotated.primary_key_id();
+        assert_eq!(key_id.len(), KEY_ID_LEN);
+        assert!(encrypted.starts_with(&format!("{STATIC_ENCRYPTION_PREFIX}{key_id}:")));
+        assert!(!rotated.needs_reencryption(&encrypted));
+
+        let old_only = EncryptionService::from_hex_key(OLD_KEY).unwrap();
+        assert!(old_only.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_reencrypt_moves_secret_to_primary_key() {
+        let old = EncryptionService::from_hex_key(OLD_KEY).unwrap();
+        let encrypted = old.encrypt("sk-rotate").unwrap();
+
+        let rotated = EncryptionService::from_hex_keys(NEW_KEY, &[OLD_KEY]).unwrap();
+        let reencrypted = rotated.reencrypt(&encrypted).unwrap();
+        assert!(!rotated.needs_reencryption(&reencrypted));
+
+        // The old key can be retired once everything is re-encrypted
+        let new_only = EncryptionService::from_hex_key(NEW_KEY).unwrap();
+        assert_eq!(new_only.decrypt(&reencrypted).unwrap(), "sk-rotate");
+    }
+
+    #[test]
+    fn test_legacy_ciphertext_decrypts_with_any_configured_key() {
+        let old_key: [u8; 32] = hex::decode(OLD_KEY).unwrap().try_into().unwrap();
+        let legacy = legacy_encrypt(&old_key, "sk-legacy");
+
+        let rotated = EncryptionService::from_hex_keys(NEW_KEY, &[OLD_KEY]).unwrap();
+        assert!(rotated.is_encrypted(&legacy));
+        assert!(rotated.needs_reencryption(&legacy));
+        assert_eq!(rotated.decrypt(&legacy).unwrap(), "sk-legacy");
+
+        let reencrypted = rotated.reencrypt(&legacy).unwrap();
+        assert!(reencrypted.starts_with(STATIC_ENCRYPTION_PREFIX));
+
+        let new_only = EncryptionService::from_hex_key(NEW_KEY).unwrap();
+        assert!(new_only.decrypt(&legacy).is_err());
+    }
+
+    #[test]
+    fn test_envelope_ciphertext_carries_wrapped_data_key() {
+        let mock = Arc::new(MockKmsProvider::default());
+        let writer = mock_service(&mock);
+        let first = writer.encrypt("sk-envelope").unwrap();
+        let second = writer.encrypt("sk-envelope-2").unwrap();
+
+        // One data key per service, reused for every secret
+        assert_eq!(mock.generated.load(Ordering::SeqCst), 1);
+        let wrapped = format!("enc:mock:{}:", writer.primary_key_id());
+        assert!(first.starts_with(&wrapped) && second.starts_with(&wrapped));
+        assert!(writer.is_encrypted(&first));
+
+        // Another instance has its own data key but unwraps the stored one,
+        // once per wrapped key
+        let reader = mock_service(&mock);
+        assert_ne!(reader.primary_key_id(), writer.primary_key_id());
+        assert_eq!(reader.decrypt(&first).unwrap(), "sk-envelope");
+        assert_eq!(reader.decrypt(&second).unwrap(), "sk-envelope-2");
+        assert_eq!(mock.resolved.load(Ordering::SeqCst), 1);
+        assert!(!reader.needs_reencryption(&first));
+    }
+
+    #[test]
+    fn test_tampered_envelope_fails() {
+        let mock = Arc::new(MockKmsProvider::default());
+        let service = mock_service(&mock);
+        let encrypted = service.encrypt("sk-envelope").unwrap();
+
+        // A different wrapped key unwraps to the wrong data key
+        let (_, payload) = encrypted
+            .strip_prefix("enc:mock:")
+            .and_then(|rest| rest.split_once(':'))
+            .unwrap();
+        let other_key = base64_engine.encode([0u8; 32]);
+        let tampered = format!("enc:mock:{other_key}:{payload}");
+        assert!(service.decrypt(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_switch_from_static_key_to_envelope_encryption() {
+        let static_service = EncryptionService::from_hex_key(OLD_KEY).unwrap();
+        let old_secret = static_service.encrypt("sk-static").unwrap();
+
+        let mock = Arc::new(MockKmsProvider::default());
+        let service = EncryptionService::new(vec![
+            mock.clone() as Arc<dyn KeyProvider>,
+            Arc::new(StaticKeyProvider::from_hex_keys(OLD_KEY, &[]).unwrap()),
+        ])
+        .unwrap();
+        assert!(service.needs_reencryption(&old_secret));
+
+        let moved = service.reencrypt(&old_secret).unwrap();
+        assert!(moved.starts_with("enc:mock:"));
+        assert!(!service.needs_reencryption(&moved));
+        assert_eq!(mock_service(&mock).decrypt(&moved).unwrap(), "sk-static");
+
+        // Without the static provider the old secret is recognised but not
+        // decryptable
+        let error = mock_service(&mock).decrypt(&old_secret).unwrap_err();
+        assert!(error.to_string().contains("not configured"), "{error}");
+    }
+}
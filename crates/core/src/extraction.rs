@@ -0,0 +1,274 @@
+//! Formats the collections worker can extract, and the extraction options
+//! that affect each of them.
+//!
+//! Lives in core so the API can advertise it without a round trip to the
+//! worker; worker-collections tests keep it in sync with its MIME dispatch.
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// An extraction option (see `ExtractionOptions` in worker-collections).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionOption {
+    PreserveFormatting,
+    ExtractTables,
+    TableFormat,
+    PreserveHeadings,
+    HeadingFormat,
+    PreserveLists,
+    PreserveCodeBlocks,
+    IncludeMetadata,
+    AppendMetadataToText,
+    SniffMimeFallback,
+    Trace,
+    MaxOutputBytes,
+    DetectLanguage,
+}
+
+use ExtractionOption::*;
+
+/// A group of MIME types handled by the same extractor.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FormatCapability {
+    #[schema(value_type = String)]
+    pub format: &'static str,
+    #[schema(value_type = Vec<String>)]
+    pub mime_types: &'static [&'static str],
+    /// Options that change the output for this format.
+    #[schema(value_type = Vec<ExtractionOption>)]
+    pub options: &'static [ExtractionOption],
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExtractionCapabilities {
+    #[schema(value_type = Vec<String>)]
+    pub strategies: &'static [&'static str],
+    /// Options that apply regardless of format.
+    #[schema(value_type = Vec<ExtractionOption>)]
+    pub common_options: &'static [ExtractionOption],
+    #[schema(value_type = Vec<FormatCapability>)]
+    pub formats: &'static [FormatCapability],
+}
+
+/// Archive entries are extracted with the same options as top-level files.
+const ALL_FORMAT_OPTIONS: &[ExtractionOption] = &[
+    PreserveFormatting,
+    ExtractTables,
+    TableFormat,
+    PreserveHeadings,
+    HeadingFormat,
+    PreserveLists,
+    PreserveCodeBlocks,
+    IncludeMetadata,
+];
+
+pub const EXTRACTION_FORMATS: &[FormatCapability] = &[
+    FormatCapability {
+        format: "pdf",
+        mime_types: &["application/pdf"],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "word",
+        mime_types: &[
+            "application/msword",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.template",
+            "application/vnd.ms-word.document.macroEnabled.12",
+            "application/vnd.ms-word.template.macroEnabled.12",
+        ],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "legacy_excel",
+        mime_types: &["application/vnd.ms-excel"],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "excel",
+        mime_types: &[
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.template",
+            "application/vnd.ms-excel.sheet.macroEnabled.12",
+            "application/vnd.ms-excel.template.macroEnabled.12",
+            "application/vnd.ms-excel.addin.macroEnabled.12",
+            "application/vnd.ms-excel.sheet.binary.macroEnabled.12",
+        ],
+        options: &[],
+    },
+    FormatCapability {
+        format: "legacy_powerpoint",
+        mime_types: &[
+            "application/mspowerpoint",
+            "application/powerpoint",
+            "application/vnd.ms-powerpoint",
+            "application/x-mspowerpoint",
+        ],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "powerpoint",
+        mime_types: &["application/vnd.openxmlformats-officedocument.presentationml.presentation"],
+        options: &[],
+    },
+    FormatCapability {
+        format: "open_document_text",
+        mime_types: &["application/vnd.oasis.opendocument.text"],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "open_document_spreadsheet",
+        mime_types: &["application/vnd.oasis.opendocument.spreadsheet"],
+        options: &[],
+    },
+    FormatCapability {
+        format: "open_document_presentation",
+        mime_types: &["application/vnd.oasis.opendocument.presentation"],
+        options: &[],
+    },
+    FormatCapability {
+        format: "xml",
+        mime_types: &["application/xml", "text/xml"],
+        options: &[],
+    },
+    FormatCapability {
+        format: "json",
+        mime_types: &["application/json", "text/json"],
+        options: &[IncludeMetadata, PreserveFormatting],
+    },
+    FormatCapability {
+        format: "ndjson",
+        mime_types: &[
+            "application/x-ndjson",
+            "application/ndjson",
+            "application/x-jsonlines",
+            "application/jsonlines",
+            "text/x-ndjson",
+            "text/ndjson",
+        ],
+        options: &[IncludeMetadata, PreserveFormatting],
+    },
+    FormatCapability {
+        format: "html",
+        mime_types: &["application/html", "text/html"],
+        options: &[
+            ExtractTables,
+            TableFormat,
+            PreserveHeadings,
+            HeadingFormat,
+            PreserveLists,
+            PreserveCodeBlocks,
+        ],
+    },
+    FormatCapability {
+        format: "zip",
+        mime_types: &["application/zip", "application/x-zip-compressed"],
+        options: ALL_FORMAT_OPTIONS,
+    },
+    FormatCapability {
+        format: "gzip",
+        mime_types: &["application/gzip", "application/x-gzip"],
+        options: ALL_FORMAT_OPTIONS,
+    },
+    FormatCapability {
+        format: "tar",
+        mime_types: &["application/x-tar", "application/tar"],
+        options: ALL_FORMAT_OPTIONS,
+    },
+    FormatCapability {
+        format: "rtf",
+        mime_types: &[
+            "application/rtf",
+            "application/x-rtf",
+            "text/rtf",
+            "text/richtext",
+        ],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "epub",
+        mime_types: &["application/epub+zip", "application/x-epub+zip"],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "plain_text",
+        mime_types: &["text/plain"],
+        options: &[IncludeMetadata, PreserveFormatting],
+    },
+    FormatCapability {
+        format: "csv",
+        mime_types: &["text/csv"],
+        options: &[],
+    },
+    FormatCapability {
+        format: "markdown",
+        mime_types: &["text/markdown", "text/x-markdown"],
+        options: &[
+            IncludeMetadata,
+            PreserveFormatting,
+            PreserveHeadings,
+            PreserveLists,
+            PreserveCodeBlocks,
+        ],
+    },
+    FormatCapability {
+        format: "log",
+        mime_types: &["text/x-log", "text/x-syslog"],
+        options: &[IncludeMetadata, PreserveFormatting],
+    },
+    FormatCapability {
+        format: "email",
+        mime_types: &["message/rfc822"],
+        options: &[IncludeMetadata],
+    },
+    FormatCapability {
+        format: "outlook_msg",
+        mime_types: &["application/vnd.ms-outlook"],
+        options: &[IncludeMetadata],
+    },
+];
+
+pub const EXTRACTION_CAPABILITIES: ExtractionCapabilities = ExtractionCapabilities {
+    strategies: &["plain_text", "structure_preserving", "markdown", "auto"],
+    common_options: &[
+        AppendMetadataToText,
+        SniffMimeFallback,
+        Trace,
+        MaxOutputBytes,
+        DetectLanguage,
+    ],
+    formats: EXTRACTION_FORMATS,
+};
+
+/// Look up the capability entry for a MIME type (parameters are ignored).
+pub fn capability_for(mime_type: &str) -> Option<&'static FormatCapability> {
+    let essence = mime_type.split(';').next().unwrap_or("").trim();
+    EXTRACTION_FORMATS
+        .iter()
+        .find(|f| f.mime_types.iter().any(|m| m.eq_ignore_ascii_case(essence)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_mime_types_are_unique() {
+        let mut seen = HashSet::new();
+        for mime in EXTRACTION_FORMATS.iter().flat_map(|f| f.mime_types) {
+            assert!(seen.insert(*mime), "{mime} listed twice");
+        }
+    }
+
+    #[test]
+    fn test_capability_lookup() {
+        assert_eq!(capability_for("application/pdf").unwrap().format, "pdf");
+        assert_eq!(
+            capability_for("text/html; charset=utf-8").unwrap().format,
+            "html"
+        );
+        assert!(capability_for("image/png").is_none());
+    }
+}
@@ -1,18 +1,24 @@
 pub mod adaptive_concurrency;
 pub mod circuit_breaker;
+pub mod compression;
 pub mod config;
+pub mod document_vectors;
 pub mod embedder;
+pub mod embedding_stamp;
 pub mod encryption;
+pub mod extraction;
 pub mod http_client;
 pub mod models;
 pub mod nats;
 pub mod observability;
 pub mod owner_info;
 pub mod retry;
+pub mod sparse;
 pub mod storage;
 pub mod subjects;
 pub mod tls;
 pub mod validation;
+pub mod webhook;
 pub mod worker;
 
 pub use adaptive_concurrency::AdaptiveConcurrency;
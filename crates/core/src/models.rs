@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use utoipa::ToSchema;
 use uuid::Uuid;
+
+use crate::document_vectors::DocumentVectorMode;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectionTransformJob {
     pub job_id: Uuid,
@@ -31,6 +35,59 @@ pub struct CollectionTransformResult {
     pub processing_duration_ms: Option<i64>,
 }
 
+/// Dry-run extraction of a single file, sent by the API to
+/// `subjects::requests::EXTRACTION_PREVIEW`; nothing is persisted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionPreviewRequest {
+    /// File name the MIME type is guessed from
+    pub file_name: String,
+    pub source: ExtractionPreviewSource,
+    pub extraction_config: serde_json::Value,
+}
+
+/// Where the worker finds the file to preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtractionPreviewSource {
+    /// File uploaded with the request
+    Inline {
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+    },
+    /// File already stored in a collection
+    Stored { bucket: String, key: String },
+}
+
+/// Worker reply to an `ExtractionPreviewRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExtractionPreviewResponse {
+    Extracted {
+        text: String,
+        metadata: Option<serde_json::Value>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// Binary payloads inside JSON messages are sent as base64
+mod base64_bytes {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatasetTransformJob {
     pub job_id: Uuid,
@@ -40,11 +97,47 @@ pub struct DatasetTransformJob {
     pub dataset_transform_id: i32,
     pub embedded_dataset_id: i32,
     pub owner_id: String,
+    /// Embedder that produced `embedder_config`, stamped on upserted points
+    #[serde(default)]
+    pub embedder_id: Option<i32>,
     pub embedder_config: EmbedderConfig,
     pub qdrant_config: QdrantConnectionConfig,
     pub collection_name: String,
     #[serde(default)]
     pub batch_size: Option<usize>,
+    /// Per-item-type embedding settings, keyed by the batch item's `item_type`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub item_type_overrides: HashMap<String, ItemTypeEmbedding>,
+    /// Embedder used while the job's embedder is unavailable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_embedder: Option<FallbackEmbedder>,
+    /// Also store a vector per item of the batch in the document collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_vectors: Option<DocumentVectorMode>,
+    /// How the job's upserts are acknowledged
+    #[serde(default)]
+    pub write_consistency: QdrantWriteConsistency,
+}
+
+/// Embedder that stands in while the primary one's circuit breaker is open.
+/// It must produce vectors of the same dimensions as the primary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackEmbedder {
+    pub embedder_id: i32,
+    pub embedder_config: EmbedderConfig,
+}
+
+/// Embedding settings for dataset items of one type (e.g. titles vs code).
+/// Anything left unset falls back to the job's embedder.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemTypeEmbedding {
+    /// Text prepended to each item of this type before embedding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedder_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedder_config: Option<EmbedderConfig>,
 }
 
 /// Trigger message for collection transform scanning.
@@ -117,6 +210,14 @@ pub struct EmbedderConfig {
     pub batch_size: i32,
     #[serde(default = "default_max_input_tokens")]
     pub max_input_tokens: i32,
+    /// Read from `config.distance_metric`; jobs serialized before the field
+    /// existed default to cosine
+    #[serde(default)]
+    pub distance_metric: DistanceMetric,
+    /// Output dimensions, when measured before; workers probe the embedder
+    /// otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u64>,
 }
 
 impl EmbedderConfig {
@@ -134,11 +235,70 @@ impl EmbedderConfig {
             base_url,
             api_key,
             model,
+            distance_metric: DistanceMetric::from_config(&config),
             config,
             batch_size,
             max_input_tokens,
+            dimensions: None,
         }
     }
+
+    /// With the measured output dimensions of the model
+    pub fn with_dimensions(mut self, dimensions: Option<u64>) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+}
+
+/// Similarity an embedder's vectors are meant to be compared with. Set as
+/// `distance_metric` in the embedder's `config`; Qdrant collections for the
+/// embedder are created with the matching distance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    Dot,
+    Euclidean,
+}
+
+impl DistanceMetric {
+    /// Key in an embedder's `config`
+    pub const CONFIG_KEY: &'static str = "distance_metric";
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cosine" => Ok(Self::Cosine),
+            "dot" | "dot_product" => Ok(Self::Dot),
+            "euclidean" | "euclid" => Ok(Self::Euclidean),
+            other => Err(format!(
+                "unsupported distance_metric '{other}', expected cosine, dot or euclidean"
+            )),
+        }
+    }
+
+    /// Metric set in an embedder `config`, cosine when unset or invalid
+    pub fn from_config(config: &serde_json::Value) -> Self {
+        config
+            .get(Self::CONFIG_KEY)
+            .and_then(|v| v.as_str())
+            .and_then(|v| Self::parse(v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Dot => "dot",
+            Self::Euclidean => "euclidean",
+        }
+    }
+
+    /// Whether a higher search score means a closer match. Qdrant returns
+    /// the distance itself as the score for Euclidean collections.
+    pub fn higher_is_better(&self) -> bool {
+        !matches!(self, Self::Euclidean)
+    }
 }
 
 fn default_max_input_tokens() -> i32 {
@@ -151,6 +311,46 @@ pub struct QdrantConnectionConfig {
     pub api_key: Option<String>,
 }
 
+/// How Qdrant upserts are acknowledged. The default waits for each write to
+/// be applied, with Qdrant's default (weak) ordering across replicas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QdrantWriteConsistency {
+    /// Return only once the write is applied, so it is visible to searches
+    #[serde(default = "default_write_wait")]
+    pub wait: bool,
+    /// Ordering of the write across replicas
+    #[serde(default)]
+    pub ordering: WriteOrderingLevel,
+}
+
+impl Default for QdrantWriteConsistency {
+    fn default() -> Self {
+        Self {
+            wait: default_write_wait(),
+            ordering: WriteOrderingLevel::default(),
+        }
+    }
+}
+
+fn default_write_wait() -> bool {
+    true
+}
+
+/// Qdrant write ordering guarantee
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteOrderingLevel {
+    /// Writes may be applied in any order on each replica
+    #[default]
+    Weak,
+    /// Writes go through a dynamically elected leader; may be inconsistent
+    /// briefly during leader changes
+    Medium,
+    /// Writes go through the permanent shard leader, so every replica
+    /// applies them in the same order
+    Strong,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMConfig {
     pub llm_id: i32,
@@ -172,6 +372,54 @@ pub struct VisualizationTransformJob {
     pub visualization_config: VisualizationConfig,
     pub qdrant_config: QdrantConnectionConfig,
     pub llm_config: Option<LLMConfig>,
+    #[serde(default)]
+    pub mode: VisualizationJobMode,
+    /// Qdrant collection with one centroid point per topic, written by full
+    /// runs and read by `assign_only` runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topics_collection_name: Option<String>,
+    /// Points of the last full run; points not in it are new to
+    /// `assign_only` runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fitted_points_s3_key: Option<String>,
+}
+
+/// What a visualization job computes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VisualizationJobMode {
+    /// Fit UMAP and HDBSCAN over every point, render the visualization and
+    /// store the topic centroids
+    #[default]
+    Full,
+    /// Assign points added since the last full run to its topics by
+    /// nearest centroid, without refitting
+    AssignOnly,
+}
+
+/// How the visualization worker names clusters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TopicNamingMethod {
+    /// Ask the transform's LLM, or number the clusters when it has none
+    #[default]
+    Llm,
+    /// Top keywords by TF-IDF over individual texts
+    Tfidf,
+    /// Top keywords by class-based TF-IDF, treating each cluster as one
+    /// document; favors terms specific to the cluster
+    Ctfidf,
+}
+
+/// How the visualization worker clusters the UMAP layout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ClusteringMethod {
+    /// As many clusters as the density finds, with outliers left as noise
+    #[default]
+    Hdbscan,
+    /// Exactly `n_clusters` clusters, every point in one of them
+    Kmeans,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -183,6 +431,11 @@ pub struct VisualizationConfig {
     // HDBSCAN parameters
     pub min_cluster_size: i32,
     pub min_samples: Option<i32>,
+    #[serde(default)]
+    pub clustering_method: ClusteringMethod,
+    // k-means parameters
+    #[serde(default = "default_n_clusters")]
+    pub n_clusters: i32, // Number of topics when clustering_method = "kmeans"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub topic_naming_llm_id: Option<i32>, // LLM database ID when mode = "llm"
 
@@ -193,6 +446,14 @@ pub struct VisualizationConfig {
     pub samples_per_cluster: i32, // Number of sample texts to send to LLM per cluster (1-100, default 5)
     #[serde(default = "default_topic_naming_prompt")]
     pub topic_naming_prompt: String, // Custom prompt template for LLM topic naming
+    #[serde(default)]
+    pub topic_naming_method: TopicNamingMethod,
+
+    // Assigning new points to existing topics (mode `assign_only`)
+    #[serde(default = "default_assignment_distance_threshold")]
+    pub assignment_distance_threshold: f32, // Max distance (in `metric`) from a topic centroid
+    #[serde(default = "default_refit_outlier_ratio")]
+    pub refit_outlier_ratio: f32, // Outlier share of new points above which a refit is suggested
 
     // Datamapplot create_interactive_plot parameters
     #[serde(default = "default_inline_data")]
@@ -311,6 +572,15 @@ fn default_llm_batch_size() -> i32 {
 fn default_samples_per_cluster() -> i32 {
     5
 }
+fn default_assignment_distance_threshold() -> f32 {
+    0.35
+}
+fn default_refit_outlier_ratio() -> f32 {
+    0.2
+}
+fn default_n_clusters() -> i32 {
+    10
+}
 fn default_topic_naming_prompt() -> String {
     "These are representative texts from a document cluster:\n\n{{samples}}\n\nProvide a short, concise topic name (2-4 words) that captures the main theme. Respond with ONLY the topic name, nothing else.".to_string()
 }
@@ -428,6 +698,8 @@ pub struct VisualizationTransformResult {
     pub error_message: Option<String>,
     #[serde(rename = "htmlS3Key")]
     pub html_s3_key: Option<String>,
+    #[serde(rename = "pointsS3Key", default)]
+    pub points_s3_key: Option<String>,
     pub point_count: Option<usize>,
     pub cluster_count: Option<i32>,
     pub processing_duration_ms: Option<i64>,
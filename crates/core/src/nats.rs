@@ -486,6 +486,203 @@ pub async fn publish_with_retry(
     PublishResult::Failed(last_error.unwrap_or_else(|| anyhow::anyhow!("Unknown publish error")))
 }
 
+/// Stream holding transform jobs that exhausted their delivery attempts
+pub const DLQ_STREAM: &str = "DLQ_TRANSFORMS";
+
+/// Subject the job was consumed from before it was dead-lettered
+pub const DLQ_SOURCE_SUBJECT_HEADER: &str = "Dlq-Source-Subject";
+/// Error from the job's last delivery attempt
+pub const DLQ_ERROR_HEADER: &str = "Dlq-Error";
+/// Number of deliveries before the job was dead-lettered
+pub const DLQ_DELIVERY_COUNT_HEADER: &str = "Dlq-Delivery-Count";
+
+/// Longest error kept in `Dlq-Error`; headers are not the place for backtraces
+const DLQ_ERROR_MAX_CHARS: usize = 1024;
+
+/// Headers a worker attaches when dead-lettering a job, so the DLQ entry can
+/// be inspected and replayed without reconstructing where it came from
+pub fn dlq_headers(source_subject: &str, error: &str, delivery_count: u64) -> HeaderMap {
+    // Header values are single-line
+    let error: String = error
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(DLQ_ERROR_MAX_CHARS)
+        .collect();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(DLQ_SOURCE_SUBJECT_HEADER, source_subject);
+    headers.insert(DLQ_ERROR_HEADER, error.as_str());
+    headers.insert(
+        DLQ_DELIVERY_COUNT_HEADER,
+        delivery_count.to_string().as_str(),
+    );
+    headers
+}
+
+/// Job subject a DLQ subject was fed from, for entries dead-lettered before
+/// workers recorded `Dlq-Source-Subject`
+pub fn source_subject_for_dlq(dlq_subject: &str) -> Option<&'static str> {
+    use crate::subjects::{dlq, jobs};
+    match dlq_subject {
+        dlq::COLLECTION_TRANSFORM => Some(jobs::COLLECTION_TRANSFORM),
+        dlq::DATASET_TRANSFORM => Some(jobs::DATASET_TRANSFORM),
+        dlq::VISUALIZATION_TRANSFORM => Some(jobs::VISUALIZATION_TRANSFORM),
+        _ => None,
+    }
+}
+
+/// A dead-lettered job as stored in the DLQ stream
+#[derive(Debug, Clone)]
+pub struct DlqMessage {
+    /// Sequence in the DLQ stream; identifies the entry for replay
+    pub sequence: u64,
+    /// DLQ subject, e.g. `dlq.dataset-transforms`
+    pub subject: String,
+    /// Subject to replay onto
+    pub source_subject: Option<String>,
+    pub error: Option<String>,
+    pub delivery_count: Option<u64>,
+    pub failed_at: String,
+    pub payload: Vec<u8>,
+}
+
+impl DlqMessage {
+    fn from_stream_message(message: jetstream::message::StreamMessage) -> Self {
+        let header = |name: &str| message.headers.get(name).map(|v| v.to_string());
+        let subject = message.subject.to_string();
+        Self {
+            sequence: message.sequence,
+            source_subject: header(DLQ_SOURCE_SUBJECT_HEADER)
+                .or_else(|| source_subject_for_dlq(&subject).map(str::to_string)),
+            error: header(DLQ_ERROR_HEADER),
+            delivery_count: header(DLQ_DELIVERY_COUNT_HEADER).and_then(|v| v.parse().ok()),
+            failed_at: message.time.to_string(),
+            payload: message.payload.to_vec(),
+            subject,
+        }
+    }
+}
+
+/// One page of DLQ entries
+#[derive(Debug)]
+pub struct DlqPage {
+    pub messages: Vec<DlqMessage>,
+    /// Pass as `after` to continue; None once the end of the stream is reached
+    pub next_after: Option<u64>,
+}
+
+/// Read DLQ entries on `dlq_subject` with sequence greater than `after`,
+/// keeping those `filter` accepts, until `limit` are found or `max_scan`
+/// sequences have been examined.
+///
+/// The DLQ stream uses limits retention and replayed entries are deleted, so
+/// sequences have gaps; those are skipped.
+pub async fn read_dlq_messages(
+    client: &Client,
+    dlq_subject: &str,
+    after: u64,
+    limit: usize,
+    max_scan: u64,
+    filter: impl Fn(&DlqMessage) -> bool,
+) -> Result<DlqPage> {
+    let jetstream = jetstream::new(client.clone());
+    let mut stream = jetstream
+        .get_stream(DLQ_STREAM)
+        .await
+        .context(format!("Failed to get stream '{}'", DLQ_STREAM))?;
+    let state = stream.info().await?.state.clone();
+
+    let first = after.saturating_add(1).max(state.first_sequence);
+    let last = state
+        .last_sequence
+        .min(first.saturating_add(max_scan.max(1)) - 1);
+    let mut messages = Vec::new();
+
+    for sequence in first..=last {
+        let message = match stream.get_raw_message(sequence).await {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::debug!(sequence, error = %e, "DLQ sequence unavailable, skipping");
+                continue;
+            }
+        };
+        if message.subject.as_str() != dlq_subject {
+            continue;
+        }
+        let message = DlqMessage::from_stream_message(message);
+        if filter(&message) {
+            messages.push(message);
+            if messages.len() == limit {
+                let next_after = (sequence < state.last_sequence).then_some(sequence);
+                return Ok(DlqPage {
+                    messages,
+                    next_after,
+                });
+            }
+        }
+    }
+
+    Ok(DlqPage {
+        messages,
+        next_after: (last < state.last_sequence).then_some(last),
+    })
+}
+
+/// Fetch a single DLQ entry; None when the sequence does not exist (e.g.
+/// it was already replayed or aged out)
+pub async fn get_dlq_message(client: &Client, sequence: u64) -> Result<Option<DlqMessage>> {
+    let jetstream = jetstream::new(client.clone());
+    let stream = jetstream
+        .get_stream(DLQ_STREAM)
+        .await
+        .context(format!("Failed to get stream '{}'", DLQ_STREAM))?;
+    match stream.get_raw_message(sequence).await {
+        Ok(message) => Ok(Some(DlqMessage::from_stream_message(message))),
+        Err(e) => {
+            tracing::debug!(sequence, error = %e, "DLQ entry not found");
+            Ok(None)
+        }
+    }
+}
+
+/// Re-publish a dead-lettered job onto its source subject and, once
+/// JetStream has acknowledged it, remove the DLQ entry. Returns the subject
+/// the job was replayed onto.
+///
+/// The publish carries a `Nats-Msg-Id` derived from the DLQ sequence, so a
+/// replay retried within the source stream's duplicate window is not
+/// enqueued twice.
+pub async fn replay_dlq_message(client: &Client, message: &DlqMessage) -> Result<String> {
+    let subject = message
+        .source_subject
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("DLQ entry {} has no source subject", message.sequence))?;
+    let msg_id = format!("dlq-replay-{}", message.sequence);
+
+    if let PublishResult::Failed(e) =
+        publish_with_retry(client, &subject, &msg_id, message.payload.clone(), 3).await
+    {
+        return Err(e.context(format!("Failed to replay DLQ entry {}", message.sequence)));
+    }
+
+    let jetstream = jetstream::new(client.clone());
+    let stream = jetstream
+        .get_stream(DLQ_STREAM)
+        .await
+        .context(format!("Failed to get stream '{}'", DLQ_STREAM))?;
+    stream
+        .delete_message(message.sequence)
+        .await
+        .context(format!("Failed to remove DLQ entry {}", message.sequence))?;
+
+    info!(
+        sequence = message.sequence,
+        subject = %subject,
+        "Replayed DLQ entry onto its source subject"
+    );
+    Ok(subject)
+}
+
 /// Start a background task to collect and export NATS metrics
 pub async fn start_metrics_collector(client: Client) -> Result<()> {
     tokio::spawn(async move {
@@ -638,3 +835,40 @@ pub fn extract_otel_context(headers: Option<&HeaderMap>) -> opentelemetry::Conte
 
     global::get_text_map_propagator(|propagator| propagator.extract(&HashMapExtractor(&carrier)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dlq_headers_are_single_line_and_bounded() {
+        let error = format!("connection refused\nCaused by:\r\n  {}", "x".repeat(5000));
+        let headers = dlq_headers("workers.dataset-transform", &error, 5);
+
+        let recorded = headers.get(DLQ_ERROR_HEADER).unwrap().to_string();
+        assert!(recorded.starts_with("connection refused Caused by:"));
+        assert!(!recorded.contains(['\r', '\n']));
+        assert_eq!(recorded.chars().count(), DLQ_ERROR_MAX_CHARS);
+        assert_eq!(
+            headers.get(DLQ_SOURCE_SUBJECT_HEADER).unwrap().to_string(),
+            "workers.dataset-transform"
+        );
+        assert_eq!(
+            headers.get(DLQ_DELIVERY_COUNT_HEADER).unwrap().to_string(),
+            "5"
+        );
+    }
+
+    #[test]
+    fn test_source_subject_for_dlq() {
+        assert_eq!(
+            source_subject_for_dlq("dlq.collection-transforms"),
+            Some("workers.collection-transform")
+        );
+        assert_eq!(
+            source_subject_for_dlq("dlq.visualization-transforms"),
+            Some("workers.visualization-transform")
+        );
+        assert_eq!(source_subject_for_dlq("dlq.unknown-transforms"), None);
+    }
+}
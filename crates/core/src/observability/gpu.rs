@@ -136,6 +136,13 @@ pub mod gpu_monitor {
         Some((used as f64 / total as f64) * 100.0)
     }
 
+    /// Highest VRAM utilization across devices, None without a readable GPU
+    pub fn max_memory_utilization_percent() -> Option<f64> {
+        (0..device_count())
+            .filter_map(get_memory_utilization_percent)
+            .max_by(f64::total_cmp)
+    }
+
     pub fn collect_metrics() {
         let count = device_count();
         for i in 0..count {
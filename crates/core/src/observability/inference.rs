@@ -1,6 +1,6 @@
 use opentelemetry::KeyValue;
 
-use super::get_metrics;
+use super::{METRICS, get_metrics};
 
 pub fn record_embed_request(model: &str, item_count: u64, duration_secs: f64, success: bool) {
     let metrics = get_metrics();
@@ -37,6 +37,45 @@ pub fn record_embed_request(model: &str, item_count: u64, duration_secs: f64, su
     }
 }
 
+/// Record the size of a single embedding input so latency can be correlated
+/// with prompt length. `tokens` is `None` for models whose tokenizer isn't
+/// available, which then only get the character histogram.
+pub fn record_embed_input_length(model: &str, chars: usize, tokens: Option<usize>) {
+    let metrics = get_metrics();
+    let labels = [KeyValue::new("model", model.to_string())];
+
+    metrics
+        .inference_embed_input_chars
+        .record(chars as f64, &labels);
+    if let Some(tokens) = tokens {
+        metrics
+            .inference_embed_input_tokens
+            .record(tokens as f64, &labels);
+    }
+}
+
+/// Record the size of one model call made by the embedding request batcher
+pub fn record_embed_coalesced_batch(model: &str, requests: usize, texts: usize) {
+    let metrics = get_metrics();
+    let labels = [KeyValue::new("model", model.to_string())];
+
+    metrics
+        .inference_embed_coalesced_requests
+        .record(requests as f64, &labels);
+    metrics
+        .inference_embed_coalesced_texts
+        .record(texts as f64, &labels);
+}
+
+/// Record embedding cache lookups for one batch of texts
+pub fn record_embedding_cache(model: &str, hits: u64, misses: u64) {
+    let metrics = get_metrics();
+    let labels = [KeyValue::new("model", model.to_string())];
+
+    metrics.embedding_cache_hits_total.add(hits, &labels);
+    metrics.embedding_cache_misses_total.add(misses, &labels);
+}
+
 pub fn record_rerank_request(model: &str, document_count: u64, duration_secs: f64, success: bool) {
     let metrics = get_metrics();
     let status = if success { "success" } else { "error" };
@@ -114,6 +153,22 @@ pub fn record_embedding_batch(model: &str, duration_secs: f64, chunk_count: usiz
     );
 }
 
+/// A no-op until metrics are initialized, like `record_chunk_tokens`, since
+/// the embedder's retry loop is also exercised by unit tests
+pub fn record_embedder_retry(model: &str, reason: &str) {
+    let Some(metrics) = super::METRICS.get() else {
+        return;
+    };
+
+    metrics.embedder_request_retries_total.add(
+        1,
+        &[
+            KeyValue::new("model", model.to_string()),
+            KeyValue::new("reason", reason.to_string()),
+        ],
+    );
+}
+
 pub fn record_embedding_per_chunk(model: &str, duration_secs: f64, success: bool) {
     let metrics = get_metrics();
     let status = if success { "success" } else { "error" };
@@ -140,6 +195,15 @@ pub fn record_llm_response(model: &str, duration_secs: f64, success: bool) {
     );
 }
 
+/// Record embeddings rejected for containing NaN or infinite values
+pub fn record_non_finite_embeddings(model: &str, count: u64) {
+    let metrics = get_metrics();
+
+    metrics
+        .embedding_non_finite_vectors_total
+        .add(count, &[KeyValue::new("model", model.to_string())]);
+}
+
 pub fn record_embedding_session_metrics(model_id: &str, request_count: u64, age_seconds: f64) {
     let metrics = get_metrics();
 
@@ -153,6 +217,74 @@ pub fn record_embedding_session_metrics(model_id: &str, request_count: u64, age_
         .record(age_seconds, &[KeyValue::new("model", model_id.to_string())]);
 }
 
+/// Record the execution provider `model_id` runs on, or that it was unloaded
+/// from `provider` (`active` false)
+/// Recorded from drop guards, including ones dropped in unit tests, so this
+/// is a no-op until metrics are initialized.
+pub fn record_inference_request_cancelled(endpoint: &str, model: &str) {
+    let Some(metrics) = METRICS.get() else {
+        return;
+    };
+
+    metrics.inference_requests_cancelled_total.add(
+        1,
+        &[
+            KeyValue::new("endpoint", endpoint.to_string()),
+            KeyValue::new("model", model.to_string()),
+        ],
+    );
+}
+
+/// Counts an inference request as cancelled when dropped before
+/// [`CancellationGuard::complete`]. Actix drops a handler's future, and a
+/// streaming body, when the client disconnects, so a guard held across the
+/// inference call fires exactly when the client goes away mid-request.
+pub struct CancellationGuard {
+    endpoint: &'static str,
+    model: String,
+    completed: bool,
+}
+
+impl CancellationGuard {
+    pub fn new(endpoint: &'static str, model: &str) -> Self {
+        Self {
+            endpoint,
+            model: model.to_string(),
+            completed: false,
+        }
+    }
+
+    /// The request ran to completion (successfully or not)
+    pub fn complete(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            tracing::info!(
+                endpoint = self.endpoint,
+                model = %self.model,
+                "Client disconnected, cancelled inference request"
+            );
+            record_inference_request_cancelled(self.endpoint, &self.model);
+        }
+    }
+}
+
+pub fn record_model_execution_provider(model_id: &str, provider: &str, active: bool) {
+    let metrics = get_metrics();
+
+    metrics.inference_model_execution_provider.record(
+        if active { 1.0 } else { 0.0 },
+        &[
+            KeyValue::new("model", model_id.to_string()),
+            KeyValue::new("provider", provider.to_string()),
+        ],
+    );
+}
+
 pub fn record_embedding_session_reset(model_id: &str, reason: &str) {
     let metrics = get_metrics();
 
@@ -165,6 +297,20 @@ pub fn record_embedding_session_reset(model_id: &str, reason: &str) {
     );
 }
 
+/// Record a model loaded into (`load`) or evicted from (`eviction`) the
+/// inference service's model cache
+pub fn record_embedding_model_cache(model_id: &str, event: &str) {
+    let metrics = get_metrics();
+
+    metrics.embedding_model_cache_events_total.add(
+        1,
+        &[
+            KeyValue::new("model", model_id.to_string()),
+            KeyValue::new("event", event.to_string()),
+        ],
+    );
+}
+
 pub fn init_embedding_session_reset_metric(model_id: &str) {
     let metrics = get_metrics();
 
@@ -176,3 +322,59 @@ pub fn init_embedding_session_reset_metric(model_id: &str) {
         ],
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::global;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use prometheus::{Encoder, Registry, TextEncoder};
+    use std::sync::OnceLock;
+
+    /// Install a Prometheus-backed meter provider once for this test binary and
+    /// return the registry that collects its samples.
+    fn test_registry() -> &'static Registry {
+        static REGISTRY: OnceLock<Registry> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let registry = Registry::new();
+            let exporter = opentelemetry_prometheus::exporter()
+                .with_registry(registry.clone())
+                .build()
+                .expect("prometheus exporter");
+            global::set_meter_provider(SdkMeterProvider::builder().with_reader(exporter).build());
+            super::super::init_metrics_otel().expect("metrics init");
+            registry
+        })
+    }
+
+    fn sample_count(registry: &Registry, metric: &str, model: &str) -> u64 {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buffer)
+            .expect("encode metrics");
+        let text = String::from_utf8(buffer).expect("utf8 metrics");
+        let prefix = format!("{metric}_count{{");
+        text.lines()
+            .filter(|line| line.starts_with(&prefix) && line.contains(model))
+            .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+            .sum()
+    }
+
+    #[test]
+    fn test_embed_input_length_histograms_record_samples() {
+        let registry = test_registry();
+        let model = "test/length-histogram-model";
+
+        record_embed_input_length(model, 120, Some(30));
+        record_embed_input_length(model, 8, None);
+
+        assert_eq!(
+            sample_count(registry, "inference_embed_input_chars", model),
+            2
+        );
+        assert_eq!(
+            sample_count(registry, "inference_embed_input_tokens", model),
+            1
+        );
+    }
+}
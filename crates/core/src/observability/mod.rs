@@ -23,7 +23,7 @@ pub use worker::*;
 use actix_web_prom::{PrometheusMetrics, PrometheusMetricsBuilder};
 use anyhow::Result;
 use opentelemetry::{
-    global,
+    KeyValue, global,
     metrics::{Counter, Gauge, Histogram, Meter},
     trace::TracerProvider,
 };
@@ -62,6 +62,7 @@ pub(crate) struct Metrics {
     pub search_request_duration: Histogram<f64>,
     pub search_embedder_call_duration: Histogram<f64>,
     pub search_qdrant_query_duration: Histogram<f64>,
+    pub search_rerank_duration: Histogram<f64>,
     pub search_results_returned: Histogram<f64>,
     pub worker_job_failures_total: Counter<u64>,
     pub worker_job_retries_total: Counter<u64>,
@@ -69,6 +70,13 @@ pub(crate) struct Metrics {
     pub inference_embed_duration: Histogram<f64>,
     pub inference_embed_items_total: Counter<u64>,
     pub inference_embed_per_item_duration: Histogram<f64>,
+    pub inference_embed_input_chars: Histogram<f64>,
+    pub inference_embed_input_tokens: Histogram<f64>,
+    pub inference_embed_coalesced_requests: Histogram<f64>,
+    pub inference_embed_coalesced_texts: Histogram<f64>,
+    pub embedding_cache_hits_total: Counter<u64>,
+    pub embedding_non_finite_vectors_total: Counter<u64>,
+    pub embedding_cache_misses_total: Counter<u64>,
     pub inference_rerank_requests_total: Counter<u64>,
     pub inference_rerank_duration: Histogram<f64>,
     pub inference_rerank_documents_total: Counter<u64>,
@@ -76,9 +84,12 @@ pub(crate) struct Metrics {
     pub inference_llm_duration: Histogram<f64>,
     pub inference_llm_tokens_generated: Counter<u64>,
     pub inference_llm_tokens_per_second: Histogram<f64>,
+    pub inference_requests_cancelled_total: Counter<u64>,
     pub document_upload_per_item_duration: Histogram<f64>,
     pub document_extraction_duration: Histogram<f64>,
     pub document_chunking_per_item_duration: Histogram<f64>,
+    pub document_chunking_tokens_per_chunk: Histogram<f64>,
+    pub document_archive_truncations_total: Counter<u64>,
     pub embedding_per_chunk_duration: Histogram<f64>,
     pub llm_response_duration: Histogram<f64>,
     pub chat_request_duration: Histogram<f64>,
@@ -96,7 +107,11 @@ pub(crate) struct Metrics {
     pub gpu_memory_utilization_percent: Gauge<f64>,
     pub embedding_session_resets_total: Counter<u64>,
     pub embedding_session_request_count: Gauge<f64>,
+    pub inference_model_execution_provider: Gauge<f64>,
     pub embedding_session_age_seconds: Gauge<f64>,
+    pub embedding_model_cache_events_total: Counter<u64>,
+    pub embedder_request_retries_total: Counter<u64>,
+    pub circuit_breaker_transitions_total: Counter<u64>,
     pub dlq_messages_total: Counter<u64>,
     pub scanner_triggers_published_total: Counter<u64>,
     pub scanner_triggers_processed_total: Counter<u64>,
@@ -225,6 +240,11 @@ impl Metrics {
             .with_description("Duration of Qdrant queries during search")
             .build();
 
+        let search_rerank_duration = meter
+            .f64_histogram("search_rerank_duration_seconds")
+            .with_description("Duration of reranker calls during search")
+            .build();
+
         let search_results_returned = meter
             .f64_histogram("search_results_returned")
             .with_description("Number of results returned per search")
@@ -260,6 +280,26 @@ impl Metrics {
             .with_description("Average duration per item in embedding requests")
             .build();
 
+        let inference_embed_input_chars = meter
+            .f64_histogram("inference_embed_input_chars")
+            .with_description("Length of each embedding input text in characters")
+            .build();
+
+        let inference_embed_input_tokens = meter
+            .f64_histogram("inference_embed_input_tokens")
+            .with_description("Length of each embedding input text in model tokens")
+            .build();
+
+        let inference_embed_coalesced_requests = meter
+            .f64_histogram("inference_embed_coalesced_requests")
+            .with_description("Embed requests coalesced into each model call")
+            .build();
+
+        let inference_embed_coalesced_texts = meter
+            .f64_histogram("inference_embed_coalesced_texts")
+            .with_description("Texts embedded by each coalesced model call")
+            .build();
+
         let inference_rerank_requests_total = meter
             .u64_counter("inference_rerank_requests")
             .with_description("Total number of reranking requests")
@@ -285,6 +325,11 @@ impl Metrics {
             .with_description("Duration of LLM generation requests in seconds")
             .build();
 
+        let inference_requests_cancelled_total = meter
+            .u64_counter("inference_requests_cancelled")
+            .with_description("Inference requests abandoned by a disconnected client")
+            .build();
+
         let inference_llm_tokens_generated = meter
             .u64_counter("inference_llm_tokens_generated")
             .with_description("Total number of tokens generated by LLMs")
@@ -310,6 +355,18 @@ impl Metrics {
             .with_description("Duration to chunk individual documents in seconds")
             .build();
 
+        let document_chunking_tokens_per_chunk = meter
+            .f64_histogram("document_chunking_tokens_per_chunk")
+            .with_description("Number of tokens in each chunk produced by token-based chunking")
+            .build();
+
+        let document_archive_truncations_total = meter
+            .u64_counter("document_archive_truncations")
+            .with_description(
+                "Archives only partly extracted because they passed the total size limit",
+            )
+            .build();
+
         let embedding_per_chunk_duration = meter
             .f64_histogram("embedding_per_chunk_duration_seconds")
             .with_description("Duration to generate embeddings per chunk in seconds")
@@ -385,11 +442,31 @@ impl Metrics {
             .with_description("GPU memory utilization percentage")
             .build();
 
+        let embedding_cache_hits_total = meter
+            .u64_counter("embedding_cache_hits")
+            .with_description("Texts whose embedding was reused from the embedding cache")
+            .build();
+
+        let embedding_cache_misses_total = meter
+            .u64_counter("embedding_cache_misses")
+            .with_description("Texts not found in the embedding cache and sent to the embedder")
+            .build();
+
+        let embedding_non_finite_vectors_total = meter
+            .u64_counter("embedding_non_finite_vectors")
+            .with_description("Embeddings rejected for containing NaN or infinite values")
+            .build();
+
         let embedding_session_resets_total = meter
             .u64_counter("embedding_session_resets")
             .with_description("Total number of embedding session resets due to memory thresholds")
             .build();
 
+        let inference_model_execution_provider = meter
+            .f64_gauge("inference_model_execution_provider")
+            .with_description("Execution provider each loaded model runs on (1 = active)")
+            .build();
+
         let embedding_session_request_count = meter
             .f64_gauge("embedding_session_request_count")
             .with_description("Current request count for each embedding model session")
@@ -400,6 +477,23 @@ impl Metrics {
             .with_description("Age of the current embedding model session in seconds")
             .build();
 
+        let embedding_model_cache_events_total = meter
+            .u64_counter("embedding_model_cache_events")
+            .with_description(
+                "Embedding models loaded into or evicted from the inference model cache",
+            )
+            .build();
+
+        let embedder_request_retries_total = meter
+            .u64_counter("embedder_request_retries")
+            .with_description("Embedder HTTP requests retried after a failed attempt")
+            .build();
+
+        let circuit_breaker_transitions_total = meter
+            .u64_counter("circuit_breaker_transitions")
+            .with_description("Circuit breaker state changes, labelled by the state entered")
+            .build();
+
         let dlq_messages_total = meter
             .u64_counter("dlq_messages")
             .with_description("Total number of messages sent to Dead Letter Queue")
@@ -541,6 +635,7 @@ impl Metrics {
             search_request_duration,
             search_embedder_call_duration,
             search_qdrant_query_duration,
+            search_rerank_duration,
             search_results_returned,
             worker_job_failures_total,
             worker_job_retries_total,
@@ -548,6 +643,13 @@ impl Metrics {
             inference_embed_duration,
             inference_embed_items_total,
             inference_embed_per_item_duration,
+            inference_embed_input_chars,
+            inference_embed_input_tokens,
+            inference_embed_coalesced_requests,
+            inference_embed_coalesced_texts,
+            embedding_cache_hits_total,
+            embedding_non_finite_vectors_total,
+            embedding_cache_misses_total,
             inference_rerank_requests_total,
             inference_rerank_duration,
             inference_rerank_documents_total,
@@ -555,9 +657,12 @@ impl Metrics {
             inference_llm_duration,
             inference_llm_tokens_generated,
             inference_llm_tokens_per_second,
+            inference_requests_cancelled_total,
             document_upload_per_item_duration,
             document_extraction_duration,
             document_chunking_per_item_duration,
+            document_chunking_tokens_per_chunk,
+            document_archive_truncations_total,
             embedding_per_chunk_duration,
             llm_response_duration,
             chat_request_duration,
@@ -575,7 +680,11 @@ impl Metrics {
             gpu_memory_utilization_percent,
             embedding_session_resets_total,
             embedding_session_request_count,
+            inference_model_execution_provider,
             embedding_session_age_seconds,
+            embedding_model_cache_events_total,
+            embedder_request_retries_total,
+            circuit_breaker_transitions_total,
             dlq_messages_total,
             scanner_triggers_published_total,
             scanner_triggers_processed_total,
@@ -619,6 +728,25 @@ fn get_metrics() -> &'static Metrics {
     METRICS.get().expect("Metrics not initialized")
 }
 
+/// Recorded by every circuit breaker, including ones built in unit tests, so
+/// this is a no-op until metrics are initialized.
+pub fn record_circuit_breaker_transition(
+    circuit_breaker: &str,
+    state: crate::circuit_breaker::CircuitState,
+) {
+    let Some(metrics) = METRICS.get() else {
+        return;
+    };
+
+    metrics.circuit_breaker_transitions_total.add(
+        1,
+        &[
+            KeyValue::new("circuit_breaker", circuit_breaker.to_string()),
+            KeyValue::new("state", state.to_string()),
+        ],
+    );
+}
+
 pub fn init_observability_api(
     service_prefix: &str,
     endpoints_to_exclude: &[(&str, Option<&str>)],
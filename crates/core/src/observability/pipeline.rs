@@ -41,6 +41,32 @@ pub fn record_document_chunking(operation: &str, duration_secs: f64, success: bo
     );
 }
 
+/// Recorded from inside the chunking strategies, which also run where
+/// metrics were never initialized (unit tests, tooling), so this is a no-op
+/// until they are.
+pub fn record_chunk_tokens(tokenizer: &str, tokens: usize) {
+    let Some(metrics) = super::METRICS.get() else {
+        return;
+    };
+
+    metrics.document_chunking_tokens_per_chunk.record(
+        tokens as f64,
+        &[KeyValue::new("tokenizer", tokenizer.to_string())],
+    );
+}
+
+/// Recorded by the archive extractors, which unit tests also run, so this is
+/// a no-op until metrics are initialized
+pub fn record_archive_truncation(format: &str) {
+    let Some(metrics) = super::METRICS.get() else {
+        return;
+    };
+
+    metrics
+        .document_archive_truncations_total
+        .add(1, &[KeyValue::new("format", format.to_string())]);
+}
+
 pub fn record_chat_request(duration_secs: f64, success: bool) {
     let metrics = get_metrics();
     let status = if success { "success" } else { "error" };
@@ -8,6 +8,7 @@ pub fn record_search_request(
     qdrant_duration_secs: f64,
     results_count: usize,
     embedded_datasets_count: usize,
+    retrieval_mode: &str,
     status: &str,
 ) {
     let metrics = get_metrics();
@@ -17,6 +18,7 @@ pub fn record_search_request(
         &[
             KeyValue::new("status", status.to_string()),
             KeyValue::new("embedded_datasets", embedded_datasets_count.to_string()),
+            KeyValue::new("retrieval_mode", retrieval_mode.to_string()),
         ],
     );
 
@@ -25,6 +27,7 @@ pub fn record_search_request(
         &[
             KeyValue::new("status", status.to_string()),
             KeyValue::new("embedded_datasets", embedded_datasets_count.to_string()),
+            KeyValue::new("retrieval_mode", retrieval_mode.to_string()),
         ],
     );
 
@@ -46,3 +49,16 @@ pub fn record_search_request(
         ],
     );
 }
+
+/// Record one reranker call made while answering a search
+pub fn record_search_rerank(duration_secs: f64, model: &str, status: &str) {
+    let metrics = get_metrics();
+
+    metrics.search_rerank_duration.record(
+        duration_secs,
+        &[
+            KeyValue::new("model", model.to_string()),
+            KeyValue::new("status", status.to_string()),
+        ],
+    );
+}
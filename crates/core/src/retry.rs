@@ -38,35 +38,44 @@ impl Default for RetryPolicy {
 impl RetryPolicy {
     /// Load retry policy from environment variables with optional prefix
     pub fn from_env_with_prefix(prefix: &str) -> Self {
+        Self::default().with_env_overrides(prefix)
+    }
+
+    /// Override this policy's fields from `{prefix}_MAX_ATTEMPTS`,
+    /// `{prefix}_INITIAL_DELAY_MS`, `{prefix}_MAX_DELAY_MS`,
+    /// `{prefix}_BACKOFF_MULTIPLIER` and `{prefix}_JITTER_FACTOR` where set
+    pub fn with_env_overrides(self, prefix: &str) -> Self {
         let max_attempts = std::env::var(format!("{}_MAX_ATTEMPTS", prefix))
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(3);
+            .unwrap_or(self.max_attempts);
 
-        let initial_delay_ms = std::env::var(format!("{}_INITIAL_DELAY_MS", prefix))
+        let initial_delay = std::env::var(format!("{}_INITIAL_DELAY_MS", prefix))
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(100);
+            .map(Duration::from_millis)
+            .unwrap_or(self.initial_delay);
 
-        let max_delay_ms = std::env::var(format!("{}_MAX_DELAY_MS", prefix))
+        let max_delay = std::env::var(format!("{}_MAX_DELAY_MS", prefix))
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(10000);
+            .map(Duration::from_millis)
+            .unwrap_or(self.max_delay);
 
         let backoff_multiplier = std::env::var(format!("{}_BACKOFF_MULTIPLIER", prefix))
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(2.0);
+            .unwrap_or(self.backoff_multiplier);
 
         let jitter_factor = std::env::var(format!("{}_JITTER_FACTOR", prefix))
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(0.1);
+            .unwrap_or(self.jitter_factor);
 
         Self {
             max_attempts,
-            initial_delay: Duration::from_millis(initial_delay_ms),
-            max_delay: Duration::from_millis(max_delay_ms),
+            initial_delay,
+            max_delay,
             backoff_multiplier,
             jitter_factor,
         }
@@ -238,9 +247,17 @@ pub fn s3_retry_policy() -> RetryPolicy {
     RetryPolicy::default()
 }
 
-/// Retry policy specifically configured for inference API operations
+/// Retry policy for embedding inference requests: five retries backing off
+/// from 1s to 16s, overridable with `EMBEDDER_RETRY_*`
 pub fn inference_retry_policy() -> RetryPolicy {
-    RetryPolicy::default()
+    RetryPolicy {
+        max_attempts: 5,
+        initial_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(16),
+        backoff_multiplier: 2.0,
+        jitter_factor: 0.1,
+    }
+    .with_env_overrides("EMBEDDER_RETRY")
 }
 
 #[cfg(test)]
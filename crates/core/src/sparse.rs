@@ -0,0 +1,122 @@
+//! BM25-style sparse vectors for keyword retrieval.
+//!
+//! Chunks are indexed with a named sparse vector alongside their dense
+//! embedding. Each term is hashed to a stable index and weighted with the
+//! BM25 term-frequency component; the IDF component is applied by Qdrant
+//! (the sparse vector is created with the `idf` modifier), so document
+//! vectors don't depend on corpus statistics and never need re-indexing
+//! as a collection grows.
+
+use std::collections::BTreeMap;
+
+/// Name of the sparse vector in Qdrant collections
+pub const SPARSE_VECTOR_NAME: &str = "bm25";
+
+/// BM25 term-frequency saturation
+const K1: f32 = 1.2;
+/// BM25 length normalization
+const B: f32 = 0.75;
+/// Assumed average chunk length in terms. Chunks are size-bounded, so a
+/// fixed value keeps length normalization close to corpus-based BM25.
+const AVG_DOC_TERMS: f32 = 128.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    pub indices: Vec<u32>,
+    pub values: Vec<f32>,
+}
+
+impl SparseVector {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn from_weights(weights: BTreeMap<u32, f32>) -> Self {
+        let (indices, values) = weights.into_iter().unzip();
+        Self { indices, values }
+    }
+}
+
+/// Sparse vector of a chunk: BM25 term-frequency weight per term
+pub fn document_vector(text: &str) -> SparseVector {
+    let mut counts: BTreeMap<u32, f32> = BTreeMap::new();
+    let mut length = 0usize;
+    for term in terms(text) {
+        *counts.entry(term_index(&term)).or_default() += 1.0;
+        length += 1;
+    }
+
+    let norm = K1 * (1.0 - B + B * length as f32 / AVG_DOC_TERMS);
+    for tf in counts.values_mut() {
+        *tf = *tf * (K1 + 1.0) / (*tf + norm);
+    }
+    SparseVector::from_weights(counts)
+}
+
+/// Sparse vector of a query: each distinct term once, so the score is the
+/// sum of the matched terms' BM25 weights
+pub fn query_vector(text: &str) -> SparseVector {
+    SparseVector::from_weights(terms(text).map(|term| (term_index(&term), 1.0)).collect())
+}
+
+/// Lowercased alphanumeric runs. Codes such as `ERR-404` become `err` and
+/// `404` on both the document and the query side.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// 32-bit FNV-1a; stable across builds, unlike the std hasher
+fn term_index(term: &str) -> u32 {
+    term.bytes().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weight(vector: &SparseVector, term: &str) -> Option<f32> {
+        let index = term_index(term);
+        vector
+            .indices
+            .iter()
+            .position(|i| *i == index)
+            .map(|pos| vector.values[pos])
+    }
+
+    #[test]
+    fn test_document_weights_saturate_and_match_query_terms() {
+        let doc = document_vector("Error ERR-404: page not found. Retry the error page.");
+        let query = query_vector("err-404 error");
+
+        // Sorted, deduplicated indices
+        assert!(doc.indices.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(query.values, vec![1.0; 3]);
+        for term in ["err", "404", "error"] {
+            assert!(weight(&doc, term).is_some(), "{term}");
+        }
+
+        // Repeated terms weigh more, but less than linearly
+        let once = weight(&doc, "404").unwrap();
+        let twice = weight(&doc, "error").unwrap();
+        assert!(twice > once && twice < 2.0 * once);
+    }
+
+    #[test]
+    fn test_longer_documents_weigh_terms_less() {
+        let short = document_vector("kafka");
+        let long = document_vector(&format!("kafka {}", "filler ".repeat(300)));
+        assert!(weight(&short, "kafka").unwrap() > weight(&long, "kafka").unwrap());
+        assert!(document_vector(" -- ").is_empty());
+    }
+
+    #[test]
+    fn test_term_index_is_stable() {
+        // Indexed vectors must keep matching queries across releases
+        assert_eq!(term_index(""), 0x811c_9dc5);
+        assert_eq!(term_index("a"), 0xe40c_292c);
+    }
+}
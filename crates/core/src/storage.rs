@@ -1,24 +1,131 @@
+use crate::config::{S3EncryptionConfig, S3ObjectTag, S3ObjectTagging};
 use crate::observability::record_storage_operation;
 use anyhow::{Context, Result, bail};
 use aws_config::{BehaviorVersion, Region};
 use aws_sdk_s3::Client;
 use aws_sdk_s3::config::Credentials;
-use aws_sdk_s3::types::{Delete, ObjectIdentifier};
-use once_cell::sync::Lazy;
+use aws_sdk_s3::operation::copy_object::builders::CopyObjectFluentBuilder;
+use aws_sdk_s3::operation::create_multipart_upload::builders::CreateMultipartUploadFluentBuilder;
+use aws_sdk_s3::operation::put_object::builders::PutObjectFluentBuilder;
+use aws_sdk_s3::types::{Delete, ObjectIdentifier, ServerSideEncryption};
 use serde::Serialize;
+use std::sync::OnceLock;
 use std::{env, time::Instant};
 use tracing::warn;
 
-// Maximum file size for in-memory processing
-// Files larger than this should be rejected to prevent OOM
-// Configurable via MAX_FILE_SIZE_MB environment variable (default: 100MB)
-static MAX_FILE_SIZE_BYTES: Lazy<i64> = Lazy::new(|| {
-    let mb = env::var("MAX_FILE_SIZE_MB")
-        .ok()
-        .and_then(|s| s.parse::<i64>().ok())
-        .unwrap_or(100);
-    mb * 1024 * 1024
-});
+/// Server-side encryption for uploads, loaded from the environment by
+/// `initialize_client` so invalid settings fail at startup
+static SERVER_SIDE_ENCRYPTION: OnceLock<Option<S3EncryptionConfig>> = OnceLock::new();
+
+/// The configured server-side encryption, if any
+pub fn server_side_encryption() -> Option<&'static S3EncryptionConfig> {
+    SERVER_SIDE_ENCRYPTION.get().and_then(Option::as_ref)
+}
+
+/// Object tagging for uploads, loaded alongside `SERVER_SIDE_ENCRYPTION`
+static OBJECT_TAGGING: OnceLock<Option<S3ObjectTagging>> = OnceLock::new();
+
+/// The configured object tagging, if any
+pub fn object_tagging() -> Option<&'static S3ObjectTagging> {
+    OBJECT_TAGGING.get().and_then(Option::as_ref)
+}
+
+/// URL-encoded `x-amz-tagging` value for an object uploaded at `key`, or
+/// `None` when no tags apply
+pub fn object_tagging_header(
+    tagging: Option<&S3ObjectTagging>,
+    key: &str,
+    content_type: &str,
+    upload_date: chrono::NaiveDate,
+) -> Option<String> {
+    let tagging = tagging?;
+    let mut pairs: Vec<(&str, String)> = Vec::new();
+    for tag in &tagging.tags {
+        let value = match tag {
+            S3ObjectTag::CollectionId => match collection_id_from_key(key) {
+                Some(id) => id.to_string(),
+                None => continue,
+            },
+            S3ObjectTag::UploadDate => upload_date.format("%Y-%m-%d").to_string(),
+            S3ObjectTag::ContentType => content_type.to_string(),
+        };
+        pairs.push((tag.key(), value));
+    }
+    for (key, value) in &tagging.static_tags {
+        pairs.push((key.as_str(), value.clone()));
+    }
+
+    if pairs.is_empty() {
+        return None;
+    }
+    Some(
+        pairs
+            .iter()
+            .map(|(k, v)| format!("{}={}", encode_tag_component(k), encode_tag_component(v)))
+            .collect::<Vec<_>>()
+            .join("&"),
+    )
+}
+
+/// Collection id from a `collections/{id}/...` key
+fn collection_id_from_key(key: &str) -> Option<&str> {
+    let id = key.strip_prefix("collections/")?.split('/').next()?;
+    (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then_some(id)
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters
+fn encode_tag_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Add the SSE headers for `sse` to a PutObject request
+pub fn put_object_with_sse(
+    request: PutObjectFluentBuilder,
+    sse: Option<&S3EncryptionConfig>,
+) -> PutObjectFluentBuilder {
+    match sse {
+        Some(sse) => request
+            .server_side_encryption(ServerSideEncryption::from(sse.algorithm.as_str()))
+            .set_ssekms_key_id(sse.kms_key_id.clone()),
+        None => request,
+    }
+}
+
+/// Add the SSE headers for `sse` to a CreateMultipartUpload request; the
+/// uploaded parts inherit the encryption
+pub fn create_multipart_upload_with_sse(
+    request: CreateMultipartUploadFluentBuilder,
+    sse: Option<&S3EncryptionConfig>,
+) -> CreateMultipartUploadFluentBuilder {
+    match sse {
+        Some(sse) => request
+            .server_side_encryption(ServerSideEncryption::from(sse.algorithm.as_str()))
+            .set_ssekms_key_id(sse.kms_key_id.clone()),
+        None => request,
+    }
+}
+
+/// Add the SSE headers for `sse` to a CopyObject request; copies don't keep
+/// the source object's encryption unless it is requested again
+pub fn copy_object_with_sse(
+    request: CopyObjectFluentBuilder,
+    sse: Option<&S3EncryptionConfig>,
+) -> CopyObjectFluentBuilder {
+    match sse {
+        Some(sse) => request
+            .server_side_encryption(ServerSideEncryption::from(sse.algorithm.as_str()))
+            .set_ssekms_key_id(sse.kms_key_id.clone()),
+        None => request,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DocumentUpload {
@@ -36,6 +143,9 @@ pub struct CollectionFile {
     pub content_type: Option<String>,
 }
 
+/// One page of a file listing. `continuation_token` is the `start_after`
+/// key for the next page and is present exactly when `has_more` is set;
+/// `total_count` is only filled in by callers that count separately.
 #[derive(Debug, Clone, Serialize)]
 pub struct PaginatedFiles {
     pub files: Vec<CollectionFile>,
@@ -43,11 +153,33 @@ pub struct PaginatedFiles {
     pub page_size: i32,
     pub has_more: bool,
     pub continuation_token: Option<String>,
+    pub total_count: Option<i64>,
+}
+
+/// Split a listing fetched with one extra item (`page_size + 1`) into the
+/// page itself and the cursor to resume after its last item, or `None` when
+/// this is the last page. Shared by every S3 listing so pagination behaves
+/// the same regardless of which one a handler calls.
+pub fn split_page<T>(
+    mut items: Vec<T>,
+    page_size: usize,
+    cursor: impl Fn(&T) -> String,
+) -> (Vec<T>, Option<String>) {
+    if items.len() <= page_size {
+        return (items, None);
+    }
+    items.truncate(page_size);
+    let next = items.last().map(cursor);
+    (items, next)
 }
 
 pub async fn initialize_client() -> Result<aws_sdk_s3::Client> {
     let region = env::var("AWS_REGION")?;
     let endpoint_url = env::var("AWS_ENDPOINT_URL")?;
+    let sse = S3EncryptionConfig::from_env()?;
+    SERVER_SIDE_ENCRYPTION.get_or_init(|| sse);
+    let tagging = S3ObjectTagging::from_env()?;
+    OBJECT_TAGGING.get_or_init(|| tagging);
 
     let mut config_loader = aws_config::defaults(BehaviorVersion::latest())
         .region(Region::new(region))
@@ -82,12 +214,19 @@ pub async fn upload_document(client: &Client, document: DocumentUpload) -> Resul
     let start = Instant::now();
     let file_size = document.content.len() as u64;
 
-    let result = client
+    let request = client
         .put_object()
         .bucket(&document.collection_id)
         .key(&document.name)
-        .body(document.content.into())
         .content_type(&document.mime_type)
+        .set_tagging(object_tagging_header(
+            object_tagging(),
+            &document.name,
+            &document.mime_type,
+            chrono::Utc::now().date_naive(),
+        ))
+        .body(document.content.into());
+    let result = put_object_with_sse(request, server_side_encryption())
         .send()
         .await;
 
@@ -124,12 +263,9 @@ pub async fn list_files(
     }
 
     let output = request.send().await?;
-    let all_objects = output.contents();
-    let has_more = all_objects.len() > page_size as usize;
-
-    let files: Vec<CollectionFile> = all_objects
+    let all_files: Vec<CollectionFile> = output
+        .contents()
         .iter()
-        .take(page_size as usize)
         .map(|obj| CollectionFile {
             key: obj.key().unwrap_or_default().to_string(),
             size: obj.size().unwrap_or(0),
@@ -137,12 +273,7 @@ pub async fn list_files(
             content_type: None,
         })
         .collect();
-
-    let continuation_token = if has_more {
-        files.last().map(|f| f.key.clone())
-    } else {
-        None
-    };
+    let (files, continuation_token) = split_page(all_files, page_size as usize, |f| f.key.clone());
 
     let duration = start.elapsed().as_secs_f64();
     record_storage_operation("list", duration, None, true);
@@ -151,8 +282,9 @@ pub async fn list_files(
         files,
         page: 0,
         page_size,
-        has_more,
+        has_more: continuation_token.is_some(),
         continuation_token,
+        total_count: None,
     })
 }
 
@@ -209,11 +341,15 @@ pub async fn get_file(client: &Client, bucket: &str, key: &str) -> Result<Vec<u8
 }
 
 /// Get file with size validation to prevent OOM on large files
-/// Returns error if file exceeds MAX_FILE_SIZE_BYTES (configurable via MAX_FILE_SIZE_MB env var)
+/// Returns error if file exceeds `max_size` (see `config::max_download_size_from_env`)
 #[tracing::instrument(name = "s3.get_file_with_size_check", skip(client), fields(storage.system = "s3", bucket = %bucket, key = %key))]
-pub async fn get_file_with_size_check(client: &Client, bucket: &str, key: &str) -> Result<Vec<u8>> {
+pub async fn get_file_with_size_check(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    max_size: i64,
+) -> Result<Vec<u8>> {
     let start = Instant::now();
-    let max_size = *MAX_FILE_SIZE_BYTES;
 
     // First, check file size using head_object
     let head_result = client
@@ -446,3 +582,143 @@ pub async fn file_exists(client: &Client, bucket: &str, key: &str) -> Result<boo
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::S3SseAlgorithm;
+
+    #[test]
+    fn test_split_page_returns_cursor_only_when_more_remain() {
+        let keys = |n: usize| (0..n).map(|i| format!("key-{i}")).collect::<Vec<_>>();
+
+        let (page, next) = split_page(keys(3), 2, Clone::clone);
+        assert_eq!(page, vec!["key-0", "key-1"]);
+        assert_eq!(next.as_deref(), Some("key-1"));
+
+        // Exactly page_size items is the last page
+        let (page, next) = split_page(keys(2), 2, Clone::clone);
+        assert_eq!(page.len(), 2);
+        assert_eq!(next, None);
+
+        let (page, next) = split_page(keys(0), 2, Clone::clone);
+        assert!(page.is_empty());
+        assert_eq!(next, None);
+    }
+
+    fn test_client() -> Client {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .build();
+        Client::from_conf(config)
+    }
+
+    #[test]
+    fn test_put_object_carries_sse_headers() {
+        let sse = S3EncryptionConfig {
+            algorithm: S3SseAlgorithm::AwsKms,
+            kms_key_id: Some("alias/semantic-explorer".to_string()),
+        };
+        let request = put_object_with_sse(
+            test_client().put_object().bucket("bucket").key("key"),
+            Some(&sse),
+        );
+        assert_eq!(
+            request.get_server_side_encryption(),
+            &Some(ServerSideEncryption::AwsKms)
+        );
+        assert_eq!(
+            request.get_ssekms_key_id().as_deref(),
+            Some("alias/semantic-explorer")
+        );
+
+        let sse = S3EncryptionConfig {
+            algorithm: S3SseAlgorithm::Aes256,
+            kms_key_id: None,
+        };
+        let request = create_multipart_upload_with_sse(
+            test_client()
+                .create_multipart_upload()
+                .bucket("bucket")
+                .key("key"),
+            Some(&sse),
+        );
+        assert_eq!(
+            request.get_server_side_encryption(),
+            &Some(ServerSideEncryption::Aes256)
+        );
+        assert_eq!(request.get_ssekms_key_id(), &None);
+
+        let sse = S3EncryptionConfig {
+            algorithm: S3SseAlgorithm::AwsKms,
+            kms_key_id: Some("alias/semantic-explorer".to_string()),
+        };
+        let request = copy_object_with_sse(
+            test_client()
+                .copy_object()
+                .copy_source("bucket/collections/1/a.txt")
+                .bucket("bucket")
+                .key("collections/2/a.txt"),
+            Some(&sse),
+        );
+        assert_eq!(
+            request.get_server_side_encryption(),
+            &Some(ServerSideEncryption::AwsKms)
+        );
+        assert_eq!(
+            request.get_ssekms_key_id().as_deref(),
+            Some("alias/semantic-explorer")
+        );
+    }
+
+    #[test]
+    fn test_upload_carries_configured_tags() {
+        let tagging = S3ObjectTagging {
+            tags: vec![
+                S3ObjectTag::CollectionId,
+                S3ObjectTag::UploadDate,
+                S3ObjectTag::ContentType,
+            ],
+            static_tags: vec![("tier".to_string(), "hot".to_string())],
+        };
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let request = test_client()
+            .put_object()
+            .bucket("bucket")
+            .key("collections/42/report.pdf")
+            .set_tagging(object_tagging_header(
+                Some(&tagging),
+                "collections/42/report.pdf",
+                "application/pdf",
+                date,
+            ));
+        assert_eq!(
+            request.get_tagging().as_deref(),
+            Some("collection_id=42&upload_date=2026-03-14&content_type=application%2Fpdf&tier=hot")
+        );
+
+        // Keys outside a collection skip the collection tag
+        assert_eq!(
+            object_tagging_header(
+                Some(&tagging),
+                "embedded-datasets/embedded-dataset-1/batches/b.json",
+                "application/json",
+                date,
+            )
+            .as_deref(),
+            Some("upload_date=2026-03-14&content_type=application%2Fjson&tier=hot")
+        );
+        assert_eq!(
+            object_tagging_header(None, "collections/42/a.txt", "text/plain", date),
+            None
+        );
+    }
+
+    #[test]
+    fn test_no_sse_headers_when_unconfigured() {
+        let request = put_object_with_sse(test_client().put_object(), None);
+        assert_eq!(request.get_server_side_encryption(), &None);
+        assert_eq!(request.get_ssekms_key_id(), &None);
+    }
+}
@@ -22,6 +22,16 @@ pub mod jobs {
     pub const VISUALIZATION_TRANSFORM: &str = "workers.visualization-transform";
 }
 
+/// Request-reply subjects - API sends a request and waits for the worker's reply
+/// (core NATS, no stream: nothing is retried or persisted)
+pub mod requests {
+    /// Subject for dry-run extraction of a single file (worker-collections)
+    pub const EXTRACTION_PREVIEW: &str = "workers.extraction-preview";
+
+    /// Queue group so each preview request is handled by one worker
+    pub const EXTRACTION_PREVIEW_WORKERS: &str = "extraction-preview-workers";
+}
+
 /// Status update subjects - Workers publish to these for SSE real-time updates
 /// Format: transforms.{type}.status.{owner}.{resource_id}.{transform_id}
 /// Stream: TRANSFORM_STATUS
@@ -331,6 +331,47 @@ pub fn validate_bucket_name(name: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Maximum length for Qdrant collection names
+pub const MAX_COLLECTION_NAME_LENGTH: usize = 255;
+
+/// Validate a Qdrant collection name.
+///
+/// Only ASCII letters, digits, hyphens, underscores and dots are accepted so
+/// the name is safe in Qdrant's REST paths and on-disk storage layout.
+pub fn validate_collection_name(name: &str) -> Result<(), ValidationError> {
+    if name.is_empty() {
+        return Err(ValidationError::Empty {
+            field: "collection name",
+        });
+    }
+
+    if name.len() > MAX_COLLECTION_NAME_LENGTH {
+        return Err(ValidationError::TooLong {
+            field: "collection name",
+            max: MAX_COLLECTION_NAME_LENGTH,
+        });
+    }
+
+    if name.starts_with('.') {
+        return Err(ValidationError::InvalidCharacters {
+            field: "collection name",
+            reason: "cannot start with a dot",
+        });
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(ValidationError::InvalidCharacters {
+            field: "collection name",
+            reason: "can only contain letters, numbers, hyphens, underscores, and dots",
+        });
+    }
+
+    Ok(())
+}
+
 /// Sanitize a string by removing potentially dangerous characters
 /// while preserving readability
 pub fn sanitize_string(input: &str) -> String {
@@ -390,6 +431,29 @@ mod tests {
         assert!(validate_file_path("file.txt").is_ok());
     }
 
+    #[test]
+    fn test_validate_collection_name() {
+        assert!(validate_collection_name("embedded-dataset-12-abc123").is_ok());
+        assert!(validate_collection_name("team_a.vectors-v2").is_ok());
+
+        assert!(matches!(
+            validate_collection_name(""),
+            Err(ValidationError::Empty { .. })
+        ));
+        assert!(matches!(
+            validate_collection_name("../escape"),
+            Err(ValidationError::InvalidCharacters { .. })
+        ));
+        assert!(matches!(
+            validate_collection_name("has space"),
+            Err(ValidationError::InvalidCharacters { .. })
+        ));
+        assert!(matches!(
+            validate_collection_name(&"a".repeat(MAX_COLLECTION_NAME_LENGTH + 1)),
+            Err(ValidationError::TooLong { .. })
+        ));
+    }
+
     #[test]
     fn test_validate_tags() {
         assert!(validate_tags(&["tag1".to_string(), "tag-2".to_string()]).is_ok());
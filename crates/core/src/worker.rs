@@ -23,6 +23,7 @@ use tracing_subscriber::{
 
 use crate::adaptive_concurrency::AdaptiveConcurrency;
 use crate::config::NatsConfig;
+use crate::embedder::EmbedderHealth;
 use crate::observability;
 
 /// Configuration for worker initialization
@@ -37,6 +38,70 @@ pub struct WorkerConfig {
     pub health_check_port: u16,
     /// NATS configuration (extracted from env in main)
     pub nats_config: NatsConfig,
+    /// Redelivery delay for failed jobs
+    pub nak_backoff: NakBackoffConfig,
+}
+
+/// Exponential backoff for NAK'd jobs, so a downstream outage is retried at
+/// a widening interval instead of a fixed cadence
+#[derive(Debug, Clone, PartialEq)]
+pub struct NakBackoffConfig {
+    /// Delay after the first failed delivery (`NAK_BACKOFF_BASE_SECS`)
+    pub base: Duration,
+    /// Upper bound on the delay (`NAK_BACKOFF_MAX_SECS`)
+    pub max: Duration,
+    /// Fraction of the delay randomly taken off, 0.0 - 1.0, so jobs that
+    /// failed together don't retry together (`NAK_BACKOFF_JITTER`)
+    pub jitter: f64,
+}
+
+impl Default for NakBackoffConfig {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(30),
+            max: Duration::from_secs(300),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl NakBackoffConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let secs = |name: &str, default: Duration| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map_or(default, Duration::from_secs)
+        };
+
+        let base = secs("NAK_BACKOFF_BASE_SECS", default.base);
+        let max = secs("NAK_BACKOFF_MAX_SECS", default.max).max(base);
+        let jitter = std::env::var("NAK_BACKOFF_JITTER")
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|j| (0.0..=1.0).contains(j))
+            .unwrap_or(default.jitter);
+
+        Self { base, max, jitter }
+    }
+}
+
+/// Delay before redelivering a job that failed on its `delivery_count`th
+/// delivery: `min(base * 2^(delivery_count - 1), max)`, less up to `jitter`
+/// of itself. Never exceeds `max`.
+pub fn compute_nak_delay(delivery_count: u64, config: &NakBackoffConfig) -> Duration {
+    nak_delay(delivery_count, config, rand::random::<f64>())
+}
+
+/// `compute_nak_delay` with the random draw (in `[0, 1)`) passed in
+fn nak_delay(delivery_count: u64, config: &NakBackoffConfig, random: f64) -> Duration {
+    let exponent = delivery_count.saturating_sub(1).min(32) as u32;
+    let delay = config
+        .base
+        .saturating_mul(2u32.saturating_pow(exponent))
+        .min(config.max);
+    delay.mul_f64(1.0 - config.jitter.clamp(0.0, 1.0) * random.clamp(0.0, 1.0))
 }
 
 /// DLQ subject mapping for each stream type
@@ -63,6 +128,8 @@ fn get_transform_type(stream_name: &str) -> &'static str {
 pub struct WorkerContext {
     pub s3_client: aws_sdk_s3::Client,
     pub nats_client: async_nats::Client,
+    /// Largest file a job may download into memory
+    pub max_download_size_bytes: i64,
 }
 
 /// Initialize OpenTelemetry for the worker
@@ -260,6 +327,7 @@ where
         in_flight: in_flight.clone(),
         stream_name: config.stream_name.clone(),
         max_deliver: config.max_deliver,
+        nak_backoff: config.nak_backoff.clone(),
     };
 
     // Run message processing with graceful shutdown
@@ -336,12 +404,38 @@ async fn shutdown_signal() {
     }
 }
 
+/// Safety timeout for waiting on a job permit — should never be reached
+const PERMIT_WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Outcome of waiting for a job permit
+enum PermitWait {
+    Acquired(tokio::sync::OwnedSemaphorePermit),
+    Closed,
+    TimedOut,
+}
+
+/// Wait for a job permit, applying backpressure instead of dropping messages.
+///
+/// This is a blocking acquire rather than try_acquire. Combined with
+/// max_ack_pending being clamped to max_concurrent_jobs, NATS won't deliver
+/// more messages than we can handle, so this should rarely block. If it does
+/// block briefly (e.g., between a task acking and releasing its permit), it
+/// naturally throttles consumption without causing NAK/redelivery churn.
+async fn acquire_permit(concurrency: &AdaptiveConcurrency, timeout: Duration) -> PermitWait {
+    match tokio::time::timeout(timeout, concurrency.acquire()).await {
+        Ok(Ok(permit)) => PermitWait::Acquired(permit),
+        Ok(Err(_)) => PermitWait::Closed,
+        Err(_) => PermitWait::TimedOut,
+    }
+}
+
 /// Internal context for message processing
 struct ProcessingContext {
     shutdown: Arc<AtomicBool>,
     in_flight: Arc<AtomicUsize>,
     stream_name: String,
     max_deliver: u64,
+    nak_backoff: NakBackoffConfig,
 }
 
 /// Process messages from the consumer with DLQ support
@@ -412,30 +506,22 @@ where
             }
         }
 
-        // Acquire semaphore permit for backpressure.
-        // We use a blocking acquire here instead of try_acquire. Combined with
-        // max_ack_pending being clamped to max_concurrent_jobs, NATS won't deliver
-        // more messages than we can handle, so this should rarely block. If it does
-        // block briefly (e.g., between a task acking and releasing its permit), it
-        // naturally throttles consumption without causing NAK/redelivery churn.
-        let permit = match tokio::time::timeout(
-            Duration::from_secs(300), // Safety timeout (5 min) — should never be reached
-            concurrency.acquire(),
-        )
-        .await
-        {
-            Ok(Ok(p)) => p,
-            Ok(Err(_)) => {
+        // Acquire semaphore permit for backpressure
+        let permit = match acquire_permit(&concurrency, PERMIT_WAIT_TIMEOUT).await {
+            PermitWait::Acquired(p) => p,
+            PermitWait::Closed => {
                 // Semaphore closed — shouldn't happen in normal operation
                 error!("Semaphore closed unexpectedly");
                 break;
             }
-            Err(_) => {
+            PermitWait::TimedOut => {
                 // Timeout waiting for permit — indicates max_ack_pending is too high
-                // relative to max_concurrent_jobs, or jobs are taking too long
+                // relative to max_concurrent_jobs, or jobs are taking too long.
+                // NAK rather than drop so JetStream redelivers the job later.
                 warn!(
-                    "Timed out waiting for semaphore permit (300s). \
-                     Consider lowering NATS_MAX_ACK_PENDING to match MAX_CONCURRENT_JOBS."
+                    "Timed out waiting for semaphore permit ({}s). \
+                     Consider lowering NATS_MAX_ACK_PENDING to match MAX_CONCURRENT_JOBS.",
+                    PERMIT_WAIT_TIMEOUT.as_secs()
                 );
                 if let Err(e) = msg
                     .ack_with(async_nats::jetstream::AckKind::Nak(Some(
@@ -456,6 +542,7 @@ where
         let process_job = Arc::clone(&process_job);
         let stream_name_clone = proc_ctx.stream_name.clone();
         let max_deliver = proc_ctx.max_deliver;
+        let nak_backoff = proc_ctx.nak_backoff.clone();
         let in_flight_clone = proc_ctx.in_flight.clone();
         let jetstream_clone = jetstream.clone();
         let payload = msg.payload.clone();
@@ -486,7 +573,10 @@ where
                     Err(e) => {
                         // Check if downstream embedding service is under pressure
                         // and propagate to adaptive concurrency controller
-                        if crate::embedder::is_downstream_under_pressure() {
+                        let embedder_unavailable = crate::embedder::unavailable_cause(&e);
+                        if embedder_unavailable.is_some()
+                            || crate::embedder::is_downstream_under_pressure()
+                        {
                             concurrency_clone.record_downstream_pressure();
                         }
 
@@ -516,8 +606,14 @@ where
                                 max_deliver, dlq_subject
                             );
 
-                            if let Err(dlq_err) =
-                                jetstream_clone.publish(dlq_subject, payload).await
+                            let headers = crate::nats::dlq_headers(
+                                msg.subject.as_str(),
+                                &format!("{e:#}"),
+                                delivery_count,
+                            );
+                            if let Err(dlq_err) = jetstream_clone
+                                .publish_with_headers(dlq_subject, headers, payload)
+                                .await
                             {
                                 error!("Failed to publish to DLQ: {}", dlq_err);
                             } else {
@@ -533,11 +629,18 @@ where
                                 error!("Failed to acknowledge DLQ'd message: {}", ack_err);
                             }
                         } else {
-                            // Negative acknowledgment for retry (30s delay)
+                            // Negative acknowledgment for retry, backing off with each delivery
+                            // and at least until the embedder's circuit breaker may close
+                            let mut delay = compute_nak_delay(delivery_count, &nak_backoff);
+                            if let Some(unavailable) = embedder_unavailable {
+                                delay = delay.max(unavailable.retry_after);
+                            }
+                            debug!(
+                                delay_secs = delay.as_secs_f64(),
+                                delivery_count, "Scheduling job redelivery"
+                            );
                             if let Err(ack_err) = msg
-                                .ack_with(async_nats::jetstream::AckKind::Nak(Some(
-                                    Duration::from_secs(30),
-                                )))
+                                .ack_with(async_nats::jetstream::AckKind::Nak(Some(delay)))
                                 .await
                             {
                                 error!("Failed to negatively acknowledge failed job: {}", ack_err);
@@ -562,12 +665,67 @@ where
     Ok(())
 }
 
+/// Probe responses written verbatim. `/healthz` and `/readyz` are what load
+/// balancers and kubelets hit every few seconds, so they are answered from
+/// these constants without formatting or reading worker state beyond the
+/// shutdown flag.
+const HEALTHZ_OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 15\r\n\r\n{\"status\":\"ok\"}";
+const READYZ_OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 18\r\n\r\n{\"status\":\"ready\"}";
+const SHUTTING_DOWN: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nContent-Length: 26\r\n\r\n{\"status\":\"shutting_down\"}";
+const NOT_FOUND: &[u8] = b"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 21\r\n\r\n{\"error\":\"not_found\"}";
+
+/// The static response for a liveness/readiness probe, or None for `/status`
+/// (the only endpoint that reports worker state)
+fn probe_response(request: &[u8], is_shutdown: bool) -> Option<&'static [u8]> {
+    if request.starts_with(b"GET /healthz") {
+        Some(if is_shutdown {
+            SHUTTING_DOWN
+        } else {
+            HEALTHZ_OK
+        })
+    } else if request.starts_with(b"GET /readyz") {
+        Some(if is_shutdown {
+            SHUTTING_DOWN
+        } else {
+            READYZ_OK
+        })
+    } else if request.starts_with(b"GET /status") {
+        None
+    } else {
+        Some(NOT_FOUND)
+    }
+}
+
+/// `/status` JSON. With an embedder, `status` is `degraded` while its
+/// circuit is open, so a worker whose jobs can't make progress stands out.
+fn status_body(
+    service_name: &str,
+    in_flight: usize,
+    concurrency: &AdaptiveConcurrency,
+    embedder: Option<&EmbedderHealth>,
+) -> String {
+    let degraded = embedder.is_some_and(|embedder| !embedder.reachable());
+    let mut status = serde_json::json!({
+        "service": service_name,
+        "status": if degraded { "degraded" } else { "ok" },
+        "in_flight": in_flight,
+        "effective_limit": concurrency.effective_limit(),
+        "max_limit": concurrency.max_limit(),
+        "available_permits": concurrency.available_permits(),
+        "downstream_pressure": concurrency.is_downstream_pressured(),
+    });
+    if let Some(embedder) = embedder {
+        status["embedder"] = embedder.to_json();
+    }
+    status.to_string()
+}
+
 /// Tiny HTTP health check server for K8s liveness/readiness probes.
 ///
 /// Listens on `HEALTH_CHECK_PORT` (default 8082) and serves:
 /// - `GET /healthz` — liveness probe (always 200 unless shutting down)
 /// - `GET /readyz` — readiness probe (200 when not shutting down)
-/// - `GET /status` — JSON status with concurrency info
+/// - `GET /status` — JSON status with concurrency info and embedder health
 async fn run_health_server(
     service_name: String,
     concurrency: Arc<AdaptiveConcurrency>,
@@ -585,15 +743,13 @@ async fn run_health_server(
             return Ok(());
         }
     };
+    let service_name: Arc<str> = service_name.into();
 
     loop {
         let (mut stream, _) = listener.accept().await?;
-        let is_shutdown = shutdown.load(Ordering::SeqCst);
-        let current_in_flight = in_flight.load(Ordering::SeqCst);
-        let effective = concurrency.effective_limit();
-        let max = concurrency.max_limit();
-        let available = concurrency.available_permits();
-        let pressured = concurrency.is_downstream_pressured();
+        let shutdown = shutdown.clone();
+        let concurrency = concurrency.clone();
+        let in_flight = in_flight.clone();
         let svc = service_name.clone();
 
         tokio::spawn(async move {
@@ -606,46 +762,187 @@ async fn run_health_server(
                 Ok(Ok(n)) => n,
                 _ => 0,
             };
-            let request = String::from_utf8_lossy(&buf[..n]);
 
-            let (status, body) = if request.contains("GET /healthz") {
-                if is_shutdown {
-                    ("503 Service Unavailable", "{\"status\":\"shutting_down\"}")
-                } else {
-                    ("200 OK", "{\"status\":\"ok\"}")
-                }
-            } else if request.contains("GET /readyz") {
-                if is_shutdown {
-                    ("503 Service Unavailable", "{\"status\":\"shutting_down\"}")
-                } else {
-                    ("200 OK", "{\"status\":\"ready\"}")
-                }
-            } else if request.contains("GET /status") {
-                // Return detailed JSON status
-                let json = format!(
-                    "{{\"service\":\"{}\",\"in_flight\":{},\"effective_limit\":{},\
-                     \"max_limit\":{},\"available_permits\":{},\
-                     \"downstream_pressure\":{}}}",
-                    svc, current_in_flight, effective, max, available, pressured
-                );
-                let response = format!(
-                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
-                    json.len(),
-                    json
-                );
-                let _ = stream.write_all(response.as_bytes()).await;
+            if let Some(response) = probe_response(&buf[..n], shutdown.load(Ordering::SeqCst)) {
+                let _ = stream.write_all(response).await;
                 return;
-            } else {
-                ("404 Not Found", "{\"error\":\"not_found\"}")
-            };
+            }
 
+            // Return detailed JSON status
+            let embedder = crate::embedder::embedder_health().await;
+            let json = status_body(
+                &svc,
+                in_flight.load(Ordering::SeqCst),
+                &concurrency,
+                embedder.as_ref(),
+            );
             let response = format!(
-                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
-                status,
-                body.len(),
-                body
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                json.len(),
+                json
             );
             let _ = stream.write_all(response.as_bytes()).await;
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nak_delay_doubles_per_delivery_and_clamps_at_max() {
+        let config = NakBackoffConfig {
+            base: Duration::from_secs(30),
+            max: Duration::from_secs(300),
+            jitter: 0.0,
+        };
+        let delays: Vec<u64> = (1..=6)
+            .map(|n| compute_nak_delay(n, &config).as_secs())
+            .collect();
+        assert_eq!(delays, vec![30, 60, 120, 240, 300, 300]);
+
+        // Delivery count 0 (missing metadata) behaves like the first delivery,
+        // and huge counts don't overflow
+        assert_eq!(compute_nak_delay(0, &config), Duration::from_secs(30));
+        assert_eq!(
+            compute_nak_delay(u64::MAX, &config),
+            Duration::from_secs(300)
+        );
+    }
+
+    #[test]
+    fn test_nak_delay_jitter_stays_within_bounds() {
+        let config = NakBackoffConfig {
+            base: Duration::from_secs(10),
+            max: Duration::from_secs(60),
+            jitter: 0.25,
+        };
+        // Extremes of the random draw
+        assert_eq!(nak_delay(2, &config, 0.0), Duration::from_secs(20));
+        assert_eq!(nak_delay(2, &config, 1.0), Duration::from_secs(15));
+        assert_eq!(nak_delay(9, &config, 1.0), Duration::from_secs(45));
+
+        for _ in 0..1000 {
+            let delay = compute_nak_delay(9, &config);
+            assert!(delay >= Duration::from_secs(45) && delay <= config.max);
+        }
+    }
+
+    #[test]
+    fn test_probe_responses_are_static_and_well_formed() {
+        for (request, shutdown, status, body) in [
+            (
+                &b"GET /healthz HTTP/1.1\r\n\r\n"[..],
+                false,
+                "200 OK",
+                "{\"status\":\"ok\"}",
+            ),
+            (
+                b"GET /readyz HTTP/1.1\r\n\r\n",
+                false,
+                "200 OK",
+                "{\"status\":\"ready\"}",
+            ),
+            (
+                b"GET /healthz HTTP/1.1\r\n\r\n",
+                true,
+                "503 Service Unavailable",
+                "{\"status\":\"shutting_down\"}",
+            ),
+            (
+                b"GET /metrics HTTP/1.1\r\n\r\n",
+                false,
+                "404 Not Found",
+                "{\"error\":\"not_found\"}",
+            ),
+            (b"", false, "404 Not Found", "{\"error\":\"not_found\"}"),
+        ] {
+            let response = std::str::from_utf8(probe_response(request, shutdown).unwrap()).unwrap();
+            let (head, got_body) = response.split_once("\r\n\r\n").unwrap();
+            assert!(
+                head.starts_with(&format!("HTTP/1.1 {status}\r\n")),
+                "{head}"
+            );
+            assert!(
+                head.ends_with(&format!("Content-Length: {}", body.len())),
+                "{head}"
+            );
+            assert_eq!(got_body, body);
+        }
+        // Only /status reads worker state
+        assert!(probe_response(b"GET /status HTTP/1.1\r\n\r\n", false).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_unreachable_embedder() {
+        let concurrency = AdaptiveConcurrency::new(4);
+
+        let status: serde_json::Value =
+            serde_json::from_str(&status_body("worker", 1, &concurrency, None)).unwrap();
+        assert_eq!(status["status"], "ok");
+        assert_eq!(status["in_flight"], 1);
+        assert!(status.get("embedder").is_none());
+
+        let open = EmbedderHealth {
+            circuit_state: crate::circuit_breaker::CircuitState::Open,
+            last_success_secs_ago: Some(120),
+            retry_after_secs: Some(30),
+        };
+        let status: serde_json::Value =
+            serde_json::from_str(&status_body("worker", 0, &concurrency, Some(&open))).unwrap();
+        assert_eq!(status["status"], "degraded");
+        assert_eq!(status["embedder"]["reachable"], false);
+        assert_eq!(status["embedder"]["circuit_state"], "open");
+        assert_eq!(status["embedder"]["last_success_secs_ago"], 120);
+
+        let closed = EmbedderHealth {
+            circuit_state: crate::circuit_breaker::CircuitState::Closed,
+            last_success_secs_ago: None,
+            retry_after_secs: None,
+        };
+        let status: serde_json::Value =
+            serde_json::from_str(&status_body("worker", 0, &concurrency, Some(&closed))).unwrap();
+        assert_eq!(status["status"], "ok");
+        assert_eq!(status["embedder"]["reachable"], true);
+    }
+
+    #[tokio::test]
+    async fn test_permit_wait_applies_backpressure_without_dropping() {
+        // Far more messages than permits: every one must wait its turn rather
+        // than being rejected while the worker is saturated
+        let concurrency = AdaptiveConcurrency::new(2);
+        let processed = Arc::new(AtomicUsize::new(0));
+        let mut handles = Vec::new();
+
+        for _ in 0..50 {
+            let permit = match acquire_permit(&concurrency, Duration::from_secs(5)).await {
+                PermitWait::Acquired(permit) => permit,
+                PermitWait::Closed => panic!("semaphore closed"),
+                PermitWait::TimedOut => panic!("message would have been NAK'd under load"),
+            };
+            let processed = Arc::clone(&processed);
+            handles.push(tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                processed.fetch_add(1, Ordering::SeqCst);
+                drop(permit);
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(processed.load(Ordering::SeqCst), 50);
+    }
+
+    #[tokio::test]
+    async fn test_permit_wait_times_out_when_saturated() {
+        let concurrency = AdaptiveConcurrency::new(1);
+        let _held = concurrency.acquire().await.unwrap();
+
+        assert!(matches!(
+            acquire_permit(&concurrency, Duration::from_millis(10)).await,
+            PermitWait::TimedOut
+        ));
+    }
+}
@@ -6,9 +6,11 @@ use std::time::Instant;
 use tracing::{info, instrument};
 use utoipa::ToSchema;
 
-use crate::config::ModelConfig;
+use crate::config::{EmptyInputBehavior, ModelConfig};
 use crate::embedding;
 use crate::errors::InferenceError;
+use crate::input_type::{self, InputType};
+use semantic_explorer_core::observability::CancellationGuard;
 
 /// Add backpressure headers to successful responses so callers can
 /// adaptively throttle without waiting for 503 failures.
@@ -32,6 +34,10 @@ pub struct EmbedRequest {
     pub text: String,
     /// Model to use (required)
     pub model: String,
+    /// Whether the text is a search query or a document; selects the
+    /// model's instruction prefix. Omitted, the text is embedded as is.
+    #[serde(default)]
+    pub input_type: Option<InputType>,
 }
 
 /// Request body for batch text embedding
@@ -41,6 +47,10 @@ pub struct BatchEmbedRequest {
     pub texts: Vec<String>,
     /// Model to use (required)
     pub model: String,
+    /// Whether the texts are search queries or documents; selects the
+    /// model's instruction prefix. Omitted, the texts are embedded as is.
+    #[serde(default)]
+    pub input_type: Option<InputType>,
 }
 
 /// Response for embedding requests
@@ -54,6 +64,127 @@ pub struct EmbedResponse {
     pub count: usize,
     /// Embedding dimensions
     pub dimensions: usize,
+    /// Per-item errors for rejected inputs (their embedding is left empty)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<EmbedItemError>,
+}
+
+/// Error for a single input of a batch request
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmbedItemError {
+    /// Position of the input in the request
+    pub index: usize,
+    /// Why the input was rejected
+    pub error: String,
+}
+
+const EMPTY_INPUT_ERROR: &str = "Input text is empty or whitespace-only";
+
+/// Split inputs into the texts to embed and the positions of empty or
+/// whitespace-only inputs.
+fn partition_empty_inputs(texts: Vec<String>) -> (Vec<String>, Vec<usize>) {
+    let mut to_embed = Vec::with_capacity(texts.len());
+    let mut empty_indices = Vec::new();
+    for (index, text) in texts.into_iter().enumerate() {
+        if text.trim().is_empty() {
+            empty_indices.push(index);
+        } else {
+            to_embed.push(text);
+        }
+    }
+    (to_embed, empty_indices)
+}
+
+/// Re-insert empty inputs at their original positions, either as an empty
+/// vector with a per-item error or as a zero vector.
+fn merge_embeddings(
+    total: usize,
+    empty_indices: &[usize],
+    embeddings: Vec<Vec<f32>>,
+    behavior: EmptyInputBehavior,
+    zero_dimensions: usize,
+) -> (Vec<Vec<f32>>, Vec<EmbedItemError>) {
+    let mut embedded = embeddings.into_iter();
+    let mut empty = empty_indices.iter().peekable();
+    let mut merged = Vec::with_capacity(total);
+    let mut errors = Vec::new();
+
+    for index in 0..total {
+        if empty.next_if(|&&i| i == index).is_some() {
+            match behavior {
+                EmptyInputBehavior::Reject => {
+                    merged.push(Vec::new());
+                    errors.push(EmbedItemError {
+                        index,
+                        error: EMPTY_INPUT_ERROR.to_string(),
+                    });
+                }
+                EmptyInputBehavior::Zero => merged.push(vec![0.0; zero_dimensions]),
+            }
+        } else if let Some(embedding) = embedded.next() {
+            merged.push(embedding);
+        }
+    }
+
+    (merged, errors)
+}
+
+/// Generate embeddings, keeping empty or whitespace-only inputs away from the
+/// model and handling them per the configured [`EmptyInputBehavior`]. The
+/// model's prefix for `input_type` is applied after empty inputs are set
+/// aside, so a prefix never makes an empty input embeddable.
+async fn embed_non_empty(
+    model_id: &str,
+    config: &ModelConfig,
+    texts: Vec<String>,
+    input_type: Option<InputType>,
+) -> Result<(Vec<Vec<f32>>, Vec<EmbedItemError>), InferenceError> {
+    let total = texts.len();
+    let (to_embed, empty_indices) = partition_empty_inputs(texts);
+    let to_embed = input_type::apply_prefix(config, model_id, input_type, to_embed);
+
+    if empty_indices.is_empty() {
+        return embedding::generate_embeddings(model_id, config, to_embed)
+            .await
+            .map(|embeddings| (embeddings, Vec::new()));
+    }
+
+    let behavior = config.empty_input_behavior;
+    if to_embed.is_empty() && behavior == EmptyInputBehavior::Reject {
+        return Err(InferenceError::BadRequest(if total == 1 {
+            EMPTY_INPUT_ERROR.to_string()
+        } else {
+            format!("All {total} inputs are empty or whitespace-only")
+        }));
+    }
+
+    let embeddings = if to_embed.is_empty() {
+        Vec::new()
+    } else {
+        embedding::generate_embeddings(model_id, config, to_embed).await?
+    };
+
+    let zero_dimensions = match behavior {
+        EmptyInputBehavior::Reject => 0,
+        EmptyInputBehavior::Zero => embeddings
+            .first()
+            .map(|e| e.len())
+            .or_else(|| embedding::model_dimensions(model_id, config))
+            .ok_or_else(|| {
+                InferenceError::unavailable_model(
+                    model_id,
+                    embedding::is_known_embedding_model(model_id),
+                )
+            })?,
+    };
+
+    Ok(merge_embeddings(
+        total,
+        &empty_indices,
+        embeddings,
+        behavior,
+        zero_dimensions,
+    ))
 }
 
 use crate::models::get_embedding_models;
@@ -83,6 +214,8 @@ pub async fn list_embedders(config: web::Data<ModelConfig>) -> impl Responder {
     responses(
         (status = 200, description = "Embedding generated successfully", body = EmbedResponse),
         (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Unknown model"),
         (status = 500, description = "Internal server error")
     ),
     tag = "embedding"
@@ -100,13 +233,17 @@ pub async fn embed(
 
     let start = Instant::now();
 
-    // Generate embeddings asynchronously
-    let result = embedding::generate_embeddings(&model_id, &config, vec![text]).await;
+    // Generate embeddings asynchronously. If the client disconnects, actix
+    // drops this future: the guard counts the cancellation and the model
+    // worker skips the request if it is still queued.
+    let guard = CancellationGuard::new("embed", &model_id);
+    let result = embed_non_empty(&model_id, &config, vec![text], body.input_type).await;
+    guard.complete();
 
     let duration = start.elapsed().as_secs_f64();
 
     match result {
-        Ok(embeddings) => {
+        Ok((embeddings, errors)) => {
             semantic_explorer_core::observability::record_embed_request(
                 &model_id, 1, duration, true,
             );
@@ -122,6 +259,7 @@ pub async fn embed(
                 model: model_id,
                 count: 1,
                 dimensions,
+                errors,
             })
         }
         Err(e) => {
@@ -141,6 +279,8 @@ pub async fn embed(
     responses(
         (status = 200, description = "Embeddings generated successfully", body = EmbedResponse),
         (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Unknown model"),
         (status = 500, description = "Internal server error")
     ),
     tag = "embedding"
@@ -157,6 +297,7 @@ pub async fn embed_batch(
             model: body.model.clone(),
             count: 0,
             dimensions: 0,
+            errors: vec![],
         });
     }
 
@@ -178,12 +319,14 @@ pub async fn embed_batch(
     let start = Instant::now();
 
     // Generate embeddings asynchronously
-    let result = embedding::generate_embeddings(&model_id, &config, texts).await;
+    let guard = CancellationGuard::new("embed_batch", &model_id);
+    let result = embed_non_empty(&model_id, &config, texts, body.input_type).await;
+    guard.complete();
 
     let duration = start.elapsed().as_secs_f64();
 
     match result {
-        Ok(embeddings) => {
+        Ok((embeddings, errors)) => {
             semantic_explorer_core::observability::record_embed_request(
                 &model_id, item_count, duration, true,
             );
@@ -192,7 +335,11 @@ pub async fn embed_batch(
                 model_id, duration
             );
             let count = embeddings.len();
-            let dimensions = embeddings.first().map(|e| e.len()).unwrap_or(0);
+            let dimensions = embeddings
+                .iter()
+                .map(|e| e.len())
+                .find(|&d| d > 0)
+                .unwrap_or(0);
             let mut response = HttpResponse::Ok();
             add_backpressure_headers(&mut response, &model_id);
             response.json(EmbedResponse {
@@ -200,6 +347,7 @@ pub async fn embed_batch(
                 model: model_id,
                 count,
                 dimensions,
+                errors,
             })
         }
         Err(e) => {
@@ -210,3 +358,153 @@ pub async fn embed_batch(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_partition_detects_empty_and_whitespace_inputs() {
+        let (to_embed, empty) = partition_empty_inputs(texts(&["hello", "", "  \n\t", "world"]));
+        assert_eq!(to_embed, texts(&["hello", "world"]));
+        assert_eq!(empty, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_merge_rejects_empty_inputs_with_per_item_errors() {
+        let embedded = vec![vec![0.1, 0.2], vec![0.3, 0.4]];
+        let (merged, errors) =
+            merge_embeddings(4, &[1, 2], embedded, EmptyInputBehavior::Reject, 0);
+
+        // Non-empty items keep their vectors at their original positions
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged[0], vec![0.1, 0.2]);
+        assert!(merged[1].is_empty());
+        assert!(merged[2].is_empty());
+        assert_eq!(merged[3], vec![0.3, 0.4]);
+        assert!(merged.iter().flatten().all(|v| v.is_finite()));
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].index, 1);
+        assert_eq!(errors[1].index, 2);
+        assert_eq!(errors[0].error, EMPTY_INPUT_ERROR);
+    }
+
+    #[test]
+    fn test_merge_zero_behavior_fills_zero_vectors() {
+        let embedded = vec![vec![0.5, 0.5, 0.5]];
+        let (merged, errors) = merge_embeddings(2, &[0], embedded, EmptyInputBehavior::Zero, 3);
+
+        assert!(errors.is_empty());
+        assert_eq!(merged, vec![vec![0.0, 0.0, 0.0], vec![0.5, 0.5, 0.5]]);
+    }
+
+    /// Config that allows every embedding model and records no metrics
+    fn test_config() -> ModelConfig {
+        use crate::config::CudaArenaExtendStrategy;
+
+        ModelConfig {
+            hf_home: None,
+            hf_endpoint: None,
+            hf_token: None,
+            model_path: None,
+            all_embedding_models: true,
+            allowed_embedding_models: vec![],
+            all_rerank_models: false,
+            allowed_rerank_models: vec![],
+            max_batch_size: 128,
+            max_queue_depth: 8,
+            max_loaded_models: None,
+            queue_timeout_ms: 30000,
+            batch_window_ms: 5,
+            max_coalesced_batch_size: 64,
+            gpu_pressure_threshold: 95.0,
+            gpu_pressure_soft_threshold: None,
+            gpu_pressure_soft_batch_size: 8,
+            cuda_arena_size: None,
+            cuda_arena_extend_strategy: CudaArenaExtendStrategy::NextPowerOfTwo,
+            require_gpu: false,
+            cpu_models: Vec::new(),
+            gpu_batch_size: 32,
+            embed_length_metrics: false,
+            empty_input_behavior: EmptyInputBehavior::Reject,
+            input_prefixes: Default::default(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_batch_accepts_compressed_request_and_compresses_response() {
+        use actix_web::http::header;
+        use actix_web::{App, middleware::Compress, test};
+        use semantic_explorer_core::compression::BodyCompression;
+
+        let config = test_config();
+        let app = test::init_service(
+            App::new()
+                .wrap(Compress::default())
+                .app_data(web::Data::new(config))
+                .service(embed_batch),
+        )
+        .await;
+
+        // An empty batch is answered without loading a model
+        let body = BodyCompression::Gzip
+            .encode(br#"{"texts":[],"model":"test-model"}"#)
+            .unwrap();
+        let req = test::TestRequest::post()
+            .uri("/api/embed/batch")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .insert_header((header::ACCEPT_ENCODING, "zstd"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+        assert_eq!(
+            resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "zstd"
+        );
+        let bytes = test::read_body(resp).await;
+        let decoded = BodyCompression::Zstd.decode(&bytes).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&decoded).unwrap();
+        assert_eq!(json["model"], "test-model");
+        assert_eq!(json["count"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_unknown_model_is_not_recorded() {
+        use crate::observability::test_support::{metrics_registry, sample};
+        use actix_web::{App, test};
+
+        let registry = metrics_registry();
+        // The model isn't allowed, so the client-chosen name must not become
+        // a metric label
+        let config = ModelConfig {
+            all_embedding_models: false,
+            embed_length_metrics: true,
+            ..test_config()
+        };
+        let app =
+            test::init_service(App::new().app_data(web::Data::new(config)).service(embed)).await;
+        let req = test::TestRequest::post()
+            .uri("/api/embed")
+            .set_json(serde_json::json!({"text": "héllo world", "model": "test/length-model"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error());
+
+        assert_eq!(
+            sample(
+                registry,
+                "inference_embed_input_chars_count",
+                "test/length-model"
+            ),
+            None
+        );
+    }
+}
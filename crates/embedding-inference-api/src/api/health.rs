@@ -2,10 +2,14 @@
 
 use crate::config::ModelConfig;
 use crate::embedding;
+use crate::execution_provider;
+use crate::gpu_pressure;
 use crate::reranker;
 use actix_web::{HttpResponse, Responder, get, web};
 
-/// Liveness probe - always returns OK if the service is running
+/// Liveness probe - always returns OK if the service is running. It touches
+/// no model or GPU state and answers with a static body, so load balancers
+/// can poll it freely; it is excluded from metrics.
 #[utoipa::path(
     get,
     path = "/health/live",
@@ -16,12 +20,13 @@ use actix_web::{HttpResponse, Responder, get, web};
 )]
 #[get("/health/live")]
 pub async fn health_live() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "ok"
-    }))
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"status":"ok"}"#)
 }
 
-/// Readiness probe - returns OK if models are loaded
+/// Readiness probe - returns OK once the default embedding model is loaded,
+/// 503 while it is still loading or if it failed to load
 #[utoipa::path(
     get,
     path = "/health/ready",
@@ -38,18 +43,19 @@ pub async fn health_ready() -> impl Responder {
 
     // Service is ready if at least embeddings are loaded
     // Reranker is optional
+    let models = embedding::model_load_status();
+    let body = serde_json::json!({
+        "status": if embedding_ready { "ok" } else { "not_ready" },
+        "embedding": embedding_ready,
+        "reranker": reranker_ready,
+        "default_model": models["default_model"],
+        "models_loaded": models["loaded"],
+        "models_pending": models["pending"]
+    });
     if embedding_ready {
-        HttpResponse::Ok().json(serde_json::json!({
-            "status": "ok",
-            "embedding": embedding_ready,
-            "reranker": reranker_ready
-        }))
+        HttpResponse::Ok().json(body)
     } else {
-        HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "status": "not_ready",
-            "embedding": embedding_ready,
-            "reranker": reranker_ready
-        }))
+        HttpResponse::ServiceUnavailable().json(body)
     }
 }
 
@@ -68,12 +74,17 @@ pub async fn health_status(_config: web::Data<ModelConfig>) -> impl Responder {
     let reranker_ready = reranker::is_ready();
     let total_queue_depth = embedding::total_queue_depth();
     let gpu_pressure = embedding::is_gpu_pressure_high();
+    let gpu_pressure_level = gpu_pressure::current_level();
 
     HttpResponse::Ok().json(serde_json::json!({
         "status": if embedding_ready { "ok" } else { "not_ready" },
         "embedding_ready": embedding_ready,
         "reranker_ready": reranker_ready,
         "total_queue_depth": total_queue_depth,
-        "gpu_vram_pressure": gpu_pressure
+        "gpu_vram_pressure": gpu_pressure,
+        "gpu_pressure_level": gpu_pressure_level.as_str(),
+        "embedding_models": embedding::model_load_status(),
+        "resident_models": embedding::resident_models().await,
+        "execution_providers": execution_provider::active_providers()
     }))
 }
@@ -89,6 +89,8 @@ pub async fn list_rerankers(config: web::Data<ModelConfig>) -> impl Responder {
     responses(
         (status = 200, description = "Documents reranked successfully", body = RerankResponse),
         (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Unknown model"),
         (status = 500, description = "Internal server error")
     ),
     tag = "reranking"
@@ -0,0 +1,137 @@
+//! Dynamic batching of embed requests in a model's queue.
+//!
+//! Many small concurrent requests each pay for a full model call. The model
+//! worker instead coalesces the requests that arrive within a short window
+//! into one call, up to a cap on the number of texts, and splits the
+//! embeddings back into one reply per request.
+
+use std::time::Duration;
+
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::mpsc::error::TryRecvError;
+
+/// Collect the requests to embed in one model call: `first`, plus those
+/// arriving within `window` of it while the total number of texts stays
+/// within `max_texts`. Requests already queued are taken without waiting,
+/// even with a zero window. A request that doesn't fit is returned
+/// separately to start the next call.
+pub(crate) async fn collect_batch<T>(
+    first: T,
+    rx: &mut Receiver<T>,
+    window: Duration,
+    max_texts: usize,
+    text_count: impl Fn(&T) -> usize,
+) -> (Vec<T>, Option<T>) {
+    let deadline = tokio::time::Instant::now() + window;
+    let mut total = text_count(&first);
+    let mut batch = vec![first];
+
+    while total < max_texts {
+        let next = match rx.try_recv() {
+            Ok(request) => request,
+            Err(TryRecvError::Disconnected) => break,
+            Err(TryRecvError::Empty) => {
+                if window.is_zero() {
+                    break;
+                }
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(request)) => request,
+                    Ok(None) | Err(_) => break,
+                }
+            }
+        };
+        let count = text_count(&next);
+        if total + count > max_texts {
+            return (batch, Some(next));
+        }
+        total += count;
+        batch.push(next);
+    }
+    (batch, None)
+}
+
+/// Split the embeddings of a coalesced call into one list per request, in
+/// order. `counts` holds the number of texts each request sent.
+pub(crate) fn scatter(
+    embeddings: Vec<Vec<f32>>,
+    counts: &[usize],
+) -> Result<Vec<Vec<Vec<f32>>>, String> {
+    let expected: usize = counts.iter().sum();
+    if embeddings.len() != expected {
+        return Err(format!(
+            "model returned {} embeddings for {} texts",
+            embeddings.len(),
+            expected
+        ));
+    }
+    let mut embeddings = embeddings.into_iter();
+    Ok(counts
+        .iter()
+        .map(|&count| embeddings.by_ref().take(count).collect())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_batch_coalesces_queued_requests() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        for texts in [1, 1, 2, 3] {
+            tx.send(texts).await.unwrap();
+        }
+
+        let first = rx.recv().await.unwrap();
+        let (batch, carry) = collect_batch(first, &mut rx, Duration::ZERO, 4, |n| *n).await;
+        assert_eq!(batch, vec![1, 1, 2]);
+        // The request that would overflow the cap starts the next call
+        assert_eq!(carry, Some(3));
+
+        let (batch, carry) =
+            collect_batch(carry.unwrap(), &mut rx, Duration::ZERO, 4, |n| *n).await;
+        assert_eq!(batch, vec![3]);
+        assert_eq!(carry, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_batch_waits_for_window() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            tx.send(1).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            tx.send(1).await.unwrap();
+        });
+
+        let start = tokio::time::Instant::now();
+        let (batch, carry) = collect_batch(1, &mut rx, Duration::from_millis(100), 8, |n| *n).await;
+        assert_eq!(batch, vec![1, 1]);
+        assert_eq!(carry, None);
+        // Gives up at the end of the window instead of waiting for the cap
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_runs_alone() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        tx.send(1).await.unwrap();
+
+        let (batch, carry) = collect_batch(10, &mut rx, Duration::ZERO, 4, |n| *n).await;
+        assert_eq!(batch, vec![10]);
+        assert_eq!(carry, None);
+        assert_eq!(rx.recv().await, Some(1));
+    }
+
+    #[test]
+    fn test_scatter_returns_each_request_its_own_embeddings() {
+        let embeddings = vec![vec![0.0], vec![1.0], vec![2.0], vec![3.0]];
+        let split = scatter(embeddings, &[1, 2, 1]).unwrap();
+        assert_eq!(
+            split,
+            vec![vec![vec![0.0]], vec![vec![1.0], vec![2.0]], vec![vec![3.0]]]
+        );
+
+        assert!(scatter(vec![vec![0.0]], &[1, 1]).is_err());
+    }
+}
@@ -5,10 +5,13 @@
 //! Supports TLS/SSL for secure deployments using shared core configuration.
 
 use anyhow::{Context, Result};
-use semantic_explorer_core::config::TlsConfig;
+use semantic_explorer_core::config::{ConfigValidationError, TlsConfig};
+use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 
+use crate::input_type::{self, InputPrefixes};
+
 /// Parse a human-readable byte size string (e.g. "4G", "512M", "1024K", "8589934592")
 /// into a byte count. Supports suffixes: K/KB, M/MB, G/GB, T/TB (case-insensitive).
 fn parse_byte_size(s: &str) -> Result<usize> {
@@ -93,11 +96,28 @@ pub struct ModelConfig {
     /// before the service returns 503. Each model has a dedicated worker thread
     /// that processes requests sequentially from a bounded channel.
     pub max_queue_depth: usize,
-    /// Queue timeout in milliseconds — how long to wait to enqueue before 503
-    /// Setting this higher allows requests to wait briefly for a queue slot
+    /// Most embedding models resident at once. Models past the cap load on
+    /// first use, evicting the least recently used one. None keeps every
+    /// allowed model loaded.
+    pub max_loaded_models: Option<usize>,
+    /// Queue timeout in milliseconds — how long to wait to enqueue before 503,
+    /// and how long a queued request may wait for the worker before it is
+    /// rejected. Setting this higher allows requests to wait briefly for a queue slot
     pub queue_timeout_ms: u64,
+    /// How long a model worker waits for more requests to coalesce into one
+    /// model call, in milliseconds. 0 only coalesces requests already queued.
+    pub batch_window_ms: u64,
+    /// Most texts a model worker coalesces into one model call. Larger
+    /// requests still run, on their own.
+    pub max_coalesced_batch_size: usize,
     /// GPU pressure threshold percentage — reject requests above this % VRAM or compute utilization
     pub gpu_pressure_threshold: f64,
+    /// Soft GPU pressure threshold percentage — above this % VRAM the ONNX
+    /// inference sub-batch shrinks to `gpu_pressure_soft_batch_size` instead
+    /// of rejecting requests. None disables the degraded stage.
+    pub gpu_pressure_soft_threshold: Option<f64>,
+    /// ONNX inference sub-batch size used above the soft threshold
+    pub gpu_pressure_soft_batch_size: usize,
     /// CUDA memory arena size limit in bytes.
     /// When set, limits how much GPU VRAM the ONNX Runtime arena can allocate.
     /// When None (default), uses all available GPU memory (usize::MAX).
@@ -106,6 +126,32 @@ pub struct ModelConfig {
     /// NextPowerOfTwo (default): each extension doubles — fewer but larger allocations.
     /// SameAsRequested: each extension is exactly the requested size — more granular.
     pub cuda_arena_extend_strategy: CudaArenaExtendStrategy,
+    /// Fail model loads when the CUDA execution provider can't initialize,
+    /// instead of falling back to the CPU. Defaults to false.
+    pub require_gpu: bool,
+    /// Models that always run on the CPU, leaving VRAM to the others
+    pub cpu_models: Vec<String>,
+    /// Record per-input length histograms (characters, and tokens for models
+    /// with an available tokenizer) on every embed request. Defaults to true.
+    pub embed_length_metrics: bool,
+    /// How empty or whitespace-only inputs are handled. Defaults to `Reject`.
+    pub empty_input_behavior: EmptyInputBehavior,
+    /// Query and document instruction prefixes per model, replacing the
+    /// built-in ones (see [`crate::input_type`])
+    pub input_prefixes: HashMap<String, InputPrefixes>,
+}
+
+/// How the embed endpoints treat empty or whitespace-only inputs.
+///
+/// Such inputs are never sent to the model: depending on the tokenizer they
+/// produce degenerate (NaN) vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmptyInputBehavior {
+    /// Reject the input. Single requests fail with 400; batch requests return
+    /// a per-item error and an empty vector at that position (default).
+    Reject,
+    /// Return an all-zero vector with the model's dimensions.
+    Zero,
 }
 
 /// Strategy for extending the CUDA memory arena.
@@ -145,6 +191,7 @@ impl InferenceConfig {
             observability: ObservabilityConfig::from_env()?,
             tls: TlsConfig::from_env()?,
         };
+        ConfigValidationError::check(config.validate())?;
 
         // Log configuration for airgapped deployments
         config.log_environment_config();
@@ -152,6 +199,27 @@ impl InferenceConfig {
         Ok(config)
     }
 
+    /// Every cross-field problem of the configuration
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = self.tls.validate();
+        let models = &self.models;
+        if models.max_batch_size == 0 {
+            problems.push("INFERENCE_MAX_BATCH_SIZE must be at least 1".to_string());
+        }
+        if models.gpu_batch_size == 0 {
+            problems.push("INFERENCE_GPU_BATCH_SIZE must be at least 1".to_string());
+        }
+        if let Some(soft) = models.gpu_pressure_soft_threshold
+            && soft >= models.gpu_pressure_threshold
+        {
+            problems.push(format!(
+                "GPU_PRESSURE_SOFT_THRESHOLD ({soft}) must be below GPU_PRESSURE_THRESHOLD ({})",
+                models.gpu_pressure_threshold
+            ));
+        }
+        problems
+    }
+
     /// Log environment configuration for debugging airgapped deployments
     fn log_environment_config(&self) {
         // Log HF_HOME if configured
@@ -273,12 +341,39 @@ impl ModelConfig {
             // Default 8: each model has a dedicated worker thread that processes
             // requests sequentially. The queue depth controls how many requests
             // can be buffered before returning 503.
+            max_loaded_models: match env::var("INFERENCE_MAX_LOADED_MODELS") {
+                Ok(val) if !val.trim().is_empty() => {
+                    let value: usize = val
+                        .trim()
+                        .parse()
+                        .context("INFERENCE_MAX_LOADED_MODELS must be a number")?;
+                    if value == 0 {
+                        anyhow::bail!("INFERENCE_MAX_LOADED_MODELS must be at least 1");
+                    }
+                    Some(value)
+                }
+                _ => None,
+            },
             queue_timeout_ms: env::var("INFERENCE_QUEUE_TIMEOUT_MS")
                 .unwrap_or_else(|_| "30000".to_string())
                 .parse()
                 .context("INFERENCE_QUEUE_TIMEOUT_MS must be a number")?,
             // Default 30s: callers should wait rather than get 503 and retry.
             // This prevents retry storms in distributed deployments.
+            batch_window_ms: env::var("INFERENCE_BATCH_WINDOW_MS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("INFERENCE_BATCH_WINDOW_MS must be a number")?,
+            max_coalesced_batch_size: {
+                let value: usize = env::var("INFERENCE_MAX_COALESCED_BATCH_SIZE")
+                    .unwrap_or_else(|_| "64".to_string())
+                    .parse()
+                    .context("INFERENCE_MAX_COALESCED_BATCH_SIZE must be a number")?;
+                if value == 0 {
+                    anyhow::bail!("INFERENCE_MAX_COALESCED_BATCH_SIZE must be at least 1");
+                }
+                value
+            },
             gpu_pressure_threshold: env::var("GPU_PRESSURE_THRESHOLD")
                 .unwrap_or_else(|_| "98.0".to_string())
                 .parse()
@@ -286,6 +381,23 @@ impl ModelConfig {
             // Default raised to 98%: the trickle-through mechanism in
             // generate_embeddings() prevents deadlocks, and the wider arena
             // margin (threshold - 10%) provides the real safety net.
+            gpu_pressure_soft_threshold: match env::var("GPU_PRESSURE_SOFT_THRESHOLD") {
+                Ok(val) if !val.trim().is_empty() => {
+                    let value: f64 = val
+                        .trim()
+                        .parse()
+                        .context("GPU_PRESSURE_SOFT_THRESHOLD must be a number")?;
+                    if !(0.0..=100.0).contains(&value) {
+                        anyhow::bail!("GPU_PRESSURE_SOFT_THRESHOLD must be between 0 and 100");
+                    }
+                    Some(value)
+                }
+                _ => None, // Default: reject above GPU_PRESSURE_THRESHOLD only
+            },
+            gpu_pressure_soft_batch_size: env::var("GPU_PRESSURE_SOFT_BATCH_SIZE")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .context("GPU_PRESSURE_SOFT_BATCH_SIZE must be a number")?,
             cuda_arena_size: match env::var("CUDA_ARENA_SIZE") {
                 Ok(val) if !val.trim().is_empty() && val.trim() != "0" => {
                     Some(parse_byte_size(&val).context(
@@ -308,6 +420,36 @@ impl ModelConfig {
                 },
                 _ => CudaArenaExtendStrategy::NextPowerOfTwo,
             },
+            require_gpu: env::var("INFERENCE_REQUIRE_GPU")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("INFERENCE_REQUIRE_GPU must be true or false")?,
+            cpu_models: env::var("INFERENCE_CPU_MODELS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect(),
+            embed_length_metrics: env::var("INFERENCE_EMBED_LENGTH_METRICS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .context("INFERENCE_EMBED_LENGTH_METRICS must be true or false")?,
+            empty_input_behavior: match env::var("INFERENCE_EMPTY_INPUT_BEHAVIOR") {
+                Ok(val) => match val.trim().to_lowercase().as_str() {
+                    "reject" | "" => EmptyInputBehavior::Reject,
+                    "zero" => EmptyInputBehavior::Zero,
+                    other => anyhow::bail!(
+                        "INFERENCE_EMPTY_INPUT_BEHAVIOR must be 'reject' (default) or 'zero', got: {other}"
+                    ),
+                },
+                _ => EmptyInputBehavior::Reject,
+            },
+            input_prefixes: input_type::parse_input_prefixes(
+                &env::var("INFERENCE_INPUT_PREFIXES").unwrap_or_default(),
+            )
+            .context(
+                "INFERENCE_INPUT_PREFIXES must be a JSON object mapping models to {\"query\": ..., \"document\": ...}",
+            )?,
         })
     }
 
@@ -319,6 +461,11 @@ impl ModelConfig {
                 .contains(&model_id.to_string())
     }
 
+    /// Whether `model_id` is pinned to the CPU execution provider
+    pub fn runs_on_cpu(&self, model_id: &str) -> bool {
+        self.cpu_models.iter().any(|m| m == model_id)
+    }
+
     /// Check if a rerank model is allowed based on configuration
     pub fn is_rerank_model_allowed(&self, model_id: &str) -> bool {
         self.all_rerank_models || self.allowed_rerank_models.contains(&model_id.to_string())
@@ -415,11 +562,21 @@ mod tests {
             allowed_rerank_models: vec![],
             max_batch_size: 128,
             max_queue_depth: 8,
+            max_loaded_models: None,
             queue_timeout_ms: 30000,
+            batch_window_ms: 5,
+            max_coalesced_batch_size: 64,
             gpu_pressure_threshold: 95.0,
+            gpu_pressure_soft_threshold: None,
+            gpu_pressure_soft_batch_size: 8,
             cuda_arena_size: None,
             cuda_arena_extend_strategy: CudaArenaExtendStrategy::NextPowerOfTwo,
+            require_gpu: false,
+            cpu_models: Vec::new(),
             gpu_batch_size: 32,
+            embed_length_metrics: true,
+            empty_input_behavior: EmptyInputBehavior::Reject,
+            input_prefixes: Default::default(),
         };
 
         assert_eq!(model.allowed_embedding_models.len(), 2);
@@ -445,11 +602,21 @@ mod tests {
             allowed_rerank_models: vec![],
             max_batch_size: 128,
             max_queue_depth: 8,
+            max_loaded_models: None,
             queue_timeout_ms: 30000,
+            batch_window_ms: 5,
+            max_coalesced_batch_size: 64,
             gpu_pressure_threshold: 95.0,
+            gpu_pressure_soft_threshold: None,
+            gpu_pressure_soft_batch_size: 8,
             cuda_arena_size: None,
             cuda_arena_extend_strategy: CudaArenaExtendStrategy::NextPowerOfTwo,
+            require_gpu: false,
+            cpu_models: Vec::new(),
             gpu_batch_size: 32,
+            embed_length_metrics: true,
+            empty_input_behavior: EmptyInputBehavior::Reject,
+            input_prefixes: Default::default(),
         };
         assert!(config_all_allowed.is_embedding_model_allowed("any-model"));
         assert!(config_all_allowed.is_rerank_model_allowed("any-model"));
@@ -469,11 +636,21 @@ mod tests {
             allowed_rerank_models: vec!["BAAI/bge-reranker-base".to_string()],
             max_batch_size: 128,
             max_queue_depth: 8,
+            max_loaded_models: None,
             queue_timeout_ms: 30000,
+            batch_window_ms: 5,
+            max_coalesced_batch_size: 64,
             gpu_pressure_threshold: 95.0,
+            gpu_pressure_soft_threshold: None,
+            gpu_pressure_soft_batch_size: 8,
             cuda_arena_size: None,
             cuda_arena_extend_strategy: CudaArenaExtendStrategy::NextPowerOfTwo,
+            require_gpu: false,
+            cpu_models: Vec::new(),
             gpu_batch_size: 32,
+            embed_length_metrics: true,
+            empty_input_behavior: EmptyInputBehavior::Reject,
+            input_prefixes: Default::default(),
         };
         // Embedding checks
         assert!(config_restricted.is_embedding_model_allowed("BAAI/bge-small-en-v1.5"));
@@ -498,11 +675,21 @@ mod tests {
             allowed_rerank_models: vec![],
             max_batch_size: 128,
             max_queue_depth: 8,
+            max_loaded_models: None,
             queue_timeout_ms: 30000,
+            batch_window_ms: 5,
+            max_coalesced_batch_size: 64,
             gpu_pressure_threshold: 95.0,
+            gpu_pressure_soft_threshold: None,
+            gpu_pressure_soft_batch_size: 8,
             cuda_arena_size: None,
             cuda_arena_extend_strategy: CudaArenaExtendStrategy::NextPowerOfTwo,
+            require_gpu: false,
+            cpu_models: Vec::new(),
             gpu_batch_size: 32,
+            embed_length_metrics: true,
+            empty_input_behavior: EmptyInputBehavior::Reject,
+            input_prefixes: Default::default(),
         };
         assert!(!config_no_rerankers.is_rerank_model_allowed("any-model"));
     }
@@ -2,21 +2,23 @@ use candle_core::{DType, Device};
 use fastembed::{EmbeddingModel, Qwen3TextEmbedding, TextEmbedding, TextInitOptions};
 use futures::stream::StreamExt;
 use once_cell::sync::OnceCell;
-use ort::ep::ArenaExtendStrategy;
-use ort::ep::CUDA;
-use ort::ep::cuda::AttentionBackend;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::thread::available_parallelism;
 use std::time::{Duration, Instant};
+use tokenizers::Tokenizer;
 use tokio::sync::{RwLock, oneshot};
 use tracing::{debug, error, info, warn};
 
 use std::sync::OnceLock;
 
+use crate::batching;
 use crate::config::ModelConfig;
 use crate::errors::InferenceError;
+use crate::execution_provider::{self, ExecutionProvider, ProviderPolicy};
+use crate::gpu_pressure::{self, Admission, PressureLevel, PressurePolicy};
+use crate::model_status::{LoadStart, ModelLoadTracker, eviction_order};
 
 use semantic_explorer_core::observability::gpu_monitor;
 
@@ -79,10 +81,11 @@ pub(crate) struct AvailableModel {
 // ---------------------------------------------------------------------------
 //
 // Each loaded model gets a dedicated worker thread and a bounded async channel.
-// Requests are submitted to the channel and the worker processes them one at a
-// time — no Mutex contention, no latency snowball, completely deterministic
-// queue depth.  The bounded channel provides natural backpressure: when full,
-// senders get a clear signal to back off.
+// Requests are submitted to the channel and the worker processes them one
+// model call at a time — no Mutex contention, no latency snowball, completely
+// deterministic queue depth.  Small requests arriving close together are
+// coalesced into one call (see [`batching`]).  The bounded channel provides
+// natural backpressure: when full, senders get a clear signal to back off.
 
 /// A request sent through the model channel.
 struct EmbedRequest {
@@ -93,10 +96,19 @@ struct EmbedRequest {
 }
 
 /// Handle to a model's dedicated processing queue.
+///
+/// Requests hold an `Arc` of the handle while they use it, so evicting a
+/// model only drops the registry's reference: the worker keeps serving the
+/// requests already holding the handle, and exits (freeing the model's
+/// VRAM) once the last sender is gone.
 struct ModelHandle {
     sender: tokio::sync::mpsc::Sender<EmbedRequest>,
     /// Current queue depth (updated atomically by sender/worker).
     queue_depth: Arc<AtomicUsize>,
+    /// `USE_CLOCK` tick of the model's most recent request, for LRU eviction
+    last_used: AtomicU64,
+    /// Copy of the model's tokenizer for input length metrics, if it has one
+    tokenizer: Option<Tokenizer>,
 }
 
 type ModelRegistry = Arc<RwLock<HashMap<String, Arc<ModelHandle>>>>;
@@ -104,23 +116,45 @@ type ModelRegistry = Arc<RwLock<HashMap<String, Arc<ModelHandle>>>>;
 /// Global model registry — maps model_code → ModelHandle
 static MODEL_REGISTRY: OnceCell<ModelRegistry> = OnceCell::new();
 
-/// Cached GPU VRAM pressure state (updated by background monitor)
-static GPU_PRESSURE_HIGH: AtomicBool = AtomicBool::new(false);
-
-/// GPU pressure threshold — configured via GPU_PRESSURE_THRESHOLD env var (default 95.0)
-static GPU_PRESSURE_THRESHOLD: OnceLock<f64> = OnceLock::new();
-
 /// Exponential moving average of per-request latency in microseconds.
 /// Used to populate the `X-Estimated-Wait-Ms` backpressure header so callers
 /// can pace themselves proactively instead of waiting for 503s.
 static EMA_LATENCY_US: AtomicU64 = AtomicU64::new(0);
 
+/// Load state of the configured models, set when `init_cache` starts
+static LOAD_TRACKER: OnceLock<std::sync::RwLock<ModelLoadTracker>> = OnceLock::new();
+
+/// Set once the default model has loaded; read by the readiness probe
+static DEFAULT_MODEL_READY: AtomicBool = AtomicBool::new(false);
+
+/// Logical clock ordering model use for LRU eviction
+static USE_CLOCK: AtomicU64 = AtomicU64::new(0);
+
 /// Queue capacity (max pending requests per model)
 static QUEUE_CAPACITY: OnceLock<usize> = OnceLock::new();
 
 /// Queue timeout for waiting to enqueue
 static QUEUE_TIMEOUT: OnceLock<Duration> = OnceLock::new();
 
+/// How long a worker waits for more requests to coalesce, and the most texts
+/// it coalesces into one model call
+static BATCHING: OnceLock<(Duration, usize)> = OnceLock::new();
+
+/// Initialize dynamic request batching.
+pub fn init_batching_config(batch_window_ms: u64, max_coalesced_batch_size: usize) {
+    BATCHING.get_or_init(|| {
+        info!(
+            batch_window_ms = batch_window_ms,
+            max_coalesced_batch_size = max_coalesced_batch_size,
+            "Initialized embedding request batching"
+        );
+        (
+            Duration::from_millis(batch_window_ms),
+            max_coalesced_batch_size.max(1),
+        )
+    });
+}
+
 /// Initialize queue configuration.
 pub fn init_queue_config(max_queue_depth: usize, queue_timeout_ms: u64) {
     QUEUE_CAPACITY.get_or_init(|| {
@@ -182,11 +216,12 @@ pub fn total_queue_depth() -> usize {
         .unwrap_or(0)
 }
 
-/// Spawn background task that monitors GPU VRAM pressure and updates cached state.
+/// Spawn background task that monitors GPU VRAM pressure and updates the
+/// cached pressure level (see [`gpu_pressure`]).
 ///
 /// Only VRAM utilization is checked.
-pub fn spawn_gpu_pressure_monitor(threshold: f64) {
-    GPU_PRESSURE_THRESHOLD.get_or_init(|| threshold);
+pub(crate) fn spawn_gpu_pressure_monitor(policy: PressurePolicy) {
+    let policy = gpu_pressure::init(policy);
     tokio::spawn(async move {
         if !gpu_monitor::init() {
             warn!("GPU monitoring disabled - NVML not available");
@@ -195,7 +230,9 @@ pub fn spawn_gpu_pressure_monitor(threshold: f64) {
 
         info!(
             device_count = gpu_monitor::device_count(),
-            threshold = threshold,
+            soft_threshold = ?policy.soft_threshold,
+            hard_threshold = policy.hard_threshold,
+            degraded_batch_size = policy.degraded_batch_size,
             "Starting GPU VRAM pressure monitor"
         );
 
@@ -211,10 +248,19 @@ pub fn spawn_gpu_pressure_monitor(threshold: f64) {
             gpu_monitor::collect_metrics();
 
             // Only check VRAM pressure — compute utilization being high is expected
-            let is_high = gpu_monitor::is_memory_pressure_high(threshold);
-            GPU_PRESSURE_HIGH.store(is_high, Ordering::Relaxed);
+            let vram_percent = gpu_monitor::max_memory_utilization_percent().unwrap_or(0.0);
+            let level = policy.level(vram_percent);
+            let previous = gpu_pressure::set_level(level);
+            if level != previous {
+                info!(
+                    from = previous.as_str(),
+                    to = level.as_str(),
+                    vram_percent,
+                    "GPU VRAM pressure level changed"
+                );
+            }
 
-            if is_high {
+            if level == PressureLevel::Critical {
                 let total_queued = total_queue_depth();
                 if total_queued == 0 {
                     consecutive_high_idle_ticks += 1;
@@ -231,7 +277,7 @@ pub fn spawn_gpu_pressure_monitor(threshold: f64) {
                         "GPU VRAM high but model idle — likely static CUDA arena allocation"
                     );
                 } else {
-                    warn!("GPU VRAM pressure HIGH (>{}% used)", threshold);
+                    warn!("GPU VRAM pressure HIGH (>{}% used)", policy.hard_threshold);
                 }
             } else {
                 consecutive_high_idle_ticks = 0;
@@ -243,7 +289,7 @@ pub fn spawn_gpu_pressure_monitor(threshold: f64) {
 /// Check cached GPU VRAM pressure (fast, no NVML call)
 #[inline]
 pub fn is_gpu_pressure_high() -> bool {
-    GPU_PRESSURE_HIGH.load(Ordering::Relaxed)
+    gpu_pressure::current_level() == PressureLevel::Critical
 }
 
 pub(crate) fn get_all_available_embedding_models(config: &ModelConfig) -> Vec<AvailableModel> {
@@ -275,6 +321,14 @@ pub(crate) fn get_all_available_embedding_models(config: &ModelConfig) -> Vec<Av
     onnx_models.chain(qwen3_models).collect()
 }
 
+/// Look up the output dimensions of an allowed embedding model.
+pub(crate) fn model_dimensions(model_id: &str, config: &ModelConfig) -> Option<usize> {
+    get_all_available_embedding_models(config)
+        .into_iter()
+        .find(|m| m.model_code == model_id)
+        .map(|m| m.dim)
+}
+
 /// Get the list of embedding models to load based on configuration
 fn get_models_to_load(config: &ModelConfig) -> Vec<String> {
     if config.all_embedding_models {
@@ -287,15 +341,21 @@ fn get_models_to_load(config: &ModelConfig) -> Vec<String> {
     }
 }
 
+/// Whether the service can serve `model_code` at all, allowed or not
+pub(crate) fn is_known_embedding_model(model_code: &str) -> bool {
+    is_qwen3_model(model_code)
+        || TextEmbedding::list_supported_models()
+            .iter()
+            .any(|m| m.model_code == model_code)
+}
+
 /// Resolve a model code string to a fastembed EmbeddingModel enum (ONNX models only)
 fn resolve_onnx_embedding_model(model_code: &str) -> Result<EmbeddingModel, InferenceError> {
     TextEmbedding::list_supported_models()
         .into_iter()
         .find(|m| m.model_code == model_code)
         .map(|m| m.model)
-        .ok_or_else(|| {
-            InferenceError::UnsupportedModel(format!("Unknown ONNX model: {}", model_code))
-        })
+        .ok_or_else(|| InferenceError::ModelNotFound(format!("Unknown ONNX model: {}", model_code)))
 }
 
 /// Embedder enum — dispatches to ONNX or Qwen3 at inference time.
@@ -305,6 +365,23 @@ enum Embedder {
     Qwen3(Box<Qwen3TextEmbedding>),
 }
 
+impl Embedder {
+    fn backend(&self) -> &'static str {
+        match self {
+            Embedder::Onnx(_) => "onnx",
+            Embedder::Qwen3(_) => "qwen3/candle",
+        }
+    }
+
+    /// The tokenizer inputs are encoded with; Qwen3 models don't expose theirs
+    fn tokenizer(&self) -> Option<Tokenizer> {
+        match self {
+            Embedder::Onnx(te) => Some(te.tokenizer.clone()),
+            Embedder::Qwen3(_) => None,
+        }
+    }
+}
+
 /// Initialize the model registry and pre-load allowed models.
 ///
 /// For each loaded model a bounded channel and dedicated worker thread are
@@ -315,13 +392,33 @@ pub async fn init_cache(config: &ModelConfig) {
     let queue_cap = QUEUE_CAPACITY.get().copied().unwrap_or(8);
     let registry = MODEL_REGISTRY.get_or_init(|| Arc::new(RwLock::new(HashMap::new())));
 
-    let models_to_load = get_models_to_load(config);
+    let mut models_to_load = get_models_to_load(config);
+    let tracker =
+        LOAD_TRACKER.get_or_init(|| std::sync::RwLock::new(ModelLoadTracker::new(&models_to_load)));
 
     if models_to_load.is_empty() {
         info!("No embedding models to pre-load");
+        DEFAULT_MODEL_READY.store(true, Ordering::Release);
         return;
     }
 
+    // Models past the resident cap load on first use instead
+    if let Some(max_loaded) = config.max_loaded_models
+        && models_to_load.len() > max_loaded
+    {
+        let deferred = models_to_load.split_off(max_loaded);
+        info!(
+            max_loaded_models = max_loaded,
+            deferred = ?deferred,
+            "Deferring embedding models past INFERENCE_MAX_LOADED_MODELS until first use"
+        );
+        if let Ok(mut tracker) = tracker.write() {
+            for model_id in &deferred {
+                tracker.unloaded(model_id);
+            }
+        }
+    }
+
     info!(
         models = ?models_to_load,
         count = models_to_load.len(),
@@ -332,95 +429,217 @@ pub async fn init_cache(config: &ModelConfig) {
     let concurrency_limit = available_parallelism().map(|n| n.get()).unwrap_or(1);
     info!("Using concurrency limit: {}", concurrency_limit);
 
-    // Load models in parallel (IO/download bound — safe to parallelise)
-    let results = futures::stream::iter(models_to_load)
+    // Load models in parallel (IO/download bound — safe to parallelise),
+    // registering each as soon as it's loaded
+    let mut results = futures::stream::iter(models_to_load)
         .map(|model_id| {
             let config = config.clone();
             async move {
-                let model_id_clone = model_id.clone();
-                let res = tokio::task::spawn_blocking(move || {
-                    if is_qwen3_model(&model_id_clone) {
-                        let id_for_err = model_id_clone.clone();
-                        create_qwen3_embedding(&model_id_clone, &config)
-                            .map(|qwen| (model_id_clone, Embedder::Qwen3(Box::new(qwen))))
-                            .map_err(|e| (id_for_err, e))
-                    } else {
-                        let id_for_err = model_id_clone.clone();
-                        resolve_onnx_embedding_model(&model_id_clone)
-                            .and_then(|emb| create_text_embedding(emb, &config))
-                            .map(|te| (model_id_clone, Embedder::Onnx(Box::new(te))))
-                            .map_err(|e| (id_for_err, e))
-                    }
-                })
-                .await;
-
-                match res {
-                    Ok(inner_res) => inner_res,
-                    Err(join_err) => {
-                        Err((model_id, InferenceError::ModelLoad(join_err.to_string())))
-                    }
-                }
+                let load_start = Instant::now();
+                let res = load_embedder(model_id.clone(), config).await;
+                (model_id, res, load_start.elapsed())
             }
         })
-        .buffer_unordered(concurrency_limit)
-        .collect::<Vec<_>>()
-        .await;
-
-    let mut reg_guard = registry.write().await;
+        .buffer_unordered(concurrency_limit);
 
-    for result in results {
+    while let Some((model_id, result, load_duration)) = results.next().await {
         match result {
-            Ok((model_id, embedder)) => {
-                let backend = match &embedder {
-                    Embedder::Onnx(_) => "onnx",
-                    Embedder::Qwen3(_) => "qwen3/candle",
-                };
-
-                // Create bounded channel + spawn worker
-                let (tx, rx) = tokio::sync::mpsc::channel::<EmbedRequest>(queue_cap);
-                let queue_depth = Arc::new(AtomicUsize::new(0));
-                let qd = Arc::clone(&queue_depth);
-                let mid = model_id.clone();
-
-                // The worker thread owns the embedder — no Mutex needed
-                std::thread::Builder::new()
-                    .name(format!("embed-{}", model_id))
-                    .spawn(move || {
-                        run_model_worker(mid, embedder, rx, qd);
-                    })
-                    .expect("Failed to spawn model worker thread");
-
-                let handle = Arc::new(ModelHandle {
-                    sender: tx,
-                    queue_depth,
-                });
-                reg_guard.insert(model_id.clone(), handle);
+            Ok(embedder) => {
+                let backend = embedder.backend();
+                let handle = spawn_model_worker(&model_id, embedder, queue_cap);
+                registry.write().await.insert(model_id.clone(), handle);
+                record_model_loaded(tracker, &model_id, load_duration);
 
-                info!(model_id = %model_id, backend = backend, "Pre-loaded embedding model with dedicated worker");
+                info!(
+                    model_id = %model_id,
+                    backend = backend,
+                    load_duration_ms = load_duration.as_millis() as u64,
+                    "Pre-loaded embedding model with dedicated worker"
+                );
             }
-            Err((model_id, e)) => {
+            Err(e) => {
                 error!(
                     model_id = %model_id,
                     error = %e,
                     "Failed to load embedding model during initialization"
                 );
+                if let Ok(mut tracker) = tracker.write() {
+                    tracker.failed(&model_id, e.to_string(), load_duration);
+                }
             }
         }
     }
 
     info!(
-        loaded_models = reg_guard.len(),
+        loaded_models = registry.read().await.len(),
         "Embedding model registry initialization complete"
     );
 }
 
+/// Load a model's weights on a blocking thread, on the GPU if possible
+async fn load_embedder(model_id: String, config: ModelConfig) -> Result<Embedder, InferenceError> {
+    tokio::task::spawn_blocking(move || {
+        let policy = ProviderPolicy::for_model(&config, &model_id);
+        let (embedder, provider) = if is_qwen3_model(&model_id) {
+            execution_provider::load_with_fallback(&model_id, policy, |provider| {
+                create_qwen3_embedding(&model_id, provider)
+                    .map(|qwen| Embedder::Qwen3(Box::new(qwen)))
+            })?
+        } else {
+            let model = resolve_onnx_embedding_model(&model_id)?;
+            execution_provider::load_with_fallback(&model_id, policy, |provider| {
+                create_text_embedding(model.clone(), provider, &config)
+                    .map(|te| Embedder::Onnx(Box::new(te)))
+            })?
+        };
+        execution_provider::record_loaded(&model_id, provider);
+        Ok(embedder)
+    })
+    .await
+    .map_err(|join_err| InferenceError::ModelLoad(join_err.to_string()))?
+}
+
+/// Create the model's bounded queue and the worker thread that owns it
+fn spawn_model_worker(model_id: &str, embedder: Embedder, queue_cap: usize) -> Arc<ModelHandle> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<EmbedRequest>(queue_cap);
+    let queue_depth = Arc::new(AtomicUsize::new(0));
+    let qd = Arc::clone(&queue_depth);
+    let mid = model_id.to_string();
+    let tokenizer = embedder.tokenizer();
+
+    // The worker thread owns the embedder — no Mutex needed
+    std::thread::Builder::new()
+        .name(format!("embed-{}", model_id))
+        .spawn(move || {
+            run_model_worker(mid, embedder, rx, qd);
+        })
+        .expect("Failed to spawn model worker thread");
+
+    semantic_explorer_core::observability::record_embedding_model_cache(model_id, "load");
+    Arc::new(ModelHandle {
+        sender: tx,
+        queue_depth,
+        last_used: AtomicU64::new(USE_CLOCK.fetch_add(1, Ordering::Relaxed)),
+        tokenizer,
+    })
+}
+
+/// Load an unloaded model for a request, first evicting least recently used
+/// models so the new one fits under `INFERENCE_MAX_LOADED_MODELS`
+async fn load_on_demand(
+    registry: &ModelRegistry,
+    model_id: &str,
+    config: &ModelConfig,
+) -> Result<Arc<ModelHandle>, InferenceError> {
+    let tracker = LOAD_TRACKER
+        .get()
+        .ok_or_else(|| InferenceError::Internal("Model registry not initialized".to_string()))?;
+    let start = tracker
+        .write()
+        .map(|mut tracker| tracker.start_loading(model_id))
+        .unwrap_or(LoadStart::NotLoadable);
+    match start {
+        LoadStart::Started => {}
+        LoadStart::InProgress => {
+            return Err(InferenceError::ServiceUnavailable(format!(
+                "Model {} is still loading, try again later",
+                model_id
+            )));
+        }
+        LoadStart::NotLoadable => {
+            warn!(model_id = %model_id, "Model not found in registry");
+            return Err(InferenceError::ModelNotFound(format!(
+                "Model {} not preloaded. Please check configuration.",
+                model_id
+            )));
+        }
+    }
+
+    if let Some(max_loaded) = config.max_loaded_models {
+        evict_least_recently_used(registry, tracker, max_loaded - 1, None).await;
+    }
+
+    info!(model_id = %model_id, "Loading embedding model on first use");
+    let load_start = Instant::now();
+    let result = load_embedder(model_id.to_string(), config.clone()).await;
+    let load_duration = load_start.elapsed();
+    let embedder = match result {
+        Ok(embedder) => embedder,
+        Err(e) => {
+            error!(model_id = %model_id, error = %e, "Failed to load embedding model on demand");
+            if let Ok(mut tracker) = tracker.write() {
+                tracker.failed(model_id, e.to_string(), load_duration);
+            }
+            return Err(e);
+        }
+    };
+
+    let queue_cap = QUEUE_CAPACITY.get().copied().unwrap_or(8);
+    let handle = spawn_model_worker(model_id, embedder, queue_cap);
+    registry
+        .write()
+        .await
+        .insert(model_id.to_string(), Arc::clone(&handle));
+    record_model_loaded(tracker, model_id, load_duration);
+    info!(
+        model_id = %model_id,
+        load_duration_ms = load_duration.as_millis() as u64,
+        "Loaded embedding model on demand"
+    );
+
+    // Another model may have loaded concurrently; trim back to the cap
+    if let Some(max_loaded) = config.max_loaded_models {
+        evict_least_recently_used(registry, tracker, max_loaded, Some(model_id)).await;
+    }
+    Ok(handle)
+}
+
+/// Drop least recently used models from the registry until at most `keep`
+/// remain, never evicting `except`. Requests already holding an evicted
+/// model's handle still complete (see `ModelHandle`).
+async fn evict_least_recently_used(
+    registry: &ModelRegistry,
+    tracker: &std::sync::RwLock<ModelLoadTracker>,
+    keep: usize,
+    except: Option<&str>,
+) {
+    let mut reg = registry.write().await;
+    let resident: Vec<(String, u64)> = reg
+        .iter()
+        .map(|(model_id, handle)| (model_id.clone(), handle.last_used.load(Ordering::Relaxed)))
+        .collect();
+    for model_id in eviction_order(&resident, keep, except) {
+        reg.remove(&model_id);
+        execution_provider::record_unloaded(&model_id);
+        if let Ok(mut tracker) = tracker.write() {
+            tracker.unloaded(&model_id);
+        }
+        semantic_explorer_core::observability::record_embedding_model_cache(&model_id, "eviction");
+        info!(model_id = %model_id, "Evicted least recently used embedding model");
+    }
+}
+
+/// Mark `model_id` loaded, flipping readiness once the default model is in
+fn record_model_loaded(
+    tracker: &std::sync::RwLock<ModelLoadTracker>,
+    model_id: &str,
+    load_duration: Duration,
+) {
+    if let Ok(mut tracker) = tracker.write() {
+        tracker.loaded(model_id, load_duration);
+        if tracker.is_ready() {
+            DEFAULT_MODEL_READY.store(true, Ordering::Release);
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Dedicated model worker (runs on its own OS thread)
 // ---------------------------------------------------------------------------
 
 /// Blocking worker loop that owns the embedder and processes requests
-/// sequentially.  Runs on a dedicated OS thread (not in the Tokio pool) so
-/// it cannot starve async tasks.
+/// sequentially, coalescing small ones into one model call.  Runs on a
+/// dedicated OS thread (not in the Tokio pool) so it cannot starve async tasks.
 fn run_model_worker(
     model_id: String,
     mut embedder: Embedder,
@@ -433,31 +652,100 @@ fn run_model_worker(
         .build()
         .expect("Failed to build worker runtime");
 
+    let (batch_window, max_coalesced) = BATCHING
+        .get()
+        .copied()
+        .unwrap_or((Duration::from_millis(5), 64));
+    let queue_timeout = QUEUE_TIMEOUT
+        .get()
+        .copied()
+        .unwrap_or(Duration::from_millis(30000));
+
     rt.block_on(async move {
         let mut rx = rx;
+        // A request that didn't fit in the previous model call
+        let mut carry: Option<EmbedRequest> = None;
+
+        loop {
+            let first = match carry.take() {
+                Some(req) => req,
+                None => match rx.recv().await {
+                    Some(req) => {
+                        queue_depth.fetch_sub(1, Ordering::Relaxed);
+                        req
+                    }
+                    None => break,
+                },
+            };
+            let (batch, next) = batching::collect_batch(
+                first,
+                &mut rx,
+                batch_window,
+                max_coalesced,
+                |req: &EmbedRequest| req.texts.len(),
+            )
+            .await;
+            queue_depth.fetch_sub(
+                batch.len() - 1 + usize::from(next.is_some()),
+                Ordering::Relaxed,
+            );
+            carry = next;
+
+            // Reject requests that outlived the queue timeout rather than
+            // keep their callers waiting, and skip callers that gave up
+            let (expired, batch): (Vec<_>, Vec<_>) = batch
+                .into_iter()
+                .filter(|req| !req.reply.is_closed())
+                .partition(|req| req.enqueued_at.elapsed() > queue_timeout);
+            for req in expired {
+                let _ = req
+                    .reply
+                    .send(Err(InferenceError::ServiceUnavailable(format!(
+                        "Request waited longer than {}ms in the model {} queue",
+                        queue_timeout.as_millis(),
+                        model_id
+                    ))));
+            }
+            if batch.is_empty() {
+                continue;
+            }
 
-        while let Some(req) = rx.recv().await {
-            queue_depth.fetch_sub(1, Ordering::Relaxed);
+            let queue_wait = batch[0].enqueued_at.elapsed();
+            let counts: Vec<usize> = batch.iter().map(|req| req.texts.len()).collect();
+            // Honour the smallest sub-batch asked for (it shrinks under VRAM pressure)
+            let batch_size = batch.iter().filter_map(|req| req.batch_size).min();
+            let mut texts = Vec::with_capacity(counts.iter().sum());
+            let mut replies = Vec::with_capacity(batch.len());
+            for req in batch {
+                texts.extend(req.texts);
+                replies.push(req.reply);
+            }
 
-            let queue_wait = req.enqueued_at.elapsed();
-            let texts_count = req.texts.len();
-            let total_chars: usize = req.texts.iter().map(|t| t.len()).sum();
-            let avg_chars = if texts_count > 0 { total_chars / texts_count } else { 0 };
+            let texts_count = texts.len();
+            let total_chars: usize = texts.iter().map(|t| t.len()).sum();
+            let avg_chars = if texts_count > 0 {
+                total_chars / texts_count
+            } else {
+                0
+            };
+            semantic_explorer_core::observability::record_embed_coalesced_batch(
+                &model_id,
+                replies.len(),
+                texts_count,
+            );
 
             let embed_start = Instant::now();
 
             let result = match &mut embedder {
-                Embedder::Onnx(te) => {
-                    te.embed(req.texts, req.batch_size).map_err(|e| {
-                        error!(error = %e, "ONNX embedding generation failed");
-                        InferenceError::Embedding(e.to_string())
-                    })
-                }
+                Embedder::Onnx(te) => te.embed(texts, batch_size).map_err(|e| {
+                    error!(error = %e, "ONNX embedding generation failed");
+                    e.to_string()
+                }),
                 Embedder::Qwen3(qwen) => {
-                    let text_refs: Vec<&str> = req.texts.iter().map(|s| s.as_str()).collect();
+                    let text_refs: Vec<&str> = texts.iter().map(|s| s.as_str()).collect();
                     qwen.embed(&text_refs).map_err(|e| {
                         error!(error = %e, "Qwen3 embedding generation failed");
-                        InferenceError::Embedding(e.to_string())
+                        e.to_string()
                     })
                 }
             };
@@ -477,7 +765,8 @@ fn run_model_worker(
 
             debug!(
                 model_id = %model_id,
-                backend = match &embedder { Embedder::Onnx(_) => "onnx", Embedder::Qwen3(_) => "qwen3/candle" },
+                backend = embedder.backend(),
+                coalesced_requests = replies.len(),
                 texts_count = texts_count,
                 total_chars = total_chars,
                 avg_chars_per_text = avg_chars,
@@ -489,8 +778,19 @@ fn run_model_worker(
                 "Embedding timing"
             );
 
-            // Send result back; ignore error if caller timed out
-            let _ = req.reply.send(result);
+            // Send each caller its own embeddings; ignore callers that timed out
+            match result.and_then(|embeddings| batching::scatter(embeddings, &counts)) {
+                Ok(per_request) => {
+                    for (reply, embeddings) in replies.into_iter().zip(per_request) {
+                        let _ = reply.send(Ok(embeddings));
+                    }
+                }
+                Err(e) => {
+                    for reply in replies {
+                        let _ = reply.send(Err(InferenceError::Embedding(e.clone())));
+                    }
+                }
+            }
         }
 
         info!(model_id = %model_id, "Model worker shutting down (channel closed)");
@@ -500,36 +800,11 @@ fn run_model_worker(
 /// Create a TextEmbedding instance with proper configuration
 fn create_text_embedding(
     model: EmbeddingModel,
+    provider: ExecutionProvider,
     config: &ModelConfig,
 ) -> Result<TextEmbedding, InferenceError> {
-    let mut cuda = CUDA::default()
-        .with_prefer_nhwc(true)
-        .with_attention_backend(AttentionBackend::CUDNN_FLASH_ATTENTION);
-
-    // Apply CUDA arena size limit if configured, otherwise uses all available GPU memory
-    if let Some(arena_size) = config.cuda_arena_size {
-        info!(
-            cuda_arena_size_bytes = arena_size,
-            cuda_arena_size_mb = arena_size / (1024 * 1024),
-            "Setting CUDA memory arena limit"
-        );
-        cuda = cuda.with_memory_limit(arena_size);
-    }
-
-    // Apply arena extend strategy
-    let strategy = match config.cuda_arena_extend_strategy {
-        crate::config::CudaArenaExtendStrategy::SameAsRequested => {
-            ArenaExtendStrategy::SameAsRequested
-        }
-        crate::config::CudaArenaExtendStrategy::NextPowerOfTwo => {
-            ArenaExtendStrategy::NextPowerOfTwo
-        }
-    };
-    cuda = cuda.with_arena_extend_strategy(strategy);
-
-    let cuda_provider = cuda.build().error_on_failure();
     let mut options = TextInitOptions::new(model)
-        .with_execution_providers(vec![cuda_provider])
+        .with_execution_providers(execution_provider::onnx_providers(provider, config))
         .with_show_download_progress(true);
 
     if let Some(ref hf_home) = config.hf_home {
@@ -537,12 +812,16 @@ fn create_text_embedding(
     }
 
     TextEmbedding::try_new(options).map_err(|e| {
-        error!(
-            error = %e,
-            "Failed to initialize embedding model with CUDA. \
-            This may indicate a CUDA/cuDNN version mismatch or driver issue. \
-            Check: nvidia-smi, nvcc --version, and cuDNN installation."
-        );
+        if provider == ExecutionProvider::Cuda {
+            error!(
+                error = %e,
+                "Failed to initialize embedding model with CUDA. \
+                This may indicate a CUDA/cuDNN version mismatch or driver issue. \
+                Check: nvidia-smi, nvcc --version, and cuDNN installation."
+            );
+        } else {
+            error!(error = %e, "Failed to initialize embedding model on the CPU");
+        }
         InferenceError::ModelLoad(e.to_string())
     })
 }
@@ -550,19 +829,21 @@ fn create_text_embedding(
 /// Create a Qwen3TextEmbedding instance using the candle backend
 fn create_qwen3_embedding(
     model_code: &str,
-    _config: &ModelConfig,
+    provider: ExecutionProvider,
 ) -> Result<Qwen3TextEmbedding, InferenceError> {
     let def = QWEN3_MODELS
         .iter()
         .find(|d| d.model_code == model_code)
         .ok_or_else(|| {
-            InferenceError::UnsupportedModel(format!("Unknown Qwen3 model: {}", model_code))
+            InferenceError::ModelNotFound(format!("Unknown Qwen3 model: {}", model_code))
         })?;
 
-    let device = Device::new_cuda(0).unwrap_or_else(|e| {
-        warn!(error = %e, "CUDA device unavailable for Qwen3, falling back to CPU");
-        Device::Cpu
-    });
+    let device = match provider {
+        ExecutionProvider::Cuda => Device::new_cuda(0).map_err(|e| {
+            InferenceError::ModelLoad(format!("CUDA device unavailable for Qwen3: {}", e))
+        })?,
+        ExecutionProvider::Cpu => Device::Cpu,
+    };
 
     info!(
         model_code = %model_code,
@@ -586,6 +867,21 @@ fn create_qwen3_embedding(
 ///
 /// Returns backpressure metadata alongside the result so API handlers can
 /// populate response headers for caller-side adaptive throttling.
+/// Record the length of each input the model is given, in characters and,
+/// if the model's tokenizer is available, in tokens after truncation
+fn record_input_lengths(model_id: &str, texts: &[String], tokenizer: Option<&Tokenizer>) {
+    for text in texts {
+        let tokens = tokenizer
+            .and_then(|tokenizer| tokenizer.encode(text.as_str(), true).ok())
+            .map(|encoding| encoding.len());
+        semantic_explorer_core::observability::record_embed_input_length(
+            model_id,
+            text.chars().count(),
+            tokens,
+        );
+    }
+}
+
 pub async fn generate_embeddings(
     model_id: &str,
     config: &ModelConfig,
@@ -593,66 +889,78 @@ pub async fn generate_embeddings(
 ) -> Result<Vec<Vec<f32>>, InferenceError> {
     // Check if model is allowed
     if !config.is_embedding_model_allowed(model_id) {
-        return Err(InferenceError::UnsupportedModel(format!(
-            "Model {} is not in the allowed models list",
-            model_id
-        )));
+        return Err(InferenceError::unavailable_model(
+            model_id,
+            is_known_embedding_model(model_id),
+        ));
     }
 
     let registry = MODEL_REGISTRY
         .get()
         .ok_or_else(|| InferenceError::Internal("Model registry not initialized".to_string()))?;
 
-    // Get the model handle
-    let handle = {
-        let reg = registry.read().await;
-        reg.get(model_id).cloned().ok_or_else(|| {
-            warn!(model_id = %model_id, "Model not found in registry");
-            InferenceError::UnsupportedModel(format!(
-                "Model {} not preloaded. Please check configuration.",
-                model_id
-            ))
-        })?
+    // Get the model handle, loading the model if it isn't resident
+    let resident = registry.read().await.get(model_id).cloned();
+    let handle = match resident {
+        Some(handle) => handle,
+        None => load_on_demand(registry, model_id, config).await?,
     };
+    handle
+        .last_used
+        .store(USE_CLOCK.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+
+    // Only now is `model_id` known to be a real model, fit for a metric label
+    if config.embed_length_metrics {
+        record_input_lengths(model_id, &texts, handle.tokenizer.as_ref());
+    }
 
     // Check GPU VRAM pressure before accepting work.
+    // Above the soft threshold the inference sub-batch shrinks; above the
+    // hard threshold work is rejected while the queue is busy.
     // Trickle-through: when the model's queue is empty we allow one request
     // through even under VRAM pressure.  The ONNX arena allocates GPU memory
     // and never releases it, so NVML reports high VRAM even when the model is
     // idle.  Blocking **all** requests creates a deadlock where no work can
     // complete and the arena never gets to reuse its already-allocated memory.
-    let threshold = GPU_PRESSURE_THRESHOLD.get().copied().unwrap_or(98.0);
-    if is_gpu_pressure_high() {
-        let current_depth = handle.queue_depth.load(Ordering::Relaxed);
-        if current_depth > 0 {
+    //
+    // Use gpu_batch_size (not max_batch_size) for the ONNX inference sub-batch.
+    // max_batch_size controls API input validation (how many texts per request).
+    // gpu_batch_size controls how many texts ORT processes per session.run() call,
+    // limiting peak VRAM usage for intermediate tensors (MatMul, attention, etc.).
+    let policy = gpu_pressure::policy();
+    let level = gpu_pressure::current_level();
+    let current_depth = handle.queue_depth.load(Ordering::Relaxed);
+    let batch_size = match policy.admit(level, current_depth, config.gpu_batch_size) {
+        Admission::Accept { batch_size } => {
+            if level == PressureLevel::Critical {
+                // Queue is empty — let this one request trickle through so progress
+                // can be made (the worker processes sequentially, one at a time).
+                info!(
+                    model_id = %model_id,
+                    "GPU VRAM pressure high but queue idle — allowing trickle-through request"
+                );
+            }
+            batch_size
+        }
+        Admission::Reject => {
             // Queue already has work — reject to prevent piling on.
             warn!(
                 model_id = %model_id,
                 queue_depth = current_depth,
                 "GPU VRAM pressure high (>{}%) and queue non-empty, rejecting request",
-                threshold
+                policy.hard_threshold
             );
             return Err(InferenceError::ServiceUnavailable(
                 "GPU VRAM pressure high, try again later".to_string(),
             ));
         }
-        // Queue is empty — let this one request trickle through so progress
-        // can be made (the worker processes sequentially, one at a time).
-        info!(
-            model_id = %model_id,
-            "GPU VRAM pressure high but queue idle — allowing trickle-through request"
-        );
-    }
+    };
 
     // Build the request
-    // Use gpu_batch_size (not max_batch_size) for the ONNX inference sub-batch.
-    // max_batch_size controls API input validation (how many texts per request).
-    // gpu_batch_size controls how many texts ORT processes per session.run() call,
-    // limiting peak VRAM usage for intermediate tensors (MatMul, attention, etc.).
     let (tx, rx) = oneshot::channel();
     let req = EmbedRequest {
         texts,
-        batch_size: Some(config.gpu_batch_size),
+        batch_size: Some(batch_size),
         reply: tx,
         enqueued_at: Instant::now(),
     };
@@ -697,7 +1005,99 @@ pub async fn generate_embeddings(
     })?
 }
 
-/// Check if models are loaded and ready
+/// Check if the default model is loaded and ready
 pub fn is_ready() -> bool {
-    MODEL_REGISTRY.get().is_some()
+    DEFAULT_MODEL_READY.load(Ordering::Acquire)
+}
+
+/// Models currently resident, most recently used first
+pub async fn resident_models() -> Vec<String> {
+    let Some(registry) = MODEL_REGISTRY.get() else {
+        return Vec::new();
+    };
+    let mut resident: Vec<(String, u64)> = registry
+        .read()
+        .await
+        .iter()
+        .map(|(model_id, handle)| (model_id.clone(), handle.last_used.load(Ordering::Relaxed)))
+        .collect();
+    resident.sort_by(|a, b| b.1.cmp(&a.1));
+    resident.into_iter().map(|(model_id, _)| model_id).collect()
+}
+
+/// Per-model load status for `/health/status`; empty until loading starts
+pub fn model_load_status() -> serde_json::Value {
+    LOAD_TRACKER
+        .get()
+        .and_then(|tracker| tracker.read().ok())
+        .map(|tracker| tracker.to_json())
+        .unwrap_or_else(|| ModelLoadTracker::default().to_json())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::test_support::{metrics_registry, sample};
+    use std::str::FromStr;
+
+    /// Splits on whitespace and maps every word to one id
+    fn word_tokenizer() -> Tokenizer {
+        Tokenizer::from_str(
+            r#"{
+                "version": "1.0",
+                "truncation": null,
+                "padding": null,
+                "added_tokens": [],
+                "normalizer": null,
+                "pre_tokenizer": {"type": "Whitespace"},
+                "post_processor": null,
+                "decoder": null,
+                "model": {"type": "WordLevel", "vocab": {"[UNK]": 0, "hello": 1}, "unk_token": "[UNK]"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_input_lengths_use_the_model_tokenizer() {
+        let registry = metrics_registry();
+        let texts = vec!["héllo big world".to_string()];
+
+        record_input_lengths("test/tokenized-model", &texts, Some(&word_tokenizer()));
+        assert_eq!(
+            sample(
+                registry,
+                "inference_embed_input_chars_sum",
+                "test/tokenized-model"
+            ),
+            Some(15.0)
+        );
+        assert_eq!(
+            sample(
+                registry,
+                "inference_embed_input_tokens_sum",
+                "test/tokenized-model"
+            ),
+            Some(3.0)
+        );
+
+        // Without a tokenizer only characters are recorded
+        record_input_lengths("test/untokenized-model", &texts, None);
+        assert_eq!(
+            sample(
+                registry,
+                "inference_embed_input_chars_count",
+                "test/untokenized-model"
+            ),
+            Some(1.0)
+        );
+        assert_eq!(
+            sample(
+                registry,
+                "inference_embed_input_tokens_count",
+                "test/untokenized-model"
+            ),
+            None
+        );
+    }
 }
@@ -13,8 +13,10 @@ pub enum InferenceError {
     Embedding(String),
     /// Reranking failed
     Rerank(String),
-    /// Unsupported model
-    UnsupportedModel(String),
+    /// Model the service doesn't know (404)
+    ModelNotFound(String),
+    /// Known model excluded by the allowed models configuration (403)
+    ModelNotAllowed(String),
     /// Bad request
     BadRequest(String),
     /// Internal error
@@ -29,7 +31,8 @@ impl fmt::Display for InferenceError {
             InferenceError::ModelLoad(msg) => write!(f, "Failed to load model: {}", msg),
             InferenceError::Embedding(msg) => write!(f, "Embedding generation failed: {}", msg),
             InferenceError::Rerank(msg) => write!(f, "Reranking failed: {}", msg),
-            InferenceError::UnsupportedModel(msg) => write!(f, "Unsupported model: {}", msg),
+            InferenceError::ModelNotFound(msg) => write!(f, "Model not found: {}", msg),
+            InferenceError::ModelNotAllowed(msg) => write!(f, "Model not allowed: {}", msg),
             InferenceError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             InferenceError::Internal(msg) => write!(f, "Internal error: {}", msg),
             InferenceError::ServiceUnavailable(msg) => write!(f, "Service unavailable: {}", msg),
@@ -39,6 +42,21 @@ impl fmt::Display for InferenceError {
 
 impl std::error::Error for InferenceError {}
 
+impl InferenceError {
+    /// Error for a model rejected by the allowed models configuration: 403
+    /// when the service knows the model, 404 when it doesn't exist at all
+    pub fn unavailable_model(model_id: &str, known: bool) -> Self {
+        if known {
+            InferenceError::ModelNotAllowed(format!(
+                "Model {} is not in the allowed models list",
+                model_id
+            ))
+        } else {
+            InferenceError::ModelNotFound(format!("Unknown model: {}", model_id))
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
@@ -49,7 +67,8 @@ impl ResponseError for InferenceError {
     fn status_code(&self) -> StatusCode {
         match self {
             InferenceError::BadRequest(_) => StatusCode::BAD_REQUEST,
-            InferenceError::UnsupportedModel(_) => StatusCode::BAD_REQUEST,
+            InferenceError::ModelNotFound(_) => StatusCode::NOT_FOUND,
+            InferenceError::ModelNotAllowed(_) => StatusCode::FORBIDDEN,
             InferenceError::ModelLoad(_) => StatusCode::SERVICE_UNAVAILABLE,
             InferenceError::Embedding(_) => StatusCode::INTERNAL_SERVER_ERROR,
             InferenceError::Rerank(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -63,7 +82,8 @@ impl ResponseError for InferenceError {
             InferenceError::ModelLoad(_) => "MODEL_LOAD_ERROR",
             InferenceError::Embedding(_) => "EMBEDDING_ERROR",
             InferenceError::Rerank(_) => "RERANK_ERROR",
-            InferenceError::UnsupportedModel(_) => "UNSUPPORTED_MODEL",
+            InferenceError::ModelNotFound(_) => "MODEL_NOT_FOUND",
+            InferenceError::ModelNotAllowed(_) => "MODEL_NOT_ALLOWED",
             InferenceError::BadRequest(_) => "BAD_REQUEST",
             InferenceError::Internal(_) => "INTERNAL_ERROR",
             InferenceError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
@@ -85,3 +105,34 @@ impl ResponseError for InferenceError {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn status_and_code(error: InferenceError) -> (StatusCode, String) {
+        let response = error.error_response();
+        let status = response.status();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (status, json["code"].as_str().unwrap().to_string())
+    }
+
+    #[actix_web::test]
+    async fn test_unknown_and_disallowed_models_are_distinct() {
+        assert_eq!(
+            status_and_code(InferenceError::unavailable_model("acme/nope", false)).await,
+            (StatusCode::NOT_FOUND, "MODEL_NOT_FOUND".to_string())
+        );
+        assert_eq!(
+            status_and_code(InferenceError::unavailable_model(
+                "BAAI/bge-small-en-v1.5",
+                true
+            ))
+            .await,
+            (StatusCode::FORBIDDEN, "MODEL_NOT_ALLOWED".to_string())
+        );
+    }
+}
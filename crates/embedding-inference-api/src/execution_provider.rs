@@ -0,0 +1,203 @@
+//! Execution provider selection with CPU fallback.
+//!
+//! Models load on the GPU first (the CUDA execution provider for ONNX
+//! models, a CUDA device for candle ones). When CUDA can't initialize — no
+//! GPU, or a driver/cuDNN mismatch — they fall back to the CPU unless
+//! `INFERENCE_REQUIRE_GPU` is set, so the service also runs on GPU-less
+//! development machines. Models listed in `INFERENCE_CPU_MODELS` always run
+//! on the CPU. The provider each loaded model runs on is reported by
+//! `/health/status` and the `inference_model_execution_provider` gauge.
+
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+use ort::ep::cuda::AttentionBackend;
+use ort::ep::{ArenaExtendStrategy, CPU, CUDA, ExecutionProviderDispatch};
+use tracing::{info, warn};
+
+use semantic_explorer_core::observability::record_model_execution_provider;
+
+use crate::config::{CudaArenaExtendStrategy, ModelConfig};
+use crate::errors::InferenceError;
+
+/// Where a model runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExecutionProvider {
+    Cuda,
+    Cpu,
+}
+
+impl ExecutionProvider {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ExecutionProvider::Cuda => "cuda",
+            ExecutionProvider::Cpu => "cpu",
+        }
+    }
+}
+
+/// How a model may pick its execution provider
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProviderPolicy {
+    /// Fail rather than fall back to the CPU
+    pub require_gpu: bool,
+    /// Skip CUDA and run on the CPU
+    pub cpu_only: bool,
+}
+
+impl ProviderPolicy {
+    pub(crate) fn for_model(config: &ModelConfig, model_id: &str) -> Self {
+        Self {
+            require_gpu: config.require_gpu,
+            cpu_only: config.runs_on_cpu(model_id),
+        }
+    }
+}
+
+/// Provider of every loaded model
+static ACTIVE_PROVIDERS: OnceLock<RwLock<BTreeMap<String, ExecutionProvider>>> = OnceLock::new();
+
+/// Load a model with `load`, on CUDA first and then on the CPU if CUDA fails
+/// and the policy allows it. Returns the model and the provider it runs on.
+pub(crate) fn load_with_fallback<T>(
+    model_id: &str,
+    policy: ProviderPolicy,
+    mut load: impl FnMut(ExecutionProvider) -> Result<T, InferenceError>,
+) -> Result<(T, ExecutionProvider), InferenceError> {
+    if policy.cpu_only {
+        info!(model_id = %model_id, "Model pinned to the CPU execution provider");
+    } else {
+        match load(ExecutionProvider::Cuda) {
+            Ok(model) => return Ok((model, ExecutionProvider::Cuda)),
+            Err(e) if policy.require_gpu => return Err(e),
+            Err(e) => warn!(
+                model_id = %model_id,
+                error = %e,
+                "CUDA UNAVAILABLE: falling back to the CPU execution provider. \
+                 Inference will be much slower. Set INFERENCE_REQUIRE_GPU=true \
+                 to fail instead."
+            ),
+        }
+    }
+    load(ExecutionProvider::Cpu).map(|model| (model, ExecutionProvider::Cpu))
+}
+
+/// ONNX Runtime execution providers for `provider`
+pub(crate) fn onnx_providers(
+    provider: ExecutionProvider,
+    config: &ModelConfig,
+) -> Vec<ExecutionProviderDispatch> {
+    match provider {
+        ExecutionProvider::Cuda => vec![cuda_provider(config)],
+        ExecutionProvider::Cpu => vec![CPU::default().build()],
+    }
+}
+
+/// The CUDA execution provider, failing session creation (rather than
+/// silently running on the CPU) when CUDA can't initialize
+fn cuda_provider(config: &ModelConfig) -> ExecutionProviderDispatch {
+    let mut cuda = CUDA::default()
+        .with_prefer_nhwc(true)
+        .with_attention_backend(AttentionBackend::CUDNN_FLASH_ATTENTION);
+
+    // Apply CUDA arena size limit if configured, otherwise uses all available GPU memory
+    if let Some(arena_size) = config.cuda_arena_size {
+        info!(
+            cuda_arena_size_bytes = arena_size,
+            cuda_arena_size_mb = arena_size / (1024 * 1024),
+            "Setting CUDA memory arena limit"
+        );
+        cuda = cuda.with_memory_limit(arena_size);
+    }
+
+    // Apply arena extend strategy
+    let strategy = match config.cuda_arena_extend_strategy {
+        CudaArenaExtendStrategy::SameAsRequested => ArenaExtendStrategy::SameAsRequested,
+        CudaArenaExtendStrategy::NextPowerOfTwo => ArenaExtendStrategy::NextPowerOfTwo,
+    };
+    cuda.with_arena_extend_strategy(strategy)
+        .build()
+        .error_on_failure()
+}
+
+/// Record that `model_id` loaded on `provider`
+pub(crate) fn record_loaded(model_id: &str, provider: ExecutionProvider) {
+    let providers = ACTIVE_PROVIDERS.get_or_init(|| RwLock::new(BTreeMap::new()));
+    if let Ok(mut providers) = providers.write() {
+        providers.insert(model_id.to_string(), provider);
+    }
+    record_model_execution_provider(model_id, provider.as_str(), true);
+}
+
+/// Record that `model_id` was unloaded
+pub(crate) fn record_unloaded(model_id: &str) {
+    let removed = ACTIVE_PROVIDERS
+        .get()
+        .and_then(|providers| providers.write().ok()?.remove(model_id));
+    if let Some(provider) = removed {
+        record_model_execution_provider(model_id, provider.as_str(), false);
+    }
+}
+
+/// Provider of every loaded model, for the status endpoint
+pub(crate) fn active_providers() -> serde_json::Value {
+    let providers = ACTIVE_PROVIDERS
+        .get()
+        .and_then(|providers| providers.read().ok())
+        .map(|providers| {
+            providers
+                .iter()
+                .map(|(model_id, provider)| (model_id.clone(), provider.as_str().into()))
+                .collect()
+        })
+        .unwrap_or_default();
+    serde_json::Value::Object(providers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(require_gpu: bool, cpu_only: bool) -> ProviderPolicy {
+        ProviderPolicy {
+            require_gpu,
+            cpu_only,
+        }
+    }
+
+    fn no_cuda(provider: ExecutionProvider) -> Result<&'static str, InferenceError> {
+        match provider {
+            ExecutionProvider::Cuda => Err(InferenceError::ModelLoad("no CUDA device".into())),
+            ExecutionProvider::Cpu => Ok("model"),
+        }
+    }
+
+    #[test]
+    fn test_prefers_cuda() {
+        let (_, provider) = load_with_fallback("m", policy(true, false), |_| Ok(())).unwrap();
+        assert_eq!(provider, ExecutionProvider::Cuda);
+    }
+
+    #[test]
+    fn test_falls_back_to_cpu_unless_gpu_required() {
+        let (_, provider) = load_with_fallback("m", policy(false, false), no_cuda).unwrap();
+        assert_eq!(provider, ExecutionProvider::Cpu);
+
+        assert!(matches!(
+            load_with_fallback("m", policy(true, false), no_cuda),
+            Err(InferenceError::ModelLoad(_))
+        ));
+    }
+
+    #[test]
+    fn test_cpu_pinned_model_skips_cuda() {
+        let mut attempts = Vec::new();
+        let (_, provider) = load_with_fallback("m", policy(true, true), |provider| {
+            attempts.push(provider);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(provider, ExecutionProvider::Cpu);
+        assert_eq!(attempts, vec![ExecutionProvider::Cpu]);
+    }
+}
@@ -0,0 +1,226 @@
+//! Graduated response to GPU VRAM pressure.
+//!
+//! The background monitor samples the highest VRAM utilization across devices
+//! and maps it to a [`PressureLevel`]:
+//!
+//! - below `GPU_PRESSURE_SOFT_THRESHOLD` requests run normally;
+//! - between the soft and hard threshold requests are still accepted, but the
+//!   ONNX inference sub-batch shrinks to `GPU_PRESSURE_SOFT_BATCH_SIZE` so
+//!   intermediate tensors need less VRAM;
+//! - above `GPU_PRESSURE_THRESHOLD` new requests are rejected with 503 while
+//!   the model's queue has work (one request still trickles through an idle
+//!   queue, see `generate_embeddings`).
+//!
+//! Without a soft threshold only the hard threshold applies.
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use tracing::warn;
+
+use crate::config::ModelConfig;
+
+/// Level last observed by the monitor
+static PRESSURE_LEVEL: AtomicU8 = AtomicU8::new(PressureLevel::Normal as u8);
+
+static POLICY: OnceLock<PressurePolicy> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub(crate) enum PressureLevel {
+    Normal = 0,
+    /// Above the soft threshold: smaller inference sub-batches
+    Degraded = 1,
+    /// Above the hard threshold: reject work while the queue is busy
+    Critical = 2,
+}
+
+impl PressureLevel {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            2 => Self::Critical,
+            1 => Self::Degraded,
+            _ => Self::Normal,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Degraded => "degraded",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// What to do with an incoming request at the current pressure level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Accept { batch_size: usize },
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PressurePolicy {
+    pub(crate) soft_threshold: Option<f64>,
+    pub(crate) hard_threshold: f64,
+    pub(crate) degraded_batch_size: usize,
+}
+
+impl PressurePolicy {
+    pub(crate) fn from_config(config: &ModelConfig) -> Self {
+        let soft_threshold = config.gpu_pressure_soft_threshold.filter(|soft| {
+            let below_hard = *soft < config.gpu_pressure_threshold;
+            if !below_hard {
+                warn!(
+                    soft_threshold = soft,
+                    hard_threshold = config.gpu_pressure_threshold,
+                    "GPU_PRESSURE_SOFT_THRESHOLD must be below GPU_PRESSURE_THRESHOLD, ignoring it"
+                );
+            }
+            below_hard
+        });
+        Self {
+            soft_threshold,
+            hard_threshold: config.gpu_pressure_threshold,
+            degraded_batch_size: config.gpu_pressure_soft_batch_size.max(1),
+        }
+    }
+
+    /// Level for the highest VRAM utilization (percent) across devices
+    pub(crate) fn level(&self, vram_percent: f64) -> PressureLevel {
+        if vram_percent > self.hard_threshold {
+            PressureLevel::Critical
+        } else if self.soft_threshold.is_some_and(|soft| vram_percent > soft) {
+            PressureLevel::Degraded
+        } else {
+            PressureLevel::Normal
+        }
+    }
+
+    /// Whether to accept a request, and with which inference sub-batch size.
+    /// At the critical level a request is only let through an idle queue.
+    pub(crate) fn admit(
+        &self,
+        level: PressureLevel,
+        queue_depth: usize,
+        gpu_batch_size: usize,
+    ) -> Admission {
+        match level {
+            PressureLevel::Normal => Admission::Accept {
+                batch_size: gpu_batch_size,
+            },
+            PressureLevel::Degraded => Admission::Accept {
+                batch_size: gpu_batch_size.min(self.degraded_batch_size),
+            },
+            PressureLevel::Critical if queue_depth > 0 => Admission::Reject,
+            PressureLevel::Critical => Admission::Accept {
+                batch_size: gpu_batch_size.min(self.degraded_batch_size),
+            },
+        }
+    }
+}
+
+/// Install the policy used by the monitor and request admission
+pub(crate) fn init(policy: PressurePolicy) -> &'static PressurePolicy {
+    POLICY.get_or_init(|| policy)
+}
+
+/// The installed policy; hard threshold only until `init` runs
+pub(crate) fn policy() -> &'static PressurePolicy {
+    POLICY.get_or_init(|| PressurePolicy {
+        soft_threshold: None,
+        hard_threshold: 98.0,
+        degraded_batch_size: 8,
+    })
+}
+
+pub(crate) fn current_level() -> PressureLevel {
+    PressureLevel::from_u8(PRESSURE_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Store a new level, returning the previous one
+pub(crate) fn set_level(level: PressureLevel) -> PressureLevel {
+    PressureLevel::from_u8(PRESSURE_LEVEL.swap(level as u8, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> PressurePolicy {
+        PressurePolicy {
+            soft_threshold: Some(85.0),
+            hard_threshold: 95.0,
+            degraded_batch_size: 8,
+        }
+    }
+
+    #[test]
+    fn test_actions_across_thresholds() {
+        let policy = policy();
+        let cases = [
+            (
+                40.0,
+                PressureLevel::Normal,
+                Admission::Accept { batch_size: 32 },
+            ),
+            (
+                85.0,
+                PressureLevel::Normal,
+                Admission::Accept { batch_size: 32 },
+            ),
+            (
+                85.5,
+                PressureLevel::Degraded,
+                Admission::Accept { batch_size: 8 },
+            ),
+            (
+                95.0,
+                PressureLevel::Degraded,
+                Admission::Accept { batch_size: 8 },
+            ),
+            (97.0, PressureLevel::Critical, Admission::Reject),
+        ];
+        for (vram, expected_level, expected_admission) in cases {
+            let level = policy.level(vram);
+            assert_eq!(level, expected_level, "{vram}%");
+            assert_eq!(policy.admit(level, 2, 32), expected_admission, "{vram}%");
+        }
+    }
+
+    #[test]
+    fn test_degraded_batch_never_grows_and_idle_queue_trickles_through() {
+        let policy = policy();
+        assert_eq!(
+            policy.admit(PressureLevel::Degraded, 0, 4),
+            Admission::Accept { batch_size: 4 }
+        );
+        assert_eq!(
+            policy.admit(PressureLevel::Critical, 0, 32),
+            Admission::Accept { batch_size: 8 }
+        );
+    }
+
+    #[test]
+    fn test_without_soft_threshold_only_rejection_applies() {
+        let policy = PressurePolicy {
+            soft_threshold: None,
+            ..policy()
+        };
+        assert_eq!(policy.level(94.0), PressureLevel::Normal);
+        assert_eq!(policy.level(96.0), PressureLevel::Critical);
+    }
+
+    #[test]
+    fn test_level_round_trips_through_the_atomic() {
+        for level in [
+            PressureLevel::Degraded,
+            PressureLevel::Critical,
+            PressureLevel::Normal,
+        ] {
+            set_level(level);
+            assert_eq!(current_level(), level);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Query vs document embedding.
+//!
+//! Asymmetric retrieval models are trained with an instruction prefix that
+//! tells them whether a text is a search query or a document to be found
+//! (`query: ` / `passage: ` for E5, `search_query: ` / `search_document: `
+//! for Nomic). Callers send an [`InputType`] and the service applies the
+//! model's prefix, so they don't have to know each model's conventions.
+//!
+//! Prefixes for the models that need them are built in;
+//! `INFERENCE_INPUT_PREFIXES` overrides or adds entries per model.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::config::ModelConfig;
+
+/// Whether the texts of an embed request are search queries or documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InputType {
+    /// A search query, embedded to be compared against documents
+    Query,
+    /// A document (or chunk) to be indexed and retrieved
+    Document,
+}
+
+/// Instruction prefixes of one model. A missing prefix leaves texts of that
+/// type unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct InputPrefixes {
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub document: Option<String>,
+}
+
+impl InputPrefixes {
+    fn get(&self, input_type: InputType) -> Option<&str> {
+        match input_type {
+            InputType::Query => self.query.as_deref(),
+            InputType::Document => self.document.as_deref(),
+        }
+    }
+}
+
+/// Query instruction of the BGE English, mxbai and Snowflake Arctic models,
+/// which embed documents without a prefix
+const RETRIEVAL_QUERY_INSTRUCTION: &str =
+    "Represent this sentence for searching relevant passages: ";
+
+/// Query instruction of the Qwen3 embedding models, in their
+/// `Instruct: {task}\nQuery:{query}` format
+const QWEN3_QUERY_INSTRUCTION: &str =
+    "Instruct: Given a web search query, retrieve relevant passages that answer the query\nQuery:";
+
+/// Built-in (query, document) prefixes, matched against a lowercased
+/// substring of the model code
+const BUILT_IN_PREFIXES: &[(&str, Option<&str>, Option<&str>)] = &[
+    ("multilingual-e5", Some("query: "), Some("passage: ")),
+    (
+        "nomic-embed-text",
+        Some("search_query: "),
+        Some("search_document: "),
+    ),
+    (
+        "modernbert-embed",
+        Some("search_query: "),
+        Some("search_document: "),
+    ),
+    ("bge-small-en-v1.5", Some(RETRIEVAL_QUERY_INSTRUCTION), None),
+    ("bge-base-en-v1.5", Some(RETRIEVAL_QUERY_INSTRUCTION), None),
+    ("bge-large-en-v1.5", Some(RETRIEVAL_QUERY_INSTRUCTION), None),
+    (
+        "mxbai-embed-large-v1",
+        Some(RETRIEVAL_QUERY_INSTRUCTION),
+        None,
+    ),
+    (
+        "snowflake-arctic-embed",
+        Some(RETRIEVAL_QUERY_INSTRUCTION),
+        None,
+    ),
+    ("qwen3-embedding", Some(QWEN3_QUERY_INSTRUCTION), None),
+];
+
+fn built_in_prefixes(model_id: &str) -> Option<InputPrefixes> {
+    let model_id = model_id.to_lowercase();
+    BUILT_IN_PREFIXES
+        .iter()
+        .find(|(pattern, _, _)| model_id.contains(pattern))
+        .map(|(_, query, document)| InputPrefixes {
+            query: query.map(str::to_string),
+            document: document.map(str::to_string),
+        })
+}
+
+/// Parse `INFERENCE_INPUT_PREFIXES`: a JSON object mapping model codes to
+/// `{"query": "...", "document": "..."}`
+pub(crate) fn parse_input_prefixes(raw: &str) -> anyhow::Result<HashMap<String, InputPrefixes>> {
+    if raw.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(serde_json::from_str(raw)?)
+}
+
+/// Prefix applied to texts of `input_type` for `model_id`, configured
+/// prefixes taking precedence over the built-in ones
+pub(crate) fn prefix_for(
+    config: &ModelConfig,
+    model_id: &str,
+    input_type: InputType,
+) -> Option<String> {
+    match config.input_prefixes.get(model_id) {
+        Some(prefixes) => prefixes.get(input_type).map(str::to_string),
+        None => built_in_prefixes(model_id)?
+            .get(input_type)
+            .map(str::to_string),
+    }
+}
+
+/// Apply the model's prefix for `input_type` to every text. Texts are left
+/// unchanged without an input type, or when the model has no such prefix.
+pub(crate) fn apply_prefix(
+    config: &ModelConfig,
+    model_id: &str,
+    input_type: Option<InputType>,
+    texts: Vec<String>,
+) -> Vec<String> {
+    let Some(prefix) = input_type.and_then(|t| prefix_for(config, model_id, t)) else {
+        return texts;
+    };
+    texts
+        .into_iter()
+        .map(|text| format!("{prefix}{text}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CudaArenaExtendStrategy, EmptyInputBehavior};
+
+    fn test_model_config() -> ModelConfig {
+        ModelConfig {
+            hf_home: None,
+            hf_endpoint: None,
+            hf_token: None,
+            model_path: None,
+            all_embedding_models: true,
+            allowed_embedding_models: vec![],
+            all_rerank_models: false,
+            allowed_rerank_models: vec![],
+            max_batch_size: 128,
+            max_queue_depth: 8,
+            max_loaded_models: None,
+            queue_timeout_ms: 30000,
+            batch_window_ms: 5,
+            max_coalesced_batch_size: 64,
+            gpu_pressure_threshold: 95.0,
+            gpu_pressure_soft_threshold: None,
+            gpu_pressure_soft_batch_size: 8,
+            cuda_arena_size: None,
+            cuda_arena_extend_strategy: CudaArenaExtendStrategy::NextPowerOfTwo,
+            require_gpu: false,
+            cpu_models: Vec::new(),
+            gpu_batch_size: 32,
+            embed_length_metrics: false,
+            empty_input_behavior: EmptyInputBehavior::Reject,
+            input_prefixes: HashMap::new(),
+        }
+    }
+
+    fn texts(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_query_and_document_differ_for_prefix_sensitive_model() {
+        let config = test_model_config();
+        let model = "intfloat/multilingual-e5-small";
+
+        let query = apply_prefix(&config, model, Some(InputType::Query), texts(&["cats"]));
+        let document = apply_prefix(&config, model, Some(InputType::Document), texts(&["cats"]));
+
+        assert_eq!(query, texts(&["query: cats"]));
+        assert_eq!(document, texts(&["passage: cats"]));
+        assert_ne!(query, document);
+    }
+
+    #[test]
+    fn test_documents_unchanged_for_query_only_instruction() {
+        let config = test_model_config();
+        let model = "Xenova/bge-small-en-v1.5";
+
+        let query = apply_prefix(&config, model, Some(InputType::Query), texts(&["cats"]));
+        assert_eq!(query, vec![format!("{RETRIEVAL_QUERY_INSTRUCTION}cats")]);
+        assert_eq!(
+            apply_prefix(&config, model, Some(InputType::Document), texts(&["cats"])),
+            texts(&["cats"])
+        );
+    }
+
+    #[test]
+    fn test_texts_unchanged_without_input_type_or_known_prefix() {
+        let config = test_model_config();
+        assert_eq!(
+            apply_prefix(
+                &config,
+                "intfloat/multilingual-e5-small",
+                None,
+                texts(&["cats"])
+            ),
+            texts(&["cats"])
+        );
+        assert_eq!(
+            apply_prefix(
+                &config,
+                "Qdrant/all-MiniLM-L6-v2-onnx",
+                Some(InputType::Query),
+                texts(&["cats"])
+            ),
+            texts(&["cats"])
+        );
+    }
+
+    #[test]
+    fn test_configured_prefixes_replace_built_in_ones() {
+        let mut config = test_model_config();
+        config.input_prefixes = parse_input_prefixes(
+            r#"{"intfloat/multilingual-e5-small": {"query": "q: "},
+                "custom/model": {"document": "doc: "}}"#,
+        )
+        .unwrap();
+
+        let e5 = "intfloat/multilingual-e5-small";
+        assert_eq!(
+            prefix_for(&config, e5, InputType::Query).as_deref(),
+            Some("q: ")
+        );
+        // The configured entry replaces the built-in one as a whole
+        assert_eq!(prefix_for(&config, e5, InputType::Document), None);
+        assert_eq!(
+            prefix_for(&config, "custom/model", InputType::Document).as_deref(),
+            Some("doc: ")
+        );
+    }
+
+    #[test]
+    fn test_parse_input_prefixes_rejects_invalid_json() {
+        assert!(parse_input_prefixes("").unwrap().is_empty());
+        assert!(parse_input_prefixes("{\"model\": \"query: \"}").is_err());
+    }
+
+    #[test]
+    fn test_input_type_deserializes_snake_case() {
+        let parsed: InputType = serde_json::from_str("\"query\"").unwrap();
+        assert_eq!(parsed, InputType::Query);
+        assert!(serde_json::from_str::<InputType>("\"passage\"").is_err());
+    }
+}
@@ -4,10 +4,15 @@
 //! capabilities using fastembed-rs ONNX models with CUDA GPU acceleration.
 
 mod api;
+mod batching;
 mod config;
 mod embedding;
 mod errors;
+mod execution_provider;
+mod gpu_pressure;
+mod input_type;
 mod model_preload;
+mod model_status;
 mod models;
 mod observability;
 mod reranker;
@@ -82,22 +87,35 @@ async fn main() -> Result<()> {
         config.models.max_queue_depth,
         config.models.queue_timeout_ms,
     );
-
-    tokio::join!(
-        embedding::init_cache(&config.models),
-        reranker::init_cache(&config.models)
+    embedding::init_batching_config(
+        config.models.batch_window_ms,
+        config.models.max_coalesced_batch_size,
     );
 
-    // Start GPU VRAM pressure monitoring (NVML-based, configurable threshold)
-    embedding::spawn_gpu_pressure_monitor(config.models.gpu_pressure_threshold);
-
-    info!(
-        max_queue_depth = config.models.max_queue_depth,
-        queue_timeout_ms = config.models.queue_timeout_ms,
-        cuda_arena_size = ?config.models.cuda_arena_size.map(|s| format!("{}MB", s / (1024 * 1024))).unwrap_or_else(|| "unlimited".to_string()),
-        cuda_arena_extend_strategy = ?config.models.cuda_arena_extend_strategy,
-        "Model workers, queue, and GPU monitor initialized."
-    );
+    // Load models in the background so the liveness probe answers while they
+    // load; the readiness probe returns 503 until the default model is loaded
+    {
+        let models_config = config.models.clone();
+        tokio::spawn(async move {
+            tokio::join!(
+                embedding::init_cache(&models_config),
+                reranker::init_cache(&models_config)
+            );
+
+            // Start GPU VRAM pressure monitoring (NVML-based, configurable thresholds)
+            embedding::spawn_gpu_pressure_monitor(gpu_pressure::PressurePolicy::from_config(
+                &models_config,
+            ));
+
+            info!(
+                max_queue_depth = models_config.max_queue_depth,
+                queue_timeout_ms = models_config.queue_timeout_ms,
+                cuda_arena_size = ?models_config.cuda_arena_size.map(|s| format!("{}MB", s / (1024 * 1024))).unwrap_or_else(|| "unlimited".to_string()),
+                cuda_arena_extend_strategy = ?models_config.cuda_arena_extend_strategy,
+                "Model workers, queue, and GPU monitor initialized."
+            );
+        });
+    }
 
     let model_config = web::Data::new(config.models.clone());
     let hostname = config.server.hostname.clone();
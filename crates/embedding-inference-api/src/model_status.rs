@@ -0,0 +1,269 @@
+//! Load status of the configured embedding models, for the health probes.
+//!
+//! Models load in the background while the server is already answering
+//! liveness probes. Readiness waits for the default model (the first one
+//! configured) so an orchestrator doesn't route traffic to a replica whose
+//! GPU is still cold; `/health/status` lists every model as loaded, pending,
+//! unloaded or failed, with how long it took to load.
+//!
+//! With `INFERENCE_MAX_LOADED_MODELS` set, models past the cap start out
+//! unloaded and load on first use, evicting the least recently used model.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+/// Where a configured model is in loading
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ModelLoadState {
+    Pending,
+    Loaded {
+        load_duration: Duration,
+    },
+    /// Not resident (deferred at startup or evicted); loads on first use
+    Unloaded,
+    Failed {
+        error: String,
+        load_duration: Duration,
+    },
+}
+
+impl ModelLoadState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Loaded { .. } => "loaded",
+            Self::Unloaded => "unloaded",
+            Self::Failed { .. } => "failed",
+        }
+    }
+}
+
+/// Outcome of asking to load a model on demand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoadStart {
+    /// The model was unloaded and is now pending; the caller loads it
+    Started,
+    /// Another request or startup is already loading it
+    InProgress,
+    /// Loaded, failed or not configured; nothing to load
+    NotLoadable,
+}
+
+/// Load state of each model the service was configured to load
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ModelLoadTracker {
+    default_model: Option<String>,
+    models: BTreeMap<String, ModelLoadState>,
+}
+
+impl ModelLoadTracker {
+    /// Track `models`, all pending; the first is the default model
+    pub(crate) fn new(models: &[String]) -> Self {
+        Self {
+            default_model: models.first().cloned(),
+            models: models
+                .iter()
+                .map(|model| (model.clone(), ModelLoadState::Pending))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn loaded(&mut self, model: &str, load_duration: Duration) {
+        self.models
+            .insert(model.to_string(), ModelLoadState::Loaded { load_duration });
+    }
+
+    pub(crate) fn failed(&mut self, model: &str, error: String, load_duration: Duration) {
+        self.models.insert(
+            model.to_string(),
+            ModelLoadState::Failed {
+                error,
+                load_duration,
+            },
+        );
+    }
+
+    pub(crate) fn unloaded(&mut self, model: &str) {
+        self.models
+            .insert(model.to_string(), ModelLoadState::Unloaded);
+    }
+
+    /// Claim an unloaded model for loading, so concurrent requests for it
+    /// don't load it twice
+    pub(crate) fn start_loading(&mut self, model: &str) -> LoadStart {
+        match self.models.get_mut(model) {
+            Some(state @ ModelLoadState::Unloaded) => {
+                *state = ModelLoadState::Pending;
+                LoadStart::Started
+            }
+            Some(ModelLoadState::Pending) => LoadStart::InProgress,
+            _ => LoadStart::NotLoadable,
+        }
+    }
+
+    /// Whether the default model is loaded. With no models configured there
+    /// is nothing to wait for.
+    pub(crate) fn is_ready(&self) -> bool {
+        match &self.default_model {
+            Some(model) => matches!(self.models.get(model), Some(ModelLoadState::Loaded { .. })),
+            None => true,
+        }
+    }
+
+    /// Per-model status for `/health/status`
+    pub(crate) fn to_json(&self) -> Value {
+        let models: Vec<Value> = self
+            .models
+            .iter()
+            .map(|(model, state)| {
+                let mut entry = json!({ "model": model, "state": state.as_str() });
+                match state {
+                    ModelLoadState::Pending | ModelLoadState::Unloaded => {}
+                    ModelLoadState::Loaded { load_duration } => {
+                        entry["load_duration_ms"] = json!(load_duration.as_millis() as u64);
+                    }
+                    ModelLoadState::Failed {
+                        error,
+                        load_duration,
+                    } => {
+                        entry["load_duration_ms"] = json!(load_duration.as_millis() as u64);
+                        entry["error"] = json!(error);
+                    }
+                }
+                entry
+            })
+            .collect();
+        let count = |state: &str| self.models.values().filter(|s| s.as_str() == state).count();
+        json!({
+            "default_model": self.default_model,
+            "loaded": count("loaded"),
+            "pending": count("pending"),
+            "unloaded": count("unloaded"),
+            "failed": count("failed"),
+            "models": models,
+        })
+    }
+}
+
+/// Models to evict, least recently used first, so that at most `keep` of
+/// `resident` (model and last use) stay loaded. `except` is never evicted.
+pub(crate) fn eviction_order(
+    resident: &[(String, u64)],
+    keep: usize,
+    except: Option<&str>,
+) -> Vec<String> {
+    let excess = resident.len().saturating_sub(keep);
+    let mut candidates: Vec<&(String, u64)> = resident
+        .iter()
+        .filter(|(model, _)| Some(model.as_str()) != except)
+        .collect();
+    candidates.sort_by_key(|(_, last_used)| *last_used);
+    candidates
+        .into_iter()
+        .take(excess)
+        .map(|(model, _)| model.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> ModelLoadTracker {
+        ModelLoadTracker::new(&["default-model".to_string(), "other-model".to_string()])
+    }
+
+    #[test]
+    fn test_ready_once_default_model_loads() {
+        let mut tracker = tracker();
+        assert!(!tracker.is_ready());
+        assert_eq!(
+            tracker.start_loading("default-model"),
+            LoadStart::InProgress
+        );
+
+        // Another model finishing first doesn't make the service ready
+        tracker.loaded("other-model", Duration::from_millis(1200));
+        assert!(!tracker.is_ready());
+
+        tracker.loaded("default-model", Duration::from_millis(3400));
+        assert!(tracker.is_ready());
+        assert_eq!(
+            tracker.start_loading("default-model"),
+            LoadStart::NotLoadable
+        );
+
+        assert!(ModelLoadTracker::new(&[]).is_ready());
+    }
+
+    #[test]
+    fn test_failed_default_model_is_not_ready() {
+        let mut tracker = tracker();
+        tracker.failed(
+            "default-model",
+            "CUDA out of memory".to_string(),
+            Duration::from_secs(2),
+        );
+        assert!(!tracker.is_ready());
+        assert_eq!(
+            tracker.start_loading("default-model"),
+            LoadStart::NotLoadable
+        );
+    }
+
+    #[test]
+    fn test_unloaded_models_are_claimed_once() {
+        let mut tracker = tracker();
+        tracker.unloaded("other-model");
+        assert_eq!(tracker.start_loading("other-model"), LoadStart::Started);
+        assert_eq!(tracker.start_loading("other-model"), LoadStart::InProgress);
+
+        tracker.loaded("other-model", Duration::from_millis(900));
+        assert_eq!(tracker.start_loading("other-model"), LoadStart::NotLoadable);
+        assert_eq!(
+            tracker.start_loading("unknown-model"),
+            LoadStart::NotLoadable
+        );
+
+        // A model that failed to load isn't retried on every request
+        tracker.failed("default-model", "bad weights".to_string(), Duration::ZERO);
+        assert_eq!(
+            tracker.start_loading("default-model"),
+            LoadStart::NotLoadable
+        );
+    }
+
+    #[test]
+    fn test_eviction_order_picks_least_recently_used() {
+        let resident = vec![
+            ("a".to_string(), 30),
+            ("b".to_string(), 10),
+            ("c".to_string(), 20),
+            ("d".to_string(), 5),
+        ];
+
+        assert_eq!(eviction_order(&resident, 2, None), vec!["d", "b"]);
+        // The model just loaded stays even if it was never used
+        assert_eq!(eviction_order(&resident, 3, Some("d")), vec!["b"]);
+        assert!(eviction_order(&resident, 4, None).is_empty());
+        assert!(eviction_order(&resident, 8, None).is_empty());
+    }
+
+    #[test]
+    fn test_status_lists_models_with_load_durations() {
+        let mut tracker = tracker();
+        tracker.loaded("default-model", Duration::from_millis(3400));
+
+        let status = tracker.to_json();
+        assert_eq!(status["default_model"], "default-model");
+        assert_eq!(status["loaded"], 1);
+        assert_eq!(status["pending"], 1);
+        assert_eq!(status["models"][0]["model"], "default-model");
+        assert_eq!(status["models"][0]["state"], "loaded");
+        assert_eq!(status["models"][0]["load_duration_ms"], 3400);
+        assert_eq!(status["models"][1]["state"], "pending");
+        assert!(status["models"][1].get("load_duration_ms").is_none());
+    }
+}
@@ -29,3 +29,43 @@ pub fn init_observability(config: &ObservabilityConfig) -> Result<PrometheusMetr
         log_format,
     )
 }
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use opentelemetry::global;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use prometheus::{Encoder, Registry, TextEncoder};
+    use std::sync::OnceLock;
+
+    /// Registry behind the process-wide metrics. Metrics can only be
+    /// initialized once, so every test in the binary shares it.
+    pub(crate) fn metrics_registry() -> &'static Registry {
+        static REGISTRY: OnceLock<Registry> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let registry = Registry::new();
+            let exporter = opentelemetry_prometheus::exporter()
+                .with_registry(registry.clone())
+                .build()
+                .expect("prometheus exporter");
+            global::set_meter_provider(SdkMeterProvider::builder().with_reader(exporter).build());
+            semantic_explorer_core::observability::init_metrics_otel().expect("metrics init");
+            registry
+        })
+    }
+
+    /// Value of `series` for `model`, if it has been recorded
+    pub(crate) fn sample(registry: &Registry, series: &str, model: &str) -> Option<f64> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buffer)
+            .expect("encode metrics");
+        String::from_utf8(buffer)
+            .expect("utf8 metrics")
+            .lines()
+            .find(|line| {
+                line.starts_with(&format!("{series}{{"))
+                    && line.contains(&format!("model=\"{model}\""))
+            })
+            .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+    }
+}
@@ -1,9 +1,6 @@
 use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
 use futures::stream::StreamExt;
 use once_cell::sync::OnceCell;
-use ort::ep::ArenaExtendStrategy;
-use ort::ep::CUDA;
-use ort::ep::cuda::AttentionBackend;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tokio::sync::Mutex as TokioMutex;
@@ -11,6 +8,7 @@ use tracing::{error, info};
 
 use crate::config::ModelConfig;
 use crate::errors::InferenceError;
+use crate::execution_provider::{self, ProviderPolicy};
 
 /// Type alias for the reranker model cache to reduce complexity
 type RerankerCache = Arc<Mutex<HashMap<String, Arc<TokioMutex<TextRerank>>>>>;
@@ -57,10 +55,12 @@ pub async fn init_cache(config: &ModelConfig) {
                 let model_id_clone = model_id.clone();
                 let res = tokio::task::spawn_blocking(move || {
                     match resolve_reranker_model(&model_id_clone) {
-                        Ok(reranker_model) => match create_text_rerank(reranker_model, &config) {
-                            Ok(text_rerank) => Ok((model_id_clone, text_rerank)),
-                            Err(e) => Err((model_id_clone, e)),
-                        },
+                        Ok(reranker_model) => {
+                            match create_text_rerank(&model_id_clone, reranker_model, &config) {
+                                Ok(text_rerank) => Ok((model_id_clone, text_rerank)),
+                                Err(e) => Err((model_id_clone, e)),
+                            }
+                        }
                         Err(e) => Err((model_id_clone, e)),
                     }
                 })
@@ -132,8 +132,8 @@ fn resolve_reranker_model(model_id: &str) -> Result<RerankerModel, InferenceErro
         "jinaai/jina-reranker-v1-turbo-en" => RerankerModel::JINARerankerV1TurboEn,
         "jinaai/jina-reranker-v2-base-multilingual" => RerankerModel::JINARerankerV2BaseMultiligual,
         _ => {
-            return Err(InferenceError::UnsupportedModel(format!(
-                "Unsupported reranker model: {}",
+            return Err(InferenceError::ModelNotFound(format!(
+                "Unknown reranker model: {}",
                 model_id
             )));
         }
@@ -142,57 +142,36 @@ fn resolve_reranker_model(model_id: &str) -> Result<RerankerModel, InferenceErro
     Ok(model)
 }
 
-/// Create a TextRerank instance with proper configuration
-///
-/// IMPORTANT: ONNX Runtime's CUDA execution provider can silently fall back to CPU
-/// if CUDA initialization fails. This function now logs detailed information.
+/// Create a TextRerank instance on the GPU, falling back to the CPU when
+/// CUDA is unavailable and `INFERENCE_REQUIRE_GPU` isn't set
 fn create_text_rerank(
+    model_id: &str,
     model: RerankerModel,
     config: &ModelConfig,
 ) -> Result<TextRerank, InferenceError> {
-    let mut cuda = CUDA::default()
-        .with_prefer_nhwc(true)
-        .with_attention_backend(AttentionBackend::CUDNN_FLASH_ATTENTION);
-
-    // Apply CUDA arena size limit if configured, otherwise uses all available GPU memory
-    if let Some(arena_size) = config.cuda_arena_size {
-        info!(
-            cuda_arena_size_bytes = arena_size,
-            cuda_arena_size_mb = arena_size / (1024 * 1024),
-            "Setting CUDA memory arena limit for reranker"
-        );
-        cuda = cuda.with_memory_limit(arena_size);
-    }
-
-    // Apply arena extend strategy
-    let strategy = match config.cuda_arena_extend_strategy {
-        crate::config::CudaArenaExtendStrategy::SameAsRequested => {
-            ArenaExtendStrategy::SameAsRequested
-        }
-        crate::config::CudaArenaExtendStrategy::NextPowerOfTwo => {
-            ArenaExtendStrategy::NextPowerOfTwo
-        }
-    };
-    cuda = cuda.with_arena_extend_strategy(strategy);
-
-    let cuda_provider = cuda.build().error_on_failure();
-    let mut options = RerankInitOptions::new(model)
-        .with_execution_providers(vec![cuda_provider])
-        .with_show_download_progress(true);
-
-    // Set cache directory if HF_HOME is configured
-    if let Some(ref hf_home) = config.hf_home {
-        options = options.with_cache_dir(hf_home.clone());
-    }
+    let policy = ProviderPolicy::for_model(config, model_id);
+    let (text_rerank, provider) =
+        execution_provider::load_with_fallback(model_id, policy, |provider| {
+            let mut options = RerankInitOptions::new(model.clone())
+                .with_execution_providers(execution_provider::onnx_providers(provider, config))
+                .with_show_download_progress(true);
+
+            // Set cache directory if HF_HOME is configured
+            if let Some(ref hf_home) = config.hf_home {
+                options = options.with_cache_dir(hf_home.clone());
+            }
 
-    let text_rerank = TextRerank::try_new(options).map_err(|e| {
-        error!(
-            error = %e,
-            "Failed to initialize reranker model with CUDA. \
-                This may indicate a CUDA/cuDNN version mismatch."
-        );
-        InferenceError::ModelLoad(e.to_string())
-    })?;
+            TextRerank::try_new(options).map_err(|e| {
+                error!(
+                    error = %e,
+                    provider = provider.as_str(),
+                    "Failed to initialize reranker model. \
+                        On CUDA this may indicate a CUDA/cuDNN version mismatch."
+                );
+                InferenceError::ModelLoad(e.to_string())
+            })
+        })?;
+    execution_provider::record_loaded(model_id, provider);
 
     Ok(text_rerank)
 }
@@ -207,10 +186,10 @@ pub async fn rerank_documents(
 ) -> Result<Vec<fastembed::RerankResult>, InferenceError> {
     // Check if model is allowed
     if !config.is_rerank_model_allowed(model_id) {
-        return Err(InferenceError::UnsupportedModel(format!(
-            "Model {} is not in the allowed models list",
-            model_id
-        )));
+        return Err(InferenceError::unavailable_model(
+            model_id,
+            resolve_reranker_model(model_id).is_ok(),
+        ));
     }
 
     let models = RERANKER_MODELS
@@ -227,7 +206,7 @@ pub async fn rerank_documents(
         if !cache.contains_key(model_id) {
             info!(model_id = %model_id, "Loading reranker model on demand");
             let reranker_model = resolve_reranker_model(model_id)?;
-            let text_rerank = create_text_rerank(reranker_model, config)?;
+            let text_rerank = create_text_rerank(model_id, reranker_model, config)?;
             cache.insert(model_id.to_string(), Arc::new(TokioMutex::new(text_rerank)));
         }
 
@@ -6,9 +6,11 @@ use serde::{Deserialize, Serialize};
 use tracing::{info, instrument, warn};
 use utoipa::ToSchema;
 
+use crate::api::generation::SamplingOptions;
 use crate::config::{GenerationConfig, ModelConfig};
 use crate::errors::InferenceError;
 use crate::llm;
+use semantic_explorer_core::observability::CancellationGuard;
 
 /// Chat message
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -53,10 +55,8 @@ pub struct ChatRequest {
     /// Maximum number of tokens to generate (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<usize>,
-    /// Stop sequences (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[allow(dead_code)]
-    pub stop: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub sampling: SamplingOptions,
 }
 
 /// Response for chat completion
@@ -81,7 +81,9 @@ pub struct ChatResponse {
     request_body = ChatRequest,
     responses(
         (status = 200, description = "Chat completion generated successfully", body = ChatResponse),
-        (status = 400, description = "Invalid request or unsupported model"),
+        (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Model not found"),
         (status = 500, description = "Internal server error")
     ),
     tag = "chat"
@@ -113,40 +115,45 @@ pub async fn chat_completion(
             .error_response();
     }
 
-    // Build generation parameters
-    let params = llm::GenerationParams {
-        temperature: body.temperature.unwrap_or(gen_config.default_temperature),
-        top_p: body.top_p.unwrap_or(gen_config.default_top_p),
-        max_tokens: body.max_tokens.unwrap_or(gen_config.default_max_tokens),
-    };
+    // Build generation parameters, rejecting out-of-range sampling values
+    let params =
+        match body
+            .sampling
+            .resolve(body.temperature, body.top_p, body.max_tokens, &gen_config)
+        {
+            Ok(params) => params,
+            Err(e) => return e.error_response(),
+        };
 
     let start = std::time::Instant::now();
 
     // Convert messages
     let llm_messages: Vec<llm::ChatMessage> = messages.into_iter().map(Into::into).collect();
 
-    // Generate chat completion
+    // Generate chat completion; the guard counts the request as cancelled if
+    // the client disconnects and actix drops this future first
+    let guard = CancellationGuard::new("chat", &model_id);
     let result =
-        match llm::chat_completion(&model_id, llm_messages, params, &model_config, &gen_config)
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let duration = start.elapsed().as_secs_f64();
-                tracing::error!(
-                    model = %model_id,
-                    error = %e,
-                    duration_secs = duration,
-                    "Chat completion failed"
-                );
-
-                semantic_explorer_core::observability::record_llm_request(
-                    &model_id, 0, duration, false,
-                );
-
-                return e.error_response();
-            }
-        };
+        llm::chat_completion(&model_id, llm_messages, params, &model_config, &gen_config).await;
+    guard.complete();
+    let result = match result {
+        Ok(r) => r,
+        Err(e) => {
+            let duration = start.elapsed().as_secs_f64();
+            tracing::error!(
+                model = %model_id,
+                error = %e,
+                duration_secs = duration,
+                "Chat completion failed"
+            );
+
+            semantic_explorer_core::observability::record_llm_request(
+                &model_id, 0, duration, false,
+            );
+
+            return e.error_response();
+        }
+    };
 
     let duration = start.elapsed().as_secs_f64();
 
@@ -181,7 +188,9 @@ pub async fn chat_completion(
     request_body = ChatRequest,
     responses(
         (status = 200, description = "Streaming chat completion", content_type = "text/event-stream"),
-        (status = 400, description = "Invalid request or unsupported model"),
+        (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Model not found"),
         (status = 500, description = "Internal server error")
     ),
     tag = "chat"
@@ -194,7 +203,7 @@ pub async fn chat_completion_stream(
     body: web::Json<ChatRequest>,
 ) -> impl Responder {
     // Backpressure: try to acquire a permit, return 503 if at capacity
-    let _permit = match llm::try_acquire_permit() {
+    let permit = match llm::try_acquire_permit() {
         Some(permit) => permit,
         None => {
             warn!(
@@ -223,12 +232,15 @@ pub async fn chat_completion_stream(
             .error_response();
     }
 
-    // Build generation parameters
-    let params = llm::GenerationParams {
-        temperature: body.temperature.unwrap_or(gen_config.default_temperature),
-        top_p: body.top_p.unwrap_or(gen_config.default_top_p),
-        max_tokens: body.max_tokens.unwrap_or(gen_config.default_max_tokens),
-    };
+    // Build generation parameters, rejecting out-of-range sampling values
+    let params =
+        match body
+            .sampling
+            .resolve(body.temperature, body.top_p, body.max_tokens, &gen_config)
+        {
+            Ok(params) => params,
+            Err(e) => return e.error_response(),
+        };
 
     info!(
         model = %model_id,
@@ -252,22 +264,28 @@ pub async fn chat_completion_stream(
         Err(e) => return e.error_response(),
     };
 
-    // Convert to SSE format
-    let sse_stream = stream.map(|result| match result {
-        Ok(chunk) => {
-            // Format as Server-Sent Events with JSON data
-            Ok::<_, actix_web::Error>(web::Bytes::from(format!(
-                "data: {}\n\n",
-                serde_json::json!({"content": chunk})
-            )))
-        }
-        Err(e) => {
-            // Send error as SSE event
-            Ok(web::Bytes::from(format!(
-                "data: {{\"error\": \"{}\"}}\n\n",
-                e
-            )))
-        }
+    // Convert to SSE format, holding the permit until the stream ends or the
+    // client disconnects
+    let guard = CancellationGuard::new("chat_stream", &model_id);
+    let sse_stream = llm::guard_stream(stream, permit, guard).filter_map(|result| {
+        std::future::ready(match result {
+            Ok(llm::StreamChunk::Text(chunk)) => {
+                // Format as Server-Sent Events with JSON data
+                Some(Ok::<_, actix_web::Error>(web::Bytes::from(format!(
+                    "data: {}\n\n",
+                    serde_json::json!({"content": chunk})
+                ))))
+            }
+            // This endpoint only streams text
+            Ok(llm::StreamChunk::Finished(_)) => None,
+            Err(e) => {
+                // Send error as SSE event
+                Some(Ok(web::Bytes::from(format!(
+                    "data: {{\"error\": \"{}\"}}\n\n",
+                    e
+                ))))
+            }
+        })
     });
 
     HttpResponse::Ok()
@@ -9,8 +9,10 @@ use serde::{Deserialize, Serialize};
 use tracing::{info, instrument, warn};
 use utoipa::ToSchema;
 
+use crate::api::generation::SamplingOptions;
 use crate::config::{GenerationConfig, ModelConfig};
 use crate::llm;
+use semantic_explorer_core::observability::CancellationGuard;
 
 /// Request body for text/code completion
 #[derive(Debug, Deserialize, ToSchema)]
@@ -31,10 +33,8 @@ pub struct CompletionRequest {
     /// Maximum number of tokens to generate (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<usize>,
-    /// Stop sequences (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[allow(dead_code)]
-    pub stop: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub sampling: SamplingOptions,
 }
 
 /// Response for text/code completion
@@ -60,7 +60,9 @@ pub struct CompletionResponse {
     request_body = CompletionRequest,
     responses(
         (status = 200, description = "Completion generated successfully", body = CompletionResponse),
-        (status = 400, description = "Invalid request or unsupported model"),
+        (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Model not found"),
         (status = 503, description = "Service at capacity"),
         (status = 500, description = "Internal server error")
     ),
@@ -87,17 +89,22 @@ pub async fn completion(
 
     tracing::Span::current().record("model", &model_id);
 
-    // Build generation parameters
-    let params = llm::GenerationParams {
-        temperature: body.temperature.unwrap_or(gen_config.default_temperature),
-        top_p: body.top_p.unwrap_or(gen_config.default_top_p),
-        max_tokens: body.max_tokens.unwrap_or(gen_config.default_max_tokens),
-    };
+    // Build generation parameters, rejecting out-of-range sampling values
+    let params =
+        match body
+            .sampling
+            .resolve(body.temperature, body.top_p, body.max_tokens, &gen_config)
+        {
+            Ok(params) => params,
+            Err(e) => return e.error_response(),
+        };
 
     let start = std::time::Instant::now();
 
-    // Generate completion
-    let result = match llm::text_completion(
+    // Generate completion; the guard counts the request as cancelled if the
+    // client disconnects and actix drops this future first
+    let guard = CancellationGuard::new("completions", &model_id);
+    let result = llm::text_completion(
         &model_id,
         prompt,
         suffix,
@@ -105,8 +112,9 @@ pub async fn completion(
         &model_config,
         &gen_config,
     )
-    .await
-    {
+    .await;
+    guard.complete();
+    let result = match result {
         Ok(r) => r,
         Err(e) => {
             let duration = start.elapsed().as_secs_f64();
@@ -159,7 +167,9 @@ pub async fn completion(
     request_body = CompletionRequest,
     responses(
         (status = 200, description = "Streaming completion", content_type = "text/event-stream"),
-        (status = 400, description = "Invalid request or unsupported model"),
+        (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Model not found"),
         (status = 503, description = "Service at capacity"),
         (status = 500, description = "Internal server error")
     ),
@@ -173,7 +183,7 @@ pub async fn completion_stream(
     body: web::Json<CompletionRequest>,
 ) -> impl Responder {
     // Backpressure: try to acquire a permit, return 503 if at capacity
-    let _permit = match llm::try_acquire_permit() {
+    let permit = match llm::try_acquire_permit() {
         Some(permit) => permit,
         None => {
             warn!(
@@ -196,12 +206,15 @@ pub async fn completion_stream(
 
     tracing::Span::current().record("model", &model_id);
 
-    // Build generation parameters
-    let params = llm::GenerationParams {
-        temperature: body.temperature.unwrap_or(gen_config.default_temperature),
-        top_p: body.top_p.unwrap_or(gen_config.default_top_p),
-        max_tokens: body.max_tokens.unwrap_or(gen_config.default_max_tokens),
-    };
+    // Build generation parameters, rejecting out-of-range sampling values
+    let params =
+        match body
+            .sampling
+            .resolve(body.temperature, body.top_p, body.max_tokens, &gen_config)
+        {
+            Ok(params) => params,
+            Err(e) => return e.error_response(),
+        };
 
     info!(
         model = %model_id,
@@ -224,22 +237,28 @@ pub async fn completion_stream(
         Err(e) => return e.error_response(),
     };
 
-    // Convert to SSE format
-    let sse_stream = stream.map(|result| match result {
-        Ok(chunk) => {
-            // Format as Server-Sent Events with JSON data
-            Ok::<_, actix_web::Error>(web::Bytes::from(format!(
-                "data: {}\n\n",
-                serde_json::json!({"text": chunk})
-            )))
-        }
-        Err(e) => {
-            // Send error as SSE event
-            Ok(web::Bytes::from(format!(
-                "data: {{\"error\": \"{}\"}}\n\n",
-                e
-            )))
-        }
+    // Convert to SSE format, holding the permit until the stream ends or the
+    // client disconnects
+    let guard = CancellationGuard::new("completions_stream", &model_id);
+    let sse_stream = llm::guard_stream(stream, permit, guard).filter_map(|result| {
+        std::future::ready(match result {
+            Ok(llm::StreamChunk::Text(chunk)) => {
+                // Format as Server-Sent Events with JSON data
+                Some(Ok::<_, actix_web::Error>(web::Bytes::from(format!(
+                    "data: {}\n\n",
+                    serde_json::json!({"text": chunk})
+                ))))
+            }
+            // This endpoint only streams text
+            Ok(llm::StreamChunk::Finished(_)) => None,
+            Err(e) => {
+                // Send error as SSE event
+                Some(Ok(web::Bytes::from(format!(
+                    "data: {{\"error\": \"{}\"}}\n\n",
+                    e
+                ))))
+            }
+        })
     });
 
     HttpResponse::Ok()
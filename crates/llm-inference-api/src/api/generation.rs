@@ -6,8 +6,10 @@ use tracing::{info, instrument};
 use utoipa::ToSchema;
 
 use crate::config::{GenerationConfig, ModelConfig};
+use crate::errors::InferenceError;
 use crate::llm;
 use crate::models::get_llm_models;
+use semantic_explorer_core::observability::CancellationGuard;
 
 /// Request body for text generation
 #[derive(Debug, Deserialize, ToSchema)]
@@ -25,12 +27,61 @@ pub struct GenerateRequest {
     /// Maximum number of tokens to generate (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<usize>,
-    /// Stop sequences (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[allow(dead_code)]
+    #[serde(flatten)]
+    pub sampling: SamplingOptions,
+}
+
+/// Sampling controls accepted by every generation endpoint, on top of
+/// temperature and top-p
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct SamplingOptions {
+    /// Sample only from the k most likely tokens (at least 1, optional)
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    /// Penalty on tokens already generated, 1.0 for none (above 0.0 up to
+    /// 2.0, optional)
+    #[serde(default)]
+    pub repetition_penalty: Option<f32>,
+    /// Penalty growing with how often a token already appeared (-2.0-2.0,
+    /// optional)
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+    /// Penalty on any token that already appeared (-2.0-2.0, optional)
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+    /// Strings that end generation, trimmed from the output (at most 8, of
+    /// up to 64 characters each, optional)
+    #[serde(default)]
     pub stop: Option<Vec<String>>,
 }
 
+impl SamplingOptions {
+    /// Generation parameters for a request, with whatever it leaves unset
+    /// taken from the configured defaults. Out-of-range values are rejected
+    /// rather than clamped.
+    pub fn resolve(
+        &self,
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+        max_tokens: Option<usize>,
+        gen_config: &GenerationConfig,
+    ) -> Result<llm::GenerationParams, InferenceError> {
+        let defaults = gen_config.default_params();
+        let params = llm::GenerationParams {
+            temperature: temperature.unwrap_or(defaults.temperature),
+            top_p: top_p.unwrap_or(defaults.top_p),
+            max_tokens: max_tokens.unwrap_or(defaults.max_tokens),
+            top_k: self.top_k.or(defaults.top_k),
+            repetition_penalty: self.repetition_penalty.or(defaults.repetition_penalty),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+            stop: self.stop.clone().unwrap_or_default(),
+        };
+        params.validate().map_err(InferenceError::BadRequest)?;
+        Ok(params)
+    }
+}
+
 /// Response for text generation
 #[derive(Debug, Serialize, ToSchema)]
 pub struct GenerateResponse {
@@ -68,7 +119,9 @@ pub async fn list_llms(model_config: web::Data<ModelConfig>) -> impl Responder {
     request_body = GenerateRequest,
     responses(
         (status = 200, description = "Text generated successfully", body = GenerateResponse),
-        (status = 400, description = "Invalid request or unsupported model"),
+        (status = 400, description = "Invalid request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Model not found"),
         (status = 500, description = "Internal server error")
     ),
     tag = "generation"
@@ -93,17 +146,24 @@ pub async fn generate(
 
     tracing::Span::current().record("model", &model_id);
 
-    // Build generation parameters
-    let params = llm::GenerationParams {
-        temperature: body.temperature.unwrap_or(gen_config.default_temperature),
-        top_p: body.top_p.unwrap_or(gen_config.default_top_p),
-        max_tokens: body.max_tokens.unwrap_or(gen_config.default_max_tokens),
-    };
+    // Build generation parameters, rejecting out-of-range sampling values
+    let params =
+        match body
+            .sampling
+            .resolve(body.temperature, body.top_p, body.max_tokens, &gen_config)
+        {
+            Ok(params) => params,
+            Err(e) => return e.error_response(),
+        };
 
     let start = std::time::Instant::now();
 
-    // Generate text
-    match llm::generate_text(&model_id, prompt, params, &model_config, &gen_config).await {
+    // Generate text; the guard counts the request as cancelled if the client
+    // disconnects and actix drops this future first
+    let guard = CancellationGuard::new("generate", &model_id);
+    let result = llm::generate_text(&model_id, prompt, params, &model_config, &gen_config).await;
+    guard.complete();
+    match result {
         Ok(result) => {
             let duration = start.elapsed().as_secs_f64();
 
@@ -169,4 +229,78 @@ mod tests {
         assert_eq!(req.top_p, None);
         assert_eq!(req.max_tokens, None);
     }
+
+    fn gen_config() -> GenerationConfig {
+        GenerationConfig {
+            default_temperature: 0.7,
+            default_top_p: 0.9,
+            default_max_tokens: 512,
+            max_tokens_limit: 4096,
+            default_top_k: Some(40),
+            default_repetition_penalty: None,
+            default_frequency_penalty: None,
+            default_presence_penalty: None,
+        }
+    }
+
+    #[test]
+    fn test_sampling_options_fall_back_to_defaults() {
+        let json = r#"{"model": "m", "prompt": "Hi", "repetition_penalty": 1.1, "presence_penalty": -0.5}"#;
+        let req: GenerateRequest = serde_json::from_str(json).unwrap();
+        let params = req
+            .sampling
+            .resolve(req.temperature, req.top_p, req.max_tokens, &gen_config())
+            .unwrap();
+        assert_eq!(params.top_k, Some(40));
+        assert_eq!(params.top_p, 0.9);
+        assert_eq!(params.repetition_penalty, Some(1.1));
+        assert_eq!(params.frequency_penalty, None);
+        assert_eq!(params.presence_penalty, Some(-0.5));
+        assert!(params.stop.is_empty());
+    }
+
+    #[test]
+    fn test_stop_sequences_are_passed_through_and_limited() {
+        let req: GenerateRequest =
+            serde_json::from_str(r#"{"model": "m", "prompt": "Hi", "stop": ["</out>", "END"]}"#)
+                .unwrap();
+        let params = req
+            .sampling
+            .resolve(req.temperature, req.top_p, req.max_tokens, &gen_config())
+            .unwrap();
+        assert_eq!(params.stop, vec!["</out>", "END"]);
+
+        let req: GenerateRequest = serde_json::from_value(serde_json::json!({
+            "model": "m",
+            "prompt": "Hi",
+            "stop": vec!["x"; crate::stop::MAX_STOP_SEQUENCES + 1],
+        }))
+        .unwrap();
+        assert!(matches!(
+            req.sampling
+                .resolve(req.temperature, req.top_p, req.max_tokens, &gen_config()),
+            Err(InferenceError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_out_of_range_sampling_is_rejected() {
+        let resolve = |json: &str| {
+            let req: GenerateRequest = serde_json::from_str(json).unwrap();
+            req.sampling
+                .resolve(req.temperature, req.top_p, req.max_tokens, &gen_config())
+        };
+
+        for json in [
+            r#"{"model": "m", "prompt": "Hi", "top_p": 1.5}"#,
+            r#"{"model": "m", "prompt": "Hi", "top_k": 0}"#,
+            r#"{"model": "m", "prompt": "Hi", "repetition_penalty": 0.0}"#,
+            r#"{"model": "m", "prompt": "Hi", "frequency_penalty": 2.5}"#,
+        ] {
+            match resolve(json) {
+                Err(InferenceError::BadRequest(msg)) => assert!(msg.contains("must be")),
+                other => panic!("expected a bad request for {json}, got {other:?}"),
+            }
+        }
+    }
 }
@@ -4,7 +4,9 @@ use actix_web::{HttpResponse, Responder, get};
 
 use crate::llm;
 
-/// Liveness probe - always returns OK if the service is running
+/// Liveness probe - always returns OK if the service is running. It touches
+/// no model or GPU state and answers with a static body, so load balancers
+/// can poll it freely; it is excluded from metrics.
 #[utoipa::path(
     get,
     path = "/health/live",
@@ -15,9 +17,9 @@ use crate::llm;
 )]
 #[get("/health/live")]
 pub async fn health_live() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "ok"
-    }))
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"status":"ok"}"#)
 }
 
 /// Readiness probe - returns OK if models are loaded
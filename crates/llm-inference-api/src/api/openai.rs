@@ -0,0 +1,628 @@
+//! OpenAI-compatible chat completions.
+//!
+//! `POST /v1/chat/completions` accepts the OpenAI request shape and answers
+//! with the OpenAI response envelope, so existing OpenAI client libraries can
+//! talk to this service. It is a thin adapter over the chat endpoints: the
+//! request is translated to [`llm::GenerationParams`] and chat messages, and
+//! the result (or stream) is wrapped back into OpenAI JSON.
+//!
+//! Fields in [`IGNORED_FIELDS`] are accepted and ignored. Anything else the
+//! adapter doesn't handle is rejected with a 400, so a client never silently
+//! loses behaviour it asked for.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{HttpResponse, Responder, ResponseError, post, web};
+use futures::StreamExt;
+use futures::stream::Stream;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tracing::{info, instrument};
+use utoipa::ToSchema;
+
+use crate::api::generation::SamplingOptions;
+use crate::config::{GenerationConfig, ModelConfig};
+use crate::errors::InferenceError;
+use crate::llm::{self, FinishReason, StreamChunk};
+use semantic_explorer_core::observability::CancellationGuard;
+
+/// Request fields accepted for compatibility but without effect
+pub const IGNORED_FIELDS: &[&str] = &[
+    "user",
+    "seed",
+    "metadata",
+    "store",
+    "service_tier",
+    "stream_options",
+    "parallel_tool_calls",
+];
+
+/// OpenAI chat completion request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OpenAiChatRequest {
+    /// Model to use
+    pub model: String,
+    /// Conversation history
+    #[schema(value_type = Vec<Object>)]
+    pub messages: Vec<Value>,
+    /// Stream the response as `chat.completion.chunk` events
+    #[serde(default)]
+    pub stream: bool,
+    /// Temperature for sampling (0.0-2.0)
+    pub temperature: Option<f32>,
+    /// Top-p for nucleus sampling (0.0-1.0)
+    pub top_p: Option<f32>,
+    /// Maximum number of tokens to generate
+    pub max_tokens: Option<usize>,
+    /// Newer name of `max_tokens`; wins when both are set
+    pub max_completion_tokens: Option<usize>,
+    /// Number of choices; only 1 is supported
+    pub n: Option<u32>,
+    /// A stop string or a list of them
+    #[schema(value_type = Option<Object>)]
+    pub stop: Option<Value>,
+    /// Penalty on tokens by how often they already appeared (-2.0-2.0)
+    pub frequency_penalty: Option<f32>,
+    /// Penalty on tokens that already appeared (-2.0-2.0)
+    pub presence_penalty: Option<f32>,
+    /// Every other field, checked against [`IGNORED_FIELDS`]
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub other: Map<String, Value>,
+}
+
+/// A request translated for the chat code
+struct ChatCall {
+    messages: Vec<llm::ChatMessage>,
+    params: llm::GenerationParams,
+}
+
+impl OpenAiChatRequest {
+    fn into_chat_call(self, gen_config: &GenerationConfig) -> Result<ChatCall, InferenceError> {
+        if let Some(field) = self
+            .other
+            .iter()
+            .find(|(key, value)| !value.is_null() && !IGNORED_FIELDS.contains(&key.as_str()))
+            .map(|(key, _)| key)
+        {
+            return Err(InferenceError::BadRequest(format!(
+                "'{field}' is not supported by this endpoint"
+            )));
+        }
+        if self.n.is_some_and(|n| n != 1) {
+            return Err(InferenceError::BadRequest(
+                "'n' must be 1; multiple choices are not supported".to_string(),
+            ));
+        }
+        if self.messages.is_empty() {
+            return Err(InferenceError::BadRequest(
+                "'messages' must not be empty".to_string(),
+            ));
+        }
+
+        let stop = match self.stop {
+            None | Some(Value::Null) => None,
+            Some(Value::String(stop)) => Some(vec![stop]),
+            Some(stop) => Some(serde_json::from_value(stop).map_err(|_| {
+                InferenceError::BadRequest(
+                    "'stop' must be a string or a list of strings".to_string(),
+                )
+            })?),
+        };
+        let sampling = SamplingOptions {
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+            stop,
+            ..Default::default()
+        };
+        let params = sampling.resolve(
+            self.temperature,
+            self.top_p,
+            self.max_completion_tokens.or(self.max_tokens),
+            gen_config,
+        )?;
+
+        let messages = self
+            .messages
+            .iter()
+            .map(chat_message)
+            .collect::<Result<_, _>>()?;
+        Ok(ChatCall { messages, params })
+    }
+}
+
+/// Convert an OpenAI message, whose content may be a string or a list of
+/// text parts
+fn chat_message(message: &Value) -> Result<llm::ChatMessage, InferenceError> {
+    let role = match message["role"].as_str() {
+        Some(role @ ("system" | "user" | "assistant")) => role,
+        Some("developer") => "system",
+        Some(role) => {
+            return Err(InferenceError::BadRequest(format!(
+                "Message role '{role}' is not supported"
+            )));
+        }
+        None => {
+            return Err(InferenceError::BadRequest(
+                "Every message needs a 'role'".to_string(),
+            ));
+        }
+    };
+
+    let content = match &message["content"] {
+        Value::String(content) => content.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(
+                |part| match (part["type"].as_str(), part["text"].as_str()) {
+                    (Some("text"), Some(text)) => Ok(text),
+                    _ => Err(InferenceError::BadRequest(
+                        "Only text content parts are supported".to_string(),
+                    )),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n"),
+        _ => {
+            return Err(InferenceError::BadRequest(
+                "Message 'content' must be a string or a list of text parts".to_string(),
+            ));
+        }
+    };
+
+    Ok(llm::ChatMessage {
+        role: role.to_string(),
+        content,
+    })
+}
+
+/// OpenAI `finish_reason` for ours
+fn finish_reason(reason: &FinishReason) -> &'static str {
+    match reason {
+        FinishReason::Length => "length",
+        FinishReason::Stop | FinishReason::Eos | FinishReason::Error => "stop",
+    }
+}
+
+/// `chatcmpl-` id, unique within this process
+fn completion_id(created: u64) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "chatcmpl-{created:x}{:06x}",
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `chat.completion` body for a finished chat
+fn completion_body(id: &str, created: u64, response: &llm::ChatResponse) -> Value {
+    json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": created,
+        "model": response.model,
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": response.message.content,
+            },
+            "logprobs": null,
+            "finish_reason": finish_reason(&response.finish_reason),
+        }],
+        "usage": {
+            "prompt_tokens": response.prompt_tokens,
+            "completion_tokens": response.tokens_generated,
+            "total_tokens": response.prompt_tokens + response.tokens_generated,
+        },
+    })
+}
+
+/// `chat.completion.chunk` body
+fn chunk_body(
+    id: &str,
+    created: u64,
+    model: &str,
+    delta: Value,
+    finish_reason: Option<&str>,
+) -> Value {
+    json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": created,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "delta": delta,
+            "logprobs": null,
+            "finish_reason": finish_reason,
+        }],
+    })
+}
+
+/// OpenAI error body
+fn error_body(error: &InferenceError) -> Value {
+    let error_type = if error.status_code().is_client_error() {
+        "invalid_request_error"
+    } else {
+        "server_error"
+    };
+    json!({
+        "error": {
+            "message": error.to_string(),
+            "type": error_type,
+            "param": null,
+            "code": null,
+        }
+    })
+}
+
+fn error_response(error: InferenceError) -> HttpResponse {
+    HttpResponse::build(error.status_code()).json(error_body(&error))
+}
+
+fn sse_event(data: &Value) -> web::Bytes {
+    web::Bytes::from(format!("data: {data}\n\n"))
+}
+
+/// SSE events for a generation stream: a role chunk, one chunk per piece of
+/// text, a final chunk with the stream's finish reason (`stop` when it
+/// reported none) and `data: [DONE]`. An error mid-stream is sent as an
+/// OpenAI error event and ends the stream.
+fn sse_events<S>(
+    text: S,
+    id: String,
+    created: u64,
+    model: String,
+) -> impl Stream<Item = Result<web::Bytes, actix_web::Error>>
+where
+    S: Stream<Item = Result<StreamChunk, InferenceError>>,
+{
+    async_stream::stream! {
+        let mut text = std::pin::pin!(text);
+        yield Ok(sse_event(&chunk_body(
+            &id,
+            created,
+            &model,
+            json!({ "role": "assistant", "content": "" }),
+            None,
+        )));
+        let mut finished = FinishReason::Eos;
+        while let Some(item) = text.next().await {
+            match item {
+                Ok(StreamChunk::Finished(reason)) => finished = reason,
+                Ok(StreamChunk::Text(content)) => {
+                    yield Ok(sse_event(&chunk_body(
+                        &id,
+                        created,
+                        &model,
+                        json!({ "content": content }),
+                        None,
+                    )));
+                }
+                Err(e) => {
+                    yield Ok(sse_event(&error_body(&e)));
+                    return;
+                }
+            }
+        }
+        yield Ok(sse_event(&chunk_body(
+            &id,
+            created,
+            &model,
+            json!({}),
+            Some(finish_reason(&finished)),
+        )));
+        yield Ok(web::Bytes::from_static(b"data: [DONE]\n\n"));
+    }
+}
+
+/// OpenAI-compatible chat completion
+///
+/// Accepts the OpenAI chat completions request and returns a
+/// `chat.completion` object, or `chat.completion.chunk` Server-Sent Events
+/// ending with `data: [DONE]` when `stream` is true
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    request_body = OpenAiChatRequest,
+    responses(
+        (status = 200, description = "OpenAI chat completion, or SSE chunks when streaming"),
+        (status = 400, description = "Invalid or unsupported request"),
+        (status = 403, description = "Model not in the allowed models list"),
+        (status = 404, description = "Model not found"),
+        (status = 503, description = "Service at capacity"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "openai"
+)]
+#[post("/v1/chat/completions")]
+#[instrument(skip(model_config, gen_config, body), fields(model, stream))]
+pub async fn chat_completions(
+    model_config: web::Data<ModelConfig>,
+    gen_config: web::Data<GenerationConfig>,
+    body: web::Json<OpenAiChatRequest>,
+) -> impl Responder {
+    let body = body.into_inner();
+    let model_id = body.model.clone();
+    let stream = body.stream;
+    tracing::Span::current().record("model", &model_id);
+    tracing::Span::current().record("stream", stream);
+
+    let call = match body.into_chat_call(&gen_config) {
+        Ok(call) => call,
+        Err(e) => return error_response(e),
+    };
+    let created = unix_now();
+    let id = completion_id(created);
+
+    if stream {
+        // Backpressure: like /api/chat/stream, fail fast when at capacity
+        let Some(permit) = llm::try_acquire_permit() else {
+            return error_response(InferenceError::ServiceUnavailable(
+                "Too many concurrent LLM requests. Please retry after a short delay.".to_string(),
+            ));
+        };
+        let text = match llm::chat_completion_stream(
+            &model_id,
+            call.messages,
+            call.params,
+            &model_config,
+            &gen_config,
+        )
+        .await
+        {
+            Ok(text) => text,
+            Err(e) => return error_response(e),
+        };
+        let guard = CancellationGuard::new("openai_chat_stream", &model_id);
+        let text = llm::guard_stream(text, permit, guard);
+        return HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header(("Cache-Control", "no-cache"))
+            .insert_header(("X-Accel-Buffering", "no"))
+            .streaming(sse_events(text, id, created, model_id));
+    }
+
+    let _permit = match llm::acquire_permit_with_timeout().await {
+        Ok(permit) => permit,
+        Err(e) => return error_response(e),
+    };
+    let start = std::time::Instant::now();
+    let guard = CancellationGuard::new("openai_chat", &model_id);
+    let result = llm::chat_completion(
+        &model_id,
+        call.messages,
+        call.params,
+        &model_config,
+        &gen_config,
+    )
+    .await;
+    guard.complete();
+    let duration = start.elapsed().as_secs_f64();
+
+    match result {
+        Ok(response) => {
+            info!(
+                model = %model_id,
+                tokens = response.tokens_generated,
+                duration_secs = duration,
+                "OpenAI-compatible chat completion generated"
+            );
+            semantic_explorer_core::observability::record_llm_request(
+                &model_id,
+                response.tokens_generated as u64,
+                duration,
+                true,
+            );
+            HttpResponse::Ok().json(completion_body(&id, created, &response))
+        }
+        Err(e) => {
+            tracing::error!(model = %model_id, error = %e, "OpenAI-compatible chat completion failed");
+            semantic_explorer_core::observability::record_llm_request(
+                &model_id, 0, duration, false,
+            );
+            error_response(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_config() -> GenerationConfig {
+        GenerationConfig {
+            default_temperature: 0.7,
+            default_top_p: 0.9,
+            default_max_tokens: 512,
+            max_tokens_limit: 4096,
+            default_top_k: None,
+            default_repetition_penalty: None,
+            default_frequency_penalty: None,
+            default_presence_penalty: None,
+        }
+    }
+
+    fn request(body: Value) -> Result<ChatCall, InferenceError> {
+        serde_json::from_value::<OpenAiChatRequest>(body)
+            .unwrap()
+            .into_chat_call(&gen_config())
+    }
+
+    /// Assert `choice` has the fields every OpenAI choice has
+    fn assert_choice(choice: &Value, body_key: &str) {
+        assert_eq!(choice["index"], 0);
+        assert!(choice[body_key].is_object());
+        assert!(choice.get("logprobs").is_some_and(Value::is_null));
+        assert!(choice.get("finish_reason").is_some());
+    }
+
+    #[test]
+    fn test_request_translation() {
+        let call = request(json!({
+            "model": "m",
+            "messages": [
+                {"role": "developer", "content": "Be brief."},
+                {"role": "user", "content": [{"type": "text", "text": "Hi"}]}
+            ],
+            "max_tokens": 10,
+            "max_completion_tokens": 20,
+            "stop": "###",
+            "user": "someone",
+            "seed": 7
+        }))
+        .unwrap();
+        assert_eq!(call.messages[0].role, "system");
+        assert_eq!(call.messages[1].content, "Hi");
+        assert_eq!(call.params.max_tokens, 20);
+        assert_eq!(call.params.stop, vec!["###"]);
+        assert_eq!(call.params.temperature, 0.7);
+    }
+
+    #[test]
+    fn test_unsupported_fields_are_rejected() {
+        for body in [
+            json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}], "tools": []}),
+            json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}], "n": 2}),
+            json!({"model": "m", "messages": [{"role": "tool", "content": "{}"}]}),
+            json!({"model": "m", "messages": [{"role": "user", "content": [{"type": "image_url"}]}]}),
+            json!({"model": "m", "messages": []}),
+        ] {
+            assert!(
+                matches!(request(body.clone()), Err(InferenceError::BadRequest(_))),
+                "expected {body} to be rejected"
+            );
+        }
+        // Explicit nulls are the same as leaving a field out
+        assert!(
+            request(json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}], "tools": null}))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_completion_matches_openai_schema() {
+        let response = llm::ChatResponse {
+            message: llm::ChatMessage {
+                role: "assistant".to_string(),
+                content: "Hello!".to_string(),
+            },
+            model: "m".to_string(),
+            prompt_tokens: 12,
+            tokens_generated: 3,
+            finish_reason: FinishReason::Length,
+        };
+        let body = completion_body("chatcmpl-1", 1_760_000_000, &response);
+
+        assert_eq!(body["object"], "chat.completion");
+        assert_eq!(body["id"], "chatcmpl-1");
+        assert!(body["created"].is_u64());
+        assert_eq!(body["model"], "m");
+        let choice = &body["choices"][0];
+        assert_choice(choice, "message");
+        assert_eq!(choice["message"]["role"], "assistant");
+        assert_eq!(choice["message"]["content"], "Hello!");
+        assert_eq!(choice["finish_reason"], "length");
+        assert_eq!(
+            body["usage"],
+            json!({"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15})
+        );
+    }
+
+    async fn events(text: Vec<Result<StreamChunk, InferenceError>>) -> Vec<String> {
+        sse_events(
+            futures::stream::iter(text),
+            "chatcmpl-1".to_string(),
+            1,
+            "m".to_string(),
+        )
+        .map(|event| String::from_utf8(event.unwrap().to_vec()).unwrap())
+        .collect()
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_stream_matches_openai_schema() {
+        let events = events(vec![
+            Ok(StreamChunk::Text("Hel".to_string())),
+            Ok(StreamChunk::Text("lo".to_string())),
+        ])
+        .await;
+        assert_eq!(events.last().unwrap(), "data: [DONE]\n\n");
+
+        let chunks: Vec<Value> = events[..events.len() - 1]
+            .iter()
+            .map(|event| {
+                let data = event.strip_prefix("data: ").unwrap().trim_end();
+                serde_json::from_str(data).unwrap()
+            })
+            .collect();
+        for chunk in &chunks {
+            assert_eq!(chunk["object"], "chat.completion.chunk");
+            assert_eq!(chunk["id"], "chatcmpl-1");
+            assert_eq!(chunk["model"], "m");
+            assert_choice(&chunk["choices"][0], "delta");
+        }
+
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        let content: String = chunks
+            .iter()
+            .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str())
+            .collect();
+        assert_eq!(content, "Hello");
+        let last = &chunks.last().unwrap()["choices"][0];
+        assert_eq!(last["delta"], json!({}));
+        assert_eq!(last["finish_reason"], "stop");
+        assert!(
+            chunks[..chunks.len() - 1]
+                .iter()
+                .all(|chunk| chunk["choices"][0]["finish_reason"].is_null())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_reports_length_at_max_tokens() {
+        let events = events(vec![
+            Ok(StreamChunk::Text("Hel".to_string())),
+            Ok(StreamChunk::Finished(FinishReason::Length)),
+        ])
+        .await;
+        let last: Value = serde_json::from_str(
+            events[events.len() - 2]
+                .strip_prefix("data: ")
+                .unwrap()
+                .trim_end(),
+        )
+        .unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "length");
+        assert_eq!(events.last().unwrap(), "data: [DONE]\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_an_openai_error_event() {
+        let events = events(vec![
+            Ok(StreamChunk::Text("Hi".to_string())),
+            Err(InferenceError::Generation("boom".to_string())),
+        ])
+        .await;
+        let last: Value = serde_json::from_str(
+            events
+                .last()
+                .unwrap()
+                .strip_prefix("data: ")
+                .unwrap()
+                .trim_end(),
+        )
+        .unwrap();
+        assert_eq!(last["error"]["type"], "server_error");
+        assert!(last["error"]["message"].as_str().unwrap().contains("boom"));
+        assert!(!events.iter().any(|event| event.contains("[DONE]")));
+    }
+}
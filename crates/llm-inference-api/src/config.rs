@@ -5,9 +5,12 @@
 //! Supports TLS/SSL for secure deployments using shared core configuration.
 
 use anyhow::{Context, Result};
-use semantic_explorer_core::config::TlsConfig;
+use semantic_explorer_core::config::{ConfigValidationError, TlsConfig};
 use std::env;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::llm::GenerationParams;
 
 /// LLM Inference API configuration
 #[derive(Debug, Clone)]
@@ -73,6 +76,14 @@ pub struct GenerationConfig {
     pub default_max_tokens: usize,
     /// Hard limit on maximum tokens (safety)
     pub max_tokens_limit: usize,
+    /// Default top-k sampling cutoff (unset: no cutoff)
+    pub default_top_k: Option<usize>,
+    /// Default repetition penalty (unset: none)
+    pub default_repetition_penalty: Option<f32>,
+    /// Default frequency penalty (unset: none)
+    pub default_frequency_penalty: Option<f32>,
+    /// Default presence penalty (unset: none)
+    pub default_presence_penalty: Option<f32>,
 }
 
 /// Observability configuration
@@ -112,6 +123,7 @@ impl LlmInferenceConfig {
             observability: ObservabilityConfig::from_env()?,
             tls: TlsConfig::from_env()?,
         };
+        ConfigValidationError::check(config.validate())?;
 
         // Log configuration for airgapped deployments
         config.log_environment_config();
@@ -119,6 +131,25 @@ impl LlmInferenceConfig {
         Ok(config)
     }
 
+    /// Every cross-field problem of the configuration
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = self.tls.validate();
+        if self.models.max_concurrent_requests == 0 {
+            problems.push("LLM_MAX_CONCURRENT_REQUESTS must be at least 1".to_string());
+        }
+        if self.models.enable_isq && self.models.isq_type.is_none() {
+            problems.push("LLM_ISQ_TYPE is required when LLM_ENABLE_ISQ=true".to_string());
+        }
+        let generation = &self.generation;
+        if generation.default_max_tokens > generation.max_tokens_limit {
+            problems.push(format!(
+                "LLM_DEFAULT_MAX_TOKENS ({}) must not exceed LLM_MAX_TOKENS_LIMIT ({})",
+                generation.default_max_tokens, generation.max_tokens_limit
+            ));
+        }
+        problems
+    }
+
     /// Log environment configuration for debugging airgapped deployments
     fn log_environment_config(&self) {
         // Log HF_HOME if configured
@@ -250,12 +281,46 @@ impl GenerationConfig {
             .parse()
             .context("LLM_MAX_TOKENS_LIMIT must be a number")?;
 
-        Ok(Self {
+        let default_top_k = optional_env("LLM_DEFAULT_TOP_K")
+            .context("LLM_DEFAULT_TOP_K must be a positive integer")?;
+        let default_repetition_penalty = optional_env("LLM_DEFAULT_REPETITION_PENALTY")
+            .context("LLM_DEFAULT_REPETITION_PENALTY must be a number")?;
+        let default_frequency_penalty = optional_env("LLM_DEFAULT_FREQUENCY_PENALTY")
+            .context("LLM_DEFAULT_FREQUENCY_PENALTY must be a number")?;
+        let default_presence_penalty = optional_env("LLM_DEFAULT_PRESENCE_PENALTY")
+            .context("LLM_DEFAULT_PRESENCE_PENALTY must be a number")?;
+
+        let config = Self {
             default_temperature,
             default_top_p,
             default_max_tokens,
             max_tokens_limit,
-        })
+            default_top_k,
+            default_repetition_penalty,
+            default_frequency_penalty,
+            default_presence_penalty,
+        };
+        // Requests fall back to these, so reject defaults no request could use
+        config
+            .default_params()
+            .validate()
+            .map_err(anyhow::Error::msg)
+            .context("Invalid LLM_DEFAULT_* sampling setting")?;
+        Ok(config)
+    }
+
+    /// Generation parameters for a request that sets none of its own
+    pub fn default_params(&self) -> GenerationParams {
+        GenerationParams {
+            temperature: self.default_temperature,
+            top_p: self.default_top_p,
+            max_tokens: self.default_max_tokens,
+            top_k: self.default_top_k,
+            repetition_penalty: self.default_repetition_penalty,
+            frequency_penalty: self.default_frequency_penalty,
+            presence_penalty: self.default_presence_penalty,
+            stop: Vec::new(),
+        }
     }
 
     /// Validate and clamp temperature to valid range
@@ -295,6 +360,19 @@ impl ObservabilityConfig {
     }
 }
 
+/// Parse an optional environment variable, `None` when unset
+fn optional_env<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    env::var(name)
+        .ok()
+        .map(|value| value.parse())
+        .transpose()
+        .map_err(Into::into)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +435,10 @@ mod tests {
             default_top_p: 0.9,
             default_max_tokens: 512,
             max_tokens_limit: 4096,
+            default_top_k: None,
+            default_repetition_penalty: None,
+            default_frequency_penalty: None,
+            default_presence_penalty: None,
         };
 
         // Test temperature clamping
@@ -12,8 +12,10 @@ pub enum InferenceError {
     ModelLoad(String),
     /// Text generation failed
     Generation(String),
-    /// Unsupported model
-    UnsupportedModel(String),
+    /// Model the service doesn't know (404)
+    ModelNotFound(String),
+    /// Known model excluded by the allowed models configuration (403)
+    ModelNotAllowed(String),
     /// Bad request
     BadRequest(String),
     /// Internal error
@@ -27,7 +29,8 @@ impl fmt::Display for InferenceError {
         match self {
             InferenceError::ModelLoad(msg) => write!(f, "Failed to load model: {}", msg),
             InferenceError::Generation(msg) => write!(f, "Text generation failed: {}", msg),
-            InferenceError::UnsupportedModel(msg) => write!(f, "Unsupported model: {}", msg),
+            InferenceError::ModelNotFound(msg) => write!(f, "Model not found: {}", msg),
+            InferenceError::ModelNotAllowed(msg) => write!(f, "Model not allowed: {}", msg),
             InferenceError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             InferenceError::Internal(msg) => write!(f, "Internal error: {}", msg),
             InferenceError::ServiceUnavailable(msg) => write!(f, "Service unavailable: {}", msg),
@@ -47,7 +50,8 @@ impl ResponseError for InferenceError {
     fn status_code(&self) -> StatusCode {
         match self {
             InferenceError::BadRequest(_) => StatusCode::BAD_REQUEST,
-            InferenceError::UnsupportedModel(_) => StatusCode::BAD_REQUEST,
+            InferenceError::ModelNotFound(_) => StatusCode::NOT_FOUND,
+            InferenceError::ModelNotAllowed(_) => StatusCode::FORBIDDEN,
             InferenceError::ModelLoad(_) => StatusCode::SERVICE_UNAVAILABLE,
             InferenceError::Generation(_) => StatusCode::INTERNAL_SERVER_ERROR,
             InferenceError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -59,7 +63,8 @@ impl ResponseError for InferenceError {
         let code = match self {
             InferenceError::ModelLoad(_) => "MODEL_LOAD_ERROR",
             InferenceError::Generation(_) => "GENERATION_ERROR",
-            InferenceError::UnsupportedModel(_) => "UNSUPPORTED_MODEL",
+            InferenceError::ModelNotFound(_) => "MODEL_NOT_FOUND",
+            InferenceError::ModelNotAllowed(_) => "MODEL_NOT_ALLOWED",
             InferenceError::BadRequest(_) => "BAD_REQUEST",
             InferenceError::Internal(_) => "INTERNAL_ERROR",
             InferenceError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
@@ -81,3 +86,32 @@ impl ResponseError for InferenceError {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_model_errors_have_distinct_status_and_code() {
+        for (error, status, code) in [
+            (
+                InferenceError::ModelNotFound("acme/missing-GGUF".to_string()),
+                StatusCode::NOT_FOUND,
+                "MODEL_NOT_FOUND",
+            ),
+            (
+                InferenceError::ModelNotAllowed("mistralai/Mistral-7B-v0.1".to_string()),
+                StatusCode::FORBIDDEN,
+                "MODEL_NOT_ALLOWED",
+            ),
+        ] {
+            let response = error.error_response();
+            assert_eq!(response.status(), status);
+            let body = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["code"], code);
+        }
+    }
+}
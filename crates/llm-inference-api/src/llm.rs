@@ -5,30 +5,34 @@
 //! - Lazy loading of models on first request
 //! - Text generation with configurable parameters
 //! - Streaming text generation support
+//! - Stop sequences, detected across token boundaries when streaming
 //! - Chat completion with message history
 //! - Backpressure control via semaphore with queue timeout
+//! - Cancellation of streams whose client disconnected
 //! - GPU memory pressure monitoring
 //! - FP8 KV cache support for Hopper+ GPUs (H100/H200)
 //! - Prefix caching for multi-turn and RAG workloads
 
+use futures::StreamExt;
 use futures::stream::Stream;
 use mistralrs::{
     GgufModelBuilder, IsqType, MemoryGpuConfig, Model as MistralRsModel, PagedAttentionConfig,
-    PagedAttentionMetaBuilder, RequestBuilder, TextMessageRole, TextMessages, TextModelBuilder,
-    TokenSource, core::PagedCacheType as MistralPagedCacheType,
+    PagedAttentionMetaBuilder, RequestBuilder, SamplingParams, StopTokens, TextMessageRole,
+    TextMessages, TextModelBuilder, TokenSource, core::PagedCacheType as MistralPagedCacheType,
 };
-use semantic_explorer_core::observability::gpu_monitor;
+use semantic_explorer_core::observability::{CancellationGuard, gpu_monitor};
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 use tokio::time::timeout;
 use tracing::{debug, info, warn};
 
 use crate::config::{GenerationConfig, ModelConfig, PagedCacheType};
 use crate::errors::InferenceError;
+use crate::stop;
 
 /// Type alias for the LLM model cache
 /// Using tokio::sync::Mutex for async compatibility
@@ -98,6 +102,28 @@ pub fn try_acquire_permit() -> Option<tokio::sync::OwnedSemaphorePermit> {
         .and_then(|sem| sem.clone().try_acquire_owned().ok())
 }
 
+/// Hold `permit` for as long as a generation stream runs rather than just
+/// until its handler returns. When the client disconnects, actix drops the
+/// response body and with it `stream`: mistral.rs stops generating once the
+/// sequence's response channel is closed, the permit is released and `guard`
+/// counts the request as cancelled.
+pub fn guard_stream<S>(
+    mut stream: S,
+    permit: OwnedSemaphorePermit,
+    guard: CancellationGuard,
+) -> impl Stream<Item = S::Item>
+where
+    S: Stream + Unpin,
+{
+    async_stream::stream! {
+        let _permit = permit;
+        while let Some(item) = stream.next().await {
+            yield item;
+        }
+        guard.complete();
+    }
+}
+
 /// Get current available permits (for monitoring)
 pub fn available_permits() -> usize {
     LLM_SEMAPHORE
@@ -391,7 +417,7 @@ async fn load_gguf_model(
 
         // Check if it's a 404 error (file not found)
         if error_msg.contains("404") || error_msg.contains("Not Found") {
-            InferenceError::ModelLoad(format!(
+            InferenceError::ModelNotFound(format!(
                 "GGUF file not found: {}\n\n\
                  Attempted to load:\n\
                  - Repository: {}\n\
@@ -554,6 +580,62 @@ pub struct GenerationParams {
     pub temperature: f32,
     pub top_p: f32,
     pub max_tokens: usize,
+    pub top_k: Option<usize>,
+    pub repetition_penalty: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    /// Strings that end generation; trimmed from the output
+    pub stop: Vec<String>,
+}
+
+impl GenerationParams {
+    /// Check the sampling parameters against the ranges mistral.rs accepts
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.top_p) {
+            return Err(format!(
+                "top_p must be between 0.0 and 1.0, got {}",
+                self.top_p
+            ));
+        }
+        if self.top_k == Some(0) {
+            return Err("top_k must be at least 1".to_string());
+        }
+        if let Some(penalty) = self.repetition_penalty
+            && !(penalty > 0.0 && penalty <= 2.0)
+        {
+            return Err(format!(
+                "repetition_penalty must be greater than 0.0 and at most 2.0, got {penalty}"
+            ));
+        }
+        for (name, penalty) in [
+            ("frequency_penalty", self.frequency_penalty),
+            ("presence_penalty", self.presence_penalty),
+        ] {
+            if let Some(penalty) = penalty
+                && !(-2.0..=2.0).contains(&penalty)
+            {
+                return Err(format!(
+                    "{name} must be between -2.0 and 2.0, got {penalty}"
+                ));
+            }
+        }
+        stop::validate(&self.stop)
+    }
+}
+
+/// mistral.rs sampling parameters for a request
+fn sampling_params(params: &GenerationParams, gen_config: &GenerationConfig) -> SamplingParams {
+    SamplingParams {
+        temperature: Some(gen_config.validate_temperature(params.temperature) as f64),
+        top_k: params.top_k,
+        top_p: Some(gen_config.validate_top_p(params.top_p) as f64),
+        frequency_penalty: params.frequency_penalty,
+        presence_penalty: params.presence_penalty,
+        repetition_penalty: params.repetition_penalty,
+        max_len: Some(gen_config.validate_max_tokens(params.max_tokens)),
+        stop_toks: (!params.stop.is_empty()).then(|| StopTokens::Seqs(params.stop.clone())),
+        ..SamplingParams::deterministic()
+    }
 }
 
 /// Response from text generation
@@ -578,6 +660,26 @@ pub enum FinishReason {
     Error,
 }
 
+impl FinishReason {
+    /// Ours for a mistral.rs chat `finish_reason`, whose "stop" means the
+    /// model ended its turn rather than hitting a stop sequence
+    fn from_chat(reason: &str) -> Self {
+        match reason {
+            "length" => FinishReason::Length,
+            _ => FinishReason::Eos,
+        }
+    }
+}
+
+/// An item of a generation stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamChunk {
+    /// Generated text
+    Text(String),
+    /// Why generation ended; the last item when the model reports it
+    Finished(FinishReason),
+}
+
 impl std::fmt::Display for FinishReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -603,7 +705,7 @@ pub async fn generate_text(
 
     // Check if model is allowed
     if !model_config.allowed_models.contains(&model_id.to_string()) {
-        return Err(InferenceError::UnsupportedModel(format!(
+        return Err(InferenceError::ModelNotAllowed(format!(
             "Model {} is not in the allowed models list",
             model_id
         )));
@@ -620,10 +722,6 @@ pub async fn generate_text(
         ));
     }
 
-    // Validate parameters
-    let temperature = gen_config.validate_temperature(params.temperature);
-    let top_p = gen_config.validate_top_p(params.top_p);
-    let max_tokens = gen_config.validate_max_tokens(params.max_tokens);
     let prompt_len = prompt.len();
 
     // Get model from cache
@@ -633,9 +731,7 @@ pub async fn generate_text(
 
     // Generate text using mistral.rs
     let request = RequestBuilder::new()
-        .set_sampler_max_len(max_tokens)
-        .set_sampler_temperature(temperature as f64)
-        .set_sampler_topp(top_p as f64)
+        .set_sampling(sampling_params(&params, gen_config))
         .add_message(TextMessageRole::System, "You are a helpful assistant.")
         .add_message(TextMessageRole::User, &prompt);
 
@@ -708,7 +804,8 @@ pub async fn generate_text(
 
     // Try to extract content from message.content or reasoning_content (for Harmony format models)
     let message = &response.choices[0].message;
-    let text = if let Some(content) = message.content.as_ref().filter(|c| !c.trim().is_empty()) {
+    let mut text = if let Some(content) = message.content.as_ref().filter(|c| !c.trim().is_empty())
+    {
         content.clone()
     } else if let Some(reasoning) = message
         .reasoning_content
@@ -736,13 +833,16 @@ pub async fn generate_text(
     };
 
     // Determine finish reason
-    let finish_reason = match response.choices[0].finish_reason.as_str() {
+    let mut finish_reason = match response.choices[0].finish_reason.as_str() {
         "stop" => FinishReason::Stop,
         "eos" | "eos_token" => FinishReason::Eos,
         "length" => FinishReason::Length,
         "error" => FinishReason::Error,
         _ => FinishReason::Eos,
     };
+    if stop::truncate(&mut text, &params.stop) {
+        finish_reason = FinishReason::Stop;
+    }
 
     Ok(GenerationResponse {
         text,
@@ -764,6 +864,7 @@ pub struct ChatMessage {
 pub struct ChatResponse {
     pub message: ChatMessage,
     pub model: String,
+    pub prompt_tokens: usize,
     pub tokens_generated: usize,
     pub finish_reason: FinishReason,
 }
@@ -782,7 +883,7 @@ pub async fn chat_completion(
 
     // Check if model is allowed
     if !model_config.allowed_models.contains(&model_id.to_string()) {
-        return Err(InferenceError::UnsupportedModel(format!(
+        return Err(InferenceError::ModelNotAllowed(format!(
             "Model {} is not in the allowed models list",
             model_id
         )));
@@ -799,10 +900,6 @@ pub async fn chat_completion(
         ));
     }
 
-    // Validate parameters
-    let temperature = gen_config.validate_temperature(params.temperature);
-    let top_p = gen_config.validate_top_p(params.top_p);
-    let max_tokens = gen_config.validate_max_tokens(params.max_tokens);
     let message_count = messages.len();
     let total_chars: usize = messages.iter().map(|m| m.content.len()).sum();
 
@@ -827,10 +924,8 @@ pub async fn chat_completion(
     }
 
     // Build request with parameters
-    let request = RequestBuilder::from(text_messages)
-        .set_sampler_max_len(max_tokens)
-        .set_sampler_temperature(temperature as f64)
-        .set_sampler_topp(top_p as f64);
+    let request =
+        RequestBuilder::from(text_messages).set_sampling(sampling_params(&params, gen_config));
 
     // Generate chat response using mistral.rs
     let gen_start = Instant::now();
@@ -870,7 +965,7 @@ pub async fn chat_completion(
 
     // Try to extract content from message.content or reasoning_content (for Harmony format models)
     let message = &response.choices[0].message;
-    let content = if let Some(c) = message.content.as_ref().filter(|c| !c.trim().is_empty()) {
+    let mut content = if let Some(c) = message.content.as_ref().filter(|c| !c.trim().is_empty()) {
         c.clone()
     } else if let Some(reasoning) = message
         .reasoning_content
@@ -897,11 +992,10 @@ pub async fn chat_completion(
         )));
     };
 
-    let finish_reason = match response.choices[0].finish_reason.as_str() {
-        "stop" => FinishReason::Eos,
-        "length" => FinishReason::Length,
-        _ => FinishReason::Eos,
-    };
+    let mut finish_reason = FinishReason::from_chat(&response.choices[0].finish_reason);
+    if stop::truncate(&mut content, &params.stop) {
+        finish_reason = FinishReason::Stop;
+    }
 
     Ok(ChatResponse {
         message: ChatMessage {
@@ -909,6 +1003,7 @@ pub async fn chat_completion(
             content,
         },
         model: model_id.to_string(),
+        prompt_tokens: response.usage.prompt_tokens,
         tokens_generated: response.usage.completion_tokens,
         finish_reason,
     })
@@ -916,27 +1011,24 @@ pub async fn chat_completion(
 
 /// Chat completion with streaming
 ///
-/// Returns a stream of text chunks as they are generated based on conversation history.
+/// Returns a stream of text chunks as they are generated based on conversation history,
+/// followed by why generation ended.
 pub async fn chat_completion_stream(
     model_id: &str,
     messages: Vec<ChatMessage>,
     params: GenerationParams,
     model_config: &ModelConfig,
     gen_config: &GenerationConfig,
-) -> Result<Pin<Box<dyn Stream<Item = Result<String, InferenceError>> + Send>>, InferenceError> {
+) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, InferenceError>> + Send>>, InferenceError>
+{
     // Check if model is allowed
     if !model_config.allowed_models.contains(&model_id.to_string()) {
-        return Err(InferenceError::UnsupportedModel(format!(
+        return Err(InferenceError::ModelNotAllowed(format!(
             "Model {} is not in the allowed models list",
             model_id
         )));
     }
 
-    // Validate parameters
-    let temperature = gen_config.validate_temperature(params.temperature);
-    let top_p = gen_config.validate_top_p(params.top_p);
-    let max_tokens = gen_config.validate_max_tokens(params.max_tokens);
-
     // Get model from cache
     let model_arc = get_or_load_model(model_id, model_config).await?;
 
@@ -956,10 +1048,8 @@ pub async fn chat_completion_stream(
     }
 
     // Build request with parameters
-    let request = RequestBuilder::from(text_messages)
-        .set_sampler_max_len(max_tokens)
-        .set_sampler_temperature(temperature as f64)
-        .set_sampler_topp(top_p as f64);
+    let request =
+        RequestBuilder::from(text_messages).set_sampling(sampling_params(&params, gen_config));
 
     // Clone the Arc to move into the stream
     let model_for_stream = model_arc.clone();
@@ -976,14 +1066,23 @@ pub async fn chat_completion_stream(
             match response {
                 mistralrs::Response::Chunk(chunk_response) => {
                     // Extract text from the chunk delta
-                    if let Some(choice) = chunk_response.choices.first()
-                        && let Some(content) = &choice.delta.content
-                    {
-                        yield content.clone();
+                    let Some(choice) = chunk_response.choices.first() else {
+                        continue;
+                    };
+                    if let Some(content) = &choice.delta.content {
+                        yield StreamChunk::Text(content.clone());
+                    }
+                    // The last chunk says why generation ended
+                    if let Some(reason) = &choice.finish_reason {
+                        yield StreamChunk::Finished(FinishReason::from_chat(reason));
+                        break;
                     }
                 }
-                mistralrs::Response::Done(_) => {
+                mistralrs::Response::Done(response) => {
                     // Stream completed successfully
+                    if let Some(choice) = response.choices.first() {
+                        yield StreamChunk::Finished(FinishReason::from_chat(&choice.finish_reason));
+                    }
                     break;
                 }
                 mistralrs::Response::ModelError(msg, _) => {
@@ -1003,7 +1102,8 @@ pub async fn chat_completion_stream(
         }
     };
 
-    Ok(Box::pin(text_stream))
+    // Hold back text that may start a stop sequence and end at the first one
+    Ok(Box::pin(stop::filter_stream(text_stream, params.stop)))
 }
 
 /// Merge consecutive messages with the same role to ensure proper alternation
@@ -1068,7 +1168,7 @@ pub async fn text_completion(
 
     // Check if model is allowed
     if !model_config.allowed_models.contains(&model_id.to_string()) {
-        return Err(InferenceError::UnsupportedModel(format!(
+        return Err(InferenceError::ModelNotAllowed(format!(
             "Model {} is not in the allowed models list",
             model_id
         )));
@@ -1085,11 +1185,6 @@ pub async fn text_completion(
         ));
     }
 
-    // Validate parameters
-    let temperature = gen_config.validate_temperature(params.temperature);
-    let top_p = gen_config.validate_top_p(params.top_p);
-    let max_tokens = gen_config.validate_max_tokens(params.max_tokens);
-
     // Build the completion prompt, incorporating suffix if provided
     let completion_prompt = if let Some(ref suf) = suffix {
         // Fill-in-middle format: use a structured prompt that signals FIM
@@ -1111,9 +1206,7 @@ pub async fn text_completion(
     // Generate completion using mistral.rs
     // For completion, we use a minimal system prompt to avoid influencing the output
     let request = RequestBuilder::new()
-        .set_sampler_max_len(max_tokens)
-        .set_sampler_temperature(temperature as f64)
-        .set_sampler_topp(top_p as f64)
+        .set_sampling(sampling_params(&params, gen_config))
         .add_message(TextMessageRole::User, &completion_prompt);
 
     let gen_start = Instant::now();
@@ -1132,7 +1225,7 @@ pub async fn text_completion(
 
     // Extract completion text
     let message = &response.choices[0].message;
-    let text = message
+    let mut text = message
         .content
         .as_ref()
         .filter(|c| !c.trim().is_empty())
@@ -1147,13 +1240,16 @@ pub async fn text_completion(
         .unwrap_or_default();
 
     // Determine finish reason
-    let finish_reason = match response.choices[0].finish_reason.as_str() {
+    let mut finish_reason = match response.choices[0].finish_reason.as_str() {
         "stop" => FinishReason::Stop,
         "eos" | "eos_token" => FinishReason::Eos,
         "length" => FinishReason::Length,
         "error" => FinishReason::Error,
         _ => FinishReason::Eos,
     };
+    if stop::truncate(&mut text, &params.stop) {
+        finish_reason = FinishReason::Stop;
+    }
 
     let total_time = total_start.elapsed();
 
@@ -1178,7 +1274,8 @@ pub async fn text_completion(
 
 /// Text/code completion with streaming
 ///
-/// Returns a stream of text chunks as they are generated.
+/// Returns a stream of text chunks as they are generated, followed by why
+/// generation ended.
 /// Supports optional suffix for fill-in-middle completion.
 pub async fn text_completion_stream(
     model_id: &str,
@@ -1187,20 +1284,16 @@ pub async fn text_completion_stream(
     params: GenerationParams,
     model_config: &ModelConfig,
     gen_config: &GenerationConfig,
-) -> Result<Pin<Box<dyn Stream<Item = Result<String, InferenceError>> + Send>>, InferenceError> {
+) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, InferenceError>> + Send>>, InferenceError>
+{
     // Check if model is allowed
     if !model_config.allowed_models.contains(&model_id.to_string()) {
-        return Err(InferenceError::UnsupportedModel(format!(
+        return Err(InferenceError::ModelNotAllowed(format!(
             "Model {} is not in the allowed models list",
             model_id
         )));
     }
 
-    // Validate parameters
-    let temperature = gen_config.validate_temperature(params.temperature);
-    let top_p = gen_config.validate_top_p(params.top_p);
-    let max_tokens = gen_config.validate_max_tokens(params.max_tokens);
-
     // Build the completion prompt, incorporating suffix if provided
     let completion_prompt = if let Some(ref suf) = suffix {
         format!(
@@ -1216,9 +1309,7 @@ pub async fn text_completion_stream(
 
     // Generate completion stream using mistral.rs
     let request = RequestBuilder::new()
-        .set_sampler_max_len(max_tokens)
-        .set_sampler_temperature(temperature as f64)
-        .set_sampler_topp(top_p as f64)
+        .set_sampling(sampling_params(&params, gen_config))
         .add_message(TextMessageRole::User, &completion_prompt);
 
     // Clone the Arc to move into the stream
@@ -1236,14 +1327,23 @@ pub async fn text_completion_stream(
             match response {
                 mistralrs::Response::Chunk(chunk_response) => {
                     // Extract text from the chunk delta
-                    if let Some(choice) = chunk_response.choices.first()
-                        && let Some(content) = &choice.delta.content
-                    {
-                        yield content.clone();
+                    let Some(choice) = chunk_response.choices.first() else {
+                        continue;
+                    };
+                    if let Some(content) = &choice.delta.content {
+                        yield StreamChunk::Text(content.clone());
+                    }
+                    // The last chunk says why generation ended
+                    if let Some(reason) = &choice.finish_reason {
+                        yield StreamChunk::Finished(FinishReason::from_chat(reason));
+                        break;
                     }
                 }
-                mistralrs::Response::Done(_) => {
+                mistralrs::Response::Done(response) => {
                     // Stream completed successfully
+                    if let Some(choice) = response.choices.first() {
+                        yield StreamChunk::Finished(FinishReason::from_chat(&choice.finish_reason));
+                    }
                     break;
                 }
                 mistralrs::Response::ModelError(msg, _) => {
@@ -1263,7 +1363,8 @@ pub async fn text_completion_stream(
         }
     };
 
-    Ok(Box::pin(text_stream))
+    // Hold back text that may start a stop sequence and end at the first one
+    Ok(Box::pin(stop::filter_stream(text_stream, params.stop)))
 }
 
 /// Check if the LLM service is ready (models loaded)
@@ -1291,10 +1392,55 @@ mod tests {
             temperature: 0.7,
             top_p: 0.9,
             max_tokens: 100,
+            top_k: None,
+            repetition_penalty: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stop: Vec::new(),
         };
 
         assert_eq!(params.temperature, 0.7);
         assert_eq!(params.top_p, 0.9);
         assert_eq!(params.max_tokens, 100);
     }
+
+    #[tokio::test]
+    async fn test_guard_stream_releases_slot_on_disconnect() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let (closed_tx, closed_rx) = tokio::sync::oneshot::channel::<()>();
+        // Stands in for the mistral.rs response stream
+        let tokens = futures::stream::iter(["a", "b", "c"]).map(move |token| {
+            let _ = &closed_tx;
+            token
+        });
+
+        let permit = semaphore.clone().try_acquire_owned().unwrap();
+        let mut body = Box::pin(guard_stream(
+            tokens,
+            permit,
+            CancellationGuard::new("chat_stream", "test-model"),
+        ));
+        assert_eq!(body.next().await, Some("a"));
+        // The slot stays taken while the stream is being sent
+        assert_eq!(semaphore.available_permits(), 0);
+
+        // The client disconnects mid-stream
+        drop(body);
+        assert_eq!(semaphore.available_permits(), 1);
+        // The generation stream was dropped rather than run to completion
+        assert!(closed_rx.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_guard_stream_passes_every_item() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore.clone().try_acquire_owned().unwrap();
+        let body = guard_stream(
+            futures::stream::iter([1, 2, 3]),
+            permit,
+            CancellationGuard::new("chat_stream", "test-model"),
+        );
+        assert_eq!(body.collect::<Vec<_>>().await, vec![1, 2, 3]);
+        assert_eq!(semaphore.available_permits(), 1);
+    }
 }
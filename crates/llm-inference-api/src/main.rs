@@ -10,6 +10,7 @@ mod llm;
 mod model_preload;
 mod models;
 mod observability;
+mod stop;
 
 use actix_cors::Cors;
 use actix_web::{App, HttpServer, middleware::Compress, web};
@@ -34,7 +35,8 @@ use config::LlmInferenceConfig;
         (name = "generation", description = "Single prompt text generation"),
         (name = "chat", description = "Chat with message history"),
         (name = "completions", description = "Text and code completions with optional fill-in-middle"),
-        (name = "models", description = "Model discovery and listing")
+        (name = "models", description = "Model discovery and listing"),
+        (name = "openai", description = "OpenAI-compatible chat completions")
     )
 )]
 struct ApiDoc;
@@ -136,6 +138,8 @@ async fn main() -> Result<()> {
             // Completion endpoints (code/text completion with optional fill-in-middle)
             .service(api::completions::completion)
             .service(api::completions::completion_stream)
+            // OpenAI-compatible chat completions for existing client libraries
+            .service(api::openai::chat_completions)
             // Swagger UI
             .openapi_service(|api| {
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api/openapi.json", api)
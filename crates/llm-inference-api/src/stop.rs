@@ -0,0 +1,283 @@
+//! Stop sequences.
+//!
+//! mistral.rs stops generating once it produces one of a request's `stop`
+//! strings; this module trims the stop string (and anything after it) from
+//! the output. Streams hold back text that could still turn into a stop
+//! string, so a stop split across several tokens never reaches the client.
+
+use futures::StreamExt;
+use futures::stream::Stream;
+
+use crate::errors::InferenceError;
+use crate::llm::{FinishReason, StreamChunk};
+
+/// Most stop strings one request may set
+pub const MAX_STOP_SEQUENCES: usize = 8;
+
+/// Longest stop string, in characters
+pub const MAX_STOP_SEQUENCE_CHARS: usize = 64;
+
+/// Check a request's stop strings against the limits
+pub fn validate(stop: &[String]) -> Result<(), String> {
+    if stop.len() > MAX_STOP_SEQUENCES {
+        return Err(format!(
+            "stop accepts at most {MAX_STOP_SEQUENCES} sequences, got {}",
+            stop.len()
+        ));
+    }
+    for sequence in stop {
+        if sequence.is_empty() {
+            return Err("stop sequences must not be empty".to_string());
+        }
+        let chars = sequence.chars().count();
+        if chars > MAX_STOP_SEQUENCE_CHARS {
+            return Err(format!(
+                "stop sequences must be at most {MAX_STOP_SEQUENCE_CHARS} characters, got {chars}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Byte offset of the earliest stop string in `text`
+pub fn find(text: &str, stop: &[String]) -> Option<usize> {
+    stop.iter()
+        .filter_map(|sequence| text.find(sequence.as_str()))
+        .min()
+}
+
+/// Cut `text` at its first stop string. Returns whether it had one.
+pub fn truncate(text: &mut String, stop: &[String]) -> bool {
+    match find(text, stop) {
+        Some(end) => {
+            text.truncate(end);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Length of the longest suffix of `text` that starts a stop string and so
+/// could still complete one with later tokens
+fn partial_suffix_len(text: &str, stop: &[String]) -> usize {
+    stop.iter()
+        .flat_map(|sequence| {
+            sequence
+                .char_indices()
+                .skip(1)
+                .map(|(end, _)| &sequence[..end])
+                .filter(|prefix| text.ends_with(prefix))
+                .map(str::len)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Buffers streamed text until it is known not to be part of a stop string
+#[derive(Debug)]
+pub struct StopSequenceFilter {
+    stop: Vec<String>,
+    pending: String,
+}
+
+/// What a chunk pushed into a [`StopSequenceFilter`] released
+#[derive(Debug, PartialEq, Eq)]
+pub enum Scan {
+    /// Text that is safe to send; more may follow
+    Emit(String),
+    /// Text before the stop string; generation should end
+    Stopped(String),
+}
+
+impl StopSequenceFilter {
+    pub fn new(stop: Vec<String>) -> Self {
+        Self {
+            stop,
+            pending: String::new(),
+        }
+    }
+
+    /// Add a chunk, releasing whatever can no longer start a stop string
+    pub fn push(&mut self, chunk: &str) -> Scan {
+        self.pending.push_str(chunk);
+        if let Some(end) = find(&self.pending, &self.stop) {
+            self.pending.truncate(end);
+            return Scan::Stopped(std::mem::take(&mut self.pending));
+        }
+        let held = partial_suffix_len(&self.pending, &self.stop);
+        let rest = self.pending.split_off(self.pending.len() - held);
+        Scan::Emit(std::mem::replace(&mut self.pending, rest))
+    }
+
+    /// Text held back when generation ended without a stop string
+    pub fn finish(self) -> String {
+        self.pending
+    }
+}
+
+/// Apply `stop` to a stream of generated text. The stream ends at the first
+/// stop string, dropping (and so cancelling) the generation behind it, with
+/// [`FinishReason::Stop`].
+pub fn filter_stream<S>(
+    stream: S,
+    stop: Vec<String>,
+) -> impl Stream<Item = Result<StreamChunk, InferenceError>>
+where
+    S: Stream<Item = Result<StreamChunk, InferenceError>>,
+{
+    async_stream::stream! {
+        let mut stream = std::pin::pin!(stream);
+        if stop.is_empty() {
+            while let Some(item) = stream.next().await {
+                yield item;
+            }
+            return;
+        }
+
+        let mut filter = StopSequenceFilter::new(stop);
+        let mut finished = None;
+        while let Some(item) = stream.next().await {
+            let chunk = match item {
+                Ok(StreamChunk::Text(chunk)) => chunk,
+                Ok(StreamChunk::Finished(reason)) => {
+                    finished = Some(reason);
+                    break;
+                }
+                Err(e) => {
+                    yield Err(e);
+                    continue;
+                }
+            };
+            match filter.push(&chunk) {
+                Scan::Emit(text) => {
+                    if !text.is_empty() {
+                        yield Ok(StreamChunk::Text(text));
+                    }
+                }
+                Scan::Stopped(text) => {
+                    if !text.is_empty() {
+                        yield Ok(StreamChunk::Text(text));
+                    }
+                    yield Ok(StreamChunk::Finished(FinishReason::Stop));
+                    return;
+                }
+            }
+        }
+        let rest = filter.finish();
+        if !rest.is_empty() {
+            yield Ok(StreamChunk::Text(rest));
+        }
+        if let Some(reason) = finished {
+            yield Ok(StreamChunk::Finished(reason));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(sequences: &[&str]) -> Vec<String> {
+        sequences.iter().map(|s| s.to_string()).collect()
+    }
+
+    async fn run_chunks(tokens: &[&str], stop: &[&str]) -> Vec<StreamChunk> {
+        let tokens: Vec<Result<StreamChunk, InferenceError>> = tokens
+            .iter()
+            .map(|t| Ok(StreamChunk::Text(t.to_string())))
+            .chain([Ok(StreamChunk::Finished(FinishReason::Length))])
+            .collect();
+        filter_stream(futures::stream::iter(tokens), stops(stop))
+            .map(Result::unwrap)
+            .collect()
+            .await
+    }
+
+    async fn run(tokens: &[&str], stop: &[&str]) -> Vec<String> {
+        run_chunks(tokens, stop)
+            .await
+            .into_iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::Text(text) => Some(text),
+                StreamChunk::Finished(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_truncate_cuts_at_earliest_stop() {
+        let mut text = "answer</out> more END".to_string();
+        assert!(truncate(&mut text, &stops(&["END", "</out>"])));
+        assert_eq!(text, "answer");
+
+        let mut text = "no stop here".to_string();
+        assert!(!truncate(&mut text, &stops(&["END"])));
+        assert_eq!(text, "no stop here");
+    }
+
+    #[tokio::test]
+    async fn test_stream_never_emits_stop_split_across_tokens() {
+        let chunks = run(
+            &["The answer", " is 4", "</", "ou", "t>", " trailing"],
+            &["</out>"],
+        )
+        .await;
+        let output = chunks.concat();
+        assert_eq!(output, "The answer is 4");
+        assert!(!output.contains("</out>"));
+        // Nothing after the stop is sent
+        assert!(chunks.iter().all(|c| !c.contains("trailing")));
+    }
+
+    #[tokio::test]
+    async fn test_stream_reports_why_it_finished() {
+        // A stop string ends the stream as a stop, not as what the model reported
+        let chunks = run_chunks(&["a", "END", "b"], &["END"]).await;
+        assert_eq!(
+            chunks.last(),
+            Some(&StreamChunk::Finished(FinishReason::Stop))
+        );
+
+        // Otherwise the model's reason comes last, after any held-back text
+        let chunks = run_chunks(&["a", "E"], &["END"]).await;
+        assert_eq!(
+            chunks,
+            vec![
+                StreamChunk::Text("a".to_string()),
+                StreamChunk::Text("E".to_string()),
+                StreamChunk::Finished(FinishReason::Length),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_flushes_safe_prefix_and_held_text() {
+        let mut filter = StopSequenceFilter::new(stops(&["###"]));
+        // "#" could start the stop, so only the text before it is released
+        assert_eq!(filter.push("done #"), Scan::Emit("done ".to_string()));
+        // "#x" can't, so the held text goes out
+        assert_eq!(filter.push("x"), Scan::Emit("#x".to_string()));
+        assert_eq!(filter.push("##"), Scan::Emit(String::new()));
+        assert_eq!(filter.finish(), "##");
+
+        // A partial stop at the end of generation is ordinary text
+        assert_eq!(run(&["a", "#", "#"], &["###"]).await.concat(), "a##");
+    }
+
+    #[test]
+    fn test_filter_handles_multibyte_prefixes() {
+        let mut filter = StopSequenceFilter::new(stops(&["→end"]));
+        assert_eq!(filter.push("x →"), Scan::Emit("x ".to_string()));
+        assert_eq!(filter.push("en"), Scan::Emit(String::new()));
+        assert_eq!(filter.push("d!"), Scan::Stopped(String::new()));
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        assert!(validate(&stops(&["END"])).is_ok());
+        assert!(validate(&[]).is_ok());
+        assert!(validate(&stops(&[""])).is_err());
+        assert!(validate(&vec!["x".to_string(); MAX_STOP_SEQUENCES + 1]).is_err());
+        assert!(validate(&["y".repeat(MAX_STOP_SEQUENCE_CHARS + 1)]).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Images embedded in PDF, Office and OpenDocument files, for captioning.
+
+use std::io::{Cursor, Read};
+
+use anyhow::Result;
+use lopdf::Object;
+use mime::Mime;
+use tracing::warn;
+
+/// Smaller images are usually icons, bullets and rules
+const MIN_IMAGE_BYTES: usize = 1024;
+
+/// Larger images are skipped rather than sent to the captioning model
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Media folders of OOXML (`word/media`, `ppt/media`, `xl/media`) and
+/// OpenDocument (`Pictures`) packages
+const MEDIA_PREFIXES: &[&str] = &["word/media/", "ppt/media/", "xl/media/", "Pictures/"];
+
+/// An image embedded in a document
+#[derive(Debug)]
+pub(crate) struct DocumentImage {
+    /// Entry name in the package, or `object N` for PDF images
+    pub(crate) name: String,
+    pub(crate) mime_type: &'static str,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Up to `max_images` images embedded in the document, in document order.
+/// Formats without embedded images, and documents that can't be read,
+/// yield none.
+pub(crate) fn extract_images(
+    mime_type: &Mime,
+    buffer: &[u8],
+    max_images: usize,
+) -> Vec<DocumentImage> {
+    let essence = mime_type.essence_str().to_ascii_lowercase();
+    let images = if essence == "application/pdf" {
+        pdf_images(buffer, max_images)
+    } else if essence.starts_with("application/vnd.openxmlformats-officedocument.")
+        || essence.starts_with("application/vnd.oasis.opendocument.")
+        || essence.contains("macroenabled")
+    {
+        package_images(buffer, max_images)
+    } else {
+        Ok(Vec::new())
+    };
+    images.unwrap_or_else(|e| {
+        warn!(error = %e, mime_type = %essence, "Failed to read embedded images");
+        Vec::new()
+    })
+}
+
+/// Images in the media folder of a ZIP-based document package
+fn package_images(buffer: &[u8], max_images: usize) -> Result<Vec<DocumentImage>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(buffer))?;
+    let mut images = Vec::new();
+    for index in 0..archive.len() {
+        if images.len() >= max_images {
+            break;
+        }
+        let entry = archive.by_index(index)?;
+        let name = entry.name().to_string();
+        if !MEDIA_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        let Some(mime_type) = image_mime_type(&name) else {
+            continue;
+        };
+        // The declared size can't be trusted, so the read is capped too
+        let mut data = Vec::new();
+        entry.take(MAX_IMAGE_BYTES + 1).read_to_end(&mut data)?;
+        if data.len() < MIN_IMAGE_BYTES || data.len() as u64 > MAX_IMAGE_BYTES {
+            continue;
+        }
+        images.push(DocumentImage {
+            name,
+            mime_type,
+            data,
+        });
+    }
+    Ok(images)
+}
+
+/// JPEG images of a PDF. An image XObject filtered only with `DCTDecode`
+/// holds a complete JPEG file; other encodings are raw pixel data.
+fn pdf_images(buffer: &[u8], max_images: usize) -> Result<Vec<DocumentImage>> {
+    let doc = lopdf::Document::load_mem(buffer)?;
+    let mut images = Vec::new();
+    for ((id, _), object) in &doc.objects {
+        if images.len() >= max_images {
+            break;
+        }
+        let Object::Stream(stream) = object else {
+            continue;
+        };
+        let is_image = stream
+            .dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .is_ok_and(|subtype| subtype == b"Image");
+        let is_jpeg = match stream.dict.get(b"Filter") {
+            Ok(Object::Name(filter)) => filter == b"DCTDecode",
+            Ok(Object::Array(filters)) => {
+                filters.len() == 1 && filters[0].as_name().is_ok_and(|f| f == b"DCTDecode")
+            }
+            _ => false,
+        };
+        let size = stream.content.len();
+        if !is_image || !is_jpeg || size < MIN_IMAGE_BYTES || size as u64 > MAX_IMAGE_BYTES {
+            continue;
+        }
+        images.push(DocumentImage {
+            name: format!("object {}", id),
+            mime_type: "image/jpeg",
+            data: stream.content.clone(),
+        });
+    }
+    Ok(images)
+}
+
+/// Image types vision models accept, by file extension
+fn image_mime_type(name: &str) -> Option<&'static str> {
+    let (_, extension) = name.rsplit_once('.')?;
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
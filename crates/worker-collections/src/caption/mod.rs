@@ -0,0 +1,329 @@
+//! Image captions for documents with embedded images (`caption_images`).
+//!
+//! Text extraction drops pictures, so a chart or a scanned figure adds
+//! nothing to search. With `caption_images` set, images embedded in PDF,
+//! Office and OpenDocument files are described by a vision model behind an
+//! OpenAI-compatible chat completions endpoint (`IMAGE_CAPTION_URL`), and
+//! the captions are appended to the extracted text. At most
+//! `max_captioned_images` images are captioned per document. Only built
+//! with the `image-captions` feature.
+
+mod images;
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use mime::Mime;
+use serde_json::{Map, Value, json};
+use tracing::{info, warn};
+
+use crate::extract::config::{ExtractionOptions, ExtractionOutput};
+
+pub(crate) use images::{DocumentImage, extract_images};
+
+/// Metadata key holding the number of images captioned
+pub(crate) const CAPTIONED_IMAGES_METADATA_KEY: &str = "captioned_images";
+
+const DEFAULT_PROMPT: &str =
+    "Describe this image in one or two sentences, including any text, numbers or labels it shows.";
+
+/// Vision model endpoint the images are captioned with
+pub(crate) struct Captioner {
+    client: reqwest::Client,
+    url: String,
+    model: Option<String>,
+    api_key: Option<String>,
+    prompt: String,
+}
+
+impl Captioner {
+    /// The captioner configured by `IMAGE_CAPTION_*`, if `IMAGE_CAPTION_URL` is set
+    pub(crate) fn global() -> Option<&'static Captioner> {
+        static CAPTIONER: OnceLock<Option<Captioner>> = OnceLock::new();
+        CAPTIONER.get_or_init(Self::from_env).as_ref()
+    }
+
+    fn from_env() -> Option<Self> {
+        let url = std::env::var("IMAGE_CAPTION_URL")
+            .ok()
+            .filter(|url| !url.is_empty())?;
+        let timeout_secs = std::env::var("IMAGE_CAPTION_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let client = match reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!(error = %e, "Failed to build image caption client");
+                return None;
+            }
+        };
+        Some(Self {
+            client,
+            url,
+            model: std::env::var("IMAGE_CAPTION_MODEL").ok(),
+            api_key: std::env::var("IMAGE_CAPTION_API_KEY").ok(),
+            prompt: std::env::var("IMAGE_CAPTION_PROMPT")
+                .unwrap_or_else(|_| DEFAULT_PROMPT.to_string()),
+        })
+    }
+
+    /// Caption one image
+    pub(crate) async fn caption(&self, image: &DocumentImage) -> Result<String> {
+        let data_url = format!(
+            "data:{};base64,{}",
+            image.mime_type,
+            STANDARD.encode(&image.data)
+        );
+        let mut body = json!({
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": self.prompt },
+                    { "type": "image_url", "image_url": { "url": data_url } }
+                ]
+            }],
+            "max_tokens": 200
+        });
+        if let Some(model) = &self.model {
+            body["model"] = json!(model);
+        }
+
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response: Value = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Invalid caption response")?;
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::trim)
+            .filter(|caption| !caption.is_empty())
+            .map(String::from)
+            .context("Caption response has no message content")
+    }
+}
+
+/// Caption the images embedded in `buffer` and append the captions to
+/// `output`, when `caption_images` is set and a captioner is configured
+pub(crate) async fn caption_document(
+    mime_type: &Mime,
+    buffer: &[u8],
+    options: &ExtractionOptions,
+    output: ExtractionOutput,
+) -> ExtractionOutput {
+    if !options.caption_images || options.max_captioned_images == 0 {
+        return output;
+    }
+    let Some(captioner) = Captioner::global() else {
+        warn!("caption_images is set but IMAGE_CAPTION_URL is not configured");
+        return output;
+    };
+
+    let mime = mime_type.clone();
+    let buffer = buffer.to_vec();
+    let max_images = options.max_captioned_images;
+    let images =
+        match tokio::task::spawn_blocking(move || extract_images(&mime, &buffer, max_images)).await
+        {
+            Ok(images) => images,
+            Err(e) => {
+                warn!(error = %e, "Embedded image extraction failed");
+                return output;
+            }
+        };
+    if images.is_empty() {
+        return output;
+    }
+
+    info!(image_count = images.len(), "Captioning embedded images");
+    append_captions(output, &images, options.max_output_bytes, |image| {
+        captioner.caption(image)
+    })
+    .await
+}
+
+/// Append a `[Image: <caption>]` paragraph per image to the text, stopping
+/// before `max_output_bytes` would be exceeded. Images that fail to caption
+/// are skipped.
+pub(crate) async fn append_captions<'a, F, Fut>(
+    mut output: ExtractionOutput,
+    images: &'a [DocumentImage],
+    max_output_bytes: Option<usize>,
+    mut caption: F,
+) -> ExtractionOutput
+where
+    F: FnMut(&'a DocumentImage) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let mut captioned = 0;
+    for image in images {
+        let caption = match caption(image).await {
+            Ok(caption) => caption.split_whitespace().collect::<Vec<_>>().join(" "),
+            Err(e) => {
+                warn!(image = %image.name, error = %e, "Failed to caption image");
+                continue;
+            }
+        };
+        if caption.is_empty() {
+            continue;
+        }
+        let separator = if output.text.is_empty() { "" } else { "\n\n" };
+        let paragraph = format!("{}[Image: {}]", separator, caption);
+        if max_output_bytes.is_some_and(|limit| output.text.len() + paragraph.len() > limit) {
+            break;
+        }
+        output.text.push_str(&paragraph);
+        captioned += 1;
+    }
+
+    if captioned > 0 {
+        let mut metadata = match output.metadata.take() {
+            Some(Value::Object(map)) => map,
+            Some(other) => Map::from_iter([("metadata".to_string(), other)]),
+            None => Map::new(),
+        };
+        metadata.insert(
+            CAPTIONED_IMAGES_METADATA_KEY.to_string(),
+            Value::from(captioned),
+        );
+        output.metadata = Some(Value::Object(metadata));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::pdf::build_pdf;
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    /// PNG signature padded past the minimum image size
+    fn png(len: usize) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.resize(len, 0);
+        data
+    }
+
+    fn docx_with_images() -> Vec<u8> {
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let entries: [(&str, Vec<u8>); 4] = [
+            (
+                "word/document.xml",
+                b"<w:document><w:body><w:p><w:r><w:t>Quarterly report</w:t></w:r></w:p></w:body></w:document>".to_vec(),
+            ),
+            ("word/media/image1.png", png(4096)),
+            // Too small to be worth captioning, and a format models can't read
+            ("word/media/bullet.png", png(64)),
+            ("word/media/logo.emf", vec![0; 4096]),
+        ];
+        for (name, content) in entries {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(&content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn extraction(text: &str) -> ExtractionOutput {
+        ExtractionOutput {
+            text: text.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_docx_image_caption_is_appended() {
+        let mime: Mime = "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            .parse()
+            .unwrap();
+        let images = extract_images(&mime, &docx_with_images(), 10);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].name, "word/media/image1.png");
+        assert_eq!(images[0].mime_type, "image/png");
+
+        let output = append_captions(extraction("Quarterly report"), &images, None, |_| async {
+            Ok("A bar chart of revenue by quarter.".to_string())
+        })
+        .await;
+        assert_eq!(
+            output.text,
+            "Quarterly report\n\n[Image: A bar chart of revenue by quarter.]"
+        );
+        assert_eq!(output.metadata.unwrap()[CAPTIONED_IMAGES_METADATA_KEY], 1);
+    }
+
+    #[tokio::test]
+    async fn test_pdf_jpeg_caption_is_appended() {
+        let mut doc = lopdf::Document::load_mem(&build_pdf("Site survey")).unwrap();
+        let mut jpeg = b"\xff\xd8\xff\xe0".to_vec();
+        jpeg.resize(4096, 0);
+        doc.add_object(lopdf::Stream::new(
+            lopdf::dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 64,
+                "Height" => 64,
+                "Filter" => "DCTDecode",
+            },
+            jpeg.clone(),
+        ));
+        let mut pdf = Vec::new();
+        doc.save_to(&mut pdf).unwrap();
+
+        let mime: Mime = "application/pdf".parse().unwrap();
+        let images = extract_images(&mime, &pdf, 10);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].mime_type, "image/jpeg");
+        assert_eq!(images[0].data, jpeg);
+
+        let output = append_captions(extraction("Site survey"), &images, None, |_| async {
+            Ok("Aerial photo of a construction site.".to_string())
+        })
+        .await;
+        assert!(
+            output
+                .text
+                .ends_with("[Image: Aerial photo of a construction site.]")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_captions_skip_failures_and_respect_output_limit() {
+        let images: Vec<_> = (0..3)
+            .map(|i| DocumentImage {
+                name: format!("word/media/image{i}.png"),
+                mime_type: "image/png",
+                data: png(4096),
+            })
+            .collect();
+
+        let output = append_captions(extraction("Report"), &images, Some(40), |image| {
+            let result = if image.name.ends_with("image0.png") {
+                Err(anyhow::anyhow!("model unavailable"))
+            } else {
+                Ok(format!("Figure {}", image.name.len()))
+            };
+            std::future::ready(result)
+        })
+        .await;
+
+        // image0 failed, image1 fits, image2 would pass the 40 byte limit
+        assert_eq!(output.text, "Report\n\n[Image: Figure 21]");
+        assert_eq!(output.metadata.unwrap()[CAPTIONED_IMAGES_METADATA_KEY], 1);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -10,9 +11,31 @@ pub enum ChunkingStrategy {
     Semantic,
     FixedSize,
     MarkdownAware,
+    #[serde(alias = "token")]
     TokenBased,
     CodeAware,
     TableAware,
+    /// One chunk per markdown section, split on sentences only when a
+    /// section exceeds the token budget
+    Structural,
+    /// Pick the strategy from the file's MIME type: CSV/TSV by row groups,
+    /// source code by function boundaries, everything else by tokens
+    Auto,
+}
+
+/// Chunking to use for files of one MIME type instead of the transform's
+/// own strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MimeChunkingOverride {
+    pub strategy: ChunkingStrategy,
+
+    /// Replaces `chunk_size` for these files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_size: Option<usize>,
+
+    /// Replaces `chunk_overlap` for these files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_overlap: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -78,6 +101,12 @@ pub struct TokenBasedOptions {
     /// Optional model name for tokenizer selection (default: cl100k_base for GPT-4/ada-002)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+
+    /// HuggingFace tokenizer to count tokens with, as a model id (e.g.
+    /// `BAAI/bge-small-en-v1.5`) or a path to a `tokenizer.json`. Should match
+    /// the target embedding model; special tokens it adds are not counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokenizer: Option<String>,
 }
 
 impl Default for TokenBasedOptions {
@@ -87,6 +116,24 @@ impl Default for TokenBasedOptions {
             overlap_tokens: 0,
             split_on_sentences: true,
             model: None,
+            tokenizer: None,
+        }
+    }
+}
+
+/// Options for structural (heading-aware) chunking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuralOptions {
+    /// Maximum number of tokens per chunk; larger sections are split on
+    /// sentence boundaries (default: 512)
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+}
+
+impl Default for StructuralOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: default_max_tokens(),
         }
     }
 }
@@ -197,6 +244,9 @@ pub struct ChunkingOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_based: Option<TokenBasedOptions>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structural: Option<StructuralOptions>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code_aware: Option<CodeAwareOptions>,
 
@@ -223,6 +273,7 @@ impl Default for ChunkingOptions {
             recursive_character: None,
             markdown_aware: None,
             token_based: None,
+            structural: None,
             code_aware: None,
             table_aware: None,
             streaming: None,
@@ -246,6 +297,25 @@ pub struct ChunkingConfig {
 
     #[serde(default)]
     pub options: ChunkingOptions,
+
+    /// Per-MIME overrides keyed by MIME type (`text/csv`) or type wildcard
+    /// (`text/*`); an exact match wins over a wildcard
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mime_overrides: BTreeMap<String, MimeChunkingOverride>,
+
+    /// Chunk size multipliers keyed by ISO 639-1 language code, e.g.
+    /// `{"ja": 2.0}`, applied to the target sizes of text in that language
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub language_multipliers: BTreeMap<String, f32>,
+
+    /// Most chunks kept from one document; the rest are dropped and
+    /// `chunks_truncated` is recorded in the chunk metadata (unlimited if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_chunks_per_document: Option<usize>,
+
+    /// Fail documents over `max_chunks_per_document` instead of truncating them
+    #[serde(default)]
+    pub reject_oversized_documents: bool,
 }
 
 impl Default for ChunkingConfig {
@@ -255,6 +325,10 @@ impl Default for ChunkingConfig {
             chunk_size: default_chunk_size(),
             chunk_overlap: 0,
             options: ChunkingOptions::default(),
+            mime_overrides: BTreeMap::new(),
+            language_multipliers: BTreeMap::new(),
+            max_chunks_per_document: None,
+            reject_oversized_documents: false,
         }
     }
 }
@@ -386,5 +460,52 @@ mod tests {
         assert_eq!(opts.max_tokens, 1024);
         assert!(!opts.split_on_sentences);
         assert_eq!(opts.model, Some("gpt-4".to_string()));
+        assert_eq!(opts.tokenizer, None);
+
+        let json = json!({
+            "strategy": "token",
+            "options": {
+                "token_based": {
+                    "max_tokens": 256,
+                    "overlap_tokens": 32,
+                    "tokenizer": "BAAI/bge-small-en-v1.5"
+                }
+            }
+        });
+        let config: ChunkingConfig = serde_json::from_value(json).unwrap();
+        assert!(matches!(config.strategy, ChunkingStrategy::TokenBased));
+        let opts = config.options.token_based.unwrap();
+        assert_eq!(opts.overlap_tokens, 32);
+        assert_eq!(opts.tokenizer.as_deref(), Some("BAAI/bge-small-en-v1.5"));
+    }
+
+    #[test]
+    fn test_deserialize_mime_overrides() {
+        let json = json!({
+            "strategy": "auto",
+            "mime_overrides": {
+                "text/csv": { "strategy": "table_aware", "chunk_size": 4000 },
+                "text/*": { "strategy": "sentence" }
+            }
+        });
+
+        let config: ChunkingConfig = serde_json::from_value(json).unwrap();
+        assert!(matches!(config.strategy, ChunkingStrategy::Auto));
+        let csv = &config.mime_overrides["text/csv"];
+        assert!(matches!(csv.strategy, ChunkingStrategy::TableAware));
+        assert_eq!(csv.chunk_size, Some(4000));
+        assert_eq!(config.mime_overrides["text/*"].chunk_overlap, None);
+    }
+
+    #[test]
+    fn test_deserialize_structural_options() {
+        let json = json!({
+            "strategy": "structural",
+            "options": { "structural": {} }
+        });
+
+        let config: ChunkingConfig = serde_json::from_value(json).unwrap();
+        assert!(matches!(config.strategy, ChunkingStrategy::Structural));
+        assert_eq!(config.options.structural.unwrap().max_tokens, 512);
     }
 }
@@ -21,10 +21,32 @@ pub struct StructureInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub section_title: Option<String>,
 
+    /// Heading hierarchy joined for display, e.g. `"Intro > Setup > Config"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breadcrumb: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page_number: Option<usize>,
 }
 
+/// Separator between heading titles in [`StructureInfo::breadcrumb`]
+pub const BREADCRUMB_SEPARATOR: &str = " > ";
+
+impl StructureInfo {
+    /// Structure of a chunk under the given headings, `None` outside any heading
+    pub fn from_headings(heading_hierarchy: &[String]) -> Option<Self> {
+        if heading_hierarchy.is_empty() {
+            return None;
+        }
+        Some(Self {
+            heading_hierarchy: Some(heading_hierarchy.to_vec()),
+            section_title: heading_hierarchy.last().cloned(),
+            breadcrumb: Some(heading_hierarchy.join(BREADCRUMB_SEPARATOR)),
+            page_number: None,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ChunkWithStructure {
     pub content: String,
@@ -1,6 +1,8 @@
 pub mod config;
 pub mod metadata;
+pub mod selection;
 pub mod service;
 pub mod strategies;
+pub mod tokenizer;
 
 pub use service::ChunkingService;
@@ -0,0 +1,229 @@
+//! Per-file chunking selection from the MIME type of the source file.
+//!
+//! A transform chunks every file with one config, but CSV rows, source code
+//! and prose split best in different ways. `mime_overrides` replaces the
+//! strategy (and optionally the size) for matching files, and the `auto`
+//! strategy picks a sensible default per file type. Token and character
+//! budgets don't carry the same amount of meaning in every language either,
+//! so `language_multipliers` scales the sizes by the text's language.
+
+use mime::Mime;
+
+use super::config::{ChunkingConfig, ChunkingStrategy, CodeAwareOptions, MimeChunkingOverride};
+use super::strategies::code_aware::CodeLanguage;
+
+/// The config to chunk a file of `mime_type` with: the matching override if
+/// any, otherwise the transform's own config with `auto` resolved. The
+/// returned strategy is never `auto`.
+pub(crate) fn resolve(config: &ChunkingConfig, mime_type: Option<&Mime>) -> ChunkingConfig {
+    let mut resolved = config.clone();
+
+    if let Some(mime_type) = mime_type
+        && let Some(chunking) = find_override(config, mime_type)
+    {
+        resolved.strategy = chunking.strategy.clone();
+        if let Some(chunk_size) = chunking.chunk_size {
+            resolved.chunk_size = chunk_size;
+        }
+        if let Some(chunk_overlap) = chunking.chunk_overlap {
+            resolved.chunk_overlap = chunk_overlap;
+        }
+    }
+
+    if matches!(resolved.strategy, ChunkingStrategy::Auto) {
+        resolved.strategy = mime_type
+            .map(default_strategy)
+            .unwrap_or(ChunkingStrategy::TokenBased);
+    }
+
+    // Let the code chunker parse with the grammar of the file's language
+    // rather than guess it from the text
+    if matches!(resolved.strategy, ChunkingStrategy::CodeAware)
+        && let Some(mime_type) = mime_type
+        && CodeLanguage::from_mime_type(mime_type.essence_str()).is_some()
+    {
+        let code_aware = resolved
+            .options
+            .code_aware
+            .get_or_insert_with(CodeAwareOptions::default);
+        if code_aware.language.is_none() {
+            code_aware.language = Some(mime_type.essence_str().to_string());
+        }
+    }
+
+    resolved
+}
+
+/// Scale the target chunk sizes of `config` by the multiplier configured for
+/// `language`, if any. Sizes never drop below one.
+pub(crate) fn scale_for_language(config: &mut ChunkingConfig, language: &str) {
+    let Some(&multiplier) = config.language_multipliers.get(language) else {
+        return;
+    };
+    if !multiplier.is_finite() || multiplier <= 0.0 {
+        return;
+    }
+    let scale = |size: usize| ((size as f32 * multiplier).round() as usize).max(1);
+    let scale_overlap = |size: usize| (size as f32 * multiplier).round() as usize;
+
+    config.chunk_size = scale(config.chunk_size);
+    config.chunk_overlap = scale_overlap(config.chunk_overlap);
+    let options = &mut config.options;
+    if let Some(token_based) = options.token_based.as_mut() {
+        token_based.max_tokens = scale(token_based.max_tokens);
+        token_based.overlap_tokens = scale_overlap(token_based.overlap_tokens);
+    }
+    if let Some(structural) = options.structural.as_mut() {
+        structural.max_tokens = scale(structural.max_tokens);
+    }
+    if let Some(semantic) = options.semantic.as_mut() {
+        semantic.max_chunk_size = scale(semantic.max_chunk_size);
+    }
+    if let Some(max_chunk_size) = options
+        .code_aware
+        .as_mut()
+        .and_then(|o| o.max_chunk_size.as_mut())
+    {
+        *max_chunk_size = scale(*max_chunk_size);
+    }
+    if let Some(max_chunk_size) = options
+        .table_aware
+        .as_mut()
+        .and_then(|o| o.max_chunk_size.as_mut())
+    {
+        *max_chunk_size = scale(*max_chunk_size);
+    }
+}
+
+/// Exact MIME type first, then the `type/*` wildcard
+fn find_override<'a>(
+    config: &'a ChunkingConfig,
+    mime_type: &Mime,
+) -> Option<&'a MimeChunkingOverride> {
+    config
+        .mime_overrides
+        .get(mime_type.essence_str())
+        .or_else(|| {
+            config
+                .mime_overrides
+                .get(&format!("{}/*", mime_type.type_()))
+        })
+}
+
+/// Strategy `auto` uses for a MIME type
+fn default_strategy(mime_type: &Mime) -> ChunkingStrategy {
+    match mime_type.essence_str() {
+        "text/csv" | "text/tab-separated-values" => ChunkingStrategy::TableAware,
+        essence => match CodeLanguage::from_mime_type(essence) {
+            // HTML and JSON are extracted to prose, not kept as source
+            Some(CodeLanguage::Html | CodeLanguage::Json) | None => ChunkingStrategy::TokenBased,
+            Some(_) => ChunkingStrategy::CodeAware,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mime(value: &str) -> Mime {
+        value.parse().unwrap()
+    }
+
+    fn auto_config() -> ChunkingConfig {
+        ChunkingConfig {
+            strategy: ChunkingStrategy::Auto,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_auto_picks_strategy_by_mime_type() {
+        let config = auto_config();
+
+        let csv = resolve(&config, Some(&mime("text/csv")));
+        assert!(matches!(csv.strategy, ChunkingStrategy::TableAware));
+
+        let python = resolve(&config, Some(&mime("text/x-python")));
+        assert!(matches!(python.strategy, ChunkingStrategy::CodeAware));
+        assert_eq!(
+            python.options.code_aware.unwrap().language.as_deref(),
+            Some("text/x-python")
+        );
+
+        let prose = resolve(&config, Some(&mime("application/pdf")));
+        assert!(matches!(prose.strategy, ChunkingStrategy::TokenBased));
+        let html = resolve(&config, Some(&mime("text/html")));
+        assert!(matches!(html.strategy, ChunkingStrategy::TokenBased));
+        let unknown = resolve(&config, None);
+        assert!(matches!(unknown.strategy, ChunkingStrategy::TokenBased));
+    }
+
+    #[test]
+    fn test_overrides_prefer_exact_match_over_wildcard() {
+        let config: ChunkingConfig = serde_json::from_value(json!({
+            "strategy": "sentence",
+            "chunk_size": 500,
+            "mime_overrides": {
+                "text/csv": { "strategy": "table_aware", "chunk_size": 4000 },
+                "text/*": { "strategy": "fixed_size", "chunk_overlap": 20 }
+            }
+        }))
+        .unwrap();
+
+        let csv = resolve(&config, Some(&mime("text/csv")));
+        assert!(matches!(csv.strategy, ChunkingStrategy::TableAware));
+        assert_eq!(csv.chunk_size, 4000);
+        assert_eq!(csv.chunk_overlap, 0);
+
+        let plain = resolve(&config, Some(&mime("text/plain")));
+        assert!(matches!(plain.strategy, ChunkingStrategy::FixedSize));
+        assert_eq!(plain.chunk_size, 500);
+        assert_eq!(plain.chunk_overlap, 20);
+
+        let pdf = resolve(&config, Some(&mime("application/pdf")));
+        assert!(matches!(pdf.strategy, ChunkingStrategy::Sentence));
+    }
+
+    #[test]
+    fn test_language_multipliers_scale_sizes() {
+        let mut config: ChunkingConfig = serde_json::from_value(json!({
+            "strategy": "token_based",
+            "chunk_size": 400,
+            "chunk_overlap": 40,
+            "options": { "token_based": { "max_tokens": 256, "overlap_tokens": 32 } },
+            "language_multipliers": { "ja": 2.0, "de": 0.0 }
+        }))
+        .unwrap();
+
+        scale_for_language(&mut config, "en");
+        assert_eq!(config.chunk_size, 400);
+
+        // Invalid multipliers are ignored
+        scale_for_language(&mut config, "de");
+        assert_eq!(config.chunk_size, 400);
+
+        scale_for_language(&mut config, "ja");
+        assert_eq!(config.chunk_size, 800);
+        assert_eq!(config.chunk_overlap, 80);
+        let token_based = config.options.token_based.unwrap();
+        assert_eq!(token_based.max_tokens, 512);
+        assert_eq!(token_based.overlap_tokens, 64);
+    }
+
+    #[test]
+    fn test_explicit_code_language_is_kept() {
+        let mut config = auto_config();
+        config.options.code_aware = Some(CodeAwareOptions {
+            language: Some("rs".to_string()),
+            ..Default::default()
+        });
+
+        let resolved = resolve(&config, Some(&mime("text/x-python")));
+        assert_eq!(
+            resolved.options.code_aware.unwrap().language.as_deref(),
+            Some("rs")
+        );
+    }
+}
@@ -1,10 +1,13 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use mime::Mime;
 use semantic_explorer_core::models::EmbedderConfig;
 use serde::{Deserialize, Serialize};
 
 use super::config::{ChunkingConfig, ChunkingStrategy};
 use super::metadata::{ChunkMetadata, ChunkWithStructure as StrategyChunkWithStructure};
+use super::selection;
 use super::strategies;
+use crate::extract::language::{self, LANGUAGE_METADATA_KEY};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkWithMetadata {
@@ -15,26 +18,45 @@ pub struct ChunkWithMetadata {
 pub struct ChunkingService;
 
 impl ChunkingService {
+    /// Chunk `text` extracted from a file of `mime_type`, which selects the
+    /// strategy when the config has a matching override or is `auto`. With
+    /// `language_multipliers`, sizes are scaled by the language recorded at
+    /// extraction, or detected here when extraction didn't record one.
     pub async fn chunk_text(
         text: String,
         config: &ChunkingConfig,
+        mime_type: Option<&Mime>,
         extraction_metadata: Option<serde_json::Value>,
         embedder_config: Option<&EmbedderConfig>,
     ) -> Result<Vec<ChunkWithMetadata>> {
-        let structured_chunks = if matches!(config.strategy, ChunkingStrategy::MarkdownAware) {
-            strategies::markdown_aware::chunk(text.clone(), config)
-                .ok()
-                .map(|chunks| {
-                    chunks
-                        .into_iter()
-                        .map(|c| StrategyChunkWithStructure {
-                            content: c.content,
-                            structure_info: c.structure_info,
-                        })
-                        .collect::<Vec<_>>()
-                })
-        } else {
-            None
+        let mut config = selection::resolve(config, mime_type);
+        if !config.language_multipliers.is_empty() {
+            let language = extraction_metadata
+                .as_ref()
+                .and_then(|m| m.get(LANGUAGE_METADATA_KEY))
+                .and_then(|v| v.as_str())
+                .unwrap_or_else(|| language::detect(&text).0);
+            selection::scale_for_language(&mut config, language);
+        }
+        let config = &config;
+        let structured_chunks = match config.strategy {
+            ChunkingStrategy::MarkdownAware => {
+                strategies::markdown_aware::chunk(text.clone(), config)
+                    .ok()
+                    .map(|chunks| {
+                        chunks
+                            .into_iter()
+                            .map(|c| StrategyChunkWithStructure {
+                                content: c.content,
+                                structure_info: c.structure_info,
+                            })
+                            .collect::<Vec<_>>()
+                    })
+            }
+            ChunkingStrategy::Structural => {
+                Some(strategies::structural::chunk(text.clone(), config)?)
+            }
+            _ => None,
         };
 
         let (chunks, structure_infos): (Vec<String>, Vec<Option<_>>) = if let Some(structured) =
@@ -62,24 +84,31 @@ impl ChunkingService {
                 ChunkingStrategy::TableAware => {
                     strategies::table_aware::chunk(text.clone(), config)?
                 }
-                ChunkingStrategy::MarkdownAware => {
-                    unreachable!("MarkdownAware is handled in the structured_chunks path above")
+                ChunkingStrategy::MarkdownAware | ChunkingStrategy::Structural => {
+                    unreachable!("structured strategies are handled in the path above")
+                }
+                ChunkingStrategy::Auto => {
+                    unreachable!("auto is resolved to a concrete strategy above")
                 }
             };
             let len = chunks.len();
             (chunks, vec![None; len])
         };
 
+        // Token-based chunking applies its own overlap, and structural chunks
+        // must not bleed into the neighbouring section
         let chunks_with_overlap = if config.chunk_overlap > 0
-            && !matches!(config.strategy, ChunkingStrategy::TokenBased)
-        {
+            && !matches!(
+                config.strategy,
+                ChunkingStrategy::TokenBased | ChunkingStrategy::Structural
+            ) {
             strategies::overlap::apply_overlap(chunks, config.chunk_overlap)?
         } else {
             chunks
         };
 
         let total_chunks = chunks_with_overlap.len();
-        let chunks_with_metadata = chunks_with_overlap
+        let mut chunks_with_metadata = chunks_with_overlap
             .into_iter()
             .enumerate()
             .map(|(idx, chunk)| {
@@ -99,14 +128,77 @@ impl ChunkingService {
             })
             .collect();
 
+        limit_chunks(&mut chunks_with_metadata, config)?;
         Ok(chunks_with_metadata)
     }
 }
 
+/// Number `chunks` as one document, for chunks of several texts (such as
+/// archive entries) chunked separately
+pub(crate) fn renumber_chunks(chunks: &mut [ChunkWithMetadata]) {
+    let total_chunks = chunks.len();
+    for (idx, chunk) in chunks.iter_mut().enumerate() {
+        chunk.metadata.chunk_index = idx;
+        chunk.metadata.total_chunks = total_chunks;
+    }
+}
+
+/// Apply `max_chunks_per_document`: fail the document with
+/// `reject_oversized_documents`, otherwise drop the chunks past the limit
+pub(crate) fn limit_chunks(
+    chunks: &mut Vec<ChunkWithMetadata>,
+    config: &ChunkingConfig,
+) -> Result<()> {
+    let Some(max_chunks) = config.max_chunks_per_document else {
+        return Ok(());
+    };
+    let original_chunks = chunks.len();
+    if original_chunks <= max_chunks {
+        return Ok(());
+    }
+    if config.reject_oversized_documents {
+        bail!(
+            "Document produced {} chunks, over the max_chunks_per_document limit of {}",
+            original_chunks,
+            max_chunks
+        );
+    }
+    tracing::warn!(
+        original_chunks,
+        max_chunks,
+        "Document exceeds max_chunks_per_document, dropping the remaining chunks"
+    );
+    chunks.truncate(max_chunks);
+    for chunk in chunks.iter_mut() {
+        chunk.metadata.total_chunks = max_chunks;
+        let metadata = chunk.metadata.extraction_metadata.take();
+        chunk.metadata.extraction_metadata = Some(mark_chunks_truncated(metadata, original_chunks));
+    }
+    Ok(())
+}
+
+/// Record in a chunk's extraction metadata that its document was cut to
+/// `max_chunks_per_document`
+fn mark_chunks_truncated(
+    metadata: Option<serde_json::Value>,
+    original_chunks: usize,
+) -> serde_json::Value {
+    let mut metadata = match metadata {
+        Some(serde_json::Value::Object(map)) => map,
+        Some(other) => serde_json::Map::from_iter([("metadata".to_string(), other)]),
+        None => serde_json::Map::new(),
+    };
+    metadata.insert("chunks_truncated".to_string(), true.into());
+    metadata.insert("original_chunk_count".to_string(), original_chunks.into());
+    serde_json::Value::Object(metadata)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chunk::config::{ChunkingOptions, MarkdownAwareOptions, RecursiveCharacterOptions};
+    use crate::chunk::config::{
+        ChunkingOptions, MarkdownAwareOptions, RecursiveCharacterOptions, StructuralOptions,
+    };
 
     fn create_basic_config(strategy: ChunkingStrategy, chunk_size: usize) -> ChunkingConfig {
         ChunkingConfig {
@@ -114,6 +206,7 @@ mod tests {
             chunk_size,
             chunk_overlap: 0,
             options: ChunkingOptions::default(),
+            ..Default::default()
         }
     }
 
@@ -122,7 +215,7 @@ mod tests {
         let text = "First sentence. Second sentence. Third sentence.".to_string();
         let config = create_basic_config(ChunkingStrategy::Sentence, 30);
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -136,12 +229,46 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_max_chunks_per_document_caps_huge_documents() {
+        let text = "a".repeat(10_000);
+        let mut config = create_basic_config(ChunkingStrategy::FixedSize, 10);
+        config.max_chunks_per_document = Some(50);
+
+        let metadata = serde_json::json!({"source_file": "huge.txt"});
+        let chunks = ChunkingService::chunk_text(text.clone(), &config, None, Some(metadata), None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 50);
+        let last = &chunks[49].metadata;
+        assert_eq!(last.total_chunks, 50);
+        let extraction_metadata = last.extraction_metadata.as_ref().unwrap();
+        assert_eq!(extraction_metadata["chunks_truncated"], true);
+        assert_eq!(extraction_metadata["original_chunk_count"], 1000);
+        assert_eq!(extraction_metadata["source_file"], "huge.txt");
+
+        // Documents under the limit are untouched
+        config.max_chunks_per_document = Some(1000);
+        let chunks = ChunkingService::chunk_text(text.clone(), &config, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1000);
+        assert!(chunks[0].metadata.extraction_metadata.is_none());
+
+        config.max_chunks_per_document = Some(50);
+        config.reject_oversized_documents = true;
+        let error = ChunkingService::chunk_text(text, &config, None, None, None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("max_chunks_per_document"));
+    }
+
     #[tokio::test]
     async fn test_fixed_size_chunking() {
         let text = "a".repeat(100);
         let config = create_basic_config(ChunkingStrategy::FixedSize, 25);
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -158,7 +285,7 @@ mod tests {
         let mut config = create_basic_config(ChunkingStrategy::RecursiveCharacter, 30);
         config.options.recursive_character = Some(RecursiveCharacterOptions::default());
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -174,7 +301,7 @@ mod tests {
             preserve_code_blocks: true,
         });
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -187,7 +314,7 @@ mod tests {
         let mut config = create_basic_config(ChunkingStrategy::Sentence, 25);
         config.chunk_overlap = 5;
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -205,7 +332,7 @@ mod tests {
         let text = String::new();
         let config = create_basic_config(ChunkingStrategy::Sentence, 100);
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 0);
     }
@@ -219,7 +346,7 @@ mod tests {
             "page": 1
         }));
 
-        let result = ChunkingService::chunk_text(text, &config, metadata.clone(), None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, metadata.clone(), None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -236,7 +363,7 @@ mod tests {
         let text = "This is one sentence.".to_string();
         let config = create_basic_config(ChunkingStrategy::Sentence, 100);
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -258,11 +385,12 @@ mod tests {
             overlap_tokens: 0, // No internal overlap
             split_on_sentences: false,
             model: None,
+            tokenizer: None,
         });
 
         let text = "one two three four five six seven eight nine ten".to_string();
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -292,7 +420,7 @@ mod tests {
         let text = "Hello 世界! Testing émojis 🚀 and ñoño.".to_string();
         let config = create_basic_config(ChunkingStrategy::Sentence, 100);
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -306,7 +434,7 @@ mod tests {
         let text = "Test chunk.".to_string();
         let config = create_basic_config(ChunkingStrategy::Sentence, 100);
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -330,7 +458,7 @@ mod tests {
             preserve_code_blocks: true,
         });
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -366,7 +494,7 @@ mod tests {
         });
         config.options.min_chunk_size = 10;
 
-        let result = ChunkingService::chunk_text(text, &config, None, None).await;
+        let result = ChunkingService::chunk_text(text, &config, None, None, None).await;
         assert!(result.is_ok());
 
         let chunks = result.unwrap();
@@ -386,4 +514,88 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_structural_chunks_carry_breadcrumbs_without_overlap() {
+        let text = "# Guide\nWelcome to the guide.\n\n## Install\nRun the installer.\n\n## Usage\nStart the app.".to_string();
+        let mut config = create_basic_config(ChunkingStrategy::Structural, 100);
+        config.chunk_overlap = 20;
+        config.options.structural = Some(StructuralOptions { max_tokens: 100 });
+
+        let chunks = ChunkingService::chunk_text(text, &config, None, None, None)
+            .await
+            .unwrap();
+
+        let breadcrumbs: Vec<_> = chunks
+            .iter()
+            .map(|c| {
+                c.metadata
+                    .structure_info
+                    .as_ref()
+                    .and_then(|s| s.breadcrumb.clone())
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            breadcrumbs,
+            vec!["Guide", "Guide > Install", "Guide > Usage"]
+        );
+        assert_eq!(chunks[2].content, "## Usage\nStart the app.");
+    }
+
+    #[tokio::test]
+    async fn test_auto_chunks_csv_by_row_groups_with_header() {
+        let header = "id,name,city";
+        let mut text = header.to_string();
+        for id in 0..12 {
+            text.push_str(&format!("\n{},person{},city{}", id, id, id));
+        }
+        let mut config = create_basic_config(ChunkingStrategy::Auto, 4000);
+        config.options.table_aware = Some(crate::chunk::config::TableAwareOptions {
+            max_rows_per_chunk: Some(5),
+            ..Default::default()
+        });
+        let csv: Mime = "text/csv".parse().unwrap();
+
+        let chunks = ChunkingService::chunk_text(text, &config, Some(&csv), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(chunks.len(), 3);
+        for chunk in &chunks {
+            let mut lines = chunk.content.lines();
+            assert_eq!(lines.next(), Some(header));
+            assert!(lines.all(|line| line != header));
+        }
+        assert!(chunks[0].content.contains("0,person0,city0"));
+        assert!(chunks[2].content.ends_with("11,person11,city11"));
+    }
+
+    #[tokio::test]
+    async fn test_language_multipliers_change_chunk_count() {
+        let text = "x".repeat(1200);
+        let mut config = create_basic_config(ChunkingStrategy::FixedSize, 100);
+        config.language_multipliers = [("ja".to_string(), 3.0), ("de".to_string(), 0.5)]
+            .into_iter()
+            .collect();
+
+        let mut counts = Vec::new();
+        for language in ["en", "ja", "de"] {
+            let metadata = serde_json::json!({ LANGUAGE_METADATA_KEY: language });
+            let chunks =
+                ChunkingService::chunk_text(text.clone(), &config, None, Some(metadata), None)
+                    .await
+                    .unwrap();
+            counts.push(chunks.len());
+        }
+        assert_eq!(counts, vec![12, 4, 24]);
+
+        // Without multipliers the language doesn't matter
+        config.language_multipliers.clear();
+        let metadata = serde_json::json!({ LANGUAGE_METADATA_KEY: "ja" });
+        let chunks = ChunkingService::chunk_text(text, &config, None, Some(metadata), None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 12);
+    }
 }
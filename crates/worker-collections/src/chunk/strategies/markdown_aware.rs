@@ -71,15 +71,7 @@ fn chunk_by_headers(
 
             if !current_chunk.is_empty() {
                 if current_chunk.trim().len() >= config.options.min_chunk_size {
-                    let structure_info = if !heading_hierarchy.is_empty() {
-                        Some(StructureInfo {
-                            heading_hierarchy: Some(heading_hierarchy.clone()),
-                            section_title: heading_hierarchy.last().cloned(),
-                            page_number: None,
-                        })
-                    } else {
-                        None
-                    };
+                    let structure_info = StructureInfo::from_headings(&heading_hierarchy);
 
                     chunks.push(ChunkWithStructure {
                         content: current_chunk.trim().to_string(),
@@ -104,15 +96,7 @@ fn chunk_by_headers(
             && current_chunk.len() > config.chunk_size
             && current_chunk.trim().len() >= config.options.min_chunk_size
         {
-            let structure_info = if !heading_hierarchy.is_empty() {
-                Some(StructureInfo {
-                    heading_hierarchy: Some(heading_hierarchy.clone()),
-                    section_title: heading_hierarchy.last().cloned(),
-                    page_number: None,
-                })
-            } else {
-                None
-            };
+            let structure_info = StructureInfo::from_headings(&heading_hierarchy);
 
             chunks.push(ChunkWithStructure {
                 content: current_chunk.trim().to_string(),
@@ -124,15 +108,7 @@ fn chunk_by_headers(
 
     if !current_chunk.trim().is_empty() {
         if current_chunk.trim().len() >= config.options.min_chunk_size || chunks.is_empty() {
-            let structure_info = if !heading_hierarchy.is_empty() {
-                Some(StructureInfo {
-                    heading_hierarchy: Some(heading_hierarchy.clone()),
-                    section_title: heading_hierarchy.last().cloned(),
-                    page_number: None,
-                })
-            } else {
-                None
-            };
+            let structure_info = StructureInfo::from_headings(&heading_hierarchy);
 
             chunks.push(ChunkWithStructure {
                 content: current_chunk.trim().to_string(),
@@ -5,5 +5,6 @@ pub mod overlap;
 pub mod recursive_character;
 pub mod semantic;
 pub mod sentence;
+pub mod structural;
 pub mod table_aware;
 pub mod token_based;
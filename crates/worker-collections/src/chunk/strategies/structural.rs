@@ -0,0 +1,220 @@
+use anyhow::Result;
+
+use crate::chunk::config::ChunkingConfig;
+use crate::chunk::metadata::{ChunkWithStructure, StructureInfo};
+use crate::chunk::strategies::token_based::chunk_by_sentences;
+use crate::chunk::tokenizer::ChunkTokenizer;
+
+/// A heading and the text up to the next heading, or the text before the
+/// first heading
+struct Section {
+    headings: Vec<String>,
+    lines: Vec<String>,
+}
+
+impl Section {
+    /// Whether the section has content beyond its heading line
+    fn has_body(&self) -> bool {
+        let skip = usize::from(!self.headings.is_empty());
+        self.lines.iter().skip(skip).any(|l| !l.trim().is_empty())
+    }
+}
+
+/// Structure-aware chunking for markdown-style text (as emitted by the
+/// markdown and HTML extractors). Each section is kept intact as one chunk
+/// while it fits the token budget; oversized sections fall back to sentence
+/// splitting. Every chunk carries its section's heading breadcrumb.
+pub fn chunk(text: String, config: &ChunkingConfig) -> Result<Vec<ChunkWithStructure>> {
+    let max_tokens = config
+        .options
+        .structural
+        .as_ref()
+        .map(|o| o.max_tokens)
+        .unwrap_or(config.chunk_size)
+        .max(1);
+    let tokenizer = ChunkTokenizer::load(None)?;
+
+    let mut chunks = Vec::new();
+    for section in parse_sections(&text) {
+        if !section.has_body() {
+            continue;
+        }
+        let content = section.lines.join("\n").trim().to_string();
+        let pieces = if tokenizer.count(&content) <= max_tokens {
+            vec![content]
+        } else {
+            chunk_by_sentences(&content, &tokenizer, max_tokens, 0)?
+        };
+
+        chunks.extend(pieces.into_iter().map(|content| ChunkWithStructure {
+            content,
+            structure_info: StructureInfo::from_headings(&section.headings),
+        }));
+    }
+
+    Ok(chunks)
+}
+
+/// Split text into sections at ATX headings (`#` to `######` followed by a
+/// space), ignoring `#` lines inside fenced code blocks
+fn parse_sections(text: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut heading_hierarchy: Vec<String> = Vec::new();
+    let mut current = Section {
+        headings: Vec::new(),
+        lines: Vec::new(),
+    };
+    let mut code_block_fence: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            match &code_block_fence {
+                None => {
+                    code_block_fence = Some(
+                        trimmed
+                            .chars()
+                            .take_while(|&c| c == '`' || c == '~')
+                            .collect(),
+                    )
+                }
+                Some(fence) if trimmed.starts_with(fence.as_str()) => code_block_fence = None,
+                Some(_) => {}
+            }
+        }
+
+        if code_block_fence.is_none()
+            && let Some((level, title)) = parse_heading(trimmed)
+        {
+            heading_hierarchy.truncate(level - 1);
+            heading_hierarchy.push(title);
+            sections.push(std::mem::replace(
+                &mut current,
+                Section {
+                    headings: heading_hierarchy.clone(),
+                    lines: Vec::new(),
+                },
+            ));
+        }
+        current.lines.push(line.to_string());
+    }
+    sections.push(current);
+
+    sections
+}
+
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim();
+    (!title.is_empty()).then(|| (level, title.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::config::{ChunkingOptions, ChunkingStrategy, StructuralOptions};
+
+    fn create_config(max_tokens: usize) -> ChunkingConfig {
+        ChunkingConfig {
+            strategy: ChunkingStrategy::Structural,
+            options: ChunkingOptions {
+                structural: Some(StructuralOptions { max_tokens }),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn breadcrumb(chunk: &ChunkWithStructure) -> Option<&str> {
+        chunk
+            .structure_info
+            .as_ref()
+            .and_then(|s| s.breadcrumb.as_deref())
+    }
+
+    #[test]
+    fn test_chunk_boundaries_align_with_headings() {
+        let text = "Preamble text.\n\n# Intro\nWhat this is.\n\n## Setup\nInstall it.\n\n### Config\nSet the options.\nThen restart.\n\n## Usage\nRun it.\n\n# FAQ\nAsk away."
+            .to_string();
+
+        let chunks = chunk(text, &create_config(200)).unwrap();
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "Preamble text.",
+                "# Intro\nWhat this is.",
+                "## Setup\nInstall it.",
+                "### Config\nSet the options.\nThen restart.",
+                "## Usage\nRun it.",
+                "# FAQ\nAsk away.",
+            ]
+        );
+
+        let breadcrumbs: Vec<Option<&str>> = chunks.iter().map(breadcrumb).collect();
+        assert_eq!(
+            breadcrumbs,
+            vec![
+                None,
+                Some("Intro"),
+                Some("Intro > Setup"),
+                Some("Intro > Setup > Config"),
+                Some("Intro > Usage"),
+                Some("FAQ"),
+            ]
+        );
+        let config_section = chunks[3].structure_info.as_ref().unwrap();
+        assert_eq!(config_section.section_title.as_deref(), Some("Config"));
+    }
+
+    #[test]
+    fn test_oversized_section_splits_on_sentences() {
+        let body = (1..=30)
+            .map(|i| format!("Sentence number {i} has a few words."))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = format!("# Big\n{body}\n\n# Small\nShort section.");
+
+        let chunks = chunk(text, &create_config(40)).unwrap();
+
+        let (big, small): (Vec<_>, Vec<_>) =
+            chunks.iter().partition(|c| breadcrumb(c) == Some("Big"));
+        assert!(big.len() > 1);
+        assert!(big.iter().all(|c| c.content.ends_with('.')));
+        assert!(!big.iter().any(|c| c.content.contains("Short section")));
+        assert_eq!(small.len(), 1);
+        assert_eq!(small[0].content, "# Small\nShort section.");
+    }
+
+    #[test]
+    fn test_headings_without_body_and_code_comments() {
+        let text =
+            "# Guide\n## Build\n```bash\n# not a heading\nmake\n```\n#hashtag is text".to_string();
+
+        let chunks = chunk(text, &create_config(200)).unwrap();
+
+        // The empty "Guide" section is only kept in the breadcrumb
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(breadcrumb(&chunks[0]), Some("Guide > Build"));
+        assert!(chunks[0].content.contains("# not a heading"));
+        assert!(chunks[0].content.ends_with("#hashtag is text"));
+    }
+
+    #[test]
+    fn test_text_without_headings() {
+        let chunks = chunk("Just plain text.".to_string(), &create_config(200)).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].structure_info.is_none());
+
+        assert!(
+            chunk(String::new(), &create_config(200))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
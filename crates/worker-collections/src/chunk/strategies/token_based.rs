@@ -1,12 +1,12 @@
 use anyhow::Result;
-use tiktoken_rs::{CoreBPE, cl100k_base};
 
 use crate::chunk::config::ChunkingConfig;
+use crate::chunk::tokenizer::ChunkTokenizer;
 
-/// Token-based chunking strategy that respects model token limits
-/// Uses tiktoken tokenizer (cl100k_base, used by GPT-4 and text-embedding-ada-002)
+/// Token-based chunking strategy that respects model token limits.
+/// Counts tokens with the HuggingFace tokenizer named in the options, or
+/// tiktoken's cl100k_base (used by GPT-4 and text-embedding-ada-002)
 pub fn chunk(text: String, config: &ChunkingConfig) -> Result<Vec<String>> {
-    let bpe = cl100k_base()?;
     let options = config.options.token_based.as_ref();
 
     // Get token limit - use chunk_size as default
@@ -24,17 +24,28 @@ pub fn chunk(text: String, config: &ChunkingConfig) -> Result<Vec<String>> {
         return Ok(Vec::new());
     }
 
-    if split_on_sentences {
-        chunk_by_sentences(&text, &bpe, max_tokens, overlap_tokens)
+    let tokenizer = ChunkTokenizer::load(options.and_then(|o| o.tokenizer.as_deref()))?;
+
+    let chunks = if split_on_sentences {
+        chunk_by_sentences(&text, &tokenizer, max_tokens, overlap_tokens)?
     } else {
-        chunk_by_tokens(&text, &bpe, max_tokens, overlap_tokens)
+        tokenizer.windows(&text, max_tokens, overlap_tokens)?
+    };
+
+    for chunk in &chunks {
+        semantic_explorer_core::observability::record_chunk_tokens(
+            tokenizer.name(),
+            tokenizer.count(chunk),
+        );
     }
+
+    Ok(chunks)
 }
 
 /// Chunk text by grouping sentences while respecting token limits
-fn chunk_by_sentences(
+pub(crate) fn chunk_by_sentences(
     text: &str,
-    bpe: &CoreBPE,
+    tokenizer: &ChunkTokenizer,
     max_tokens: usize,
     overlap_tokens: usize,
 ) -> Result<Vec<String>> {
@@ -55,7 +66,7 @@ fn chunk_by_sentences(
     let mut current_tokens = 0;
 
     for sentence in sentences {
-        let sentence_tokens = count_tokens(bpe, sentence);
+        let sentence_tokens = tokenizer.count(sentence);
 
         // If single sentence exceeds max, split it by tokens
         if sentence_tokens > max_tokens {
@@ -67,8 +78,7 @@ fn chunk_by_sentences(
             }
 
             // Split the long sentence into token-sized pieces
-            let sub_chunks = split_by_tokens(sentence, bpe, max_tokens, overlap_tokens)?;
-            chunks.extend(sub_chunks);
+            chunks.extend(tokenizer.windows(sentence, max_tokens, overlap_tokens)?);
             continue;
         }
 
@@ -78,8 +88,8 @@ fn chunk_by_sentences(
 
             // Apply overlap - take tokens from end of current chunk
             if overlap_tokens > 0 {
-                current_chunk = get_overlap_text(&current_chunk, bpe, overlap_tokens);
-                current_tokens = count_tokens(bpe, &current_chunk);
+                current_chunk = tokenizer.tail(&current_chunk, overlap_tokens);
+                current_tokens = tokenizer.count(&current_chunk);
             } else {
                 current_chunk = String::new();
                 current_tokens = 0;
@@ -103,78 +113,13 @@ fn chunk_by_sentences(
     Ok(chunks)
 }
 
-/// Chunk text purely by token count (no sentence awareness)
-fn chunk_by_tokens(
-    text: &str,
-    bpe: &CoreBPE,
-    max_tokens: usize,
-    overlap_tokens: usize,
-) -> Result<Vec<String>> {
-    let tokens = bpe.encode_with_special_tokens(text);
-
-    if tokens.len() <= max_tokens {
-        return Ok(vec![text.to_string()]);
-    }
-
-    let mut chunks = Vec::new();
-    let mut start = 0;
-
-    while start < tokens.len() {
-        let end = (start + max_tokens).min(tokens.len());
-        let chunk_tokens = &tokens[start..end];
-
-        // Decode tokens back to text
-        let chunk_text = bpe.decode(chunk_tokens.to_vec())?;
-        chunks.push(chunk_text.trim().to_string());
-
-        // Move start, accounting for overlap
-        if overlap_tokens > 0 && end < tokens.len() {
-            start = end.saturating_sub(overlap_tokens);
-        } else {
-            start = end;
-        }
-    }
-
-    Ok(chunks)
-}
-
-/// Split a single piece of text by token count
-fn split_by_tokens(
-    text: &str,
-    bpe: &CoreBPE,
-    max_tokens: usize,
-    overlap_tokens: usize,
-) -> Result<Vec<String>> {
-    chunk_by_tokens(text, bpe, max_tokens, overlap_tokens)
-}
-
-/// Get overlap text from the end of a chunk
-fn get_overlap_text(text: &str, bpe: &CoreBPE, overlap_tokens: usize) -> String {
-    let tokens = bpe.encode_with_special_tokens(text);
-    if tokens.len() <= overlap_tokens {
-        return text.to_string();
-    }
-
-    let overlap_start = tokens.len() - overlap_tokens;
-    let overlap_tokens = &tokens[overlap_start..];
-
-    bpe.decode(overlap_tokens.to_vec())
-        .unwrap_or_default()
-        .trim()
-        .to_string()
-}
-
-/// Count tokens in a piece of text
-fn count_tokens(bpe: &CoreBPE, text: &str) -> usize {
-    bpe.encode_with_special_tokens(text).len()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::chunk::config::{
         ChunkingConfig, ChunkingOptions, ChunkingStrategy, TokenBasedOptions,
     };
+    use tiktoken_rs::cl100k_base;
 
     fn create_token_config(
         max_tokens: usize,
@@ -191,9 +136,11 @@ mod tests {
                     overlap_tokens: overlap,
                     split_on_sentences: split_sentences,
                     model: None,
+                    tokenizer: None,
                 }),
                 ..Default::default()
             },
+            ..Default::default()
         }
     }
 
@@ -281,4 +228,19 @@ mod tests {
         let result = chunk(text, &config);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_sentence_overlap_counts_model_tokens() {
+        let text = "Red fox runs. Blue bird sings loudly. Green frog jumps high now.";
+        let tokenizer = crate::chunk::tokenizer::tests::word_tokenizer(text);
+
+        let chunks = chunk_by_sentences(text, &tokenizer, 8, 2).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                "Red fox runs. Blue bird sings loudly.",
+                "sings loudly. Green frog jumps high now.",
+            ]
+        );
+    }
 }
@@ -0,0 +1,255 @@
+//! Tokenizers used to measure and split chunks in model tokens.
+//!
+//! By default chunks are counted with tiktoken's `cl100k_base`. Setting
+//! `tokenizer` in the token-based options uses the HuggingFace tokenizer of
+//! the target embedding model instead, so chunk limits match what the
+//! embedder will actually see. HuggingFace tokenizers are loaded once per
+//! name and shared across jobs.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::{Result, anyhow};
+use tiktoken_rs::{CoreBPE, cl100k_base};
+use tokenizers::{FromPretrainedParameters, Tokenizer};
+
+/// Loaded HuggingFace tokenizers keyed by model name (or file path)
+static HF_TOKENIZERS: OnceLock<Mutex<HashMap<String, Arc<Tokenizer>>>> = OnceLock::new();
+
+pub enum ChunkTokenizer {
+    Tiktoken(CoreBPE),
+    HuggingFace {
+        name: String,
+        tokenizer: Arc<Tokenizer>,
+    },
+}
+
+impl ChunkTokenizer {
+    /// `cl100k_base` when `name` is `None`, otherwise the HuggingFace
+    /// tokenizer of that model id or `tokenizer.json` path
+    pub fn load(name: Option<&str>) -> Result<Self> {
+        match name.map(str::trim).filter(|n| !n.is_empty()) {
+            None => Ok(Self::Tiktoken(cl100k_base()?)),
+            Some(name) => Ok(Self::HuggingFace {
+                name: name.to_string(),
+                tokenizer: load_hf_tokenizer(name)?,
+            }),
+        }
+    }
+
+    /// Name used to label metrics
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Tiktoken(_) => "cl100k_base",
+            Self::HuggingFace { name, .. } => name,
+        }
+    }
+
+    /// Number of tokens in `text`, excluding special tokens the model adds
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Tiktoken(bpe) => bpe.encode_with_special_tokens(text).len(),
+            Self::HuggingFace { tokenizer, .. } => tokenizer
+                .encode(text, false)
+                .map(|encoding| encoding.len())
+                .unwrap_or_else(|_| text.split_whitespace().count()),
+        }
+    }
+
+    /// Split `text` into windows of at most `max_tokens` tokens where each
+    /// window starts exactly `overlap_tokens` tokens before the previous one
+    /// ended.
+    pub fn windows(
+        &self,
+        text: &str,
+        max_tokens: usize,
+        overlap_tokens: usize,
+    ) -> Result<Vec<String>> {
+        let max_tokens = max_tokens.max(1);
+        // Overlap must leave room for progress
+        let step = max_tokens - overlap_tokens.min(max_tokens - 1);
+
+        match self {
+            Self::Tiktoken(bpe) => {
+                let tokens = bpe.encode_with_special_tokens(text);
+                if tokens.len() <= max_tokens {
+                    return Ok(vec![text.to_string()]);
+                }
+                let mut chunks = Vec::new();
+                let mut start = 0;
+                loop {
+                    let end = (start + max_tokens).min(tokens.len());
+                    chunks.push(bpe.decode(tokens[start..end].to_vec())?.trim().to_string());
+                    if end == tokens.len() {
+                        break;
+                    }
+                    start += step;
+                }
+                Ok(chunks)
+            }
+            Self::HuggingFace { tokenizer, .. } => {
+                let encoding = tokenizer
+                    .encode(text, false)
+                    .map_err(|e| anyhow!("Failed to tokenize text: {e}"))?;
+                let offsets = encoding.get_offsets();
+                if offsets.len() <= max_tokens {
+                    return Ok(vec![text.to_string()]);
+                }
+                // Slice the original text by token offsets rather than
+                // decoding, which would normalize it (e.g. lowercase)
+                let mut chunks = Vec::new();
+                let mut start = 0;
+                loop {
+                    let end = (start + max_tokens).min(offsets.len());
+                    let span = text
+                        .get(offsets[start].0..offsets[end - 1].1)
+                        .ok_or_else(|| anyhow!("Tokenizer returned invalid offsets"))?;
+                    chunks.push(span.trim().to_string());
+                    if end == offsets.len() {
+                        break;
+                    }
+                    start += step;
+                }
+                Ok(chunks)
+            }
+        }
+    }
+
+    /// The last `count` tokens of `text`
+    pub fn tail(&self, text: &str, count: usize) -> String {
+        if count == 0 {
+            return String::new();
+        }
+        match self {
+            Self::Tiktoken(bpe) => {
+                let tokens = bpe.encode_with_special_tokens(text);
+                if tokens.len() <= count {
+                    return text.to_string();
+                }
+                bpe.decode(tokens[tokens.len() - count..].to_vec())
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            }
+            Self::HuggingFace { tokenizer, .. } => {
+                let Ok(encoding) = tokenizer.encode(text, false) else {
+                    return String::new();
+                };
+                let offsets = encoding.get_offsets();
+                if offsets.len() <= count {
+                    return text.to_string();
+                }
+                text.get(offsets[offsets.len() - count].0..)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            }
+        }
+    }
+}
+
+fn load_hf_tokenizer(name: &str) -> Result<Arc<Tokenizer>> {
+    let cache = HF_TOKENIZERS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(tokenizer) = cache.lock().expect("tokenizer cache poisoned").get(name) {
+        return Ok(tokenizer.clone());
+    }
+
+    // Load outside the lock: downloading from the hub can take a while
+    let tokenizer = if Path::new(name).is_file() {
+        Tokenizer::from_file(name)
+    } else {
+        Tokenizer::from_pretrained(
+            name,
+            Some(FromPretrainedParameters {
+                token: std::env::var("HF_TOKEN").ok(),
+                ..Default::default()
+            }),
+        )
+    }
+    .map_err(|e| anyhow!("Failed to load tokenizer '{name}': {e}"))?;
+    tracing::info!(tokenizer = %name, "Loaded HuggingFace tokenizer for chunking");
+
+    let tokenizer = Arc::new(tokenizer);
+    cache
+        .lock()
+        .expect("tokenizer cache poisoned")
+        .insert(name.to_string(), tokenizer.clone());
+    Ok(tokenizer)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::WhitespaceSplit;
+
+    /// Word-level tokenizer: one token per whitespace-separated word
+    pub(crate) fn word_tokenizer(text: &str) -> ChunkTokenizer {
+        let mut vocab: HashMap<String, u32> = HashMap::from([("[UNK]".to_string(), 0)]);
+        for word in text.split_whitespace() {
+            let next = vocab.len() as u32;
+            vocab.entry(word.to_string()).or_insert(next);
+        }
+        let model = WordLevel::builder()
+            .vocab(vocab.into_iter().collect())
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Some(WhitespaceSplit));
+        ChunkTokenizer::HuggingFace {
+            name: "test-words".to_string(),
+            tokenizer: Arc::new(tokenizer),
+        }
+    }
+
+    #[test]
+    fn test_windows_share_exactly_overlap_tokens() {
+        let text = "Alpha beta gamma delta epsilon zeta eta theta iota kappa";
+        let tokenizer = word_tokenizer(text);
+
+        let windows = tokenizer.windows(text, 4, 2).unwrap();
+        assert_eq!(
+            windows,
+            vec![
+                "Alpha beta gamma delta",
+                "gamma delta epsilon zeta",
+                "epsilon zeta eta theta",
+                "eta theta iota kappa",
+            ]
+        );
+        // Original casing and spacing are kept
+        assert!(windows.iter().all(|w| tokenizer.count(w) <= 4));
+    }
+
+    #[test]
+    fn test_overlap_is_capped_below_window_size() {
+        let text = "one two three four five";
+        let tokenizer = word_tokenizer(text);
+
+        let windows = tokenizer.windows(text, 2, 5).unwrap();
+        assert_eq!(
+            windows,
+            vec!["one two", "two three", "three four", "four five"]
+        );
+        assert_eq!(tokenizer.tail(text, 2), "four five");
+        assert_eq!(tokenizer.windows("one two", 2, 1).unwrap(), vec!["one two"]);
+    }
+
+    #[test]
+    fn test_tiktoken_windows_overlap() {
+        let tokenizer = ChunkTokenizer::load(None).unwrap();
+        assert_eq!(tokenizer.name(), "cl100k_base");
+
+        let text = "one two three four five six seven eight nine ten";
+        let windows = tokenizer.windows(text, 4, 1).unwrap();
+        assert!(windows.len() > 2);
+        assert!(windows.iter().all(|w| tokenizer.count(w) <= 4));
+        // The last token of each window starts the next one
+        for pair in windows.windows(2) {
+            let last_word = pair[0].split_whitespace().last().unwrap();
+            assert!(pair[1].starts_with(last_word));
+        }
+    }
+}
@@ -4,10 +4,15 @@ use serde_json::{Value, json};
 use std::io::{Cursor, Read};
 use tar::Archive as TarArchive;
 use zip::ZipArchive;
+use zip::result::ZipError;
 
 use crate::extract::config::{ExtractionConfig, ExtractionOptions};
+use crate::extract::limit::{self, BoundedText};
 use crate::extract::plain_text;
 
+/// Metadata key for the number of entries skipped at `max_total_size`
+pub const SKIPPED_COUNT_METADATA_KEY: &str = "skipped_count";
+
 /// Result of archive extraction
 #[derive(Debug)]
 pub struct ArchiveExtractionResult {
@@ -26,11 +31,133 @@ pub struct ArchiveFileResult {
     pub size: usize,
 }
 
+/// What was extracted from an archive, built up entry by entry so it is
+/// available without holding the extracted text
+#[derive(Debug, Default)]
+pub struct ArchiveSummary {
+    pub files: Vec<ArchiveFileSummary>,
+    pub failed_files: Vec<ArchiveFileError>,
+    /// Entries left unextracted because `max_total_size` was reached
+    pub skipped_count: usize,
+}
+
+/// An extracted file, less its text
+#[derive(Debug)]
+pub struct ArchiveFileSummary {
+    pub path: String,
+    pub mime_type: String,
+    pub size: usize,
+    pub text_length: usize,
+}
+
+impl ArchiveSummary {
+    fn record(&mut self, file: &ArchiveFileResult) {
+        self.files.push(ArchiveFileSummary {
+            path: file.path.clone(),
+            mime_type: file.mime_type.clone(),
+            size: file.size,
+            text_length: file.text.len(),
+        });
+    }
+
+    /// Entries skipped because they need a password
+    pub fn encrypted_count(&self) -> usize {
+        self.failed_files
+            .iter()
+            .filter(|f| f.is_encrypted())
+            .count()
+    }
+
+    /// Whether extraction stopped at `max_total_size` before the last entry
+    pub fn is_truncated(&self) -> bool {
+        self.skipped_count > 0
+    }
+
+    /// Stop at the total size limit, leaving `skipped` entries unextracted
+    fn truncate(&mut self, format: &str, skipped: usize) {
+        self.skipped_count = skipped;
+        semantic_explorer_core::observability::record_archive_truncation(format);
+    }
+
+    /// Extraction metadata for an archive of `format`
+    pub fn metadata(&self, format: &str) -> Value {
+        json!({
+            "format": format,
+            "file_count": self.files.len(),
+            "failed_count": self.failed_files.len() - self.encrypted_count(),
+            "encrypted_count": self.encrypted_count(),
+            SKIPPED_COUNT_METADATA_KEY: self.skipped_count,
+            "files": self.files.iter().map(|f| json!({
+                "path": f.path,
+                "mime_type": f.mime_type,
+                "size": f.size,
+                "text_length": f.text_length,
+            })).collect::<Vec<_>>(),
+            "failed_files": self.failed_files.iter().map(|f| json!({
+                "path": f.path,
+                "error": f.error,
+                "reason": f.reason.as_str(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
 /// Error for a file that couldn't be extracted
 #[derive(Debug)]
 pub struct ArchiveFileError {
     pub path: String,
     pub error: String,
+    pub reason: ArchiveFileErrorReason,
+}
+
+/// Why a file in the archive couldn't be extracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFileErrorReason {
+    /// The entry is encrypted and no `zip_password` was given
+    Encrypted,
+    /// The entry is encrypted and `zip_password` doesn't decrypt it
+    InvalidPassword,
+    /// The entry is over `max_file_size` or decompresses suspiciously far
+    TooLarge,
+    /// Any other read or extraction error
+    Failed,
+}
+
+impl ArchiveFileErrorReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Encrypted => "encrypted",
+            Self::InvalidPassword => "invalid_password",
+            Self::TooLarge => "too_large",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl ArchiveFileError {
+    fn too_large(path: &str, error: String) -> Self {
+        Self {
+            path: path.to_string(),
+            error,
+            reason: ArchiveFileErrorReason::TooLarge,
+        }
+    }
+
+    fn failed(path: impl Into<String>, error: impl ToString) -> Self {
+        Self {
+            path: path.into(),
+            error: error.to_string(),
+            reason: ArchiveFileErrorReason::Failed,
+        }
+    }
+
+    /// Skipped because it needs a password; counted as `encrypted_count`
+    fn is_encrypted(&self) -> bool {
+        matches!(
+            self.reason,
+            ArchiveFileErrorReason::Encrypted | ArchiveFileErrorReason::InvalidPassword
+        )
+    }
 }
 
 /// Configuration for archive extraction
@@ -40,10 +167,17 @@ pub struct ArchiveOptions {
     pub max_depth: usize,
     /// Maximum total size to extract (in bytes)
     pub max_total_size: usize,
+    /// Maximum uncompressed size of a single entry (in bytes)
+    pub max_file_size: usize,
+    /// Entries that decompress to more than this many times their compressed
+    /// size are skipped as likely zip bombs
+    pub max_compression_ratio: u64,
     /// File extensions to skip
     pub skip_extensions: Vec<String>,
     /// Continue extracting after file errors
     pub continue_on_error: bool,
+    /// Password for encrypted ZIP entries; without one they are skipped
+    pub zip_password: Option<String>,
 }
 
 impl Default for ArchiveOptions {
@@ -51,6 +185,8 @@ impl Default for ArchiveOptions {
         Self {
             max_depth: 3,
             max_total_size: 100 * 1024 * 1024, // 100MB
+            max_file_size: 50 * 1024 * 1024,   // 50MB
+            max_compression_ratio: 100,
             skip_extensions: vec![
                 "exe".into(),
                 "dll".into(),
@@ -78,6 +214,7 @@ impl Default for ArchiveOptions {
                 "wmv".into(),
             ],
             continue_on_error: true,
+            zip_password: None,
         }
     }
 }
@@ -91,12 +228,46 @@ pub(crate) fn extract_from_zip(
     extract_from_zip_with_depth(bytes, options, archive_options, 0)
 }
 
+/// Extract a ZIP archive entry by entry, handing each extracted file to
+/// `on_file` instead of combining them, so only one entry's text is held at
+/// a time. An error from `on_file` stops the extraction.
+pub(crate) fn extract_from_zip_streaming(
+    bytes: &[u8],
+    options: &ExtractionOptions,
+    archive_options: &ArchiveOptions,
+    mut on_file: impl FnMut(ArchiveFileResult) -> Result<()>,
+) -> Result<ArchiveSummary> {
+    walk_zip(bytes, options, archive_options, 0, &mut on_file)
+}
+
+/// Number of entries in a ZIP archive, or None if it can't be opened
+pub(crate) fn zip_entry_count(bytes: &[u8]) -> Option<usize> {
+    ZipArchive::new(Cursor::new(bytes))
+        .ok()
+        .map(|archive| archive.len())
+}
+
 fn extract_from_zip_with_depth(
     bytes: &[u8],
     options: &ExtractionOptions,
     archive_options: &ArchiveOptions,
     depth: usize,
 ) -> Result<ArchiveExtractionResult> {
+    let mut files = Vec::new();
+    let summary = walk_zip(bytes, options, archive_options, depth, &mut |file| {
+        files.push(file);
+        Ok(())
+    })?;
+    build_result(files, summary, "zip", options)
+}
+
+fn walk_zip(
+    bytes: &[u8],
+    options: &ExtractionOptions,
+    archive_options: &ArchiveOptions,
+    depth: usize,
+    on_file: &mut dyn FnMut(ArchiveFileResult) -> Result<()>,
+) -> Result<ArchiveSummary> {
     if depth >= archive_options.max_depth {
         return Err(anyhow!(
             "Maximum archive depth ({}) exceeded",
@@ -108,22 +279,51 @@ fn extract_from_zip_with_depth(
     let mut archive =
         ZipArchive::new(reader).map_err(|e| anyhow!("Failed to open ZIP archive: {}", e))?;
 
-    let mut files = Vec::new();
-    let mut failed_files = Vec::new();
+    let mut summary = ArchiveSummary::default();
     let mut total_size = 0usize;
+    let mut text_bytes = 0usize;
 
     for i in 0..archive.len() {
-        let mut file = match archive.by_index(i) {
+        let (encrypted, raw_name) = match archive.by_index_raw(i) {
+            Ok(f) if f.is_dir() || should_skip_file(f.name(), archive_options) => continue,
+            Ok(f) => (f.encrypted(), f.name().to_string()),
+            Err(_) => (false, format!("file_index_{}", i)),
+        };
+        let entry = match (encrypted, archive_options.zip_password.as_deref()) {
+            (true, Some(password)) => archive.by_index_decrypt(i, password.as_bytes()),
+            (true, None) => Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)),
+            (false, _) => archive.by_index(i),
+        };
+        let mut file = match entry {
             Ok(f) => f,
             Err(e) => {
-                if archive_options.continue_on_error {
-                    failed_files.push(ArchiveFileError {
-                        path: format!("file_index_{}", i),
+                let error = match e {
+                    ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => ArchiveFileError {
+                        path: raw_name,
+                        error: "Entry is encrypted; set zip_password to extract it".to_string(),
+                        reason: ArchiveFileErrorReason::Encrypted,
+                    },
+                    ZipError::InvalidPassword => ArchiveFileError {
+                        path: raw_name,
+                        error: "zip_password does not decrypt this entry".to_string(),
+                        reason: ArchiveFileErrorReason::InvalidPassword,
+                    },
+                    e if encrypted => ArchiveFileError {
+                        path: raw_name,
                         error: e.to_string(),
-                    });
+                        reason: ArchiveFileErrorReason::Encrypted,
+                    },
+                    e => ArchiveFileError::failed(raw_name, e),
+                };
+                if archive_options.continue_on_error {
+                    summary.failed_files.push(error);
                     continue;
                 }
-                return Err(anyhow!("Failed to read archive entry: {}", e));
+                return Err(anyhow!(
+                    "Failed to read archive entry {}: {}",
+                    error.path,
+                    error.error
+                ));
             }
         };
 
@@ -140,34 +340,61 @@ fn extract_from_zip_with_depth(
         }
 
         // Read file contents
-        let mut buffer = Vec::new();
-        if let Err(e) = file.read_to_end(&mut buffer) {
-            if archive_options.continue_on_error {
-                failed_files.push(ArchiveFileError {
-                    path: path.clone(),
-                    error: e.to_string(),
-                });
-                continue;
+        let declared_size = file.size();
+        let compressed_size = file.compressed_size();
+        let buffer = match read_entry(
+            &mut file,
+            &path,
+            declared_size,
+            Some(compressed_size),
+            archive_options,
+        ) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                if archive_options.continue_on_error
+                    || error.reason == ArchiveFileErrorReason::TooLarge
+                {
+                    summary.failed_files.push(error);
+                    continue;
+                }
+                return Err(anyhow!("Failed to read file {}: {}", path, error.error));
             }
-            return Err(anyhow!("Failed to read file {}: {}", path, e));
-        }
+        };
 
-        // Check size limits
+        // Stop at the size limit without failing, counting this entry and
+        // the rest as skipped
         total_size += buffer.len();
         if total_size > archive_options.max_total_size {
-            break; // Stop processing, don't fail
+            drop(file);
+            let remaining = (i + 1..archive.len())
+                .filter(|&j| {
+                    archive
+                        .by_index_raw(j)
+                        .is_ok_and(|f| !f.is_dir() && !should_skip_file(f.name(), archive_options))
+                })
+                .count();
+            summary.truncate("zip", 1 + remaining);
+            break;
         }
 
         // Detect content type and extract
         let result = extract_file_content(&path, &buffer, options, archive_options, depth);
         match result {
-            Ok(file_result) => files.push(file_result),
+            Ok(file_result) => {
+                text_bytes += file_result.text.len();
+                summary.record(&file_result);
+                on_file(file_result)?;
+                // Enough text for the output limit; skip the remaining entries
+                if options
+                    .max_output_bytes
+                    .is_some_and(|max| text_bytes >= max)
+                {
+                    break;
+                }
+            }
             Err(e) => {
                 if archive_options.continue_on_error {
-                    failed_files.push(ArchiveFileError {
-                        path,
-                        error: e.to_string(),
-                    });
+                    summary.failed_files.push(ArchiveFileError::failed(path, e));
                 } else {
                     return Err(e);
                 }
@@ -175,12 +402,26 @@ fn extract_from_zip_with_depth(
         }
     }
 
-    build_result(files, failed_files, "zip", options)
+    // Every entry needed a password: fail with the reason rather than
+    // returning an empty text
+    let encrypted_count = summary.encrypted_count();
+    if summary.files.is_empty() && encrypted_count > 0 {
+        return Err(anyhow!(
+            "ZIP archive is password-protected ({} encrypted entries could not be extracted); set zip_password in the extraction options",
+            encrypted_count
+        ));
+    }
+
+    Ok(summary)
 }
 
 /// Extract contents from a gzipped file
-pub(crate) fn extract_from_gzip(bytes: &[u8], _options: &ExtractionOptions) -> Result<Vec<u8>> {
-    let mut decoder = GzDecoder::new(bytes);
+pub(crate) fn extract_from_gzip(bytes: &[u8], options: &ExtractionOptions) -> Result<Vec<u8>> {
+    // Read one byte past the output limit so callers can still tell the text was cut
+    let limit = options
+        .max_output_bytes
+        .map_or(u64::MAX, |max| (max as u64).saturating_add(1));
+    let mut decoder = GzDecoder::new(bytes).take(limit);
     let mut decompressed = Vec::new();
     decoder
         .read_to_end(&mut decompressed)
@@ -210,26 +451,71 @@ fn extract_from_tar_gz_with_depth(
         ));
     }
 
-    let decoder = GzDecoder::new(bytes);
-    let mut archive = TarArchive::new(decoder);
+    extract_tar_entries(
+        TarArchive::new(GzDecoder::new(bytes)),
+        "tar.gz",
+        options,
+        archive_options,
+        depth,
+    )
+}
+
+/// Extract contents from an uncompressed tar archive
+pub(crate) fn extract_from_tar(
+    bytes: &[u8],
+    options: &ExtractionOptions,
+    archive_options: &ArchiveOptions,
+) -> Result<ArchiveExtractionResult> {
+    extract_from_tar_with_depth(bytes, options, archive_options, 0)
+}
+
+fn extract_from_tar_with_depth(
+    bytes: &[u8],
+    options: &ExtractionOptions,
+    archive_options: &ArchiveOptions,
+    depth: usize,
+) -> Result<ArchiveExtractionResult> {
+    if depth >= archive_options.max_depth {
+        return Err(anyhow!(
+            "Maximum archive depth ({}) exceeded",
+            archive_options.max_depth
+        ));
+    }
+
+    extract_tar_entries(
+        TarArchive::new(bytes),
+        "tar",
+        options,
+        archive_options,
+        depth,
+    )
+}
 
+/// Extract every entry of a (possibly compressed) tar stream
+fn extract_tar_entries<R: Read>(
+    mut archive: TarArchive<R>,
+    format: &str,
+    options: &ExtractionOptions,
+    archive_options: &ArchiveOptions,
+    depth: usize,
+) -> Result<ArchiveExtractionResult> {
     let mut files = Vec::new();
-    let mut failed_files = Vec::new();
+    let mut summary = ArchiveSummary::default();
     let mut total_size = 0usize;
+    let mut text_bytes = 0usize;
 
-    let entries = archive
+    let mut entries = archive
         .entries()
         .map_err(|e| anyhow!("Failed to read tar entries: {}", e))?;
 
-    for entry_result in entries {
+    while let Some(entry_result) = entries.next() {
         let mut entry = match entry_result {
             Ok(e) => e,
             Err(e) => {
                 if archive_options.continue_on_error {
-                    failed_files.push(ArchiveFileError {
-                        path: "unknown".into(),
-                        error: e.to_string(),
-                    });
+                    summary
+                        .failed_files
+                        .push(ArchiveFileError::failed("unknown", e));
                     continue;
                 }
                 return Err(anyhow!("Failed to read tar entry: {}", e));
@@ -241,10 +527,9 @@ fn extract_from_tar_gz_with_depth(
             Ok(p) => p.to_string_lossy().to_string(),
             Err(e) => {
                 if archive_options.continue_on_error {
-                    failed_files.push(ArchiveFileError {
-                        path: "unknown".into(),
-                        error: e.to_string(),
-                    });
+                    summary
+                        .failed_files
+                        .push(ArchiveFileError::failed("unknown", e));
                     continue;
                 }
                 return Err(anyhow!("Failed to get entry path: {}", e));
@@ -261,35 +546,57 @@ fn extract_from_tar_gz_with_depth(
             continue;
         }
 
-        // Read file contents
-        let mut buffer = Vec::new();
-        if let Err(e) = entry.read_to_end(&mut buffer) {
-            if archive_options.continue_on_error {
-                failed_files.push(ArchiveFileError {
-                    path: path.clone(),
-                    error: e.to_string(),
-                });
-                continue;
+        // Read file contents; tar entries aren't compressed individually
+        let declared_size = entry.header().size().unwrap_or(0);
+        let buffer = match read_entry(&mut entry, &path, declared_size, None, archive_options) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                if archive_options.continue_on_error
+                    || error.reason == ArchiveFileErrorReason::TooLarge
+                {
+                    summary.failed_files.push(error);
+                    continue;
+                }
+                return Err(anyhow!("Failed to read file {}: {}", path, error.error));
             }
-            return Err(anyhow!("Failed to read file {}: {}", path, e));
-        }
+        };
 
-        // Check size limits
+        // Stop at the size limit without failing; tar has no index, so the
+        // rest of the stream is walked (without reading contents) to count
+        // what is skipped
         total_size += buffer.len();
         if total_size > archive_options.max_total_size {
+            let remaining = entries
+                .by_ref()
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    !e.header().entry_type().is_dir()
+                        && e.path()
+                            .is_ok_and(|p| !should_skip_file(&p.to_string_lossy(), archive_options))
+                })
+                .count();
+            summary.truncate(format, 1 + remaining);
             break;
         }
 
         // Detect content type and extract
         let result = extract_file_content(&path, &buffer, options, archive_options, depth);
         match result {
-            Ok(file_result) => files.push(file_result),
+            Ok(file_result) => {
+                text_bytes += file_result.text.len();
+                summary.record(&file_result);
+                files.push(file_result);
+                // Enough text for the output limit; skip the remaining entries
+                if options
+                    .max_output_bytes
+                    .is_some_and(|max| text_bytes >= max)
+                {
+                    break;
+                }
+            }
             Err(e) => {
                 if archive_options.continue_on_error {
-                    failed_files.push(ArchiveFileError {
-                        path,
-                        error: e.to_string(),
-                    });
+                    summary.failed_files.push(ArchiveFileError::failed(path, e));
                 } else {
                     return Err(e);
                 }
@@ -297,7 +604,57 @@ fn extract_from_tar_gz_with_depth(
         }
     }
 
-    build_result(files, failed_files, "tar.gz", options)
+    build_result(files, summary, format, options)
+}
+
+/// Decompressed size below which the compression ratio isn't checked, since
+/// small repetitive files legitimately compress very well
+const MIN_RATIO_CHECK_BYTES: u64 = 1024 * 1024;
+
+/// Read one entry into memory, stopping as soon as it passes
+/// `max_file_size` or `max_compression_ratio` times `compressed_size`. The
+/// sizes declared in the archive can lie, so the limits are enforced on the
+/// bytes actually read, not only on `declared_size`.
+fn read_entry(
+    reader: impl Read,
+    path: &str,
+    declared_size: u64,
+    compressed_size: Option<u64>,
+    archive_options: &ArchiveOptions,
+) -> Result<Vec<u8>, ArchiveFileError> {
+    let max_file_size = archive_options.max_file_size as u64;
+    if declared_size > max_file_size {
+        return Err(ArchiveFileError::too_large(
+            path,
+            format!(
+                "Entry is {} bytes, over the {} byte limit",
+                declared_size, max_file_size
+            ),
+        ));
+    }
+    let ratio_limit = compressed_size.map(|compressed| {
+        compressed
+            .saturating_mul(archive_options.max_compression_ratio)
+            .max(MIN_RATIO_CHECK_BYTES)
+    });
+    let limit = ratio_limit.map_or(max_file_size, |ratio| ratio.min(max_file_size));
+
+    let mut buffer = Vec::with_capacity(declared_size.min(limit) as usize);
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut buffer)
+        .map_err(|e| ArchiveFileError::failed(path, e))?;
+    if buffer.len() as u64 > limit {
+        let error = match ratio_limit {
+            Some(ratio) if ratio < max_file_size => format!(
+                "Entry decompresses to more than {} times its compressed size",
+                archive_options.max_compression_ratio
+            ),
+            _ => format!("Entry is over the {} byte limit", max_file_size),
+        };
+        return Err(ArchiveFileError::too_large(path, error));
+    }
+    Ok(buffer)
 }
 
 /// Check if file should be skipped based on extension
@@ -393,6 +750,16 @@ fn extract_file_content(
                     size: buffer.len(),
                 });
             }
+            "x-tar" => {
+                let nested =
+                    extract_from_tar_with_depth(buffer, options, archive_options, depth + 1)?;
+                return Ok(ArchiveFileResult {
+                    path: path.to_string(),
+                    text: nested.text,
+                    mime_type: "application/x-tar".to_string(),
+                    size: buffer.len(),
+                });
+            }
             "gzip" => {
                 // Check if it's a .tar.gz
                 if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
@@ -411,7 +778,8 @@ fn extract_file_content(
                 }
                 // Regular gzip - decompress and try to extract as text
                 let decompressed = extract_from_gzip(buffer, options)?;
-                let text = String::from_utf8_lossy(&decompressed).to_string();
+                let mut text = String::from_utf8_lossy(&decompressed).to_string();
+                limit::truncate_to_limit(&mut text, options.max_output_bytes);
                 return Ok(ArchiveFileResult {
                     path: path.to_string(),
                     text,
@@ -423,13 +791,17 @@ fn extract_file_content(
         }
     }
 
-    // Try to extract text using the existing extractors
+    // Try to extract text using the existing extractors. Entry metadata is
+    // dropped, so the language is only detected over the combined text.
     let extraction_config = ExtractionConfig {
-        options: options.clone(),
+        options: ExtractionOptions {
+            detect_language: false,
+            ..options.clone()
+        },
         ..Default::default()
     };
 
-    let text = if let Some(mime) = mime_type.clone() {
+    let mut text = if let Some(mime) = mime_type.clone() {
         match plain_text::extract(&mime, buffer, &extraction_config) {
             Ok(content) => content.text,
             Err(_) => {
@@ -445,6 +817,7 @@ fn extract_file_content(
             return Err(anyhow!("Cannot extract text from binary file: {}", path));
         }
     };
+    limit::truncate_to_limit(&mut text, options.max_output_bytes);
 
     Ok(ArchiveFileResult {
         path: path.to_string(),
@@ -478,38 +851,40 @@ fn is_likely_text(buffer: &[u8]) -> bool {
 /// Build the final extraction result
 fn build_result(
     files: Vec<ArchiveFileResult>,
-    failed_files: Vec<ArchiveFileError>,
+    summary: ArchiveSummary,
     format: &str,
     options: &ExtractionOptions,
 ) -> Result<ArchiveExtractionResult> {
-    // Combine all text with file path headers
-    let text_parts: Vec<String> = files
-        .iter()
-        .filter(|f| !f.text.trim().is_empty())
-        .map(|f| format!("--- {} ---\n{}", f.path, f.text))
-        .collect();
-
-    let text = text_parts.join("\n\n");
+    // Combine all text with file path headers, stopping at the output limit
+    let mut combined = BoundedText::new(options.max_output_bytes);
+    for file in files.iter().filter(|f| !f.text.trim().is_empty()) {
+        let part = format!("--- {} ---\n{}", file.path, file.text);
+        if !combined.push_part("\n\n", &part) {
+            break;
+        }
+    }
+    let (text, truncated) = combined.finish();
 
     let metadata = if options.include_metadata {
-        Some(json!({
-            "format": format,
-            "file_count": files.len(),
-            "failed_count": failed_files.len(),
-            "files": files.iter().map(|f| json!({
-                "path": f.path,
-                "mime_type": f.mime_type,
-                "size": f.size,
-                "text_length": f.text.len(),
-            })).collect::<Vec<_>>(),
-            "failed_files": failed_files.iter().map(|f| json!({
-                "path": f.path,
-                "error": f.error,
-            })).collect::<Vec<_>>(),
-        }))
+        Some(summary.metadata(format))
     } else {
         None
     };
+    // Flagged even without `include_metadata`, so consumers can tell the
+    // text is missing entries
+    let metadata = if summary.is_truncated() {
+        limit::mark_truncated(metadata, None).map(|mut metadata| {
+            metadata[SKIPPED_COUNT_METADATA_KEY] = Value::from(summary.skipped_count);
+            metadata
+        })
+    } else {
+        metadata
+    };
+    let metadata = if truncated {
+        limit::mark_truncated(metadata, options.max_output_bytes)
+    } else {
+        metadata
+    };
 
     Ok(ArchiveExtractionResult { text, metadata })
 }
@@ -535,6 +910,36 @@ mod tests {
         buffer.into_inner()
     }
 
+    fn create_encrypted_test_zip(files: &[(&str, &[u8], Option<&[u8]>)]) -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            for (name, content, password) in files {
+                let mut options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Stored);
+                if let Some(password) = password {
+                    options = options.with_deprecated_encryption(password);
+                }
+                zip.start_file(*name, options).unwrap();
+                zip.write_all(content).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        buffer.into_inner()
+    }
+
+    fn create_test_tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
     #[test]
     fn test_extract_simple_zip() {
         let zip_data = create_test_zip(&[
@@ -617,6 +1022,222 @@ mod tests {
         assert_eq!(meta["file_count"], 1);
     }
 
+    #[test]
+    fn test_encrypted_entries_are_skipped_and_reported() {
+        let zip_data = create_encrypted_test_zip(&[
+            ("public.txt", b"Visible notes", None),
+            ("secret.txt", b"Launch codes", Some(b"hunter2")),
+        ]);
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+
+        let extraction = extract_from_zip(&zip_data, &options, &ArchiveOptions::default()).unwrap();
+        assert!(extraction.text.contains("Visible notes"));
+        assert!(!extraction.text.contains("Launch codes"));
+
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["file_count"], 1);
+        assert_eq!(meta["encrypted_count"], 1);
+        assert_eq!(meta["failed_count"], 0);
+        assert_eq!(meta["failed_files"][0]["path"], "secret.txt");
+        assert_eq!(meta["failed_files"][0]["reason"], "encrypted");
+
+        // Nothing extractable without the password is an error, not empty text
+        let zip_data =
+            create_encrypted_test_zip(&[("secret.txt", b"Launch codes", Some(b"hunter2"))]);
+        let error = extract_from_zip(&zip_data, &options, &ArchiveOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("password-protected"));
+        assert!(error.contains("zip_password"));
+    }
+
+    #[test]
+    fn test_encrypted_entries_decrypt_with_password() {
+        let zip_data = create_encrypted_test_zip(&[
+            ("public.txt", b"Visible notes", None),
+            ("secret.txt", b"Launch codes", Some(b"hunter2")),
+        ]);
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        let archive_opts = ArchiveOptions {
+            zip_password: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+
+        let extraction = extract_from_zip(&zip_data, &options, &archive_opts).unwrap();
+        assert!(extraction.text.contains("Visible notes"));
+        assert!(extraction.text.contains("Launch codes"));
+
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["file_count"], 2);
+        assert_eq!(meta["encrypted_count"], 0);
+    }
+
+    #[test]
+    fn test_oversized_entries_are_skipped() {
+        let zip_data = create_test_zip(&[
+            ("small.txt", b"Short note"),
+            ("large.txt", "long line ".repeat(100).as_bytes()),
+        ]);
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        // Skipped even when other errors would fail the archive
+        let archive_opts = ArchiveOptions {
+            max_file_size: 100,
+            continue_on_error: false,
+            ..Default::default()
+        };
+
+        let extraction = extract_from_zip(&zip_data, &options, &archive_opts).unwrap();
+        assert!(extraction.text.contains("Short note"));
+        assert!(!extraction.text.contains("long line"));
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["failed_files"][0]["path"], "large.txt");
+        assert_eq!(meta["failed_files"][0]["reason"], "too_large");
+
+        let tar_data = create_test_tar(&[("large.txt", "long line ".repeat(100).as_bytes())]);
+        let extraction = extract_from_tar(&tar_data, &options, &archive_opts).unwrap();
+        assert_eq!(
+            extraction.metadata.unwrap()["failed_files"][0]["reason"],
+            "too_large"
+        );
+    }
+
+    #[test]
+    fn test_total_size_limit_flags_truncation_and_skipped_count() {
+        let files: [(&str, &[u8]); 5] = [
+            ("a.txt", b"first entry"),
+            ("b.txt", b"second entry"),
+            ("c.txt", b"third entry"),
+            ("d.exe", &[0x00, 0x01]),
+            ("e.txt", b"fifth entry"),
+        ];
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        // Room for the first entry only
+        let archive_opts = ArchiveOptions {
+            max_total_size: 20,
+            ..Default::default()
+        };
+
+        let zip_data = create_test_zip(&files);
+        let extraction = extract_from_zip(&zip_data, &options, &archive_opts).unwrap();
+        assert!(extraction.text.contains("first entry"));
+        assert!(!extraction.text.contains("second entry"));
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["truncated"], true);
+        // b, c and e; the skipped extension isn't counted
+        assert_eq!(meta["skipped_count"], 3);
+        assert_eq!(meta["file_count"], 1);
+
+        let tar_data = create_test_tar(&files);
+        let meta = extract_from_tar(&tar_data, &options, &archive_opts)
+            .unwrap()
+            .metadata
+            .unwrap();
+        assert_eq!(meta["truncated"], true);
+        assert_eq!(meta["skipped_count"], 3);
+
+        // Flagged even when metadata wasn't asked for
+        let meta = extract_from_zip(&zip_data, &ExtractionOptions::default(), &archive_opts)
+            .unwrap()
+            .metadata
+            .unwrap();
+        assert_eq!(meta["truncated"], true);
+        assert_eq!(meta["skipped_count"], 3);
+
+        // Under the limit nothing is flagged
+        let extraction = extract_from_zip(&zip_data, &options, &ArchiveOptions::default()).unwrap();
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["skipped_count"], 0);
+        assert!(meta.get("truncated").is_none());
+    }
+
+    #[test]
+    fn test_zip_bomb_entry_is_aborted() {
+        // 20MB of zeros deflates to a few KB, far past the ratio limit
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let stored = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            let deflated = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            zip.start_file("readme.txt", stored).unwrap();
+            zip.write_all(b"Nothing to see here").unwrap();
+            zip.start_file("bomb.txt", deflated).unwrap();
+            zip.write_all(&vec![b'0'; 20 * 1024 * 1024]).unwrap();
+            zip.finish().unwrap();
+        }
+        let zip_data = buffer.into_inner();
+        assert!(zip_data.len() < 1024 * 1024);
+
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        let extraction = extract_from_zip(&zip_data, &options, &ArchiveOptions::default()).unwrap();
+        assert!(extraction.text.contains("Nothing to see here"));
+
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["file_count"], 1);
+        assert_eq!(meta["failed_files"][0]["path"], "bomb.txt");
+        assert_eq!(meta["failed_files"][0]["reason"], "too_large");
+        assert!(
+            meta["failed_files"][0]["error"]
+                .as_str()
+                .unwrap()
+                .contains("compressed size")
+        );
+    }
+
+    #[test]
+    fn test_streaming_hands_over_each_entry() {
+        let zip_data = create_test_zip(&[
+            ("a.txt", b"First entry"),
+            ("b.bin", &[0x00, 0x01, 0x02, 0x03]),
+            ("c.md", b"# Third entry"),
+        ]);
+        let options = ExtractionOptions::default();
+        let archive_opts = ArchiveOptions::default();
+
+        let mut seen = Vec::new();
+        let summary = extract_from_zip_streaming(&zip_data, &options, &archive_opts, |file| {
+            seen.push((file.path, file.text));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0], ("a.txt".to_string(), "First entry".to_string()));
+        assert_eq!(seen[1].0, "c.md");
+
+        // The summary matches what the buffered API reports
+        let metadata = summary.metadata("zip");
+        assert_eq!(metadata["file_count"], 2);
+        assert_eq!(metadata["failed_count"], 1);
+        assert_eq!(metadata["files"][1]["text_length"], seen[1].1.len());
+        assert_eq!(zip_entry_count(&zip_data), Some(3));
+        assert_eq!(zip_entry_count(b"not a zip"), None);
+
+        // An error from the callback stops the walk
+        let mut calls = 0;
+        let result = extract_from_zip_streaming(&zip_data, &options, &archive_opts, |_| {
+            calls += 1;
+            Err(anyhow!("consumer gone"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
     #[test]
     fn test_detect_mime_from_extension() {
         assert_eq!(
@@ -646,6 +1267,61 @@ mod tests {
         assert!(is_likely_text(b"")); // Empty is considered text
     }
 
+    #[test]
+    fn test_extract_tar_merges_text_and_skips_binaries() {
+        let tar_data = create_test_tar(&[
+            ("logs/app.log", b"2024-01-01 INFO started"),
+            ("logs/image.png", &[0x89, 0x50, 0x4E, 0x47, 0x00, 0x00]),
+            ("notes.txt", b"Deploy notes"),
+            ("blob", &[0x00, 0x01, 0x02, 0x03, 0xFF, 0xFE]),
+        ]);
+
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        let archive_opts = ArchiveOptions {
+            continue_on_error: true,
+            ..Default::default()
+        };
+
+        let extraction = extract_from_tar(&tar_data, &options, &archive_opts).unwrap();
+        assert!(extraction.text.contains("2024-01-01 INFO started"));
+        assert!(extraction.text.contains("Deploy notes"));
+        assert!(!extraction.text.contains('\0'));
+
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["format"], "tar");
+        assert_eq!(meta["file_count"], 2);
+    }
+
+    #[test]
+    fn test_extract_tar_respects_options() {
+        let tar_data = create_test_tar(&[("a.txt", b"first file"), ("b.txt", b"second file")]);
+        let options = ExtractionOptions::default();
+
+        let skip_txt = ArchiveOptions {
+            skip_extensions: vec!["txt".into()],
+            ..Default::default()
+        };
+        let extraction = extract_from_tar(&tar_data, &options, &skip_txt).unwrap();
+        assert!(extraction.text.is_empty());
+
+        // Binary entry without continue_on_error fails the whole archive
+        let tar_data = create_test_tar(&[("blob", &[0x00, 0x01, 0x02, 0x03])]);
+        let strict = ArchiveOptions {
+            continue_on_error: false,
+            ..Default::default()
+        };
+        assert!(extract_from_tar(&tar_data, &options, &strict).is_err());
+
+        let too_deep = ArchiveOptions {
+            max_depth: 0,
+            ..Default::default()
+        };
+        assert!(extract_from_tar(&tar_data, &options, &too_deep).is_err());
+    }
+
     #[test]
     fn test_max_depth_limit() {
         let zip_data = create_test_zip(&[("file.txt", b"Hello")]);
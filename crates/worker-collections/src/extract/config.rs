@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,6 +10,10 @@ pub enum ExtractionStrategy {
     PlainText,
     StructurePreserving,
     Markdown,
+    /// Pick the strategy from the file's MIME type: spreadsheets and CSV
+    /// structure-preserving, HTML and Markdown as Markdown, everything else
+    /// (logs, plain text, PDFs, ...) as plain text
+    Auto,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +66,54 @@ pub struct ExtractionOptions {
     /// This enables metadata to be chunked alongside the main content
     #[serde(default)]
     pub append_metadata_to_text: bool,
+
+    /// When extraction with the declared MIME type fails, sniff the content
+    /// and retry once with the detected type (handles mislabeled uploads)
+    #[serde(default = "default_sniff_mime_fallback")]
+    pub sniff_mime_fallback: bool,
+
+    /// Record the extractors run, fallbacks taken and warnings under
+    /// `extraction_trace` in the metadata (for diagnosing problem documents)
+    #[serde(default)]
+    pub trace: bool,
+
+    /// Cap the extracted text at this many bytes; output past the limit is
+    /// dropped and `truncated: true` is set in the metadata (unlimited if unset)
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+
+    /// Record the text's language (ISO 639-1) and the detector's confidence
+    /// as `detected_language` and `language_confidence` in the metadata;
+    /// very short texts are recorded as `unknown`
+    #[serde(default = "default_detect_language")]
+    pub detect_language: bool,
+
+    /// Caption images embedded in PDF, Office and OpenDocument files with a
+    /// vision model and append the captions to the text (needs a worker
+    /// built with the `image-captions` feature and `IMAGE_CAPTION_URL`)
+    #[serde(default)]
+    pub caption_images: bool,
+
+    /// Most images captioned per document with `caption_images`
+    #[serde(default = "default_max_captioned_images")]
+    pub max_captioned_images: usize,
+
+    /// Password for encrypted entries in ZIP archives; without one they are
+    /// skipped and counted as `encrypted_count` in the metadata
+    #[serde(default)]
+    pub zip_password: Option<String>,
+}
+
+fn default_sniff_mime_fallback() -> bool {
+    true
+}
+
+fn default_detect_language() -> bool {
+    true
+}
+
+fn default_max_captioned_images() -> usize {
+    10
 }
 
 impl Default for ExtractionOptions {
@@ -74,6 +128,13 @@ impl Default for ExtractionOptions {
             preserve_code_blocks: false,
             include_metadata: false,
             append_metadata_to_text: false,
+            sniff_mime_fallback: true,
+            trace: false,
+            max_output_bytes: None,
+            detect_language: true,
+            caption_images: false,
+            max_captioned_images: default_max_captioned_images(),
+            zip_password: None,
         }
     }
 }
@@ -85,6 +146,11 @@ pub struct ExtractionConfig {
 
     #[serde(default)]
     pub options: ExtractionOptions,
+
+    /// Per-MIME strategies keyed by MIME type (`text/csv`) or type wildcard
+    /// (`text/*`); an exact match wins over a wildcard
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mime_overrides: BTreeMap<String, ExtractionStrategy>,
 }
 
 impl Default for ExtractionConfig {
@@ -92,6 +158,7 @@ impl Default for ExtractionConfig {
         Self {
             strategy: ExtractionStrategy::PlainText,
             options: ExtractionOptions::default(),
+            mime_overrides: BTreeMap::new(),
         }
     }
 }
@@ -114,6 +181,7 @@ mod tests {
         assert!(!config.options.preserve_formatting);
         assert!(config.options.extract_tables);
         assert!(!config.options.include_metadata);
+        assert!(config.options.detect_language);
     }
 
     #[test]
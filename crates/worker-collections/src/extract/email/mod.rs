@@ -3,6 +3,7 @@ use mail_parser::{MessageParser, MimeHeaders, PartType};
 use serde_json::{Value, json};
 
 use crate::extract::config::ExtractionOptions;
+use crate::extract::limit::{self, BoundedText};
 
 /// Result of email extraction
 #[derive(Debug)]
@@ -67,7 +68,7 @@ pub(crate) fn extract_with_options(
         .parse(bytes)
         .ok_or_else(|| anyhow!("Failed to parse email message"))?;
 
-    let mut text_parts = Vec::new();
+    let mut text = BoundedText::new(options.max_output_bytes);
     let mut attachments = Vec::new();
 
     // Extract headers
@@ -89,30 +90,30 @@ pub(crate) fn extract_with_options(
 
     // Add header information
     if !subject.is_empty() {
-        text_parts.push(format!("Subject: {}", subject));
+        text.push_part("\n", &format!("Subject: {}", subject));
     }
     if !from.is_empty() {
-        text_parts.push(format!("From: {}", from));
+        text.push_part("\n", &format!("From: {}", from));
     }
     if !to.is_empty() {
-        text_parts.push(format!("To: {}", to));
+        text.push_part("\n", &format!("To: {}", to));
     }
     if !date.is_empty() {
-        text_parts.push(format!("Date: {}", date));
+        text.push_part("\n", &format!("Date: {}", date));
     }
 
-    text_parts.push(String::new()); // Separator
+    text.push_part("\n", ""); // Separator
 
     // Extract body parts
     for part in message.parts.iter() {
         match &part.body {
-            PartType::Text(text) => {
-                text_parts.push(text.to_string());
+            PartType::Text(body) => {
+                text.push_part("\n", body);
             }
             PartType::Html(html) => {
                 // Extract text from HTML
                 let html_text = extract_text_from_html(html.as_bytes());
-                text_parts.push(html_text);
+                text.push_part("\n", &html_text);
             }
             PartType::Binary(binary) | PartType::InlineBinary(binary) => {
                 if email_opts.extract_attachments {
@@ -144,14 +145,14 @@ pub(crate) fn extract_with_options(
                 // Handle forwarded/attached emails
                 if let Some(nested) = MessageParser::default().parse(nested_msg.raw_message()) {
                     if let Some(nested_subject) = nested.subject() {
-                        text_parts.push(format!(
-                            "\n--- Forwarded Message ---\nSubject: {}",
-                            nested_subject
-                        ));
+                        text.push_part(
+                            "\n",
+                            &format!("\n--- Forwarded Message ---\nSubject: {}", nested_subject),
+                        );
                     }
                     for nested_part in nested.parts.iter() {
-                        if let PartType::Text(text) = &nested_part.body {
-                            text_parts.push(text.to_string());
+                        if let PartType::Text(body) = &nested_part.body {
+                            text.push_part("\n", body);
                         }
                     }
                 }
@@ -162,19 +163,7 @@ pub(crate) fn extract_with_options(
         }
     }
 
-    // Process attachments for text extraction
-    let attachment_texts = if email_opts.flatten_attachments && !attachments.is_empty() {
-        process_attachments(&attachments)
-    } else {
-        Vec::new()
-    };
-
-    // Combine text
-    let mut full_text = text_parts.join("\n");
-    if !attachment_texts.is_empty() {
-        full_text.push_str("\n\n--- Attachments ---\n");
-        full_text.push_str(&attachment_texts.join("\n\n"));
-    }
+    append_attachment_texts(&mut text, &attachments, email_opts);
 
     // Build metadata
     let metadata = if options.include_metadata {
@@ -196,14 +185,39 @@ pub(crate) fn extract_with_options(
         None
     };
 
-    Ok(EmailExtractionResult {
-        text: full_text,
-        metadata,
-    })
+    let (text, truncated) = text.finish();
+    let metadata = if truncated {
+        limit::mark_truncated(metadata, options.max_output_bytes)
+    } else {
+        metadata
+    };
+
+    Ok(EmailExtractionResult { text, metadata })
+}
+
+/// Append attachment text lazily so nothing past the output limit is extracted
+pub(crate) fn append_attachment_texts(
+    text: &mut BoundedText,
+    attachments: &[EmailAttachment],
+    email_opts: &EmailOptions,
+) {
+    if !email_opts.flatten_attachments || text.is_full() {
+        return;
+    }
+    let mut attachment_texts = attachment_texts(attachments).peekable();
+    if attachment_texts.peek().is_some() {
+        text.push_part("\n", "\n--- Attachments ---");
+        for (i, attachment_text) in attachment_texts.enumerate() {
+            let separator = if i == 0 { "\n" } else { "\n\n" };
+            if !text.push_part(separator, &attachment_text) {
+                break;
+            }
+        }
+    }
 }
 
 /// Check if attachment should be skipped
-fn should_skip_attachment(filename: &str, options: &EmailOptions) -> bool {
+pub(crate) fn should_skip_attachment(filename: &str, options: &EmailOptions) -> bool {
     if let Some(ext) = filename.rsplit('.').next() {
         options
             .skip_extensions
@@ -215,7 +229,7 @@ fn should_skip_attachment(filename: &str, options: &EmailOptions) -> bool {
 }
 
 /// Simple HTML text extraction for email bodies
-fn extract_text_from_html(html: &[u8]) -> String {
+pub(crate) fn extract_text_from_html(html: &[u8]) -> String {
     let html_str = String::from_utf8_lossy(html);
 
     // Remove script and style tags with content
@@ -257,11 +271,9 @@ fn extract_text_from_html(html: &[u8]) -> String {
         .join("\n")
 }
 
-/// Process attachments to extract text where possible
-fn process_attachments(attachments: &[EmailAttachment]) -> Vec<String> {
-    let mut texts = Vec::new();
-
-    for attachment in attachments {
+/// Extract text from attachments where possible, one attachment at a time
+fn attachment_texts(attachments: &[EmailAttachment]) -> impl Iterator<Item = String> + '_ {
+    attachments.iter().filter_map(|attachment| {
         // Try to extract text based on content type
         let extracted = match attachment.content_type.as_str() {
             "text/plain" => Some(String::from_utf8_lossy(&attachment.content).to_string()),
@@ -270,14 +282,10 @@ fn process_attachments(attachments: &[EmailAttachment]) -> Vec<String> {
             _ => None,
         };
 
-        if let Some(text) = extracted
-            && !text.trim().is_empty()
-        {
-            texts.push(format!("[{}]\n{}", attachment.filename, text));
-        }
-    }
-
-    texts
+        extracted
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| format!("[{}]\n{}", attachment.filename, text))
+    })
 }
 
 #[cfg(test)]
@@ -410,4 +418,18 @@ mod tests {
         let extraction = result.unwrap();
         assert!(extraction.text.contains("Hello, this is the email body"));
     }
+
+    #[test]
+    fn test_max_output_bytes_truncates_email() {
+        let email = create_simple_email();
+        let options = ExtractionOptions {
+            max_output_bytes: Some(30),
+            ..Default::default()
+        };
+
+        let extraction = extract_with_options(&email, &options, &EmailOptions::default()).unwrap();
+        assert_eq!(extraction.text.len(), 30);
+        assert!(extraction.text.starts_with("Subject: Test Email"));
+        assert_eq!(extraction.metadata.unwrap()["truncated"], true);
+    }
 }
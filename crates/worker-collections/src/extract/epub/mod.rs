@@ -1,11 +1,19 @@
 //! EPUB (Electronic Publication) text extraction.
 //!
-//! This module extracts plain text from EPUB ebook files.
+//! This module extracts plain text from EPUB ebook files. Chapters are read
+//! in spine order and each one starts with a `# <title>` heading, so the
+//! structural chunker keeps chapter boundaries. Titles come from the table
+//! of contents (NCX); spine items it doesn't list continue the previous
+//! chapter. Books without one get `Chapter N` titles in spine order. The
+//! metadata lists the chapters with their character offsets in the text.
 
 use anyhow::{Context, Result};
-use epub::doc::EpubDoc;
+use epub::doc::{EpubDoc, NavPoint};
+use serde::Serialize;
 use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::io::Cursor;
+use std::path::PathBuf;
 
 use crate::extract::config::ExtractionOptions;
 
@@ -18,6 +26,49 @@ pub struct EpubExtractionResult {
     pub metadata: Option<Value>,
 }
 
+/// A chapter of the extracted text. Offsets are in characters; `start` is
+/// the chapter heading and `end` the end of the chapter's last paragraph.
+#[derive(Debug, Serialize)]
+struct Chapter {
+    title: String,
+    start: usize,
+    end: usize,
+}
+
+/// Extracted text and its chapters, with the length in characters so far
+#[derive(Default)]
+struct ChapterText {
+    text: String,
+    chars: usize,
+    chapters: Vec<Chapter>,
+}
+
+impl ChapterText {
+    fn push(&mut self, s: &str) {
+        self.text.push_str(s);
+        self.chars += s.chars().count();
+    }
+
+    /// Append one spine item, starting a new chapter if it has a title
+    fn push_item(&mut self, title: Option<String>, content: &str) {
+        if !self.text.is_empty() {
+            self.push("\n\n");
+        }
+        if let Some(title) = title {
+            self.chapters.push(Chapter {
+                start: self.chars,
+                end: self.chars,
+                title: title.clone(),
+            });
+            self.push(&format!("# {}\n\n", title));
+        }
+        self.push(content);
+        if let Some(chapter) = self.chapters.last_mut() {
+            chapter.end = self.chars;
+        }
+    }
+}
+
 /// Extract text with metadata from EPUB
 pub fn extract_with_metadata(
     bytes: &[u8],
@@ -26,27 +77,65 @@ pub fn extract_with_metadata(
     let cursor = Cursor::new(bytes.to_vec());
     let mut doc = EpubDoc::from_reader(cursor).context("Failed to parse EPUB document")?;
 
-    // Extract text
-    let mut all_text = Vec::new();
+    let titles = toc_titles(&doc);
+    let mut output = ChapterText::default();
     let spine = doc.spine.clone();
-    for item in spine {
+    for (index, item) in spine.iter().enumerate() {
         if let Some((content_bytes, _mime)) = doc.get_resource(&item.idref) {
             let content = String::from_utf8_lossy(&content_bytes);
             let cleaned = strip_html_tags(&content);
-            if !cleaned.trim().is_empty() {
-                all_text.push(cleaned);
+            if cleaned.trim().is_empty() {
+                continue;
             }
+            let title = match titles.get(&index) {
+                Some(title) => Some(title.clone()),
+                None if !titles.is_empty() && !output.chapters.is_empty() => None,
+                None => Some(format!("Chapter {}", output.chapters.len() + 1)),
+            };
+            output.push_item(title, &cleaned);
         }
     }
-    let text = all_text.join("\n\n");
 
     let metadata = if options.include_metadata {
-        Some(extract_epub_metadata(&mut doc))
+        let mut metadata = extract_epub_metadata(&mut doc);
+        metadata["chapters"] = json!(output.chapters);
+        Some(metadata)
     } else {
         None
     };
 
-    Ok(EpubExtractionResult { text, metadata })
+    Ok(EpubExtractionResult {
+        text: output.text,
+        metadata,
+    })
+}
+
+/// Chapter titles from the table of contents, by spine index. The first
+/// entry pointing into a spine item names it, so a chapter's title wins over
+/// its sections' titles.
+fn toc_titles(doc: &EpubDoc<Cursor<Vec<u8>>>) -> HashMap<usize, String> {
+    fn collect(
+        doc: &EpubDoc<Cursor<Vec<u8>>>,
+        points: &[NavPoint],
+        titles: &mut HashMap<usize, String>,
+    ) {
+        for point in points {
+            let title = point.label.split_whitespace().collect::<Vec<_>>().join(" ");
+            // Entries may point at an anchor inside the chapter's file
+            let content = point.content.to_string_lossy();
+            let path = PathBuf::from(content.split('#').next().unwrap_or_default());
+            if !title.is_empty()
+                && let Some(index) = doc.resource_uri_to_chapter(&path)
+            {
+                titles.entry(index).or_insert(title);
+            }
+            collect(doc, &point.children, titles);
+        }
+    }
+
+    let mut titles = HashMap::new();
+    collect(doc, &doc.toc, &mut titles);
+    titles
 }
 
 /// Extract metadata from EPUB document
@@ -310,6 +399,145 @@ fn clean_whitespace(text: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    /// A two-chapter EPUB 2 book, with or without an NCX table of contents
+    fn build_epub(with_toc: bool) -> Vec<u8> {
+        let (toc_item, spine_toc) = if with_toc {
+            (
+                r#"<item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>"#,
+                r#" toc="ncx""#,
+            )
+        } else {
+            ("", "")
+        };
+        let opf = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0" unique-identifier="bookid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Two Chapters</dc:title>
+    <dc:language>en</dc:language>
+    <dc:identifier id="bookid">urn:uuid:two-chapters</dc:identifier>
+  </metadata>
+  <manifest>
+    <item id="ch1" href="chapter1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ch2" href="chapter2.xhtml" media-type="application/xhtml+xml"/>
+    {toc_item}
+  </manifest>
+  <spine{spine_toc}>
+    <itemref idref="ch1"/>
+    <itemref idref="ch2"/>
+  </spine>
+</package>"#
+        );
+        let ncx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <head><meta name="dtb:uid" content="urn:uuid:two-chapters"/></head>
+  <docTitle><text>Two Chapters</text></docTitle>
+  <navMap>
+    <navPoint id="np1" playOrder="1">
+      <navLabel><text>The Beginning</text></navLabel>
+      <content src="chapter1.xhtml"/>
+    </navPoint>
+    <navPoint id="np2" playOrder="2">
+      <navLabel><text>The End</text></navLabel>
+      <content src="chapter2.xhtml#start"/>
+    </navPoint>
+  </navMap>
+</ncx>"#;
+        let chapter = |body: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><head><title>x</title></head>
+<body><p>{body}</p></body></html>"#
+            )
+        };
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let mut entries = vec![
+            ("mimetype".to_string(), "application/epub+zip".to_string()),
+            (
+                "META-INF/container.xml".to_string(),
+                r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#
+                    .to_string(),
+            ),
+            ("OEBPS/content.opf".to_string(), opf),
+            (
+                "OEBPS/chapter1.xhtml".to_string(),
+                chapter("It was a dark and stormy night."),
+            ),
+            (
+                "OEBPS/chapter2.xhtml".to_string(),
+                chapter("And they lived happily ever after."),
+            ),
+        ];
+        if with_toc {
+            entries.push(("OEBPS/toc.ncx".to_string(), ncx.to_string()));
+        }
+        for (name, content) in entries {
+            writer.start_file(name, stored).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn metadata_options() -> ExtractionOptions {
+        ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        }
+    }
+
+    fn chapters(metadata: &Value) -> Vec<(String, usize, usize)> {
+        metadata["chapters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    c["title"].as_str().unwrap().to_string(),
+                    c["start"].as_u64().unwrap() as usize,
+                    c["end"].as_u64().unwrap() as usize,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chapters_from_table_of_contents() {
+        let result = extract_with_metadata(&build_epub(true), &metadata_options()).unwrap();
+        let chapters = chapters(&result.metadata.unwrap());
+        let titles: Vec<_> = chapters.iter().map(|(t, _, _)| t.as_str()).collect();
+        assert_eq!(titles, vec!["The Beginning", "The End"]);
+
+        let text: Vec<char> = result.text.chars().collect();
+        let chapter_text =
+            |i: usize| -> String { text[chapters[i].1..chapters[i].2].iter().collect() };
+        assert!(chapter_text(0).starts_with("# The Beginning\n\n"));
+        assert!(chapter_text(0).ends_with("stormy night."));
+        assert!(chapter_text(1).starts_with("# The End\n\n"));
+        assert!(chapter_text(1).contains("happily ever after."));
+    }
+
+    #[test]
+    fn test_chapters_without_table_of_contents() {
+        let result = extract_with_metadata(&build_epub(false), &metadata_options()).unwrap();
+        assert!(result.text.starts_with("# Chapter 1\n\n"));
+        assert!(result.text.contains("# Chapter 2\n\n"));
+        let titles: Vec<_> = chapters(&result.metadata.unwrap())
+            .into_iter()
+            .map(|(t, _, _)| t)
+            .collect();
+        assert_eq!(titles, vec!["Chapter 1", "Chapter 2"]);
+    }
 
     #[test]
     fn test_strip_html_simple() {
@@ -0,0 +1,179 @@
+//! Language detection for extracted text (`ExtractionOptions::detect_language`).
+//!
+//! The detected language is recorded in the extraction metadata as an
+//! ISO 639-1 code with the detector's confidence, so chunks can be filtered
+//! and routed by language downstream. Texts too short to judge reliably are
+//! recorded as `unknown` rather than guessed.
+
+use serde_json::{Map, Value};
+
+/// Metadata key holding the ISO 639-1 code (or `unknown`)
+pub const LANGUAGE_METADATA_KEY: &str = "detected_language";
+
+/// Metadata key holding the detector's confidence in `0.0..=1.0`
+pub const LANGUAGE_CONFIDENCE_METADATA_KEY: &str = "language_confidence";
+
+/// Recorded when the text is too short or no language could be detected
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
+/// Texts with fewer characters than this are not classified
+const MIN_DETECTION_CHARS: usize = 40;
+
+/// Only the start of long documents is sampled; more text doesn't change
+/// the answer, only the cost
+const MAX_SAMPLE_BYTES: usize = 16 * 1024;
+
+/// Detected language and confidence of `text`
+pub(crate) fn detect(text: &str) -> (&'static str, f64) {
+    if text.chars().filter(|c| !c.is_whitespace()).count() < MIN_DETECTION_CHARS {
+        return (UNKNOWN_LANGUAGE, 0.0);
+    }
+    let mut end = text.len().min(MAX_SAMPLE_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    match whatlang::detect(&text[..end]) {
+        Some(info) => (iso_639_1(info.lang().code()), info.confidence()),
+        None => (UNKNOWN_LANGUAGE, 0.0),
+    }
+}
+
+/// Record the language of `text` in the extraction metadata, creating the
+/// metadata object if the extractor produced none
+pub(crate) fn attach(metadata: Option<Value>, text: &str) -> Option<Value> {
+    let (language, confidence) = detect(text);
+    let mut map = match metadata {
+        Some(Value::Object(map)) => map,
+        Some(other) => {
+            let mut map = Map::new();
+            map.insert("metadata".to_string(), other);
+            map
+        }
+        None => Map::new(),
+    };
+    map.insert(LANGUAGE_METADATA_KEY.to_string(), Value::from(language));
+    map.insert(
+        LANGUAGE_CONFIDENCE_METADATA_KEY.to_string(),
+        Value::from((confidence * 100.0).round() / 100.0),
+    );
+    Some(Value::Object(map))
+}
+
+/// ISO 639-1 code for the ISO 639-3 code reported by the detector. Codes
+/// without a two-letter equivalent are passed through.
+fn iso_639_1(code: &'static str) -> &'static str {
+    match code {
+        "afr" => "af",
+        "aka" => "ak",
+        "amh" => "am",
+        "ara" => "ar",
+        "aze" => "az",
+        "bel" => "be",
+        "ben" => "bn",
+        "bul" => "bg",
+        "cat" => "ca",
+        "ces" => "cs",
+        "cmn" => "zh",
+        "cym" => "cy",
+        "dan" => "da",
+        "deu" => "de",
+        "ell" => "el",
+        "eng" => "en",
+        "epo" => "eo",
+        "est" => "et",
+        "fin" => "fi",
+        "fra" => "fr",
+        "guj" => "gu",
+        "heb" => "he",
+        "hin" => "hi",
+        "hrv" => "hr",
+        "hun" => "hu",
+        "hye" => "hy",
+        "ind" => "id",
+        "ita" => "it",
+        "jav" => "jv",
+        "jpn" => "ja",
+        "kan" => "kn",
+        "kat" => "ka",
+        "khm" => "km",
+        "kor" => "ko",
+        "lat" => "la",
+        "lav" => "lv",
+        "lit" => "lt",
+        "mal" => "ml",
+        "mar" => "mr",
+        "mkd" => "mk",
+        "mya" => "my",
+        "nep" => "ne",
+        "nld" => "nl",
+        "nob" => "nb",
+        "ori" => "or",
+        "pan" => "pa",
+        "pes" => "fa",
+        "pol" => "pl",
+        "por" => "pt",
+        "ron" => "ro",
+        "rus" => "ru",
+        "sin" => "si",
+        "slk" => "sk",
+        "slv" => "sl",
+        "sna" => "sn",
+        "spa" => "es",
+        "srp" => "sr",
+        "swe" => "sv",
+        "tam" => "ta",
+        "tel" => "te",
+        "tgl" => "tl",
+        "tha" => "th",
+        "tuk" => "tk",
+        "tur" => "tr",
+        "ukr" => "uk",
+        "urd" => "ur",
+        "uzb" => "uz",
+        "vie" => "vi",
+        "yid" => "yi",
+        "zul" => "zu",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detects_common_languages() {
+        let english = "The quick brown fox jumps over the lazy dog while the farmer \
+                       watches from the porch and drinks his morning coffee.";
+        let german = "Der schnelle braune Fuchs springt über den faulen Hund, während \
+                      der Bauer von der Veranda aus zusieht und seinen Kaffee trinkt.";
+        let spanish = "El rápido zorro marrón salta sobre el perro perezoso mientras \
+                       el granjero mira desde el porche y bebe su café de la mañana.";
+
+        let (language, confidence) = detect(english);
+        assert_eq!(language, "en");
+        assert!(confidence > 0.5);
+        assert_eq!(detect(german).0, "de");
+        assert_eq!(detect(spanish).0, "es");
+    }
+
+    #[test]
+    fn test_short_text_is_unknown() {
+        assert_eq!(detect("Hello world"), (UNKNOWN_LANGUAGE, 0.0));
+        assert_eq!(detect(""), (UNKNOWN_LANGUAGE, 0.0));
+    }
+
+    #[test]
+    fn test_attach_keeps_existing_metadata() {
+        let text = "Ceci est un document rédigé en français pour vérifier que la \
+                    langue est bien détectée par l'extracteur.";
+        let metadata = attach(Some(json!({"title": "Rapport"})), text).unwrap();
+        assert_eq!(metadata["title"], "Rapport");
+        assert_eq!(metadata[LANGUAGE_METADATA_KEY], "fr");
+        assert!(metadata[LANGUAGE_CONFIDENCE_METADATA_KEY].as_f64().unwrap() > 0.0);
+
+        let metadata = attach(None, "short").unwrap();
+        assert_eq!(metadata[LANGUAGE_METADATA_KEY], UNKNOWN_LANGUAGE);
+    }
+}
@@ -0,0 +1,160 @@
+//! Output size limit for extracted text (`ExtractionOptions::max_output_bytes`).
+//!
+//! Extractors that combine many parts (archives, emails) append through
+//! [`BoundedText`] so they stop allocating once the limit is reached; the
+//! final text is cut again after cleaning and flagged in the metadata.
+
+use serde_json::{Map, Value};
+
+/// Metadata key set to `true` when the extracted text was cut at the limit
+pub const TRUNCATED_METADATA_KEY: &str = "truncated";
+
+/// Metadata key recording the limit that was applied
+pub const MAX_OUTPUT_BYTES_METADATA_KEY: &str = "max_output_bytes";
+
+/// Cut `text` to at most `max_bytes`, backing off to a UTF-8 character
+/// boundary. Returns `true` if anything was removed.
+pub(crate) fn truncate_to_limit(text: &mut String, max_bytes: Option<usize>) -> bool {
+    match max_bytes {
+        Some(max) if text.len() > max => {
+            text.truncate(floor_char_boundary(text, max));
+            true
+        }
+        _ => false,
+    }
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut end = index.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+/// Text buffer that stops growing once a byte limit is reached
+#[derive(Debug)]
+pub(crate) struct BoundedText {
+    text: String,
+    max_bytes: Option<usize>,
+    parts: usize,
+    truncated: bool,
+}
+
+impl BoundedText {
+    pub(crate) fn new(max_bytes: Option<usize>) -> Self {
+        Self {
+            text: String::new(),
+            max_bytes,
+            parts: 0,
+            truncated: false,
+        }
+    }
+
+    /// Append `part`, keeping only what fits. Returns `false` once the limit
+    /// has been reached and further parts would be dropped.
+    fn push(&mut self, part: &str) -> bool {
+        if self.truncated {
+            return false;
+        }
+        if let Some(max) = self.max_bytes {
+            let remaining = max.saturating_sub(self.text.len());
+            if part.len() > remaining {
+                self.text
+                    .push_str(&part[..floor_char_boundary(part, remaining)]);
+                self.truncated = true;
+                return false;
+            }
+        }
+        self.text.push_str(part);
+        true
+    }
+
+    /// Append `part` preceded by `separator` unless it is the first part,
+    /// like `join`. Returns `false` once the limit has been reached.
+    pub(crate) fn push_part(&mut self, separator: &str, part: &str) -> bool {
+        let kept = (self.parts == 0 || self.push(separator)) && self.push(part);
+        self.parts += 1;
+        kept
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns the collected text and whether anything was dropped
+    pub(crate) fn finish(self) -> (String, bool) {
+        (self.text, self.truncated)
+    }
+}
+
+/// Flag truncated output in the extraction metadata, creating the metadata
+/// object if the extractor produced none
+pub(crate) fn mark_truncated(metadata: Option<Value>, max_bytes: Option<usize>) -> Option<Value> {
+    let mut map = match metadata {
+        Some(Value::Object(map)) => map,
+        Some(other) => {
+            let mut map = Map::new();
+            map.insert("metadata".to_string(), other);
+            map
+        }
+        None => Map::new(),
+    };
+    map.insert(TRUNCATED_METADATA_KEY.to_string(), Value::Bool(true));
+    if let Some(max) = max_bytes {
+        map.insert(MAX_OUTPUT_BYTES_METADATA_KEY.to_string(), Value::from(max));
+    }
+    Some(Value::Object(map))
+}
+
+/// Whether an inner extractor already flagged its output as truncated
+pub(crate) fn is_truncated(metadata: &Option<Value>) -> bool {
+    metadata
+        .as_ref()
+        .and_then(|m| m.get(TRUNCATED_METADATA_KEY))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_respects_char_boundaries() {
+        let mut text = "héllo".to_string();
+        assert!(truncate_to_limit(&mut text, Some(2)));
+        assert_eq!(text, "h");
+
+        let mut text = "short".to_string();
+        assert!(!truncate_to_limit(&mut text, Some(100)));
+        assert!(!truncate_to_limit(&mut text, None));
+        assert_eq!(text, "short");
+    }
+
+    #[test]
+    fn test_bounded_text_stops_at_limit() {
+        let mut bounded = BoundedText::new(Some(8));
+        assert!(bounded.push_part(", ", "abc"));
+        assert!(!bounded.push_part(", ", "defghij"));
+        assert!(bounded.is_full());
+        assert!(!bounded.push_part(", ", "more"));
+
+        let (text, truncated) = bounded.finish();
+        assert_eq!(text, "abc, def");
+        assert!(truncated);
+
+        let mut unbounded = BoundedText::new(None);
+        unbounded.push_part("\n", "a");
+        unbounded.push_part("\n", "b");
+        assert_eq!(unbounded.finish(), ("a\nb".to_string(), false));
+    }
+
+    #[test]
+    fn test_mark_truncated_creates_metadata() {
+        let metadata = mark_truncated(None, Some(10));
+        assert!(is_truncated(&metadata));
+        assert_eq!(metadata.unwrap()[MAX_OUTPUT_BYTES_METADATA_KEY], 10);
+        assert!(!is_truncated(&Some(serde_json::json!({ "format": "zip" }))));
+    }
+}
@@ -2,21 +2,27 @@ pub mod config;
 pub mod error;
 pub mod plain_text;
 pub mod service;
+pub mod trace;
 
-mod archive;
+pub(crate) mod archive;
 mod email;
 mod epub;
 mod html;
 mod json;
+pub(crate) mod language;
 mod legacy_doc;
 mod legacy_ppt;
 mod legacy_xls;
+mod limit;
 mod log;
 mod markdown;
+mod msg;
 mod office;
 mod open_office;
-mod pdf;
+pub(crate) mod pdf;
 mod rtf;
+mod selection;
+mod sniff;
 mod xml;
 
 pub use service::ExtractionService;
@@ -0,0 +1,475 @@
+//! Outlook .msg email extraction.
+//!
+//! .msg files are OLE/CFB compound files. Variable-length MAPI properties are
+//! stored in `__substg1.0_<id><type>` streams; fixed-size ones (times,
+//! integers) are packed into the `__properties_version1.0` stream of their
+//! storage. Recipients and attachments are `__recip_version1.0_#N` and
+//! `__attach_version1.0_#N` storages with the same layout.
+
+use anyhow::{Result, anyhow};
+use cfb::CompoundFile;
+use serde_json::json;
+use std::io::{Cursor, Read};
+
+use crate::extract::config::ExtractionOptions;
+use crate::extract::email::{
+    EmailAttachment, EmailExtractionResult, EmailOptions, append_attachment_texts,
+    extract_text_from_html, should_skip_attachment,
+};
+use crate::extract::limit::{self, BoundedText};
+
+/// Stream holding the fixed-size properties of a storage
+pub(crate) const PROPERTIES_STREAM: &str = "__properties_version1.0";
+const RECIPIENT_STORAGE_PREFIX: &str = "__recip_version1.0_";
+const ATTACHMENT_STORAGE_PREFIX: &str = "__attach_version1.0_";
+
+/// Header size of the properties stream in the top-level message storage
+const MESSAGE_PROPERTIES_HEADER: usize = 32;
+/// Header size of the properties stream in recipient and attachment storages
+const SUBOBJECT_PROPERTIES_HEADER: usize = 8;
+const PROPERTY_ENTRY_SIZE: usize = 16;
+
+// MAPI property ids
+const PID_SUBJECT: u16 = 0x0037;
+const PID_CLIENT_SUBMIT_TIME: u16 = 0x0039;
+const PID_RECIPIENT_TYPE: u16 = 0x0C15;
+const PID_SENDER_NAME: u16 = 0x0C1A;
+const PID_SENDER_EMAIL_ADDRESS: u16 = 0x0C1F;
+const PID_MESSAGE_DELIVERY_TIME: u16 = 0x0E06;
+const PID_BODY: u16 = 0x1000;
+const PID_BODY_HTML: u16 = 0x1013;
+const PID_INTERNET_MESSAGE_ID: u16 = 0x1035;
+const PID_DISPLAY_NAME: u16 = 0x3001;
+const PID_EMAIL_ADDRESS: u16 = 0x3003;
+const PID_ATTACH_DATA: u16 = 0x3701;
+const PID_ATTACH_FILENAME: u16 = 0x3704;
+const PID_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PID_ATTACH_MIME_TAG: u16 = 0x370E;
+const PID_SMTP_ADDRESS: u16 = 0x39FE;
+const PID_SENDER_SMTP_ADDRESS: u16 = 0x5D01;
+
+// MAPI property types
+const PT_LONG: u16 = 0x0003;
+const PT_STRING8: u16 = 0x001E;
+const PT_UNICODE: u16 = 0x001F;
+const PT_SYSTIME: u16 = 0x0040;
+const PT_BINARY: u16 = 0x0102;
+
+// PidTagRecipientType values
+const MAPI_TO: i32 = 1;
+const MAPI_CC: i32 = 2;
+
+/// Seconds between the FILETIME epoch (1601) and the Unix epoch
+const FILETIME_UNIX_OFFSET_SECS: i64 = 11_644_473_600;
+
+type Msg<'a> = CompoundFile<Cursor<&'a [u8]>>;
+
+#[derive(Debug)]
+struct Recipient {
+    name: Option<String>,
+    address: Option<String>,
+    kind: i32,
+}
+
+impl Recipient {
+    fn display(&self) -> Option<&str> {
+        self.address.as_deref().or(self.name.as_deref())
+    }
+}
+
+/// Extract text from an Outlook .msg file with the same output shape as .eml
+/// extraction. The plain-text body is used when present; messages that only
+/// carry an HTML body are converted with the .eml HTML extractor.
+pub(crate) fn extract_with_options(
+    bytes: &[u8],
+    options: &ExtractionOptions,
+    email_opts: &EmailOptions,
+) -> Result<EmailExtractionResult> {
+    let mut msg = CompoundFile::open(Cursor::new(bytes)).map_err(|e| {
+        anyhow!(
+            "Failed to open CFB file: {}. File may not be a valid .msg format.",
+            e
+        )
+    })?;
+    if !msg.exists(format!("/{PROPERTIES_STREAM}")) {
+        return Err(anyhow!(
+            "Compound file has no {PROPERTIES_STREAM} stream; not an Outlook message"
+        ));
+    }
+
+    let mut text = BoundedText::new(options.max_output_bytes);
+
+    // Extract headers
+    let subject = read_string(&mut msg, "", PID_SUBJECT).unwrap_or_default();
+    let from_name = read_string(&mut msg, "", PID_SENDER_NAME).unwrap_or_default();
+    let from = read_string(&mut msg, "", PID_SENDER_SMTP_ADDRESS)
+        .or_else(|| read_string(&mut msg, "", PID_SENDER_EMAIL_ADDRESS))
+        .unwrap_or_else(|| from_name.clone());
+    let recipients = read_recipients(&mut msg);
+    let to = join_recipients(&recipients, MAPI_TO);
+    let cc = join_recipients(&recipients, MAPI_CC);
+    let date = [PID_CLIENT_SUBMIT_TIME, PID_MESSAGE_DELIVERY_TIME]
+        .into_iter()
+        .find_map(|id| {
+            fixed_property(&mut msg, "", MESSAGE_PROPERTIES_HEADER, id, PT_SYSTIME)
+                .and_then(|value| filetime_to_rfc3339(u64::from_le_bytes(value)))
+        })
+        .unwrap_or_default();
+    let message_id = read_string(&mut msg, "", PID_INTERNET_MESSAGE_ID).unwrap_or_default();
+
+    // Add header information
+    for (label, value) in [
+        ("Subject", &subject),
+        ("From", &from),
+        ("To", &to),
+        ("Cc", &cc),
+        ("Date", &date),
+    ] {
+        if !value.is_empty() {
+            text.push_part("\n", &format!("{}: {}", label, value));
+        }
+    }
+
+    text.push_part("\n", ""); // Separator
+
+    match read_string(&mut msg, "", PID_BODY).filter(|body| !body.trim().is_empty()) {
+        Some(body) => {
+            text.push_part("\n", &body);
+        }
+        None => {
+            let html = read_binary(&mut msg, "", PID_BODY_HTML)
+                .or_else(|| read_string(&mut msg, "", PID_BODY_HTML).map(String::into_bytes));
+            if let Some(html) = html {
+                text.push_part("\n", &extract_text_from_html(&html));
+            }
+        }
+    }
+
+    let attachments = if email_opts.extract_attachments {
+        read_attachments(&mut msg, email_opts)
+    } else {
+        Vec::new()
+    };
+    append_attachment_texts(&mut text, &attachments, email_opts);
+
+    // Build metadata
+    let metadata = if options.include_metadata {
+        Some(json!({
+            "format": "msg",
+            "subject": subject,
+            "from": from,
+            "from_name": from_name,
+            "to": to,
+            "cc": cc,
+            "date": date,
+            "attachment_count": attachments.len(),
+            "attachments": attachments.iter().map(|a| json!({
+                "filename": a.filename,
+                "content_type": a.content_type,
+                "size": a.size,
+            })).collect::<Vec<_>>(),
+            "message_id": message_id,
+        }))
+    } else {
+        None
+    };
+
+    let (text, truncated) = text.finish();
+    let metadata = if truncated {
+        limit::mark_truncated(metadata, options.max_output_bytes)
+    } else {
+        metadata
+    };
+
+    Ok(EmailExtractionResult { text, metadata })
+}
+
+fn read_recipients(msg: &mut Msg<'_>) -> Vec<Recipient> {
+    substorages(msg, RECIPIENT_STORAGE_PREFIX)
+        .into_iter()
+        .map(|storage| Recipient {
+            name: read_string(msg, &storage, PID_DISPLAY_NAME),
+            address: read_string(msg, &storage, PID_SMTP_ADDRESS)
+                .or_else(|| read_string(msg, &storage, PID_EMAIL_ADDRESS)),
+            kind: fixed_property(
+                msg,
+                &storage,
+                SUBOBJECT_PROPERTIES_HEADER,
+                PID_RECIPIENT_TYPE,
+                PT_LONG,
+            )
+            .map(|value| i32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+            .unwrap_or(MAPI_TO),
+        })
+        .collect()
+}
+
+fn join_recipients(recipients: &[Recipient], kind: i32) -> String {
+    recipients
+        .iter()
+        .filter(|r| r.kind == kind)
+        .filter_map(Recipient::display)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Attachments stored as binary data. Embedded messages and OLE objects
+/// (stored as sub-storages instead) are skipped.
+fn read_attachments(msg: &mut Msg<'_>, email_opts: &EmailOptions) -> Vec<EmailAttachment> {
+    let mut attachments = Vec::new();
+    for storage in substorages(msg, ATTACHMENT_STORAGE_PREFIX) {
+        let Some(content) = read_binary(msg, &storage, PID_ATTACH_DATA) else {
+            continue;
+        };
+        let filename = read_string(msg, &storage, PID_ATTACH_LONG_FILENAME)
+            .or_else(|| read_string(msg, &storage, PID_ATTACH_FILENAME))
+            .unwrap_or_else(|| "attachment".to_string());
+        let content_type = read_string(msg, &storage, PID_ATTACH_MIME_TAG)
+            .or_else(|| {
+                mime_guess::from_path(&filename)
+                    .first_raw()
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        // Check size and extension
+        if content.len() <= email_opts.max_attachment_size
+            && !should_skip_attachment(&filename, email_opts)
+        {
+            attachments.push(EmailAttachment {
+                filename,
+                content_type,
+                size: content.len(),
+                content,
+            });
+        }
+    }
+    attachments
+}
+
+/// Paths of the top-level storages whose name starts with `prefix`, in order
+fn substorages(msg: &Msg<'_>, prefix: &str) -> Vec<String> {
+    let mut storages: Vec<String> = msg
+        .read_root_storage()
+        .filter(|entry| entry.is_storage() && entry.name().starts_with(prefix))
+        .map(|entry| format!("/{}", entry.name()))
+        .collect();
+    storages.sort();
+    storages
+}
+
+fn read_stream(msg: &mut Msg<'_>, path: &str) -> Option<Vec<u8>> {
+    let mut stream = msg.open_stream(path).ok()?;
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+fn property_stream(storage: &str, id: u16, property_type: u16) -> String {
+    format!("{storage}/__substg1.0_{id:04X}{property_type:04X}")
+}
+
+/// String property, stored as UTF-16LE or (in older files) 8-bit text
+fn read_string(msg: &mut Msg<'_>, storage: &str, id: u16) -> Option<String> {
+    let value = if let Some(data) = read_stream(msg, &property_stream(storage, id, PT_UNICODE)) {
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        let data = read_stream(msg, &property_stream(storage, id, PT_STRING8))?;
+        String::from_utf8_lossy(&data).into_owned()
+    };
+    let value = value.trim_end_matches('\0').trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn read_binary(msg: &mut Msg<'_>, storage: &str, id: u16) -> Option<Vec<u8>> {
+    read_stream(msg, &property_stream(storage, id, PT_BINARY))
+}
+
+/// Raw 8-byte value of a fixed-size property from a storage's properties stream
+fn fixed_property(
+    msg: &mut Msg<'_>,
+    storage: &str,
+    header_len: usize,
+    id: u16,
+    property_type: u16,
+) -> Option<[u8; 8]> {
+    let data = read_stream(msg, &format!("{storage}/{PROPERTIES_STREAM}"))?;
+    data.get(header_len..)?
+        .chunks_exact(PROPERTY_ENTRY_SIZE)
+        .find(|entry| {
+            u16::from_le_bytes([entry[0], entry[1]]) == property_type
+                && u16::from_le_bytes([entry[2], entry[3]]) == id
+        })
+        .and_then(|entry| entry[8..16].try_into().ok())
+}
+
+/// Convert a Windows FILETIME (100ns ticks since 1601) to RFC 3339
+fn filetime_to_rfc3339(filetime: u64) -> Option<String> {
+    let secs = i64::try_from(filetime / 10_000_000).ok()? - FILETIME_UNIX_OFFSET_SECS;
+    let nanos = (filetime % 10_000_000) as u32 * 100;
+    chrono::DateTime::from_timestamp(secs, nanos).map(|date| date.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn utf16(value: &str) -> Vec<u8> {
+        value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn property_entry(id: u16, property_type: u16, value: u64) -> Vec<u8> {
+        let mut entry = Vec::with_capacity(PROPERTY_ENTRY_SIZE);
+        entry.extend_from_slice(&property_type.to_le_bytes());
+        entry.extend_from_slice(&id.to_le_bytes());
+        entry.extend_from_slice(&0u32.to_le_bytes());
+        entry.extend_from_slice(&value.to_le_bytes());
+        entry
+    }
+
+    fn write_stream(msg: &mut CompoundFile<Cursor<Vec<u8>>>, path: &str, data: &[u8]) {
+        msg.create_stream(path).unwrap().write_all(data).unwrap();
+    }
+
+    /// Build a .msg with one To and one Cc recipient and a text attachment
+    fn create_test_msg(plain_body: Option<&str>, html_body: Option<&str>) -> Vec<u8> {
+        let mut msg = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+
+        // 2024-01-15T10:30:00Z as a FILETIME
+        let submit_time = (1_705_314_600 + FILETIME_UNIX_OFFSET_SECS) as u64 * 10_000_000;
+        let mut properties = vec![0u8; MESSAGE_PROPERTIES_HEADER];
+        properties.extend(property_entry(
+            PID_CLIENT_SUBMIT_TIME,
+            PT_SYSTIME,
+            submit_time,
+        ));
+        write_stream(&mut msg, "/__properties_version1.0", &properties);
+        write_stream(
+            &mut msg,
+            "/__substg1.0_0037001F",
+            &utf16("Quarterly review"),
+        );
+        write_stream(&mut msg, "/__substg1.0_0C1A001F", &utf16("Ada Sender"));
+        write_stream(&mut msg, "/__substg1.0_5D01001F", &utf16("ada@example.com"));
+        write_stream(&mut msg, "/__substg1.0_1035001E", b"<id-1@example.com>");
+        if let Some(body) = plain_body {
+            write_stream(&mut msg, "/__substg1.0_1000001F", &utf16(body));
+        }
+        if let Some(html) = html_body {
+            write_stream(&mut msg, "/__substg1.0_10130102", html.as_bytes());
+        }
+
+        for (i, (address, kind)) in [("bob@example.com", MAPI_TO), ("carol@example.com", MAPI_CC)]
+            .into_iter()
+            .enumerate()
+        {
+            let storage = format!("/__recip_version1.0_#{i:08X}");
+            msg.create_storage(&storage).unwrap();
+            let mut properties = vec![0u8; SUBOBJECT_PROPERTIES_HEADER];
+            properties.extend(property_entry(PID_RECIPIENT_TYPE, PT_LONG, kind as u64));
+            write_stream(
+                &mut msg,
+                &format!("{storage}/__properties_version1.0"),
+                &properties,
+            );
+            write_stream(
+                &mut msg,
+                &format!("{storage}/__substg1.0_39FE001F"),
+                &utf16(address),
+            );
+        }
+
+        let storage = "/__attach_version1.0_#00000000";
+        msg.create_storage(storage).unwrap();
+        write_stream(
+            &mut msg,
+            &format!("{storage}/__substg1.0_3707001F"),
+            &utf16("notes.txt"),
+        );
+        write_stream(
+            &mut msg,
+            &format!("{storage}/__substg1.0_37010102"),
+            b"Attached meeting notes",
+        );
+
+        msg.flush().unwrap();
+        msg.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_extract_msg_headers_body_and_attachments() {
+        let msg = create_test_msg(Some("Numbers are up this quarter."), None);
+        assert_eq!(
+            crate::extract::sniff::sniff_mime(&msg)
+                .unwrap()
+                .essence_str(),
+            "application/vnd.ms-outlook"
+        );
+
+        let options = ExtractionOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        let extraction = extract_with_options(&msg, &options, &EmailOptions::default()).unwrap();
+
+        assert!(extraction.text.contains("Subject: Quarterly review"));
+        assert!(extraction.text.contains("From: ada@example.com"));
+        assert!(extraction.text.contains("To: bob@example.com"));
+        assert!(extraction.text.contains("Cc: carol@example.com"));
+        assert!(extraction.text.contains("Numbers are up this quarter."));
+        assert!(
+            extraction
+                .text
+                .contains("[notes.txt]\nAttached meeting notes")
+        );
+
+        let meta = extraction.metadata.unwrap();
+        assert_eq!(meta["format"], "msg");
+        assert_eq!(meta["from_name"], "Ada Sender");
+        assert_eq!(meta["date"], "2024-01-15T10:30:00+00:00");
+        assert_eq!(meta["message_id"], "<id-1@example.com>");
+        assert_eq!(meta["attachment_count"], 1);
+        assert_eq!(meta["attachments"][0]["content_type"], "text/plain");
+    }
+
+    #[test]
+    fn test_extract_msg_html_body() {
+        let msg = create_test_msg(
+            None,
+            Some("<html><body><p>Hello <b>team</b></p><script>x()</script></body></html>"),
+        );
+        let email_opts = EmailOptions {
+            extract_attachments: false,
+            ..Default::default()
+        };
+        let extraction =
+            extract_with_options(&msg, &ExtractionOptions::default(), &email_opts).unwrap();
+
+        assert!(extraction.text.contains("Hello team"));
+        assert!(!extraction.text.contains("<p>"));
+        assert!(!extraction.text.contains("x()"));
+        assert!(!extraction.text.contains("Attached meeting notes"));
+    }
+
+    #[test]
+    fn test_rejects_non_msg_compound_file() {
+        let mut cfb = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        write_stream(&mut cfb, "/WordDocument", b"not a message");
+        cfb.flush().unwrap();
+        let bytes = cfb.into_inner().into_inner();
+
+        assert!(
+            extract_with_options(
+                &bytes,
+                &ExtractionOptions::default(),
+                &EmailOptions::default()
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,11 +1,15 @@
 use mime::Mime;
+use semantic_explorer_core::extraction::capability_for;
 use unicode_normalization::UnicodeNormalization;
 
 use crate::extract::config::{ExtractionConfig, ExtractionOptions};
 use crate::extract::error::{ExtractionError, ExtractionResult};
+use crate::extract::language;
+use crate::extract::limit;
+use crate::extract::trace::ExtractionTrace;
 use crate::extract::{
-    archive, email, epub, html, json, legacy_doc, legacy_ppt, legacy_xls, log, markdown, office,
-    open_office, pdf, rtf, xml,
+    archive, email, epub, html, json, legacy_doc, legacy_ppt, legacy_xls, log, markdown, msg,
+    office, open_office, pdf, rtf, sniff, xml,
 };
 
 /// Result of text extraction with optional metadata
@@ -20,23 +24,88 @@ pub fn extract(
     buffer: &[u8],
     config: &ExtractionConfig,
 ) -> ExtractionResult<ExtractedContent> {
+    let options = &config.options;
+    let mut trace = ExtractionTrace::new(mime_type.essence_str(), buffer.len());
+
+    let result = match process_mime_type(mime_type, buffer, options, &mut trace) {
+        Ok(result) => result,
+        Err(error) if options.sniff_mime_fallback => {
+            // The declared type may simply be wrong; retry once with the sniffed type
+            let sniffed = sniff::sniff_mime(buffer)
+                .filter(|sniffed| sniffed.essence_str() != mime_type.essence_str())
+                .ok_or(error)?;
+            tracing::debug!(
+                declared = %mime_type,
+                sniffed = %sniffed,
+                "extraction with declared MIME type failed, retrying with sniffed type"
+            );
+            trace.fallback(format!(
+                "{} extraction failed; retried as sniffed type {}",
+                mime_type.essence_str(),
+                sniffed.essence_str()
+            ));
+            process_mime_type(&sniffed, buffer, options, &mut trace)?
+        }
+        Err(error) => return Err(error),
+    };
+
+    // Cut before cleaning to bound the work, and again after since
+    // normalization can change the byte length
+    let mut raw_text = result.text;
+    let mut truncated = limit::truncate_to_limit(&mut raw_text, options.max_output_bytes);
+    let mut text = clean_text(&raw_text);
+    truncated |= limit::truncate_to_limit(&mut text, options.max_output_bytes);
+
+    let metadata = if truncated && !limit::is_truncated(&result.metadata) {
+        limit::mark_truncated(result.metadata, options.max_output_bytes)
+    } else {
+        result.metadata
+    };
+
+    let metadata = if options.detect_language {
+        language::attach(metadata, &text)
+    } else {
+        metadata
+    };
+
+    let metadata = if options.trace {
+        trace.set_output_chars(text.chars().count());
+        if text.is_empty() && !buffer.is_empty() {
+            trace.warn("extraction produced no text from a non-empty file");
+        }
+        if let Some(max) = options.max_output_bytes
+            && limit::is_truncated(&metadata)
+        {
+            trace.warn(format!("output truncated at max_output_bytes ({max})"));
+        }
+        trace.attach(metadata)
+    } else {
+        metadata
+    };
+
+    Ok(ExtractedContent { text, metadata })
+}
+
+fn process_mime_type(
+    mime_type: &Mime,
+    buffer: &[u8],
+    options: &ExtractionOptions,
+    trace: &mut ExtractionTrace,
+) -> ExtractionResult<InternalExtraction> {
+    let extractor = capability_for(mime_type.essence_str()).map_or("unsupported", |c| c.format);
+    trace.begin(mime_type.essence_str(), extractor);
+
     let result = match mime_type.type_() {
         mime::APPLICATION => {
-            process_application_type(mime_type.subtype().as_str(), buffer, &config.options)
-        }
-        mime::TEXT => process_text_type(mime_type, buffer, &config.options),
-        mime::MESSAGE => {
-            process_message_type(mime_type.subtype().as_str(), buffer, &config.options)
+            process_application_type(mime_type.subtype().as_str(), buffer, options, trace)
         }
+        mime::TEXT => process_text_type(mime_type, buffer, options, trace),
+        mime::MESSAGE => process_message_type(mime_type.subtype().as_str(), buffer, options),
         _ => Err(ExtractionError::unsupported_mime(mime_type.to_string())),
-    }?;
-
-    let text = clean_text(&result.text);
+    };
 
-    Ok(ExtractedContent {
-        text,
-        metadata: result.metadata,
-    })
+    trace.finish(&result);
+    result
 }
 
 /// Internal extraction result that includes metadata
@@ -58,6 +127,7 @@ fn process_application_type(
     sub_type: &str,
     buffer: &[u8],
     options: &ExtractionOptions,
+    trace: &mut ExtractionTrace,
 ) -> ExtractionResult<InternalExtraction> {
     match sub_type {
         "pdf" => {
@@ -77,6 +147,7 @@ fn process_application_type(
         "msword" => {
             // Check if this is a legacy .doc (OLE/CFB format) or modern .docx
             if legacy_doc::is_legacy_doc(buffer) {
+                trace.sub_extractor("legacy_doc");
                 let result = legacy_doc::extract_with_metadata(buffer, options)
                     .map_err(|e| ExtractionError::parse_error("Legacy DOC", e.to_string()))?;
                 Ok(InternalExtraction {
@@ -85,6 +156,7 @@ fn process_application_type(
                 })
             } else {
                 // Try as modern .docx
+                trace.sub_extractor("office_document");
                 let text = office::extract_text_from_document(buffer)
                     .map_err(|e| ExtractionError::parse_error("Word", e.to_string()))?;
 
@@ -113,6 +185,7 @@ fn process_application_type(
         "vnd.ms-excel" => {
             // Check if this is a legacy .xls (OLE/CFB format) or modern .xlsx
             if legacy_xls::is_legacy_xls(buffer) {
+                trace.sub_extractor("legacy_xls");
                 let result = legacy_xls::extract_with_metadata(buffer, options)
                     .map_err(|e| ExtractionError::parse_error("Legacy XLS", e.to_string()))?;
                 Ok(InternalExtraction {
@@ -121,6 +194,7 @@ fn process_application_type(
                 })
             } else {
                 // Try as modern .xlsx
+                trace.sub_extractor("office_spreadsheet");
                 let text = office::extract_text_from_spreadsheet(buffer)
                     .map_err(|e| ExtractionError::parse_error("Excel", e.to_string()))?;
                 Ok(InternalExtraction::text_only(text))
@@ -139,6 +213,7 @@ fn process_application_type(
         "mspowerpoint" | "powerpoint" | "vnd.ms-powerpoint" | "x-mspowerpoint" => {
             // Check if this is a legacy .ppt (OLE/CFB format) or modern .pptx
             if legacy_ppt::is_legacy_ppt(buffer) {
+                trace.sub_extractor("legacy_ppt");
                 let result = legacy_ppt::extract_with_metadata(buffer, options)
                     .map_err(|e| ExtractionError::parse_error("Legacy PPT", e.to_string()))?;
                 Ok(InternalExtraction {
@@ -147,6 +222,7 @@ fn process_application_type(
                 })
             } else {
                 // Try as modern .pptx
+                trace.sub_extractor("office_presentation");
                 let text = office::extract_text_from_presentation(buffer)
                     .map_err(|e| ExtractionError::parse_error("PowerPoint", e.to_string()))?;
                 Ok(InternalExtraction::text_only(text))
@@ -204,7 +280,10 @@ fn process_application_type(
             Ok(InternalExtraction::text_only(text))
         }
         "zip" | "x-zip-compressed" => {
-            let archive_opts = archive::ArchiveOptions::default();
+            let archive_opts = archive::ArchiveOptions {
+                zip_password: options.zip_password.clone(),
+                ..Default::default()
+            };
             let result = archive::extract_from_zip(buffer, options, &archive_opts)
                 .map_err(|e| ExtractionError::archive_error("ZIP", e.to_string()))?;
             Ok(InternalExtraction {
@@ -215,13 +294,18 @@ fn process_application_type(
         "gzip" | "x-gzip" => {
             // Check if it might be tar.gz based on content
             let archive_opts = archive::ArchiveOptions::default();
+            trace.sub_extractor("tar_gz");
             match archive::extract_from_tar_gz(buffer, options, &archive_opts) {
                 Ok(result) => Ok(InternalExtraction {
                     text: result.text,
                     metadata: result.metadata,
                 }),
-                Err(_) => {
+                Err(e) => {
                     // Fall back to simple gzip decompression
+                    trace.sub_extractor("gzip");
+                    trace.fallback(format!(
+                        "tar.gz extraction failed ({e}); decompressed as plain gzip"
+                    ));
                     let decompressed = archive::extract_from_gzip(buffer, options)
                         .map_err(|e| ExtractionError::archive_error("GZIP", e.to_string()))?;
                     let text = String::from_utf8_lossy(&decompressed).to_string();
@@ -230,11 +314,13 @@ fn process_application_type(
             }
         }
         "x-tar" | "tar" => {
-            // Uncompressed tar - not yet implemented
-            Err(ExtractionError::unsupported_mime_with_context(
-                format!("application/{}", sub_type),
-                "uncompressed tar not yet supported",
-            ))
+            let archive_opts = archive::ArchiveOptions::default();
+            let result = archive::extract_from_tar(buffer, options, &archive_opts)
+                .map_err(|e| ExtractionError::archive_error("TAR", e.to_string()))?;
+            Ok(InternalExtraction {
+                text: result.text,
+                metadata: result.metadata,
+            })
         }
         // RTF (Rich Text Format)
         "rtf" | "x-rtf" => {
@@ -256,11 +342,13 @@ fn process_application_type(
         }
         // Email formats
         "vnd.ms-outlook" => {
-            // .msg format - requires different parser, mark as unsupported for now
-            Err(ExtractionError::unsupported_mime_with_context(
-                "application/vnd.ms-outlook".to_string(),
-                "Outlook .msg format requires separate parser",
-            ))
+            let email_opts = email::EmailOptions::default();
+            let result = msg::extract_with_options(buffer, options, &email_opts)
+                .map_err(|e| ExtractionError::parse_error("Outlook MSG", e.to_string()))?;
+            Ok(InternalExtraction {
+                text: result.text,
+                metadata: result.metadata,
+            })
         }
         _ => Err(ExtractionError::unsupported_mime_with_context(
             format!("application/{}", sub_type),
@@ -273,12 +361,14 @@ fn process_text_type(
     content_type: &Mime,
     buffer: &[u8],
     options: &ExtractionOptions,
+    trace: &mut ExtractionTrace,
 ) -> ExtractionResult<InternalExtraction> {
     match content_type.subtype().as_str() {
         "plain" => {
             // Check if plain text is actually a log file
             let content = String::from_utf8_lossy(buffer);
             if log::is_log_file(&content) {
+                trace.sub_extractor("log");
                 let result = log::extract_with_options(buffer, options)
                     .map_err(|e| ExtractionError::parse_error("Log", e.to_string()))?;
                 return Ok(InternalExtraction {
@@ -388,6 +478,7 @@ fn clean_text(raw_text: &str) -> String {
 mod tests {
     use super::*;
     use crate::extract::config::{ExtractionConfig, ExtractionStrategy};
+    use crate::extract::pdf::build_pdf;
 
     fn create_default_config() -> ExtractionConfig {
         ExtractionConfig {
@@ -561,6 +652,226 @@ mod tests {
         assert!(!extraction.text.contains('\x02'));
     }
 
+    #[test]
+    fn test_mislabeled_pdf_extracts_via_sniff_reroute() {
+        let pdf = build_pdf("Quarterly report");
+        let mime_type: mime::Mime = "application/msword".parse().unwrap();
+        let config = create_default_config();
+
+        let extraction = extract(&mime_type, &pdf, &config).unwrap();
+        assert!(extraction.text.contains("Quarterly report"));
+    }
+
+    #[test]
+    fn test_mislabeled_pdf_fails_without_sniff_fallback() {
+        let pdf = build_pdf("Quarterly report");
+        let mime_type: mime::Mime = "application/msword".parse().unwrap();
+        let mut config = create_default_config();
+        config.options.sniff_mime_fallback = false;
+
+        let error = extract(&mime_type, &pdf, &config).unwrap_err();
+        assert!(error.to_string().contains("Word"));
+    }
+
+    #[test]
+    fn test_trace_records_sniff_fallback() {
+        let pdf = build_pdf("Quarterly report");
+        let mime_type: mime::Mime = "application/msword".parse().unwrap();
+        let mut config = create_default_config();
+        config.options.trace = true;
+
+        let extraction = extract(&mime_type, &pdf, &config).unwrap();
+        let trace = &extraction.metadata.unwrap()["extraction_trace"];
+
+        assert_eq!(trace["declared_mime_type"], "application/msword");
+        assert_eq!(trace["bytes_processed"], pdf.len());
+        let steps = trace["steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0]["extractor"], "word");
+        assert_eq!(steps[0]["sub_extractor"], "office_document");
+        assert_eq!(steps[0]["succeeded"], false);
+        assert!(steps[0]["error"].is_string());
+        assert_eq!(steps[1]["mime_type"], "application/pdf");
+        assert_eq!(steps[1]["succeeded"], true);
+
+        let fallbacks = trace["fallbacks"].as_array().unwrap();
+        assert_eq!(fallbacks.len(), 1);
+        assert!(
+            fallbacks[0]
+                .as_str()
+                .unwrap()
+                .contains("retried as sniffed type application/pdf")
+        );
+    }
+
+    #[test]
+    fn test_trace_absent_unless_enabled() {
+        let content = b"Hello, World!";
+        let mime_type: mime::Mime = "text/plain".parse().unwrap();
+
+        let extraction = extract(&mime_type, content, &create_default_config()).unwrap();
+        assert!(
+            extraction
+                .metadata
+                .unwrap()
+                .get("extraction_trace")
+                .is_none()
+        );
+
+        let mut config = create_default_config();
+        config.options.trace = true;
+        let extraction = extract(&mime_type, content, &config).unwrap();
+        let trace = &extraction.metadata.unwrap()["extraction_trace"];
+        assert_eq!(trace["output_chars"], 13);
+        assert!(trace["fallbacks"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_max_output_bytes_caps_text_and_flags_metadata() {
+        let content = "word ".repeat(1000);
+        let mime_type: mime::Mime = "text/plain".parse().unwrap();
+        let mut config = create_default_config();
+        config.options.max_output_bytes = Some(64);
+
+        let extraction = extract(&mime_type, content.as_bytes(), &config).unwrap();
+        assert!(extraction.text.len() <= 64);
+        assert!(extraction.text.starts_with("word word"));
+        let metadata = extraction.metadata.unwrap();
+        assert_eq!(metadata["truncated"], true);
+        assert_eq!(metadata["max_output_bytes"], 64);
+
+        // Output under the limit is left alone and not flagged
+        config.options.max_output_bytes = Some(1 << 20);
+        let extraction = extract(&mime_type, content.as_bytes(), &config).unwrap();
+        assert_eq!(extraction.text, content.trim());
+        assert!(extraction.metadata.unwrap().get("truncated").is_none());
+    }
+
+    #[test]
+    fn test_detected_language_recorded_unless_disabled() {
+        let content = "Dies ist ein deutscher Text, der lang genug ist, damit die \
+                       Sprache zuverlässig erkannt werden kann.";
+        let mime_type: mime::Mime = "text/plain".parse().unwrap();
+
+        let mut config = create_default_config();
+        let extraction = extract(&mime_type, content.as_bytes(), &config).unwrap();
+        let metadata = extraction.metadata.unwrap();
+        assert_eq!(metadata["detected_language"], "de");
+        assert!(metadata["language_confidence"].is_number());
+
+        let extraction = extract(&mime_type, b"Kurz.", &config).unwrap();
+        assert_eq!(extraction.metadata.unwrap()["detected_language"], "unknown");
+
+        config.options.detect_language = false;
+        let extraction = extract(&mime_type, content.as_bytes(), &config).unwrap();
+        assert!(extraction.metadata.is_none());
+    }
+
+    #[test]
+    fn test_max_output_bytes_bounds_archive_output() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        {
+            use std::io::Write;
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            for i in 0..50 {
+                zip.start_file(format!("file{i}.txt"), options).unwrap();
+                zip.write_all("archive text ".repeat(100).as_bytes())
+                    .unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        let mime_type: mime::Mime = "application/zip".parse().unwrap();
+        let mut config = create_default_config();
+        config.options.max_output_bytes = Some(2000);
+
+        let extraction = extract(&mime_type, buffer.get_ref(), &config).unwrap();
+        assert!(extraction.text.len() <= 2000);
+        assert!(extraction.text.contains("--- file0.txt ---"));
+        assert!(!extraction.text.contains("--- file49.txt ---"));
+        assert_eq!(extraction.metadata.unwrap()["truncated"], true);
+    }
+
+    #[test]
+    fn test_sniff_reroute_keeps_original_error_when_undetectable() {
+        let mime_type: mime::Mime = "application/msword".parse().unwrap();
+        let config = create_default_config();
+
+        let error = extract(&mime_type, b"not a document", &config).unwrap_err();
+        assert!(error.to_string().contains("Word"));
+    }
+
+    fn is_unsupported(mime_type: &Mime) -> bool {
+        matches!(
+            process_mime_type(
+                mime_type,
+                b"not a real document",
+                &ExtractionOptions::default(),
+                &mut ExtractionTrace::default(),
+            ),
+            Err(ExtractionError::UnsupportedMimeType { .. })
+        )
+    }
+
+    #[test]
+    fn test_capabilities_are_all_routed() {
+        use semantic_explorer_core::extraction::EXTRACTION_FORMATS;
+
+        for format in EXTRACTION_FORMATS {
+            for mime_type in format.mime_types {
+                let mime: Mime = mime_type.parse().unwrap();
+                assert!(
+                    !is_unsupported(&mime),
+                    "{mime_type} is advertised but not handled by the extractor"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities_include_registered_formats() {
+        use semantic_explorer_core::extraction::capability_for;
+
+        // Scan the match arms of the dispatch functions so new formats can't
+        // be added without advertising them.
+        let source = include_str!("mod.rs");
+        let section = |start: &str, end: &str| {
+            let from = source.find(start).unwrap();
+            let to = source[from..].find(end).unwrap() + from;
+            &source[from..to]
+        };
+        let arm = regex::Regex::new(r#""([A-Za-z0-9.+\-]+)"\s*(?:\||=>)"#).unwrap();
+        let sections = [
+            (
+                "application",
+                section("fn process_application_type", "fn process_text_type"),
+            ),
+            (
+                "text",
+                section("fn process_text_type", "fn process_message_type"),
+            ),
+            (
+                "message",
+                section("fn process_message_type", "fn clean_text"),
+            ),
+        ];
+
+        let mut checked = 0;
+        for (top_level, body) in sections {
+            for subtype in arm.captures_iter(body).map(|c| c[1].to_string()) {
+                let mime_type = format!("{top_level}/{subtype}");
+                let mime: Mime = mime_type.parse().unwrap();
+                assert!(
+                    capability_for(&mime_type).is_some() || is_unsupported(&mime),
+                    "{mime_type} is extracted but missing from the capability list"
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 40, "match arm scan found only {checked} formats");
+    }
+
     #[test]
     fn test_extract_tabs_and_newlines_preserved() {
         let content = b"Line1\tTabbed\nLine2\rLine3\r\nLine4";
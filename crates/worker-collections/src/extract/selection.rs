@@ -0,0 +1,138 @@
+//! Per-file extraction strategy selection from the MIME type of the file.
+//!
+//! A transform extracts every file with one strategy, but spreadsheets keep
+//! their meaning only with table structure, while logs and plain text are
+//! best left as they are. `mime_overrides` replaces the strategy for
+//! matching files, and the `auto` strategy picks a default per file type.
+
+use mime::Mime;
+use semantic_explorer_core::extraction::capability_for;
+
+use super::config::{ExtractionConfig, ExtractionStrategy};
+
+/// The strategy to extract a file of `mime_type` with: the matching
+/// override if any, otherwise the transform's own strategy, with `auto`
+/// resolved by file type. Never `auto`.
+pub(crate) fn resolve_strategy(config: &ExtractionConfig, mime_type: &Mime) -> ExtractionStrategy {
+    let strategy = find_override(config, mime_type).unwrap_or(&config.strategy);
+    match strategy {
+        ExtractionStrategy::Auto => default_strategy(mime_type),
+        strategy => strategy.clone(),
+    }
+}
+
+/// Exact MIME type first, then the `type/*` wildcard
+fn find_override<'a>(
+    config: &'a ExtractionConfig,
+    mime_type: &Mime,
+) -> Option<&'a ExtractionStrategy> {
+    config
+        .mime_overrides
+        .get(mime_type.essence_str())
+        .or_else(|| {
+            config
+                .mime_overrides
+                .get(&format!("{}/*", mime_type.type_()))
+        })
+}
+
+/// Strategy `auto` uses for a MIME type
+fn default_strategy(mime_type: &Mime) -> ExtractionStrategy {
+    if mime_type.essence_str() == "text/tab-separated-values" {
+        return ExtractionStrategy::StructurePreserving;
+    }
+    match capability_for(mime_type.essence_str()).map(|c| c.format) {
+        Some("excel" | "legacy_excel" | "open_document_spreadsheet" | "csv") => {
+            ExtractionStrategy::StructurePreserving
+        }
+        Some("html" | "markdown") => ExtractionStrategy::Markdown,
+        _ => ExtractionStrategy::PlainText,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mime(value: &str) -> Mime {
+        value.parse().unwrap()
+    }
+
+    fn auto_config() -> ExtractionConfig {
+        ExtractionConfig {
+            strategy: ExtractionStrategy::Auto,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_auto_uses_table_aware_strategy_for_spreadsheets_and_plain_for_logs() {
+        let config = auto_config();
+
+        for spreadsheet in [
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "application/vnd.ms-excel",
+            "application/vnd.oasis.opendocument.spreadsheet",
+            "text/csv",
+        ] {
+            assert!(
+                matches!(
+                    resolve_strategy(&config, &mime(spreadsheet)),
+                    ExtractionStrategy::StructurePreserving
+                ),
+                "{spreadsheet}"
+            );
+        }
+
+        for plain in ["text/x-log", "text/plain", "application/pdf", "video/mp4"] {
+            assert!(
+                matches!(
+                    resolve_strategy(&config, &mime(plain)),
+                    ExtractionStrategy::PlainText
+                ),
+                "{plain}"
+            );
+        }
+
+        assert!(matches!(
+            resolve_strategy(&config, &mime("text/html; charset=utf-8")),
+            ExtractionStrategy::Markdown
+        ));
+    }
+
+    #[test]
+    fn test_overrides_prefer_exact_match_over_wildcard() {
+        let config: ExtractionConfig = serde_json::from_value(json!({
+            "strategy": "plain_text",
+            "mime_overrides": {
+                "text/csv": "plain_text",
+                "text/*": "markdown",
+                "application/vnd.ms-excel": "auto"
+            }
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            resolve_strategy(&config, &mime("text/csv")),
+            ExtractionStrategy::PlainText
+        ));
+        assert!(matches!(
+            resolve_strategy(&config, &mime("text/x-log")),
+            ExtractionStrategy::Markdown
+        ));
+        // An `auto` override falls back to the per-format default
+        assert!(matches!(
+            resolve_strategy(&config, &mime("application/vnd.ms-excel")),
+            ExtractionStrategy::StructurePreserving
+        ));
+        // Unmatched files keep the transform's strategy
+        assert!(matches!(
+            resolve_strategy(
+                &config,
+                &mime("application/vnd.oasis.opendocument.spreadsheet")
+            ),
+            ExtractionStrategy::PlainText
+        ));
+    }
+}
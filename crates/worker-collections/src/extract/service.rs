@@ -2,7 +2,11 @@ use mime::Mime;
 
 use super::config::{ExtractionConfig, ExtractionOutput, ExtractionStrategy};
 use super::error::ExtractionResult;
+use super::language::{LANGUAGE_CONFIDENCE_METADATA_KEY, LANGUAGE_METADATA_KEY};
+use super::limit::{MAX_OUTPUT_BYTES_METADATA_KEY, TRUNCATED_METADATA_KEY};
 use super::plain_text;
+use super::selection::resolve_strategy;
+use super::trace::TRACE_METADATA_KEY;
 
 pub struct ExtractionService;
 
@@ -12,8 +16,11 @@ impl ExtractionService {
         buffer: &[u8],
         config: &ExtractionConfig,
     ) -> ExtractionResult<ExtractionOutput> {
-        let result = match config.strategy {
-            ExtractionStrategy::PlainText => plain_text::extract(mime_type, buffer, config)?,
+        // `resolve_strategy` never returns `auto`
+        let result = match resolve_strategy(config, mime_type) {
+            ExtractionStrategy::PlainText | ExtractionStrategy::Auto => {
+                plain_text::extract(mime_type, buffer, config)?
+            }
             ExtractionStrategy::StructurePreserving => {
                 // Structure-preserving extraction: enables all structure options
                 // while maintaining plain text output format
@@ -75,6 +82,16 @@ fn append_metadata_as_text(text: &str, metadata: &Option<serde_json::Value>) ->
     entries.sort_by_key(|(k, _)| *k);
 
     for (key, value) in entries {
+        // The extraction trace, truncation flags and detected language
+        // describe the extraction, not the document
+        if key == TRACE_METADATA_KEY
+            || key == TRUNCATED_METADATA_KEY
+            || key == MAX_OUTPUT_BYTES_METADATA_KEY
+            || key == LANGUAGE_METADATA_KEY
+            || key == LANGUAGE_CONFIDENCE_METADATA_KEY
+        {
+            continue;
+        }
         let formatted_key = key
             .replace('_', " ")
             .split_whitespace()
@@ -160,7 +177,7 @@ mod tests {
     fn test_plain_text_extraction() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/plain".parse().unwrap();
@@ -176,7 +193,7 @@ mod tests {
     fn test_html_extraction() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/html".parse().unwrap();
@@ -193,7 +210,7 @@ mod tests {
     fn test_xml_extraction() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "application/xml".parse().unwrap();
@@ -209,7 +226,7 @@ mod tests {
     fn test_csv_extraction() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/csv".parse().unwrap();
@@ -226,7 +243,7 @@ mod tests {
     fn test_unsupported_mime_type() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "video/mp4".parse().unwrap();
@@ -246,7 +263,7 @@ mod tests {
     fn test_structure_preserving_extracts_with_options() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::StructurePreserving,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/html".parse().unwrap();
@@ -264,7 +281,7 @@ mod tests {
     fn test_markdown_strategy_formats_as_markdown() {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::Markdown,
-            options: ExtractionOptions::default(),
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/html".parse().unwrap();
@@ -299,6 +316,7 @@ mod tests {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
             options,
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/html".parse().unwrap();
@@ -323,6 +341,7 @@ mod tests {
         let config = ExtractionConfig {
             strategy: ExtractionStrategy::PlainText,
             options,
+            ..Default::default()
         };
 
         let mime_type: mime::Mime = "text/plain".parse().unwrap();
@@ -0,0 +1,147 @@
+//! Content sniffing for files whose declared MIME type does not match their bytes.
+//!
+//! Only formats the extractors understand are detected; anything else returns
+//! `None` so the original extraction error is reported.
+
+use std::io::{Cursor, Read};
+
+use cfb::CompoundFile;
+use mime::Mime;
+use zip::ZipArchive;
+
+use crate::extract::msg;
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const RTF_MAGIC: &[u8] = b"{\\rtf";
+
+/// Longest `mimetype` entry read from a ZIP; real ones are a few dozen bytes
+const MAX_ZIP_MIMETYPE_LEN: u64 = 256;
+
+/// Detect the MIME type of a buffer from its content.
+pub(crate) fn sniff_mime(buffer: &[u8]) -> Option<Mime> {
+    let essence = if buffer.starts_with(PDF_MAGIC) {
+        "application/pdf"
+    } else if buffer.starts_with(OLE_MAGIC) {
+        return sniff_ole(buffer);
+    } else if buffer.starts_with(ZIP_MAGIC) {
+        return sniff_zip(buffer);
+    } else if buffer.starts_with(GZIP_MAGIC) {
+        "application/gzip"
+    } else if buffer.starts_with(RTF_MAGIC) {
+        "application/rtf"
+    } else {
+        return None;
+    };
+    essence.parse().ok()
+}
+
+/// Tell legacy Office formats and Outlook messages apart by the streams in
+/// the compound file.
+fn sniff_ole(buffer: &[u8]) -> Option<Mime> {
+    let cfb = CompoundFile::open(Cursor::new(buffer)).ok()?;
+    let essence = if cfb.exists("/WordDocument") {
+        "application/msword"
+    } else if cfb.exists("/Workbook") || cfb.exists("/Book") {
+        "application/vnd.ms-excel"
+    } else if cfb.exists("/PowerPoint Document") {
+        "application/vnd.ms-powerpoint"
+    } else if cfb.exists(format!("/{}", msg::PROPERTIES_STREAM)) {
+        "application/vnd.ms-outlook"
+    } else {
+        return None;
+    };
+    essence.parse().ok()
+}
+
+/// Tell OOXML, OpenDocument and EPUB apart from plain ZIP archives.
+fn sniff_zip(buffer: &[u8]) -> Option<Mime> {
+    let mut zip = ZipArchive::new(Cursor::new(buffer)).ok()?;
+
+    // OpenDocument and EPUB store their MIME type in an uncompressed `mimetype` entry
+    if let Ok(entry) = zip.by_name("mimetype") {
+        let mut declared = String::new();
+        if entry
+            .take(MAX_ZIP_MIMETYPE_LEN + 1)
+            .read_to_string(&mut declared)
+            .is_ok()
+            && declared.len() as u64 <= MAX_ZIP_MIMETYPE_LEN
+            && let Ok(mime) = declared.trim().parse()
+        {
+            return Some(mime);
+        }
+    }
+
+    let has = |zip: &ZipArchive<_>, name: &str| zip.file_names().any(|n| n == name);
+    let essence = if has(&zip, "word/document.xml") {
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    } else if has(&zip, "xl/workbook.xml") {
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+    } else if has(&zip, "ppt/presentation.xml") {
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+    } else {
+        "application/zip"
+    };
+    essence.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    fn zip_with(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_sniff_magic_bytes() {
+        assert_eq!(
+            sniff_mime(b"%PDF-1.7\n...").unwrap().essence_str(),
+            "application/pdf"
+        );
+        assert_eq!(
+            sniff_mime(b"{\\rtf1\\ansi hello}").unwrap().essence_str(),
+            "application/rtf"
+        );
+        assert!(sniff_mime(b"just some text").is_none());
+        assert!(sniff_mime(b"").is_none());
+    }
+
+    #[test]
+    fn test_sniff_zip_containers() {
+        let docx = zip_with(&[("word/document.xml", "<w:document/>")]);
+        assert_eq!(
+            sniff_mime(&docx).unwrap().essence_str(),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+
+        let odt = zip_with(&[("mimetype", "application/vnd.oasis.opendocument.text")]);
+        assert_eq!(
+            sniff_mime(&odt).unwrap().essence_str(),
+            "application/vnd.oasis.opendocument.text"
+        );
+
+        let plain = zip_with(&[("notes.txt", "hello")]);
+        assert_eq!(sniff_mime(&plain).unwrap().essence_str(), "application/zip");
+    }
+
+    #[test]
+    fn test_oversized_zip_mimetype_is_ignored() {
+        // Deflates to a few dozen KiB but would inflate to 16 MiB if read whole
+        let padded = format!("application/epub+zip{}", " ".repeat(16 << 20));
+        let zip = zip_with(&[("mimetype", &padded)]);
+        assert!(zip.len() < 1 << 20);
+        assert_eq!(sniff_mime(&zip).unwrap().essence_str(), "application/zip");
+    }
+}
@@ -0,0 +1,96 @@
+//! Opt-in record of the path an extraction took, for diagnosing problem documents.
+//!
+//! Enabled with `ExtractionOptions::trace`; the trace is attached to the
+//! extraction metadata under [`TRACE_METADATA_KEY`].
+
+use serde::Serialize;
+
+/// Metadata key holding the serialized trace.
+pub const TRACE_METADATA_KEY: &str = "extraction_trace";
+
+#[derive(Debug, Serialize)]
+struct TraceStep {
+    mime_type: String,
+    /// Format handler selected for the MIME type
+    extractor: &'static str,
+    /// Concrete parser used when a handler picks between several
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub_extractor: Option<&'static str>,
+    succeeded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ExtractionTrace {
+    declared_mime_type: String,
+    bytes_processed: usize,
+    output_chars: usize,
+    steps: Vec<TraceStep>,
+    fallbacks: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl ExtractionTrace {
+    pub(crate) fn new(declared_mime_type: &str, bytes_processed: usize) -> Self {
+        Self {
+            declared_mime_type: declared_mime_type.to_string(),
+            bytes_processed,
+            ..Default::default()
+        }
+    }
+
+    /// Start a new extraction attempt.
+    pub(crate) fn begin(&mut self, mime_type: &str, extractor: &'static str) {
+        self.steps.push(TraceStep {
+            mime_type: mime_type.to_string(),
+            extractor,
+            sub_extractor: None,
+            succeeded: false,
+            error: None,
+        });
+    }
+
+    /// Record which concrete parser the current attempt used.
+    pub(crate) fn sub_extractor(&mut self, name: &'static str) {
+        if let Some(step) = self.steps.last_mut() {
+            step.sub_extractor = Some(name);
+        }
+    }
+
+    /// Finish the current attempt.
+    pub(crate) fn finish<T, E: std::fmt::Display>(&mut self, result: &Result<T, E>) {
+        if let Some(step) = self.steps.last_mut() {
+            step.succeeded = result.is_ok();
+            step.error = result.as_ref().err().map(|e| e.to_string());
+        }
+    }
+
+    pub(crate) fn fallback(&mut self, description: impl Into<String>) {
+        self.fallbacks.push(description.into());
+    }
+
+    pub(crate) fn warn(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into());
+    }
+
+    pub(crate) fn set_output_chars(&mut self, chars: usize) {
+        self.output_chars = chars;
+    }
+
+    /// Attach the trace to extraction metadata, creating it if needed.
+    pub(crate) fn attach(self, metadata: Option<serde_json::Value>) -> Option<serde_json::Value> {
+        let trace = serde_json::to_value(&self).ok()?;
+        let mut metadata = match metadata {
+            Some(serde_json::Value::Object(map)) => map,
+            Some(other) => {
+                let mut map = serde_json::Map::new();
+                map.insert("metadata".to_string(), other);
+                map
+            }
+            None => serde_json::Map::new(),
+        };
+        metadata.insert(TRACE_METADATA_KEY.to_string(), trace);
+        Some(serde_json::Value::Object(metadata))
+    }
+}
@@ -1,15 +1,34 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use mime::Mime;
 use semantic_explorer_core::models::{CollectionTransformJob, CollectionTransformResult};
 use semantic_explorer_core::observability::record_worker_job;
 use semantic_explorer_core::storage::{DocumentUpload, get_file_with_size_check, upload_document};
 use semantic_explorer_core::validation::{validate_bucket_name, validate_s3_key};
 use semantic_explorer_core::worker::WorkerContext;
+use std::sync::OnceLock;
 use std::time::Instant;
 use tracing::{error, info, instrument};
 
+use crate::chunk::service::{ChunkWithMetadata, limit_chunks, renumber_chunks};
 use crate::chunk::{ChunkingService, config::ChunkingConfig};
+use crate::extract::archive::{self, ArchiveFileResult, ArchiveOptions};
 use crate::extract::{ExtractionService, config::ExtractionConfig};
 
+/// Extracted archive entries waiting to be chunked
+const STREAMED_ENTRY_BUFFER: usize = 4;
+
+/// ZIPs with at least this many entries are chunked entry by entry instead
+/// of as one combined text (`ARCHIVE_STREAMING_ENTRY_THRESHOLD`, default 200)
+fn archive_streaming_threshold() -> usize {
+    static THRESHOLD: OnceLock<usize> = OnceLock::new();
+    *THRESHOLD.get_or_init(|| {
+        std::env::var("ARCHIVE_STREAMING_ENTRY_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(200)
+    })
+}
+
 #[instrument(skip(ctx), fields(job_id = %job.job_id, collection_transform_id = %job.collection_transform_id, file = %job.source_file_key))]
 pub(crate) async fn process_file_job(
     job: CollectionTransformJob,
@@ -53,32 +72,38 @@ pub(crate) async fn process_file_job(
     }
 
     info!(bucket = %s3_bucket_name, key = %full_source_key, "Downloading file");
-    let file_content =
-        match get_file_with_size_check(&ctx.s3_client, s3_bucket_name, &full_source_key).await {
-            Ok(content) => content,
-            Err(e) => {
-                let duration = start_time.elapsed().as_secs_f64();
-                let error_msg = e.to_string();
-
-                // Check if this is a file size error
-                if error_msg.contains("exceeds maximum limit") {
-                    record_worker_job("transform-file", duration, "failed_file_too_large");
-                    error!(error = %e, "File exceeds size limit");
-                } else {
-                    record_worker_job("transform-file", duration, "failed_download");
-                    error!(error = %e, "Failed to download file");
-                }
+    let file_content = match get_file_with_size_check(
+        &ctx.s3_client,
+        s3_bucket_name,
+        &full_source_key,
+        ctx.max_download_size_bytes,
+    )
+    .await
+    {
+        Ok(content) => content,
+        Err(e) => {
+            let duration = start_time.elapsed().as_secs_f64();
+            let error_msg = e.to_string();
 
-                send_result(
-                    &ctx.nats_client,
-                    &job,
-                    Err(error_msg),
-                    Some((duration * 1000.0) as i64),
-                )
-                .await?;
-                return Ok(());
+            // Check if this is a file size error
+            if error_msg.contains("exceeds maximum limit") {
+                record_worker_job("transform-file", duration, "failed_file_too_large");
+                error!(error = %e, "File exceeds size limit");
+            } else {
+                record_worker_job("transform-file", duration, "failed_download");
+                error!(error = %e, "Failed to download file");
             }
-        };
+
+            send_result(
+                &ctx.nats_client,
+                &job,
+                Err(error_msg),
+                Some((duration * 1000.0) as i64),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
     info!(
         file_size_bytes = file_content.len(),
         "Downloaded file successfully"
@@ -103,6 +128,17 @@ pub(crate) async fn process_file_job(
         };
 
     let mime_type = mime_guess::from_path(&job.source_file_key).first_or_octet_stream();
+
+    // Large ZIPs are chunked entry by entry so their text is never held at once
+    if matches!(
+        mime_type.essence_str(),
+        "application/zip" | "application/x-zip-compressed"
+    ) && archive::zip_entry_count(&file_content)
+        .is_some_and(|count| count >= archive_streaming_threshold())
+    {
+        return process_zip_streaming(job, ctx, file_content, extraction_config, start_time).await;
+    }
+
     info!(
         mime_type = %mime_type,
         strategy = ?extraction_config.strategy,
@@ -191,6 +227,22 @@ pub(crate) async fn process_file_job(
             return Ok(());
         }
     };
+
+    #[cfg(feature = "image-captions")]
+    let extraction_result = crate::caption::caption_document(
+        &mime_type,
+        &file_content,
+        &extraction_config.options,
+        extraction_result,
+    )
+    .await;
+    #[cfg(not(feature = "image-captions"))]
+    if extraction_config.options.caption_images {
+        tracing::warn!(
+            "caption_images is set but this worker was built without the image-captions feature"
+        );
+    }
+
     info!(
         text_length_chars = extraction_result.text.len(),
         "Text extracted successfully"
@@ -235,6 +287,7 @@ pub(crate) async fn process_file_job(
     let chunks_with_metadata = match ChunkingService::chunk_text(
         extraction_result.text.clone(),
         &chunking_config,
+        Some(&mime_type),
         Some(extraction_metadata),
         job.embedder_config.as_ref(), // Use embedder config from job for semantic chunking
     )
@@ -293,6 +346,17 @@ pub(crate) async fn process_file_job(
         return Ok(());
     }
 
+    upload_chunks(&ctx, &job, chunks_with_metadata, start_time).await
+}
+
+/// Upload a file's chunks and report the job's success
+async fn upload_chunks(
+    ctx: &WorkerContext,
+    job: &CollectionTransformJob,
+    chunks_with_metadata: Vec<ChunkWithMetadata>,
+    start_time: Instant,
+) -> Result<()> {
+    let s3_bucket_name = &job.bucket;
     let chunks_key = format!("chunks/{}.json", job.job_id);
     let full_chunks_key = format!(
         "transforms/collection-transforms/{}/{}",
@@ -324,7 +388,7 @@ pub(crate) async fn process_file_job(
     info!(duration_secs = duration, "Job completed successfully");
     send_result(
         &ctx.nats_client,
-        &job,
+        job,
         Ok((chunks_key, chunks_with_metadata.len())),
         Some((duration * 1000.0) as i64),
     )
@@ -333,6 +397,146 @@ pub(crate) async fn process_file_job(
     Ok(())
 }
 
+/// Process a large ZIP by chunking each entry as it is extracted
+async fn process_zip_streaming(
+    job: CollectionTransformJob,
+    ctx: WorkerContext,
+    file_content: Vec<u8>,
+    extraction_config: ExtractionConfig,
+    start_time: Instant,
+) -> Result<()> {
+    let chunking_config: ChunkingConfig = match serde_json::from_value(job.chunking_config.clone())
+    {
+        Ok(config) => config,
+        Err(e) => {
+            let duration = start_time.elapsed().as_secs_f64();
+            record_worker_job("transform-file", duration, "failed_config_parse");
+            error!(error = %e, "Failed to parse chunking config");
+            send_result(
+                &ctx.nats_client,
+                &job,
+                Err(format!("Invalid chunking config: {}", e)),
+                Some((duration * 1000.0) as i64),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    info!("Extracting and chunking archive entries one at a time");
+    let chunking_start = Instant::now();
+    let chunks_with_metadata =
+        match chunk_zip_streaming(&job, file_content, &extraction_config, &chunking_config).await {
+            Ok(chunks) => {
+                semantic_explorer_core::observability::record_document_chunking(
+                    "collection",
+                    chunking_start.elapsed().as_secs_f64(),
+                    true,
+                );
+                chunks
+            }
+            Err(e) => {
+                semantic_explorer_core::observability::record_document_chunking(
+                    "collection",
+                    chunking_start.elapsed().as_secs_f64(),
+                    false,
+                );
+                let duration = start_time.elapsed().as_secs_f64();
+                record_worker_job("transform-file", duration, "failed_chunking");
+                error!(error = %e, "Streaming archive chunking failed");
+                send_result(
+                    &ctx.nats_client,
+                    &job,
+                    Err(e.to_string()),
+                    Some((duration * 1000.0) as i64),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+    if chunks_with_metadata.is_empty() {
+        let duration = start_time.elapsed().as_secs_f64();
+        record_worker_job("transform-file", duration, "failed_empty_chunks");
+        error!("No archive entry produced any chunks");
+        send_result(
+            &ctx.nats_client,
+            &job,
+            Err("Chunking produced no chunks - no archive entry had extractable text".to_string()),
+            Some((duration * 1000.0) as i64),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    upload_chunks(&ctx, &job, chunks_with_metadata, start_time).await
+}
+
+/// Extract the entries of a ZIP on a blocking thread and chunk each one as
+/// it arrives, so memory holds the chunks and one entry's text rather than
+/// the text of the whole archive. Chunks are numbered across the archive,
+/// and `max_chunks_per_document` applies to the archive as a whole.
+async fn chunk_zip_streaming(
+    job: &CollectionTransformJob,
+    file_content: Vec<u8>,
+    extraction_config: &ExtractionConfig,
+    chunking_config: &ChunkingConfig,
+) -> Result<Vec<ChunkWithMetadata>> {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<ArchiveFileResult>(STREAMED_ENTRY_BUFFER);
+    let options = extraction_config.options.clone();
+    let extraction = tokio::task::spawn_blocking(move || {
+        let archive_options = ArchiveOptions {
+            zip_password: options.zip_password.clone(),
+            ..Default::default()
+        };
+        archive::extract_from_zip_streaming(&file_content, &options, &archive_options, |file| {
+            tx.blocking_send(file)
+                .map_err(|_| anyhow!("Chunking of archive entries stopped"))
+        })
+    });
+
+    let entry_config = ChunkingConfig {
+        max_chunks_per_document: None,
+        ..chunking_config.clone()
+    };
+    let mut chunks = Vec::new();
+    while let Some(file) = rx.recv().await {
+        if file.text.trim().is_empty() {
+            continue;
+        }
+        let mime_type = file.mime_type.parse::<Mime>().ok();
+        let metadata = serde_json::json!({
+            "source_file": job.source_file_key,
+            "archive_path": file.path,
+        });
+        let entry_chunks = ChunkingService::chunk_text(
+            file.text,
+            &entry_config,
+            mime_type.as_ref(),
+            Some(metadata),
+            job.embedder_config.as_ref(),
+        )
+        .await?;
+        chunks.extend(entry_chunks);
+    }
+
+    let summary = extraction
+        .await
+        .map_err(|e| anyhow!("Archive extraction task failed: {}", e))??;
+    info!(
+        file_count = summary.files.len(),
+        failed_count = summary.failed_files.len(),
+        encrypted_count = summary.encrypted_count(),
+        skipped_count = summary.skipped_count,
+        chunk_count = chunks.len(),
+        "Chunked archive entries"
+    );
+
+    renumber_chunks(&mut chunks);
+    limit_chunks(&mut chunks, chunking_config)?;
+    Ok(chunks)
+}
+
 async fn send_result(
     nats: &async_nats::Client,
     job: &CollectionTransformJob,
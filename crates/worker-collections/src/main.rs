@@ -6,9 +6,12 @@ use semantic_explorer_core::{
     worker::{self, WorkerContext},
 };
 
+#[cfg(feature = "image-captions")]
+mod caption;
 mod chunk;
 mod extract;
 mod job;
+mod preview;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -24,6 +27,7 @@ async fn main() -> Result<()> {
 
     // Initialize S3 client
     let s3_client = initialize_client().await?;
+    let max_download_size_bytes = semantic_explorer_core::config::max_download_size_from_env()?;
 
     // Load NATS config from env at startup
     let nats_config = NatsConfig::from_env()?;
@@ -40,8 +44,17 @@ async fn main() -> Result<()> {
     let context = WorkerContext {
         s3_client,
         nats_client: nats_client.clone(),
+        max_download_size_bytes,
     };
 
+    // Answer dry-run extraction requests from the API alongside the job consumer
+    let preview_context = context.clone();
+    tokio::spawn(async move {
+        if let Err(e) = preview::serve(preview_context).await {
+            tracing::error!(error = %e, "Extraction preview listener stopped");
+        }
+    });
+
     // Configure and run worker
     let max_concurrent_jobs = std::env::var("MAX_CONCURRENT_JOBS")
         .unwrap_or_else(|_| "10".to_string())
@@ -61,6 +74,7 @@ async fn main() -> Result<()> {
         max_deliver: 5, // Matches consumer config
         health_check_port,
         nats_config,
+        nak_backoff: worker::NakBackoffConfig::from_env(),
     };
 
     worker::run_worker(config, context, job::process_file_job).await
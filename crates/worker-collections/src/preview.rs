@@ -0,0 +1,222 @@
+//! Dry-run extraction for the API's extraction preview endpoints.
+//!
+//! The API sends an `ExtractionPreviewRequest` over NATS request-reply and
+//! gets the extracted text and metadata back, so users can check extraction
+//! options against a file before running a transform. Nothing is uploaded,
+//! chunked or recorded.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures_util::StreamExt;
+use semantic_explorer_core::models::{
+    ExtractionPreviewRequest, ExtractionPreviewResponse, ExtractionPreviewSource,
+};
+use semantic_explorer_core::storage::get_file_with_size_check;
+use semantic_explorer_core::subjects::requests;
+use semantic_explorer_core::validation::{validate_bucket_name, validate_s3_key};
+use semantic_explorer_core::worker::WorkerContext;
+use tokio::sync::Semaphore;
+use tracing::{info, instrument, warn};
+
+use crate::extract::{ExtractionService, config::ExtractionConfig};
+
+/// Previews never return more text than this, whatever `max_output_bytes`
+/// asks for, so the reply stays well under the NATS payload limit
+pub(crate) const PREVIEW_MAX_OUTPUT_BYTES: usize = 256 * 1024;
+
+/// Previews extracting at once on this worker; they share the CPU with jobs
+const MAX_CONCURRENT_PREVIEWS: usize = 4;
+
+/// Extract `buffer` as a transform would, with the output capped at
+/// `PREVIEW_MAX_OUTPUT_BYTES`
+pub(crate) fn extract_preview(
+    file_name: &str,
+    buffer: &[u8],
+    extraction_config: &serde_json::Value,
+) -> ExtractionPreviewResponse {
+    let mut config: ExtractionConfig = match serde_json::from_value(extraction_config.clone()) {
+        Ok(config) => config,
+        Err(e) => {
+            return ExtractionPreviewResponse::Failed {
+                error: format!("Invalid extraction config: {}", e),
+            };
+        }
+    };
+    config.options.max_output_bytes = Some(
+        config
+            .options
+            .max_output_bytes
+            .map_or(PREVIEW_MAX_OUTPUT_BYTES, |limit| {
+                limit.min(PREVIEW_MAX_OUTPUT_BYTES)
+            }),
+    );
+
+    let mime_type = mime_guess::from_path(file_name).first_or_octet_stream();
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        ExtractionService::extract(&mime_type, buffer, &config)
+    })) {
+        Ok(Ok(output)) => ExtractionPreviewResponse::Extracted {
+            text: output.text,
+            metadata: output.metadata,
+        },
+        Ok(Err(e)) => ExtractionPreviewResponse::Failed {
+            error: e.to_string(),
+        },
+        Err(_) => ExtractionPreviewResponse::Failed {
+            error: "Extraction panicked".to_string(),
+        },
+    }
+}
+
+/// Answer preview requests until the subscription closes
+pub(crate) async fn serve(ctx: WorkerContext) -> Result<()> {
+    let mut subscriber = ctx
+        .nats_client
+        .queue_subscribe(
+            requests::EXTRACTION_PREVIEW,
+            requests::EXTRACTION_PREVIEW_WORKERS.to_string(),
+        )
+        .await?;
+    info!(
+        subject = requests::EXTRACTION_PREVIEW,
+        "Serving extraction previews"
+    );
+
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_PREVIEWS));
+    while let Some(message) = subscriber.next().await {
+        let Some(reply) = message.reply.clone() else {
+            warn!("Ignoring extraction preview request without a reply subject");
+            continue;
+        };
+        let permit = permits.clone().acquire_owned().await?;
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let response = handle_request(&ctx, &message.payload).await;
+            match serde_json::to_vec(&response) {
+                Ok(payload) => {
+                    if let Err(e) = ctx.nats_client.publish(reply, payload.into()).await {
+                        warn!(error = %e, "Failed to reply to extraction preview request");
+                    }
+                }
+                Err(e) => warn!(error = %e, "Failed to serialize extraction preview"),
+            }
+            drop(permit);
+        });
+    }
+    Ok(())
+}
+
+#[instrument(skip_all)]
+async fn handle_request(ctx: &WorkerContext, payload: &[u8]) -> ExtractionPreviewResponse {
+    let request: ExtractionPreviewRequest = match serde_json::from_slice(payload) {
+        Ok(request) => request,
+        Err(e) => {
+            return ExtractionPreviewResponse::Failed {
+                error: format!("Invalid preview request: {}", e),
+            };
+        }
+    };
+
+    let buffer = match request.source {
+        ExtractionPreviewSource::Inline { content } => content,
+        ExtractionPreviewSource::Stored { bucket, key } => {
+            if let Err(e) = validate_bucket_name(&bucket).and(validate_s3_key(&key)) {
+                return ExtractionPreviewResponse::Failed {
+                    error: format!("Invalid file location: {}", e),
+                };
+            }
+            match get_file_with_size_check(
+                &ctx.s3_client,
+                &bucket,
+                &key,
+                ctx.max_download_size_bytes,
+            )
+            .await
+            {
+                Ok(content) => content,
+                Err(e) => {
+                    return ExtractionPreviewResponse::Failed {
+                        error: e.to_string(),
+                    };
+                }
+            }
+        }
+    };
+
+    let file_name = request.file_name;
+    let extraction_config = request.extraction_config;
+    tokio::task::spawn_blocking(move || extract_preview(&file_name, &buffer, &extraction_config))
+        .await
+        .unwrap_or_else(|e| ExtractionPreviewResponse::Failed {
+            error: format!("Extraction task failed: {}", e),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::pdf::build_pdf;
+    use serde_json::json;
+
+    fn extracted(response: ExtractionPreviewResponse) -> (String, Option<serde_json::Value>) {
+        match response {
+            ExtractionPreviewResponse::Extracted { text, metadata } => (text, metadata),
+            ExtractionPreviewResponse::Failed { error } => panic!("extraction failed: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_preview_extracts_pdf_text() {
+        let pdf = build_pdf("Quarterly revenue grew");
+        let (text, _) = extracted(extract_preview("report.pdf", &pdf, &json!({})));
+        assert!(text.contains("Quarterly revenue grew"));
+    }
+
+    #[test]
+    fn test_preview_extracts_html_text() {
+        let html = b"<html><head><title>Release notes</title><script>track()</script></head>\
+                     <body><h1>Version 2</h1><p>Faster imports.</p></body></html>";
+        let (text, _) = extracted(extract_preview("notes.html", html, &json!({})));
+        assert!(text.contains("Version 2"));
+        assert!(text.contains("Faster imports."));
+        assert!(!text.contains("track()"));
+    }
+
+    #[test]
+    fn test_preview_honors_include_metadata() {
+        let pdf = build_pdf("Quarterly revenue grew");
+
+        let (_, metadata) = extracted(extract_preview(
+            "report.pdf",
+            &pdf,
+            &json!({"options": {"include_metadata": true}}),
+        ));
+        assert_eq!(metadata.unwrap()["page_count"], 1);
+
+        let (_, metadata) = extracted(extract_preview(
+            "report.pdf",
+            &pdf,
+            &json!({"options": {"include_metadata": false}}),
+        ));
+        assert!(metadata.is_none_or(|metadata| metadata.get("page_count").is_none()));
+    }
+
+    #[test]
+    fn test_preview_caps_output() {
+        let text = "word ".repeat(PREVIEW_MAX_OUTPUT_BYTES);
+        let (preview, metadata) = extracted(extract_preview(
+            "big.txt",
+            text.as_bytes(),
+            &json!({"options": {"max_output_bytes": PREVIEW_MAX_OUTPUT_BYTES * 4}}),
+        ));
+        assert!(preview.len() <= PREVIEW_MAX_OUTPUT_BYTES);
+        assert_eq!(metadata.unwrap()["truncated"], true);
+    }
+
+    #[test]
+    fn test_preview_rejects_invalid_config() {
+        let response = extract_preview("notes.txt", b"hello", &json!({"strategy": 42}));
+        assert!(matches!(response, ExtractionPreviewResponse::Failed { .. }));
+    }
+}
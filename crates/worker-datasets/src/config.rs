@@ -0,0 +1,194 @@
+//! Dataset worker settings read from the environment at startup.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use tracing::warn;
+
+const DEFAULT_MAX_CONCURRENT_JOBS: usize = 10;
+const DEFAULT_QDRANT_PARALLEL_UPLOADS: usize = 4;
+const DEFAULT_HEALTH_CHECK_PORT: u16 = 8083;
+const DEFAULT_MAX_EMBED_BATCH_SIZE: usize = 128;
+const DEFAULT_EMBED_BATCH_CONCURRENCY: usize = 4;
+const DEFAULT_EMBEDDING_CACHE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DatasetWorkerSettings {
+    /// Jobs processed concurrently (`MAX_CONCURRENT_JOBS`)
+    pub(crate) max_concurrent_jobs: usize,
+    /// Qdrant upsert chunks sent in parallel per job (`QDRANT_PARALLEL_UPLOADS`)
+    pub(crate) qdrant_parallel_uploads: usize,
+    /// Port for `/healthz`, `/readyz` and `/status` (`HEALTH_CHECK_PORT`)
+    pub(crate) health_check_port: u16,
+    /// Upper bound on texts per embedding request; smaller embedder batch
+    /// sizes still apply (`MAX_EMBED_BATCH_SIZE`)
+    pub(crate) max_embed_batch_size: usize,
+    /// Embedding requests in flight per job (`EMBED_BATCH_CONCURRENCY`)
+    pub(crate) embed_batch_concurrency: usize,
+    /// Reuse embeddings of previously seen texts from Valkey
+    /// (`EMBEDDING_CACHE_ENABLED`)
+    pub(crate) embedding_cache_enabled: bool,
+    /// Lifetime of cached embeddings (`EMBEDDING_CACHE_TTL_SECS`)
+    pub(crate) embedding_cache_ttl_secs: u64,
+    /// Reject embeddings containing NaN or infinite values instead of
+    /// upserting them (`EMBEDDING_REJECT_NON_FINITE`)
+    pub(crate) reject_non_finite_embeddings: bool,
+}
+
+impl DatasetWorkerSettings {
+    pub(crate) fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            max_concurrent_jobs: parse_or_default(
+                "MAX_CONCURRENT_JOBS",
+                lookup("MAX_CONCURRENT_JOBS"),
+                DEFAULT_MAX_CONCURRENT_JOBS,
+                1,
+            ),
+            qdrant_parallel_uploads: parse_or_default(
+                "QDRANT_PARALLEL_UPLOADS",
+                lookup("QDRANT_PARALLEL_UPLOADS"),
+                DEFAULT_QDRANT_PARALLEL_UPLOADS,
+                1,
+            ),
+            health_check_port: parse_or_default(
+                "HEALTH_CHECK_PORT",
+                lookup("HEALTH_CHECK_PORT"),
+                DEFAULT_HEALTH_CHECK_PORT,
+                1,
+            ),
+            max_embed_batch_size: parse_or_default(
+                "MAX_EMBED_BATCH_SIZE",
+                lookup("MAX_EMBED_BATCH_SIZE"),
+                DEFAULT_MAX_EMBED_BATCH_SIZE,
+                1,
+            ),
+            embed_batch_concurrency: parse_or_default(
+                "EMBED_BATCH_CONCURRENCY",
+                lookup("EMBED_BATCH_CONCURRENCY"),
+                DEFAULT_EMBED_BATCH_CONCURRENCY,
+                1,
+            ),
+            embedding_cache_enabled: parse_or_default(
+                "EMBEDDING_CACHE_ENABLED",
+                lookup("EMBEDDING_CACHE_ENABLED").map(|v| v.trim().to_lowercase()),
+                false,
+                false,
+            ),
+            embedding_cache_ttl_secs: parse_or_default(
+                "EMBEDDING_CACHE_TTL_SECS",
+                lookup("EMBEDDING_CACHE_TTL_SECS"),
+                DEFAULT_EMBEDDING_CACHE_TTL_SECS,
+                1,
+            ),
+            reject_non_finite_embeddings: parse_or_default(
+                "EMBEDDING_REJECT_NON_FINITE",
+                lookup("EMBEDDING_REJECT_NON_FINITE").map(|v| v.trim().to_lowercase()),
+                true,
+                false,
+            ),
+        }
+    }
+}
+
+/// Parse an optional env value, falling back to `default` (with a warning)
+/// when it is unparseable or below `min`, so a typo can't stall the worker.
+fn parse_or_default<T>(name: &str, value: Option<String>, default: T, min: T) -> T
+where
+    T: FromStr + PartialOrd + Display + Copy,
+{
+    let Some(raw) = value else {
+        return default;
+    };
+    match raw.trim().parse::<T>() {
+        Ok(parsed) if parsed >= min => parsed,
+        Ok(parsed) => {
+            warn!(
+                variable = name,
+                value = %parsed,
+                default = %default,
+                "Value below minimum of {min}, using default"
+            );
+            default
+        }
+        Err(_) => {
+            warn!(
+                variable = name,
+                value = %raw,
+                default = %default,
+                "Invalid value, using default"
+            );
+            default
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn settings(vars: &[(&str, &str)]) -> DatasetWorkerSettings {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        DatasetWorkerSettings::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_defaults_when_unset() {
+        assert_eq!(
+            settings(&[]),
+            DatasetWorkerSettings {
+                max_concurrent_jobs: 10,
+                qdrant_parallel_uploads: 4,
+                health_check_port: 8083,
+                max_embed_batch_size: 128,
+                embed_batch_concurrency: 4,
+                embedding_cache_enabled: false,
+                embedding_cache_ttl_secs: 604_800,
+                reject_non_finite_embeddings: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_reads_configured_values() {
+        let settings = settings(&[
+            ("MAX_CONCURRENT_JOBS", "32"),
+            ("QDRANT_PARALLEL_UPLOADS", " 8 "),
+            ("HEALTH_CHECK_PORT", "9000"),
+            ("MAX_EMBED_BATCH_SIZE", "64"),
+            ("EMBED_BATCH_CONCURRENCY", "2"),
+            ("EMBEDDING_CACHE_ENABLED", "TRUE"),
+            ("EMBEDDING_CACHE_TTL_SECS", "3600"),
+            ("EMBEDDING_REJECT_NON_FINITE", "false"),
+        ]);
+        assert_eq!(settings.max_concurrent_jobs, 32);
+        assert_eq!(settings.qdrant_parallel_uploads, 8);
+        assert_eq!(settings.health_check_port, 9000);
+        assert_eq!(settings.max_embed_batch_size, 64);
+        assert_eq!(settings.embed_batch_concurrency, 2);
+        assert!(settings.embedding_cache_enabled);
+        assert_eq!(settings.embedding_cache_ttl_secs, 3600);
+        assert!(!settings.reject_non_finite_embeddings);
+    }
+
+    #[test]
+    fn test_invalid_values_fall_back_to_defaults() {
+        let settings = settings(&[
+            ("MAX_CONCURRENT_JOBS", "0"),
+            ("QDRANT_PARALLEL_UPLOADS", "lots"),
+            ("HEALTH_CHECK_PORT", "70000"),
+            ("MAX_EMBED_BATCH_SIZE", "0"),
+        ]);
+        assert_eq!(settings.max_concurrent_jobs, 10);
+        assert_eq!(settings.qdrant_parallel_uploads, 4);
+        assert_eq!(settings.health_check_port, 8083);
+        assert_eq!(settings.max_embed_batch_size, 128);
+    }
+}
@@ -0,0 +1,40 @@
+//! Output dimensions of embedders.
+//!
+//! Jobs carry the dimensions the API measured for their embedder. Jobs for
+//! embedders that were never measured probe the embedder once; the result is
+//! remembered per model for the following jobs.
+
+use once_cell::sync::Lazy;
+use semantic_explorer_core::embedder;
+use semantic_explorer_core::models::EmbedderConfig;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Probed dimensions keyed by "{provider}|{base_url}|{model}"
+static PROBED_DIMENSIONS: Lazy<RwLock<HashMap<String, u64>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn probe_cache_key(config: &EmbedderConfig) -> String {
+    format!("{}|{}|{}", config.provider, config.base_url, config.model)
+}
+
+/// Output dimensions of `config`'s model
+pub(crate) async fn embedder_dimensions(config: &EmbedderConfig) -> anyhow::Result<u64> {
+    if let Some(dimensions) = config.dimensions {
+        return Ok(dimensions);
+    }
+
+    let key = probe_cache_key(config);
+    if let Some(dimensions) = PROBED_DIMENSIONS.read().await.get(&key) {
+        return Ok(*dimensions);
+    }
+
+    let dimensions = embedder::probe_dimensions(config).await?;
+    info!(
+        embedder_model = %config.model,
+        dimensions, "Probed embedder dimensions"
+    );
+    PROBED_DIMENSIONS.write().await.insert(key, dimensions);
+    Ok(dimensions)
+}
@@ -0,0 +1,340 @@
+//! Document-level vectors.
+//!
+//! Jobs with `document_vectors` set also store one vector per item of the
+//! batch in the embedded dataset's document collection: the normalized mean
+//! of the item's chunk vectors. An item's chunks can span several batches, or
+//! be partly skipped on redelivery, so once a batch's chunks are upserted the
+//! vectors of all of its items' chunks are read back from the chunk
+//! collection rather than taken from what this job embedded. Document point
+//! ids are derived from the item, so the last batch of an item to finish
+//! leaves its complete vector.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use qdrant_client::Qdrant;
+use qdrant_client::qdrant::point_id::PointIdOptions;
+use qdrant_client::qdrant::value::Kind;
+use qdrant_client::qdrant::vector_output::Vector as VectorOutputKind;
+use qdrant_client::qdrant::vectors_output::VectorsOptions;
+use qdrant_client::qdrant::{Condition, Filter, PointStruct, RetrievedPoint, ScrollPointsBuilder};
+use semantic_explorer_core::document_vectors::{document_collection_name, document_point_id};
+use semantic_explorer_core::models::DatasetTransformJob;
+
+use crate::job::BatchItem;
+
+const SCROLL_PAGE_SIZE: u32 = 256;
+
+/// A stored chunk point of a document
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkVector {
+    pub(crate) id: String,
+    pub(crate) item_id: i64,
+    pub(crate) chunk_index: i64,
+    pub(crate) vector: Vec<f32>,
+}
+
+/// Where the chunk vectors of documents are read from
+pub(crate) trait StoredChunks {
+    /// Every stored chunk of the given items
+    async fn chunk_vectors(&self, item_ids: &[i64]) -> Result<Vec<ChunkVector>>;
+}
+
+pub(crate) struct QdrantChunks<'a> {
+    pub(crate) client: &'a Qdrant,
+    pub(crate) collection: &'a str,
+}
+
+impl StoredChunks for QdrantChunks<'_> {
+    async fn chunk_vectors(&self, item_ids: &[i64]) -> Result<Vec<ChunkVector>> {
+        let filter = Filter::must([Condition::matches("item_id", item_ids.to_vec())]);
+        let mut chunks = Vec::new();
+        let mut offset = None;
+        loop {
+            let mut request = ScrollPointsBuilder::new(self.collection)
+                .filter(filter.clone())
+                .limit(SCROLL_PAGE_SIZE)
+                .with_payload(true)
+                .with_vectors(true);
+            if let Some(offset) = offset {
+                request = request.offset(offset);
+            }
+            let response = self.client.scroll(request).await?;
+            chunks.extend(response.result.into_iter().filter_map(chunk_vector));
+            offset = response.next_page_offset;
+            if offset.is_none() {
+                return Ok(chunks);
+            }
+        }
+    }
+}
+
+fn chunk_vector(point: RetrievedPoint) -> Option<ChunkVector> {
+    let id = match point.id?.point_id_options? {
+        PointIdOptions::Uuid(uuid) => uuid,
+        PointIdOptions::Num(num) => num.to_string(),
+    };
+    let integer = |key: &str| match point.payload.get(key)?.kind.clone()? {
+        Kind::IntegerValue(value) => Some(value),
+        _ => None,
+    };
+    let item_id = integer("item_id")?;
+    let chunk_index = integer("chunk_index")?;
+    let vector = match point.vectors?.vectors_options? {
+        VectorsOptions::Vector(vector) => vector,
+        // Collections with the BM25 vector name the dense one ""
+        VectorsOptions::Vectors(mut named) => named.vectors.remove("")?,
+    };
+    match vector.into_vector() {
+        VectorOutputKind::Dense(dense) => Some(ChunkVector {
+            id,
+            item_id,
+            chunk_index,
+            vector: dense.data,
+        }),
+        _ => None,
+    }
+}
+
+/// One document's vector and the chunk points it was computed from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DocumentVector {
+    pub(crate) item_id: i64,
+    pub(crate) vector: Vec<f32>,
+    pub(crate) chunk_ids: Vec<String>,
+}
+
+/// The items a batch's chunks belong to, with their titles
+pub(crate) fn batch_documents(items: &[BatchItem]) -> BTreeMap<i64, serde_json::Value> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let item_id = item.payload.get("item_id")?.as_i64()?;
+            let title = item.payload.get("item_title").cloned();
+            Some((item_id, title.unwrap_or_default()))
+        })
+        .collect()
+}
+
+/// L2-normalized mean of each document's chunk vectors. Documents whose
+/// chunks disagree on the vector size are left out.
+pub(crate) fn mean_vectors(chunks: Vec<ChunkVector>) -> Vec<DocumentVector> {
+    let mut by_item: BTreeMap<i64, Vec<ChunkVector>> = BTreeMap::new();
+    for chunk in chunks {
+        by_item.entry(chunk.item_id).or_default().push(chunk);
+    }
+    by_item
+        .into_iter()
+        .filter_map(|(item_id, mut chunks)| {
+            chunks.sort_by_key(|chunk| chunk.chunk_index);
+            let dimensions = chunks.first()?.vector.len();
+            if chunks.iter().any(|chunk| chunk.vector.len() != dimensions) {
+                return None;
+            }
+            let mut vector = vec![0.0f32; dimensions];
+            for chunk in &chunks {
+                for (sum, value) in vector.iter_mut().zip(&chunk.vector) {
+                    *sum += value;
+                }
+            }
+            let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                vector.iter_mut().for_each(|v| *v /= norm);
+            }
+            Some(DocumentVector {
+                item_id,
+                vector,
+                chunk_ids: chunks.into_iter().map(|chunk| chunk.id).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Document vectors of `documents`, from their stored chunks
+pub(crate) async fn document_vectors<S: StoredChunks>(
+    store: &S,
+    documents: &BTreeMap<i64, serde_json::Value>,
+) -> Result<Vec<DocumentVector>> {
+    if documents.is_empty() {
+        return Ok(Vec::new());
+    }
+    let item_ids: Vec<i64> = documents.keys().copied().collect();
+    Ok(mean_vectors(store.chunk_vectors(&item_ids).await?))
+}
+
+fn build_document_points(
+    embedded_dataset_id: i32,
+    documents: &BTreeMap<i64, serde_json::Value>,
+    vectors: Vec<DocumentVector>,
+) -> Vec<PointStruct> {
+    vectors
+        .into_iter()
+        .map(|document| {
+            let mut payload = serde_json::Map::new();
+            payload.insert("item_id".to_string(), serde_json::json!(document.item_id));
+            if let Some(title) = documents.get(&document.item_id) {
+                payload.insert("item_title".to_string(), title.clone());
+            }
+            payload.insert(
+                "chunk_count".to_string(),
+                serde_json::json!(document.chunk_ids.len()),
+            );
+            payload.insert(
+                "chunk_ids".to_string(),
+                serde_json::json!(document.chunk_ids),
+            );
+            PointStruct::new(
+                document_point_id(embedded_dataset_id, document.item_id),
+                document.vector,
+                qdrant_client::Payload::from(payload),
+            )
+        })
+        .collect()
+}
+
+/// Recompute and upsert the vectors of `documents`, whose chunks are already
+/// in the job's collection. Returns how many were stored.
+pub(crate) async fn store_document_vectors(
+    client: &Arc<Qdrant>,
+    job: &DatasetTransformJob,
+    documents: &BTreeMap<i64, serde_json::Value>,
+) -> Result<usize> {
+    let store = QdrantChunks {
+        client,
+        collection: &job.collection_name,
+    };
+    let vectors = document_vectors(&store, documents).await?;
+    let Some(dimensions) = vectors.first().map(|d| d.vector.len() as u64) else {
+        return Ok(0);
+    };
+
+    let collection = document_collection_name(&job.collection_name);
+    crate::qdrant_cache::ensure_collection_exists(
+        client,
+        &job.qdrant_config.url,
+        &collection,
+        dimensions,
+        job.embedder_config.distance_metric,
+    )
+    .await?;
+
+    let points = build_document_points(job.embedded_dataset_id, documents, vectors);
+    let count = points.len();
+    client
+        .upsert_points(crate::qdrant_cache::upsert_request(
+            &collection,
+            points,
+            job.write_consistency,
+        ))
+        .await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MemoryChunks(Vec<ChunkVector>);
+
+    impl StoredChunks for MemoryChunks {
+        async fn chunk_vectors(&self, item_ids: &[i64]) -> Result<Vec<ChunkVector>> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|chunk| item_ids.contains(&chunk.item_id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn chunk(item_id: i64, chunk_index: i64, vector: Vec<f32>) -> ChunkVector {
+        ChunkVector {
+            id: format!("chunk-{item_id}-{chunk_index}"),
+            item_id,
+            chunk_index,
+            vector,
+        }
+    }
+
+    fn batch_item(chunk: &ChunkVector) -> BatchItem {
+        let payload = serde_json::json!({
+            "item_id": chunk.item_id,
+            "item_title": format!("Item {}", chunk.item_id),
+            "chunk_index": chunk.chunk_index,
+        });
+        BatchItem {
+            id: chunk.id.clone(),
+            text: String::new(),
+            payload: payload.as_object().unwrap().clone(),
+            item_type: None,
+            fields: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_one_document_vector_per_document() {
+        let chunks = vec![
+            chunk(1, 0, vec![1.0, 0.0]),
+            chunk(1, 1, vec![0.0, 1.0]),
+            chunk(1, 2, vec![1.0, 1.0]),
+            chunk(2, 0, vec![3.0, 4.0]),
+        ];
+        let items: Vec<BatchItem> = chunks.iter().map(batch_item).collect();
+        let batch = batch_documents(&items);
+        let store = MemoryChunks(chunks);
+
+        let documents = document_vectors(&store, &batch).await.unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].item_id, 1);
+        assert_eq!(
+            documents[0].chunk_ids,
+            vec!["chunk-1-0", "chunk-1-1", "chunk-1-2"]
+        );
+        let expected = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((documents[0].vector[0] - expected).abs() < 1e-6);
+        assert!((documents[0].vector[1] - expected).abs() < 1e-6);
+        assert_eq!(documents[1].vector, vec![0.6, 0.8]);
+
+        // Chunk points are untouched; document points get their own ids
+        let points = build_document_points(7, &batch, documents);
+        assert_eq!(points.len(), 2);
+        assert_eq!(store.0.len(), 4);
+        assert_eq!(
+            points[0].id,
+            Some(document_point_id(7, 1).into()),
+            "document ids are derived from the item"
+        );
+        assert_eq!(
+            points[1]
+                .payload
+                .get("chunk_count")
+                .and_then(|v| v.as_integer()),
+            Some(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_document_includes_chunks_from_other_batches() {
+        // The item's first chunk went out in an earlier batch, or was skipped
+        // on redelivery; it still counts towards the document vector
+        let store = MemoryChunks(vec![
+            chunk(5, 0, vec![2.0, 0.0]),
+            chunk(5, 1, vec![0.0, 2.0]),
+            chunk(6, 0, vec![1.0, 0.0]),
+        ]);
+        let items = vec![batch_item(&chunk(5, 1, vec![0.0, 2.0]))];
+
+        let documents = document_vectors(&store, &batch_documents(&items))
+            .await
+            .unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].chunk_ids, vec!["chunk-5-0", "chunk-5-1"]);
+    }
+
+    #[test]
+    fn test_mismatched_chunk_sizes_are_skipped() {
+        let documents = mean_vectors(vec![chunk(1, 0, vec![1.0]), chunk(1, 1, vec![1.0, 0.0])]);
+        assert!(documents.is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+//! Optional persistent cache of embeddings keyed by content hash.
+//!
+//! Re-running a transform re-embeds every chunk even when most texts are
+//! unchanged. With `EMBEDDING_CACHE_ENABLED=true` each text is looked up in
+//! Valkey under `embedding:{embedder_id}:{model_version}:{fingerprint}:{sha256}`
+//! before the embedder is called, and freshly embedded vectors are stored for
+//! later runs. The key follows the [`EmbeddingStamp`], so changing the
+//! embedder, its pinned model revision or its preprocessing config never
+//! reuses old vectors. Cache errors are logged and treated as misses.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use redis::aio::ConnectionManager;
+use semantic_explorer_core::config::ValkeyConfig;
+use semantic_explorer_core::embedding_stamp::EmbeddingStamp;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+static STORE: OnceLock<ValkeyStore> = OnceLock::new();
+
+/// Where cached vectors live
+pub(crate) trait EmbeddingCacheStore {
+    /// Cached vectors for `keys`, in order, `None` where absent
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<Vec<f32>>>>;
+
+    async fn put_many(&self, entries: Vec<(String, Vec<f32>)>) -> Result<()>;
+}
+
+pub(crate) struct ValkeyStore {
+    conn: ConnectionManager,
+    ttl_secs: u64,
+}
+
+impl EmbeddingCacheStore for ValkeyStore {
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<Vec<f32>>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.conn.clone();
+        // Explicit MGET: a single key must still come back as an array
+        let values: Vec<Option<Vec<u8>>> =
+            redis::cmd("MGET").arg(keys).query_async(&mut conn).await?;
+        Ok(values
+            .into_iter()
+            .map(|value| value.and_then(|bytes| decode(&bytes)))
+            .collect())
+    }
+
+    async fn put_many(&self, entries: Vec<(String, Vec<f32>)>) -> Result<()> {
+        let mut pipe = redis::pipe();
+        for (key, vector) in &entries {
+            pipe.set_ex(key, encode(vector), self.ttl_secs).ignore();
+        }
+        let mut conn = self.conn.clone();
+        let _: () = pipe.query_async(&mut conn).await?;
+        Ok(())
+    }
+}
+
+/// Connect the shared cache. Call once from main when the cache is enabled.
+pub(crate) async fn init(config: &ValkeyConfig, ttl_secs: u64) -> Result<()> {
+    let client = redis::Client::open(config.connection_url().as_str())
+        .with_context(|| format!("Failed to create Valkey client for URL: {}", config.url))?;
+    let conn = ConnectionManager::new(client)
+        .await
+        .context("Failed to connect to Valkey")?;
+    if STORE.set(ValkeyStore { conn, ttl_secs }).is_ok() {
+        info!(ttl_secs, "Embedding cache enabled");
+    }
+    Ok(())
+}
+
+/// The shared cache, if enabled and connected
+pub(crate) fn store() -> Option<&'static ValkeyStore> {
+    STORE.get()
+}
+
+pub(crate) struct CachedEmbeddings {
+    /// One embedding per input text, in order
+    pub(crate) embeddings: Vec<Vec<f32>>,
+    pub(crate) hits: usize,
+    pub(crate) misses: usize,
+}
+
+/// Embed `texts`, reusing cached vectors. Only distinct texts missing from
+/// the cache are passed to `embed`, and their vectors are cached afterwards.
+pub(crate) async fn embed_with_cache<S, F, Fut>(
+    store: &S,
+    stamp: &EmbeddingStamp,
+    texts: &[String],
+    embed: F,
+) -> Result<CachedEmbeddings>
+where
+    S: EmbeddingCacheStore,
+    F: FnOnce(Vec<String>) -> Fut,
+    Fut: Future<Output = Result<Vec<Vec<f32>>>>,
+{
+    let keys: Vec<String> = texts.iter().map(|text| cache_key(stamp, text)).collect();
+    let cached = match store.get_many(&keys).await {
+        Ok(values) if values.len() == keys.len() => values,
+        Ok(values) => {
+            warn!(
+                expected = keys.len(),
+                actual = values.len(),
+                "Embedding cache returned the wrong number of values, ignoring it"
+            );
+            vec![None; keys.len()]
+        }
+        Err(e) => {
+            warn!(error = %e, "Embedding cache lookup failed, embedding all texts");
+            vec![None; keys.len()]
+        }
+    };
+
+    // Each distinct missing text is embedded once
+    let mut miss_positions: HashMap<&str, usize> = HashMap::new();
+    let mut miss_keys = Vec::new();
+    let mut miss_texts = Vec::new();
+    for ((key, text), value) in keys.iter().zip(texts).zip(&cached) {
+        if value.is_none() && !miss_positions.contains_key(key.as_str()) {
+            miss_positions.insert(key, miss_texts.len());
+            miss_keys.push(key.clone());
+            miss_texts.push(text.clone());
+        }
+    }
+    let hits = cached.iter().filter(|value| value.is_some()).count();
+
+    let fresh = if miss_texts.is_empty() {
+        Vec::new()
+    } else {
+        embed(miss_texts).await?
+    };
+    if fresh.len() != miss_keys.len() {
+        bail!(
+            "Embedding count mismatch: expected {}, got {}",
+            miss_keys.len(),
+            fresh.len()
+        );
+    }
+
+    let embeddings = cached
+        .into_iter()
+        .zip(&keys)
+        .map(|(value, key)| value.unwrap_or_else(|| fresh[miss_positions[key.as_str()]].clone()))
+        .collect();
+
+    if !fresh.is_empty()
+        && let Err(e) = store
+            .put_many(miss_keys.into_iter().zip(fresh).collect())
+            .await
+    {
+        warn!(error = %e, "Failed to store embeddings in the cache");
+    }
+
+    Ok(CachedEmbeddings {
+        embeddings,
+        hits,
+        misses: texts.len() - hits,
+    })
+}
+
+fn cache_key(stamp: &EmbeddingStamp, text: &str) -> String {
+    format!(
+        "embedding:{}:{}:{}:{:x}",
+        stamp
+            .embedder_id
+            .map_or_else(|| "-".to_string(), |id| id.to_string()),
+        stamp.model_version.as_deref().unwrap_or("unpinned"),
+        stamp.preprocessing_fingerprint,
+        Sha256::digest(text.as_bytes())
+    )
+}
+
+fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode(bytes: &[u8]) -> Option<Vec<f32>> {
+    if bytes.is_empty() || bytes.len() % 4 != 0 {
+        return None;
+    }
+    Some(
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semantic_explorer_core::models::EmbedderConfig;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        entries: Mutex<HashMap<String, Vec<u8>>>,
+        fail: bool,
+    }
+
+    impl EmbeddingCacheStore for MemoryStore {
+        async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<Vec<f32>>>> {
+            if self.fail {
+                bail!("connection refused");
+            }
+            let entries = self.entries.lock().unwrap();
+            Ok(keys
+                .iter()
+                .map(|key| entries.get(key).and_then(|bytes| decode(bytes)))
+                .collect())
+        }
+
+        async fn put_many(&self, new_entries: Vec<(String, Vec<f32>)>) -> Result<()> {
+            if self.fail {
+                bail!("connection refused");
+            }
+            let mut entries = self.entries.lock().unwrap();
+            for (key, vector) in new_entries {
+                entries.insert(key, encode(&vector));
+            }
+            Ok(())
+        }
+    }
+
+    fn stamp(model: &str) -> EmbeddingStamp {
+        let config = EmbedderConfig::new(
+            "internal".to_string(),
+            "http://localhost:8090".to_string(),
+            None,
+            model.to_string(),
+            serde_json::json!({}),
+            8,
+            512,
+        );
+        EmbeddingStamp::from_embedder_config(Some(7), &config)
+    }
+
+    fn texts(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    /// Embeds each text as its length, recording every text sent
+    async fn run(
+        store: &MemoryStore,
+        stamp: &EmbeddingStamp,
+        input: &[String],
+        sent: &Mutex<Vec<String>>,
+    ) -> CachedEmbeddings {
+        embed_with_cache(store, stamp, input, |batch| async move {
+            sent.lock().unwrap().extend(batch.iter().cloned());
+            Ok(batch.iter().map(|t| vec![t.len() as f32, 0.5]).collect())
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repeated_chunk_across_runs_skips_the_embedder() {
+        let store = MemoryStore::default();
+        let stamp = stamp("bge-small");
+        let sent = Mutex::new(Vec::new());
+
+        // First run: duplicates within the batch are embedded once
+        let first = run(&store, &stamp, &texts(&["alpha", "be", "alpha"]), &sent).await;
+        assert_eq!(*sent.lock().unwrap(), texts(&["alpha", "be"]));
+        assert_eq!((first.hits, first.misses), (0, 3));
+        assert_eq!(first.embeddings[2], vec![5.0, 0.5]);
+
+        // Second run: only the new chunk reaches the embedder
+        sent.lock().unwrap().clear();
+        let second = run(&store, &stamp, &texts(&["be", "gamma"]), &sent).await;
+        assert_eq!(*sent.lock().unwrap(), texts(&["gamma"]));
+        assert_eq!((second.hits, second.misses), (1, 1));
+        assert_eq!(second.embeddings, vec![vec![2.0, 0.5], vec![5.0, 0.5]]);
+
+        // Fully cached: no embedder call at all
+        sent.lock().unwrap().clear();
+        let third = run(&store, &stamp, &texts(&["alpha", "gamma"]), &sent).await;
+        assert!(sent.lock().unwrap().is_empty());
+        assert_eq!(third.hits, 2);
+    }
+
+    #[tokio::test]
+    async fn test_other_model_or_unavailable_cache_misses() {
+        let store = MemoryStore::default();
+        let sent = Mutex::new(Vec::new());
+        run(&store, &stamp("bge-small"), &texts(&["alpha"]), &sent).await;
+
+        // A different model must not reuse the vector
+        sent.lock().unwrap().clear();
+        let other = run(&store, &stamp("bge-large"), &texts(&["alpha"]), &sent).await;
+        assert_eq!(other.hits, 0);
+        assert_eq!(*sent.lock().unwrap(), texts(&["alpha"]));
+
+        // Cache errors degrade to embedding everything
+        let broken = MemoryStore {
+            fail: true,
+            ..Default::default()
+        };
+        sent.lock().unwrap().clear();
+        let result = run(
+            &broken,
+            &stamp("bge-small"),
+            &texts(&["alpha", "be"]),
+            &sent,
+        )
+        .await;
+        assert_eq!(result.misses, 2);
+        assert_eq!(sent.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_vectors_round_trip() {
+        let vector = vec![0.25, -1.5, f32::MIN_POSITIVE];
+        assert_eq!(decode(&encode(&vector)), Some(vector));
+        assert_eq!(decode(&[1, 2, 3]), None);
+    }
+}
@@ -1,28 +1,57 @@
 use anyhow::Result;
 use async_nats::jetstream;
-use futures_util::stream::{self, StreamExt};
-use qdrant_client::qdrant::PointStruct;
-use qdrant_client::qdrant::UpsertPointsBuilder;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use qdrant_client::qdrant::{NamedVectors, PointStruct, Vector, Vectors};
 use semantic_explorer_core::embedder;
-use semantic_explorer_core::models::{DatasetTransformJob, DatasetTransformResult};
+use semantic_explorer_core::embedding_stamp::{EmbeddingStamp, FALLBACK_FOR_PAYLOAD_KEY};
+use semantic_explorer_core::models::{DatasetTransformJob, DatasetTransformResult, EmbedderConfig};
 use semantic_explorer_core::nats::inject_trace_context;
-use semantic_explorer_core::observability::record_worker_job;
+use semantic_explorer_core::observability::{
+    record_embed_request, record_embedding_cache, record_non_finite_embeddings, record_worker_job,
+};
+use semantic_explorer_core::sparse::{self, SPARSE_VECTOR_NAME};
 use semantic_explorer_core::storage::get_file;
 use semantic_explorer_core::validation::{validate_bucket_name, validate_s3_key};
 use semantic_explorer_core::worker::WorkerContext;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::sync::Arc;
 use std::sync::OnceLock;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{error, info, instrument, warn};
 
+use crate::config::DatasetWorkerSettings;
+use crate::dimensions;
+use crate::document_vectors;
+use crate::embedding_cache;
+use crate::multi_field::{self, WeightedField};
+use crate::resume;
+
 const QDRANT_CHUNK_SIZE: usize = 1000;
 
+/// Attempts per embedding sub-batch. The embedder already retries transient
+/// HTTP errors; this covers failures that outlast those retries without
+/// re-embedding the rest of the batch.
+const SUB_BATCH_ATTEMPTS: u32 = 3;
+
 /// Cached parallel upload count (set once from main via init_job_config)
 static QDRANT_PARALLEL_UPLOADS: OnceLock<usize> = OnceLock::new();
 
+/// Cached cap on texts per embedding request
+static MAX_EMBED_BATCH_SIZE: OnceLock<usize> = OnceLock::new();
+
+/// Cached number of embedding requests in flight per job
+static EMBED_BATCH_CONCURRENCY: OnceLock<usize> = OnceLock::new();
+
+/// Whether embeddings with NaN or infinite values are rejected
+static REJECT_NON_FINITE: OnceLock<bool> = OnceLock::new();
+
 /// Initialize job-level configuration. Call once from main.
-pub fn init_job_config(qdrant_parallel_uploads: usize) {
-    QDRANT_PARALLEL_UPLOADS.get_or_init(|| qdrant_parallel_uploads);
+pub(crate) fn init_job_config(settings: &DatasetWorkerSettings) {
+    QDRANT_PARALLEL_UPLOADS.get_or_init(|| settings.qdrant_parallel_uploads);
+    MAX_EMBED_BATCH_SIZE.get_or_init(|| settings.max_embed_batch_size);
+    EMBED_BATCH_CONCURRENCY.get_or_init(|| settings.embed_batch_concurrency);
+    REJECT_NON_FINITE.get_or_init(|| settings.reject_non_finite_embeddings);
 }
 
 #[derive(serde::Deserialize)]
@@ -30,6 +59,391 @@ pub(crate) struct BatchItem {
     pub(crate) id: String,
     pub(crate) text: String,
     pub(crate) payload: serde_json::Map<String, serde_json::Value>,
+    /// Type used to look up `DatasetTransformJob::item_type_overrides`
+    #[serde(default)]
+    pub(crate) item_type: Option<String>,
+    /// Fields embedded separately and combined by weight, in place of `text`
+    #[serde(default)]
+    pub(crate) fields: Vec<WeightedField>,
+}
+
+/// Items of a batch that share an embedder and text prefix, embedded in one call
+struct EmbeddingGroup<'a> {
+    embedder_config: &'a EmbedderConfig,
+    batch_size: Option<usize>,
+    stamp: EmbeddingStamp,
+    /// Embedder used instead while the group's own is unavailable
+    fallback: Option<GroupFallback<'a>>,
+    /// Positions of the group's items in the batch
+    indices: Vec<usize>,
+    /// Per item, the weights of its fields; empty for items embedded from
+    /// their text alone
+    field_weights: Vec<Vec<f32>>,
+    /// Texts sent to the embedder, with the item type's prefix applied: one
+    /// per plain item, one per field for multi-field items
+    texts: Vec<String>,
+}
+
+/// The job's fallback embedder, as applied to one group
+struct GroupFallback<'a> {
+    embedder_config: &'a EmbedderConfig,
+    batch_size: Option<usize>,
+    stamp: EmbeddingStamp,
+}
+
+/// Split a batch by item type override. Items without a type, or whose type
+/// has no override, use the job's embedder; groups keep first-seen order.
+fn plan_embedding_groups<'a>(
+    items: &[BatchItem],
+    job: &'a DatasetTransformJob,
+) -> Vec<EmbeddingGroup<'a>> {
+    let mut groups: Vec<EmbeddingGroup<'a>> = Vec::new();
+    let mut group_by_type: HashMap<Option<&str>, usize> = HashMap::new();
+
+    for (idx, item) in items.iter().enumerate() {
+        let item_override = item
+            .item_type
+            .as_deref()
+            .and_then(|t| job.item_type_overrides.get_key_value(t));
+        let key = item_override.map(|(t, _)| t.as_str());
+
+        let group_idx = *group_by_type.entry(key).or_insert_with(|| {
+            let override_embedder =
+                item_override.and_then(|(_, o)| Some((o.embedder_id, o.embedder_config.as_ref()?)));
+            let (embedder_id, embedder_config, batch_size) = match override_embedder {
+                Some((embedder_id, config)) => {
+                    (embedder_id, config, Some(config.batch_size.max(1) as usize))
+                }
+                None => (job.embedder_id, &job.embedder_config, job.batch_size),
+            };
+            let fallback = job
+                .fallback_embedder
+                .as_ref()
+                .filter(|fallback| Some(fallback.embedder_id) != embedder_id)
+                .map(|fallback| GroupFallback {
+                    embedder_config: &fallback.embedder_config,
+                    batch_size: Some(fallback.embedder_config.batch_size.max(1) as usize),
+                    stamp: EmbeddingStamp::from_embedder_config(
+                        Some(fallback.embedder_id),
+                        &fallback.embedder_config,
+                    ),
+                });
+            groups.push(EmbeddingGroup {
+                embedder_config,
+                batch_size,
+                stamp: EmbeddingStamp::from_embedder_config(embedder_id, embedder_config),
+                fallback,
+                indices: Vec::new(),
+                field_weights: Vec::new(),
+                texts: Vec::new(),
+            });
+            groups.len() - 1
+        });
+
+        let prefix = item_override
+            .and_then(|(_, o)| o.prefix.as_deref())
+            .unwrap_or_default();
+        let group = &mut groups[group_idx];
+        group.indices.push(idx);
+        if item.fields.is_empty() {
+            group.field_weights.push(Vec::new());
+            group.texts.push(format!("{prefix}{}", item.text));
+        } else {
+            group
+                .field_weights
+                .push(item.fields.iter().map(|f| f.weight).collect());
+            group
+                .texts
+                .extend(item.fields.iter().map(|f| format!("{prefix}{}", f.text)));
+        }
+    }
+
+    groups
+}
+
+/// One embedding per item of a group from the embeddings of its texts,
+/// combining each multi-field item's field vectors by weight
+fn item_embeddings(
+    text_embeddings: Vec<Vec<f32>>,
+    field_weights: &[Vec<f32>],
+) -> Result<Vec<Vec<f32>>> {
+    let mut text_embeddings = text_embeddings.into_iter();
+    field_weights
+        .iter()
+        .map(|weights| {
+            if weights.is_empty() {
+                return text_embeddings
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("missing embedding"));
+            }
+            let fields: Vec<Vec<f32>> = text_embeddings.by_ref().take(weights.len()).collect();
+            multi_field::combine(&fields, weights)
+                .ok_or_else(|| anyhow::anyhow!("field embeddings cannot be combined"))
+        })
+        .collect()
+}
+
+/// Each item's content hash under the embedder of its group
+fn content_hashes(items: &[BatchItem], groups: &[EmbeddingGroup<'_>]) -> Vec<String> {
+    let mut hashes = vec![String::new(); items.len()];
+    for group in groups {
+        for &idx in &group.indices {
+            hashes[idx] = resume::content_hash(&items[idx], &group.stamp);
+        }
+    }
+    hashes
+}
+
+/// Embed one group's texts with its embedder, recording each request
+async fn embed_group(
+    config: &EmbedderConfig,
+    texts: &[String],
+    sub_batch_size: usize,
+    concurrency: usize,
+) -> Result<Vec<Vec<f32>>> {
+    embed_in_sub_batches(texts, sub_batch_size, concurrency, |texts| async move {
+        let item_count = texts.len();
+        let started = Instant::now();
+        let result = embedder::generate_batch_embeddings(config, texts, Some(item_count)).await;
+        record_embed_request(
+            &config.model,
+            item_count as u64,
+            started.elapsed().as_secs_f64(),
+            result.is_ok(),
+        );
+        // A rejected sub-batch is retried like any other failed one
+        match result {
+            Ok(embeddings) if REJECT_NON_FINITE.get().copied().unwrap_or(true) => {
+                reject_non_finite(&config.model, embeddings)
+            }
+            result => result,
+        }
+    })
+    .await
+}
+
+/// Fail if any embedding contains NaN or infinite values, which would poison
+/// the collection: such points never rank sensibly in search
+fn reject_non_finite(model: &str, embeddings: Vec<Vec<f32>>) -> Result<Vec<Vec<f32>>> {
+    let invalid = non_finite_positions(&embeddings);
+    if invalid.is_empty() {
+        return Ok(embeddings);
+    }
+
+    record_non_finite_embeddings(model, invalid.len() as u64);
+    warn!(
+        embedder_model = %model,
+        invalid_count = invalid.len(),
+        first_position = invalid[0],
+        "Embedder returned vectors with NaN or infinite values"
+    );
+    Err(anyhow::anyhow!(
+        "{} of {} embeddings contain NaN or infinite values (first at position {})",
+        invalid.len(),
+        embeddings.len(),
+        invalid[0]
+    ))
+}
+
+/// Positions of the embeddings that contain NaN or infinite values
+fn non_finite_positions(embeddings: &[Vec<f32>]) -> Vec<usize> {
+    embeddings
+        .iter()
+        .enumerate()
+        .filter(|(_, embedding)| embedding.iter().any(|v| !v.is_finite()))
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// A group's embeddings and the stamp of the embedder that produced them
+struct GroupEmbeddings<'g> {
+    embeddings: Vec<Vec<f32>>,
+    stamp: &'g EmbeddingStamp,
+    /// The group's own stamp, when its fallback produced the embeddings
+    fallback_for: Option<&'g EmbeddingStamp>,
+}
+
+/// Embed a group with its embedder or, while that embedder is unavailable,
+/// with the group's fallback. `embed` is given the embedder, its batch size
+/// and the stamp of the vectors it produces.
+async fn embed_group_with_fallback<'g, F, Fut>(
+    group: &'g EmbeddingGroup<'_>,
+    embed: F,
+) -> Result<GroupEmbeddings<'g>>
+where
+    F: Fn(&'g EmbedderConfig, Option<usize>, &'g EmbeddingStamp) -> Fut,
+    Fut: Future<Output = Result<Vec<Vec<f32>>>>,
+{
+    let result = embed(group.embedder_config, group.batch_size, &group.stamp).await;
+    match (result, &group.fallback) {
+        (Err(e), Some(fallback)) if embedder::unavailable_cause(&e).is_some() => {
+            warn!(
+                error = %e,
+                embedder_model = %group.embedder_config.model,
+                fallback_model = %fallback.embedder_config.model,
+                "Embedder unavailable, embedding with its fallback"
+            );
+            let embeddings = embed(
+                fallback.embedder_config,
+                fallback.batch_size,
+                &fallback.stamp,
+            )
+            .await?;
+            Ok(GroupEmbeddings {
+                embeddings,
+                stamp: &fallback.stamp,
+                fallback_for: Some(&group.stamp),
+            })
+        }
+        (result, _) => result.map(|embeddings| GroupEmbeddings {
+            embeddings,
+            stamp: &group.stamp,
+            fallback_for: None,
+        }),
+    }
+}
+
+/// Embed `texts` in sub-batches of at most `batch_size` texts with up to
+/// `concurrency` requests in flight. Embeddings come back in the order of
+/// `texts`. A failing sub-batch is retried on its own.
+async fn embed_in_sub_batches<'t, F, Fut>(
+    texts: &'t [String],
+    batch_size: usize,
+    concurrency: usize,
+    embed: F,
+) -> Result<Vec<Vec<f32>>>
+where
+    F: Fn(Vec<&'t str>) -> Fut,
+    Fut: Future<Output = Result<Vec<Vec<f32>>>>,
+{
+    let embed = &embed;
+    let sub_batches = texts
+        .chunks(batch_size.max(1))
+        .enumerate()
+        .map(|(index, sub_batch)| async move {
+            let mut attempt = 1;
+            loop {
+                let result = embed(sub_batch.iter().map(String::as_str).collect())
+                    .await
+                    .and_then(|embeddings| {
+                        if embeddings.len() == sub_batch.len() {
+                            Ok(embeddings)
+                        } else {
+                            Err(anyhow::anyhow!(
+                                "Embedding count mismatch: expected {}, got {}",
+                                sub_batch.len(),
+                                embeddings.len()
+                            ))
+                        }
+                    });
+                match result {
+                    Ok(embeddings) => return Ok(embeddings),
+                    // Retrying against an open circuit breaker only fails again
+                    Err(e)
+                        if attempt < SUB_BATCH_ATTEMPTS
+                            && embedder::unavailable_cause(&e).is_none() =>
+                    {
+                        warn!(error = %e, sub_batch = index, attempt, "Embedding sub-batch failed, retrying it");
+                        tokio::time::sleep(Duration::from_millis(250 * 2u64.pow(attempt - 1)))
+                            .await;
+                        attempt += 1;
+                    }
+                    Err(e) => {
+                        return Err(e.context(format!(
+                            "sub-batch {index} failed after {attempt} attempts"
+                        )));
+                    }
+                }
+            }
+        });
+
+    // `buffered` (not `buffer_unordered`) keeps sub-batch results in order
+    let results: Vec<Vec<Vec<f32>>> = stream::iter(sub_batches)
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await?;
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// Build Qdrant points, stamping each payload with the version of the
+/// embedder that produced it so search can detect vectors produced by a
+/// different model or preprocessing, and with the content hash that lets a
+/// redelivered job skip it. The stored text is the unprefixed item text.
+/// With `with_sparse`, points also get the BM25 sparse vector of that text for
+/// keyword and hybrid search. A language detected at extraction is copied to a
+/// top-level `language` field so search can filter on it. Points a fallback
+/// embedder produced also carry the stamp from `fallback_for` of the embedder
+/// they were meant for, marking them for re-embedding.
+fn build_points(
+    items: &[BatchItem],
+    embeddings: Vec<Vec<f32>>,
+    stamps: &[&EmbeddingStamp],
+    fallback_for: &[Option<&EmbeddingStamp>],
+    with_sparse: bool,
+) -> Vec<PointStruct> {
+    items
+        .iter()
+        .zip(embeddings)
+        .zip(stamps)
+        .zip(fallback_for)
+        .map(|(((item, embedding), stamp), fallback_for)| {
+            let mut payload = item.payload.clone();
+            payload.insert(
+                resume::CONTENT_HASH_PAYLOAD_KEY.to_string(),
+                serde_json::json!(resume::content_hash(item, stamp)),
+            );
+            payload.insert("text".to_string(), serde_json::json!(item.text));
+            if let Some(language) = detected_language(&item.payload) {
+                payload.insert(
+                    LANGUAGE_PAYLOAD_KEY.to_string(),
+                    serde_json::json!(language),
+                );
+            }
+            stamp.stamp_payload(&mut payload);
+            if let Some(primary) = fallback_for {
+                payload.insert(
+                    FALLBACK_FOR_PAYLOAD_KEY.to_string(),
+                    serde_json::json!(primary),
+                );
+            }
+
+            let vectors: Vectors = match with_sparse.then(|| sparse::document_vector(&item.text)) {
+                Some(terms) if !terms.is_empty() => NamedVectors::default()
+                    // The dense vector stays the collection's default (unnamed) vector
+                    .add_vector("", embedding)
+                    .add_vector(
+                        SPARSE_VECTOR_NAME,
+                        Vector::new_sparse(terms.indices, terms.values),
+                    )
+                    .into(),
+                _ => embedding.into(),
+            };
+            PointStruct::new(
+                item.id.clone(),
+                vectors,
+                qdrant_client::Payload::from(payload),
+            )
+        })
+        .collect()
+}
+
+/// Payload key holding the language detected when the source was extracted
+const LANGUAGE_PAYLOAD_KEY: &str = "language";
+
+/// Language recorded by the collection worker's extraction, from the chunk's
+/// extraction metadata or else the item metadata; `unknown` is left out
+fn detected_language(payload: &serde_json::Map<String, serde_json::Value>) -> Option<&str> {
+    let chunk = payload
+        .get("chunk_metadata")
+        .and_then(|m| m.get("extraction_metadata"))
+        .and_then(|m| m.get("detected_language"));
+    let item = payload
+        .get("item_metadata")
+        .and_then(|m| m.get("detected_language"));
+    chunk
+        .or(item)
+        .and_then(|v| v.as_str())
+        .filter(|language| *language != "unknown")
 }
 
 #[instrument(skip(ctx), fields(job_id = %job.job_id, dataset_transform_id = %job.dataset_transform_id, embedded_dataset_id = %job.embedded_dataset_id, collection = %job.collection_name))]
@@ -106,7 +520,7 @@ pub(crate) async fn process_dataset_transform_job(
         batch_size_bytes = batch_content.len(),
         "Batch file downloaded"
     );
-    let items: Vec<BatchItem> = match serde_json::from_slice(&batch_content) {
+    let mut items: Vec<BatchItem> = match serde_json::from_slice(&batch_content) {
         Ok(items) => items,
         Err(e) => {
             let duration = start_time.elapsed().as_secs_f64();
@@ -140,8 +554,6 @@ pub(crate) async fn process_dataset_transform_job(
         return Ok(());
     }
 
-    let texts: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
-
     // Pre-embedding abort check: verify batch file still exists in S3.
     // If the transform was deleted, the API cleans up S3 batch files so workers
     // can detect deletion and abort BEFORE wasting embedding tokens.
@@ -184,95 +596,231 @@ pub(crate) async fn process_dataset_transform_job(
         }
     }
 
+    // Get cached Qdrant client instead of recreating for each job
+    let qdrant_client = crate::qdrant_cache::get_or_create_client(
+        &job.qdrant_config.url,
+        job.qdrant_config.api_key.clone(),
+    )
+    .await?;
+
+    // Skip chunks a previous delivery of this job already upserted
+    let mut groups = plan_embedding_groups(&items, &job);
+    let hashes = content_hashes(&items, &groups);
+    let stored = resume::already_stored(
+        &resume::QdrantPoints {
+            client: &qdrant_client,
+            url: &job.qdrant_config.url,
+            collection: &job.collection_name,
+        },
+        &items,
+        &hashes,
+    )
+    .await;
+    let skipped = stored.iter().filter(|&&stored| stored).count();
+    // Taken before skipped chunks are dropped, so a document whose chunks were
+    // all stored before a restart still gets its vector
+    let documents = job
+        .document_vectors
+        .map(|_| document_vectors::batch_documents(&items))
+        .unwrap_or_default();
+    if skipped == chunk_count {
+        if let Err(e) = store_document_vectors(&qdrant_client, &job, &documents).await {
+            let duration = start_time.elapsed().as_secs_f64();
+            record_worker_job("dataset-transform", duration, "failed_upsert");
+            return Err(e);
+        }
+        let duration = start_time.elapsed().as_secs_f64();
+        record_worker_job("dataset-transform", duration, "success_resumed");
+        info!(chunk_count, "All chunks already upserted, skipping job");
+        send_result(
+            &ctx.nats_client,
+            &job,
+            Ok(chunk_count),
+            Some((duration * 1000.0) as i64),
+        )
+        .await?;
+        return Ok(());
+    }
+    if skipped > 0 {
+        info!(
+            skipped,
+            remaining = chunk_count - skipped,
+            "Resuming job, skipping already upserted chunks"
+        );
+        items = items
+            .into_iter()
+            .zip(stored)
+            .filter_map(|(item, stored)| (!stored).then_some(item))
+            .collect();
+        groups = plan_embedding_groups(&items, &job);
+    }
+
+    // Check the embedder's vectors fit the collection before embedding
+    // anything, creating a missing collection with the embedder's dimensions.
+    // Without the dimensions the check waits until the vectors are back.
+    match dimensions::embedder_dimensions(&job.embedder_config).await {
+        Ok(dimensions) => {
+            if let Err(e) = crate::qdrant_cache::ensure_collection_exists(
+                &qdrant_client,
+                &job.qdrant_config.url,
+                &job.collection_name,
+                dimensions,
+                job.embedder_config.distance_metric,
+            )
+            .await
+            {
+                return fail_on_dimension_mismatch(&ctx, &job, chunk_count, start_time, e).await;
+            }
+        }
+        Err(e) => warn!(
+            error = %format!("{e:#}"),
+            embedder_model = %job.embedder_config.model,
+            "Failed to determine embedder dimensions, checking the collection after embedding"
+        ),
+    }
+
     info!(
         chunk_count,
         batch_size = job.batch_size,
         embedder_provider = ?job.embedder_config.provider,
         embedder_model = ?job.embedder_config.model,
+        embedding_groups = groups.len(),
         collection_name = %job.collection_name,
         embedded_dataset_id = job.embedded_dataset_id,
         "Generating embeddings"
     );
 
-    let embeddings = match embedder::generate_batch_embeddings(
-        &job.embedder_config,
-        texts,
-        job.batch_size,
-    )
-    .await
-    {
-        Ok(embeddings) => embeddings,
-        Err(e) => {
+    let max_embed_batch_size = MAX_EMBED_BATCH_SIZE.get().copied().unwrap_or(128);
+    let embed_concurrency = EMBED_BATCH_CONCURRENCY.get().copied().unwrap_or(4);
+
+    let mut embeddings: Vec<Vec<f32>> = vec![Vec::new(); items.len()];
+    let mut stamps: Vec<&EmbeddingStamp> = vec![&groups[0].stamp; items.len()];
+    let mut fallback_for: Vec<Option<&EmbeddingStamp>> = vec![None; items.len()];
+    for group in &groups {
+        let config = group.embedder_config;
+        let result = embed_group_with_fallback(group, |config, batch_size, stamp| async move {
+            let sub_batch_size = batch_size
+                .unwrap_or(max_embed_batch_size)
+                .min(max_embed_batch_size);
+            match embedding_cache::store() {
+                Some(store) => embedding_cache::embed_with_cache(
+                    store,
+                    stamp,
+                    &group.texts,
+                    |texts| async move {
+                        embed_group(config, &texts, sub_batch_size, embed_concurrency).await
+                    },
+                )
+                .await
+                .map(|cached| {
+                    record_embedding_cache(&config.model, cached.hits as u64, cached.misses as u64);
+                    cached.embeddings
+                }),
+                None => embed_group(config, &group.texts, sub_batch_size, embed_concurrency).await,
+            }
+        })
+        .await;
+        let GroupEmbeddings {
+            embeddings: group_embeddings,
+            stamp,
+            fallback_for: group_fallback_for,
+        } = match result {
+            Ok(result) => result,
+            // The embedder was never called; let the worker NAK the job so it
+            // is redelivered once the circuit breaker lets requests through
+            Err(e) if embedder::unavailable_cause(&e).is_some() => {
+                let duration = start_time.elapsed().as_secs_f64();
+                record_worker_job("dataset-transform", duration, "embedder_unavailable");
+                return Err(e);
+            }
+            Err(e) => {
+                let duration = start_time.elapsed().as_secs_f64();
+                record_worker_job("dataset-transform", duration, "failed_embedding");
+                error!(error = %format!("{e:#}"), embedder_model = %config.model, "Embedding failed");
+                send_result(
+                    &ctx.nats_client,
+                    &job,
+                    Err((chunk_count, format!("Embedding failed: {e:#}"))),
+                    Some((duration * 1000.0) as i64),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if group_embeddings.len() != group.texts.len() {
             let duration = start_time.elapsed().as_secs_f64();
-            record_worker_job("dataset-transform", duration, "failed_embedding");
-            error!(error = %e, "Embedding failed");
+            record_worker_job("dataset-transform", duration, "failed_mismatch");
+            error!(
+                expected = group.texts.len(),
+                actual = group_embeddings.len(),
+                embedder_model = %group.embedder_config.model,
+                "Embedding count mismatch"
+            );
             send_result(
                 &ctx.nats_client,
                 &job,
-                Err((chunk_count, format!("Embedding failed: {e}"))),
+                Err((chunk_count, "Embedding count mismatch".to_string())),
                 Some((duration * 1000.0) as i64),
             )
             .await?;
             return Ok(());
         }
-    };
+
+        let group_embeddings = match item_embeddings(group_embeddings, &group.field_weights) {
+            Ok(embeddings) => embeddings,
+            Err(e) => {
+                let duration = start_time.elapsed().as_secs_f64();
+                record_worker_job("dataset-transform", duration, "failed_mismatch");
+                error!(error = %e, embedder_model = %group.embedder_config.model, "Failed to combine field embeddings");
+                send_result(
+                    &ctx.nats_client,
+                    &job,
+                    Err((
+                        chunk_count,
+                        format!("Failed to combine field embeddings: {e}"),
+                    )),
+                    Some((duration * 1000.0) as i64),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        for (&idx, embedding) in group.indices.iter().zip(group_embeddings) {
+            embeddings[idx] = embedding;
+            stamps[idx] = stamp;
+            fallback_for[idx] = group_fallback_for;
+        }
+    }
     info!(
         embedding_count = embeddings.len(),
         "Embeddings generated successfully"
     );
 
-    if embeddings.len() != items.len() {
-        let duration = start_time.elapsed().as_secs_f64();
-        record_worker_job("dataset-transform", duration, "failed_mismatch");
-        error!(
-            expected = items.len(),
-            actual = embeddings.len(),
-            "Embedding count mismatch"
-        );
-        send_result(
-            &ctx.nats_client,
-            &job,
-            Err((chunk_count, "Embedding count mismatch".to_string())),
-            Some((duration * 1000.0) as i64),
-        )
-        .await?;
-        return Ok(());
-    }
-
-    // Get cached Qdrant client instead of recreating for each job
-    let qdrant_client = crate::qdrant_cache::get_or_create_client(
-        &job.qdrant_config.url,
-        job.qdrant_config.api_key.clone(),
-    )
-    .await?;
-
     let embedding_size = embeddings
         .first()
         .map(|e| e.len() as u64)
         .ok_or_else(|| anyhow::anyhow!("No embeddings to determine vector size"))?;
 
-    // Ensure collection exists using cached check (avoids redundant API calls)
-    crate::qdrant_cache::ensure_collection_exists(
+    // Ensure collection exists using cached check (avoids redundant API calls).
+    // This also catches fallback or item type embedders of other dimensions.
+    let with_sparse = match crate::qdrant_cache::ensure_collection_exists(
         &qdrant_client,
         &job.qdrant_config.url,
         &job.collection_name,
         embedding_size,
+        job.embedder_config.distance_metric,
     )
-    .await?;
+    .await
+    {
+        Ok(with_sparse) => with_sparse,
+        Err(e) => {
+            return fail_on_dimension_mismatch(&ctx, &job, chunk_count, start_time, e).await;
+        }
+    };
 
-    let points: Vec<PointStruct> = items
-        .iter()
-        .zip(embeddings.into_iter())
-        .map(|(item, embedding)| {
-            let mut payload = item.payload.clone();
-            payload.insert("text".to_string(), serde_json::json!(item.text));
-            PointStruct::new(
-                item.id.clone(),
-                embedding,
-                qdrant_client::Payload::from(payload),
-            )
-        })
-        .collect();
+    let points = build_points(&items, embeddings, &stamps, &fallback_for, with_sparse);
 
     let point_chunks: Vec<Vec<PointStruct>> = points
         .chunks(QDRANT_CHUNK_SIZE)
@@ -292,6 +840,7 @@ pub(crate) async fn process_dataset_transform_job(
 
     let upsert_start = Instant::now();
     let collection_name = job.collection_name.clone();
+    let write_consistency = job.write_consistency;
     let client = Arc::clone(&qdrant_client);
 
     // Parallel upsert with bounded concurrency
@@ -302,7 +851,11 @@ pub(crate) async fn process_dataset_transform_job(
                 let collection = collection_name.clone();
                 async move {
                     match client
-                        .upsert_points(UpsertPointsBuilder::new(&collection, chunk).wait(true))
+                        .upsert_points(crate::qdrant_cache::upsert_request(
+                            &collection,
+                            chunk,
+                            write_consistency,
+                        ))
                         .await
                     {
                         Ok(_) => Ok(idx),
@@ -327,6 +880,11 @@ pub(crate) async fn process_dataset_transform_job(
             ));
         }
     }
+    if let Err(e) = store_document_vectors(&qdrant_client, &job, &documents).await {
+        let duration = start_time.elapsed().as_secs_f64();
+        record_worker_job("dataset-transform", duration, "failed_upsert");
+        return Err(e);
+    }
     let upsert_duration = upsert_start.elapsed().as_secs_f64();
 
     let duration = start_time.elapsed().as_secs_f64();
@@ -347,6 +905,59 @@ pub(crate) async fn process_dataset_transform_job(
     Ok(())
 }
 
+/// Fail the job for good when its embedder's vectors don't fit the
+/// collection, since redelivery can't fix that. Other errors are returned so
+/// the job is redelivered.
+async fn fail_on_dimension_mismatch(
+    ctx: &WorkerContext,
+    job: &DatasetTransformJob,
+    chunk_count: usize,
+    start_time: Instant,
+    error: anyhow::Error,
+) -> Result<()> {
+    let Some(mismatch) = embedder::dimension_mismatch_cause(&error) else {
+        return Err(error);
+    };
+    let duration = start_time.elapsed().as_secs_f64();
+    record_worker_job("dataset-transform", duration, "failed_dimension_mismatch");
+    error!(
+        collection = %mismatch.collection,
+        collection_dimensions = mismatch.collection_dimensions,
+        embedder_dimensions = mismatch.embedder_dimensions,
+        embedder_model = %job.embedder_config.model,
+        "Embedder dimensions don't match the collection"
+    );
+    send_result(
+        &ctx.nats_client,
+        job,
+        Err((chunk_count, mismatch.to_string())),
+        Some((duration * 1000.0) as i64),
+    )
+    .await
+}
+
+/// Store the vectors of `documents` when the job asks for them. Failures are
+/// returned so the job is redelivered; its chunks are then skipped.
+async fn store_document_vectors(
+    client: &Arc<qdrant_client::Qdrant>,
+    job: &DatasetTransformJob,
+    documents: &BTreeMap<i64, serde_json::Value>,
+) -> Result<()> {
+    if documents.is_empty() {
+        return Ok(());
+    }
+    match document_vectors::store_document_vectors(client, job, documents).await {
+        Ok(count) => {
+            info!(document_count = count, "Document vectors upserted");
+            Ok(())
+        }
+        Err(e) => {
+            error!(error = %format!("{e:#}"), "Failed to upsert document vectors");
+            Err(e.context("Document vector upsert failed"))
+        }
+    }
+}
+
 async fn send_progress_update(
     nats: &async_nats::Client,
     job: &DatasetTransformJob,
@@ -490,3 +1101,429 @@ async fn send_result(
     );
     Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Unknown publish error")))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qdrant_client::qdrant::value::Kind;
+    use semantic_explorer_core::embedding_stamp::EMBEDDING_STAMP_PAYLOAD_KEY;
+    use semantic_explorer_core::models::ItemTypeEmbedding;
+
+    #[test]
+    fn test_points_carry_embedding_stamp() {
+        let config = EmbedderConfig::new(
+            "internal".to_string(),
+            "http://localhost:8090".to_string(),
+            None,
+            "BAAI/bge-small-en-v1.5".to_string(),
+            serde_json::json!({ "model": "BAAI/bge-small-en-v1.5" }),
+            32,
+            512,
+        );
+        let stamp = EmbeddingStamp::from_embedder_config(Some(12), &config);
+        let items = vec![BatchItem {
+            id: "00000000-0000-0000-0000-000000000001".to_string(),
+            text: "hello".to_string(),
+            payload: serde_json::Map::new(),
+            item_type: None,
+            fields: Vec::new(),
+        }];
+
+        let points = build_points(&items, vec![vec![0.1, 0.2]], &[&stamp], &[None], false);
+
+        let Some(Kind::StructValue(stamped)) = points[0]
+            .payload
+            .get(EMBEDDING_STAMP_PAYLOAD_KEY)
+            .and_then(|v| v.kind.clone())
+        else {
+            panic!("point payload is missing the embedding stamp");
+        };
+        assert_eq!(
+            stamped.fields["embedder_id"].kind,
+            Some(Kind::IntegerValue(12))
+        );
+        assert_eq!(
+            stamped.fields["preprocessing_fingerprint"].kind,
+            Some(Kind::StringValue(stamp.preprocessing_fingerprint.clone()))
+        );
+    }
+
+    #[test]
+    fn test_points_carry_detected_language() {
+        let config = embedder_config("model-a");
+        let stamp = EmbeddingStamp::from_embedder_config(Some(1), &config);
+        let item = |payload: serde_json::Value| BatchItem {
+            id: "00000000-0000-0000-0000-000000000001".to_string(),
+            text: "hallo".to_string(),
+            payload: payload.as_object().unwrap().clone(),
+            item_type: None,
+            fields: Vec::new(),
+        };
+        let items = vec![
+            item(serde_json::json!({
+                "chunk_metadata": { "extraction_metadata": { "detected_language": "de" } }
+            })),
+            item(serde_json::json!({
+                "chunk_metadata": { "extraction_metadata": { "detected_language": "unknown" } }
+            })),
+        ];
+
+        let points = build_points(
+            &items,
+            vec![vec![0.1, 0.2], vec![0.3, 0.4]],
+            &[&stamp, &stamp],
+            &[None, None],
+            false,
+        );
+        assert_eq!(
+            points[0]
+                .payload
+                .get(LANGUAGE_PAYLOAD_KEY)
+                .and_then(|v| v.kind.clone()),
+            Some(Kind::StringValue("de".to_string()))
+        );
+        assert!(!points[1].payload.contains_key(LANGUAGE_PAYLOAD_KEY));
+    }
+
+    #[test]
+    fn test_points_carry_sparse_vector_when_collection_has_one() {
+        use qdrant_client::qdrant::vectors::VectorsOptions;
+
+        let config = embedder_config("model-a");
+        let stamp = EmbeddingStamp::from_embedder_config(Some(1), &config);
+        let items = vec![BatchItem {
+            id: "00000000-0000-0000-0000-000000000001".to_string(),
+            text: "Error ERR-404 on checkout".to_string(),
+            payload: serde_json::Map::new(),
+            item_type: None,
+            fields: Vec::new(),
+        }];
+
+        let points = build_points(&items, vec![vec![0.1, 0.2]], &[&stamp], &[None], true);
+        let Some(VectorsOptions::Vectors(named)) = points[0]
+            .vectors
+            .as_ref()
+            .and_then(|v| v.vectors_options.clone())
+        else {
+            panic!("expected named vectors");
+        };
+        assert!(named.vectors.contains_key(""));
+        assert!(named.vectors.contains_key(SPARSE_VECTOR_NAME));
+
+        // Dense-only collections keep the plain vector
+        let points = build_points(&items, vec![vec![0.1, 0.2]], &[&stamp], &[None], false);
+        assert!(matches!(
+            points[0]
+                .vectors
+                .as_ref()
+                .and_then(|v| v.vectors_options.clone()),
+            Some(VectorsOptions::Vector(_))
+        ));
+    }
+
+    fn embedder_config(model: &str) -> EmbedderConfig {
+        EmbedderConfig::new(
+            "internal".to_string(),
+            "http://localhost:8090".to_string(),
+            None,
+            model.to_string(),
+            serde_json::json!({ "model": model }),
+            32,
+            512,
+        )
+    }
+
+    fn batch_item(id: u8, text: &str, item_type: Option<&str>) -> BatchItem {
+        BatchItem {
+            id: format!("00000000-0000-0000-0000-0000000000{id:02}"),
+            text: text.to_string(),
+            payload: serde_json::Map::new(),
+            item_type: item_type.map(str::to_string),
+            fields: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_item_types_embed_with_configured_settings() {
+        let mut job: DatasetTransformJob = serde_json::from_value(serde_json::json!({
+            "job_id": "00000000-0000-0000-0000-000000000000",
+            "batch_file_key": "batches/batch-0.json",
+            "bucket": "bucket",
+            "dataset_id": 1,
+            "dataset_transform_id": 2,
+            "embedded_dataset_id": 3,
+            "owner_id": "owner",
+            "embedder_id": 10,
+            "embedder_config": embedder_config("base-model"),
+            "qdrant_config": { "url": "http://localhost:6334", "api_key": null },
+            "collection_name": "collection",
+            "batch_size": 16
+        }))
+        .unwrap();
+        job.item_type_overrides = HashMap::from([
+            (
+                "title".to_string(),
+                ItemTypeEmbedding {
+                    prefix: Some("title: ".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "code".to_string(),
+                ItemTypeEmbedding {
+                    prefix: Some("code: ".to_string()),
+                    embedder_id: Some(20),
+                    embedder_config: Some(embedder_config("code-model")),
+                },
+            ),
+        ]);
+        let items = vec![
+            batch_item(1, "fn main() {}", Some("code")),
+            batch_item(2, "Getting Started", Some("title")),
+            batch_item(3, "plain body text", None),
+            batch_item(4, "let x = 1;", Some("code")),
+            batch_item(5, "unconfigured", Some("comment")),
+        ];
+
+        let groups = plan_embedding_groups(&items, &job);
+        assert_eq!(groups.len(), 3);
+
+        let code = &groups[0];
+        assert_eq!(code.indices, vec![0, 3]);
+        assert_eq!(code.texts, vec!["code: fn main() {}", "code: let x = 1;"]);
+        assert_eq!(code.embedder_config.model, "code-model");
+        assert_eq!(code.batch_size, Some(32));
+        assert_eq!(code.stamp.embedder_id, Some(20));
+
+        let title = &groups[1];
+        assert_eq!(title.indices, vec![1]);
+        assert_eq!(title.texts, vec!["title: Getting Started"]);
+        assert_eq!(title.embedder_config.model, "base-model");
+        assert_eq!(title.batch_size, Some(16));
+        assert_eq!(title.stamp.embedder_id, Some(10));
+
+        // Untyped items and types without an override share the job's embedder
+        let default = &groups[2];
+        assert_eq!(default.indices, vec![2, 4]);
+        assert_eq!(default.texts, vec!["plain body text", "unconfigured"]);
+        assert_eq!(default.embedder_config.model, "base-model");
+
+        // Stored payload text is the original, unprefixed text
+        let points = build_points(
+            &items[..1],
+            vec![vec![0.1, 0.2]],
+            &[&groups[0].stamp],
+            &[None],
+            false,
+        );
+        assert_eq!(
+            points[0].payload.get("text").and_then(|v| v.kind.clone()),
+            Some(Kind::StringValue("fn main() {}".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_multi_field_items_embed_each_field_and_combine() {
+        let job: DatasetTransformJob = serde_json::from_value(serde_json::json!({
+            "job_id": "00000000-0000-0000-0000-000000000000",
+            "batch_file_key": "batches/batch-0.json",
+            "bucket": "bucket",
+            "dataset_id": 1,
+            "dataset_transform_id": 2,
+            "embedded_dataset_id": 3,
+            "owner_id": "owner",
+            "embedder_config": embedder_config("base-model"),
+            "qdrant_config": { "url": "http://localhost:6334", "api_key": null },
+            "collection_name": "collection"
+        }))
+        .unwrap();
+        let mut structured = batch_item(1, "body text", None);
+        structured.fields = vec![
+            WeightedField {
+                text: "A Title".to_string(),
+                weight: 3.0,
+            },
+            WeightedField {
+                text: "body text".to_string(),
+                weight: 1.0,
+            },
+        ];
+        let items = vec![batch_item(2, "plain", None), structured];
+
+        let groups = plan_embedding_groups(&items, &job);
+        assert_eq!(groups[0].texts, vec!["plain", "A Title", "body text"]);
+        assert_eq!(groups[0].field_weights, vec![vec![], vec![3.0, 1.0]]);
+
+        let combined = item_embeddings(
+            vec![vec![0.5, 0.5], vec![1.0, 0.0], vec![0.0, 1.0]],
+            &groups[0].field_weights,
+        )
+        .unwrap();
+        assert_eq!(combined.len(), 2);
+        assert_eq!(combined[0], vec![0.5, 0.5]);
+        assert!((combined[1][0] / combined[1][1] - 3.0).abs() < 1e-5);
+
+        // Too few embeddings for the fields is an error, not a panic
+        assert!(item_embeddings(vec![vec![0.5, 0.5]], &groups[0].field_weights).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_embedder_falls_back_and_tags_points() {
+        let job: DatasetTransformJob = serde_json::from_value(serde_json::json!({
+            "job_id": "00000000-0000-0000-0000-000000000000",
+            "batch_file_key": "batches/batch-0.json",
+            "bucket": "bucket",
+            "dataset_id": 1,
+            "dataset_transform_id": 2,
+            "embedded_dataset_id": 3,
+            "owner_id": "owner",
+            "embedder_id": 10,
+            "embedder_config": embedder_config("primary-model"),
+            "qdrant_config": { "url": "http://localhost:6334", "api_key": null },
+            "collection_name": "collection",
+            "fallback_embedder": {
+                "embedder_id": 30,
+                "embedder_config": embedder_config("fallback-model")
+            }
+        }))
+        .unwrap();
+        let items = vec![batch_item(1, "first", None), batch_item(2, "second", None)];
+        let groups = plan_embedding_groups(&items, &job);
+
+        // The primary's circuit breaker is open
+        let embedded = embed_group_with_fallback(&groups[0], |config, _, _| async move {
+            if config.model == "primary-model" {
+                return Err(embedder::EmbedderUnavailable {
+                    retry_after: Duration::from_secs(30),
+                }
+                .into());
+            }
+            Ok(vec![vec![1.0, 0.0]; 2])
+        })
+        .await
+        .unwrap();
+        assert_eq!(embedded.embeddings.len(), 2);
+        assert_eq!(embedded.stamp.embedder_id, Some(30));
+        assert_eq!(embedded.stamp.model, "fallback-model");
+        assert_eq!(embedded.fallback_for, Some(&groups[0].stamp));
+
+        let points = build_points(
+            &items,
+            embedded.embeddings,
+            &[embedded.stamp; 2],
+            &[embedded.fallback_for; 2],
+            false,
+        );
+        for point in &points {
+            let Some(Kind::StructValue(stamped)) = point
+                .payload
+                .get(EMBEDDING_STAMP_PAYLOAD_KEY)
+                .and_then(|v| v.kind.clone())
+            else {
+                panic!("point payload is missing the embedding stamp");
+            };
+            assert_eq!(
+                stamped.fields["embedder_id"].kind,
+                Some(Kind::IntegerValue(30))
+            );
+            let Some(Kind::StructValue(primary)) = point
+                .payload
+                .get(FALLBACK_FOR_PAYLOAD_KEY)
+                .and_then(|v| v.kind.clone())
+            else {
+                panic!("fallback point is not tagged with the primary embedder");
+            };
+            assert_eq!(
+                primary.fields["embedder_id"].kind,
+                Some(Kind::IntegerValue(10))
+            );
+        }
+
+        // Other failures are not hidden behind the fallback
+        let result = embed_group_with_fallback(&groups[0], |_, _, _| async move {
+            anyhow::bail!("invalid input")
+        })
+        .await;
+        assert!(result.is_err());
+
+        // A healthy primary is used as is
+        let embedded =
+            embed_group_with_fallback(&groups[0], |_, _, _| async move { Ok(vec![vec![0.5]; 2]) })
+                .await
+                .unwrap();
+        assert_eq!(embedded.stamp.embedder_id, Some(10));
+        assert!(embedded.fallback_for.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sub_batches_keep_order_and_retry_only_failures() {
+        use std::sync::Mutex;
+
+        let texts: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let calls: Mutex<Vec<Vec<String>>> = Mutex::new(Vec::new());
+
+        let embeddings = embed_in_sub_batches(&texts, 3, 4, |batch| {
+            let first_call = {
+                let mut calls = calls.lock().unwrap();
+                let first = !calls.iter().any(|c| c[0] == batch[0]);
+                calls.push(batch.iter().map(|t| t.to_string()).collect());
+                first
+            };
+            async move {
+                // Later sub-batches finish first; the one starting at "3"
+                // fails its first attempt
+                let first: f32 = batch[0].parse().unwrap();
+                tokio::time::sleep(Duration::from_millis(40 - first as u64 * 4)).await;
+                if first == 3.0 && first_call {
+                    anyhow::bail!("inference API unavailable");
+                }
+                let embeddings: Vec<Vec<f32>> =
+                    batch.iter().map(|t| vec![t.parse().unwrap()]).collect();
+                Ok(embeddings)
+            }
+        })
+        .await
+        .unwrap();
+
+        let expected: Vec<Vec<f32>> = (0..10).map(|i| vec![i as f32]).collect();
+        assert_eq!(embeddings, expected);
+
+        let calls = calls.into_inner().unwrap();
+        assert_eq!(calls.len(), 5);
+        assert_eq!(calls.iter().filter(|c| c[0] == "3").count(), 2);
+        assert_eq!(calls.last().unwrap(), &vec!["3", "4", "5"]);
+    }
+
+    #[test]
+    fn test_non_finite_embeddings_are_detected() {
+        let clean = vec![vec![0.1, -0.2, 0.3], vec![1.0, 0.0, -1.0]];
+        assert!(non_finite_positions(&clean).is_empty());
+
+        let poisoned = vec![
+            vec![0.1, 0.2, 0.3],
+            vec![0.1, f32::NAN, 0.3],
+            vec![0.5, 0.5, 0.5],
+            vec![f32::INFINITY, 0.0, f32::NEG_INFINITY],
+        ];
+        assert_eq!(non_finite_positions(&poisoned), vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_sub_batch_gives_up_after_repeated_failures() {
+        let texts: Vec<String> = vec!["a".to_string(), "b".to_string()];
+        let result = embed_in_sub_batches(&texts, 1, 2, |batch| async move {
+            let embeddings: Vec<Vec<f32>> = if batch[0] == "b" {
+                // Wrong number of vectors counts as a failure too
+                Vec::new()
+            } else {
+                vec![vec![1.0]]
+            };
+            Ok(embeddings)
+        })
+        .await;
+
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("sub-batch 1 failed after 3 attempts"), "{err}");
+        assert!(err.contains("Embedding count mismatch"), "{err}");
+    }
+}
@@ -1,11 +1,18 @@
 use anyhow::Result;
-use semantic_explorer_core::config::{EmbeddingInferenceConfig, NatsConfig};
+use semantic_explorer_core::config::{EmbeddingInferenceConfig, NatsConfig, ValkeyConfig};
 use semantic_explorer_core::nats::connect_with_retry;
 use semantic_explorer_core::worker::WorkerContext;
 use semantic_explorer_core::{storage::initialize_client, worker};
+use tracing::warn;
 
+mod config;
+mod dimensions;
+mod document_vectors;
+mod embedding_cache;
 mod job;
+mod multi_field;
 mod qdrant_cache;
+mod resume;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -21,6 +28,7 @@ async fn main() -> Result<()> {
 
     // Initialize S3 client
     let s3_client = initialize_client().await?;
+    let max_download_size_bytes = semantic_explorer_core::config::max_download_size_from_env()?;
 
     // Load NATS config from env at startup
     let nats_config = NatsConfig::from_env()?;
@@ -33,38 +41,37 @@ async fn main() -> Result<()> {
         embedding_config.max_concurrent_requests,
     );
 
-    // Initialize job-level config from env at startup
-    let qdrant_parallel_uploads: usize = std::env::var("QDRANT_PARALLEL_UPLOADS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(4);
-    job::init_job_config(qdrant_parallel_uploads);
+    // Read worker concurrency and job-level settings from env at startup
+    let settings = config::DatasetWorkerSettings::from_env();
+    job::init_job_config(&settings);
+
+    // The embedding cache is an optimization; run without it if Valkey is unreachable
+    if settings.embedding_cache_enabled {
+        let valkey_config = ValkeyConfig::from_env()?;
+        if let Err(e) =
+            embedding_cache::init(&valkey_config, settings.embedding_cache_ttl_secs).await
+        {
+            warn!(error = %format!("{e:#}"), "Embedding cache unavailable, continuing without it");
+        }
+    }
 
     // Create worker context with Qdrant client cache
     let context = WorkerContext {
         s3_client,
         nats_client: nats_client.clone(),
+        max_download_size_bytes,
     };
 
     // Configure and run worker
-    let max_concurrent_jobs = std::env::var("MAX_CONCURRENT_JOBS")
-        .unwrap_or_else(|_| "10".to_string())
-        .parse::<usize>()
-        .unwrap_or(10);
-
-    let health_check_port: u16 = std::env::var("HEALTH_CHECK_PORT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(8083);
-
     let config = worker::WorkerConfig {
         service_name,
         stream_name: "DATASET_TRANSFORMS".to_string(),
         consumer_config: semantic_explorer_core::nats::create_dataset_transform_consumer_config(),
-        max_concurrent_jobs,
+        max_concurrent_jobs: settings.max_concurrent_jobs,
         max_deliver: 5, // Matches consumer config
-        health_check_port,
+        health_check_port: settings.health_check_port,
         nats_config,
+        nak_backoff: worker::NakBackoffConfig::from_env(),
     };
 
     worker::run_worker(config, context, job::process_dataset_transform_job).await
@@ -0,0 +1,108 @@
+//! Weighted multi-field embeddings for structured records.
+//!
+//! A dataset transform with `field_weights` in its `job_config` has the
+//! scanner attach the weighted fields (title, chunk text, tags, ...) to each
+//! batch item. The worker embeds every field on its own and combines the
+//! vectors into one point: a weighted average, with weights renormalized over
+//! the fields the item actually has, scaled back to unit length. Unlike
+//! embedding the concatenated fields, this lets a short, heavily weighted
+//! field such as a title pull the vector as far as it is configured to.
+
+use serde::{Deserialize, Serialize};
+
+/// One field of a batch item and its weight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct WeightedField {
+    pub(crate) text: String,
+    pub(crate) weight: f32,
+}
+
+/// Weighted average of `vectors`, normalized to unit length. Non-positive
+/// weights are ignored; `None` if nothing is left to combine or the vectors
+/// disagree on dimensions.
+pub(crate) fn combine(vectors: &[Vec<f32>], weights: &[f32]) -> Option<Vec<f32>> {
+    let dimensions = vectors.first()?.len();
+    if vectors.len() != weights.len() || vectors.iter().any(|v| v.len() != dimensions) {
+        return None;
+    }
+
+    let total: f32 = weights.iter().filter(|w| **w > 0.0).sum();
+    if total <= 0.0 || !total.is_finite() {
+        return None;
+    }
+
+    let mut combined = vec![0.0f32; dimensions];
+    for (vector, &weight) in vectors.iter().zip(weights) {
+        if weight <= 0.0 {
+            continue;
+        }
+        let share = weight / total;
+        for (c, v) in combined.iter_mut().zip(vector) {
+            *c += share * v;
+        }
+    }
+
+    let norm = combined.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        combined.iter_mut().for_each(|c| *c /= norm);
+    }
+    Some(combined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        dot / (norm(a) * norm(b))
+    }
+
+    /// Toy bag-of-words embedder over a fixed vocabulary
+    fn embed(text: &str) -> Vec<f32> {
+        const VOCAB: [&str; 6] = ["rust", "async", "runtime", "guide", "tokio", "threads"];
+        let mut v: Vec<f32> = VOCAB
+            .iter()
+            .map(|term| text.split_whitespace().filter(|w| w == term).count() as f32)
+            .collect();
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v.iter_mut().for_each(|x| *x /= norm);
+        v
+    }
+
+    #[test]
+    fn test_combined_vector_follows_weights_unlike_concatenation() {
+        let title = "rust guide";
+        let body = "tokio async runtime threads async runtime threads tokio";
+        let (title_vec, body_vec) = (embed(title), embed(body));
+
+        // A long body drowns out the title when the fields are concatenated
+        let naive = embed(&format!("{title} {body}"));
+        assert!(cosine(&naive, &body_vec) > cosine(&naive, &title_vec));
+
+        // Weighting the title 3:1 makes it dominate the combined vector
+        let title_heavy = combine(&[title_vec.clone(), body_vec.clone()], &[3.0, 1.0]).unwrap();
+        assert!(cosine(&title_heavy, &title_vec) > cosine(&title_heavy, &body_vec));
+        assert!(cosine(&title_heavy, &title_vec) > cosine(&naive, &title_vec));
+
+        // Swapping the weights swaps which field dominates
+        let body_heavy = combine(&[title_vec.clone(), body_vec.clone()], &[1.0, 3.0]).unwrap();
+        assert!(cosine(&body_heavy, &body_vec) > cosine(&body_heavy, &title_vec));
+
+        // Orthogonal unit inputs: components follow the renormalized weights
+        let combined = combine(&[vec![1.0, 0.0], vec![0.0, 1.0]], &[3.0, 1.0]).unwrap();
+        assert!((combined[0] / combined[1] - 3.0).abs() < 1e-5);
+        assert!((combined.iter().map(|c| c * c).sum::<f32>() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_combine_rejects_unusable_input() {
+        assert_eq!(combine(&[], &[]), None);
+        assert_eq!(combine(&[vec![1.0]], &[0.0]), None);
+        assert_eq!(combine(&[vec![1.0], vec![1.0, 2.0]], &[1.0, 1.0]), None);
+        assert_eq!(combine(&[vec![1.0]], &[1.0, 1.0]), None);
+        // A single field is just normalized
+        assert_eq!(combine(&[vec![3.0, 4.0]], &[2.0]), Some(vec![0.6, 0.8]));
+    }
+}
@@ -2,14 +2,24 @@
 //!
 //! This module provides:
 //! - Thread-safe cache for Qdrant clients, keyed by URL
-//! - Collection existence cache to avoid redundant collection_info() calls
+//! - Collection existence cache to avoid redundant collection_info() calls,
+//!   remembering whether each collection has the BM25 sparse vector and the
+//!   size of its dense vectors
+//! - Upsert requests acknowledged per the job's write consistency
 //!
 //! Clients and collection state are reused across jobs to avoid overhead.
 
 use once_cell::sync::Lazy;
 use qdrant_client::Qdrant;
-use qdrant_client::qdrant::{CreateCollectionBuilder, Distance, VectorParams};
-use std::collections::{HashMap, HashSet};
+use qdrant_client::qdrant::{
+    CreateCollectionBuilder, Distance, GetCollectionInfoResponse, Modifier, PointStruct,
+    SparseVectorParamsBuilder, SparseVectorsConfigBuilder, UpsertPointsBuilder, VectorParams,
+    WriteOrdering, WriteOrderingType, vectors_config::Config as VectorsConfigKind,
+};
+use semantic_explorer_core::embedder::check_dimensions;
+use semantic_explorer_core::models::{DistanceMetric, QdrantWriteConsistency, WriteOrderingLevel};
+use semantic_explorer_core::sparse::SPARSE_VECTOR_NAME;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -19,9 +29,37 @@ use tracing::{debug, info, warn};
 static QDRANT_CLIENTS: Lazy<RwLock<HashMap<String, Arc<Qdrant>>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+/// What is known about a collection verified to exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KnownCollection {
+    has_sparse: bool,
+    /// Size of the dense vectors, None when Qdrant didn't report one
+    vector_size: Option<u64>,
+}
+
+impl KnownCollection {
+    fn from_info(info: &GetCollectionInfoResponse) -> Self {
+        Self {
+            has_sparse: has_sparse_vector(info),
+            vector_size: dense_vector_size(info),
+        }
+    }
+
+    /// Whether the collection has the sparse vector, or a
+    /// [`DimensionMismatch`](semantic_explorer_core::embedder::DimensionMismatch)
+    /// when its vectors are not `vector_size` long
+    fn accept(self, collection_name: &str, vector_size: u64) -> anyhow::Result<bool> {
+        if let Some(size) = self.vector_size {
+            check_dimensions(collection_name, size, vector_size)?;
+        }
+        Ok(self.has_sparse)
+    }
+}
+
 /// Global cache of known collection names (verified to exist)
 /// Key format: "{url}|{collection_name}"
-static KNOWN_COLLECTIONS: Lazy<RwLock<HashSet<String>>> = Lazy::new(|| RwLock::new(HashSet::new()));
+static KNOWN_COLLECTIONS: Lazy<RwLock<HashMap<String, KnownCollection>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
 
 fn collection_cache_key(url: &str, collection_name: &str) -> String {
     format!("{}|{}", url, collection_name)
@@ -87,43 +125,102 @@ pub async fn get_or_create_client(
     Ok(client)
 }
 
+fn qdrant_distance(metric: DistanceMetric) -> Distance {
+    match metric {
+        DistanceMetric::Cosine => Distance::Cosine,
+        DistanceMetric::Dot => Distance::Dot,
+        DistanceMetric::Euclidean => Distance::Euclid,
+    }
+}
+
+/// Whether a collection was created with the BM25 sparse vector. Collections
+/// created before hybrid search only hold dense vectors.
+fn has_sparse_vector(info: &GetCollectionInfoResponse) -> bool {
+    info.result
+        .as_ref()
+        .and_then(|info| info.config.as_ref())
+        .and_then(|config| config.params.as_ref())
+        .and_then(|params| params.sparse_vectors_config.as_ref())
+        .is_some_and(|sparse| sparse.map.contains_key(SPARSE_VECTOR_NAME))
+}
+
+/// Size of the collection's dense vectors, if it has a single unnamed one
+fn dense_vector_size(info: &GetCollectionInfoResponse) -> Option<u64> {
+    let vectors = info
+        .result
+        .as_ref()
+        .and_then(|info| info.config.as_ref())
+        .and_then(|config| config.params.as_ref())
+        .and_then(|params| params.vectors_config.as_ref())
+        .and_then(|vectors| vectors.config.as_ref());
+    match vectors {
+        Some(VectorsConfigKind::Params(params)) => Some(params.size),
+        _ => None,
+    }
+}
+
+/// Whether a collection exists, answered from the cache when it is known.
+pub async fn collection_exists(
+    client: &Qdrant,
+    url: &str,
+    collection_name: &str,
+) -> anyhow::Result<bool> {
+    let cache_key = collection_cache_key(url, collection_name);
+    if KNOWN_COLLECTIONS.read().await.contains_key(&cache_key) {
+        return Ok(true);
+    }
+    Ok(client.collection_exists(collection_name).await?)
+}
+
 /// Ensure a collection exists, creating it if necessary.
 /// Uses a local cache to avoid redundant collection_info() API calls.
 /// Retries transient failures with exponential backoff to handle Qdrant
 /// cluster consensus delays (common with 3+ replica clusters).
 ///
-/// Returns Ok if collection exists (or was created), Err on failure.
+/// New collections get the BM25 sparse vector (with Qdrant's IDF modifier)
+/// next to the dense one.
+///
+/// An existing collection whose dense vectors are not `vector_size` long is
+/// rejected with a
+/// [`DimensionMismatch`](semantic_explorer_core::embedder::DimensionMismatch)
+/// error, before any points are written to it.
+///
+/// Returns whether the collection has the sparse vector, Err on failure.
 pub async fn ensure_collection_exists(
     client: &Arc<Qdrant>,
     url: &str,
     collection_name: &str,
     vector_size: u64,
-) -> anyhow::Result<()> {
+    distance_metric: DistanceMetric,
+) -> anyhow::Result<bool> {
     let cache_key = collection_cache_key(url, collection_name);
 
     // Fast path: check cache first (read lock only)
     {
         let known = KNOWN_COLLECTIONS.read().await;
-        if known.contains(&cache_key) {
+        if let Some(collection) = known.get(&cache_key) {
             debug!(
                 collection = collection_name,
                 "Collection known to exist (cached)"
             );
-            return Ok(());
+            return collection.accept(collection_name, vector_size);
         }
     }
 
     // Not in cache, check with Qdrant (with retry for transient failures)
     match client.collection_info(collection_name).await {
-        Ok(_) => {
+        Ok(info) => {
             // Collection exists, add to cache
+            let collection = KnownCollection::from_info(&info);
             let mut known = KNOWN_COLLECTIONS.write().await;
-            known.insert(cache_key);
+            known.insert(cache_key, collection);
             debug!(
                 collection = collection_name,
+                has_sparse = collection.has_sparse,
+                vector_size = ?collection.vector_size,
                 "Collection exists, added to cache"
             );
-            return Ok(());
+            return collection.accept(collection_name, vector_size);
         }
         Err(e) => {
             // Only proceed to creation if the error indicates the collection doesn't exist.
@@ -144,7 +241,7 @@ pub async fn ensure_collection_exists(
     info!(
         collection = collection_name,
         vector_size = vector_size,
-        distance = "Cosine",
+        distance = distance_metric.as_str(),
         "Creating collection"
     );
 
@@ -159,10 +256,17 @@ pub async fn ensure_collection_exists(
         let create_collection = CreateCollectionBuilder::new(collection_name)
             .vectors_config(VectorParams {
                 size: vector_size,
-                distance: Distance::Cosine.into(),
+                distance: qdrant_distance(distance_metric).into(),
                 on_disk: Some(true), // Store vectors on disk for large collections
                 ..Default::default()
             })
+            .sparse_vectors_config(
+                SparseVectorsConfigBuilder::default().add_named_vector_params(
+                    SPARSE_VECTOR_NAME,
+                    // Qdrant applies IDF at query time; points only carry term weights
+                    SparseVectorParamsBuilder::default().modifier(Modifier::Idf),
+                ),
+            )
             .on_disk_payload(true) // Store payloads on disk to reduce memory usage
             .build();
 
@@ -174,8 +278,14 @@ pub async fn ensure_collection_exists(
                     "Collection created successfully"
                 );
                 let mut known = KNOWN_COLLECTIONS.write().await;
-                known.insert(cache_key);
-                return Ok(());
+                known.insert(
+                    cache_key,
+                    KnownCollection {
+                        has_sparse: true,
+                        vector_size: Some(vector_size),
+                    },
+                );
+                return Ok(true);
             }
             Err(e) => {
                 let error_str = e.to_string();
@@ -186,9 +296,17 @@ pub async fn ensure_collection_exists(
                         collection = collection_name,
                         "Collection already exists (created by another worker), continuing"
                     );
+                    // Check its vectors rather than assume the other worker's version
+                    let collection = match client.collection_info(collection_name).await {
+                        Ok(info) => KnownCollection::from_info(&info),
+                        Err(_) => KnownCollection {
+                            has_sparse: false,
+                            vector_size: None,
+                        },
+                    };
                     let mut known = KNOWN_COLLECTIONS.write().await;
-                    known.insert(cache_key);
-                    return Ok(());
+                    known.insert(cache_key, collection);
+                    return collection.accept(collection_name, vector_size);
                 }
 
                 // Check if this is a retryable error (GRPC errors, timeouts, consensus issues)
@@ -235,3 +353,73 @@ pub async fn ensure_collection_exists(
         last_error.unwrap_or_else(|| "unknown error".to_string())
     ))
 }
+
+/// Upsert request for `points`, waiting and ordered as `consistency` asks
+pub fn upsert_request(
+    collection_name: &str,
+    points: Vec<PointStruct>,
+    consistency: QdrantWriteConsistency,
+) -> UpsertPointsBuilder {
+    let ordering = match consistency.ordering {
+        WriteOrderingLevel::Weak => WriteOrderingType::Weak,
+        WriteOrderingLevel::Medium => WriteOrderingType::Medium,
+        WriteOrderingLevel::Strong => WriteOrderingType::Strong,
+    };
+    UpsertPointsBuilder::new(collection_name, points)
+        .wait(consistency.wait)
+        .ordering(WriteOrdering {
+            r#type: ordering as i32,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semantic_explorer_core::embedder::dimension_mismatch_cause;
+
+    #[test]
+    fn test_known_collection_rejects_other_vector_sizes() {
+        let collection = KnownCollection {
+            has_sparse: true,
+            vector_size: Some(384),
+        };
+        assert!(collection.accept("docs", 384).unwrap());
+
+        let err = collection.accept("docs", 768).unwrap_err();
+        let mismatch = dimension_mismatch_cause(&err).unwrap();
+        assert_eq!(mismatch.collection_dimensions, 384);
+        assert_eq!(mismatch.embedder_dimensions, 768);
+
+        // Without a known size there is nothing to compare against
+        let unknown = KnownCollection {
+            has_sparse: false,
+            vector_size: None,
+        };
+        assert!(!unknown.accept("docs", 768).unwrap());
+    }
+
+    fn sent(consistency: QdrantWriteConsistency) -> (Option<bool>, Option<i32>) {
+        let request = upsert_request("c", Vec::new(), consistency).build();
+        (request.wait, request.ordering.map(|o| o.r#type))
+    }
+
+    #[test]
+    fn test_upsert_request_sets_wait_and_ordering_per_job() {
+        assert_eq!(
+            sent(QdrantWriteConsistency::default()),
+            (Some(true), Some(WriteOrderingType::Weak as i32))
+        );
+        assert_eq!(
+            sent(QdrantWriteConsistency {
+                wait: false,
+                ordering: WriteOrderingLevel::Strong,
+            }),
+            (Some(false), Some(WriteOrderingType::Strong as i32))
+        );
+
+        // Fields left out keep their defaults
+        let job: serde_json::Value = serde_json::json!({});
+        let consistency: QdrantWriteConsistency = serde_json::from_value(job).unwrap();
+        assert_eq!(consistency, QdrantWriteConsistency::default());
+    }
+}
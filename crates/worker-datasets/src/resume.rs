@@ -0,0 +1,193 @@
+//! Chunk-level resumption of redelivered jobs.
+//!
+//! A worker restarted mid-batch gets its unacked jobs redelivered, and most
+//! of their chunks may already be in Qdrant. Point ids are deterministic
+//! (the API derives them from the embedded dataset, item and chunk index),
+//! and each point carries a `content_hash` of its text, payload and
+//! [`EmbeddingStamp`]. Before embedding, the job looks its point ids up and
+//! skips every chunk whose stored hash still matches, so a rerun only embeds
+//! and upserts what is missing or changed.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use qdrant_client::Qdrant;
+use qdrant_client::qdrant::point_id::PointIdOptions;
+use qdrant_client::qdrant::value::Kind;
+use qdrant_client::qdrant::{GetPointsBuilder, PointId};
+use semantic_explorer_core::embedding_stamp::EmbeddingStamp;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::job::BatchItem;
+
+/// Payload key holding the hash a point was written with
+pub(crate) const CONTENT_HASH_PAYLOAD_KEY: &str = "content_hash";
+
+/// Where already-upserted points are looked up
+pub(crate) trait StoredPoints {
+    /// Content hashes of the points among `ids` that exist, keyed by id
+    async fn content_hashes(&self, ids: &[String]) -> Result<HashMap<String, String>>;
+}
+
+pub(crate) struct QdrantPoints<'a> {
+    pub(crate) client: &'a Qdrant,
+    pub(crate) url: &'a str,
+    pub(crate) collection: &'a str,
+}
+
+impl StoredPoints for QdrantPoints<'_> {
+    async fn content_hashes(&self, ids: &[String]) -> Result<HashMap<String, String>> {
+        // The first job of a dataset runs before its collection exists
+        if !crate::qdrant_cache::collection_exists(self.client, self.url, self.collection).await? {
+            return Ok(HashMap::new());
+        }
+
+        let point_ids: Vec<PointId> = ids.iter().map(|id| PointId::from(id.clone())).collect();
+        let response = self
+            .client
+            .get_points(
+                GetPointsBuilder::new(self.collection, point_ids)
+                    .with_payload(true)
+                    .with_vectors(false),
+            )
+            .await?;
+
+        Ok(response
+            .result
+            .into_iter()
+            .filter_map(|point| {
+                let id = match point.id?.point_id_options? {
+                    PointIdOptions::Uuid(uuid) => uuid,
+                    PointIdOptions::Num(num) => num.to_string(),
+                };
+                match point.payload.get(CONTENT_HASH_PAYLOAD_KEY)?.kind.clone()? {
+                    Kind::StringValue(hash) => Some((id, hash)),
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Hash identifying what a point was embedded from and how
+pub(crate) fn content_hash(item: &BatchItem, stamp: &EmbeddingStamp) -> String {
+    let encoded = if item.fields.is_empty() {
+        serde_json::to_vec(&(stamp, &item.text, &item.payload))
+    } else {
+        serde_json::to_vec(&(stamp, &item.text, &item.payload, &item.fields))
+    }
+    .unwrap_or_default();
+    format!("{:x}", Sha256::digest(&encoded))
+}
+
+/// Which items already have an up-to-date point, given each item's content
+/// hash. Lookup errors are logged and mean nothing is skipped.
+pub(crate) async fn already_stored<S: StoredPoints>(
+    store: &S,
+    items: &[BatchItem],
+    hashes: &[String],
+) -> Vec<bool> {
+    let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+    let stored = match store.content_hashes(&ids).await {
+        Ok(stored) => stored,
+        Err(e) => {
+            warn!(error = %format!("{e:#}"), "Failed to look up existing points, embedding all chunks");
+            return vec![false; items.len()];
+        }
+    };
+    items
+        .iter()
+        .zip(hashes)
+        .map(|(item, hash)| stored.get(&item.id) == Some(hash))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semantic_explorer_core::models::EmbedderConfig;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryPoints {
+        points: Mutex<HashMap<String, String>>,
+    }
+
+    impl StoredPoints for MemoryPoints {
+        async fn content_hashes(&self, ids: &[String]) -> Result<HashMap<String, String>> {
+            let points = self.points.lock().unwrap();
+            Ok(ids
+                .iter()
+                .filter_map(|id| Some((id.clone(), points.get(id)?.clone())))
+                .collect())
+        }
+    }
+
+    impl MemoryPoints {
+        /// Run a job's resume step, "upserting" what it doesn't skip.
+        /// Returns how many points were upserted.
+        async fn run_job(&self, items: &[BatchItem], stamp: &EmbeddingStamp) -> usize {
+            let hashes: Vec<String> = items.iter().map(|i| content_hash(i, stamp)).collect();
+            let skip = already_stored(self, items, &hashes).await;
+            let mut points = self.points.lock().unwrap();
+            let mut upserted = 0;
+            for ((item, hash), skip) in items.iter().zip(hashes).zip(skip) {
+                if !skip {
+                    points.insert(item.id.clone(), hash);
+                    upserted += 1;
+                }
+            }
+            upserted
+        }
+    }
+
+    fn stamp(model: &str) -> EmbeddingStamp {
+        let config = EmbedderConfig::new(
+            "internal".to_string(),
+            "http://localhost:8090".to_string(),
+            None,
+            model.to_string(),
+            serde_json::json!({}),
+            8,
+            512,
+        );
+        EmbeddingStamp::from_embedder_config(Some(3), &config)
+    }
+
+    fn item(id: u8, text: &str) -> BatchItem {
+        BatchItem {
+            id: format!("00000000-0000-0000-0000-0000000000{id:02}"),
+            text: text.to_string(),
+            payload: serde_json::Map::new(),
+            item_type: None,
+            fields: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerun_of_same_job_upserts_nothing() {
+        let store = MemoryPoints::default();
+        let stamp = stamp("bge-small");
+        let items = vec![item(1, "alpha"), item(2, "beta"), item(3, "gamma")];
+
+        assert_eq!(store.run_job(&items, &stamp).await, 3);
+        assert_eq!(store.run_job(&items, &stamp).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_or_changed_chunks_are_redone() {
+        let store = MemoryPoints::default();
+        let stamp = stamp("bge-small");
+
+        // A previous run only got the first chunk in before the restart
+        store.run_job(&[item(1, "alpha")], &stamp).await;
+        let items = vec![item(1, "alpha"), item(2, "beta")];
+        assert_eq!(store.run_job(&items, &stamp).await, 1);
+
+        // Edited text or a different model invalidates the stored point
+        let edited = vec![item(1, "alpha, revised"), item(2, "beta")];
+        assert_eq!(store.run_job(&edited, &stamp).await, 1);
+        assert_eq!(store.run_job(&edited, &self::stamp("bge-large")).await, 2);
+    }
+}
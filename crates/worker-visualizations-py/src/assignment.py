@@ -0,0 +1,84 @@
+"""
+Online Topic Assignment
+
+Assigns points added since the last full run to that run's topics, without
+refitting UMAP and HDBSCAN (like HDBSCAN's `approximate_predict`, but against
+topic centroids in the original embedding space).
+
+Each new point goes to the topic whose centroid is nearest, if that centroid
+is within `assignment_distance_threshold`; otherwise it is an outlier. Once
+more than `refit_outlier_ratio` of the new points are outliers, the topics no
+longer describe the collection well and a full refit is suggested.
+"""
+
+from collections import Counter
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Sequence, Tuple
+
+# Topic ID of points that are not close enough to any topic, as for HDBSCAN noise
+OUTLIER = -1
+
+
+def assign_to_nearest(
+    nearest: Sequence[Tuple[int, float]], distance_threshold: float
+) -> List[int]:
+    """
+    Topic of each point, given its nearest topic and the distance to that
+    topic's centroid. Points farther than `distance_threshold` are outliers;
+    a point exactly at the threshold is assigned.
+    """
+    return [
+        topic_id if distance <= distance_threshold else OUTLIER
+        for topic_id, distance in nearest
+    ]
+
+
+@dataclass
+class AssignmentSummary:
+    """Outcome of assigning new points to existing topics."""
+
+    new_points: int
+    assigned: int
+    outliers: int
+    refit_outlier_ratio: float
+    topic_counts: Dict[int, int] = field(default_factory=dict)
+
+    @property
+    def outlier_ratio(self) -> float:
+        if self.new_points == 0:
+            return 0.0
+        return self.outliers / self.new_points
+
+    @property
+    def refit_suggested(self) -> bool:
+        return self.outlier_ratio > self.refit_outlier_ratio
+
+    def as_dict(self) -> Dict[str, Any]:
+        return {
+            "new_points": self.new_points,
+            "assigned": self.assigned,
+            "outliers": self.outliers,
+            "outlier_ratio": round(self.outlier_ratio, 4),
+            "refit_outlier_ratio": self.refit_outlier_ratio,
+            "refit_suggested": self.refit_suggested,
+            # String keys, as JSON needs them
+            "topic_counts": {
+                str(topic_id): count
+                for topic_id, count in sorted(self.topic_counts.items())
+            },
+        }
+
+
+def summarize_assignments(
+    assignments: Sequence[int], refit_outlier_ratio: float
+) -> AssignmentSummary:
+    """Count assigned points per topic and outliers"""
+    counts = Counter(assignments)
+    outliers = counts.pop(OUTLIER, 0)
+    return AssignmentSummary(
+        new_points=len(assignments),
+        assigned=len(assignments) - outliers,
+        outliers=outliers,
+        refit_outlier_ratio=refit_outlier_ratio,
+        topic_counts=dict(counts),
+    )
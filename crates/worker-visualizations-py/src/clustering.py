@@ -0,0 +1,117 @@
+"""
+k-means Clustering
+
+Fixed-K alternative to HDBSCAN for jobs that want an exact number of topics.
+Every point is assigned to one of exactly `n_clusters` clusters, labelled
+0..n_clusters-1, so there is no noise (-1) and the labels feed topic naming,
+topic storage and the plot the same way HDBSCAN's do.
+
+Plain numpy: k-means++ seeding and Lloyd iterations, with a fixed seed so a
+rerun over the same points gives the same topics.
+"""
+
+import numpy as np
+
+# Lloyd iterations per run; the UMAP layout is low-dimensional and converges
+# long before this
+MAX_ITERATIONS = 300
+
+# Independent seedings tried; the one with the lowest inertia wins
+N_INIT = 4
+
+DEFAULT_SEED = 42
+
+
+def _kmeans_plus_plus(
+    vectors: np.ndarray, n_clusters: int, rng: np.random.Generator
+) -> np.ndarray:
+    """Initial centers, each drawn with probability proportional to its
+    squared distance from the centers picked so far"""
+    centers = np.empty((n_clusters, vectors.shape[1]), dtype=np.float64)
+    centers[0] = vectors[rng.integers(len(vectors))]
+    closest = np.sum((vectors - centers[0]) ** 2, axis=1)
+    for i in range(1, n_clusters):
+        total = closest.sum()
+        if total == 0:
+            # Fewer distinct points than clusters; any point will do
+            index = rng.integers(len(vectors))
+        else:
+            index = rng.choice(len(vectors), p=closest / total)
+        centers[i] = vectors[index]
+        closest = np.minimum(closest, np.sum((vectors - centers[i]) ** 2, axis=1))
+    return centers
+
+
+def _squared_distances(vectors: np.ndarray, centers: np.ndarray) -> np.ndarray:
+    squared = (
+        np.sum(vectors**2, axis=1)[:, None]
+        + np.sum(centers**2, axis=1)[None, :]
+        - 2.0 * vectors @ centers.T
+    )
+    return np.maximum(squared, 0.0)
+
+
+def _fill_empty_clusters(
+    vectors: np.ndarray, labels: np.ndarray, distances: np.ndarray, n_clusters: int
+) -> np.ndarray:
+    """Move the points farthest from their centers into empty clusters, so
+    every cluster has at least one point"""
+    labels = labels.copy()
+    counts = np.bincount(labels, minlength=n_clusters)
+    own = distances[np.arange(len(vectors)), labels]
+    for cluster_id in np.flatnonzero(counts == 0):
+        # Only take from clusters that would not become empty themselves
+        candidates = np.flatnonzero(counts[labels] > 1)
+        index = candidates[np.argmax(own[candidates])]
+        counts[labels[index]] -= 1
+        counts[cluster_id] += 1
+        labels[index] = cluster_id
+        own[index] = 0.0
+    return labels
+
+
+def _lloyd(
+    vectors: np.ndarray, centers: np.ndarray, n_clusters: int
+) -> tuple[np.ndarray, float]:
+    labels = np.zeros(len(vectors), dtype=np.int64)
+    for iteration in range(MAX_ITERATIONS):
+        distances = _squared_distances(vectors, centers)
+        new_labels = _fill_empty_clusters(
+            vectors, distances.argmin(axis=1), distances, n_clusters
+        )
+        if iteration > 0 and np.array_equal(new_labels, labels):
+            break
+        labels = new_labels
+        for cluster_id in range(n_clusters):
+            centers[cluster_id] = vectors[labels == cluster_id].mean(axis=0)
+    inertia = float(
+        np.sum(_squared_distances(vectors, centers)[np.arange(len(vectors)), labels])
+    )
+    return labels, inertia
+
+
+def kmeans(
+    vectors: np.ndarray, n_clusters: int, seed: int = DEFAULT_SEED
+) -> np.ndarray:
+    """
+    Cluster labels (0..n_clusters-1) of `vectors`, each cluster non-empty.
+
+    Raises:
+        ValueError: If n_clusters is below 1 or above the number of points
+    """
+    n_samples = len(vectors)
+    if not 1 <= n_clusters <= n_samples:
+        raise ValueError(
+            f"n_clusters must be between 1 and the number of points ({n_samples}), "
+            f"got {n_clusters}"
+        )
+    vectors = np.asarray(vectors, dtype=np.float64)
+    rng = np.random.default_rng(seed)
+
+    best_labels, best_inertia = None, np.inf
+    for _ in range(N_INIT):
+        centers = _kmeans_plus_plus(vectors, n_clusters, rng)
+        labels, inertia = _lloyd(vectors, centers, n_clusters)
+        if inertia < best_inertia:
+            best_labels, best_inertia = labels, inertia
+    return best_labels
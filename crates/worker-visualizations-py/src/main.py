@@ -43,23 +43,25 @@ else:
 try:
     # Try relative imports (for package execution)
     from .font_initializer import init_fonts_for_offline_mode
-    from .processor import process_visualization_job
+    from .processor import assign_visualization_job, process_visualization_job
     from .models import (
         VisualizationTransformJob,
         VisualizationTransformResult,
     )
     from .storage import S3Storage
+    from .points import serialize_points
     from .llm_namer import LLMProvider
     from .observability import init_metrics
 except ImportError:
     # Fallback to absolute imports (for direct script execution)
     from font_initializer import init_fonts_for_offline_mode
-    from processor import process_visualization_job
+    from processor import assign_visualization_job, process_visualization_job
     from models import (
         VisualizationTransformJob,
         VisualizationTransformResult,
     )
     from storage import S3Storage
+    from points import serialize_points
     from llm_namer import LLMProvider
     from observability import init_metrics
 
@@ -235,6 +237,23 @@ async def initialize():
     metrics.worker_ready.set(1)
 
 
+async def assign_new_points(
+    job: VisualizationTransformJob, storage: S3Storage, progress_callback
+) -> dict:
+    """
+    Run an assign_only job: points that the last full run (whose points
+    document is `fitted_points_s3_key`) didn't include are assigned to its
+    topics. Without that document every point counts as new.
+    """
+    fitted_ids: set[str] = set()
+    if job.fitted_points_s3_key:
+        fitted = await storage.download_points(job.fitted_points_s3_key)
+        fitted_ids = set(fitted.get("ids", []))
+    return await assign_visualization_job(
+        job, fitted_ids, progress_callback=progress_callback
+    )
+
+
 async def handle_job(
     nc: NATSConnection, msg: Msg, job: VisualizationTransformJob
 ) -> None:
@@ -310,30 +329,42 @@ async def handle_job(
         # Process the visualization job
         process_start = time.time()
         logger.debug(f"Starting visualization processing for job {job.job_id}")
-        processed_result = await asyncio.wait_for(
-            process_visualization_job(
+        if s3_storage is None:
+            raise RuntimeError("S3 storage not initialized")
+        if job.mode == "assign_only":
+            job_run = assign_new_points(job, s3_storage, send_progress)
+        else:
+            job_run = process_visualization_job(
                 job, llm_provider, progress_callback=send_progress
-            ),
-            timeout=PROCESSING_TIMEOUT_SECS,
+            )
+        processed_result = await asyncio.wait_for(
+            job_run, timeout=PROCESSING_TIMEOUT_SECS
         )
         process_elapsed = time.time() - process_start
         metrics.visualization_processing_duration.observe(process_elapsed)
         logger.info(f"Visualization processing completed in {process_elapsed:.3f}s")
 
-        # Upload result to S3
-        if s3_storage is None:
-            raise RuntimeError("S3 storage not initialized")
-        s3_start = time.time()
-        logger.debug(f"Starting S3 upload for job {job.job_id}")
-        s3_key = await s3_storage.upload_visualization(
-            owner=job.owner_id,
-            transform_id=job.visualization_transform_id,
-            visualization_id=job.visualization_id,
-            html_content=processed_result["html"],
-        )
-        s3_elapsed = time.time() - s3_start
-        metrics.visualization_s3_upload_duration.observe(s3_elapsed)
-        logger.info(f"S3 upload completed in {s3_elapsed:.3f}s")
+        # Upload result to S3; assign_only runs render nothing
+        s3_key = None
+        points_s3_key = None
+        if job.mode == "full":
+            s3_start = time.time()
+            logger.debug(f"Starting S3 upload for job {job.job_id}")
+            s3_key = await s3_storage.upload_visualization(
+                owner=job.owner_id,
+                transform_id=job.visualization_transform_id,
+                visualization_id=job.visualization_id,
+                html_content=processed_result["html"],
+            )
+            points_s3_key = await s3_storage.upload_points(
+                owner=job.owner_id,
+                transform_id=job.visualization_transform_id,
+                visualization_id=job.visualization_id,
+                points_json=serialize_points(processed_result["points"]),
+            )
+            s3_elapsed = time.time() - s3_start
+            metrics.visualization_s3_upload_duration.observe(s3_elapsed)
+            logger.info(f"S3 upload completed in {s3_elapsed:.3f}s")
 
         # Calculate processing duration
         processing_duration_ms = int((time.time() - job_start_time) * 1000)
@@ -341,6 +372,7 @@ async def handle_job(
         # Update result with success
         result.status = "success"
         result.html_s3_key = s3_key
+        result.points_s3_key = points_s3_key
         result.point_count = processed_result.get("point_count")
         result.cluster_count = processed_result.get("cluster_count")
         result.processing_duration_ms = processing_duration_ms
@@ -4,7 +4,7 @@ Data Models for Visualization Worker
 Pydantic models for NATS message deserialization and result publishing.
 """
 
-from typing import Any, Dict, Optional
+from typing import Any, Dict, Literal, Optional
 from uuid import UUID
 from pydantic import BaseModel, Field
 
@@ -30,6 +30,28 @@ class VisualizationConfig(BaseModel):
         default=5, description="HDBSCAN min_samples parameter"
     )
 
+    # Clustering method; k-means gives exactly n_clusters topics, no noise
+    clustering_method: Literal["hdbscan", "kmeans"] = Field(
+        default="hdbscan",
+        description="How the UMAP layout is clustered: hdbscan or kmeans",
+    )
+    n_clusters: int = Field(
+        default=10, description="Number of topics when clustering_method is kmeans"
+    )
+
+    # Assigning new points to existing topics (mode "assign_only")
+    assignment_distance_threshold: float = Field(
+        default=0.35,
+        ge=0,
+        description="Maximum distance (in `metric`) from a topic centroid for a new point to join that topic",
+    )
+    refit_outlier_ratio: float = Field(
+        default=0.2,
+        ge=0,
+        le=1,
+        description="Share of new points left as outliers above which a full refit is suggested",
+    )
+
     # LLM naming configuration
     llm_batch_size: int = Field(
         default=10, description="Number of clusters to process in parallel (1-100)"
@@ -169,9 +191,13 @@ class VisualizationConfig(BaseModel):
         default=None, description="Background image URL"
     )
 
-    # Topic naming (only LLM is supported)
-    # LLM naming is conditional: only applied if llm_config is provided in the job
-    # If no LLM config, numeric cluster labels are used
+    # Topic naming
+    # "llm": LLM naming if llm_config is provided in the job, numeric labels otherwise
+    # "tfidf" / "ctfidf": label each cluster with its top keywords, no LLM needed
+    topic_naming_method: Literal["llm", "tfidf", "ctfidf"] = Field(
+        default="llm",
+        description="How clusters are named: llm, tfidf or ctfidf (class-based TF-IDF)",
+    )
     topic_naming_prompt: str = Field(
         default="These are representative texts from a document cluster:\n\n{{samples}}\n\nProvide a short, concise topic name (2-4 words) that captures the main theme. Respond with ONLY the topic name, nothing else.",
         description="Custom prompt template for LLM topic naming. Use {{samples}} as placeholder for the sample texts.",
@@ -204,6 +230,13 @@ class VisualizationTransformJob(BaseModel):
     visualization_config: VisualizationConfig
     qdrant_config: QdrantConnectionConfig
     llm_config: Optional[LLMConfig] = None
+    # "full" fits UMAP and HDBSCAN and stores topic centroids; "assign_only"
+    # assigns points added since that fit to the stored topics
+    mode: Literal["full", "assign_only"] = "full"
+    # Qdrant collection holding one centroid point per topic
+    topics_collection_name: Optional[str] = None
+    # Points of the last full run, whose IDs are not new (assign_only)
+    fitted_points_s3_key: Optional[str] = None
 
     class Config:
         json_encoders = {UUID: str}
@@ -218,6 +251,7 @@ class VisualizationTransformResult(BaseModel):
     owner_id: str = Field(alias="ownerId")
     status: str  # "processing", "success", "failed"
     html_s3_key: Optional[str] = Field(default=None, alias="htmlS3Key")
+    points_s3_key: Optional[str] = Field(default=None, alias="pointsS3Key")
     point_count: Optional[int] = Field(default=None, alias="pointCount")
     cluster_count: Optional[int] = Field(default=None, alias="clusterCount")
     processing_duration_ms: Optional[int] = Field(
@@ -162,6 +162,14 @@ class Metrics:
             registry=registry,
         )
 
+        self.visualization_kmeans_duration = Histogram(
+            "visualization_kmeans_duration_seconds",
+            "Duration to run k-means clustering in seconds",
+            ["status"],
+            buckets=(0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, float("inf")),
+            registry=registry,
+        )
+
         self.visualization_plot_duration = Histogram(
             "visualization_plot_duration_seconds",
             "Duration to generate visualization plots in seconds",
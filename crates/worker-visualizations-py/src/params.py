@@ -0,0 +1,212 @@
+"""
+UMAP/Clustering Parameter Resolution
+
+Checks a job's UMAP and clustering (HDBSCAN or k-means) parameters against the number of points
+actually fetched, before any of them reach the libraries. Values that can
+never work are rejected with a message naming the parameter; values that are
+only too large for this dataset (e.g. n_neighbors >= number of points) are
+clamped. With auto-tuning on, parameters the job did not set explicitly are
+derived from the number of points instead of the fixed defaults.
+"""
+
+import math
+from dataclasses import asdict, dataclass, field
+from typing import Any, Dict, List, Optional
+
+try:
+    from .models import VisualizationConfig
+except ImportError:
+    from models import VisualizationConfig
+
+# UMAP needs at least 2 neighbors, and fewer neighbors than points
+MIN_POINTS = 3
+
+# Metrics supported by both umap-learn's and cuML's UMAP
+SUPPORTED_METRICS = {
+    "cosine",
+    "euclidean",
+    "l2",
+    "manhattan",
+    "l1",
+    "chebyshev",
+    "minkowski",
+    "canberra",
+    "correlation",
+    "hellinger",
+    "hamming",
+    "jaccard",
+}
+
+# Auto-tuning bounds
+AUTO_MIN_NEIGHBORS = 5
+AUTO_MAX_NEIGHBORS = 50
+AUTO_MIN_CLUSTER_SIZE = 5
+AUTO_MAX_MIN_SAMPLES = 10
+
+# min_samples used when a job leaves it unset
+DEFAULT_MIN_SAMPLES = 5
+
+
+class ParameterError(ValueError):
+    """A visualization parameter that can't work for any dataset."""
+
+
+@dataclass
+class EffectiveParameters:
+    """The parameters UMAP and the clustering are actually run with."""
+
+    n_samples: int
+    n_neighbors: int
+    min_dist: float
+    metric: str
+    min_cluster_size: int
+    min_samples: int
+    clustering_method: str = "hdbscan"
+    n_clusters: int = 10
+    auto_tuned: bool = False
+    adjustments: List[str] = field(default_factory=list)
+
+    def as_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+    def describe(self) -> str:
+        if self.clustering_method == "kmeans":
+            clustering = f"kmeans n_clusters={self.n_clusters}"
+        else:
+            clustering = (
+                f"min_cluster_size={self.min_cluster_size}, "
+                f"min_samples={self.min_samples}"
+            )
+        return (
+            f"n_neighbors={self.n_neighbors}, min_dist={self.min_dist}, "
+            f"metric={self.metric}, {clustering} for {self.n_samples} points"
+            + (" (auto-tuned)" if self.auto_tuned else "")
+        )
+
+
+def _validate(config: VisualizationConfig) -> None:
+    if config.n_neighbors < 2:
+        raise ParameterError(
+            f"n_neighbors must be at least 2, got {config.n_neighbors}"
+        )
+    if not 0.0 <= config.min_dist <= 1.0:
+        raise ParameterError(
+            f"min_dist must be between 0 and 1, got {config.min_dist}"
+        )
+    if config.metric not in SUPPORTED_METRICS:
+        raise ParameterError(
+            f"metric '{config.metric}' is not supported; use one of "
+            + ", ".join(sorted(SUPPORTED_METRICS))
+        )
+    if config.min_cluster_size < 2:
+        raise ParameterError(
+            f"min_cluster_size must be at least 2, got {config.min_cluster_size}"
+        )
+    if config.min_samples is not None and config.min_samples < 1:
+        raise ParameterError(
+            f"min_samples must be at least 1, got {config.min_samples}"
+        )
+    if config.clustering_method == "kmeans" and config.n_clusters < 2:
+        raise ParameterError(
+            f"n_clusters must be at least 2, got {config.n_clusters}"
+        )
+
+
+def auto_tuned_defaults(
+    n_samples: int, min_cluster_fraction: float
+) -> Dict[str, int]:
+    """
+    Parameters derived from the number of points.
+
+    n_neighbors grows with the square root of the point count, and
+    min_cluster_size is a fraction of it, so large datasets aren't split into
+    thousands of tiny clusters and small ones aren't all noise.
+    """
+    n_neighbors = min(
+        max(int(math.sqrt(n_samples)), AUTO_MIN_NEIGHBORS), AUTO_MAX_NEIGHBORS
+    )
+    min_cluster_size = max(
+        int(n_samples * min_cluster_fraction), AUTO_MIN_CLUSTER_SIZE
+    )
+    min_samples = min(min_cluster_size, AUTO_MAX_MIN_SAMPLES)
+    return {
+        "n_neighbors": n_neighbors,
+        "min_cluster_size": min_cluster_size,
+        "min_samples": min_samples,
+    }
+
+
+def resolve_parameters(
+    config: VisualizationConfig,
+    n_samples: int,
+    auto_tune: bool = False,
+    min_cluster_fraction: float = 0.005,
+) -> EffectiveParameters:
+    """
+    Effective UMAP and clustering parameters for a dataset of `n_samples`
+    points.
+
+    Raises:
+        ParameterError: If a parameter is invalid, or there are too few points
+    """
+    _validate(config)
+    if n_samples < MIN_POINTS:
+        raise ParameterError(
+            f"Need at least {MIN_POINTS} points to visualize, got {n_samples}"
+        )
+
+    n_neighbors = config.n_neighbors
+    min_cluster_size = config.min_cluster_size
+    min_samples: Optional[int] = config.min_samples
+    if auto_tune:
+        # Only parameters the job didn't set explicitly are tuned
+        explicit = config.model_fields_set
+        tuned = auto_tuned_defaults(n_samples, min_cluster_fraction)
+        if "n_neighbors" not in explicit:
+            n_neighbors = tuned["n_neighbors"]
+        if "min_cluster_size" not in explicit:
+            min_cluster_size = tuned["min_cluster_size"]
+        if "min_samples" not in explicit:
+            min_samples = tuned["min_samples"]
+    if min_samples is None:
+        min_samples = DEFAULT_MIN_SAMPLES
+
+    adjustments = []
+    if n_neighbors >= n_samples:
+        adjustments.append(
+            f"n_neighbors {n_neighbors} clamped to {n_samples - 1}: "
+            f"must be less than the number of points ({n_samples})"
+        )
+        n_neighbors = n_samples - 1
+    if min_cluster_size > n_samples:
+        adjustments.append(
+            f"min_cluster_size {min_cluster_size} clamped to {n_samples}: "
+            f"can't exceed the number of points"
+        )
+        min_cluster_size = n_samples
+    if min_samples >= n_samples:
+        adjustments.append(
+            f"min_samples {min_samples} clamped to {n_samples - 1}: "
+            f"must be less than the number of points ({n_samples})"
+        )
+        min_samples = n_samples - 1
+    n_clusters = config.n_clusters
+    if config.clustering_method == "kmeans" and n_clusters > n_samples:
+        adjustments.append(
+            f"n_clusters {n_clusters} clamped to {n_samples}: "
+            f"can't exceed the number of points"
+        )
+        n_clusters = n_samples
+
+    return EffectiveParameters(
+        n_samples=n_samples,
+        n_neighbors=n_neighbors,
+        min_dist=config.min_dist,
+        metric=config.metric,
+        min_cluster_size=min_cluster_size,
+        min_samples=min_samples,
+        clustering_method=config.clustering_method,
+        n_clusters=n_clusters,
+        auto_tuned=auto_tune,
+        adjustments=adjustments,
+    )
@@ -0,0 +1,48 @@
+"""
+Scatter Point Export
+
+The 2D UMAP layout and cluster assignment of every point, stored next to the
+rendered HTML so a client can draw its own interactive scatter without
+recomputing the projection.
+
+The document is columnar: `ids`, `x`, `y` and `cluster_ids` are parallel
+lists, and `cluster_names` maps each cluster ID (as a string, since JSON keys
+are strings) to its topic name. Noise points have cluster ID -1 and no name.
+"""
+
+import json
+from typing import Any, Dict, Mapping, Sequence
+
+# Coordinates are rounded to keep the document small; far below what a
+# scatter plot can show
+COORDINATE_DECIMALS = 5
+
+
+def build_points(
+    ids: Sequence[str],
+    coordinates: Sequence[Sequence[float]],
+    labels: Sequence[int],
+    cluster_names: Mapping[int, str],
+) -> Dict[str, Any]:
+    """Columnar points document for `ids` laid out at 2D `coordinates`"""
+    if not (len(ids) == len(coordinates) == len(labels)):
+        raise ValueError(
+            f"Point columns differ in length: {len(ids)} ids, "
+            f"{len(coordinates)} coordinates, {len(labels)} labels"
+        )
+    return {
+        "ids": [str(point_id) for point_id in ids],
+        "x": [round(float(xy[0]), COORDINATE_DECIMALS) for xy in coordinates],
+        "y": [round(float(xy[1]), COORDINATE_DECIMALS) for xy in coordinates],
+        "cluster_ids": [int(label) for label in labels],
+        "cluster_names": {
+            str(int(cluster_id)): name
+            for cluster_id, name in sorted(cluster_names.items())
+            if int(cluster_id) >= 0
+        },
+    }
+
+
+def serialize_points(points: Dict[str, Any]) -> bytes:
+    """Compact JSON encoding of a points document"""
+    return json.dumps(points, separators=(",", ":")).encode("utf-8")
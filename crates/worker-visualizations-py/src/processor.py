@@ -1,7 +1,7 @@
 """
 Visualization Processing Pipeline
 
-Handles UMAP dimensionality reduction, HDBSCAN clustering, and datamapplot
+Handles UMAP dimensionality reduction, HDBSCAN or k-means clustering, and datamapplot
 interactive visualization generation.
 """
 
@@ -14,7 +14,7 @@ from typing import Any, Dict, Optional
 
 import numpy as np
 import random
-from qdrant_client import AsyncQdrantClient
+from qdrant_client import AsyncQdrantClient, models
 from umap import UMAP
 from fast_hdbscan import HDBSCAN
 import datamapplot
@@ -22,19 +22,81 @@ import datamapplot
 # Maximum points to visualize to prevent OOM
 MAX_POINTS = int(os.environ.get("MAX_VISUALIZATION_POINTS", 100_000_000))
 
+# Derive UMAP/HDBSCAN parameters a job leaves unset from its point count
+AUTO_TUNE = os.environ.get("AUTO_TUNE", "false").lower() in ("1", "true", "yes")
+AUTO_TUNE_MIN_CLUSTER_FRACTION = float(
+    os.environ.get("AUTO_TUNE_MIN_CLUSTER_FRACTION", "0.005")
+)
+
 try:
     # Try relative imports (for package execution)
     from .models import VisualizationTransformJob, VisualizationConfig
     from .llm_namer import LLMProvider
     from .font_patcher import patch_html_fonts, verify_no_external_requests
+    from .params import EffectiveParameters, resolve_parameters
+    from .topic_naming import create_topic_namer
+    from .points import build_points
+    from .assignment import assign_to_nearest, summarize_assignments
+    from .clustering import kmeans
 except ImportError:
     # Fallback to absolute imports (for direct script execution)
     from models import VisualizationTransformJob, VisualizationConfig
     from llm_namer import LLMProvider
     from font_patcher import patch_html_fonts, verify_no_external_requests
+    from params import EffectiveParameters, resolve_parameters
+    from topic_naming import create_topic_namer
+    from points import build_points
+    from assignment import assign_to_nearest, summarize_assignments
+    from clustering import kmeans
 
 logger = logging.getLogger(__name__)
 
+
+def _unit_rows(vectors: np.ndarray) -> np.ndarray:
+    """Rows scaled to unit length; zero rows are left as they are"""
+    norms = np.linalg.norm(vectors, axis=1, keepdims=True)
+    return vectors / np.where(norms == 0, 1, norms)
+
+
+def _topic_centroids(
+    vectors: np.ndarray, labels: np.ndarray, metric: str
+) -> Dict[int, np.ndarray]:
+    """
+    Centroid of each cluster in the original embedding space. For cosine the
+    vectors are normalized first and the centroid is normalized again, so it
+    points in the cluster's mean direction. Noise (-1) has no centroid.
+    """
+    if metric == "cosine":
+        vectors = _unit_rows(vectors)
+    centroids = {}
+    for cluster_id in sorted({int(label) for label in labels if label >= 0}):
+        centroid = vectors[labels == cluster_id].mean(axis=0)
+        if metric == "cosine":
+            centroid = _unit_rows(centroid[None, :])[0]
+        centroids[cluster_id] = centroid
+    return centroids
+
+
+def _nearest_topics(
+    vectors: np.ndarray, centroids: np.ndarray, metric: str
+) -> tuple[np.ndarray, np.ndarray]:
+    """
+    Index of each vector's nearest centroid and the distance to it: cosine
+    distance for the cosine metric, Euclidean otherwise.
+    """
+    if metric == "cosine":
+        distances = 1.0 - _unit_rows(vectors) @ _unit_rows(centroids).T
+    else:
+        # |a - b|^2 = |a|^2 + |b|^2 - 2ab, without an n x k x dim array
+        squared = (
+            np.sum(vectors**2, axis=1)[:, None]
+            + np.sum(centroids**2, axis=1)[None, :]
+            - 2.0 * vectors @ centroids.T
+        )
+        distances = np.sqrt(np.maximum(squared, 0.0))
+    nearest = distances.argmin(axis=1)
+    return nearest, distances[np.arange(len(vectors)), nearest]
+
 # Datamapplot cache file names
 DATAMAPPLOT_FONTS_CACHE = "datamapplot_fonts_encoded.json"
 DATAMAPPLOT_JS_CACHE = "datamapplot_js_encoded.json"
@@ -207,6 +269,7 @@ class VisualizationProcessor:
         Returns:
             Dictionary with keys:
             - html: Generated interactive HTML
+            - points: 2D coordinates and cluster of each point (see points.py)
             - point_count: Number of points
             - cluster_count: Number of clusters
             - stats: Processing statistics
@@ -226,7 +289,7 @@ class VisualizationProcessor:
         )
         fetch_start = time.time()
         try:
-            vectors, _ids, texts = await self._fetch_vectors_from_qdrant(
+            vectors, ids, texts = await self._fetch_vectors_from_qdrant(
                 job.qdrant_collection_name, job.owner_id
             )
             fetch_duration = time.time() - fetch_start
@@ -259,6 +322,17 @@ class VisualizationProcessor:
 
         logger.info(f"Fetched {len(vectors)} vectors")
 
+        # Check the parameters against the dataset before handing them to UMAP
+        params = resolve_parameters(
+            job.visualization_config,
+            len(vectors),
+            auto_tune=AUTO_TUNE,
+            min_cluster_fraction=AUTO_TUNE_MIN_CLUSTER_FRACTION,
+        )
+        for adjustment in params.adjustments:
+            logger.warning(f"Visualization parameter adjusted: {adjustment}")
+        logger.info(f"Effective visualization parameters: {params.describe()}")
+
         # Get current event loop for running CPU-bound tasks
         loop = asyncio.get_running_loop()
 
@@ -266,15 +340,15 @@ class VisualizationProcessor:
         if progress_callback:
             await progress_callback("applying_umap", 25)
         logger.info(
-            f"Applying UMAP: n_neighbors={job.visualization_config.n_neighbors}, "
-            f"min_dist={job.visualization_config.min_dist}, "
-            f"metric={job.visualization_config.metric}"
+            f"Applying UMAP: n_neighbors={params.n_neighbors}, "
+            f"min_dist={params.min_dist}, "
+            f"metric={params.metric}"
         )
         # Run UMAP in executor to avoid blocking the event loop
         umap_start = time.time()
         try:
             umap_vectors = await loop.run_in_executor(
-                None, self._run_umap, vectors, job
+                None, self._run_umap, vectors, params
             )
             umap_duration = time.time() - umap_start
             try:
@@ -303,40 +377,47 @@ class VisualizationProcessor:
         if progress_callback:
             await progress_callback("applying_umap", 50)
 
-        # Apply HDBSCAN clustering (50-70%)
+        # Cluster the UMAP layout (50-70%)
         if progress_callback:
             await progress_callback("clustering", 55)
-        logger.info(
-            f"Applying HDBSCAN: min_cluster_size={job.visualization_config.min_cluster_size}, "
-            f"min_samples={job.visualization_config.min_samples}"
-        )
-        # Run HDBSCAN in executor
-        hdbscan_start = time.time()
+        if params.clustering_method == "kmeans":
+            logger.info(f"Applying k-means: n_clusters={params.n_clusters}")
+            run_clustering = self._run_kmeans
+            duration_metric = "visualization_kmeans_duration"
+        else:
+            logger.info(
+                f"Applying HDBSCAN: min_cluster_size={params.min_cluster_size}, "
+                f"min_samples={params.min_samples}"
+            )
+            run_clustering = self._run_hdbscan
+            duration_metric = "visualization_hdbscan_duration"
+        # Run clustering in executor
+        clustering_start = time.time()
         try:
             labels = await loop.run_in_executor(
-                None, self._run_hdbscan, umap_vectors, job
+                None, run_clustering, umap_vectors, params
             )
-            hdbscan_duration = time.time() - hdbscan_start
+            clustering_duration = time.time() - clustering_start
             try:
                 from .observability import get_metrics
 
                 metrics = get_metrics()
                 if metrics:
-                    metrics.visualization_hdbscan_duration.labels(
+                    getattr(metrics, duration_metric).labels(
                         status="success"
-                    ).observe(hdbscan_duration)
+                    ).observe(clustering_duration)
             except Exception:
                 pass
         except Exception as e:
-            hdbscan_duration = time.time() - hdbscan_start
+            clustering_duration = time.time() - clustering_start
             try:
                 from .observability import get_metrics
 
                 metrics = get_metrics()
                 if metrics:
-                    metrics.visualization_hdbscan_duration.labels(
+                    getattr(metrics, duration_metric).labels(
                         status="error"
-                    ).observe(hdbscan_duration)
+                    ).observe(clustering_duration)
             except Exception:
                 pass
             raise e
@@ -352,6 +433,22 @@ class VisualizationProcessor:
         if progress_callback:
             await progress_callback("naming_clusters", 85)
 
+        # Keep the topic centroids for later assign_only jobs. Failing to
+        # store them doesn't fail the visualization itself.
+        topics_stored = False
+        if job.topics_collection_name:
+            try:
+                await self._store_topics(
+                    job, vectors, labels, cluster_labels, params.metric
+                )
+                topics_stored = True
+            except Exception as e:
+                logger.error(
+                    f"Failed to store topics in {job.topics_collection_name}: "
+                    f"{type(e).__name__}: {e}",
+                    exc_info=True,
+                )
+
         # Generate interactive visualization (85-100%)
         if progress_callback:
             await progress_callback("generating_html", 88)
@@ -359,6 +456,9 @@ class VisualizationProcessor:
         # datamapplot can be CPU intensive too, run in executor
         plot_start = time.time()
         try:
+            # The 2D layout and clusters of each point, for clients that
+            # draw their own scatter
+            points = build_points(ids, umap_vectors, labels, cluster_labels)
             html_content = await loop.run_in_executor(
                 None,
                 self._run_generate_visualization,
@@ -399,13 +499,18 @@ class VisualizationProcessor:
         unique_clusters = len(set(labels[labels >= 0]))  # Exclude noise points (-1)
         result = {
             "html": html_content,
+            "points": points,
             "point_count": len(vectors),
             "cluster_count": unique_clusters,
             "stats": {
                 "unique_clusters": unique_clusters,
                 "noise_points": int(np.sum(labels == -1)),
-                "umap_n_neighbors": job.visualization_config.n_neighbors,
-                "hdbscan_min_cluster_size": job.visualization_config.min_cluster_size,
+                "umap_n_neighbors": params.n_neighbors,
+                "hdbscan_min_cluster_size": params.min_cluster_size,
+                "clustering_method": params.clustering_method,
+                "topics_stored": topics_stored,
+                # Everything needed to reproduce the layout and clustering
+                "parameters": params.as_dict(),
             },
         }
 
@@ -415,13 +520,165 @@ class VisualizationProcessor:
         )
         return result
 
-    def _run_umap(self, vectors, job):
+    async def assign_job(
+        self,
+        job: VisualizationTransformJob,
+        fitted_ids: set[str],
+        progress_callback=None,
+    ) -> Dict[str, Any]:
+        """
+        Assign points that were not part of the last full run to its stored
+        topics, without refitting.
+
+        Args:
+            job: Visualization transform job with mode "assign_only"
+            fitted_ids: IDs of the points the topics were fitted on
+            progress_callback: Optional async callback(stage: str, progress: int)
+
+        Returns:
+            Dictionary with point_count (new points), cluster_count (topics)
+            and stats describing the assignment
+
+        Raises:
+            ValueError: If no topics were stored by a full run
+        """
+        config = job.visualization_config
+        if not job.topics_collection_name:
+            raise ValueError("assign_only needs the topics collection of a full run")
+
+        if progress_callback:
+            await progress_callback("loading_topics", 5)
+        topic_ids, centroids = await self._load_topics(job.topics_collection_name)
+        if not topic_ids:
+            raise ValueError(
+                f"No topics stored in {job.topics_collection_name}; run a full visualization first"
+            )
+
+        if progress_callback:
+            await progress_callback("fetching_vectors", 10)
+        vectors, ids, _texts = await self._fetch_vectors_from_qdrant(
+            job.qdrant_collection_name, job.owner_id
+        )
+        new_rows = [row for row, point_id in enumerate(ids) if point_id not in fitted_ids]
+
+        if progress_callback:
+            await progress_callback("assigning_topics", 60)
+        nearest = []
+        if new_rows:
+            indices, distances = _nearest_topics(
+                vectors[new_rows], centroids, config.metric
+            )
+            nearest = [
+                (topic_ids[int(index)], float(distance))
+                for index, distance in zip(indices, distances)
+            ]
+        assignments = assign_to_nearest(nearest, config.assignment_distance_threshold)
+        summary = summarize_assignments(assignments, config.refit_outlier_ratio)
+
+        logger.info(
+            f"Assigned {summary.assigned} of {summary.new_points} new points to "
+            f"{len(topic_ids)} topics; {summary.outliers} outliers"
+        )
+        if summary.refit_suggested:
+            logger.warning(
+                f"{summary.outlier_ratio:.0%} of new points are outliers (above "
+                f"{config.refit_outlier_ratio:.0%}); a full refit is suggested for "
+                f"transform {job.visualization_transform_id}"
+            )
+        if progress_callback:
+            await progress_callback("assigning_topics", 100)
+
+        return {
+            "point_count": summary.new_points,
+            "cluster_count": len(topic_ids),
+            "stats": {
+                "mode": "assign_only",
+                "distance_threshold": config.assignment_distance_threshold,
+                **summary.as_dict(),
+            },
+        }
+
+    async def _store_topics(
+        self,
+        job: VisualizationTransformJob,
+        vectors: np.ndarray,
+        labels: np.ndarray,
+        cluster_labels: Dict[int, str],
+        metric: str,
+    ) -> None:
+        """Replace the topics collection with one centroid point per cluster."""
+        collection_name = job.topics_collection_name
+        centroids = _topic_centroids(vectors, labels, metric)
+
+        if await self.qdrant.collection_exists(collection_name):
+            await self.qdrant.delete_collection(collection_name)
+        if not centroids:
+            return
+
+        distance = (
+            models.Distance.COSINE if metric == "cosine" else models.Distance.EUCLID
+        )
+        await self.qdrant.create_collection(
+            collection_name=collection_name,
+            vectors_config=models.VectorParams(
+                size=int(vectors.shape[1]), distance=distance
+            ),
+        )
+        await self.qdrant.upsert(
+            collection_name=collection_name,
+            points=[
+                models.PointStruct(
+                    id=cluster_id,
+                    vector=centroid.tolist(),
+                    payload={
+                        "cluster_id": cluster_id,
+                        "name": cluster_labels.get(cluster_id, f"Cluster {cluster_id}"),
+                        "size": int(np.sum(labels == cluster_id)),
+                        "visualization_id": job.visualization_id,
+                    },
+                )
+                for cluster_id, centroid in centroids.items()
+            ],
+            wait=True,
+        )
+        logger.info(f"Stored {len(centroids)} topic centroids in {collection_name}")
+
+    async def _load_topics(
+        self, collection_name: str
+    ) -> tuple[list[int], np.ndarray]:
+        """Topic IDs and centroids (one row per topic) of a topics collection"""
+        if not await self.qdrant.collection_exists(collection_name):
+            return [], np.empty((0, 0), dtype=np.float32)
+
+        topic_ids = []
+        centroids = []
+        offset = None
+        while True:
+            points, offset = await self.qdrant.scroll(
+                collection_name=collection_name,
+                limit=1000,
+                offset=offset,
+                with_vectors=True,
+                with_payload=True,
+            )
+            for point in points:
+                topic_ids.append(int(point.payload["cluster_id"]))
+                centroids.append(point.vector)
+            if offset is None:
+                break
+        return topic_ids, np.array(centroids, dtype=np.float32)
+
+    def _run_umap(self, vectors, params):
         """Synchronous wrapper for UMAP."""
-        return self._apply_umap_sync(vectors, job)
+        return self._apply_umap_sync(vectors, params)
 
-    def _run_hdbscan(self, vectors, job):
+    def _run_hdbscan(self, vectors, params):
         """Synchronous wrapper for HDBSCAN."""
-        return self._apply_hdbscan_sync(vectors, job)
+        return self._apply_hdbscan_sync(vectors, params)
+
+    def _run_kmeans(self, vectors, params):
+        """Synchronous wrapper for k-means."""
+        return self._apply_kmeans_sync(vectors, params)
 
     def _run_generate_visualization(
         self, vectors, labels, cluster_labels, texts, config
@@ -577,7 +834,7 @@ class VisualizationProcessor:
         return hover_text
 
     def _apply_umap_sync(
-        self, vectors: np.ndarray, job: VisualizationTransformJob
+        self, vectors: np.ndarray, params: EffectiveParameters
     ) -> np.ndarray:
         """Sync version of apply_umap for executor."""
 
@@ -585,10 +842,10 @@ class VisualizationProcessor:
         try:
             logger.debug(f"Initializing UMAP with {vectors.shape[0]} vectors")
             umap = UMAP(
-                n_neighbors=job.visualization_config.n_neighbors,
+                n_neighbors=params.n_neighbors,
                 n_components=2,
-                min_dist=job.visualization_config.min_dist,
-                metric=job.visualization_config.metric,
+                min_dist=params.min_dist,
+                metric=params.metric,
                 random_state=42,  # For reproducibility
             )
 
@@ -611,14 +868,14 @@ class VisualizationProcessor:
             raise
 
     def _apply_hdbscan_sync(
-        self, vectors: np.ndarray, job: VisualizationTransformJob
+        self, vectors: np.ndarray, params: EffectiveParameters
     ) -> np.ndarray:
         """
         Apply HDBSCAN clustering (Synchronous).
 
         Args:
             vectors: Input vectors (typically UMAP output)
-            job: Visualization job with HDBSCAN config
+            params: Effective HDBSCAN parameters
 
         Returns:
             Cluster labels array
@@ -627,8 +884,8 @@ class VisualizationProcessor:
         try:
             logger.debug(f"Initializing HDBSCAN with {vectors.shape[0]} vectors")
             clusterer = HDBSCAN(
-                min_cluster_size=job.visualization_config.min_cluster_size,
-                min_samples=job.visualization_config.min_samples or 5,
+                min_cluster_size=params.min_cluster_size,
+                min_samples=params.min_samples,
             )
 
             logger.debug("Running HDBSCAN fit_predict...")
@@ -651,6 +908,36 @@ class VisualizationProcessor:
             )
             raise
 
+    def _apply_kmeans_sync(
+        self, vectors: np.ndarray, params: EffectiveParameters
+    ) -> np.ndarray:
+        """
+        Apply k-means clustering (Synchronous).
+
+        Args:
+            vectors: Input vectors (typically UMAP output)
+            params: Effective parameters, with n_clusters
+
+        Returns:
+            Cluster labels array with exactly n_clusters clusters and no noise
+        """
+        kmeans_start = time.time()
+        try:
+            labels = kmeans(vectors, params.n_clusters)
+            kmeans_elapsed = time.time() - kmeans_start
+            logger.info(
+                f"k-means complete in {kmeans_elapsed:.3f}s: {params.n_clusters} clusters"
+            )
+            return labels
+
+        except Exception as e:
+            kmeans_elapsed = time.time() - kmeans_start
+            logger.error(
+                f"k-means clustering failed in {kmeans_elapsed:.3f}s: {type(e).__name__}: {e}",
+                exc_info=True,
+            )
+            raise
+
     async def _generate_cluster_labels(
         self,
         labels: np.ndarray,
@@ -661,8 +948,9 @@ class VisualizationProcessor:
         """
         Generate human-readable labels for each cluster.
 
-        If LLM config is provided and llm_provider available, use LLM naming.
-        Otherwise, use simple numeric labels.
+        With topic_naming_method "tfidf" or "ctfidf", clusters are named by
+        their top keywords. Otherwise, if LLM config is provided and
+        llm_provider available, use LLM naming, else simple numeric labels.
 
         Note: Cluster -1 (noise) is intentionally excluded from labeling.
 
@@ -686,6 +974,20 @@ class VisualizationProcessor:
                 f"Generating labels for {len(unique_clusters)} clusters (excluding noise cluster -1)"
             )
 
+            naming_method = job.visualization_config.topic_naming_method
+            if naming_method != "llm":
+                namer = create_topic_namer(naming_method).fit(texts, labels)
+                for cluster_id in unique_clusters:
+                    cluster_labels[cluster_id] = namer.generate_name_for_cluster(
+                        int(cluster_id)
+                    )
+                label_elapsed = time.time() - label_start
+                logger.info(
+                    f"Generated {naming_method} labels for {len(cluster_labels)} clusters "
+                    f"in {label_elapsed:.3f}s"
+                )
+                return cluster_labels
+
             # Check if we should use LLM naming
             # For "internal" provider, API key is not required (uses internal inference API)
             is_internal_provider = (
@@ -1033,3 +1335,24 @@ async def process_visualization_job(
     """
     processor = VisualizationProcessor(job.qdrant_config.url)
     return await processor.process_job(job, llm_provider, progress_callback)
+
+
+async def assign_visualization_job(
+    job: VisualizationTransformJob,
+    fitted_ids: set[str],
+    progress_callback=None,
+) -> Dict[str, Any]:
+    """
+    Assign new points of an assign_only job to the topics of the last full
+    run. Called from main.py instead of process_visualization_job.
+
+    Args:
+        job: Visualization transform job from NATS
+        fitted_ids: IDs of the points the last full run was fitted on
+        progress_callback: Optional async callback(stage: str, progress: int) for progress updates
+
+    Returns:
+        Result dictionary with point_count, cluster_count, stats
+    """
+    processor = VisualizationProcessor(job.qdrant_config.url)
+    return await processor.assign_job(job, fitted_ids, progress_callback)
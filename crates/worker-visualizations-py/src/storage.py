@@ -4,6 +4,7 @@ S3 Storage Module
 Handles uploading visualization results to S3 with owner/transform/timestamp tracking.
 """
 
+import json
 import logging
 import os
 import time
@@ -127,6 +128,94 @@ class S3Storage:
             )
             raise
 
+    async def upload_points(
+        self,
+        owner: str,
+        transform_id: int,
+        visualization_id: int,
+        points_json: bytes,
+    ) -> str:
+        """
+        Upload the scatter points document next to the visualization HTML.
+
+        Args:
+            owner: Owner/username
+            transform_id: Visualization transform ID
+            visualization_id: Visualization ID (for audit trail)
+            points_json: Serialized points document
+
+        Returns:
+            Full S3 key where the points were stored
+
+        Raises:
+            Exception: If upload fails
+        """
+        upload_start = time.time()
+        try:
+            timestamp_str = datetime.now(timezone.utc).strftime("%Y-%m-%dT%H:%M:%SZ")
+            s3_key = f"visualizations/{transform_id}/points-{timestamp_str}.json"
+
+            self.s3_client.put_object(
+                Bucket=self.bucket_name,
+                Key=s3_key,
+                Body=points_json,
+                ContentType="application/json",
+                Metadata={
+                    "owner": owner,
+                    "transform-id": str(transform_id),
+                    "visualization-id": str(visualization_id),
+                    "timestamp": timestamp_str,
+                },
+            )
+            upload_elapsed = time.time() - upload_start
+
+            logger.info(
+                f"Successfully uploaded points to s3://{self.bucket_name}/{s3_key} in {upload_elapsed:.3f}s "
+                f"(size: {len(points_json)} bytes)"
+            )
+            return s3_key
+
+        except Exception as e:
+            upload_elapsed = time.time() - upload_start
+            logger.error(
+                f"Failed to upload visualization points to S3 in {upload_elapsed:.3f}s: "
+                f"{type(e).__name__}: {e}",
+                exc_info=True,
+            )
+            raise
+
+    async def download_points(self, s3_key: str) -> dict:
+        """
+        Download a points document stored by upload_points.
+
+        Args:
+            s3_key: Full S3 key of the points document
+
+        Returns:
+            The parsed points document
+
+        Raises:
+            Exception: If download or parsing fails
+        """
+        download_start = time.time()
+        try:
+            response = self.s3_client.get_object(Bucket=self.bucket_name, Key=s3_key)
+            points = json.loads(response["Body"].read())
+            download_elapsed = time.time() - download_start
+            logger.info(
+                f"Downloaded points from s3://{self.bucket_name}/{s3_key} in {download_elapsed:.3f}s"
+            )
+            return points
+
+        except Exception as e:
+            download_elapsed = time.time() - download_start
+            logger.error(
+                f"Failed to download visualization points from S3 in {download_elapsed:.3f}s: "
+                f"{type(e).__name__}: {e}",
+                exc_info=True,
+            )
+            raise
+
     async def get_visualization_url(
         self, owner: str, transform_id: int, s3_key: str, expires_in: int = 3600
     ) -> str:
@@ -0,0 +1,150 @@
+"""
+Keyword Topic Naming
+
+Names clusters from their most important terms, without an LLM.
+
+- TfidfTopicNamer: standard TF-IDF. Each text is a document; a term's weight
+  in a cluster is its count there times its inverse document frequency.
+- CTfidfTopicNamer: class-based TF-IDF, as in BERTopic. All texts of a
+  cluster are joined into one document, and a term is weighted by its
+  frequency in the cluster against its frequency across all clusters, so
+  terms common to every cluster score low even when they are frequent.
+
+Both are fitted once on all texts and their cluster labels, then asked for
+each cluster's name with `generate_name_for_cluster`.
+"""
+
+import math
+import re
+from collections import Counter
+from typing import Dict, Iterable, List, Sequence
+
+TOKEN_PATTERN = re.compile(r"[a-z][a-z0-9]+")
+
+# Terms used in a name
+DEFAULT_TOP_N = 3
+
+STOP_WORDS = frozenset(
+    """
+    a about above after again against all also am an and any are as at be
+    because been before being below between both but by can could did do does
+    doing down during each few for from further had has have having he her here
+    hers herself him himself his how i if in into is it its itself just me more
+    most my myself no nor not now of off on once only or other our ours
+    ourselves out over own same she should so some such than that the their
+    theirs them themselves then there these they this those through to too
+    under until up very was we were what when where which while who whom why
+    will with would you your yours yourself yourselves
+    """.split()
+)
+
+
+def tokenize(text: str) -> List[str]:
+    """Lowercase word tokens of `text`, without stop words"""
+    return [
+        token
+        for token in TOKEN_PATTERN.findall(text.lower())
+        if token not in STOP_WORDS
+    ]
+
+
+class TopicNamer:
+    """Base for namers that label a cluster with its top-weighted terms."""
+
+    def __init__(self, top_n: int = DEFAULT_TOP_N):
+        self.top_n = max(1, top_n)
+        self._weights: Dict[int, Dict[str, float]] = {}
+
+    def fit(self, texts: Sequence[str], labels: Iterable[int]) -> "TopicNamer":
+        """Compute term weights for every cluster. Noise (-1) is skipped."""
+        documents: Dict[int, List[List[str]]] = {}
+        for text, label in zip(texts, labels):
+            label = int(label)
+            if label < 0 or not text:
+                continue
+            documents.setdefault(label, []).append(tokenize(text))
+        self._weights = self._term_weights(documents)
+        return self
+
+    def _term_weights(
+        self, documents: Dict[int, List[List[str]]]
+    ) -> Dict[int, Dict[str, float]]:
+        raise NotImplementedError
+
+    def top_terms(self, cluster_id: int) -> List[str]:
+        """The cluster's highest-weighted terms, ties broken alphabetically"""
+        weights = self._weights.get(cluster_id, {})
+        ranked = sorted(weights.items(), key=lambda item: (-item[1], item[0]))
+        return [term for term, weight in ranked[: self.top_n] if weight > 0]
+
+    def generate_name_for_cluster(self, cluster_id: int) -> str:
+        terms = self.top_terms(cluster_id)
+        if not terms:
+            return f"Cluster {cluster_id}"
+        return ", ".join(terms)
+
+
+class TfidfTopicNamer(TopicNamer):
+    """Standard TF-IDF over individual texts, summed per cluster."""
+
+    def _term_weights(
+        self, documents: Dict[int, List[List[str]]]
+    ) -> Dict[int, Dict[str, float]]:
+        n_documents = sum(len(docs) for docs in documents.values())
+        document_frequency: Counter = Counter()
+        for docs in documents.values():
+            for tokens in docs:
+                document_frequency.update(set(tokens))
+
+        # Smoothed IDF, as in scikit-learn's TfidfVectorizer
+        idf = {
+            term: math.log((1 + n_documents) / (1 + df)) + 1
+            for term, df in document_frequency.items()
+        }
+
+        weights = {}
+        for cluster_id, docs in documents.items():
+            counts = Counter(token for tokens in docs for token in tokens)
+            weights[cluster_id] = {
+                term: count * idf[term] for term, count in counts.items()
+            }
+        return weights
+
+
+class CTfidfTopicNamer(TopicNamer):
+    """Class-based TF-IDF: each cluster is a single document."""
+
+    def _term_weights(
+        self, documents: Dict[int, List[List[str]]]
+    ) -> Dict[int, Dict[str, float]]:
+        cluster_counts = {
+            cluster_id: Counter(token for tokens in docs for token in tokens)
+            for cluster_id, docs in documents.items()
+        }
+        total_frequency: Counter = Counter()
+        for counts in cluster_counts.values():
+            total_frequency.update(counts)
+        if not cluster_counts:
+            return {}
+
+        # Average number of words per cluster
+        average_words = sum(total_frequency.values()) / len(cluster_counts)
+
+        weights = {}
+        for cluster_id, counts in cluster_counts.items():
+            cluster_words = sum(counts.values())
+            weights[cluster_id] = {
+                term: (count / cluster_words)
+                * math.log(1 + average_words / total_frequency[term])
+                for term, count in counts.items()
+            }
+        return weights
+
+
+def create_topic_namer(method: str, top_n: int = DEFAULT_TOP_N) -> TopicNamer:
+    """Namer for a `topic_naming_method` of `tfidf` or `ctfidf`"""
+    if method == "tfidf":
+        return TfidfTopicNamer(top_n)
+    if method == "ctfidf":
+        return CTfidfTopicNamer(top_n)
+    raise ValueError(f"Unknown keyword topic naming method: {method}")
@@ -0,0 +1,50 @@
+"""
+Tests for assigning new points to existing topics without refitting.
+
+These are pure Python; no UMAP, HDBSCAN or Qdrant is needed.
+"""
+
+import sys
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).parent.parent / "src"))
+
+from assignment import (  # noqa: E402
+    OUTLIER,
+    assign_to_nearest,
+    summarize_assignments,
+)
+
+
+def test_points_beyond_threshold_are_outliers():
+    nearest = [(0, 0.05), (1, 0.3), (2, 0.31), (1, 0.9)]
+    assert assign_to_nearest(nearest, 0.3) == [0, 1, OUTLIER, OUTLIER]
+
+
+def test_zero_threshold_only_assigns_exact_matches():
+    assert assign_to_nearest([(3, 0.0), (3, 1e-6)], 0.0) == [3, OUTLIER]
+
+
+def test_summary_counts_assigned_and_outliers_per_topic():
+    summary = summarize_assignments([0, 0, 1, OUTLIER], refit_outlier_ratio=0.5)
+    assert summary.new_points == 4
+    assert summary.assigned == 3
+    assert summary.outliers == 1
+    assert summary.outlier_ratio == 0.25
+    assert not summary.refit_suggested
+    assert summary.as_dict()["topic_counts"] == {"0": 2, "1": 1}
+
+
+def test_refit_suggested_only_above_outlier_ratio():
+    at_ratio = summarize_assignments([0, OUTLIER], refit_outlier_ratio=0.5)
+    assert not at_ratio.refit_suggested
+
+    above_ratio = summarize_assignments([0, OUTLIER, OUTLIER], refit_outlier_ratio=0.5)
+    assert above_ratio.refit_suggested
+    assert above_ratio.as_dict()["refit_suggested"] is True
+
+
+def test_no_new_points_never_suggests_refit():
+    summary = summarize_assignments([], refit_outlier_ratio=0.0)
+    assert summary.outlier_ratio == 0.0
+    assert not summary.refit_suggested
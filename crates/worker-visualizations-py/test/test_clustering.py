@@ -0,0 +1,72 @@
+"""
+Tests for fixed-K k-means clustering.
+
+These only need numpy; UMAP and HDBSCAN are never imported.
+"""
+
+import sys
+from pathlib import Path
+
+import numpy as np
+import pytest
+
+sys.path.insert(0, str(Path(__file__).parent.parent / "src"))
+
+from clustering import kmeans  # noqa: E402
+from points import build_points  # noqa: E402
+
+
+def blobs(n_blobs: int, per_blob: int, seed: int = 0) -> np.ndarray:
+    """Tight, well separated 2D blobs, blob after blob"""
+    rng = np.random.default_rng(seed)
+    centers = np.array([[i * 10.0, (i % 2) * 10.0] for i in range(n_blobs)])
+    return np.concatenate(
+        [center + rng.normal(scale=0.5, size=(per_blob, 2)) for center in centers]
+    )
+
+
+def test_k5_yields_exactly_five_topics():
+    rng = np.random.default_rng(7)
+    vectors = rng.uniform(size=(500, 2))
+
+    labels = kmeans(vectors, 5)
+
+    assert sorted(set(labels.tolist())) == [0, 1, 2, 3, 4]
+    # Every point is in a topic; k-means leaves no noise
+    assert not np.any(labels == -1)
+
+    names = {cluster_id: f"Topic {cluster_id}" for cluster_id in set(labels.tolist())}
+    points = build_points([str(i) for i in range(len(vectors))], vectors, labels, names)
+    assert len(points["cluster_names"]) == 5
+    assert set(points["cluster_ids"]) == {0, 1, 2, 3, 4}
+
+
+def test_separated_blobs_each_become_one_cluster():
+    vectors = blobs(5, 40)
+
+    labels = kmeans(vectors, 5)
+
+    for blob in range(5):
+        blob_labels = set(labels[blob * 40 : (blob + 1) * 40].tolist())
+        assert len(blob_labels) == 1
+    assert len(set(labels.tolist())) == 5
+
+
+def test_same_seed_gives_same_labels():
+    vectors = np.random.default_rng(3).normal(size=(200, 2))
+    assert np.array_equal(kmeans(vectors, 5, seed=1), kmeans(vectors, 5, seed=1))
+
+
+def test_every_cluster_is_used_even_with_duplicate_points():
+    # Two distinct positions but five clusters asked for
+    vectors = np.array([[0.0, 0.0]] * 6 + [[1.0, 1.0]] * 6)
+
+    labels = kmeans(vectors, 5)
+
+    assert sorted(set(labels.tolist())) == [0, 1, 2, 3, 4]
+
+
+@pytest.mark.parametrize("n_clusters", [0, 11])
+def test_cluster_count_must_fit_the_points(n_clusters):
+    with pytest.raises(ValueError, match="n_clusters must be between 1"):
+        kmeans(np.zeros((10, 2)), n_clusters)
@@ -0,0 +1,110 @@
+"""
+Tests for UMAP/clustering parameter validation, clamping and auto-tuning.
+
+These only need pydantic; UMAP and HDBSCAN are never imported.
+"""
+
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).parent.parent / "src"))
+
+from models import VisualizationConfig  # noqa: E402
+from params import (  # noqa: E402
+    DEFAULT_MIN_SAMPLES,
+    ParameterError,
+    auto_tuned_defaults,
+    resolve_parameters,
+)
+
+
+def test_defaults_pass_through_for_large_datasets():
+    params = resolve_parameters(VisualizationConfig(), 10_000)
+    assert params.n_neighbors == 15
+    assert params.min_cluster_size == 15
+    assert params.min_samples == 5
+    assert params.adjustments == []
+    assert not params.auto_tuned
+
+
+def test_parameters_larger_than_dataset_are_clamped():
+    config = VisualizationConfig(n_neighbors=50, min_cluster_size=40, min_samples=30)
+    params = resolve_parameters(config, 20)
+    assert params.n_neighbors == 19
+    assert params.min_cluster_size == 20
+    assert params.min_samples == 19
+    assert len(params.adjustments) == 3
+    assert "n_neighbors 50 clamped to 19" in params.adjustments[0]
+
+
+def test_unset_min_samples_uses_default():
+    params = resolve_parameters(VisualizationConfig(min_samples=None), 100)
+    assert params.min_samples == DEFAULT_MIN_SAMPLES
+
+
+@pytest.mark.parametrize(
+    "config, message",
+    [
+        (VisualizationConfig(n_neighbors=1), "n_neighbors must be at least 2"),
+        (VisualizationConfig(min_dist=-0.1), "min_dist must be between 0 and 1"),
+        (VisualizationConfig(metric="telepathy"), "metric 'telepathy'"),
+        (VisualizationConfig(min_cluster_size=1), "min_cluster_size must be"),
+        (VisualizationConfig(min_samples=0), "min_samples must be at least 1"),
+        (
+            VisualizationConfig(clustering_method="kmeans", n_clusters=1),
+            "n_clusters must be at least 2",
+        ),
+    ],
+)
+def test_invalid_parameters_are_rejected(config, message):
+    with pytest.raises(ParameterError, match=message):
+        resolve_parameters(config, 1_000)
+
+
+def test_too_few_points_is_rejected():
+    with pytest.raises(ParameterError, match="at least 3 points"):
+        resolve_parameters(VisualizationConfig(), 2)
+    assert resolve_parameters(VisualizationConfig(), 3).n_neighbors == 2
+
+
+def test_auto_tune_scales_with_sample_count():
+    small = auto_tuned_defaults(100, 0.005)
+    large = auto_tuned_defaults(1_000_000, 0.005)
+    assert small == {"n_neighbors": 10, "min_cluster_size": 5, "min_samples": 5}
+    assert large == {"n_neighbors": 50, "min_cluster_size": 5000, "min_samples": 10}
+
+
+def test_auto_tune_keeps_explicit_parameters():
+    config = VisualizationConfig(n_neighbors=30)
+    params = resolve_parameters(config, 40_000, auto_tune=True)
+    assert params.auto_tuned
+    assert params.n_neighbors == 30
+    assert params.min_cluster_size == 200
+    assert params.min_samples == 10
+
+
+def test_effective_parameters_are_recorded():
+    params = resolve_parameters(VisualizationConfig(n_neighbors=99), 50)
+    recorded = params.as_dict()
+    assert recorded["n_neighbors"] == 49
+    assert recorded["n_samples"] == 50
+    assert recorded["metric"] == "cosine"
+    assert recorded["adjustments"]
+    assert "n_neighbors=49" in params.describe()
+
+
+def test_kmeans_cluster_count_is_clamped_to_the_points():
+    config = VisualizationConfig(clustering_method="kmeans", n_clusters=50)
+    params = resolve_parameters(config, 20)
+    assert params.clustering_method == "kmeans"
+    assert params.n_clusters == 20
+    assert any("n_clusters 50 clamped to 20" in a for a in params.adjustments)
+    assert "kmeans n_clusters=20" in params.describe()
+
+
+def test_hdbscan_ignores_n_clusters():
+    params = resolve_parameters(VisualizationConfig(n_clusters=1), 10)
+    assert params.clustering_method == "hdbscan"
+    assert params.adjustments == []
@@ -0,0 +1,48 @@
+"""
+Tests for the scatter points document stored with each visualization.
+
+These are pure Python; no UMAP or HDBSCAN is needed.
+"""
+
+import json
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).parent.parent / "src"))
+
+from points import build_points, serialize_points  # noqa: E402
+
+
+def test_points_are_columnar_with_named_clusters():
+    points = build_points(
+        ["a", "b", "c"],
+        [[0.1234567, -1.0], [2.0, 3.5], [4.0, 5.0]],
+        [0, 1, -1],
+        {0: "cats", 1: "rockets"},
+    )
+    assert points == {
+        "ids": ["a", "b", "c"],
+        "x": [0.12346, 2.0, 4.0],
+        "y": [-1.0, 3.5, 5.0],
+        "cluster_ids": [0, 1, -1],
+        "cluster_names": {"0": "cats", "1": "rockets"},
+    }
+
+
+def test_noise_has_no_cluster_name():
+    points = build_points(["a"], [[0.0, 0.0]], [-1], {-1: "Noise"})
+    assert points["cluster_names"] == {}
+
+
+def test_mismatched_columns_are_rejected():
+    with pytest.raises(ValueError):
+        build_points(["a", "b"], [[0.0, 0.0]], [0, 0], {})
+
+
+def test_serialized_points_round_trip():
+    points = build_points(["a"], [[1.5, 2.5]], [0], {0: "topic"})
+    encoded = serialize_points(points)
+    assert b" " not in encoded
+    assert json.loads(encoded) == points
@@ -0,0 +1,83 @@
+"""
+Tests for keyword topic naming with TF-IDF and class-based TF-IDF.
+
+These are pure Python; no UMAP, HDBSCAN or LLM is needed.
+"""
+
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).parent.parent / "src"))
+
+from topic_naming import (  # noqa: E402
+    CTfidfTopicNamer,
+    TfidfTopicNamer,
+    create_topic_namer,
+    tokenize,
+)
+
+# Every text mentions "data" twice; each cluster has its own subject
+TEXTS = [
+    "data data cat food",
+    "data data cat toys",
+    "data data kitten",
+    "data data rocket launch",
+    "data data rocket engine",
+    "data data orbit",
+    "data data bread oven",
+    "data data bread flour",
+    "data data yeast",
+    "unclustered noise text",
+]
+LABELS = [0, 0, 0, 1, 1, 1, 2, 2, 2, -1]
+
+
+def test_ctfidf_surfaces_cluster_specific_terms():
+    namer = CTfidfTopicNamer(top_n=1).fit(TEXTS, LABELS)
+    assert namer.generate_name_for_cluster(0) == "cat"
+    assert namer.generate_name_for_cluster(1) == "rocket"
+    assert namer.generate_name_for_cluster(2) == "bread"
+
+
+def test_plain_tfidf_surfaces_globally_common_terms():
+    namer = TfidfTopicNamer(top_n=1).fit(TEXTS, LABELS)
+    # "data" is in every text, yet outweighs each cluster's own subject
+    for cluster_id in (0, 1, 2):
+        assert namer.generate_name_for_cluster(cluster_id) == "data"
+
+
+def test_name_joins_top_terms_with_alphabetical_ties():
+    namer = CTfidfTopicNamer(top_n=3).fit(TEXTS, LABELS)
+    # flour, oven and yeast tie after bread and data
+    assert namer.generate_name_for_cluster(2) == "bread, data, flour"
+
+
+def test_noise_and_unknown_clusters_get_numeric_names():
+    for namer in (TfidfTopicNamer(), CTfidfTopicNamer()):
+        namer.fit(TEXTS, LABELS)
+        assert namer.generate_name_for_cluster(-1) == "Cluster -1"
+        assert namer.generate_name_for_cluster(7) == "Cluster 7"
+
+
+def test_empty_and_stop_word_only_texts():
+    namer = CTfidfTopicNamer().fit(["the and of", "", None], [0, 1, 1])
+    assert namer.generate_name_for_cluster(0) == "Cluster 0"
+    assert namer.generate_name_for_cluster(1) == "Cluster 1"
+    assert CTfidfTopicNamer().fit([], []).generate_name_for_cluster(0) == "Cluster 0"
+
+
+def test_tokenize_drops_stop_words_and_punctuation():
+    assert tokenize("The Rocket's engine, and 2 orbits!") == [
+        "rocket",
+        "engine",
+        "orbits",
+    ]
+
+
+def test_create_topic_namer():
+    assert isinstance(create_topic_namer("tfidf"), TfidfTopicNamer)
+    assert isinstance(create_topic_namer("ctfidf"), CTfidfTopicNamer)
+    with pytest.raises(ValueError):
+        create_topic_namer("llm")
@@ -1,4 +1,4 @@
-import type { Embedder, PaginatedResponse } from '$lib/types/models';
+import type { Embedder, EmbedderTestResult, PaginatedResponse } from '$lib/types/models';
 import { apiDelete, apiGet, apiPatch, apiPost, buildQueryString } from '$lib/utils/api';
 
 export function getEmbedders(
@@ -35,12 +35,8 @@ export function deleteEmbedder(embedderId: number, signal?: AbortSignal): Promis
 export function testEmbedder(
 	embedderId: number,
 	signal?: AbortSignal
-): Promise<{ success: boolean; message: string; model?: string; dimensions?: number }> {
-	return apiPost<{ success: boolean; message: string; model?: string; dimensions?: number }>(
-		`/api/embedders/${embedderId}/test`,
-		undefined,
-		signal
-	);
+): Promise<EmbedderTestResult> {
+	return apiPost<EmbedderTestResult>(`/api/embedders/${embedderId}/test`, undefined, signal);
 }
 
 export function getEmbeddingInferenceModels(
@@ -212,6 +212,8 @@ export interface Embedder {
 	config: Record<string, unknown>;
 	batch_size?: number;
 	dimensions?: number;
+	/** Whether `dimensions` were measured from the embedder's output */
+	dimensions_verified?: boolean;
 	max_input_tokens?: number;
 	truncate_strategy?: string;
 	collection_name: string;
@@ -221,6 +223,29 @@ export interface Embedder {
 	title?: string;
 }
 
+export interface CollectionDimensionMismatch {
+	collection_name: string;
+	embedded_dataset_id?: number;
+	collection_dimensions: number;
+}
+
+/** Diagnostics from probing an embedder with a fixed set of texts */
+export interface EmbedderTestResult {
+	success: boolean;
+	message: string;
+	dimensions: number | null;
+	configured_dimensions: number;
+	consistent_dimensions: boolean;
+	probe_count: number;
+	single_latencies_ms: number[];
+	mean_single_latency_ms: number;
+	batch_supported: boolean;
+	batch_latency_ms?: number;
+	batch_error?: string;
+	dimension_mismatches: CollectionDimensionMismatch[];
+	warnings: string[];
+}
+
 export type ProviderDefaultConfig = {
 	url: string;
 	models: string[];
@@ -403,6 +428,7 @@ export interface Visualization {
 	started_at: string | null;
 	completed_at: string | null;
 	html_s3_key: string | null;
+	points_s3_key: string | null;
 	point_count: number | null;
 	cluster_count: number | null;
 	error_message: string | null;
@@ -417,6 +443,7 @@ export interface DatabaseVisualization {
 	started_at: string | null;
 	completed_at: string | null;
 	html_s3_key: string | null;
+	points_s3_key: string | null;
 	point_count: number | null;
 	cluster_count: number | null;
 	error_message: string | null;